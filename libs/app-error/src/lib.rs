@@ -183,6 +183,12 @@ pub enum AppError {
 
   #[error("Apply update error:{0}")]
   ApplyUpdateError(String),
+
+  #[error("{0}")]
+  AIQuotaExceeded(String),
+
+  #[error("{0}")]
+  TooManyRequests(String),
 }
 
 impl AppError {
@@ -202,6 +208,10 @@ impl AppError {
     matches!(self, AppError::UserUnAuthorized(_))
   }
 
+  pub fn is_ai_quota_exceeded(&self) -> bool {
+    matches!(self, AppError::AIQuotaExceeded(_))
+  }
+
   pub fn code(&self) -> ErrorCode {
     match self {
       AppError::Ok => ErrorCode::Ok,
@@ -262,6 +272,8 @@ impl AppError {
       AppError::ServiceTemporaryUnavailable(_) => ErrorCode::ServiceTemporaryUnavailable,
       AppError::DecodeUpdateError(_) => ErrorCode::DecodeUpdateError,
       AppError::ApplyUpdateError(_) => ErrorCode::ApplyUpdateError,
+      AppError::AIQuotaExceeded(_) => ErrorCode::AIQuotaExceeded,
+      AppError::TooManyRequests(_) => ErrorCode::TooManyRequests,
     }
   }
 }
@@ -404,6 +416,8 @@ pub enum ErrorCode {
   ServiceTemporaryUnavailable = 1054,
   DecodeUpdateError = 1055,
   ApplyUpdateError = 1056,
+  AIQuotaExceeded = 1057,
+  TooManyRequests = 1058,
 }
 
 impl ErrorCode {
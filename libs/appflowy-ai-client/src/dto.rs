@@ -7,9 +7,23 @@ use std::str::FromStr;
 
 pub const STREAM_METADATA_KEY: &str = "0";
 pub const STREAM_ANSWER_KEY: &str = "1";
+
+/// Tokens consumed by a request, as reported by the AI service.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AIUsage {
+  #[serde(default)]
+  pub prompt_tokens: i64,
+  #[serde(default)]
+  pub completion_tokens: i64,
+  #[serde(default)]
+  pub total_tokens: i64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SummarizeRowResponse {
   pub text: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub usage: Option<AIUsage>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -34,6 +48,8 @@ pub struct ChatAnswer {
   pub content: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub metadata: Option<serde_json::Value>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub usage: Option<AIUsage>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,6 +69,8 @@ pub struct RelatedQuestion {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CompleteTextResponse {
   pub text: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub usage: Option<AIUsage>,
 }
 
 #[derive(Clone, Debug, Serialize_repr, Deserialize_repr)]
@@ -126,6 +144,8 @@ pub struct TranslateItem {
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct TranslateRowResponse {
   pub items: Vec<HashMap<String, String>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub usage: Option<AIUsage>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
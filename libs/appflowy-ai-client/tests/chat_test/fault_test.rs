@@ -0,0 +1,81 @@
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{AIModel, CompletionType};
+use appflowy_ai_mock::{MockAIServer, MockFault, MockFaultRule};
+use futures::stream::StreamExt;
+
+#[tokio::test]
+async fn completion_fault_test() {
+  // faults apply to all the requests of a server, so the test doesn't share it with other tests
+  let server = MockAIServer::start("127.0.0.1:0").unwrap();
+  let client = AppFlowyAIClient::new(server.url());
+  server.inject_fault(MockFaultRule::new("/completion", MockFault::rate_limited()).times(1));
+
+  let err = client
+    .completion_text(
+      "I feel hungry",
+      CompletionType::ImproveWriting,
+      None,
+      AIModel::GPT4oMini,
+    )
+    .await
+    .unwrap_err();
+  assert!(err.to_string().contains("429"), "{}", err);
+
+  // the fault was applied once, the same request succeeds now
+  let resp = client
+    .completion_text(
+      "I feel hungry",
+      CompletionType::ImproveWriting,
+      None,
+      AIModel::GPT4oMini,
+    )
+    .await
+    .unwrap();
+  assert_eq!(resp.text, "I feel hungry.");
+  assert_eq!(server.request_count("/completion"), 2);
+
+  server.inject_fault(MockFaultRule::new("/completion", MockFault::MalformedBody));
+  assert!(client
+    .completion_text(
+      "I feel hungry",
+      CompletionType::ImproveWriting,
+      None,
+      AIModel::GPT4oMini,
+    )
+    .await
+    .is_err());
+  server.clear_faults();
+}
+
+#[tokio::test]
+async fn interrupted_stream_test() {
+  let server = MockAIServer::start("127.0.0.1:0").unwrap();
+  let client = AppFlowyAIClient::new(server.url());
+  server.inject_fault(MockFaultRule::new(
+    "/completion/stream",
+    MockFault::InterruptStream { after_chunks: 2 },
+  ));
+
+  let mut stream = client
+    .stream_completion_text(
+      "one two three four five",
+      CompletionType::ImproveWriting,
+      None,
+      AIModel::GPT4oMini,
+    )
+    .await
+    .unwrap();
+  let mut chunks = vec![];
+  let mut failed = false;
+  while let Some(item) = stream.next().await {
+    match item {
+      Ok(bytes) => chunks.push(String::from_utf8(bytes.to_vec()).unwrap()),
+      Err(_) => {
+        failed = true;
+        break;
+      },
+    }
+  }
+  assert!(failed);
+  assert_eq!(chunks.concat(), "One two ");
+}
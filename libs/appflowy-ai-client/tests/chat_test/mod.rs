@@ -1,4 +1,5 @@
 mod completion_test;
 mod context_test;
 mod embedding_test;
+mod fault_test;
 mod qa_test;
@@ -1,5 +1,6 @@
 use appflowy_ai_client::client::AppFlowyAIClient;
-use std::sync::Once;
+use appflowy_ai_mock::MockAIServer;
+use std::sync::{Once, OnceLock};
 use tracing_subscriber::fmt::Subscriber;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
@@ -9,9 +10,19 @@ mod row_test;
 
 // mod index_test;
 
+/// Returns a client of the mock AI service, or of the AI service at `APPFLOWY_AI_TEST_URL` if
+/// it's set, ie. `http://localhost:5001`.
 pub fn appflowy_ai_client() -> AppFlowyAIClient {
   setup_log();
-  AppFlowyAIClient::new("http://localhost:5001")
+  match std::env::var("APPFLOWY_AI_TEST_URL") {
+    Ok(url) => AppFlowyAIClient::new(&url),
+    Err(_) => AppFlowyAIClient::new(mock_ai_server().url()),
+  }
+}
+
+pub fn mock_ai_server() -> &'static MockAIServer {
+  static MOCK_AI_SERVER: OnceLock<MockAIServer> = OnceLock::new();
+  MOCK_AI_SERVER.get_or_init(|| MockAIServer::start("127.0.0.1:0").unwrap())
 }
 
 pub fn setup_log() {
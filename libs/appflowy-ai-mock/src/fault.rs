@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+/// A failure the mock AI service simulates instead of responding normally.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MockFault {
+  /// Responds with the given HTTP status code and message.
+  Status { code: u16, message: String },
+  /// Delays the regular response.
+  Delay { millis: u64 },
+  /// Responds with a body which is not valid JSON.
+  MalformedBody,
+  /// Aborts a streaming response after the given number of chunks. Non-streaming routes respond
+  /// with a truncated body instead.
+  InterruptStream { after_chunks: usize },
+}
+
+impl MockFault {
+  pub fn status(code: u16, message: &str) -> Self {
+    Self::Status {
+      code,
+      message: message.to_string(),
+    }
+  }
+
+  /// Simulates the AI service rejecting requests because of its rate limits.
+  pub fn rate_limited() -> Self {
+    Self::status(429, "Rate limit exceeded")
+  }
+
+  pub fn unavailable() -> Self {
+    Self::status(503, "AI service is unavailable")
+  }
+}
+
+/// Applies a [MockFault] to requests of a route.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockFaultRule {
+  /// Path of the route, ie. `/completion`. A trailing `*` matches all paths starting with the
+  /// given prefix and a single `*` matches all routes.
+  pub route: String,
+  pub fault: MockFault,
+  /// Number of requests the fault is applied to. `None` applies it until the faults are cleared.
+  #[serde(default)]
+  pub times: Option<u32>,
+}
+
+impl MockFaultRule {
+  pub fn new(route: &str, fault: MockFault) -> Self {
+    Self {
+      route: route.to_string(),
+      fault,
+      times: None,
+    }
+  }
+
+  pub fn times(mut self, times: u32) -> Self {
+    self.times = Some(times);
+    self
+  }
+
+  pub(crate) fn matches(&self, path: &str) -> bool {
+    match self.route.strip_suffix('*') {
+      Some(prefix) => path.starts_with(prefix),
+      None => self.route == path,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn route_matching_test() {
+    let rule = MockFaultRule::new("/completion", MockFault::MalformedBody);
+    assert!(rule.matches("/completion"));
+    assert!(!rule.matches("/completion/stream"));
+
+    let rule = MockFaultRule::new("/chat/*", MockFault::MalformedBody);
+    assert!(rule.matches("/chat/message"));
+    assert!(rule.matches("/chat/1/2/related_question"));
+    assert!(!rule.matches("/v2/chat/message/stream"));
+
+    assert!(MockFaultRule::new("*", MockFault::MalformedBody).matches("/embeddings"));
+  }
+
+  #[test]
+  fn fault_serde_test() {
+    let rule = MockFaultRule::new("/embeddings", MockFault::rate_limited()).times(2);
+    let json = serde_json::to_value(&rule).unwrap();
+    assert_eq!(
+      json,
+      serde_json::json!({
+        "route": "/embeddings",
+        "fault": {"type": "status", "code": 429, "message": "Rate limit exceeded"},
+        "times": 2
+      })
+    );
+    assert_eq!(serde_json::from_value::<MockFaultRule>(json).unwrap(), rule);
+  }
+}
@@ -0,0 +1,544 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use actix_web::web::{Data, Json, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+use appflowy_ai_client::dto::{
+  AIUsage, AppFlowyOfflineAI, CalculateSimilarityParams, ChatAnswer, ChatQuestion,
+  CompleteTextResponse, CompletionType, CreateChatContext, CustomPrompt, Document, Embedding,
+  EmbeddingInput, EmbeddingOutput, EmbeddingRequest, EmbeddingResponse, LLMModel, LocalAIConfig,
+  ModelInfo, RelatedQuestion, RepeatedLocalAIPackage, RepeatedRelatedQuestion, SimilarityResponse,
+  SummarizeRowResponse, TranslateRowData, TranslateRowResponse, STREAM_ANSWER_KEY,
+  STREAM_METADATA_KEY,
+};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::fault::{MockFault, MockFaultRule};
+use crate::response;
+use crate::state::{ChatContext, MockState};
+
+pub(crate) fn routes(cfg: &mut web::ServiceConfig) {
+  cfg
+    .route("/health", web::get().to(health_handler))
+    .route("/completion", web::post().to(completion_handler))
+    .route(
+      "/completion/stream",
+      web::post().to(stream_completion_handler),
+    )
+    .route("/summarize_row", web::post().to(summarize_row_handler))
+    .route("/translate_row", web::post().to(translate_row_handler))
+    .route("/embeddings", web::post().to(embeddings_handler))
+    .route("/index_documents", web::post().to(index_documents_handler))
+    .route("/search", web::get().to(search_documents_handler))
+    .route("/chat/context/text", web::post().to(chat_context_handler))
+    .route("/chat/message", web::post().to(chat_message_handler))
+    .route(
+      "/chat/message/stream",
+      web::post().to(stream_chat_message_handler),
+    )
+    .route(
+      "/v2/chat/message/stream",
+      web::post().to(stream_chat_message_v2_handler),
+    )
+    .route(
+      "/chat/{chat_id}/{message_id}/related_question",
+      web::get().to(related_question_handler),
+    )
+    .route("/local_ai/plugin", web::get().to(local_ai_plugin_handler))
+    .route("/local_ai/config", web::get().to(local_ai_config_handler))
+    .route("/similarity", web::post().to(similarity_handler))
+    .route("/mock/faults", web::post().to(inject_fault_handler))
+    .route("/mock/faults", web::delete().to(clear_faults_handler))
+    .route("/mock/requests", web::get().to(request_counts_handler));
+}
+
+type FaultResult = Result<Option<usize>, HttpResponse>;
+
+/// Counts the request and applies the fault injected for its route. Returns a response replacing
+/// the regular one, or the number of chunks a streaming response should be interrupted after.
+async fn apply_fault(state: &MockState, req: &HttpRequest, streaming: bool) -> FaultResult {
+  match state.begin_request(req.path()) {
+    None => Ok(None),
+    Some(MockFault::Status { code, message }) => {
+      let status = actix_web::http::StatusCode::from_u16(code)
+        .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+      Err(HttpResponse::build(status).body(message))
+    },
+    Some(MockFault::Delay { millis }) => {
+      actix_web::rt::time::sleep(Duration::from_millis(millis)).await;
+      Ok(None)
+    },
+    Some(MockFault::InterruptStream { after_chunks }) if streaming => Ok(Some(after_chunks)),
+    Some(MockFault::MalformedBody) | Some(MockFault::InterruptStream { .. }) => Err(
+      HttpResponse::Ok()
+        .content_type("application/json")
+        .body(r#"{"data": {"#),
+    ),
+  }
+}
+
+fn ok_response<T: Serialize>(data: T) -> HttpResponse {
+  HttpResponse::Ok().json(json!({ "data": data, "message": "" }))
+}
+
+/// Streams given chunks. An interrupted stream fails after the given number of chunks, which
+/// aborts the response.
+fn stream_response(chunks: Vec<Bytes>, interrupt_after: Option<usize>) -> HttpResponse {
+  let mut items: Vec<Result<Bytes, std::io::Error>> = chunks.into_iter().map(Ok).collect();
+  if let Some(after) = interrupt_after {
+    items.truncate(after);
+    items.push(Err(std::io::Error::new(
+      std::io::ErrorKind::ConnectionAborted,
+      "stream interrupted by mock fault",
+    )));
+  }
+  HttpResponse::Ok()
+    .content_type("text/event-stream")
+    .streaming(futures::stream::iter(items))
+}
+
+/// Splits a text into chunks of single words, keeping the whitespace.
+fn text_chunks(text: &str) -> Vec<String> {
+  text.split_inclusive(' ').map(ToString::to_string).collect()
+}
+
+async fn health_handler(state: Data<MockState>, req: HttpRequest) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  HttpResponse::Ok().body("ok")
+}
+
+#[derive(Deserialize)]
+struct CompletionParams {
+  text: String,
+  #[serde(rename = "type")]
+  completion_type: Option<CompletionType>,
+  custom_prompt: Option<CustomPrompt>,
+}
+
+impl CompletionParams {
+  fn complete(&self) -> String {
+    response::complete_text(
+      &self.text,
+      self.completion_type.as_ref(),
+      self.custom_prompt.as_ref(),
+    )
+  }
+}
+
+async fn completion_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  params: Json<CompletionParams>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let text = params.complete();
+  ok_response(CompleteTextResponse {
+    usage: usage(&params.text, &text),
+    text,
+  })
+}
+
+async fn stream_completion_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  params: Json<CompletionParams>,
+) -> HttpResponse {
+  let interrupt_after = match apply_fault(&state, &req, true).await {
+    Ok(interrupt_after) => interrupt_after,
+    Err(resp) => return resp,
+  };
+  let chunks = text_chunks(&params.complete())
+    .into_iter()
+    .map(Bytes::from)
+    .collect();
+  stream_response(chunks, interrupt_after)
+}
+
+async fn summarize_row_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  params: Json<Map<String, Value>>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let text = response::summarize_row(&params);
+  ok_response(SummarizeRowResponse {
+    usage: usage(&Value::Object(params.into_inner()).to_string(), &text),
+    text,
+  })
+}
+
+async fn translate_row_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  data: Json<TranslateRowData>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let items: Vec<HashMap<String, String>> = data
+    .cells
+    .iter()
+    .map(|cell| {
+      [(
+        cell.title.clone(),
+        response::translate(&cell.content, &data.language),
+      )]
+      .into_iter()
+      .collect::<HashMap<_, _>>()
+    })
+    .collect();
+  let prompt = data
+    .cells
+    .iter()
+    .map(|cell| cell.content.as_str())
+    .collect::<Vec<_>>()
+    .join(" ");
+  let completion = items
+    .iter()
+    .flat_map(|item| item.values().cloned())
+    .collect::<Vec<_>>()
+    .join(" ");
+  ok_response(TranslateRowResponse {
+    usage: usage(&prompt, &completion),
+    items,
+  })
+}
+
+async fn embeddings_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  params: Json<EmbeddingRequest>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let params = params.into_inner();
+  let inputs = match params.input {
+    EmbeddingInput::String(input) => vec![input],
+    EmbeddingInput::StringArray(inputs) => inputs,
+  };
+  let dimensions = params.dimensions.max(1) as usize;
+  let total_tokens = inputs
+    .iter()
+    .map(|input| response::count_tokens(input))
+    .sum::<usize>();
+  let data = inputs
+    .iter()
+    .enumerate()
+    .map(|(index, input)| Embedding {
+      index: index as i32,
+      embedding: EmbeddingOutput::Float(response::embed(input, dimensions)),
+    })
+    .collect();
+  ok_response(EmbeddingResponse {
+    object: "list".to_string(),
+    data,
+    model: params.model,
+    total_tokens: total_tokens as i32,
+  })
+}
+
+async fn index_documents_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  documents: Json<Vec<Document>>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  state.index_documents(documents.into_inner());
+  HttpResponse::Ok().finish()
+}
+
+#[derive(Deserialize)]
+struct SearchDocumentsQuery {
+  workspaces: String,
+  query: String,
+  result_count: Option<u32>,
+}
+
+async fn search_documents_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  query: Query<SearchDocumentsQuery>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let workspaces: Vec<&str> = query.workspaces.split(',').collect();
+  let mut documents: Vec<(f64, Document)> = state
+    .documents()
+    .into_iter()
+    .filter(|document| workspaces.contains(&document.workspace_id.as_str()))
+    .map(|document| {
+      (
+        response::similarity(&query.query, &document.content),
+        document,
+      )
+    })
+    .filter(|(score, _)| *score > 0.0)
+    .collect();
+  documents.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+  let documents: Vec<Document> = documents
+    .into_iter()
+    .take(query.result_count.unwrap_or(10) as usize)
+    .map(|(_, document)| document)
+    .collect();
+  ok_response(documents)
+}
+
+async fn chat_context_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  context: Json<CreateChatContext>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let context = context.into_inner();
+  state.add_chat_context(
+    &context.chat_id,
+    ChatContext {
+      content: context.content,
+      metadata: context.metadata,
+    },
+  );
+  ok_response(())
+}
+
+/// Answers a question from the contexts of its chat and the content found in its metadata, ie.
+/// citations of workspace documents.
+fn answer(state: &MockState, question: &ChatQuestion) -> ChatAnswer {
+  state.set_last_question(&question.chat_id, &question.data.content);
+  let contexts = state.chat_contexts(&question.chat_id);
+  let mut texts: Vec<String> = contexts
+    .iter()
+    .map(|context| context.content.clone())
+    .collect();
+  if let Some(metadata) = &question.data.metadata {
+    collect_contents(metadata, &mut texts);
+  }
+  let content = response::answer_question(&question.data.content, &texts);
+
+  // like the AI service, report which of the chat contexts the answer is based on
+  let metadata: Vec<Value> = contexts
+    .iter()
+    .map(|context| {
+      let mut metadata = Map::new();
+      for key in ["id", "name", "source"] {
+        if let Some(value) = context.metadata.get(key) {
+          metadata.insert(key.to_string(), value.clone());
+        }
+      }
+      Value::Object(metadata)
+    })
+    .collect();
+  let prompt = format!("{} {}", question.data.content, texts.join(" "));
+  ChatAnswer {
+    usage: usage(&prompt, &content),
+    content,
+    metadata: (!metadata.is_empty()).then(|| Value::Array(metadata)),
+  }
+}
+
+/// Reports the tokens of a request like the AI service does.
+fn usage(prompt: &str, completion: &str) -> Option<AIUsage> {
+  let prompt_tokens = response::count_tokens(prompt) as i64;
+  let completion_tokens = response::count_tokens(completion) as i64;
+  Some(AIUsage {
+    prompt_tokens,
+    completion_tokens,
+    total_tokens: prompt_tokens + completion_tokens,
+  })
+}
+
+fn collect_contents(value: &Value, texts: &mut Vec<String>) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        match value {
+          Value::String(content) if key == "content" => texts.push(content.clone()),
+          value => collect_contents(value, texts),
+        }
+      }
+    },
+    Value::Array(items) => items
+      .iter()
+      .for_each(|value| collect_contents(value, texts)),
+    _ => {},
+  }
+}
+
+async fn chat_message_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  question: Json<ChatQuestion>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  ok_response(answer(&state, &question))
+}
+
+async fn stream_chat_message_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  question: Json<ChatQuestion>,
+) -> HttpResponse {
+  let interrupt_after = match apply_fault(&state, &req, true).await {
+    Ok(interrupt_after) => interrupt_after,
+    Err(resp) => return resp,
+  };
+  let answer = answer(&state, &question);
+  let chunks = text_chunks(&answer.content)
+    .into_iter()
+    .map(Bytes::from)
+    .collect();
+  stream_response(chunks, interrupt_after)
+}
+
+async fn stream_chat_message_v2_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  question: Json<ChatQuestion>,
+) -> HttpResponse {
+  let interrupt_after = match apply_fault(&state, &req, true).await {
+    Ok(interrupt_after) => interrupt_after,
+    Err(resp) => return resp,
+  };
+  let answer = answer(&state, &question);
+  let mut chunks = vec![];
+  if let Some(metadata) = answer.metadata {
+    chunks.push(json!({ STREAM_METADATA_KEY: metadata }));
+  }
+  chunks.extend(
+    text_chunks(&answer.content)
+      .into_iter()
+      .map(|chunk| json!({ STREAM_ANSWER_KEY: chunk })),
+  );
+  let chunks = chunks
+    .into_iter()
+    .map(|chunk| Bytes::from(format!("{}\n", chunk)))
+    .collect();
+  stream_response(chunks, interrupt_after)
+}
+
+async fn related_question_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  path: Path<(String, i64)>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  let (chat_id, message_id) = path.into_inner();
+  let topic = state
+    .last_question(&chat_id)
+    .unwrap_or_else(|| "this topic".to_string());
+  ok_response(RepeatedRelatedQuestion {
+    message_id,
+    items: response::related_questions(&topic)
+      .into_iter()
+      .map(|content| RelatedQuestion {
+        content,
+        metadata: None,
+      })
+      .collect(),
+  })
+}
+
+#[derive(Deserialize)]
+struct LocalAIQuery {
+  platform: String,
+}
+
+fn local_ai_plugin(platform: &str) -> AppFlowyOfflineAI {
+  AppFlowyOfflineAI {
+    app_name: "AppFlowy".to_string(),
+    ai_plugin_name: "appflowy_ai_plugin".to_string(),
+    version: "0.1.0".to_string(),
+    url: format!(
+      "http://localhost/mock/local_ai/{}/appflowy_ai_plugin.zip",
+      platform
+    ),
+    etag: format!("mock-{}-0.1.0", platform),
+  }
+}
+
+fn local_ai_model(name: &str, file_size: i64) -> ModelInfo {
+  ModelInfo {
+    name: name.to_string(),
+    file_name: format!("{}.gguf", name),
+    file_size,
+    requirements: "8GB RAM".to_string(),
+    download_url: format!("http://localhost/mock/local_ai/models/{}.gguf", name),
+    desc: format!("Mock {} model", name),
+  }
+}
+
+async fn local_ai_plugin_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  query: Query<LocalAIQuery>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  ok_response(RepeatedLocalAIPackage(vec![local_ai_plugin(
+    &query.platform,
+  )]))
+}
+
+async fn local_ai_config_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  query: Query<LocalAIQuery>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  ok_response(LocalAIConfig {
+    models: vec![LLMModel {
+      llm_id: 1,
+      provider: "mock".to_string(),
+      embedding_model: local_ai_model("mock-embedding", 1024 * 1024),
+      chat_model: local_ai_model("mock-chat", 16 * 1024 * 1024),
+    }],
+    plugin: local_ai_plugin(&query.platform),
+  })
+}
+
+async fn similarity_handler(
+  state: Data<MockState>,
+  req: HttpRequest,
+  params: Json<CalculateSimilarityParams>,
+) -> HttpResponse {
+  if let Err(resp) = apply_fault(&state, &req, false).await {
+    return resp;
+  }
+  ok_response(SimilarityResponse {
+    score: response::similarity(&params.input, &params.expected),
+  })
+}
+
+async fn inject_fault_handler(state: Data<MockState>, rule: Json<MockFaultRule>) -> HttpResponse {
+  state.inject_fault(rule.into_inner());
+  HttpResponse::Ok().finish()
+}
+
+async fn clear_faults_handler(state: Data<MockState>) -> HttpResponse {
+  state.clear_faults();
+  HttpResponse::Ok().finish()
+}
+
+async fn request_counts_handler(state: Data<MockState>) -> HttpResponse {
+  HttpResponse::Ok().json(state.request_counts())
+}
@@ -0,0 +1,98 @@
+//! A mock of the AppFlowy AI service, implementing every route called by `AppFlowyAIClient` with
+//! deterministic responses. Failures of the AI service can be simulated by injecting
+//! [MockFault]s, either through [MockAIServer] or the `/mock/faults` route of a running service.
+
+mod fault;
+mod handlers;
+mod response;
+mod state;
+
+use std::io;
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::Arc;
+
+use actix_web::dev::ServerHandle;
+use actix_web::web::Data;
+use actix_web::{App, HttpServer};
+use tracing::info;
+
+pub use fault::{MockFault, MockFaultRule};
+
+use crate::state::MockState;
+
+/// Mock AI service running in a background thread. The service is stopped when dropped.
+pub struct MockAIServer {
+  url: String,
+  state: Arc<MockState>,
+  handle: ServerHandle,
+}
+
+impl MockAIServer {
+  /// Starts the service on a given address. Use port `0` to start it on any free port.
+  pub fn start<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+    let listener = TcpListener::bind(addr)?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let state = Arc::new(MockState::default());
+    let (tx, rx) = std::sync::mpsc::channel();
+    let server_state = state.clone();
+    std::thread::Builder::new()
+      .name("appflowy-ai-mock".to_string())
+      .spawn(move || {
+        actix_web::rt::System::new().block_on(async move {
+          let server = HttpServer::new(move || {
+            App::new()
+              .app_data(Data::from(server_state.clone()))
+              .configure(handlers::routes)
+          })
+          .workers(1)
+          .disable_signals()
+          .listen(listener);
+          match server {
+            Ok(server) => {
+              let server = server.run();
+              let _ = tx.send(Ok(server.handle()));
+              let _ = server.await;
+            },
+            Err(err) => {
+              let _ = tx.send(Err(err));
+            },
+          }
+        })
+      })?;
+    let handle = rx
+      .recv()
+      .map_err(|_| io::Error::new(io::ErrorKind::Other, "mock AI service failed to start"))??;
+    info!("mock AI service is listening on {}", url);
+    Ok(Self { url, state, handle })
+  }
+
+  /// Base url of the service, to be passed to `AppFlowyAIClient::new`.
+  pub fn url(&self) -> &str {
+    &self.url
+  }
+
+  pub fn inject_fault(&self, rule: MockFaultRule) {
+    self.state.inject_fault(rule);
+  }
+
+  pub fn clear_faults(&self) {
+    self.state.clear_faults();
+  }
+
+  /// Returns the number of requests received by a given route, ie. `/completion`.
+  pub fn request_count(&self, route: &str) -> u64 {
+    self
+      .state
+      .request_counts()
+      .get(route)
+      .copied()
+      .unwrap_or_default()
+  }
+}
+
+impl Drop for MockAIServer {
+  fn drop(&mut self) {
+    // the stop command is sent right away, there's no need to wait for the server to shut down
+    let _ = self.handle.stop(false);
+  }
+}
@@ -0,0 +1,18 @@
+use appflowy_ai_mock::MockAIServer;
+use tracing_subscriber::EnvFilter;
+
+/// Runs the mock AI service standalone, ie. for a locally running AppFlowy Cloud server:
+/// ```shell
+/// APPFLOWY_AI_SERVER_PORT=5001 cargo run -p appflowy-ai-mock
+/// ```
+fn main() -> std::io::Result<()> {
+  tracing_subscriber::fmt()
+    .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+    .init();
+
+  let port = std::env::var("APPFLOWY_AI_SERVER_PORT").unwrap_or_else(|_| "5001".to_string());
+  let _server = MockAIServer::start(format!("0.0.0.0:{}", port))?;
+  loop {
+    std::thread::park();
+  }
+}
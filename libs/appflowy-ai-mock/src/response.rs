@@ -0,0 +1,225 @@
+//! Deterministic replacements of the answers of a language model. They're simple on purpose, but
+//! keep the properties tests rely on, ie. texts sharing words get similar embeddings and answers
+//! are taken from the context of a question.
+
+use appflowy_ai_client::dto::{CompletionType, CustomPrompt};
+use serde_json::{Map, Value};
+
+/// Words which don't carry meaning on their own, ignored when matching questions with contexts.
+const STOP_WORDS: &[&str] = &[
+  "about", "and", "are", "can", "does", "for", "how", "the", "tell", "what", "when", "where",
+  "which", "who", "why", "with", "you",
+];
+
+pub(crate) fn complete_text(
+  text: &str,
+  completion_type: Option<&CompletionType>,
+  custom_prompt: Option<&CustomPrompt>,
+) -> String {
+  let text = text.trim();
+  if custom_prompt.is_some() {
+    return first_sentence(text);
+  }
+  match completion_type {
+    None | Some(CompletionType::ImproveWriting) | Some(CompletionType::SpellingAndGrammar) => {
+      as_sentence(&words(text).join(" "))
+    },
+    Some(CompletionType::MakeShorter) => {
+      let words = words(text);
+      as_sentence(&words[..words.len().div_ceil(2)].join(" "))
+    },
+    Some(CompletionType::MakeLonger) => format!("{} In other words, {}", as_sentence(text), text),
+    Some(CompletionType::ContinueWriting) => {
+      format!("{} And the story continues from here.", as_sentence(text))
+    },
+  }
+}
+
+pub(crate) fn summarize_row(params: &Map<String, Value>) -> String {
+  let fields: Vec<String> = params
+    .iter()
+    .map(|(key, value)| match value {
+      Value::String(value) => format!("{} is {}", key, value),
+      value => format!("{} is {}", key, value),
+    })
+    .collect();
+  as_sentence(&fields.join(", "))
+}
+
+pub(crate) fn translate(content: &str, language: &str) -> String {
+  format!("[{}] {}", language, content)
+}
+
+/// Answers a question with the sentences of the context which share a word with the question.
+pub(crate) fn answer_question(question: &str, contexts: &[String]) -> String {
+  let keywords: Vec<String> = tokenize(question)
+    .into_iter()
+    .filter(|word| word.len() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+    .collect();
+  let sentences: Vec<&str> = contexts
+    .iter()
+    .flat_map(|context| context.split(['.', '!', '?', '\n']))
+    .map(str::trim)
+    .filter(|sentence| {
+      let words = tokenize(sentence);
+      keywords.iter().any(|keyword| {
+        words.iter().any(|word| {
+          word.starts_with(keyword.as_str())
+            || (word.len() >= 3 && keyword.starts_with(word.as_str()))
+        })
+      })
+    })
+    .collect();
+  if sentences.is_empty() {
+    return format!(
+      "I don't have enough information to answer \"{}\".",
+      question.trim()
+    );
+  }
+  sentences
+    .into_iter()
+    .map(as_sentence)
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+pub(crate) fn related_questions(topic: &str) -> Vec<String> {
+  let topic = topic.trim().trim_end_matches(['?', '.', '!']);
+  vec![
+    format!("Can you explain \"{}\" in more detail?", topic),
+    format!("What are some examples related to \"{}\"?", topic),
+    format!("Why does \"{}\" matter?", topic),
+  ]
+}
+
+/// Embeds a text as a normalized bag of words: every word adds to the dimension its hash falls
+/// into, so the cosine similarity of two embeddings grows with the words their texts share.
+pub(crate) fn embed(text: &str, dimensions: usize) -> Vec<f64> {
+  let dimensions = dimensions.max(1);
+  let mut embedding = vec![0.0; dimensions];
+  for word in tokenize(text) {
+    embedding[(fnv1a(word.as_bytes()) % dimensions as u64) as usize] += 1.0;
+  }
+  let norm = embedding.iter().map(|v| v * v).sum::<f64>().sqrt();
+  if norm == 0.0 {
+    // a zero vector has no direction, which makes cosine distance undefined
+    embedding[0] = 1.0;
+  } else {
+    embedding.iter_mut().for_each(|v| *v /= norm);
+  }
+  embedding
+}
+
+pub(crate) fn similarity(input: &str, expected: &str) -> f64 {
+  const DIMENSIONS: usize = 1536;
+  let a = embed(input, DIMENSIONS);
+  let b = embed(expected, DIMENSIONS);
+  a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+pub(crate) fn count_tokens(text: &str) -> usize {
+  tokenize(text).len().max(1)
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(str::to_lowercase)
+    .collect()
+}
+
+fn words(text: &str) -> Vec<&str> {
+  text.split_whitespace().collect()
+}
+
+fn first_sentence(text: &str) -> String {
+  let sentence = text
+    .split(['.', '!', '?', '\n'])
+    .map(str::trim)
+    .find(|sentence| !sentence.is_empty())
+    .unwrap_or(text);
+  as_sentence(sentence)
+}
+
+/// Capitalizes the text and ends it with a period.
+fn as_sentence(text: &str) -> String {
+  let text = text.trim();
+  let mut chars = text.chars();
+  let mut sentence = match chars.next() {
+    None => return String::new(),
+    Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+  };
+  if !sentence.ends_with(['.', '!', '?']) {
+    sentence.push('.');
+  }
+  sentence
+}
+
+/// Hash which is stable across processes and Rust versions, unlike the std `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn complete_text_test() {
+    assert_eq!(
+      complete_text(
+        "i feel  hungry",
+        Some(&CompletionType::ImproveWriting),
+        None
+      ),
+      "I feel hungry."
+    );
+    assert_eq!(
+      complete_text(
+        "one two three four",
+        Some(&CompletionType::MakeShorter),
+        None
+      ),
+      "One two."
+    );
+    let prompt = CustomPrompt {
+      system: "Summarize".to_string(),
+      user: None,
+    };
+    assert_eq!(
+      complete_text("\nfirst line. second line", None, Some(&prompt)),
+      "First line."
+    );
+  }
+
+  #[test]
+  fn answer_question_test() {
+    let contexts = vec![
+      "I am Lucas. I live in Singapore for 10 years. I'm a fan of AI.".to_string(),
+      "Lacus have lived in the US for five years".to_string(),
+    ];
+    let answer = answer_question("Where lucas live?", &contexts[..1]);
+    assert_eq!(answer, "I am Lucas. I live in Singapore for 10 years.");
+    let answer = answer_question("Where Lacus live?", &contexts[1..]);
+    assert_eq!(answer, "Lacus have lived in the US for five years.");
+    assert!(answer_question("What is Rust?", &contexts).starts_with("I don't have enough"));
+  }
+
+  #[test]
+  fn embedding_test() {
+    let a = embed("The quick brown fox", 64);
+    assert_eq!(a.len(), 64);
+    assert_eq!(a, embed("the QUICK brown fox!", 64));
+    let norm: f64 = a.iter().map(|v| v * v).sum();
+    assert!((norm - 1.0).abs() < 1e-9);
+    assert_eq!(embed("", 8)[0], 1.0);
+
+    assert!((similarity("quick brown fox", "quick brown fox") - 1.0).abs() < 1e-9);
+    let related = similarity("the quick brown fox", "a quick brown dog");
+    let unrelated = similarity("the quick brown fox", "tennis tournament schedule");
+    assert!(related > unrelated);
+  }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use appflowy_ai_client::dto::Document;
+use serde_json::Value;
+
+use crate::fault::{MockFault, MockFaultRule};
+
+/// Text added as a context of a chat.
+#[derive(Debug, Clone)]
+pub(crate) struct ChatContext {
+  pub content: String,
+  pub metadata: Value,
+}
+
+/// State shared by all the requests of a mock AI service.
+#[derive(Default)]
+pub(crate) struct MockState {
+  faults: Mutex<Vec<MockFaultRule>>,
+  requests: Mutex<HashMap<String, u64>>,
+  contexts: Mutex<HashMap<String, Vec<ChatContext>>>,
+  questions: Mutex<HashMap<String, String>>,
+  documents: Mutex<Vec<Document>>,
+}
+
+impl MockState {
+  /// Counts a request of a given route and returns the fault which should be applied to it.
+  pub fn begin_request(&self, path: &str) -> Option<MockFault> {
+    *self
+      .requests
+      .lock()
+      .unwrap()
+      .entry(path.to_string())
+      .or_default() += 1;
+
+    let mut faults = self.faults.lock().unwrap();
+    let index = faults.iter().position(|rule| rule.matches(path))?;
+    let rule = &mut faults[index];
+    let fault = rule.fault.clone();
+    if let Some(times) = rule.times.as_mut() {
+      *times = times.saturating_sub(1);
+      if *times == 0 {
+        faults.remove(index);
+      }
+    }
+    Some(fault)
+  }
+
+  pub fn inject_fault(&self, rule: MockFaultRule) {
+    self.faults.lock().unwrap().push(rule);
+  }
+
+  pub fn clear_faults(&self) {
+    self.faults.lock().unwrap().clear();
+  }
+
+  pub fn request_counts(&self) -> HashMap<String, u64> {
+    self.requests.lock().unwrap().clone()
+  }
+
+  pub fn add_chat_context(&self, chat_id: &str, context: ChatContext) {
+    self
+      .contexts
+      .lock()
+      .unwrap()
+      .entry(chat_id.to_string())
+      .or_default()
+      .push(context);
+  }
+
+  pub fn chat_contexts(&self, chat_id: &str) -> Vec<ChatContext> {
+    self
+      .contexts
+      .lock()
+      .unwrap()
+      .get(chat_id)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub fn set_last_question(&self, chat_id: &str, question: &str) {
+    self
+      .questions
+      .lock()
+      .unwrap()
+      .insert(chat_id.to_string(), question.to_string());
+  }
+
+  pub fn last_question(&self, chat_id: &str) -> Option<String> {
+    self.questions.lock().unwrap().get(chat_id).cloned()
+  }
+
+  /// Stores documents, replacing the ones with the same id.
+  pub fn index_documents(&self, documents: Vec<Document>) {
+    let mut stored = self.documents.lock().unwrap();
+    for document in documents {
+      stored.retain(|existing| existing.id != document.id);
+      stored.push(document);
+    }
+  }
+
+  pub fn documents(&self) -> Vec<Document> {
+    self.documents.lock().unwrap().clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fault_is_applied_given_number_of_times() {
+    let state = MockState::default();
+    state.inject_fault(MockFaultRule::new("/completion", MockFault::rate_limited()).times(2));
+    assert_eq!(state.begin_request("/embeddings"), None);
+    assert_eq!(
+      state.begin_request("/completion"),
+      Some(MockFault::rate_limited())
+    );
+    assert_eq!(
+      state.begin_request("/completion"),
+      Some(MockFault::rate_limited())
+    );
+    assert_eq!(state.begin_request("/completion"), None);
+
+    state.inject_fault(MockFaultRule::new("*", MockFault::MalformedBody));
+    assert_eq!(
+      state.begin_request("/completion"),
+      Some(MockFault::MalformedBody)
+    );
+    state.clear_faults();
+    assert_eq!(state.begin_request("/completion"), None);
+
+    let counts = state.request_counts();
+    assert_eq!(counts["/completion"], 5);
+    assert_eq!(counts["/embeddings"], 1);
+  }
+}
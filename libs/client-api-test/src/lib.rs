@@ -1,10 +1,14 @@
 mod client;
 mod database_util;
 mod log;
+#[cfg(not(target_arch = "wasm32"))]
+mod mock_ai;
 mod test_client;
 mod user;
 
 pub use client::*;
 pub use log::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use mock_ai::*;
 pub use test_client::*;
 pub use user::*;
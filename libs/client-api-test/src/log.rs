@@ -7,13 +7,18 @@ use {
 
 #[cfg(not(target_arch = "wasm32"))]
 fn get_bool_from_env_var(env_var_name: &str) -> bool {
+  get_bool_from_env_var_or(env_var_name, false)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn get_bool_from_env_var_or(env_var_name: &str, default: bool) -> bool {
   match std::env::var(env_var_name) {
     Ok(value) => match value.to_lowercase().as_str() {
       "true" | "1" => true,
       "false" | "0" => false,
-      _ => false,
+      _ => default,
     },
-    Err(_) => false,
+    Err(_) => default,
   }
 }
 
@@ -38,8 +43,15 @@ pub fn ai_test_enabled() -> bool {
   let enabled = get_bool_from_env_var("APPFLOWY_LOCAL_AI_TEST_ENABLED");
   if enabled {
     trace!("Local AI test is enabled");
+    return true;
+  }
+
+  // without the real AI service, AI tests run against the mock one
+  #[cfg(not(target_arch = "wasm32"))]
+  if crate::mock_ai::mock_ai_server().is_some() {
+    return true;
   }
-  enabled
+  false
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,105 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use appflowy_ai_mock::MockAIServer;
+use tracing::{info, warn};
+
+use crate::log::{get_bool_from_env_var_or, load_env};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Mock AI service the AppFlowy Cloud server under test connects to.
+pub struct MockAIService {
+  url: String,
+  /// `None` if the mock is run by another process, ie. another test binary or docker compose.
+  _server: Option<MockAIServer>,
+}
+
+impl MockAIService {
+  pub fn url(&self) -> &str {
+    &self.url
+  }
+}
+
+/// Returns the mock AI service the AppFlowy Cloud server under test connects to. The first call
+/// looks for a mock already listening on `APPFLOWY_AI_SERVER_PORT`, which is shared by all the
+/// test binaries, and otherwise starts one on that port, so that AI tests can run without the real
+/// AI service.
+///
+/// Returns `None` if `APPFLOWY_AI_MOCK_ENABLED` is false, or if neither a mock is running nor one
+/// can be started, ie. because the real AI service is listening on the port.
+pub fn mock_ai_server() -> Option<&'static MockAIService> {
+  static MOCK_AI_SERVICE: OnceLock<Option<MockAIService>> = OnceLock::new();
+  MOCK_AI_SERVICE
+    .get_or_init(find_or_start_mock_ai_service)
+    .as_ref()
+}
+
+fn find_or_start_mock_ai_service() -> Option<MockAIService> {
+  load_env();
+  if !get_bool_from_env_var_or("APPFLOWY_AI_MOCK_ENABLED", true) {
+    return None;
+  }
+  let port = std::env::var("APPFLOWY_AI_SERVER_PORT").unwrap_or_else(|_| "5001".to_string());
+  // a mock run by docker compose publishes its port on the host as well
+  let url = format!("http://127.0.0.1:{}", port);
+  if is_mock_ai_service(&url) {
+    info!("using mock AI service at {}", url);
+    return Some(MockAIService { url, _server: None });
+  }
+
+  let host = std::env::var("APPFLOWY_AI_SERVER_HOST").unwrap_or_else(|_| "localhost".to_string());
+  if !matches!(host.as_str(), "localhost" | "127.0.0.1" | "0.0.0.0") {
+    // the server connects to an AI service the tests can't start
+    return None;
+  }
+  // bind all interfaces, so that the mock is reachable from a server running in docker as well
+  match MockAIServer::start(format!("0.0.0.0:{}", port)) {
+    Ok(server) => {
+      info!("using mock AI service at {}", server.url());
+      Some(MockAIService {
+        url,
+        _server: Some(server),
+      })
+    },
+    // another test binary may have started the mock in the meantime
+    Err(_) if is_mock_ai_service(&url) => {
+      info!("using mock AI service at {}", url);
+      Some(MockAIService { url, _server: None })
+    },
+    Err(err) => {
+      warn!("mock AI service is not started on port {}: {}", port, err);
+      None
+    },
+  }
+}
+
+/// Returns whether the service at a given url is the mock, by requesting a route only the mock
+/// has. A plain HTTP request is sent, since this may be called from within an async runtime.
+fn is_mock_ai_service(url: &str) -> bool {
+  let addr = url.trim_start_matches("http://");
+  let Some(addr) = addr
+    .to_socket_addrs()
+    .ok()
+    .and_then(|mut addrs| addrs.next())
+  else {
+    return false;
+  };
+  let probe = || -> std::io::Result<String> {
+    let mut stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT)?;
+    stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+    stream.set_write_timeout(Some(PROBE_TIMEOUT))?;
+    write!(
+      stream,
+      "GET /mock/requests HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+      addr
+    )?;
+    let mut status_line = [0u8; 12];
+    stream.read_exact(&mut status_line)?;
+    Ok(String::from_utf8_lossy(&status_line).into_owned())
+  };
+  // ie. `HTTP/1.1 200`
+  matches!(probe(), Ok(status_line) if status_line.ends_with(" 200"))
+}
@@ -31,6 +31,9 @@ use client_api_entity::{
 use semver::Version;
 use shared_entity::dto::auth_dto::SignInTokenResponse;
 use shared_entity::dto::auth_dto::UpdateUserParams;
+use shared_entity::dto::auth_dto::{
+  AFUserNotificationSettings, UpdateUserNotificationSettingsParams,
+};
 use shared_entity::dto::workspace_dto::WorkspaceSpaceUsage;
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -845,6 +848,40 @@ impl Client {
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
 
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_user_notification_settings(
+    &self,
+  ) -> Result<AFUserNotificationSettings, AppResponseError> {
+    let url = format!("{}/api/user/notification_settings", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFUserNotificationSettings>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn update_user_notification_settings(
+    &self,
+    params: &UpdateUserNotificationSettingsParams,
+  ) -> Result<AFUserNotificationSettings, AppResponseError> {
+    let url = format!("{}/api/user/notification_settings", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFUserNotificationSettings>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn delete_user(&self) -> Result<(), AppResponseError> {
     let (provider_access_token, provider_refresh_token) = {
@@ -4,12 +4,15 @@ use bytes::Bytes;
 use futures_core::Stream;
 use reqwest::Method;
 use shared_entity::dto::ai_dto::{
-  CompleteTextParams, CompleteTextResponse, LocalAIConfig, SummarizeRowParams,
-  SummarizeRowResponse, TranslateRowParams, TranslateRowResponse,
+  AIPromptTemplate, CompleteTextParams, CompleteTextResponse, CreateAIPromptTemplateParams,
+  CreateMeetingNotesParams, LocalAIConfig, MeetingNotes, RepeatedAIPromptTemplate,
+  SummarizeRowParams, SummarizeRowResponse, TranslateRowParams, TranslateRowResponse,
+  UpdateAIPromptTemplateParams,
 };
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::time::Duration;
 use tracing::instrument;
+use uuid::Uuid;
 
 impl Client {
   pub async fn stream_completion_text(
@@ -116,4 +119,123 @@ impl Client {
       .await?
       .into_data()
   }
+
+  #[instrument(level = "info", skip_all)]
+  pub async fn list_ai_prompt_templates(
+    &self,
+    workspace_id: &str,
+  ) -> Result<RepeatedAIPromptTemplate, AppResponseError> {
+    let url = format!("{}/api/ai/{}/prompt", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<RepeatedAIPromptTemplate>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Creates a page with the notes of a meeting, written by AI from its transcript.
+  #[instrument(level = "info", skip_all)]
+  pub async fn create_meeting_notes(
+    &self,
+    workspace_id: &str,
+    params: CreateMeetingNotesParams,
+  ) -> Result<MeetingNotes, AppResponseError> {
+    let url = format!("{}/api/ai/{}/transcript", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<MeetingNotes>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all)]
+  pub async fn create_ai_prompt_template(
+    &self,
+    workspace_id: &str,
+    params: CreateAIPromptTemplateParams,
+  ) -> Result<AIPromptTemplate, AppResponseError> {
+    let url = format!("{}/api/ai/{}/prompt", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AIPromptTemplate>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all)]
+  pub async fn get_ai_prompt_template(
+    &self,
+    workspace_id: &str,
+    template_id: &Uuid,
+  ) -> Result<AIPromptTemplate, AppResponseError> {
+    let url = format!(
+      "{}/api/ai/{}/prompt/{}",
+      self.base_url, workspace_id, template_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AIPromptTemplate>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all)]
+  pub async fn update_ai_prompt_template(
+    &self,
+    workspace_id: &str,
+    template_id: &Uuid,
+    params: UpdateAIPromptTemplateParams,
+  ) -> Result<AIPromptTemplate, AppResponseError> {
+    let url = format!(
+      "{}/api/ai/{}/prompt/{}",
+      self.base_url, workspace_id, template_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AIPromptTemplate>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all)]
+  pub async fn delete_ai_prompt_template(
+    &self,
+    workspace_id: &str,
+    template_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/ai/{}/prompt/{}",
+      self.base_url, workspace_id, template_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
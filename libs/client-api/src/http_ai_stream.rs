@@ -0,0 +1,296 @@
+use crate::http::log_request_id;
+use crate::ws::{ConnectState, WSClient, WSConnectStateReceiver};
+use crate::Client;
+use bytes::Bytes;
+use collab_rt_entity::ai::AIStreamEvent;
+use futures::future::{select, Either};
+use futures::stream;
+use futures_core::Stream;
+use reqwest::Method;
+use shared_entity::dto::ai_dto::{AIStreamChunks, AIStreamStarted, CompleteTextParams};
+use shared_entity::response::{AppResponse, AppResponseError, ErrorCode};
+use std::collections::{BTreeMap, VecDeque};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tracing::{instrument, warn};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::http_chat::QuestionStream;
+#[cfg(not(target_arch = "wasm32"))]
+use infra::reqwest::JsonStream;
+
+impl Client {
+  /// Completes the text like [Client::stream_completion_text], but streams the completion over the
+  /// realtime connection of `ws_client`. The returned stream survives reconnections of the realtime
+  /// connection, fetching the chunks missed in the meantime.
+  #[instrument(level = "info", skip_all)]
+  pub async fn stream_completion_text_realtime(
+    &self,
+    ws_client: &WSClient,
+    workspace_id: &str,
+    params: CompleteTextParams,
+  ) -> Result<impl Stream<Item = Result<Bytes, AppResponseError>>, AppResponseError> {
+    // subscribe before the stream starts, so that no event is missed
+    let events = ws_client.subscribe_ai_stream_events();
+    let connect_state = ws_client.subscribe_connect_state();
+    let url = format!(
+      "{}/api/ai/{}/complete/realtime",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    let started = AppResponse::<AIStreamStarted>::from_response(resp)
+      .await?
+      .into_data()?;
+    Ok(self.resume_ai_stream(workspace_id, &started.stream_id, 0, events, connect_state))
+  }
+
+  /// Streams the answer like [Client::stream_answer_v2], but over the realtime connection of
+  /// `ws_client`. See [Client::stream_completion_text_realtime].
+  #[cfg(not(target_arch = "wasm32"))]
+  #[instrument(level = "info", skip_all)]
+  pub async fn stream_answer_v2_realtime(
+    &self,
+    ws_client: &WSClient,
+    workspace_id: &str,
+    chat_id: &str,
+    question_id: i64,
+  ) -> Result<QuestionStream, AppResponseError> {
+    let events = ws_client.subscribe_ai_stream_events();
+    let connect_state = ws_client.subscribe_connect_state();
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/{question_id}/v2/answer/realtime",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    let started = AppResponse::<AIStreamStarted>::from_response(resp)
+      .await?
+      .into_data()?;
+    let stream = self.resume_ai_stream(workspace_id, &started.stream_id, 0, events, connect_state);
+    Ok(QuestionStream::new(JsonStream::<
+      serde_json::Value,
+      _,
+      AppResponseError,
+    >::new(stream)))
+  }
+
+  /// Returns the chunks of an AI stream following the chunk numbered `after_seq`.
+  #[instrument(level = "info", skip_all)]
+  pub async fn get_ai_stream_chunks(
+    &self,
+    workspace_id: &str,
+    stream_id: &str,
+    after_seq: u64,
+  ) -> Result<AIStreamChunks, AppResponseError> {
+    let url = format!(
+      "{}/api/ai/{}/stream/{}",
+      self.base_url, workspace_id, stream_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&[("after", after_seq)])
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AIStreamChunks>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Puts the chunks of an AI stream back together, in order, from the chunk following
+  /// `after_seq`. Chunks received twice are skipped, and chunks missed, ie. while the realtime
+  /// connection was lost, are fetched with [Client::get_ai_stream_chunks].
+  pub fn resume_ai_stream(
+    &self,
+    workspace_id: &str,
+    stream_id: &str,
+    after_seq: u64,
+    events: Receiver<AIStreamEvent>,
+    connect_state: WSConnectStateReceiver,
+  ) -> impl Stream<Item = Result<Bytes, AppResponseError>> {
+    let state = AIStreamState {
+      client: self.clone(),
+      workspace_id: workspace_id.to_string(),
+      stream_id: stream_id.to_string(),
+      events,
+      connect_state: Some(connect_state),
+      last_seq: after_seq,
+      pending: BTreeMap::new(),
+      end: None,
+      ready: VecDeque::new(),
+      // chunks may have been sent before the events were subscribed
+      need_fetch: true,
+      done: false,
+    };
+    stream::unfold(state, |mut state| async move {
+      let item = state.next().await?;
+      Some((item, state))
+    })
+  }
+}
+
+struct AIStreamState {
+  client: Client,
+  workspace_id: String,
+  stream_id: String,
+  events: Receiver<AIStreamEvent>,
+  connect_state: Option<WSConnectStateReceiver>,
+  /// Sequence number of the last chunk returned by the stream.
+  last_seq: u64,
+  /// Chunks received ahead of the chunks preceding them.
+  pending: BTreeMap<u64, String>,
+  /// Sequence number of the last chunk of the stream and its error, once it's known.
+  end: Option<(u64, Option<String>)>,
+  ready: VecDeque<Result<Bytes, AppResponseError>>,
+  need_fetch: bool,
+  done: bool,
+}
+
+impl AIStreamState {
+  async fn next(&mut self) -> Option<Result<Bytes, AppResponseError>> {
+    loop {
+      if let Some(item) = self.ready.pop_front() {
+        return Some(item);
+      }
+      if self.done {
+        return None;
+      }
+
+      while let Some(data) = self.pending.remove(&(self.last_seq + 1)) {
+        self.last_seq += 1;
+        self.ready.push_back(Ok(Bytes::from(data)));
+      }
+      if !self.ready.is_empty() {
+        continue;
+      }
+
+      if let Some((last_seq, error)) = &self.end {
+        if self.last_seq >= *last_seq {
+          if let Some(error) = error {
+            self.ready.push_back(Err(AppResponseError::new(
+              ErrorCode::AIServiceUnavailable,
+              error.clone(),
+            )));
+          }
+          self.done = true;
+          continue;
+        }
+        // the end of the stream is known, but some chunks never arrived
+        self.need_fetch = true;
+      }
+
+      if self.need_fetch {
+        self.need_fetch = false;
+        match self
+          .client
+          .get_ai_stream_chunks(&self.workspace_id, &self.stream_id, self.last_seq)
+          .await
+        {
+          Ok(chunks) => self.on_chunks_fetched(chunks),
+          Err(err) if err.is_record_not_found() => {
+            self.ready.push_back(Err(err));
+            self.done = true;
+          },
+          Err(err) => {
+            // fetched again after reconnecting
+            warn!(
+              "failed to fetch chunks of AI stream {}: {}",
+              self.stream_id, err
+            );
+            if self.end.is_some() {
+              self.ready.push_back(Err(err));
+              self.done = true;
+            }
+          },
+        }
+        continue;
+      }
+
+      self.wait_for_event().await;
+    }
+  }
+
+  fn on_chunks_fetched(&mut self, chunks: AIStreamChunks) {
+    let mut last_fetched_seq = self.last_seq;
+    for chunk in chunks.chunks {
+      last_fetched_seq = last_fetched_seq.max(chunk.seq);
+      if chunk.seq > self.last_seq {
+        self.pending.insert(chunk.seq, chunk.data);
+      }
+    }
+    if chunks.finished {
+      // the fetched chunks are all the remaining chunks of a finished stream
+      self.end = Some((last_fetched_seq, chunks.error));
+    }
+    if let Some((last_seq, _)) = self.end {
+      if !self.is_contiguous_to(last_seq) {
+        self.ready.push_back(Err(AppResponseError::new(
+          ErrorCode::Internal,
+          format!("chunks of AI stream {} are missing", self.stream_id),
+        )));
+        self.done = true;
+      }
+    }
+  }
+
+  fn is_contiguous_to(&self, last_seq: u64) -> bool {
+    (self.last_seq + 1..=last_seq).all(|seq| self.pending.contains_key(&seq))
+  }
+
+  async fn wait_for_event(&mut self) {
+    let event = match self.connect_state.as_mut() {
+      None => Either::Left(self.events.recv().await),
+      Some(connect_state) => {
+        match select(Box::pin(self.events.recv()), Box::pin(connect_state.recv())).await {
+          Either::Left((event, _)) => Either::Left(event),
+          Either::Right((state, _)) => Either::Right(state),
+        }
+      },
+    };
+
+    match event {
+      Either::Left(Ok(event)) => {
+        if event.stream_id() != self.stream_id {
+          return;
+        }
+        match event {
+          AIStreamEvent::Chunk(chunk) => {
+            if chunk.seq > self.last_seq {
+              self.pending.insert(chunk.seq, chunk.data);
+            }
+          },
+          AIStreamEvent::Finished { last_seq, .. } => {
+            self.end.get_or_insert((last_seq, None));
+          },
+          AIStreamEvent::Failed {
+            last_seq, error, ..
+          } => {
+            self.end.get_or_insert((last_seq, Some(error)));
+          },
+        }
+      },
+      Either::Left(Err(RecvError::Lagged(_))) => self.need_fetch = true,
+      Either::Left(Err(RecvError::Closed)) => {
+        self.ready.push_back(Err(AppResponseError::new(
+          ErrorCode::Internal,
+          "realtime connection closed",
+        )));
+        self.done = true;
+      },
+      Either::Right(Ok(ConnectState::Connected)) => self.need_fetch = true,
+      Either::Right(Ok(_)) | Either::Right(Err(RecvError::Lagged(_))) => {},
+      Either::Right(Err(RecvError::Closed)) => self.connect_state = None,
+    }
+  }
+}
@@ -1,7 +1,8 @@
 use crate::Client;
 use client_api_entity::billing_dto::{
   SetSubscriptionRecurringInterval, SubscriptionCancelRequest, SubscriptionLinkRequest,
-  SubscriptionPlanDetail, SubscriptionTrialRequest, WorkspaceUsageAndLimit,
+  SubscriptionPlanDetail, SubscriptionTrialRequest, WorkspaceAIUsageAndLimit,
+  WorkspaceUsageAndLimit,
 };
 use reqwest::Method;
 use shared_entity::{
@@ -146,6 +147,26 @@ impl Client {
       .into_data()
   }
 
+  /// Query AI token and request usage of a workspace, together with its quotas
+  pub async fn get_workspace_ai_usage_and_limit(
+    &self,
+    workspace_id: &str,
+  ) -> Result<WorkspaceAIUsageAndLimit, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/ai-usage-and-limit",
+      self.base_url, workspace_id
+    );
+    self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<AppResponse<WorkspaceAIUsageAndLimit>>()
+      .await?
+      .into_data()
+  }
+
   /// Query all subscription status for a workspace
   pub async fn get_workspace_subscriptions(
     &self,
@@ -5,6 +5,7 @@ use client_api_entity::chat_dto::{
   ChatMessage, CreateAnswerMessageParams, CreateChatMessageParams, CreateChatParams, MessageCursor,
   RepeatedChatMessage, UpdateChatMessageContentParams,
 };
+use client_api_entity::workspace_dto::Page;
 use futures_core::{ready, Stream};
 use pin_project::pin_project;
 use reqwest::Method;
@@ -13,7 +14,10 @@ use shared_entity::dto::ai_dto::{
   CalculateSimilarityParams, RepeatedRelatedQuestion, SimilarityResponse, STREAM_ANSWER_KEY,
   STREAM_METADATA_KEY,
 };
-use shared_entity::dto::chat_dto::{ChatSettings, UpdateChatParams};
+use shared_entity::dto::chat_dto::{
+  ChatMessageBranches, ChatSettings, ExportChatParams, RemoveChatParticipantsParams,
+  RepeatedChatParticipant, SwitchChatBranchParams, UpdateChatParams, UpsertChatParticipantsParams,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -77,6 +81,127 @@ impl Client {
       .into_data()
   }
 
+  /// Get the participants of a shared chat
+  pub async fn get_chat_participants(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+  ) -> Result<RepeatedChatParticipant, AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/participant",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<RepeatedChatParticipant>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Share a chat with members of the workspace, or update their roles
+  pub async fn upsert_chat_participants(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: UpsertChatParticipantsParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/participant",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Stop sharing a chat with given users
+  pub async fn remove_chat_participants(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: RemoveChatParticipantsParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/participant",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Get the branches of the conversation starting at the same message as the given message
+  pub async fn get_chat_message_branches(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<ChatMessageBranches, AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/{message_id}/branch",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ChatMessageBranches>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Select the branch of the conversation containing the given message
+  pub async fn switch_chat_branch(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: SwitchChatBranchParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/chat/{workspace_id}/{chat_id}/branch", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Export all the messages of a chat to a new document page
+  pub async fn export_chat(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: ExportChatParams,
+  ) -> Result<Page, AppResponseError> {
+    let url = format!("{}/api/chat/{workspace_id}/{chat_id}/export", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Page>::from_response(resp).await?.into_data()
+  }
+
   /// Delete a chat for given chat_id
   pub async fn delete_chat(
     &self,
@@ -6,8 +6,15 @@ use app_error::AppError;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use client_api_entity::workspace_dto::{
-  AFDatabase, AFDatabaseField, AFDatabaseRow, AFDatabaseRowDetail, AFInsertDatabaseField,
-  DatabaseRowUpdatedItem, ListDatabaseRowDetailParam, ListDatabaseRowUpdatedParam,
+  AFDatabase, AFDatabaseAICell, AFDatabaseAIField, AFDatabaseComputedField, AFDatabaseField,
+  AFDatabaseRow, AFDatabaseRowChange, AFDatabaseRowDetail, AFDatabaseRowQueryResult,
+  AFDatabaseRowSchedule, AFDatabaseRowScheduleRun, AFDatabaseWebhook, AFDatabaseWebhookDelivery,
+  AFImportDatabaseCsv, AFImportDatabaseCsvResult, AFInsertDatabaseAIField,
+  AFInsertDatabaseComputedField, AFInsertDatabaseField, AFInsertDatabaseRowSchedule,
+  AFInsertDatabaseWebhook, AFUpdateDatabaseAIField, AFUpdateDatabaseComputedField,
+  AFUpdateDatabaseRowSchedule, AFUpdateDatabaseWebhook, DatabaseRowUpdatedItem,
+  ExportDatabaseCsvParams, ListDatabaseRowDetailParam, ListDatabaseRowUpdatedParam,
+  QueryDatabaseRowHistoryParams, QueryDatabaseRowsParams,
 };
 use client_api_entity::{
   AFCollabInfo, BatchQueryCollabParams, BatchQueryCollabResult, CollabParams, CreateCollabParams,
@@ -31,6 +38,7 @@ use std::time::Duration;
 use tokio_retry::strategy::ExponentialBackoff;
 use tokio_retry::{Action, Condition, RetryIf};
 use tracing::{event, instrument};
+use uuid::Uuid;
 
 impl Client {
   #[instrument(level = "info", skip_all, err)]
@@ -236,6 +244,190 @@ impl Client {
     AppResponse::from_response(resp).await?.into_data()
   }
 
+  /// Adds a field whose cells are filled by the AI, following a prompt which references other
+  /// fields of the row, e.g. `Summarize {{Description}}`. The cells are computed in the background
+  /// when rows are inserted or edited.
+  pub async fn add_database_ai_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    insert_field: &AFInsertDatabaseAIField,
+  ) -> Result<AFDatabaseAIField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/ai_field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(insert_field)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn list_database_ai_fields(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseAIField>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/ai_field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Changes the prompt of an AI field. All of its cells are computed again.
+  pub async fn update_database_ai_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+    params: &AFUpdateDatabaseAIField,
+  ) -> Result<AFDatabaseAIField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/ai_field/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Stops filling the field with the AI. The field itself is kept.
+  pub async fn delete_database_ai_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/ai_field/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Adds a field whose cells are computed by the server, from a formula or a rollup.
+  pub async fn add_database_computed_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    insert_field: &AFInsertDatabaseComputedField,
+  ) -> Result<AFDatabaseComputedField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/computed_field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(insert_field)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn list_database_computed_fields(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseComputedField>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/computed_field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Changes the formula or the rollup of a computed field. All of its cells are computed again.
+  pub async fn update_database_computed_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+    params: &AFUpdateDatabaseComputedField,
+  ) -> Result<AFDatabaseComputedField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/computed_field/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Stops computing the cells of the field. The field itself is kept.
+  pub async fn delete_database_computed_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/computed_field/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the state of the cells of an AI field, ie. whether they failed to be computed.
+  pub async fn list_database_ai_field_cells(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+  ) -> Result<Vec<AFDatabaseAICell>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/ai_field/{}/cell",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
   pub async fn list_database_row_ids_updated(
     &self,
     workspace_id: &str,
@@ -276,6 +468,28 @@ impl Client {
     AppResponse::from_response(resp).await?.into_data()
   }
 
+  /// Returns a page of the rows matching the filters of the query, in the order of its sorts.
+  /// Following pages are queried with the `next_cursor` of the result.
+  pub async fn query_database_rows(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &QueryDatabaseRowsParams,
+  ) -> Result<AFDatabaseRowQueryResult, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/query",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
   /// Example payload:
   /// {
   ///   "Name": "some_data",        # using column name
@@ -302,6 +516,324 @@ impl Client {
     AppResponse::from_response(resp).await?.into_data()
   }
 
+  /// Updates the cells of a row, with the same payload as [Client::add_database_item].
+  /// Cells of fields missing from the payload are left unchanged.
+  pub async fn update_database_item(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+    payload: &serde_json::Value,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(&payload)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Deletes a row from every view of the database.
+  pub async fn delete_database_item(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the changes of the cells of a row, the most recent first. Use the `changed_at` of
+  /// the last change as `before` to get the next page.
+  pub async fn get_database_row_history(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+    params: &QueryDatabaseRowHistoryParams,
+  ) -> Result<Vec<AFDatabaseRowChange>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/history",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Appends the rows of a CSV to the database. Records which can't be imported are returned
+  /// along with the new rows.
+  pub async fn import_database_csv(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &AFImportDatabaseCsv,
+  ) -> Result<AFImportDatabaseCsvResult, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/import/csv",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Returns the rows of a view of the database as a CSV, the inline view when `view_id` is
+  /// omitted.
+  pub async fn export_database_csv(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    view_id: Option<&str>,
+  ) -> Result<String, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/export/csv",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&ExportDatabaseCsvParams {
+        view_id: view_id.map(|view_id| view_id.to_string()),
+      })
+      .send()
+      .await?;
+    log_request_id(&resp);
+    if resp.status().is_success() {
+      Ok(resp.text().await?)
+    } else {
+      AppResponse::from_response(resp).await?.into_data()
+    }
+  }
+
+  /// Registers a webhook notified of the changes of the database. A secret is generated when
+  /// none is given. The secret is only returned by this call.
+  pub async fn create_database_webhook(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &AFInsertDatabaseWebhook,
+  ) -> Result<AFDatabaseWebhook, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/webhook",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn list_database_webhooks(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseWebhook>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/webhook",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn update_database_webhook(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    webhook_id: &Uuid,
+    params: &AFUpdateDatabaseWebhook,
+  ) -> Result<AFDatabaseWebhook, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/webhook/{}",
+      self.base_url, workspace_id, database_id, webhook_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Deletes a webhook, together with its pending deliveries.
+  pub async fn delete_database_webhook(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    webhook_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/webhook/{}",
+      self.base_url, workspace_id, database_id, webhook_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the latest deliveries of a webhook, the most recent first.
+  pub async fn list_database_webhook_deliveries(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    webhook_id: &Uuid,
+  ) -> Result<Vec<AFDatabaseWebhookDelivery>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/webhook/{}/delivery",
+      self.base_url, workspace_id, database_id, webhook_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Schedules creating rows of the database from a template, each time the rule of the schedule
+  /// runs.
+  pub async fn create_database_row_schedule(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &AFInsertDatabaseRowSchedule,
+  ) -> Result<AFDatabaseRowSchedule, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row_schedule",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn list_database_row_schedules(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseRowSchedule>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row_schedule",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn update_database_row_schedule(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    schedule_id: &Uuid,
+    params: &AFUpdateDatabaseRowSchedule,
+  ) -> Result<AFDatabaseRowSchedule, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row_schedule/{}",
+      self.base_url, workspace_id, database_id, schedule_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Deletes a schedule, together with its runs. Rows already created are kept.
+  pub async fn delete_database_row_schedule(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    schedule_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row_schedule/{}",
+      self.base_url, workspace_id, database_id, schedule_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the latest runs of a schedule, the most recent first.
+  pub async fn list_database_row_schedule_runs(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    schedule_id: &Uuid,
+  ) -> Result<Vec<AFDatabaseRowScheduleRun>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row_schedule/{}/run",
+      self.base_url, workspace_id, database_id, schedule_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
   #[instrument(level = "debug", skip_all, err)]
   pub async fn post_realtime_msg(
     &self,
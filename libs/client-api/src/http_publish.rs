@@ -1,5 +1,8 @@
 use bytes::Bytes;
-use client_api_entity::workspace_dto::PublishInfoView;
+use client_api_entity::workspace_dto::{
+  AFDatabaseForm, AFDatabaseFormSubmission, AFPublishDatabaseForm, AFSubmitDatabaseForm,
+  PublishInfoView,
+};
 use client_api_entity::{workspace_dto::PublishedDuplicate, PublishInfo, UpdatePublishNamespace};
 use client_api_entity::{
   CreateGlobalCommentParams, CreateReactionParams, DeleteGlobalCommentParams, DeleteReactionParams,
@@ -383,6 +386,44 @@ impl Client {
     Ok(bytes)
   }
 
+  /// Publishes a form through which anyone can add rows to the database. Like other published views,
+  /// the form is unpublished with [Client::unpublish_collabs].
+  pub async fn publish_database_form(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &AFPublishDatabaseForm,
+  ) -> Result<AFDatabaseForm, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/form",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Submits a published database form. Visitors don't need to be signed in.
+  pub async fn submit_published_database_form(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+    params: &AFSubmitDatabaseForm,
+  ) -> Result<AFDatabaseFormSubmission, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/published/{}/{}/form",
+      self.base_url, publish_namespace, publish_name
+    );
+    let resp = self.cloud_client.post(&url).json(params).send().await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
   pub async fn duplicate_published_to_workspace(
     &self,
     workspace_id: &str,
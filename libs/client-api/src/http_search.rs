@@ -1,4 +1,5 @@
 use app_error::ErrorCode;
+use client_api_entity::{AFIndexRebuildProgress, AFWorkspaceIndexStatus};
 use reqwest::Method;
 use shared_entity::dto::search_dto::SearchDocumentResponseItem;
 use shared_entity::response::{AppResponse, AppResponseError};
@@ -31,4 +32,64 @@ impl Client {
       .await?
       .into_data()
   }
+
+  /// Returns indexing status of all documents in a workspace.
+  pub async fn get_workspace_index_status(
+    &self,
+    workspace_id: &str,
+  ) -> Result<AFWorkspaceIndexStatus, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{workspace_id}/index/status",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFWorkspaceIndexStatus>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Starts re-indexing all documents in a workspace. Progress can be tracked with
+  /// [Client::get_workspace_index_status].
+  pub async fn rebuild_workspace_index(
+    &self,
+    workspace_id: &str,
+  ) -> Result<AFIndexRebuildProgress, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{workspace_id}/index/rebuild",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFIndexRebuildProgress>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn cancel_workspace_index_rebuild(
+    &self,
+    workspace_id: &str,
+  ) -> Result<AFIndexRebuildProgress, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{workspace_id}/index/rebuild",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFIndexRebuildProgress>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
@@ -4,6 +4,7 @@ use client_api_entity::workspace_dto::{
 };
 use reqwest::Method;
 use serde_json::json;
+use shared_entity::dto::ai_dto::PageTitleSuggestions;
 use shared_entity::response::{AppResponse, AppResponseError};
 use uuid::Uuid;
 
@@ -168,4 +169,24 @@ impl Client {
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
+
+  /// Returns titles suggested by AI for a page, based on its content.
+  pub async fn get_workspace_page_title_suggestions(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+  ) -> Result<PageTitleSuggestions, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/title-suggestion",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<PageTitleSuggestions>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
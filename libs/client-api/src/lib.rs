@@ -1,5 +1,6 @@
 mod http;
 mod http_ai;
+mod http_ai_stream;
 mod http_billing;
 
 mod http_access_request;
@@ -19,6 +19,8 @@ use crate::retry::retry_connect;
 use crate::ws::msg_queue::{AggregateMessageQueue, AggregateMessagesReceiver};
 use crate::ws::{ConnectState, ConnectStateNotify, WSError, WebSocketChannel};
 use client_websocket::{CloseCode, CloseFrame, Message, WebSocketStream};
+use collab_rt_entity::ai::AIStreamEvent;
+use collab_rt_entity::chat::ChatEvent;
 use collab_rt_entity::user::UserMessage;
 use collab_rt_entity::ClientCollabMessage;
 use collab_rt_entity::ServerCollabMessage;
@@ -74,6 +76,8 @@ pub struct WSClient {
   rt_msg_sender: Sender<Vec<ClientCollabMessage>>,
   http_sender: Arc<dyn WSClientHttpSender>,
   user_channel: Arc<Sender<UserMessage>>,
+  chat_channel: Arc<Sender<ChatEvent>>,
+  ai_stream_channel: Arc<Sender<AIStreamEvent>>,
   channels: Arc<RwLock<ChannelByObjectId>>,
   ping: Arc<Mutex<Option<ServerFixIntervalPing>>>,
   stop_ws_msg_loop_tx: Mutex<Option<oneshot::Sender<()>>>,
@@ -95,6 +99,8 @@ impl WSClient {
     let ping = Arc::new(Mutex::from(None));
     let http_sender = Arc::new(http_sender);
     let (user_channel, _) = channel(1);
+    let (chat_channel, _) = channel(config.buffer_capacity);
+    let (ai_stream_channel, _) = channel(config.buffer_capacity);
     let (rt_msg_sender, _) = channel(config.buffer_capacity);
     let connect_provider = Arc::new(connect_provider);
     let aggregate_queue = Arc::new(AggregateMessageQueue::new(MAXIMUM_BATCH_MESSAGE_SIZE));
@@ -105,6 +111,8 @@ impl WSClient {
       rt_msg_sender,
       http_sender,
       user_channel: Arc::new(user_channel),
+      chat_channel: Arc::new(chat_channel),
+      ai_stream_channel: Arc::new(ai_stream_channel),
       channels,
       ping,
       stop_ws_msg_loop_tx: Mutex::from(None),
@@ -264,6 +272,8 @@ impl WSClient {
     #[cfg(debug_assertions)]
     let cloned_skip_realtime_message = self.skip_realtime_message.clone();
     let user_message_tx = self.user_channel.as_ref().clone();
+    let chat_event_tx = self.chat_channel.as_ref().clone();
+    let ai_stream_event_tx = self.ai_stream_channel.as_ref().clone();
     tokio::spawn(async move {
       while let Some(Ok(ws_msg)) = stream.next().await {
         match ws_msg {
@@ -289,6 +299,12 @@ impl WSClient {
                 RealtimeMessage::User(user_message) => {
                   let _ = user_message_tx.send(user_message);
                 },
+                RealtimeMessage::Chat(chat_event) => {
+                  let _ = chat_event_tx.send(chat_event);
+                },
+                RealtimeMessage::AIStream(event) => {
+                  let _ = ai_stream_event_tx.send(event);
+                },
                 RealtimeMessage::System(sys_message) => match sys_message {
                   SystemMessage::RateLimit(_limit) => {},
                   SystemMessage::KickOff => {
@@ -368,6 +384,17 @@ impl WSClient {
     self.user_channel.subscribe()
   }
 
+  /// Receive events of all shared chats the user participates in.
+  pub fn subscribe_chat_events(&self) -> Receiver<ChatEvent> {
+    self.chat_channel.subscribe()
+  }
+
+  /// Receive the events of the AI responses streamed over the realtime connection. See
+  /// [crate::Client::resume_ai_stream] to put the chunks of a response back together.
+  pub fn subscribe_ai_stream_events(&self) -> Receiver<AIStreamEvent> {
+    self.ai_stream_channel.subscribe()
+  }
+
   pub fn subscribe_connect_state(&self) -> WSConnectStateReceiver {
     self.state_notify.lock().subscribe()
   }
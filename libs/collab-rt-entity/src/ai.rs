@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Events of an AI response delivered over the realtime connection instead of an HTTP stream.
+///
+/// Every stream is identified by the id returned when it was started. Its chunks are numbered from
+/// `1`, so that a client which missed some of them, ie. because it reconnected, can fetch them over
+/// HTTP and continue from the last chunk it received. Events may arrive out of order.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum AIStreamEvent {
+  Chunk(AIStreamChunk),
+  /// The response has been fully streamed. `last_seq` is the number of its last chunk.
+  Finished {
+    stream_id: String,
+    last_seq: u64,
+  },
+  /// The response failed after `last_seq` chunks.
+  Failed {
+    stream_id: String,
+    last_seq: u64,
+    error: String,
+  },
+}
+
+impl AIStreamEvent {
+  pub fn stream_id(&self) -> &str {
+    match self {
+      AIStreamEvent::Chunk(chunk) => &chunk.stream_id,
+      AIStreamEvent::Finished { stream_id, .. } => stream_id,
+      AIStreamEvent::Failed { stream_id, .. } => stream_id,
+    }
+  }
+}
+
+impl Display for AIStreamEvent {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AIStreamEvent::Chunk(chunk) => f.write_fmt(format_args!(
+        "Chunk: stream_id:{}, seq:{}",
+        chunk.stream_id, chunk.seq
+      )),
+      AIStreamEvent::Finished {
+        stream_id,
+        last_seq,
+      } => f.write_fmt(format_args!(
+        "Finished: stream_id:{}, last_seq:{}",
+        stream_id, last_seq
+      )),
+      AIStreamEvent::Failed {
+        stream_id,
+        last_seq,
+        error,
+      } => f.write_fmt(format_args!(
+        "Failed: stream_id:{}, last_seq:{}, error:{}",
+        stream_id, last_seq, error
+      )),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AIStreamChunk {
+  pub stream_id: String,
+  /// Position of the chunk in the stream, starting at `1`.
+  pub seq: u64,
+  /// Text of the chunk, in the same format as returned by the HTTP stream endpoints.
+  pub data: String,
+}
+
+/// Redis channel used to deliver [AIStreamNotification]s to every server.
+pub const AI_STREAM_EVENT_CHANNEL: &str = "af_ai_stream_channel";
+
+/// An [AIStreamEvent] published by the server streaming the response, for the server the user is
+/// connected to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AIStreamNotification {
+  /// User who started the stream, whose realtime connections receive the event.
+  pub uid: i64,
+  pub event: AIStreamEvent,
+}
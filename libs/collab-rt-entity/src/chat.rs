@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Events of a shared chat, broadcast to all of its participants.
+///
+/// Since bincode is used to serialize [crate::RealtimeMessage], which doesn't support the Serde
+/// `deserialize_any` method, the events only contain plain values. Message metadata is not included
+/// and should be fetched over HTTP if needed.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ChatEvent {
+  /// A new message was added to the chat.
+  NewMessage(ChatMessageEvent),
+  /// A chunk of an answer streamed by the AI service.
+  AnswerChunk(ChatAnswerChunk),
+  /// The answer to a question has been fully streamed.
+  AnswerFinished { chat_id: String, question_id: i64 },
+}
+
+impl ChatEvent {
+  pub fn chat_id(&self) -> &str {
+    match self {
+      ChatEvent::NewMessage(event) => &event.chat_id,
+      ChatEvent::AnswerChunk(event) => &event.chat_id,
+      ChatEvent::AnswerFinished { chat_id, .. } => chat_id,
+    }
+  }
+}
+
+impl Display for ChatEvent {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ChatEvent::NewMessage(event) => f.write_fmt(format_args!(
+        "NewMessage: chat_id:{}, message_id:{}",
+        event.chat_id, event.message_id
+      )),
+      ChatEvent::AnswerChunk(event) => f.write_fmt(format_args!(
+        "AnswerChunk: chat_id:{}, question_id:{}",
+        event.chat_id, event.question_id
+      )),
+      ChatEvent::AnswerFinished {
+        chat_id,
+        question_id,
+      } => f.write_fmt(format_args!(
+        "AnswerFinished: chat_id:{}, question_id:{}",
+        chat_id, question_id
+      )),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ChatMessageEvent {
+  pub chat_id: String,
+  pub message_id: i64,
+  /// Id of the user who created the message. `0` for messages created by the AI.
+  pub author_uid: i64,
+  pub content: String,
+  pub reply_message_id: Option<i64>,
+  /// The time, in milliseconds since the Unix epoch, when the message was created.
+  pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ChatAnswerChunk {
+  pub chat_id: String,
+  pub question_id: i64,
+  /// Raw bytes of the chunk, in the same format as returned by the answer stream endpoint.
+  pub data: Vec<u8>,
+}
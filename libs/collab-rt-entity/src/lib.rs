@@ -1,3 +1,5 @@
+pub mod ai;
+pub mod chat;
 mod message;
 pub mod user;
 
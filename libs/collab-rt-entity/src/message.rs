@@ -2,6 +2,8 @@ use anyhow::{anyhow, Error};
 use bincode::{DefaultOptions, Options};
 use std::collections::HashMap;
 
+use crate::ai::AIStreamEvent;
+use crate::chat::ChatEvent;
 use crate::client_message::ClientCollabMessage;
 use crate::server_message::ServerCollabMessage;
 use crate::user::UserMessage;
@@ -68,6 +70,8 @@ pub enum RealtimeMessage {
   ClientCollabV1(Vec<ClientCollabMessage>), // Deprecated
   ClientCollabV2(MessageByObjectId),
   ServerCollabV1(Vec<ServerCollabMessage>),
+  Chat(ChatEvent),
+  AIStream(AIStreamEvent),
 }
 
 impl RealtimeMessage {
@@ -161,6 +165,8 @@ impl Display for RealtimeMessage {
       RealtimeMessage::ClientCollabV1(_) => f.write_fmt(format_args!("ClientCollabV1")),
       RealtimeMessage::ClientCollabV2(_) => f.write_fmt(format_args!("ClientCollabV2")),
       RealtimeMessage::ServerCollabV1(_) => f.write_fmt(format_args!("ServerCollabV1")),
+      RealtimeMessage::Chat(event) => f.write_fmt(format_args!("Chat:{}", event)),
+      RealtimeMessage::AIStream(event) => f.write_fmt(format_args!("AIStream:{}", event)),
     }
   }
 }
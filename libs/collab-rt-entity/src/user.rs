@@ -7,6 +7,7 @@ use std::hash::Hash;
 pub enum UserMessage {
   ProfileChange(AFUserChange),
   WorkspaceMemberChange(AFWorkspaceMemberChange),
+  Reminder(AFReminderNotification),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
@@ -24,6 +25,37 @@ pub struct AFWorkspaceMemberChange {
   removed: Vec<AFWorkspaceMember>,
 }
 
+/// A reminder of a date the user set in a database row or a document, which is due.
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct AFReminderNotification {
+  /// Id of the reminder in the date cell or the date mention.
+  pub reminder_id: String,
+  pub workspace_id: String,
+  pub object_type: AFReminderObjectType,
+  /// Id of the row or of the document.
+  pub object_id: String,
+  /// Database of the row, none for documents.
+  pub database_id: Option<String>,
+  /// Field of the date cell, none for documents.
+  pub field_id: Option<String>,
+  /// Block of the date mention, none for database rows.
+  pub block_id: Option<String>,
+  /// Primary value of the row, or text of the block of the date mention.
+  pub title: String,
+  /// Time of the date, in seconds since the Unix epoch. Dates without time are at midnight in the
+  /// time zone of the user.
+  pub event_at: i64,
+  pub include_time: bool,
+  /// Time the reminder was due, in seconds since the Unix epoch.
+  pub remind_at: i64,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub enum AFReminderObjectType {
+  DatabaseRow,
+  Document,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub struct UserDevice {
   device_id: String,
@@ -1,4 +1,5 @@
 use crate::error::StreamError;
+use crate::indexing_queue::{IndexingQueue, IndexingQueueConfig};
 use crate::pubsub::{CollabStreamPub, CollabStreamSub};
 use crate::stream::CollabStream;
 use crate::stream_group::{StreamConfig, StreamGroup};
@@ -46,6 +47,13 @@ impl CollabRedisStream {
     Ok(group)
   }
 
+  pub async fn indexing_queue(
+    &self,
+    config: IndexingQueueConfig,
+  ) -> Result<IndexingQueue, StreamError> {
+    IndexingQueue::new(self.connection_manager.clone(), config).await
+  }
+
   pub async fn collab_update_stream(
     &self,
     workspace_id: &str,
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use collab_entity::CollabType;
+use redis::aio::ConnectionManager;
+use redis::{pipe, AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use tracing::{error, trace, warn};
+use uuid::Uuid;
+
+use crate::error::StreamError;
+use crate::model::{MessageId, StreamBinary, StreamMessage};
+use crate::stream_group::{ReadOption, StreamConfig, StreamGroup};
+
+pub const INDEXING_STREAM_KEY: &str = "af_indexing_queue";
+pub const INDEXING_GROUP_NAME: &str = "af_indexer";
+/// Prefix of the keys of the objects which have a job waiting in the queue. Used to deduplicate
+/// jobs. The keys expire, so that they don't outlive a consumer which stopped before removing them.
+const INDEXING_PENDING_KEY_PREFIX: &str = "af_indexing_queue:pending";
+/// Sorted set of jobs which should be enqueued later, scored by their due time in milliseconds.
+const INDEXING_DELAYED_KEY: &str = "af_indexing_queue:delayed";
+/// List of jobs which failed too many times.
+const INDEXING_DEAD_LETTER_KEY: &str = "af_indexing_queue:dead_letter";
+
+/// Moves jobs, which are due, from the delayed set to the stream. Running it as a script makes sure
+/// that the same job is not moved twice when multiple consumers are running.
+const PROMOTE_DUE_JOBS_SCRIPT: &str = r#"
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, ARGV[2])
+for _, job in ipairs(due) do
+  redis.call('XADD', KEYS[2], 'MAXLEN', '~', ARGV[3], '*', 'data', job)
+  redis.call('ZREM', KEYS[1], job)
+end
+return #due
+"#;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexingJob {
+  pub workspace_id: String,
+  pub object_id: String,
+  pub collab_type: CollabType,
+  /// Number of failed attempts to process this job so far.
+  pub attempt: u32,
+  pub enqueued_at: DateTime<Utc>,
+  /// Re-indexing of the workspace the job belongs to. All fragments of the object are embedded
+  /// again, instead of only the ones which changed.
+  #[serde(default)]
+  pub rebuild_id: Option<Uuid>,
+}
+
+impl IndexingJob {
+  pub fn new(workspace_id: String, object_id: String, collab_type: CollabType) -> Self {
+    Self {
+      workspace_id,
+      object_id,
+      collab_type,
+      attempt: 0,
+      enqueued_at: Utc::now(),
+      rebuild_id: None,
+    }
+  }
+
+  /// Returns a job of the re-indexing `rebuild_id` of the workspace.
+  pub fn rebuild(
+    workspace_id: String,
+    object_id: String,
+    collab_type: CollabType,
+    rebuild_id: Uuid,
+  ) -> Self {
+    Self {
+      rebuild_id: Some(rebuild_id),
+      ..Self::new(workspace_id, object_id, collab_type)
+    }
+  }
+
+  pub fn encode(&self) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(self)
+  }
+
+  pub fn decode(data: &[u8]) -> Result<Self, serde_json::Error> {
+    serde_json::from_slice(data)
+  }
+}
+
+impl TryFrom<&IndexingJob> for StreamBinary {
+  type Error = StreamError;
+
+  fn try_from(value: &IndexingJob) -> Result<Self, Self::Error> {
+    Ok(StreamBinary(value.encode()?))
+  }
+}
+
+/// Job which exceeded the maximum number of attempts, together with its last error.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeadLetterJob {
+  pub job: IndexingJob,
+  pub error: String,
+  pub failed_at: DateTime<Utc>,
+}
+
+/// Job received from the queue. It must be either acknowledged with [IndexingQueue::ack] or
+/// rescheduled with [IndexingQueue::retry] once processed.
+#[derive(Clone, Debug)]
+pub struct IndexingTask {
+  pub message_id: MessageId,
+  pub job: IndexingJob,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOutcome {
+  /// Job will be processed again after given delay.
+  Scheduled(Duration),
+  /// Job exceeded the maximum number of attempts and was moved to the dead-letter list.
+  DeadLettered,
+  /// Another job for the same object is already waiting in the queue.
+  Superseded,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexingQueueConfig {
+  /// Maximum number of jobs in the stream.
+  pub max_len: usize,
+  /// Number of attempts after which the job is moved to the dead-letter list.
+  pub max_attempts: u32,
+  /// Delay before the first retry. Every following retry doubles it.
+  pub base_backoff: Duration,
+  pub max_backoff: Duration,
+  /// Maximum number of jobs of a single workspace handed out in one batch. The remaining jobs of
+  /// that workspace are moved to the end of the queue so that other workspaces are not starved.
+  pub max_jobs_per_workspace: usize,
+  /// Maximum number of jobs kept in the dead-letter list.
+  pub dead_letter_max_len: usize,
+  /// Time after which a job is no longer considered as waiting in the queue when deduplicating
+  /// jobs. It should exceed [Self::max_backoff], so that retried jobs stay deduplicated.
+  pub pending_ttl: Duration,
+  /// Time after which a job received by a consumer, but not acknowledged, can be claimed by
+  /// another consumer.
+  pub claim_min_idle: Duration,
+}
+
+impl Default for IndexingQueueConfig {
+  fn default() -> Self {
+    Self {
+      max_len: 100_000,
+      max_attempts: 5,
+      base_backoff: Duration::from_secs(10),
+      max_backoff: Duration::from_secs(60 * 30),
+      max_jobs_per_workspace: 5,
+      dead_letter_max_len: 1000,
+      pending_ttl: Duration::from_secs(60 * 60),
+      claim_min_idle: Duration::from_secs(60 * 5),
+    }
+  }
+}
+
+impl IndexingQueueConfig {
+  /// Returns delay before the next attempt of a job which already failed `attempt` times.
+  pub fn backoff(&self, attempt: u32) -> Duration {
+    let exp = attempt.saturating_sub(1).min(31);
+    self
+      .base_backoff
+      .saturating_mul(1 << exp)
+      .min(self.max_backoff)
+  }
+}
+
+/// Queue of indexing jobs built on top of a [StreamGroup].
+///
+/// Jobs are deduplicated by object id: while a job for a given object is waiting in the queue,
+/// enqueuing another one is a no-op. Failed jobs are retried with an exponential backoff and moved
+/// to a dead-letter list after [IndexingQueueConfig::max_attempts].
+///
+/// Dropping the queue stops the background tasks of its [StreamGroup], so it's not cloneable:
+/// producers share an instance and every consumer creates its own.
+pub struct IndexingQueue {
+  group: StreamGroup,
+  connection_manager: ConnectionManager,
+  config: IndexingQueueConfig,
+}
+
+impl IndexingQueue {
+  pub async fn new(
+    connection_manager: ConnectionManager,
+    config: IndexingQueueConfig,
+  ) -> Result<Self, StreamError> {
+    let mut group = StreamGroup::new_with_config(
+      INDEXING_STREAM_KEY.to_string(),
+      INDEXING_GROUP_NAME,
+      connection_manager.clone(),
+      StreamConfig::new().with_max_len(config.max_len),
+    );
+    group.ensure_consumer_group().await?;
+    Ok(Self {
+      group,
+      connection_manager,
+      config,
+    })
+  }
+
+  /// Recreates the consumer group, ie. after the stream has been removed from Redis.
+  pub async fn ensure_consumer_group(&mut self) -> Result<(), StreamError> {
+    self.group.ensure_consumer_group().await
+  }
+
+  pub fn config(&self) -> &IndexingQueueConfig {
+    &self.config
+  }
+
+  /// Enqueues a job to be processed after given delay. Returns `false` if a job for the same object
+  /// is already waiting in the queue.
+  pub async fn enqueue(&mut self, job: IndexingJob, delay: Duration) -> Result<bool, StreamError> {
+    let pending_key = pending_key(&job);
+    let added: Option<String> = redis::cmd("SET")
+      .arg(&pending_key)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(self.config.pending_ttl.as_secs().max(1))
+      .query_async(&mut self.connection_manager)
+      .await?;
+    if added.is_none() {
+      trace!("indexing job for {} is already enqueued", job.object_id);
+      return Ok(false);
+    }
+
+    let result = self.schedule(&job, delay).await;
+    if result.is_err() {
+      let _: Result<usize, _> = self.connection_manager.del(&pending_key).await;
+    }
+    result.map(|_| true)
+  }
+
+  async fn schedule(&mut self, job: &IndexingJob, delay: Duration) -> Result<(), StreamError> {
+    if delay.is_zero() {
+      self.group.insert_message(job).await
+    } else {
+      let due = Utc::now().timestamp_millis() + delay.as_millis() as i64;
+      let () = self
+        .connection_manager
+        .zadd(INDEXING_DELAYED_KEY, job.encode()?, due)
+        .await?;
+      Ok(())
+    }
+  }
+
+  /// Claims up to `count` jobs left unacknowledged for [IndexingQueueConfig::claim_min_idle], ie.
+  /// by consumers which stopped before finishing them. Claimed jobs aren't claimed again before
+  /// they are idle again, so calling it until it returns no job claims all of them.
+  pub async fn claim_unacked(
+    &mut self,
+    consumer: &str,
+    count: usize,
+  ) -> Result<Vec<IndexingTask>, StreamError> {
+    let messages = self
+      .group
+      .claim_idle_messages(consumer, self.config.claim_min_idle, count)
+      .await?;
+    self.decode_tasks(messages).await
+  }
+
+  /// Receives up to `count` jobs. Jobs which are due are moved from the delayed set to the stream
+  /// first.
+  pub async fn consume(
+    &mut self,
+    consumer: &str,
+    count: usize,
+  ) -> Result<Vec<IndexingTask>, StreamError> {
+    self.promote_due_jobs(count).await?;
+    let messages = self
+      .group
+      .consumer_messages(consumer, ReadOption::Count(count))
+      .await?;
+    let tasks = self.decode_tasks(messages).await?;
+    let (tasks, deferred) = self.split_by_workspace(tasks);
+    if !deferred.is_empty() {
+      self.defer(deferred).await?;
+    }
+
+    if !tasks.is_empty() {
+      // Jobs received by the consumer are no longer waiting, so new changes of these objects need
+      // to be enqueued again.
+      let pending_keys = tasks
+        .iter()
+        .map(|task| pending_key(&task.job))
+        .collect::<Vec<_>>();
+      let _: usize = self.connection_manager.del(pending_keys).await?;
+    }
+    Ok(tasks)
+  }
+
+  /// Acknowledges jobs which have been processed.
+  pub async fn ack(&mut self, tasks: &[IndexingTask]) -> Result<(), StreamError> {
+    let ids = tasks
+      .iter()
+      .map(|task| task.message_id.to_string())
+      .collect::<Vec<_>>();
+    self.group.ack_message_ids(ids).await
+  }
+
+  /// Acknowledges a failed job and either schedules its next attempt or moves it to the
+  /// dead-letter list.
+  pub async fn retry(
+    &mut self,
+    task: IndexingTask,
+    error: &str,
+  ) -> Result<RetryOutcome, StreamError> {
+    self.group.ack_message_ids(vec![task.message_id]).await?;
+
+    let mut job = task.job;
+    job.attempt += 1;
+    if job.attempt >= self.config.max_attempts {
+      let dead_letter = DeadLetterJob {
+        job,
+        error: error.to_string(),
+        failed_at: Utc::now(),
+      };
+      let () = pipe()
+        .lpush(INDEXING_DEAD_LETTER_KEY, serde_json::to_vec(&dead_letter)?)
+        .ignore()
+        .ltrim(
+          INDEXING_DEAD_LETTER_KEY,
+          0,
+          self.config.dead_letter_max_len as isize - 1,
+        )
+        .ignore()
+        .query_async(&mut self.connection_manager)
+        .await?;
+      warn!(
+        "indexing job for {} moved to dead-letter list after {} attempts: {}",
+        dead_letter.job.object_id, dead_letter.job.attempt, error
+      );
+      return Ok(RetryOutcome::DeadLettered);
+    }
+
+    let delay = self.config.backoff(job.attempt);
+    if self.enqueue(job, delay).await? {
+      Ok(RetryOutcome::Scheduled(delay))
+    } else {
+      Ok(RetryOutcome::Superseded)
+    }
+  }
+
+  /// Returns up to `limit` most recent jobs from the dead-letter list.
+  pub async fn dead_letters(&mut self, limit: usize) -> Result<Vec<DeadLetterJob>, StreamError> {
+    if limit == 0 {
+      return Ok(vec![]);
+    }
+    let values: Vec<Vec<u8>> = self
+      .connection_manager
+      .lrange(INDEXING_DEAD_LETTER_KEY, 0, limit as isize - 1)
+      .await?;
+    let jobs = values
+      .into_iter()
+      .filter_map(|value| serde_json::from_slice(&value).ok())
+      .collect();
+    Ok(jobs)
+  }
+
+  async fn promote_due_jobs(&mut self, count: usize) -> Result<(), StreamError> {
+    let promoted: usize = Script::new(PROMOTE_DUE_JOBS_SCRIPT)
+      .key(INDEXING_DELAYED_KEY)
+      .key(INDEXING_STREAM_KEY)
+      .arg(Utc::now().timestamp_millis())
+      .arg(count)
+      .arg(self.config.max_len)
+      .invoke_async(&mut self.connection_manager)
+      .await?;
+    if promoted > 0 {
+      trace!("promoted {} delayed indexing jobs", promoted);
+    }
+    Ok(())
+  }
+
+  async fn decode_tasks(
+    &mut self,
+    messages: Vec<StreamMessage>,
+  ) -> Result<Vec<IndexingTask>, StreamError> {
+    let mut tasks = Vec::with_capacity(messages.len());
+    let mut invalid = vec![];
+    for message in messages {
+      match IndexingJob::decode(&message.data) {
+        Ok(job) => tasks.push(IndexingTask {
+          message_id: message.id,
+          job,
+        }),
+        Err(err) => {
+          error!("failed to decode indexing job {}: {}", message.id, err);
+          invalid.push(message.id);
+        },
+      }
+    }
+    self.group.ack_message_ids(invalid).await?;
+    Ok(tasks)
+  }
+
+  /// Splits tasks into those which can be processed now and those which exceed the per-workspace
+  /// limit of a batch.
+  fn split_by_workspace(&self, tasks: Vec<IndexingTask>) -> (Vec<IndexingTask>, Vec<IndexingTask>) {
+    let mut per_workspace: HashMap<String, usize> = HashMap::new();
+    tasks.into_iter().partition(|task| {
+      let count = per_workspace
+        .entry(task.job.workspace_id.clone())
+        .or_default();
+      *count += 1;
+      *count <= self.config.max_jobs_per_workspace
+    })
+  }
+
+  /// Moves tasks to the end of the queue.
+  async fn defer(&mut self, tasks: Vec<IndexingTask>) -> Result<(), StreamError> {
+    trace!("deferring {} indexing jobs", tasks.len());
+    let mut messages = Vec::with_capacity(tasks.len());
+    for task in tasks.iter() {
+      messages.push(StreamBinary::try_from(&task.job)?);
+    }
+    self.group.insert_messages(messages).await?;
+    self.ack(&tasks).await
+  }
+}
+
+/// Jobs of a re-indexing are deduplicated separately, so that they are not dropped in favor of a
+/// regular job of the same object, nor of a job of a previous re-indexing.
+fn pending_key(job: &IndexingJob) -> String {
+  match &job.rebuild_id {
+    None => format!("{}:{}", INDEXING_PENDING_KEY_PREFIX, job.object_id),
+    Some(rebuild_id) => format!(
+      "{}:{}:{}",
+      INDEXING_PENDING_KEY_PREFIX, rebuild_id, job.object_id
+    ),
+  }
+}
+
+/// Returns a consumer name unique to this process, so that consumers of different processes
+/// don't receive each other's unacknowledged jobs.
+pub fn unique_consumer_name(prefix: &str) -> String {
+  let host = std::env::var("HOSTNAME")
+    .ok()
+    .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+    .map(|host| host.trim().to_string())
+    .filter(|host| !host.is_empty())
+    .unwrap_or_else(|| "localhost".to_string());
+  format!("{}-{}-{}", prefix, host, std::process::id())
+}
+
+#[cfg(test)]
+mod test {
+  use super::IndexingQueueConfig;
+  use std::time::Duration;
+
+  #[test]
+  fn test_indexing_job_backoff() {
+    let config = IndexingQueueConfig {
+      base_backoff: Duration::from_secs(10),
+      max_backoff: Duration::from_secs(60),
+      ..Default::default()
+    };
+    assert_eq!(config.backoff(1), Duration::from_secs(10));
+    assert_eq!(config.backoff(2), Duration::from_secs(20));
+    assert_eq!(config.backoff(3), Duration::from_secs(40));
+    assert_eq!(config.backoff(4), Duration::from_secs(60));
+    assert_eq!(config.backoff(100), Duration::from_secs(60));
+  }
+}
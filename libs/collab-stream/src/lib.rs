@@ -1,5 +1,6 @@
 pub mod client;
 pub mod error;
+pub mod indexing_queue;
 pub mod model;
 pub mod pubsub;
 pub mod stream;
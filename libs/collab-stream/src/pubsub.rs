@@ -6,8 +6,10 @@ use prost::Message;
 #[allow(deprecated)]
 use redis::aio::{Connection, ConnectionManager};
 use redis::{AsyncCommands, RedisWrite, ToRedisArgs};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tracing::instrument;
+use tokio::sync::broadcast;
+use tracing::{error, instrument};
 
 const ACTIVE_COLLAB_CHANNEL: &str = "active_collab_channel";
 
@@ -55,6 +57,47 @@ impl CollabStreamPub {
   }
 }
 
+/// Receives the JSON messages published on a Redis channel, which are delivered to every server
+/// subscribed to it.
+pub struct RedisPubSubListener<T: Clone> {
+  pub notify: broadcast::Sender<T>,
+}
+
+impl<T> RedisPubSubListener<T>
+where
+  T: Clone + DeserializeOwned + Send + 'static,
+{
+  pub async fn new(redis_client: &redis::Client, channel: &str) -> Result<Self, StreamError> {
+    #[allow(deprecated)]
+    let mut pubsub = redis_client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+
+    let (tx, _) = broadcast::channel(1000);
+    let notify = tx.clone();
+    let channel = channel.to_string();
+    tokio::spawn(async move {
+      let mut messages = Box::pin(pubsub.into_on_message());
+      while let Some(msg) = messages.next().await {
+        let payload = msg.get_payload_bytes();
+        match serde_json::from_slice::<T>(payload) {
+          Ok(message) => {
+            let _ = tx.send(message);
+          },
+          Err(err) => {
+            error!(
+              "Failed to deserialize message: {:?}, payload: {}",
+              err,
+              String::from_utf8_lossy(payload)
+            );
+          },
+        }
+      }
+      error!("Subscription to redis channel {} is closed", channel);
+    });
+    Ok(Self { notify })
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PubSubMessage {
   pub workspace_id: String,
@@ -7,7 +7,7 @@ use redis::streams::{
   StreamClaimOptions, StreamClaimReply, StreamMaxlen, StreamPendingData, StreamPendingReply,
   StreamReadOptions,
 };
-use redis::{pipe, AsyncCommands, ErrorKind, Pipeline, RedisResult};
+use redis::{from_redis_value, pipe, AsyncCommands, ErrorKind, Pipeline, RedisResult, Value};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, trace, warn};
 
@@ -344,6 +344,59 @@ impl StreamGroup {
     Ok(messages)
   }
 
+  /// Claims up to `count` messages which were delivered to any consumer of the group, but haven't
+  /// been acknowledged for at least `min_idle`, ie. because their consumer stopped. Unlike
+  /// [Self::get_unacked_messages], it goes through all the pending messages of the group, using
+  /// `XAUTOCLAIM`. Claimed messages are idle again, so calling it until it returns no message
+  /// claims all of them.
+  pub async fn claim_idle_messages(
+    &mut self,
+    consumer_name: &str,
+    min_idle: Duration,
+    count: usize,
+  ) -> Result<Vec<StreamMessage>, StreamError> {
+    let mut cursor = "0-0".to_string();
+    loop {
+      let reply: Vec<Value> = redis::cmd("XAUTOCLAIM")
+        .arg(&self.stream_key)
+        .arg(&self.group_name)
+        .arg(consumer_name)
+        .arg(min_idle.as_millis() as u64)
+        .arg(&cursor)
+        .arg("COUNT")
+        .arg(count)
+        .query_async(&mut self.connection_manager)
+        .await?;
+      // the reply of redis 7 has a third element: the ids of the deleted messages
+      let (next_cursor, claimed) = match reply.as_slice() {
+        [next_cursor, claimed, ..] => (
+          from_redis_value::<String>(next_cursor)?,
+          from_redis_value::<StreamClaimReply>(claimed)?,
+        ),
+        _ => {
+          return Err(StreamError::UnexpectedValue(
+            "invalid XAUTOCLAIM reply".to_string(),
+          ))
+        },
+      };
+
+      let mut messages = Vec::with_capacity(claimed.ids.len());
+      for id in claimed.ids {
+        match StreamMessage::try_from(id) {
+          Ok(message) => messages.push(message),
+          Err(err) => error!("{:?}", err),
+        }
+      }
+      // a call only scans a limited number of pending messages, so the cursor is followed until
+      // messages are claimed or all of them have been scanned
+      if !messages.is_empty() || next_cursor == "0-0" {
+        trace!("Claimed idle messages: {}", messages.len());
+        return Ok(messages);
+      }
+      cursor = next_cursor;
+    }
+  }
+
   /// Reads all messages from the stream
   ///
   pub async fn get_all_message(&mut self) -> Result<Vec<StreamMessage>, StreamError> {
@@ -950,6 +950,48 @@ pub enum IndexingStatus {
   Indexed,
 }
 
+/// Indexing status of all documents in a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFWorkspaceIndexStatus {
+  /// Whether search indexing has been disabled in workspace settings.
+  pub disabled: bool,
+  /// Number of documents, which have been indexed successfully.
+  pub indexed: i64,
+  /// Number of documents, which have not been indexed yet.
+  pub pending: i64,
+  /// Number of documents, which failed to be indexed the last time it was attempted.
+  pub failed: i64,
+  /// Most recent indexing errors.
+  pub errors: Vec<AFCollabIndexError>,
+  /// Progress of the last workspace re-indexing, if one has been requested.
+  pub rebuild: Option<AFIndexRebuildProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabIndexError {
+  pub object_id: String,
+  pub collab_type: CollabType,
+  pub error: String,
+  /// Number of consecutive failed attempts to index this collab.
+  pub attempts: i32,
+  pub failed_at: DateTime<Utc>,
+}
+
+/// Progress of re-indexing all documents in a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFIndexRebuildProgress {
+  /// Number of documents to be indexed.
+  pub total: u64,
+  /// Number of documents processed so far, including failed ones.
+  pub processed: u64,
+  /// Number of documents which failed to be indexed.
+  pub failed: u64,
+  pub started_at: DateTime<Utc>,
+  /// Set once re-indexing has finished or has been cancelled.
+  pub finished_at: Option<DateTime<Utc>>,
+  pub cancelled: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TemplateCategories {
   pub categories: Vec<TemplateCategory>,
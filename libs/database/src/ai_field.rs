@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseAICell, AFDatabaseAICellStatus, AFDatabaseAIField,
+};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFAIDatabaseFieldRow {
+  field_id: String,
+  prompt: String,
+  created_by: i64,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl From<AFAIDatabaseFieldRow> for AFDatabaseAIField {
+  fn from(row: AFAIDatabaseFieldRow) -> Self {
+    Self {
+      field_id: row.field_id,
+      prompt: row.prompt,
+      created_by: row.created_by,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    }
+  }
+}
+
+#[derive(FromRow)]
+struct AFAIDatabaseCellRow {
+  row_id: String,
+  status: i16,
+  error: Option<String>,
+  attempts: i32,
+  updated_at: DateTime<Utc>,
+}
+
+impl From<AFAIDatabaseCellRow> for AFDatabaseAICell {
+  fn from(row: AFAIDatabaseCellRow) -> Self {
+    Self {
+      row_id: row.row_id,
+      status: AFDatabaseAICellStatus::from(row.status),
+      error: row.error,
+      attempts: row.attempts,
+      updated_at: row.updated_at,
+    }
+  }
+}
+
+/// State of an AI cell, used to decide whether the cell has to be computed again.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFAIDatabaseCellState {
+  pub field_id: String,
+  pub status: i16,
+  pub input_hash: String,
+  pub attempts: i32,
+}
+
+/// A row which has AI cells to be computed.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFAIDatabaseRowId {
+  pub workspace_id: Uuid,
+  pub database_id: String,
+  pub row_id: String,
+}
+
+/// A database which has at least one AI field.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFAIDatabaseId {
+  pub workspace_id: Uuid,
+  pub database_id: String,
+}
+
+fn ai_field_not_found(database_id: &str, field_id: &str) -> AppError {
+  AppError::RecordNotFound(format!(
+    "AI field:{} is not found in database:{}",
+    field_id, database_id
+  ))
+}
+
+pub async fn insert_ai_database_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+  prompt: &str,
+  uid: i64,
+) -> Result<AFDatabaseAIField, AppError> {
+  let row = sqlx::query_as::<_, AFAIDatabaseFieldRow>(
+    r#"
+      INSERT INTO af_ai_database_field (database_id, field_id, workspace_id, prompt, created_by)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING field_id, prompt, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(database_id)
+  .bind(field_id)
+  .bind(workspace_id)
+  .bind(prompt)
+  .bind(uid)
+  .fetch_one(executor)
+  .await
+  .map_err(|err| match &err {
+    sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+      AppError::RecordAlreadyExists(format!(
+        "field:{} of database:{} is already an AI field",
+        field_id, database_id
+      ))
+    },
+    _ => AppError::from(err),
+  })?;
+  Ok(row.into())
+}
+
+pub async fn select_ai_database_fields<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseAIField>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIDatabaseFieldRow>(
+    r#"
+      SELECT field_id, prompt, created_by, created_at, updated_at
+      FROM af_ai_database_field
+      WHERE workspace_id = $1 AND database_id = $2
+      ORDER BY created_at ASC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Updates the prompt of an AI field. The states of its cells are removed, so that all of them are
+/// computed again.
+pub async fn update_ai_database_field_prompt(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+  prompt: &str,
+) -> Result<AFDatabaseAIField, AppError> {
+  let row = sqlx::query_as::<_, AFAIDatabaseFieldRow>(
+    r#"
+      UPDATE af_ai_database_field
+      SET prompt = $4, updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND database_id = $2 AND field_id = $3
+      RETURNING field_id, prompt, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .bind(prompt)
+  .fetch_optional(txn.deref_mut())
+  .await?
+  .ok_or_else(|| ai_field_not_found(database_id, field_id))?;
+
+  sqlx::query("DELETE FROM af_ai_database_cell WHERE database_id = $1 AND field_id = $2")
+    .bind(database_id)
+    .bind(field_id)
+    .execute(txn.deref_mut())
+    .await?;
+  Ok(row.into())
+}
+
+pub async fn delete_ai_database_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_ai_database_field
+      WHERE workspace_id = $1 AND database_id = $2 AND field_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .execute(executor)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(ai_field_not_found(database_id, field_id));
+  }
+  Ok(())
+}
+
+pub async fn select_ai_databases<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+) -> Result<Vec<AFAIDatabaseId>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIDatabaseId>(
+    r#"
+      SELECT DISTINCT workspace_id, database_id
+      FROM af_ai_database_field
+    "#,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn select_ai_database_cells<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+) -> Result<Vec<AFDatabaseAICell>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIDatabaseCellRow>(
+    r#"
+      SELECT c.row_id, c.status, c.error, c.attempts, c.updated_at
+      FROM af_ai_database_cell c
+      JOIN af_ai_database_field f USING (database_id, field_id)
+      WHERE f.workspace_id = $1 AND c.database_id = $2 AND c.field_id = $3
+      ORDER BY c.updated_at DESC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Returns the states of the AI cells of a row, by field id.
+pub async fn select_ai_database_cell_states<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  database_id: &str,
+  row_id: &str,
+) -> Result<HashMap<String, AFAIDatabaseCellState>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIDatabaseCellState>(
+    r#"
+      SELECT field_id, status, input_hash, attempts
+      FROM af_ai_database_cell
+      WHERE database_id = $1 AND row_id = $2
+    "#,
+  )
+  .bind(database_id)
+  .bind(row_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|state| (state.field_id.clone(), state))
+      .collect(),
+  )
+}
+
+/// Records the state of an AI cell. The number of attempts counts the failures since the input of
+/// the cell last changed.
+pub async fn upsert_ai_database_cell<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  database_id: &str,
+  field_id: &str,
+  row_id: &str,
+  status: AFDatabaseAICellStatus,
+  input_hash: &str,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  let is_failed = status == AFDatabaseAICellStatus::Failed;
+  let is_completed = status == AFDatabaseAICellStatus::Completed;
+  sqlx::query(
+    r#"
+      INSERT INTO af_ai_database_cell
+        (database_id, field_id, row_id, status, input_hash, error, attempts)
+      VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $7 THEN 1 ELSE 0 END)
+      ON CONFLICT (database_id, field_id, row_id) DO UPDATE
+      SET status = EXCLUDED.status,
+          input_hash = EXCLUDED.input_hash,
+          error = EXCLUDED.error,
+          attempts = CASE
+            WHEN af_ai_database_cell.input_hash <> EXCLUDED.input_hash OR $8 THEN EXCLUDED.attempts
+            WHEN $7 THEN af_ai_database_cell.attempts + 1
+            ELSE af_ai_database_cell.attempts
+          END,
+          updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(database_id)
+  .bind(field_id)
+  .bind(row_id)
+  .bind(status as i16)
+  .bind(input_hash)
+  .bind(error)
+  .bind(is_failed)
+  .bind(is_completed)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns rows whose AI cells should be computed again: cells which were rate limited before
+/// `rate_limited_before`, cells which failed fewer than `max_attempts` times before `failed_before`
+/// and cells which are pending since then, ie. because the server was restarted.
+pub async fn select_retriable_ai_database_rows<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  rate_limited_before: DateTime<Utc>,
+  failed_before: DateTime<Utc>,
+  max_attempts: i32,
+  limit: i64,
+) -> Result<Vec<AFAIDatabaseRowId>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIDatabaseRowId>(
+    r#"
+      SELECT DISTINCT f.workspace_id, c.database_id, c.row_id
+      FROM af_ai_database_cell c
+      JOIN af_ai_database_field f USING (database_id, field_id)
+      WHERE (c.status = $1 AND c.updated_at < $4)
+         OR (c.status = $2 AND c.attempts < $6 AND c.updated_at < $5)
+         OR (c.status = $3 AND c.updated_at < $5)
+      LIMIT $7
+    "#,
+  )
+  .bind(AFDatabaseAICellStatus::RateLimited as i16)
+  .bind(AFDatabaseAICellStatus::Failed as i16)
+  .bind(AFDatabaseAICellStatus::Pending as i16)
+  .bind(rate_limited_before)
+  .bind(failed_before)
+  .bind(max_attempts)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
@@ -0,0 +1,176 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use shared_entity::dto::ai_dto::{
+  AIPromptTemplate, CreateAIPromptTemplateParams, UpdateAIPromptTemplateParams,
+};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFAIPromptTemplateRow {
+  template_id: Uuid,
+  name: String,
+  description: String,
+  system_prompt: String,
+  user_prompt: Option<String>,
+  created_by: Option<i64>,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl From<AFAIPromptTemplateRow> for AIPromptTemplate {
+  fn from(row: AFAIPromptTemplateRow) -> Self {
+    Self {
+      template_id: row.template_id,
+      name: row.name,
+      description: row.description,
+      system_prompt: row.system_prompt,
+      user_prompt: row.user_prompt,
+      created_by: row.created_by,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    }
+  }
+}
+
+fn template_not_found(workspace_id: &Uuid, template_id: &Uuid) -> AppError {
+  AppError::RecordNotFound(format!(
+    "prompt template:{} is not found in workspace:{}",
+    template_id, workspace_id
+  ))
+}
+
+/// Maps the violation of the unique template name constraint to an [AppError::RecordAlreadyExists].
+fn map_unique_name_error(err: sqlx::Error, name: &str) -> AppError {
+  match &err {
+    sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+      AppError::RecordAlreadyExists(format!("prompt template with name:{} already exists", name))
+    },
+    _ => AppError::from(err),
+  }
+}
+
+pub async fn insert_ai_prompt_template<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  uid: i64,
+  params: CreateAIPromptTemplateParams,
+) -> Result<AIPromptTemplate, AppError> {
+  let row = sqlx::query_as::<_, AFAIPromptTemplateRow>(
+    r#"
+      INSERT INTO af_ai_prompt_template
+        (workspace_id, name, description, system_prompt, user_prompt, created_by)
+      VALUES ($1, $2, $3, $4, NULLIF($5, ''), $6)
+      RETURNING template_id, name, description, system_prompt, user_prompt, created_by,
+                created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(&params.name)
+  .bind(&params.description)
+  .bind(&params.system_prompt)
+  .bind(&params.user_prompt)
+  .bind(uid)
+  .fetch_one(executor)
+  .await
+  .map_err(|err| map_unique_name_error(err, &params.name))?;
+  Ok(row.into())
+}
+
+pub async fn select_ai_prompt_templates<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<AIPromptTemplate>, AppError> {
+  let rows = sqlx::query_as::<_, AFAIPromptTemplateRow>(
+    r#"
+      SELECT template_id, name, description, system_prompt, user_prompt, created_by,
+             created_at, updated_at
+      FROM af_ai_prompt_template
+      WHERE workspace_id = $1
+      ORDER BY name ASC
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+pub async fn select_ai_prompt_template<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+) -> Result<AIPromptTemplate, AppError> {
+  let row = sqlx::query_as::<_, AFAIPromptTemplateRow>(
+    r#"
+      SELECT template_id, name, description, system_prompt, user_prompt, created_by,
+             created_at, updated_at
+      FROM af_ai_prompt_template
+      WHERE workspace_id = $1 AND template_id = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(template_id)
+  .fetch_optional(executor)
+  .await?;
+  row
+    .map(Into::into)
+    .ok_or_else(|| template_not_found(workspace_id, template_id))
+}
+
+/// Updates the fields of a template which are set in the params. Setting the user prompt to an
+/// empty string removes it.
+pub async fn update_ai_prompt_template<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+  params: UpdateAIPromptTemplateParams,
+) -> Result<AIPromptTemplate, AppError> {
+  let name = params.name.clone().unwrap_or_default();
+  let row = sqlx::query_as::<_, AFAIPromptTemplateRow>(
+    r#"
+      UPDATE af_ai_prompt_template
+      SET name = COALESCE($3, name),
+          description = COALESCE($4, description),
+          system_prompt = COALESCE($5, system_prompt),
+          user_prompt = CASE WHEN $6::TEXT IS NULL THEN user_prompt ELSE NULLIF($6, '') END,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND template_id = $2
+      RETURNING template_id, name, description, system_prompt, user_prompt, created_by,
+                created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(template_id)
+  .bind(params.name)
+  .bind(params.description)
+  .bind(params.system_prompt)
+  .bind(params.user_prompt)
+  .fetch_optional(executor)
+  .await
+  .map_err(|err| map_unique_name_error(err, &name))?;
+  row
+    .map(Into::into)
+    .ok_or_else(|| template_not_found(workspace_id, template_id))
+}
+
+pub async fn delete_ai_prompt_template<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+) -> Result<(), AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_ai_prompt_template
+      WHERE workspace_id = $1 AND template_id = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(template_id)
+  .execute(executor)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(template_not_found(workspace_id, template_id));
+  }
+  Ok(())
+}
@@ -0,0 +1,183 @@
+use app_error::AppError;
+use shared_entity::dto::billing_dto::{SubscriptionPlan, WorkspaceAIUsageAndLimit};
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFWorkspaceAIUsageRow {
+  plan: i16,
+  daily_tokens: i64,
+  daily_token_limit: Option<i64>,
+  monthly_tokens: i64,
+  monthly_token_limit: Option<i64>,
+  daily_requests: i64,
+  daily_request_limit: Option<i64>,
+  monthly_requests: i64,
+  monthly_request_limit: Option<i64>,
+}
+
+impl From<AFWorkspaceAIUsageRow> for WorkspaceAIUsageAndLimit {
+  fn from(row: AFWorkspaceAIUsageRow) -> Self {
+    Self {
+      plan: SubscriptionPlan::try_from(row.plan).unwrap_or(SubscriptionPlan::Free),
+      daily_tokens: row.daily_tokens,
+      daily_token_limit: row.daily_token_limit,
+      monthly_tokens: row.monthly_tokens,
+      monthly_token_limit: row.monthly_token_limit,
+      daily_requests: row.daily_requests,
+      daily_request_limit: row.daily_request_limit,
+      monthly_requests: row.monthly_requests,
+      monthly_request_limit: row.monthly_request_limit,
+    }
+  }
+}
+
+/// Returns AI usage of a workspace in the current day and calendar month, together with its
+/// quotas. Workspace quota overrides take precedence over the quotas of its subscription plan.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_workspace_ai_usage_and_limit<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceAIUsageAndLimit, AppError>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let row = sqlx::query_as::<_, AFWorkspaceAIUsageRow>(
+    r#"
+    WITH usage AS (
+      SELECT
+        COALESCE(SUM(COALESCE(search_tokens_consumed, 0) + COALESCE(index_tokens_consumed, 0) + ai_tokens_consumed)
+          FILTER (WHERE created_at = now()::date), 0)::BIGINT AS daily_tokens,
+        COALESCE(SUM(COALESCE(search_tokens_consumed, 0) + COALESCE(index_tokens_consumed, 0) + ai_tokens_consumed), 0)::BIGINT AS monthly_tokens,
+        COALESCE(SUM(COALESCE(search_requests, 0) + ai_requests)
+          FILTER (WHERE created_at = now()::date), 0)::BIGINT AS daily_requests,
+        COALESCE(SUM(COALESCE(search_requests, 0) + ai_requests), 0)::BIGINT AS monthly_requests
+      FROM af_workspace_ai_usage
+      WHERE workspace_id = $1 AND created_at >= date_trunc('month', now())::date
+    )
+    SELECT
+      COALESCE(wq.plan, 0) AS plan,
+      usage.daily_tokens,
+      COALESCE(wq.daily_token_limit, pq.daily_token_limit) AS daily_token_limit,
+      usage.monthly_tokens,
+      COALESCE(wq.monthly_token_limit, pq.monthly_token_limit) AS monthly_token_limit,
+      usage.daily_requests,
+      COALESCE(wq.daily_request_limit, pq.daily_request_limit) AS daily_request_limit,
+      usage.monthly_requests,
+      COALESCE(wq.monthly_request_limit, pq.monthly_request_limit) AS monthly_request_limit
+    FROM usage
+    LEFT JOIN af_workspace_ai_quota wq ON wq.workspace_id = $1
+    LEFT JOIN af_ai_plan_quota pq ON pq.plan = COALESCE(wq.plan, 0)
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_one(executor)
+  .await?;
+  Ok(row.into())
+}
+
+/// Returns [AppError::AIQuotaExceeded] if the workspace has used up any of its AI quotas.
+pub async fn check_workspace_ai_quota<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<(), AppError>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let usage = select_workspace_ai_usage_and_limit(executor, workspace_id).await?;
+  match usage.exceeded_quota() {
+    Some(reason) => Err(AppError::AIQuotaExceeded(reason)),
+    None => Ok(()),
+  }
+}
+
+/// Counts a completion or chat request made by a workspace, unless the workspace has used up any of
+/// its AI quotas, in which case [AppError::AIQuotaExceeded] is returned.
+///
+/// The quotas are checked and the request is counted by a single statement. Concurrent requests of
+/// a workspace are serialized by the lock on its usage row of the day, which is why the usage of the
+/// day is read from that row, while the usage of the previous days of the month is summed up.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn consume_workspace_ai_request(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  let consumed: Option<i32> = sqlx::query_scalar(
+    r#"
+    WITH quota AS (
+      SELECT
+        COALESCE(wq.daily_token_limit, pq.daily_token_limit) AS daily_token_limit,
+        COALESCE(wq.monthly_token_limit, pq.monthly_token_limit) AS monthly_token_limit,
+        COALESCE(wq.daily_request_limit, pq.daily_request_limit) AS daily_request_limit,
+        COALESCE(wq.monthly_request_limit, pq.monthly_request_limit) AS monthly_request_limit
+      FROM (SELECT 1) AS one
+      LEFT JOIN af_workspace_ai_quota wq ON wq.workspace_id = $1
+      LEFT JOIN af_ai_plan_quota pq ON pq.plan = COALESCE(wq.plan, 0)
+    ),
+    earlier AS (
+      SELECT
+        COALESCE(SUM(COALESCE(search_tokens_consumed, 0) + COALESCE(index_tokens_consumed, 0) + ai_tokens_consumed), 0)::BIGINT AS tokens,
+        COALESCE(SUM(COALESCE(search_requests, 0) + ai_requests), 0)::BIGINT AS requests
+      FROM af_workspace_ai_usage
+      WHERE workspace_id = $1
+        AND created_at >= date_trunc('month', now())::date
+        AND created_at < now()::date
+    )
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed, ai_requests)
+    SELECT now()::date, $1, 0, 0, 0, 1
+    FROM quota, earlier
+    WHERE (quota.daily_token_limit IS NULL OR quota.daily_token_limit > 0)
+      AND (quota.daily_request_limit IS NULL OR quota.daily_request_limit > 0)
+      AND (quota.monthly_token_limit IS NULL OR earlier.tokens < quota.monthly_token_limit)
+      AND (quota.monthly_request_limit IS NULL OR earlier.requests < quota.monthly_request_limit)
+    ON CONFLICT (created_at, workspace_id) DO UPDATE
+    SET ai_requests = af_workspace_ai_usage.ai_requests + 1
+    WHERE (
+      SELECT
+        (quota.daily_token_limit IS NULL OR (COALESCE(af_workspace_ai_usage.search_tokens_consumed, 0) + COALESCE(af_workspace_ai_usage.index_tokens_consumed, 0) + af_workspace_ai_usage.ai_tokens_consumed) < quota.daily_token_limit)
+        AND (quota.daily_request_limit IS NULL OR (COALESCE(af_workspace_ai_usage.search_requests, 0) + af_workspace_ai_usage.ai_requests) < quota.daily_request_limit)
+        AND (quota.monthly_token_limit IS NULL OR earlier.tokens + (COALESCE(af_workspace_ai_usage.search_tokens_consumed, 0) + COALESCE(af_workspace_ai_usage.index_tokens_consumed, 0) + af_workspace_ai_usage.ai_tokens_consumed) < quota.monthly_token_limit)
+        AND (quota.monthly_request_limit IS NULL OR earlier.requests + (COALESCE(af_workspace_ai_usage.search_requests, 0) + af_workspace_ai_usage.ai_requests) < quota.monthly_request_limit)
+      FROM quota, earlier
+    )
+    RETURNING ai_requests
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(pg_pool)
+  .await?;
+  if consumed.is_some() {
+    return Ok(());
+  }
+  let usage = select_workspace_ai_usage_and_limit(pg_pool, workspace_id).await?;
+  let reason = usage
+    .exceeded_quota()
+    .unwrap_or_else(|| "AI quota of the workspace exceeded".to_string());
+  Err(AppError::AIQuotaExceeded(reason))
+}
+
+/// Adds the tokens consumed by a completion or chat request of a workspace to its usage.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn record_workspace_ai_tokens<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+  tokens: i64,
+) -> Result<(), AppError>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  sqlx::query(
+    r#"
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed, ai_tokens_consumed)
+    VALUES (now()::date, $1, 0, 0, 0, $2)
+    ON CONFLICT (created_at, workspace_id) DO UPDATE
+    SET ai_tokens_consumed = af_workspace_ai_usage.ai_tokens_consumed + $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(tokens)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
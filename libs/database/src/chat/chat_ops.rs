@@ -1,17 +1,19 @@
-use crate::pg_row::AFChatRow;
+use crate::pg_row::{AFChatNotification, AFChatRow, CHAT_NOTIFICATION_CHANNEL};
 use anyhow::anyhow;
 use app_error::AppError;
 use chrono::{DateTime, Utc};
+use collab_rt_entity::chat::ChatEvent;
 use shared_entity::dto::chat_dto::{
-  ChatAuthor, ChatMessage, ChatMessageMetadata, ChatSettings, CreateChatParams,
-  GetChatMessageParams, MessageCursor, RepeatedChatMessage, UpdateChatMessageContentParams,
-  UpdateChatMessageMetaParams, UpdateChatParams,
+  ChatAuthor, ChatAuthorType, ChatMessage, ChatMessageBranches, ChatMessageMetadata,
+  ChatParticipant, ChatParticipantParams, ChatParticipantRole, ChatSettings, CreateChatParams,
+  GetChatMessageParams, MessageCursor, RepeatedChatMessage, UpdateChatMessageMetaParams,
+  UpdateChatParams,
 };
 
 use serde_json::json;
 use sqlx::postgres::PgArguments;
 
-use sqlx::{Arguments, Executor, PgPool, Postgres, Transaction};
+use sqlx::{Arguments, Executor, FromRow, PgPool, Postgres, Transaction};
 use std::ops::DerefMut;
 use std::str::FromStr;
 use tracing::warn;
@@ -21,21 +23,23 @@ use uuid::Uuid;
 pub async fn insert_chat<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
   workspace_id: &str,
+  uid: i64,
   params: CreateChatParams,
 ) -> Result<(), AppError> {
   let chat_id = Uuid::from_str(&params.chat_id)?;
   let workspace_id = Uuid::from_str(workspace_id)?;
   let rag_ids = json!(params.rag_ids);
-  sqlx::query!(
+  sqlx::query(
     r#"
-       INSERT INTO af_chat (chat_id, name, workspace_id, rag_ids)
-       VALUES ($1, $2, $3, $4)
+       INSERT INTO af_chat (chat_id, name, workspace_id, rag_ids, created_by)
+       VALUES ($1, $2, $3, $4, $5)
     "#,
-    chat_id,
-    params.name,
-    workspace_id,
-    rag_ids,
   )
+  .bind(chat_id)
+  .bind(params.name)
+  .bind(workspace_id)
+  .bind(rag_ids)
+  .bind(uid)
   .execute(executor)
   .await
   .map_err(|err| AppError::Internal(anyhow!("Failed to insert chat: {}", err)))?;
@@ -154,15 +158,15 @@ pub async fn select_chat<'a, E: Executor<'a, Database = Postgres>>(
   chat_id: &str,
 ) -> Result<AFChatRow, AppError> {
   let chat_id = Uuid::from_str(chat_id)?;
-  let row = sqlx::query_as!(
-    AFChatRow,
+  let row = sqlx::query_as::<_, AFChatRow>(
     r#"
-        SELECT *
+        SELECT chat_id, name, created_at, deleted_at, rag_ids, workspace_id, meta_data,
+               active_message_id
         FROM af_chat
         WHERE chat_id = $1 AND deleted_at IS NULL
     "#,
-    &chat_id,
   )
+  .bind(chat_id)
   .fetch_optional(executor)
   .await?;
   match row {
@@ -193,6 +197,8 @@ pub async fn select_chat_rag_ids<'a, E: Executor<'a, Database = Postgres>>(
   Ok(rag_ids)
 }
 
+/// Inserts an answer to a question. Answers are never overwritten: regenerating the answer to a
+/// question adds a new branch to the conversation, which becomes the active one.
 pub async fn insert_answer_message_with_transaction(
   transaction: &mut Transaction<'_, Postgres>,
   author: ChatAuthor,
@@ -202,101 +208,39 @@ pub async fn insert_answer_message_with_transaction(
   question_message_id: i64,
 ) -> Result<ChatMessage, AppError> {
   let chat_id = Uuid::from_str(chat_id)?;
-  let existing_reply_message_id: Option<i64> = sqlx::query_scalar!(
-    r#"
-      SELECT reply_message_id
-      FROM af_chat_messages
-      WHERE message_id = $1
-    "#,
-    question_message_id
+  let (message_id, parent_message_id, created_at) = insert_chat_message(
+    transaction.deref_mut(),
+    &chat_id,
+    &author,
+    &content,
+    &metadata,
+    ChatMessageParent::Message(Some(question_message_id)),
   )
-  .fetch_one(transaction.deref_mut())
   .await?;
 
-  if let Some(reply_id) = existing_reply_message_id {
-    // If there is an existing reply_message_id, update the existing message
-    sqlx::query!(
-      r#"
-         UPDATE af_chat_messages
-         SET content = $2,
-             author = $3,
-             created_at = CURRENT_TIMESTAMP,
-             meta_data = $4
-         WHERE message_id = $1
-      "#,
-      reply_id,
-      &content,
-      json!(author),
-      metadata,
-    )
-    .execute(transaction.deref_mut())
-    .await
-    .map_err(|err| AppError::Internal(anyhow!("Failed to update chat message: {}", err)))?;
-
-    let row = sqlx::query!(
-      r#"
-        SELECT message_id, content, created_at, author, meta_data, reply_message_id
-        FROM af_chat_messages
-        WHERE message_id = $1
-      "#,
-      reply_id
-    )
-    .fetch_one(transaction.deref_mut())
-    .await
-    .map_err(|err| AppError::Internal(anyhow!("Failed to fetch updated message: {}", err)))?;
-
-    let chat_message = ChatMessage {
-      author,
-      message_id: row.message_id,
-      content: row.content,
-      created_at: row.created_at,
-      meta_data: row.meta_data,
-      reply_message_id: Some(question_message_id),
-    };
-
-    Ok(chat_message)
-  } else {
-    // Insert a new chat message
-    let row = sqlx::query!(
-      r#"
-        INSERT INTO af_chat_messages (chat_id, author, content, meta_data)
-        VALUES ($1, $2, $3, $4)
-        RETURNING message_id, created_at
-      "#,
-      chat_id,
-      json!(author),
-      &content,
-      &metadata,
-    )
-    .fetch_one(transaction.deref_mut())
-    .await
-    .map_err(|err| AppError::Internal(anyhow!("Failed to insert chat message: {}", err)))?;
-
-    // Update the question message with the new reply_message_id
-    sqlx::query!(
-      r#"
+  // Update the question message with the new reply_message_id
+  sqlx::query!(
+    r#"
         UPDATE af_chat_messages
         SET reply_message_id = $2
         WHERE message_id = $1
       "#,
-      question_message_id,
-      row.message_id,
-    )
-    .execute(transaction.deref_mut())
-    .await
-    .map_err(|err| AppError::Internal(anyhow!("Failed to update reply_message_id: {}", err)))?;
-
-    let chat_message = ChatMessage {
-      author,
-      message_id: row.message_id,
-      content,
-      created_at: row.created_at,
-      meta_data: metadata,
-      reply_message_id: None,
-    };
+    question_message_id,
+    message_id,
+  )
+  .execute(transaction.deref_mut())
+  .await
+  .map_err(|err| AppError::Internal(anyhow!("Failed to update reply_message_id: {}", err)))?;
 
-    Ok(chat_message)
-  }
+  Ok(ChatMessage {
+    author,
+    message_id,
+    content,
+    created_at,
+    meta_data: metadata,
+    reply_message_id: None,
+    parent_message_id,
+  })
 }
 
 pub async fn insert_answer_message(
@@ -326,6 +270,7 @@ pub async fn insert_answer_message(
   Ok(chat_message)
 }
 
+/// Inserts a question after the last message of the active branch of the chat.
 pub async fn insert_question_message<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
   author: ChatAuthor,
@@ -333,44 +278,112 @@ pub async fn insert_question_message<'a, E: Executor<'a, Database = Postgres>>(
   content: String,
   metadata: Vec<ChatMessageMetadata>,
 ) -> Result<ChatMessage, AppError> {
-  let metadata = json!(metadata);
-  let chat_id = Uuid::from_str(chat_id)?;
-  let row = sqlx::query!(
-    r#"
-        INSERT INTO af_chat_messages (chat_id, author, content, meta_data)
-        VALUES ($1, $2, $3, $4)
-        RETURNING message_id, created_at
-        "#,
+  insert_question_message_in_branch(
+    executor,
+    author,
     chat_id,
-    json!(author),
-    &content,
-    &metadata,
+    content,
+    json!(metadata),
+    ChatMessageParent::ActiveBranch,
   )
-  .fetch_one(executor)
   .await
-  .map_err(|err| AppError::Internal(anyhow!("Failed to insert chat message: {}", err)))?;
+}
+
+pub async fn insert_question_message_in_branch<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  author: ChatAuthor,
+  chat_id: &str,
+  content: String,
+  metadata: serde_json::Value,
+  parent: ChatMessageParent,
+) -> Result<ChatMessage, AppError> {
+  let chat_id = Uuid::from_str(chat_id)?;
+  let (message_id, parent_message_id, created_at) =
+    insert_chat_message(executor, &chat_id, &author, &content, &metadata, parent).await?;
 
   let chat_message = ChatMessage {
     author,
-    message_id: row.message_id,
+    message_id,
     content,
-    created_at: row.created_at,
+    created_at,
     meta_data: metadata,
     reply_message_id: None,
+    parent_message_id,
   };
   Ok(chat_message)
 }
 
+/// Position of a new message in the tree of messages of a chat.
+#[derive(Debug, Clone, Copy)]
+pub enum ChatMessageParent {
+  /// After the last message of the active branch.
+  ActiveBranch,
+  /// After the given message, or as the first message of the conversation if `None`.
+  Message(Option<i64>),
+}
+
+/// Inserts a message and makes it the last message of the active branch of the chat. Returns the
+/// id, the parent id and the creation time of the message.
+async fn insert_chat_message<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+  author: &ChatAuthor,
+  content: &str,
+  metadata: &serde_json::Value,
+  parent: ChatMessageParent,
+) -> Result<(i64, Option<i64>, DateTime<Utc>), AppError> {
+  let (follow_active_branch, parent_message_id) = match parent {
+    ChatMessageParent::ActiveBranch => (true, None),
+    ChatMessageParent::Message(parent_message_id) => (false, parent_message_id),
+  };
+  let row = sqlx::query_as::<_, (i64, Option<i64>, DateTime<Utc>)>(
+    r#"
+      WITH new_message AS (
+        INSERT INTO af_chat_messages (chat_id, author, content, meta_data, parent_message_id)
+        VALUES (
+          $1, $2, $3, $4,
+          CASE
+            WHEN $5 THEN (SELECT active_message_id FROM af_chat WHERE chat_id = $1)
+            ELSE $6::BIGINT
+          END
+        )
+        RETURNING message_id, parent_message_id, created_at
+      ),
+      active_branch AS (
+        UPDATE af_chat
+        SET active_message_id = new_message.message_id
+        FROM new_message
+        WHERE af_chat.chat_id = $1
+      )
+      SELECT message_id, parent_message_id, created_at FROM new_message
+    "#,
+  )
+  .bind(chat_id)
+  .bind(json!(author))
+  .bind(content)
+  .bind(metadata)
+  .bind(follow_active_branch)
+  .bind(parent_message_id)
+  .fetch_one(executor)
+  .await
+  .map_err(|err| AppError::Internal(anyhow!("Failed to insert chat message: {}", err)))?;
+  Ok(row)
+}
+
+/// Returns a page of the messages of the active branch of the chat. Messages of other branches
+/// are not returned and are not counted in the total.
 pub async fn select_chat_messages(
   txn: &mut Transaction<'_, Postgres>,
   chat_id: &str,
   params: GetChatMessageParams,
 ) -> Result<RepeatedChatMessage, AppError> {
   let chat_id = Uuid::from_str(chat_id)?;
+  let active_branch = select_chat_active_branch_ids(txn.deref_mut(), &chat_id).await?;
   let mut query = r#"
-        SELECT message_id, content, created_at, author, meta_data, reply_message_id
+        SELECT message_id, content, created_at, author, meta_data, reply_message_id,
+               parent_message_id
         FROM af_chat_messages
-        WHERE chat_id = $1
+        WHERE chat_id = $1 AND message_id = ANY($2)
     "#
   .to_string();
 
@@ -381,6 +394,12 @@ pub async fn select_chat_messages(
       desc: format!("unable to encode chat id {}", chat_id),
       err,
     })?;
+  args
+    .add(&active_branch)
+    .map_err(|err| AppError::SqlxArgEncodingError {
+      desc: format!("unable to encode message ids of chat {}", chat_id),
+      err,
+    })?;
 
   // Message IDs:   1    2    3    4    5
   // AfterMessageId(3, 5):   [4]  [5]  has_more = false
@@ -388,14 +407,14 @@ pub async fn select_chat_messages(
   // Offset(3, 5):           [4]  [5]  has_more = true
   match params.cursor {
     MessageCursor::AfterMessageId(after_message_id) => {
-      query += " AND message_id > $2";
+      query += " AND message_id > $3";
       args
         .add(after_message_id)
         .map_err(|err| AppError::SqlxArgEncodingError {
           desc: format!("unable to encode message id {}", after_message_id),
           err,
         })?;
-      query += " ORDER BY message_id DESC LIMIT $3";
+      query += " ORDER BY message_id DESC LIMIT $4";
       args
         .add(params.limit as i64)
         .map_err(|err| AppError::SqlxArgEncodingError {
@@ -404,7 +423,7 @@ pub async fn select_chat_messages(
         })?;
     },
     MessageCursor::Offset(offset) => {
-      query += " ORDER BY message_id ASC LIMIT $2 OFFSET $3";
+      query += " ORDER BY message_id ASC LIMIT $3 OFFSET $4";
       args
         .add(params.limit as i64)
         .map_err(|err| AppError::SqlxArgEncodingError {
@@ -419,14 +438,14 @@ pub async fn select_chat_messages(
         })?;
     },
     MessageCursor::BeforeMessageId(before_message_id) => {
-      query += " AND message_id < $2";
+      query += " AND message_id < $3";
       args
         .add(before_message_id)
         .map_err(|err| AppError::SqlxArgEncodingError {
           desc: format!("unable to encode message id {}", before_message_id),
           err,
         })?;
-      query += " ORDER BY message_id DESC LIMIT $3";
+      query += " ORDER BY message_id DESC LIMIT $4";
       args
         .add(params.limit as i64)
         .map_err(|err| AppError::SqlxArgEncodingError {
@@ -435,7 +454,7 @@ pub async fn select_chat_messages(
         })?;
     },
     MessageCursor::NextBack => {
-      query += " ORDER BY message_id DESC LIMIT $2";
+      query += " ORDER BY message_id DESC LIMIT $3";
       args
         .add(params.limit as i64)
         .map_err(|err| AppError::SqlxArgEncodingError {
@@ -445,84 +464,26 @@ pub async fn select_chat_messages(
     },
   }
 
-  #[allow(clippy::type_complexity)]
-  let rows: Vec<(
-    i64,
-    String,
-    DateTime<Utc>,
-    serde_json::Value,
-    serde_json::Value,
-    Option<i64>,
-  )> = sqlx::query_as_with(&query, args)
+  let messages = sqlx::query_as_with::<_, AFChatMessageWithAuthorRow, _>(&query, args)
     .fetch_all(txn.deref_mut())
-    .await?;
-
-  let messages = rows
+    .await?
     .into_iter()
-    .flat_map(
-      |(message_id, content, created_at, author, meta_data, reply_message_id)| {
-        match serde_json::from_value::<ChatAuthor>(author) {
-          Ok(author) => Some(ChatMessage {
-            author,
-            message_id,
-            content,
-            created_at,
-            meta_data,
-            reply_message_id,
-          }),
-          Err(err) => {
-            warn!("Failed to deserialize author: {}", err);
-            None
-          },
-        }
-      },
-    )
+    .flat_map(to_chat_message)
     .collect::<Vec<ChatMessage>>();
 
-  let total = sqlx::query_scalar!(
-    r#"
-        SELECT COUNT(*)
-        FROM public.af_chat_messages
-        WHERE chat_id = $1
-        "#,
-    &chat_id
-  )
-  .fetch_one(txn.deref_mut())
-  .await?
-  .unwrap_or(0);
-
+  // The ids of the active branch are in ascending order, as a message is always created after
+  // the message it follows.
+  let total = active_branch.len() as i64;
   let has_more = match params.cursor {
-    MessageCursor::AfterMessageId(_) => {
-      if messages.is_empty() {
-        false
-      } else {
-        sqlx::query!(
-          "SELECT EXISTS(SELECT 1 FROM af_chat_messages WHERE chat_id = $1 AND message_id > $2)",
-          &chat_id,
-          messages[0].message_id
-        )
-        .fetch_one(txn.deref_mut())
-        .await?
-        .exists
-        .unwrap_or(false)
-      }
-    },
+    MessageCursor::AfterMessageId(_) => messages
+      .first()
+      .map(|first| active_branch.iter().any(|id| *id > first.message_id))
+      .unwrap_or(false),
     MessageCursor::Offset(offset) => (offset + params.limit) < total as u64,
-    MessageCursor::BeforeMessageId(_) => {
-      if messages.is_empty() {
-        false
-      } else {
-        sqlx::query!(
-          "SELECT EXISTS(SELECT 1 FROM af_chat_messages WHERE chat_id = $1 AND message_id < $2)",
-          &chat_id,
-          messages.last().as_ref().unwrap().message_id
-        )
-        .fetch_one(txn.deref_mut())
-        .await?
-        .exists
-        .unwrap_or(false)
-      }
-    },
+    MessageCursor::BeforeMessageId(_) => messages
+      .last()
+      .map(|last| active_branch.iter().any(|id| *id < last.message_id))
+      .unwrap_or(false),
     MessageCursor::NextBack => params.limit < total as u64,
   };
 
@@ -533,97 +494,202 @@ pub async fn select_chat_messages(
   })
 }
 
+/// Selects the messages going from the first message of the chat to the last message of its
+/// active branch.
+const ACTIVE_BRANCH_CTE: &str = r#"
+  WITH RECURSIVE active_branch AS (
+    SELECT m.message_id, m.parent_message_id
+    FROM af_chat c
+    JOIN af_chat_messages m ON m.message_id = c.active_message_id
+    WHERE c.chat_id = $1
+    UNION ALL
+    SELECT m.message_id, m.parent_message_id
+    FROM af_chat_messages m
+    JOIN active_branch b ON m.message_id = b.parent_message_id
+  )
+"#;
+
+/// Returns the ids of the messages of the active branch of the chat, oldest first.
+pub async fn select_chat_active_branch_ids<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+) -> Result<Vec<i64>, AppError> {
+  let query = format!(
+    "{} SELECT message_id FROM active_branch ORDER BY message_id ASC",
+    ACTIVE_BRANCH_CTE
+  );
+  let ids = sqlx::query_scalar::<_, i64>(&query)
+    .bind(chat_id)
+    .fetch_all(executor)
+    .await?;
+  Ok(ids)
+}
+
+/// Returns all the messages of the active branch of the chat, oldest first.
 pub async fn get_all_chat_messages<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
   chat_id: &str,
 ) -> Result<Vec<ChatMessage>, AppError> {
   let chat_id = Uuid::from_str(chat_id)?;
-  let rows = sqlx::query!(
-    // ChatMessage,
+  let query = format!(
     r#"
-     SELECT message_id, content, created_at, author, meta_data, reply_message_id
-          FROM af_chat_messages
-          WHERE chat_id = $1
-          ORDER BY created_at ASC
-   "#,
-    chat_id,
-  )
-  .fetch_all(executor)
-  .await?;
-
-  let messages = rows
+      {}
+      SELECT m.message_id, m.content, m.created_at, m.author, m.meta_data, m.reply_message_id,
+             m.parent_message_id
+      FROM af_chat_messages m
+      JOIN active_branch b ON m.message_id = b.message_id
+      ORDER BY m.message_id ASC
+    "#,
+    ACTIVE_BRANCH_CTE
+  );
+  let messages = sqlx::query_as::<_, AFChatMessageWithAuthorRow>(&query)
+    .bind(chat_id)
+    .fetch_all(executor)
+    .await?
     .into_iter()
-    .flat_map(
-      |row| match serde_json::from_value::<ChatAuthor>(row.author) {
-        Ok(author) => Some(ChatMessage {
-          author,
-          message_id: row.message_id,
-          content: row.content,
-          created_at: row.created_at,
-          meta_data: row.meta_data,
-          reply_message_id: row.reply_message_id,
-        }),
-        Err(err) => {
-          warn!("Failed to deserialize author: {}", err);
-          None
-        },
-      },
-    )
+    .flat_map(to_chat_message)
     .collect::<Vec<ChatMessage>>();
 
   Ok(messages)
 }
 
-pub async fn delete_answer_message_by_question_message_id(
-  transaction: &mut Transaction<'_, Postgres>,
+pub async fn select_chat_message<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &str,
   message_id: i64,
-) -> Result<(), AppError> {
-  // Step 1: Get the reply_message_id of the chat message with the given message_id
-  let reply_message_id: Option<i64> = sqlx::query_scalar!(
+) -> Result<ChatMessage, AppError> {
+  let chat_id = Uuid::from_str(chat_id)?;
+  let row = sqlx::query_as::<_, AFChatMessageWithAuthorRow>(
     r#"
-      SELECT reply_message_id
+      SELECT message_id, content, created_at, author, meta_data, reply_message_id,
+             parent_message_id
       FROM af_chat_messages
-      WHERE message_id = $1
+      WHERE chat_id = $1 AND message_id = $2
     "#,
-    message_id
   )
-  .fetch_one(transaction.deref_mut())
+  .bind(chat_id)
+  .bind(message_id)
+  .fetch_optional(executor)
   .await?;
+  row.and_then(to_chat_message).ok_or_else(|| {
+    AppError::RecordNotFound(format!(
+      "message:{} is not found in chat:{}",
+      message_id, chat_id
+    ))
+  })
+}
 
-  if let Some(reply_id) = reply_message_id {
-    // Step 2: Delete the chat message with the reply_message_id
-    sqlx::query!(
-      r#"
-        DELETE FROM af_chat_messages
-        WHERE message_id = $1
-      "#,
-      reply_id
-    )
-    .execute(transaction.deref_mut())
-    .await?;
-  }
-
-  Ok(())
+/// Returns the message with the given id together with its siblings of the same kind: the other
+/// answers to the same question, or the other versions of the same question.
+pub async fn select_chat_message_branches(
+  txn: &mut Transaction<'_, Postgres>,
+  chat_id: &str,
+  message_id: i64,
+) -> Result<ChatMessageBranches, AppError> {
+  let message = select_chat_message(txn.deref_mut(), chat_id, message_id).await?;
+  let chat_id = Uuid::from_str(chat_id)?;
+  let is_answer = matches!(message.author.author_type, ChatAuthorType::AI);
+  let messages = sqlx::query_as::<_, AFChatMessageWithAuthorRow>(
+    r#"
+      SELECT message_id, content, created_at, author, meta_data, reply_message_id,
+             parent_message_id
+      FROM af_chat_messages
+      WHERE chat_id = $1 AND parent_message_id IS NOT DISTINCT FROM $2
+      ORDER BY message_id ASC
+    "#,
+  )
+  .bind(chat_id)
+  .bind(message.parent_message_id)
+  .fetch_all(txn.deref_mut())
+  .await?
+  .into_iter()
+  .flat_map(to_chat_message)
+  .filter(|sibling| matches!(sibling.author.author_type, ChatAuthorType::AI) == is_answer)
+  .collect::<Vec<ChatMessage>>();
+
+  let active_branch = select_chat_active_branch_ids(txn.deref_mut(), &chat_id).await?;
+  let active_message_id = messages
+    .iter()
+    .map(|sibling| sibling.message_id)
+    .find(|id| active_branch.contains(id));
+  Ok(ChatMessageBranches {
+    messages,
+    active_message_id,
+  })
 }
 
-pub async fn update_chat_message_content(
-  transaction: &mut Transaction<'_, Postgres>,
-  params: &UpdateChatMessageContentParams,
-) -> Result<(), AppError> {
-  sqlx::query(
+/// Makes the branch containing the given message the active branch of the chat. The branch
+/// continues from the message with its most recent descendants. Returns the id of the last message
+/// of the branch.
+pub async fn update_chat_active_branch<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &str,
+  message_id: i64,
+) -> Result<i64, AppError> {
+  let chat_id = Uuid::from_str(chat_id)?;
+  let active_message_id = sqlx::query_scalar::<_, Option<i64>>(
     r#"
-            UPDATE af_chat_messages
-            SET content = $2,
-                edited_at = CURRENT_TIMESTAMP
-            WHERE message_id = $1
-            "#,
+      WITH RECURSIVE branch AS (
+        SELECT message_id
+        FROM af_chat_messages
+        WHERE chat_id = $1 AND message_id = $2
+        UNION ALL
+        SELECT child.message_id
+        FROM branch
+        CROSS JOIN LATERAL (
+          SELECT c.message_id
+          FROM af_chat_messages c
+          WHERE c.parent_message_id = branch.message_id
+          ORDER BY c.message_id DESC
+          LIMIT 1
+        ) child
+      )
+      UPDATE af_chat
+      SET active_message_id = (SELECT MAX(message_id) FROM branch)
+      WHERE chat_id = $1 AND EXISTS (SELECT 1 FROM branch)
+      RETURNING active_message_id
+    "#,
   )
-  .bind(params.message_id)
-  .bind(&params.content)
-  .execute(transaction.deref_mut())
-  .await?;
+  .bind(chat_id)
+  .bind(message_id)
+  .fetch_optional(executor)
+  .await?
+  .flatten();
+  active_message_id.ok_or_else(|| {
+    AppError::RecordNotFound(format!(
+      "message:{} is not found in chat:{}",
+      message_id, chat_id
+    ))
+  })
+}
 
-  Ok(())
+#[derive(FromRow)]
+struct AFChatMessageWithAuthorRow {
+  message_id: i64,
+  content: String,
+  created_at: DateTime<Utc>,
+  author: serde_json::Value,
+  meta_data: serde_json::Value,
+  reply_message_id: Option<i64>,
+  parent_message_id: Option<i64>,
+}
+
+fn to_chat_message(row: AFChatMessageWithAuthorRow) -> Option<ChatMessage> {
+  match serde_json::from_value::<ChatAuthor>(row.author) {
+    Ok(author) => Some(ChatMessage {
+      author,
+      message_id: row.message_id,
+      content: row.content,
+      created_at: row.created_at,
+      meta_data: row.meta_data,
+      reply_message_id: row.reply_message_id,
+      parent_message_id: row.parent_message_id,
+    }),
+    Err(err) => {
+      warn!("Failed to deserialize author: {}", err);
+      None
+    },
+  }
 }
 
 pub async fn update_chat_message_meta(
@@ -669,3 +735,171 @@ pub async fn select_chat_message_content<'a, E: Executor<'a, Database = Postgres
   .await?;
   Ok((row.content, row.meta_data))
 }
+
+/// Returns the role of a user in a chat, or `None` if the user can't access the chat.
+///
+/// Chats without participants haven't been shared yet and are only accessible to their creator,
+/// as their owner. Participants of a shared chat must still be members of its workspace.
+pub async fn select_chat_participant_role<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+  uid: i64,
+) -> Result<Option<ChatParticipantRole>, AppError> {
+  let (role, shared, creator, workspace_member): (Option<i16>, bool, bool, bool) = sqlx::query_as(
+    r#"
+      SELECT
+        (SELECT role FROM af_chat_participant WHERE chat_id = $1 AND uid = $2),
+        EXISTS(SELECT 1 FROM af_chat_participant WHERE chat_id = $1),
+        EXISTS(SELECT 1 FROM af_chat WHERE chat_id = $1 AND created_by = $2),
+        EXISTS(
+          SELECT 1
+          FROM af_chat c
+          JOIN af_workspace_member m ON m.workspace_id = c.workspace_id
+          WHERE c.chat_id = $1 AND c.deleted_at IS NULL AND m.uid = $2
+        )
+    "#,
+  )
+  .bind(chat_id)
+  .bind(uid)
+  .fetch_one(executor)
+  .await?;
+
+  if !workspace_member {
+    return Ok(None);
+  }
+  if !shared {
+    return Ok(creator.then_some(ChatParticipantRole::Owner));
+  }
+  Ok(role.map(ChatParticipantRole::from))
+}
+
+pub async fn select_chat_participants<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+) -> Result<Vec<ChatParticipant>, AppError> {
+  let rows: Vec<(i64, String, String, i16, DateTime<Utc>)> = sqlx::query_as(
+    r#"
+      SELECT p.uid, u.name, u.email, p.role, p.created_at
+      FROM af_chat_participant p
+      JOIN af_user u ON u.uid = p.uid
+      WHERE p.chat_id = $1
+      ORDER BY p.created_at
+    "#,
+  )
+  .bind(chat_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|(uid, name, email, role, created_at)| ChatParticipant {
+        uid,
+        name,
+        email,
+        role: ChatParticipantRole::from(role),
+        created_at,
+      })
+      .collect(),
+  )
+}
+
+pub async fn select_chat_participant_uids<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+) -> Result<Vec<i64>, AppError> {
+  let uids = sqlx::query_scalar("SELECT uid FROM af_chat_participant WHERE chat_id = $1")
+    .bind(chat_id)
+    .fetch_all(executor)
+    .await?;
+  Ok(uids)
+}
+
+/// Adds participants to a chat or updates roles of the existing ones. Fails if any of the users
+/// is not a member of the workspace of the chat.
+pub async fn upsert_chat_participants(
+  txn: &mut Transaction<'_, Postgres>,
+  chat_id: &Uuid,
+  participants: &[ChatParticipantParams],
+) -> Result<(), AppError> {
+  let uids: Vec<i64> = participants.iter().map(|p| p.uid).collect();
+  let roles: Vec<i16> = participants.iter().map(|p| p.role as i16).collect();
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_chat_participant (chat_id, uid, role)
+      SELECT $1, p.uid, p.role
+      FROM UNNEST($2::BIGINT[], $3::SMALLINT[]) AS p(uid, role)
+      JOIN af_chat c ON c.chat_id = $1
+      JOIN af_workspace_member m ON m.workspace_id = c.workspace_id AND m.uid = p.uid
+      ON CONFLICT (chat_id, uid) DO UPDATE SET role = EXCLUDED.role
+    "#,
+  )
+  .bind(chat_id)
+  .bind(&uids)
+  .bind(&roles)
+  .execute(txn.deref_mut())
+  .await?;
+
+  if result.rows_affected() != participants.len() as u64 {
+    return Err(AppError::InvalidRequest(
+      "chat participants must be members of the workspace".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Locks the chat until the end of the transaction, so that concurrent changes of its participants
+/// are applied one after another.
+pub async fn lock_chat(
+  txn: &mut Transaction<'_, Postgres>,
+  chat_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query("SELECT 1 FROM af_chat WHERE chat_id = $1 FOR UPDATE")
+    .bind(chat_id)
+    .fetch_optional(txn.deref_mut())
+    .await?;
+  Ok(())
+}
+
+/// Returns whether any of the participants of the chat is its owner.
+pub async fn select_chat_has_owner<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+) -> Result<bool, AppError> {
+  let has_owner = sqlx::query_scalar(
+    "SELECT EXISTS(SELECT 1 FROM af_chat_participant WHERE chat_id = $1 AND role = $2)",
+  )
+  .bind(chat_id)
+  .bind(ChatParticipantRole::Owner as i16)
+  .fetch_one(executor)
+  .await?;
+  Ok(has_owner)
+}
+
+pub async fn delete_chat_participants<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+  uids: &[i64],
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_chat_participant WHERE chat_id = $1 AND uid = ANY($2)")
+    .bind(chat_id)
+    .bind(uids)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Publishes an event of a chat to the given users over [CHAT_NOTIFICATION_CHANNEL]. Postgres limits
+/// the size of the notification payload to 8000 bytes, so events carrying large content may fail.
+pub async fn notify_chat_event<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  recipients: Vec<i64>,
+  event: ChatEvent,
+) -> Result<(), AppError> {
+  let payload = serde_json::to_string(&AFChatNotification { recipients, event })?;
+  sqlx::query("SELECT pg_notify($1, $2)")
+    .bind(CHAT_NOTIFICATION_CHANNEL)
+    .bind(payload)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
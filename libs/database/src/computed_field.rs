@@ -0,0 +1,164 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{AFDatabaseComputation, AFDatabaseComputedField};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFDatabaseComputedFieldRow {
+  field_id: String,
+  computation: Value,
+  error: Option<String>,
+  created_by: i64,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<AFDatabaseComputedFieldRow> for AFDatabaseComputedField {
+  type Error = AppError;
+
+  fn try_from(row: AFDatabaseComputedFieldRow) -> Result<Self, Self::Error> {
+    Ok(Self {
+      field_id: row.field_id,
+      computation: serde_json::from_value(row.computation)?,
+      error: row.error,
+      created_by: row.created_by,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    })
+  }
+}
+
+fn computed_field_not_found(database_id: &str, field_id: &str) -> AppError {
+  AppError::RecordNotFound(format!(
+    "computed field:{} is not found in database:{}",
+    field_id, database_id
+  ))
+}
+
+pub async fn insert_database_computed_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+  computation: &AFDatabaseComputation,
+  uid: i64,
+) -> Result<AFDatabaseComputedField, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseComputedFieldRow>(
+    r#"
+      INSERT INTO af_database_computed_field
+        (database_id, field_id, workspace_id, computation, created_by)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING field_id, computation, error, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(database_id)
+  .bind(field_id)
+  .bind(workspace_id)
+  .bind(serde_json::to_value(computation)?)
+  .bind(uid)
+  .fetch_one(executor)
+  .await
+  .map_err(|err| match &err {
+    sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+      AppError::RecordAlreadyExists(format!(
+        "field:{} of database:{} is already a computed field",
+        field_id, database_id
+      ))
+    },
+    _ => AppError::from(err),
+  })?;
+  row.try_into()
+}
+
+pub async fn select_database_computed_fields<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseComputedField>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseComputedFieldRow>(
+    r#"
+      SELECT field_id, computation, error, created_by, created_at, updated_at
+      FROM af_database_computed_field
+      WHERE workspace_id = $1 AND database_id = $2
+      ORDER BY created_at ASC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_all(executor)
+  .await?;
+  rows.into_iter().map(TryInto::try_into).collect()
+}
+
+/// Updates the computation of a computed field, clearing its error.
+pub async fn update_database_computed_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+  computation: &AFDatabaseComputation,
+) -> Result<AFDatabaseComputedField, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseComputedFieldRow>(
+    r#"
+      UPDATE af_database_computed_field
+      SET computation = $4, error = NULL, updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND database_id = $2 AND field_id = $3
+      RETURNING field_id, computation, error, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .bind(serde_json::to_value(computation)?)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| computed_field_not_found(database_id, field_id))?;
+  row.try_into()
+}
+
+/// Records why the cells of a computed field couldn't be computed, or clears the error.
+pub async fn update_database_computed_field_error<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  database_id: &str,
+  field_id: &str,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_database_computed_field
+      SET error = $3
+      WHERE database_id = $1 AND field_id = $2
+    "#,
+  )
+  .bind(database_id)
+  .bind(field_id)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn delete_database_computed_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_database_computed_field
+      WHERE workspace_id = $1 AND database_id = $2 AND field_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .execute(executor)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(computed_field_not_found(database_id, field_id));
+  }
+  Ok(())
+}
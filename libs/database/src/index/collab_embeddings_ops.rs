@@ -1,12 +1,14 @@
+use chrono::{DateTime, Utc};
 use collab_entity::CollabType;
 use pgvector::Vector;
 use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::{Error, Executor, Postgres, Transaction};
+use std::collections::HashSet;
 use std::ops::DerefMut;
 use uuid::Uuid;
 
 use database_entity::dto::{
-  AFCollabEmbeddingParams, IndexingStatus, QueryCollab, QueryCollabParams,
+  AFCollabEmbeddingParams, AFCollabIndexError, IndexingStatus, QueryCollab, QueryCollabParams,
 };
 
 pub async fn get_index_status<'a, E>(
@@ -84,24 +86,23 @@ impl PgHasArrayType for Fragment {
   }
 }
 
+/// Replaces the embeddings stored for a collab with the given set of fragments. Fragments without
+/// an embedding are expected to be already stored and are kept as they are, while stored fragments
+/// which are missing from `records` are removed, ie. all of them when `records` is empty.
 pub async fn upsert_collab_embeddings(
   tx: &mut Transaction<'_, sqlx::Postgres>,
   workspace_id: &Uuid,
+  object_id: &str,
+  collab_type: &CollabType,
   tokens_used: u32,
   records: Vec<AFCollabEmbeddingParams>,
 ) -> Result<(), sqlx::Error> {
-  if records.is_empty() {
-    return Ok(());
-  }
-  let object_id = records[0].object_id.clone();
-  let collab_type = records[0].collab_type.clone();
-
   let fragments = records.into_iter().map(Fragment::from).collect::<Vec<_>>();
 
   sqlx::query(r#"CALL af_collab_embeddings_upsert($1, $2, $3, $4, $5::af_fragment[])"#)
     .bind(*workspace_id)
     .bind(object_id)
-    .bind(crate::collab::partition_key_from_collab_type(&collab_type))
+    .bind(crate::collab::partition_key_from_collab_type(collab_type))
     .bind(tokens_used as i32)
     .bind(fragments)
     .execute(tx.deref_mut())
@@ -109,6 +110,24 @@ pub async fn upsert_collab_embeddings(
   Ok(())
 }
 
+/// Returns ids of all fragments currently stored for a given collab. Since fragment ids are derived
+/// from the fragment content, they can be used to detect which fragments haven't changed since the
+/// last time the collab was indexed.
+pub async fn select_collab_embedding_fragment_ids<'a, E>(
+  executor: E,
+  object_id: &str,
+) -> Result<HashSet<String>, sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let fragment_ids =
+    sqlx::query_scalar::<_, String>("SELECT fragment_id FROM af_collab_embeddings WHERE oid = $1")
+      .bind(object_id)
+      .fetch_all(executor)
+      .await?;
+  Ok(fragment_ids.into_iter().collect())
+}
+
 pub async fn get_collabs_without_embeddings<'a, E>(
   executor: E,
 ) -> Result<Vec<CollabId>, sqlx::Error>
@@ -140,6 +159,158 @@ where
   )
 }
 
+/// Returns all documents of a given workspace, which can be indexed.
+pub async fn get_workspace_collabs_for_indexing<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<CollabId>, sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let rows = sqlx::query_as::<_, (Uuid, String, i32)>(
+    r#"
+  SELECT c.workspace_id, c.oid, c.partition_key
+  FROM af_collab c
+  WHERE c.workspace_id = $1 AND c.partition_key = 0 AND c.deleted_at IS NULL"#, // atm. get only documents
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|(workspace_id, object_id, partition_key)| CollabId {
+        collab_type: CollabType::from(partition_key),
+        workspace_id,
+        object_id,
+      })
+      .collect(),
+  )
+}
+
+/// Removes all embeddings of a given collab, so that it will be indexed from scratch next time.
+pub async fn delete_collab_embeddings<'a, E>(
+  executor: E,
+  object_id: &str,
+  collab_type: &CollabType,
+) -> Result<(), sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  sqlx::query("DELETE FROM af_collab_embeddings WHERE oid = $1 AND partition_key = $2")
+    .bind(object_id)
+    .bind(crate::collab::partition_key_from_collab_type(collab_type))
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Records a failed attempt to index a collab. Error is cleared once the embeddings of that collab
+/// are successfully stored with [upsert_collab_embeddings].
+pub async fn upsert_collab_index_error<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+  object_id: &str,
+  collab_type: &CollabType,
+  error: &str,
+) -> Result<(), sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  sqlx::query(
+    r#"
+  INSERT INTO af_collab_index_error (oid, partition_key, workspace_id, error)
+  VALUES ($1, $2, $3, $4)
+  ON CONFLICT (oid, partition_key) DO UPDATE
+  SET error = EXCLUDED.error,
+      attempts = af_collab_index_error.attempts + 1,
+      failed_at = NOW()"#,
+  )
+  .bind(object_id)
+  .bind(crate::collab::partition_key_from_collab_type(collab_type))
+  .bind(workspace_id)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct WorkspaceIndexCounts {
+  /// Number of documents with stored embeddings and no pending indexing errors.
+  pub indexed: i64,
+  /// Number of documents, which have neither been indexed nor failed to be indexed.
+  pub pending: i64,
+  /// Number of documents, which failed to be indexed the last time it was attempted.
+  pub failed: i64,
+}
+
+pub async fn select_workspace_index_counts<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceIndexCounts, sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  sqlx::query_as::<_, WorkspaceIndexCounts>(
+    r#"
+  WITH status AS (
+    SELECT
+      EXISTS (SELECT 1 FROM af_collab_index_error e
+              WHERE e.oid = c.oid AND e.partition_key = c.partition_key) AS failed,
+      EXISTS (SELECT 1 FROM af_collab_embeddings em
+              WHERE em.oid = c.oid AND em.partition_key = c.partition_key) AS indexed
+    FROM af_collab c
+    WHERE c.workspace_id = $1 AND c.partition_key = 0 AND c.deleted_at IS NULL
+  )
+  SELECT
+    COUNT(*) FILTER (WHERE indexed AND NOT failed) AS indexed,
+    COUNT(*) FILTER (WHERE NOT indexed AND NOT failed) AS pending,
+    COUNT(*) FILTER (WHERE failed) AS failed
+  FROM status"#,
+  )
+  .bind(workspace_id)
+  .fetch_one(executor)
+  .await
+}
+
+/// Returns the most recent indexing errors of collabs in a given workspace.
+pub async fn select_collab_index_errors<'a, E>(
+  executor: E,
+  workspace_id: &Uuid,
+  limit: i64,
+) -> Result<Vec<AFCollabIndexError>, sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let rows = sqlx::query_as::<_, (String, i32, String, i32, DateTime<Utc>)>(
+    r#"
+  SELECT oid, partition_key, error, attempts, failed_at
+  FROM af_collab_index_error
+  WHERE workspace_id = $1
+  ORDER BY failed_at DESC
+  LIMIT $2"#,
+  )
+  .bind(workspace_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(object_id, partition_key, error, attempts, failed_at)| AFCollabIndexError {
+          object_id,
+          collab_type: CollabType::from(partition_key),
+          error,
+          attempts,
+          failed_at,
+        },
+      )
+      .collect(),
+  )
+}
+
 #[derive(Debug, Clone)]
 pub struct CollabId {
   pub collab_type: CollabType,
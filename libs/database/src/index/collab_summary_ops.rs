@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+/// Summary of a document, together with a description of the content it was generated from.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFCollabSummary {
+  pub oid: String,
+  pub summary: String,
+  pub content_hash: String,
+  pub content_len: i32,
+  pub updated_at: DateTime<Utc>,
+}
+
+pub async fn select_collab_summary<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  oid: &str,
+) -> Result<Option<AFCollabSummary>, sqlx::Error> {
+  sqlx::query_as::<_, AFCollabSummary>(
+    r#"
+      SELECT oid, summary, content_hash, content_len, updated_at
+      FROM af_collab_summary
+      WHERE oid = $1
+    "#,
+  )
+  .bind(oid)
+  .fetch_optional(executor)
+  .await
+}
+
+/// Returns the summaries of all the documents of a workspace, by their object id.
+pub async fn select_workspace_collab_summaries<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<HashMap<String, String>, sqlx::Error> {
+  let rows: Vec<(String, String)> = sqlx::query_as(
+    r#"
+      SELECT oid, summary
+      FROM af_collab_summary
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().collect())
+}
+
+pub async fn upsert_collab_summary<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  summary: &str,
+  content_hash: &str,
+  content_len: i32,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_summary (oid, workspace_id, summary, content_hash, content_len)
+      VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (oid) DO UPDATE
+      SET summary = EXCLUDED.summary,
+          content_hash = EXCLUDED.content_hash,
+          content_len = EXCLUDED.content_len,
+          updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(oid)
+  .bind(workspace_id)
+  .bind(summary)
+  .bind(content_hash)
+  .bind(content_len)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
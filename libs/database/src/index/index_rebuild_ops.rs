@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use database_entity::dto::AFIndexRebuildProgress;
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+/// Last re-indexing requested for a workspace.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFWorkspaceIndexRebuild {
+  pub rebuild_id: Uuid,
+  pub total: i64,
+  pub processed: i64,
+  pub failed: i64,
+  pub started_at: DateTime<Utc>,
+  pub finished_at: Option<DateTime<Utc>>,
+  pub cancelled: bool,
+}
+
+impl AFWorkspaceIndexRebuild {
+  pub fn is_finished(&self) -> bool {
+    self.finished_at.is_some()
+  }
+}
+
+impl From<AFWorkspaceIndexRebuild> for AFIndexRebuildProgress {
+  fn from(rebuild: AFWorkspaceIndexRebuild) -> Self {
+    Self {
+      total: rebuild.total as u64,
+      processed: rebuild.processed as u64,
+      failed: rebuild.failed as u64,
+      started_at: rebuild.started_at,
+      finished_at: rebuild.finished_at,
+      cancelled: rebuild.cancelled,
+    }
+  }
+}
+
+/// Starts a re-indexing of `total` documents, unless the last one of the workspace is still in
+/// progress, in which case `None` is returned. A re-indexing without documents is finished at once.
+pub async fn start_workspace_index_rebuild<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  rebuild_id: &Uuid,
+  total: i64,
+) -> Result<Option<AFWorkspaceIndexRebuild>, sqlx::Error> {
+  sqlx::query_as::<_, AFWorkspaceIndexRebuild>(
+    r#"
+      INSERT INTO af_workspace_index_rebuild (workspace_id, rebuild_id, total, finished_at)
+      VALUES ($1, $2, $3, CASE WHEN $3 = 0 THEN NOW() END)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET rebuild_id = EXCLUDED.rebuild_id,
+          total = EXCLUDED.total,
+          processed = 0,
+          failed = 0,
+          started_at = NOW(),
+          finished_at = EXCLUDED.finished_at,
+          cancelled = FALSE
+      WHERE af_workspace_index_rebuild.finished_at IS NOT NULL
+      RETURNING rebuild_id, total, processed, failed, started_at, finished_at, cancelled
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(rebuild_id)
+  .bind(total)
+  .fetch_optional(executor)
+  .await
+}
+
+pub async fn select_workspace_index_rebuild<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceIndexRebuild>, sqlx::Error> {
+  sqlx::query_as::<_, AFWorkspaceIndexRebuild>(
+    r#"
+      SELECT rebuild_id, total, processed, failed, started_at, finished_at, cancelled
+      FROM af_workspace_index_rebuild
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await
+}
+
+/// Cancels the re-indexing of the workspace if it's still in progress. The jobs of its remaining
+/// documents are skipped.
+pub async fn cancel_workspace_index_rebuild<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceIndexRebuild>, sqlx::Error> {
+  sqlx::query_as::<_, AFWorkspaceIndexRebuild>(
+    r#"
+      UPDATE af_workspace_index_rebuild
+      SET cancelled = cancelled OR finished_at IS NULL,
+          finished_at = COALESCE(finished_at, NOW())
+      WHERE workspace_id = $1
+      RETURNING rebuild_id, total, processed, failed, started_at, finished_at, cancelled
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await
+}
+
+/// Records that `processed` documents of the re-indexing have been processed, `failed` of which
+/// failed to be indexed. The re-indexing is finished once all its documents are processed.
+/// Nothing is recorded if the re-indexing has been finished or replaced in the meantime.
+pub async fn record_workspace_index_rebuild_progress<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  rebuild_id: &Uuid,
+  processed: i64,
+  failed: i64,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_index_rebuild
+      SET processed = processed + $3,
+          failed = failed + $4,
+          finished_at = CASE WHEN processed + $3 >= total THEN NOW() END
+      WHERE workspace_id = $1 AND rebuild_id = $2 AND finished_at IS NULL
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(rebuild_id)
+  .bind(processed)
+  .bind(failed)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
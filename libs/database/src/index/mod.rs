@@ -1,5 +1,9 @@
 mod collab_embeddings_ops;
+mod collab_summary_ops;
+mod index_rebuild_ops;
 mod search_ops;
 
 pub use collab_embeddings_ops::*;
+pub use collab_summary_ops::*;
+pub use index_rebuild_ops::*;
 pub use search_ops::*;
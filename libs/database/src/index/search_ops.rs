@@ -29,18 +29,22 @@ pub async fn search_documents(
     )
     SELECT
       em.oid AS object_id,
+      em.fragment_id,
       collab.workspace_id,
       em.partition_key AS collab_type,
       em.content_type,
       LEFT(em.content, $4) AS content_preview,
       u.name AS created_by,
       collab.created_at AS created_at,
-      em.embedding <=> $3 AS score
+      em.embedding <=> $3 AS score,
+      summary.summary
     FROM af_collab_embeddings em
     JOIN af_collab collab ON em.oid = collab.oid AND em.partition_key = collab.partition_key
+    LEFT JOIN af_collab_summary summary ON em.oid = summary.oid
     JOIN af_workspace_member member ON collab.workspace_id = member.workspace_id
     JOIN af_user u ON collab.owner_uid = u.uid
     WHERE member.uid = $1 AND collab.workspace_id = $2 AND collab.deleted_at IS NULL
+      AND (cardinality($7::text[]) = 0 OR em.oid = ANY($7::text[]))
     ORDER BY em.embedding <=> $3
     LIMIT $5
   "#,
@@ -50,7 +54,8 @@ pub async fn search_documents(
   .bind(Vector::from(params.embedding))
   .bind(params.preview)
   .bind(params.limit)
-  .bind(tokens_used as i64);
+  .bind(tokens_used as i64)
+  .bind(params.object_ids);
   let rows = query.fetch_all(tx.deref_mut()).await?;
   Ok(rows)
 }
@@ -67,12 +72,16 @@ pub struct SearchDocumentParams {
   pub preview: i32,
   /// Embedding of the query - generated by OpenAI embedder.
   pub embedding: Vec<f32>,
+  /// If not empty, only documents with these object ids are searched.
+  pub object_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct SearchDocumentItem {
   /// Document identifier.
   pub object_id: String,
+  /// Identifier of the indexed fragment of the document.
+  pub fragment_id: String,
   /// Workspace identifier, given document belongs to.
   pub workspace_id: Uuid,
   /// Partition key, which maps directly onto [collab_entity::CollabType].
@@ -87,4 +96,6 @@ pub struct SearchDocumentItem {
   pub created_at: DateTime<Utc>,
   /// Similarity score to an original query. Lower is better.
   pub score: f64,
+  /// AI generated summary of the document, if any.
+  pub summary: Option<String>,
 }
@@ -1,13 +1,21 @@
 pub mod access_request;
+pub mod ai_field;
+pub mod ai_prompt;
+pub mod ai_usage;
 pub mod chat;
 pub mod collab;
+pub mod computed_field;
 pub mod file;
 pub mod history;
 pub mod index;
 pub mod listener;
 pub mod pg_row;
 pub mod publish;
+pub mod reminder;
 pub mod resource_usage;
+pub mod row_history;
+pub mod row_schedule;
 pub mod template;
 pub mod user;
+pub mod webhook;
 pub mod workspace;
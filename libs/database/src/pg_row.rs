@@ -1,6 +1,8 @@
 use anyhow::anyhow;
 use app_error::AppError;
 use chrono::{DateTime, Utc};
+use collab_rt_entity::chat::ChatEvent;
+use collab_rt_entity::user::AFReminderNotification;
 
 use database_entity::dto::{
   AFAccessLevel, AFRole, AFUserProfile, AFWebUser, AFWorkspace, AFWorkspaceInvitationStatus,
@@ -211,6 +213,41 @@ pub struct AFUserNotification {
   pub payload: Option<AFUserRow>,
 }
 
+/// Postgres channel used to broadcast [AFChatNotification]s.
+pub const CHAT_NOTIFICATION_CHANNEL: &str = "af_chat_channel";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFChatNotification {
+  /// Users who should receive the event.
+  pub recipients: Vec<i64>,
+  pub event: ChatEvent,
+}
+
+/// Postgres channel used to deliver [AFReminderPgNotification]s.
+pub const REMINDER_NOTIFICATION_CHANNEL: &str = "af_reminder_channel";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFReminderPgNotification {
+  /// Id of the saved reminder, which is marked as delivered once it's sent to a connection.
+  pub id: Uuid,
+  /// User who set the reminder, whose realtime connections receive it.
+  pub uid: i64,
+  pub reminder: AFReminderNotification,
+}
+
+/// Postgres channel used to deliver [AFDatabaseRowChange]s.
+pub const DATABASE_ROW_CHANGE_CHANNEL: &str = "af_database_row_change_channel";
+
+/// Cells of a database row changed by a user, through the realtime server or the REST API.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFDatabaseRowChange {
+  pub workspace_id: Uuid,
+  pub database_id: String,
+  pub row_id: String,
+  pub uid: i64,
+  pub field_ids: Vec<String>,
+}
+
 #[derive(FromRow, Debug, Clone)]
 pub struct AFPermissionRow {
   pub id: i32,
@@ -258,6 +295,8 @@ pub struct AFChatRow {
   pub rag_ids: serde_json::Value,
   pub workspace_id: Uuid,
   pub meta_data: serde_json::Value,
+  /// The last message of the branch of the conversation currently selected.
+  pub active_message_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
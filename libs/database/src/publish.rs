@@ -2,7 +2,7 @@ use app_error::AppError;
 use database_entity::dto::{
   PatchPublishedCollab, PublishCollabItem, PublishCollabKey, PublishInfo, WorkspaceNamespace,
 };
-use sqlx::{Executor, PgPool, Postgres};
+use sqlx::{Executor, FromRow, PgPool, Postgres};
 use uuid::Uuid;
 
 pub async fn select_user_is_collab_publisher_for_all_views(
@@ -677,3 +677,33 @@ pub async fn select_published_view_ids_for_workspace<'a, E: Executor<'a, Databas
 
   Ok(res)
 }
+
+/// A published collab, along with the user who published it.
+#[derive(Debug, FromRow)]
+pub struct PublishedCollabWithPublisher {
+  pub workspace_id: Uuid,
+  pub view_id: Uuid,
+  pub published_by: i64,
+  pub metadata: serde_json::Value,
+}
+
+pub async fn select_published_collab_with_publisher<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  publish_namespace: &str,
+  publish_name: &str,
+) -> Result<Option<PublishedCollabWithPublisher>, AppError> {
+  let res = sqlx::query_as::<_, PublishedCollabWithPublisher>(
+    r#"
+      SELECT workspace_id, view_id, published_by, metadata
+      FROM af_published_collab
+      WHERE workspace_id = (SELECT workspace_id FROM af_workspace_namespace WHERE namespace = $1)
+        AND unpublished_at IS NULL
+        AND publish_name = $2
+    "#,
+  )
+  .bind(publish_namespace)
+  .bind(publish_name)
+  .fetch_optional(executor)
+  .await?;
+  Ok(res)
+}
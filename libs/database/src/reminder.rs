@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use collab_rt_entity::user::{AFReminderNotification, AFReminderObjectType};
+use shared_entity::dto::auth_dto::{
+  AFUserNotificationSettings, UpdateUserNotificationSettingsParams,
+};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::pg_row::{AFReminderPgNotification, REMINDER_NOTIFICATION_CHANNEL};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i16)]
+pub enum AFReminderStatus {
+  Pending = 0,
+  /// Sent to the realtime connections of the user, if any.
+  Notified = 1,
+  /// Received by a realtime connection of the user.
+  Delivered = 2,
+  Emailed = 3,
+  /// Not sent, ie. because the user disabled reminders or the reminder was too late.
+  Skipped = 4,
+}
+
+pub fn reminder_object_type_to_i16(object_type: AFReminderObjectType) -> i16 {
+  match object_type {
+    AFReminderObjectType::DatabaseRow => 0,
+    AFReminderObjectType::Document => 1,
+  }
+}
+
+pub fn reminder_object_type_from_i16(value: i16) -> AFReminderObjectType {
+  match value {
+    1 => AFReminderObjectType::Document,
+    _ => AFReminderObjectType::DatabaseRow,
+  }
+}
+
+/// A reminder of a database row or a document, with its times resolved in the time zone of the
+/// user who set it. The local times are set when the reminder is at a time of the day of the
+/// user, so that it's resolved again when the time zone of the user changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AFReminderParams {
+  pub reminder_id: String,
+  pub field_id: Option<String>,
+  pub block_id: Option<String>,
+  pub title: String,
+  pub include_time: bool,
+  pub event_at: DateTime<Utc>,
+  pub remind_at: DateTime<Utc>,
+  pub local_event_at: Option<NaiveDateTime>,
+  pub local_remind_at: Option<NaiveDateTime>,
+}
+
+/// A reminder which is due, claimed to be sent to the user who set it.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFDueReminder {
+  pub id: Uuid,
+  pub workspace_id: Uuid,
+  pub object_type: i16,
+  pub object_id: String,
+  pub reminder_id: String,
+  pub database_id: Option<String>,
+  pub field_id: Option<String>,
+  pub block_id: Option<String>,
+  pub uid: i64,
+  pub include_time: bool,
+  pub event_at: DateTime<Utc>,
+  pub remind_at: DateTime<Utc>,
+}
+
+/// A reminder which wasn't delivered to a realtime connection of the user, claimed to be sent by
+/// email.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFUndeliveredReminder {
+  pub id: Uuid,
+  pub workspace_id: Uuid,
+  pub object_type: i16,
+  pub object_id: String,
+  pub title: String,
+  pub include_time: bool,
+  pub event_at: DateTime<Utc>,
+  pub name: Option<String>,
+  pub email: String,
+  pub timezone: Option<String>,
+  pub reminder_email_enabled: Option<bool>,
+}
+
+#[derive(FromRow)]
+struct AFUserNotificationSettingsRow {
+  timezone: String,
+  reminder_enabled: bool,
+  reminder_email_enabled: bool,
+}
+
+impl From<AFUserNotificationSettingsRow> for AFUserNotificationSettings {
+  fn from(row: AFUserNotificationSettingsRow) -> Self {
+    Self {
+      timezone: row.timezone,
+      reminder_enabled: row.reminder_enabled,
+      reminder_email_enabled: row.reminder_email_enabled,
+    }
+  }
+}
+
+/// Returns the notification settings of the user, the default settings when the user has never
+/// changed them.
+pub async fn select_user_notification_settings<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<AFUserNotificationSettings, AppError> {
+  let row = sqlx::query_as::<_, AFUserNotificationSettingsRow>(
+    r#"
+      SELECT timezone, reminder_enabled, reminder_email_enabled
+      FROM af_user_notification_setting
+      WHERE uid = $1
+    "#,
+  )
+  .bind(uid)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row.map(Into::into).unwrap_or_default())
+}
+
+/// Updates the given notification settings of the user. When the time zone changes, the pending
+/// reminders set at a time of the day of the user are moved to the same time in the new time zone.
+pub async fn upsert_user_notification_settings(
+  txn: &mut Transaction<'_, Postgres>,
+  uid: i64,
+  params: &UpdateUserNotificationSettingsParams,
+) -> Result<AFUserNotificationSettings, AppError> {
+  let defaults = AFUserNotificationSettings::default();
+  let row = sqlx::query_as::<_, AFUserNotificationSettingsRow>(
+    r#"
+      INSERT INTO af_user_notification_setting
+        (uid, timezone, reminder_enabled, reminder_email_enabled)
+      VALUES ($1, COALESCE($2, $5), COALESCE($3, $6), COALESCE($4, $7))
+      ON CONFLICT (uid) DO UPDATE SET
+        timezone = COALESCE($2, af_user_notification_setting.timezone),
+        reminder_enabled = COALESCE($3, af_user_notification_setting.reminder_enabled),
+        reminder_email_enabled = COALESCE($4, af_user_notification_setting.reminder_email_enabled),
+        updated_at = CURRENT_TIMESTAMP
+      RETURNING timezone, reminder_enabled, reminder_email_enabled
+    "#,
+  )
+  .bind(uid)
+  .bind(params.timezone.as_deref())
+  .bind(params.reminder_enabled)
+  .bind(params.reminder_email_enabled)
+  .bind(&defaults.timezone)
+  .bind(defaults.reminder_enabled)
+  .bind(defaults.reminder_email_enabled)
+  .fetch_one(txn.deref_mut())
+  .await?;
+
+  if params.timezone.is_some() {
+    sqlx::query(
+      r#"
+        UPDATE af_reminder
+        SET event_at = COALESCE(local_event_at AT TIME ZONE $2, event_at),
+            remind_at = local_remind_at AT TIME ZONE $2,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE uid = $1 AND status = $3 AND local_remind_at IS NOT NULL
+      "#,
+    )
+    .bind(uid)
+    .bind(&row.timezone)
+    .bind(AFReminderStatus::Pending as i16)
+    .execute(txn.deref_mut())
+    .await?;
+  }
+  Ok(row.into())
+}
+
+/// Returns the time zones of the users who set the given reminders of an object, by reminder id.
+/// Reminders which aren't saved yet are left out.
+pub async fn select_reminder_owner_timezones<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  object_id: &str,
+  reminder_ids: &[String],
+) -> Result<HashMap<String, String>, AppError> {
+  let rows: Vec<(String, Option<String>)> = sqlx::query_as(
+    r#"
+      SELECT r.reminder_id, s.timezone
+      FROM af_reminder r
+      LEFT JOIN af_user_notification_setting s ON s.uid = r.uid
+      WHERE r.object_id = $1 AND r.reminder_id = ANY($2)
+    "#,
+  )
+  .bind(object_id)
+  .bind(reminder_ids)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|(reminder_id, timezone)| {
+        (
+          reminder_id,
+          timezone.unwrap_or_else(|| AFUserNotificationSettings::default().timezone),
+        )
+      })
+      .collect(),
+  )
+}
+
+/// Saves the reminders of an object. New reminders are attributed to `uid`, while existing ones
+/// keep the user who set them. A reminder whose time changed is sent again, unless its time has
+/// already passed.
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_reminders(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  object_type: AFReminderObjectType,
+  object_id: &str,
+  database_id: Option<&str>,
+  uid: i64,
+  reminders: &[AFReminderParams],
+) -> Result<(), AppError> {
+  for reminder in reminders {
+    sqlx::query(
+      r#"
+        INSERT INTO af_reminder (workspace_id, object_type, object_id, reminder_id, database_id,
+          field_id, block_id, uid, title, include_time, event_at, remind_at, local_event_at,
+          local_remind_at, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+          CASE WHEN $12 > CURRENT_TIMESTAMP THEN $15 ELSE $16 END)
+        ON CONFLICT (object_id, reminder_id) DO UPDATE SET
+          field_id = EXCLUDED.field_id,
+          block_id = EXCLUDED.block_id,
+          title = EXCLUDED.title,
+          include_time = EXCLUDED.include_time,
+          event_at = EXCLUDED.event_at,
+          remind_at = EXCLUDED.remind_at,
+          local_event_at = EXCLUDED.local_event_at,
+          local_remind_at = EXCLUDED.local_remind_at,
+          status = CASE WHEN af_reminder.remind_at = EXCLUDED.remind_at
+            THEN af_reminder.status ELSE EXCLUDED.status END,
+          notified_at = CASE WHEN af_reminder.remind_at = EXCLUDED.remind_at
+            THEN af_reminder.notified_at ELSE NULL END,
+          delivered_at = CASE WHEN af_reminder.remind_at = EXCLUDED.remind_at
+            THEN af_reminder.delivered_at ELSE NULL END,
+          updated_at = CURRENT_TIMESTAMP
+      "#,
+    )
+    .bind(workspace_id)
+    .bind(reminder_object_type_to_i16(object_type))
+    .bind(object_id)
+    .bind(&reminder.reminder_id)
+    .bind(database_id)
+    .bind(reminder.field_id.as_deref())
+    .bind(reminder.block_id.as_deref())
+    .bind(uid)
+    .bind(&reminder.title)
+    .bind(reminder.include_time)
+    .bind(reminder.event_at)
+    .bind(reminder.remind_at)
+    .bind(reminder.local_event_at)
+    .bind(reminder.local_remind_at)
+    .bind(AFReminderStatus::Pending as i16)
+    .bind(AFReminderStatus::Skipped as i16)
+    .execute(txn.deref_mut())
+    .await?;
+  }
+  Ok(())
+}
+
+/// Deletes the reminders of an object which were removed from it.
+pub async fn delete_reminders<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  object_id: &str,
+  reminder_ids: &[String],
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_reminder WHERE object_id = $1 AND reminder_id = ANY($2)")
+    .bind(object_id)
+    .bind(reminder_ids)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Claims the pending reminders which are due, marking them as notified.
+pub async fn claim_due_reminders<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+) -> Result<Vec<AFDueReminder>, AppError> {
+  let reminders = sqlx::query_as::<_, AFDueReminder>(
+    r#"
+      UPDATE af_reminder
+      SET status = $2, notified_at = CURRENT_TIMESTAMP
+      WHERE id IN (
+        SELECT id
+        FROM af_reminder
+        WHERE status = $1 AND remind_at <= CURRENT_TIMESTAMP
+        ORDER BY remind_at ASC
+        LIMIT $3
+        FOR UPDATE SKIP LOCKED
+      )
+      RETURNING id, workspace_id, object_type, object_id, reminder_id, database_id, field_id,
+        block_id, uid, include_time, event_at, remind_at
+    "#,
+  )
+  .bind(AFReminderStatus::Pending as i16)
+  .bind(AFReminderStatus::Notified as i16)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(reminders)
+}
+
+/// Claims the reminders which weren't delivered to a realtime connection of their user within
+/// `delay_secs` of being notified, marking them as emailed.
+pub async fn claim_undelivered_reminders<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+  delay_secs: i64,
+) -> Result<Vec<AFUndeliveredReminder>, AppError> {
+  let reminders = sqlx::query_as::<_, AFUndeliveredReminder>(
+    r#"
+      WITH claimed AS (
+        UPDATE af_reminder
+        SET status = $2
+        WHERE id IN (
+          SELECT id
+          FROM af_reminder
+          WHERE status = $1
+            AND notified_at <= CURRENT_TIMESTAMP - make_interval(secs => $4)
+          ORDER BY notified_at ASC
+          LIMIT $3
+          FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, workspace_id, object_type, object_id, uid, title, include_time, event_at
+      )
+      SELECT c.id, c.workspace_id, c.object_type, c.object_id, c.title, c.include_time,
+        c.event_at, u.name, u.email, s.timezone, s.reminder_email_enabled
+      FROM claimed c
+      JOIN af_user u ON u.uid = c.uid
+      LEFT JOIN af_user_notification_setting s ON s.uid = c.uid
+    "#,
+  )
+  .bind(AFReminderStatus::Notified as i16)
+  .bind(AFReminderStatus::Emailed as i16)
+  .bind(limit)
+  .bind(delay_secs as f64)
+  .fetch_all(executor)
+  .await?;
+  Ok(reminders)
+}
+
+pub async fn update_reminder_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  id: &Uuid,
+  status: AFReminderStatus,
+) -> Result<(), AppError> {
+  sqlx::query("UPDATE af_reminder SET status = $2 WHERE id = $1")
+    .bind(id)
+    .bind(status as i16)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+pub async fn update_reminder_title<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  id: &Uuid,
+  title: &str,
+) -> Result<(), AppError> {
+  sqlx::query("UPDATE af_reminder SET title = $2 WHERE id = $1")
+    .bind(id)
+    .bind(title)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Marks a notified reminder as delivered, so that it isn't sent by email. Reminders which were
+/// already emailed are left unchanged.
+pub async fn update_reminder_delivered<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_reminder
+      SET status = $2, delivered_at = CURRENT_TIMESTAMP
+      WHERE id = $1 AND status = $3
+    "#,
+  )
+  .bind(id)
+  .bind(AFReminderStatus::Delivered as i16)
+  .bind(AFReminderStatus::Notified as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Delivers a reminder to the realtime connections of the user who set it, whichever server they
+/// are connected to.
+pub async fn notify_reminder<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  id: Uuid,
+  uid: i64,
+  reminder: AFReminderNotification,
+) -> Result<(), AppError> {
+  let payload = serde_json::to_string(&AFReminderPgNotification { id, uid, reminder })?;
+  sqlx::query("SELECT pg_notify($1, $2)")
+    .bind(REMINDER_NOTIFICATION_CHANNEL)
+    .bind(payload)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
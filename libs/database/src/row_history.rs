@@ -0,0 +1,163 @@
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::pg_row::{AFDatabaseRowChange, DATABASE_ROW_CHANGE_CHANNEL};
+
+/// A cell of a row which was changed. The cells are none when they didn't exist or were removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AFDatabaseRowCellChange {
+  pub field_id: String,
+  pub old_cell: Option<Value>,
+  pub new_cell: Option<Value>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFDatabaseRowHistoryRow {
+  pub history_id: i64,
+  pub field_id: String,
+  pub uid: Option<i64>,
+  pub user_name: Option<String>,
+  pub old_cell: Option<Value>,
+  pub new_cell: Option<Value>,
+  pub created_at: DateTime<Utc>,
+  pub changed_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct LatestCellChange {
+  history_id: i64,
+  uid: Option<i64>,
+  old_cell: Option<Value>,
+  changed_at: DateTime<Utc>,
+}
+
+/// Records the changes made by a user to the cells of a row. A change is merged into the latest
+/// change of the same cell when it was made by the same user within `merge_window_secs`, and the
+/// merged change is removed when the cell is back to its previous value.
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_database_row_changes(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  database_id: &str,
+  row_id: &str,
+  uid: i64,
+  changes: &[AFDatabaseRowCellChange],
+  changed_at: DateTime<Utc>,
+  merge_window_secs: i64,
+) -> Result<(), AppError> {
+  for change in changes {
+    let latest = sqlx::query_as::<_, LatestCellChange>(
+      r#"
+        SELECT history_id, uid, old_cell, changed_at
+        FROM af_database_row_history
+        WHERE row_id = $1 AND field_id = $2
+        ORDER BY changed_at DESC, history_id DESC
+        LIMIT 1
+        FOR UPDATE
+      "#,
+    )
+    .bind(row_id)
+    .bind(&change.field_id)
+    .fetch_optional(txn.deref_mut())
+    .await?;
+
+    match latest {
+      Some(latest)
+        if latest.uid == Some(uid)
+          && changed_at - latest.changed_at < chrono::Duration::seconds(merge_window_secs) =>
+      {
+        if latest.old_cell == change.new_cell {
+          sqlx::query("DELETE FROM af_database_row_history WHERE history_id = $1")
+            .bind(latest.history_id)
+            .execute(txn.deref_mut())
+            .await?;
+        } else {
+          sqlx::query(
+            r#"
+              UPDATE af_database_row_history
+              SET new_cell = $2, changed_at = $3
+              WHERE history_id = $1
+            "#,
+          )
+          .bind(latest.history_id)
+          .bind(&change.new_cell)
+          .bind(changed_at)
+          .execute(txn.deref_mut())
+          .await?;
+        }
+      },
+      _ => {
+        sqlx::query(
+          r#"
+            INSERT INTO af_database_row_history
+              (workspace_id, database_id, row_id, field_id, uid, old_cell, new_cell, created_at,
+                changed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+          "#,
+        )
+        .bind(workspace_id)
+        .bind(database_id)
+        .bind(row_id)
+        .bind(&change.field_id)
+        .bind(uid)
+        .bind(&change.old_cell)
+        .bind(&change.new_cell)
+        .bind(changed_at)
+        .execute(txn.deref_mut())
+        .await?;
+      },
+    }
+  }
+  Ok(())
+}
+
+/// Notifies the servers listening on [DATABASE_ROW_CHANGE_CHANNEL] that the cells of a row were
+/// changed. The notification is sent when the transaction is committed.
+pub async fn notify_database_row_change<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  change: &AFDatabaseRowChange,
+) -> Result<(), AppError> {
+  let payload = serde_json::to_string(change)?;
+  sqlx::query("SELECT pg_notify($1, $2)")
+    .bind(DATABASE_ROW_CHANGE_CHANNEL)
+    .bind(payload)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Returns the changes of a row made before `before`, the most recent first.
+pub async fn select_database_row_history<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  row_id: &str,
+  before: Option<DateTime<Utc>>,
+  limit: i64,
+) -> Result<Vec<AFDatabaseRowHistoryRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseRowHistoryRow>(
+    r#"
+      SELECT h.history_id, h.field_id, h.uid, u.name AS user_name, h.old_cell, h.new_cell,
+        h.created_at, h.changed_at
+      FROM af_database_row_history h
+      LEFT JOIN af_user u ON u.uid = h.uid
+      WHERE h.row_id = $3 AND h.workspace_id = $1 AND h.database_id = $2
+        AND ($4::TIMESTAMPTZ IS NULL OR h.changed_at < $4)
+      ORDER BY h.changed_at DESC, h.history_id DESC
+      LIMIT $5
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(row_id)
+  .bind(before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
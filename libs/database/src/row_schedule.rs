@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseRowSchedule, AFDatabaseRowScheduleRun, AFInsertDatabaseRowSchedule, AFMissedRunPolicy,
+  AFRowScheduleRule, AFRowScheduleRunStatus,
+};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFDatabaseRowScheduleRow {
+  schedule_id: Uuid,
+  database_id: String,
+  name: String,
+  rule: Value,
+  cells: Value,
+  relative_dates: Value,
+  missed_run_policy: i16,
+  enabled: bool,
+  next_run_at: Option<DateTime<Utc>>,
+  last_run_at: Option<DateTime<Utc>>,
+  created_by: i64,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<AFDatabaseRowScheduleRow> for AFDatabaseRowSchedule {
+  type Error = AppError;
+
+  fn try_from(row: AFDatabaseRowScheduleRow) -> Result<Self, Self::Error> {
+    Ok(Self {
+      schedule_id: row.schedule_id,
+      database_id: row.database_id,
+      name: row.name,
+      rule: serde_json::from_value(row.rule)?,
+      cells: serde_json::from_value(row.cells)?,
+      relative_dates: serde_json::from_value(row.relative_dates)?,
+      missed_run_policy: AFMissedRunPolicy::from(row.missed_run_policy),
+      enabled: row.enabled,
+      next_run_at: row.next_run_at,
+      last_run_at: row.last_run_at,
+      created_by: row.created_by,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    })
+  }
+}
+
+#[derive(FromRow)]
+struct AFDatabaseRowScheduleRunRow {
+  run_id: Uuid,
+  scheduled_for: DateTime<Utc>,
+  row_id: String,
+  status: i16,
+  attempts: i32,
+  error: Option<String>,
+  created_at: DateTime<Utc>,
+  completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<AFDatabaseRowScheduleRunRow> for AFDatabaseRowScheduleRun {
+  fn from(row: AFDatabaseRowScheduleRunRow) -> Self {
+    Self {
+      run_id: row.run_id,
+      scheduled_for: row.scheduled_for,
+      row_id: row.row_id,
+      status: AFRowScheduleRunStatus::from(row.status),
+      attempts: row.attempts,
+      error: row.error,
+      created_at: row.created_at,
+      completed_at: row.completed_at,
+    }
+  }
+}
+
+/// A schedule whose next run is due.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFDueRowSchedule {
+  pub schedule_id: Uuid,
+  pub rule: Value,
+  pub missed_run_policy: i16,
+  pub next_run_at: DateTime<Utc>,
+}
+
+/// A run whose row is to be created.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFRowScheduleRunJob {
+  pub run_id: Uuid,
+  pub schedule_id: Uuid,
+  pub workspace_id: Uuid,
+  pub database_id: String,
+  pub row_id: String,
+  pub scheduled_for: DateTime<Utc>,
+  pub cells: Value,
+  pub relative_dates: Value,
+  pub created_by: i64,
+  pub attempts: i32,
+}
+
+fn row_schedule_not_found(database_id: &str, schedule_id: &Uuid) -> AppError {
+  AppError::RecordNotFound(format!(
+    "row schedule:{} is not found in database:{}",
+    schedule_id, database_id
+  ))
+}
+
+pub async fn insert_database_row_schedule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  params: &AFInsertDatabaseRowSchedule,
+  next_run_at: Option<DateTime<Utc>>,
+  uid: i64,
+) -> Result<AFDatabaseRowSchedule, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseRowScheduleRow>(
+    r#"
+      INSERT INTO af_database_row_schedule
+        (workspace_id, database_id, name, rule, cells, relative_dates, missed_run_policy,
+          next_run_at, created_by)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+      RETURNING schedule_id, database_id, name, rule, cells, relative_dates, missed_run_policy,
+        enabled, next_run_at, last_run_at, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(&params.name)
+  .bind(serde_json::to_value(&params.rule)?)
+  .bind(serde_json::to_value(&params.cells)?)
+  .bind(serde_json::to_value(&params.relative_dates)?)
+  .bind(params.missed_run_policy as i16)
+  .bind(next_run_at)
+  .bind(uid)
+  .fetch_one(executor)
+  .await?;
+  row.try_into()
+}
+
+pub async fn select_database_row_schedules<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseRowSchedule>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseRowScheduleRow>(
+    r#"
+      SELECT schedule_id, database_id, name, rule, cells, relative_dates, missed_run_policy,
+        enabled, next_run_at, last_run_at, created_by, created_at, updated_at
+      FROM af_database_row_schedule
+      WHERE workspace_id = $1 AND database_id = $2
+      ORDER BY created_at ASC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_all(executor)
+  .await?;
+  rows.into_iter().map(TryInto::try_into).collect()
+}
+
+pub async fn select_database_row_schedule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  schedule_id: &Uuid,
+) -> Result<AFDatabaseRowSchedule, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseRowScheduleRow>(
+    r#"
+      SELECT schedule_id, database_id, name, rule, cells, relative_dates, missed_run_policy,
+        enabled, next_run_at, last_run_at, created_by, created_at, updated_at
+      FROM af_database_row_schedule
+      WHERE workspace_id = $1 AND database_id = $2 AND schedule_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(schedule_id)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| row_schedule_not_found(database_id, schedule_id))?;
+  row.try_into()
+}
+
+/// Updates the given properties of a schedule, leaving the others unchanged. The next run of the
+/// schedule is replaced by `next_run_at` when `reschedule` is true.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_database_row_schedule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  schedule_id: &Uuid,
+  name: Option<&str>,
+  rule: Option<&AFRowScheduleRule>,
+  cells: Option<&HashMap<String, Value>>,
+  relative_dates: Option<&HashMap<String, i64>>,
+  missed_run_policy: Option<AFMissedRunPolicy>,
+  enabled: Option<bool>,
+  reschedule: bool,
+  next_run_at: Option<DateTime<Utc>>,
+) -> Result<AFDatabaseRowSchedule, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseRowScheduleRow>(
+    r#"
+      UPDATE af_database_row_schedule
+      SET name = COALESCE($4, name),
+          rule = COALESCE($5, rule),
+          cells = COALESCE($6, cells),
+          relative_dates = COALESCE($7, relative_dates),
+          missed_run_policy = COALESCE($8, missed_run_policy),
+          enabled = COALESCE($9, enabled),
+          next_run_at = CASE WHEN $10 THEN $11 ELSE next_run_at END,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND database_id = $2 AND schedule_id = $3
+      RETURNING schedule_id, database_id, name, rule, cells, relative_dates, missed_run_policy,
+        enabled, next_run_at, last_run_at, created_by, created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(schedule_id)
+  .bind(name)
+  .bind(rule.map(serde_json::to_value).transpose()?)
+  .bind(cells.map(serde_json::to_value).transpose()?)
+  .bind(relative_dates.map(serde_json::to_value).transpose()?)
+  .bind(missed_run_policy.map(|policy| policy as i16))
+  .bind(enabled)
+  .bind(reschedule)
+  .bind(next_run_at)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| row_schedule_not_found(database_id, schedule_id))?;
+  row.try_into()
+}
+
+/// Deletes a schedule, together with its runs. Rows already created are kept.
+pub async fn delete_database_row_schedule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  schedule_id: &Uuid,
+) -> Result<(), AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_database_row_schedule
+      WHERE workspace_id = $1 AND database_id = $2 AND schedule_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(schedule_id)
+  .execute(executor)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(row_schedule_not_found(database_id, schedule_id));
+  }
+  Ok(())
+}
+
+/// Returns the latest runs of a schedule, most recent first.
+pub async fn select_database_row_schedule_runs<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  schedule_id: &Uuid,
+  limit: i64,
+) -> Result<Vec<AFDatabaseRowScheduleRun>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseRowScheduleRunRow>(
+    r#"
+      SELECT r.run_id, r.scheduled_for, r.row_id, r.status, r.attempts, r.error, r.created_at,
+        r.completed_at
+      FROM af_database_row_schedule_run r
+      JOIN af_database_row_schedule s USING (schedule_id)
+      WHERE s.workspace_id = $1 AND s.database_id = $2 AND r.schedule_id = $3
+      ORDER BY r.scheduled_for DESC
+      LIMIT $4
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(schedule_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Returns the enabled schedules whose next run is due, locking them until the end of the
+/// transaction. Schedules locked by another transaction are skipped.
+pub async fn select_due_row_schedules_for_update(
+  txn: &mut Transaction<'_, Postgres>,
+  limit: i64,
+) -> Result<Vec<AFDueRowSchedule>, AppError> {
+  let schedules = sqlx::query_as::<_, AFDueRowSchedule>(
+    r#"
+      SELECT schedule_id, rule, missed_run_policy, next_run_at
+      FROM af_database_row_schedule
+      WHERE enabled AND next_run_at <= CURRENT_TIMESTAMP
+      ORDER BY next_run_at ASC
+      LIMIT $1
+      FOR UPDATE SKIP LOCKED
+    "#,
+  )
+  .bind(limit)
+  .fetch_all(txn.deref_mut())
+  .await?;
+  Ok(schedules)
+}
+
+/// Queues the runs of a schedule, each with the id of the row it creates. Runs already queued for
+/// the same time are left unchanged.
+pub async fn insert_row_schedule_runs(
+  txn: &mut Transaction<'_, Postgres>,
+  schedule_id: &Uuid,
+  runs: &[(DateTime<Utc>, String)],
+) -> Result<(), AppError> {
+  for (scheduled_for, row_id) in runs {
+    sqlx::query(
+      r#"
+        INSERT INTO af_database_row_schedule_run (schedule_id, scheduled_for, row_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (schedule_id, scheduled_for) DO NOTHING
+      "#,
+    )
+    .bind(schedule_id)
+    .bind(scheduled_for)
+    .bind(row_id)
+    .execute(txn.deref_mut())
+    .await?;
+  }
+  Ok(())
+}
+
+/// Moves a schedule to its next run, none when the schedule has ended.
+pub async fn update_row_schedule_next_run(
+  txn: &mut Transaction<'_, Postgres>,
+  schedule_id: &Uuid,
+  next_run_at: Option<DateTime<Utc>>,
+  last_run_at: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_database_row_schedule
+      SET next_run_at = $2, last_run_at = COALESCE($3, last_run_at)
+      WHERE schedule_id = $1
+    "#,
+  )
+  .bind(schedule_id)
+  .bind(next_run_at)
+  .bind(last_run_at)
+  .execute(txn.deref_mut())
+  .await?;
+  Ok(())
+}
+
+/// Claims the pending runs which are due. The runs aren't claimed again before `lease_secs`, so
+/// that their row is created if the server stops while creating it.
+pub async fn claim_due_row_schedule_runs<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+  lease_secs: i64,
+) -> Result<Vec<AFRowScheduleRunJob>, AppError> {
+  let jobs = sqlx::query_as::<_, AFRowScheduleRunJob>(
+    r#"
+      UPDATE af_database_row_schedule_run r
+      SET next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $3)
+      FROM af_database_row_schedule s
+      WHERE r.schedule_id = s.schedule_id
+        AND r.run_id IN (
+          SELECT run_id
+          FROM af_database_row_schedule_run
+          WHERE status = $1 AND next_attempt_at <= CURRENT_TIMESTAMP
+          ORDER BY next_attempt_at ASC
+          LIMIT $2
+          FOR UPDATE SKIP LOCKED
+        )
+      RETURNING r.run_id, r.schedule_id, s.workspace_id, s.database_id, r.row_id, r.scheduled_for,
+        s.cells, s.relative_dates, s.created_by, r.attempts
+    "#,
+  )
+  .bind(AFRowScheduleRunStatus::Pending as i16)
+  .bind(limit)
+  .bind(lease_secs as f64)
+  .fetch_all(executor)
+  .await?;
+  Ok(jobs)
+}
+
+/// Records the outcome of an attempt to create the row of a run. Runs which are still pending are
+/// attempted again after `retry_in_secs`.
+pub async fn update_row_schedule_run_attempt<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  run_id: &Uuid,
+  status: AFRowScheduleRunStatus,
+  error: Option<&str>,
+  retry_in_secs: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_database_row_schedule_run
+      SET status = $2,
+          attempts = attempts + 1,
+          error = $3,
+          next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $4),
+          completed_at = CASE WHEN $2 = $5 THEN CURRENT_TIMESTAMP ELSE completed_at END
+      WHERE run_id = $1
+    "#,
+  )
+  .bind(run_id)
+  .bind(status as i16)
+  .bind(error)
+  .bind(retry_in_secs as f64)
+  .bind(AFRowScheduleRunStatus::Created as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
@@ -0,0 +1,438 @@
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseWebhook, AFDatabaseWebhookDelivery, AFDatabaseWebhookDeliveryStatus,
+  AFDatabaseWebhookEvent,
+};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(FromRow)]
+struct AFDatabaseWebhookRow {
+  webhook_id: Uuid,
+  database_id: String,
+  url: String,
+  event_types: Vec<String>,
+  enabled: bool,
+  created_by: i64,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl From<AFDatabaseWebhookRow> for AFDatabaseWebhook {
+  fn from(row: AFDatabaseWebhookRow) -> Self {
+    Self {
+      webhook_id: row.webhook_id,
+      database_id: row.database_id,
+      url: row.url,
+      secret: None,
+      events: row
+        .event_types
+        .iter()
+        .filter_map(|event| event.parse().ok())
+        .collect(),
+      enabled: row.enabled,
+      created_by: row.created_by,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    }
+  }
+}
+
+#[derive(FromRow)]
+struct AFDatabaseWebhookDeliveryRow {
+  delivery_id: Uuid,
+  event_type: String,
+  payload: Value,
+  status: i16,
+  attempts: i32,
+  response_status: Option<i32>,
+  error: Option<String>,
+  created_at: DateTime<Utc>,
+  delivered_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<AFDatabaseWebhookDeliveryRow> for AFDatabaseWebhookDelivery {
+  type Error = AppError;
+
+  fn try_from(row: AFDatabaseWebhookDeliveryRow) -> Result<Self, Self::Error> {
+    Ok(Self {
+      delivery_id: row.delivery_id,
+      event: row
+        .event_type
+        .parse()
+        .map_err(|err: String| AppError::Internal(anyhow::anyhow!(err)))?,
+      payload: serde_json::from_value(row.payload)?,
+      status: AFDatabaseWebhookDeliveryStatus::from(row.status),
+      attempts: row.attempts,
+      response_status: row.response_status,
+      error: row.error,
+      created_at: row.created_at,
+      delivered_at: row.delivered_at,
+    })
+  }
+}
+
+/// A database which has at least one webhook.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFWebhookDatabaseId {
+  pub workspace_id: Uuid,
+  pub database_id: String,
+}
+
+/// Rows and fields of a database as of the last time it was polled.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFDatabaseWebhookSnapshot {
+  pub row_ids: Vec<String>,
+  pub field_ids: Vec<String>,
+  pub polled_at: DateTime<Utc>,
+}
+
+/// A delivery to be sent to its webhook.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFWebhookDeliveryJob {
+  pub delivery_id: Uuid,
+  pub event_type: String,
+  pub payload: Value,
+  pub attempts: i32,
+  pub url: String,
+  pub secret: String,
+}
+
+fn webhook_not_found(database_id: &str, webhook_id: &Uuid) -> AppError {
+  AppError::RecordNotFound(format!(
+    "webhook:{} is not found in database:{}",
+    webhook_id, database_id
+  ))
+}
+
+fn event_types(events: &[AFDatabaseWebhookEvent]) -> Vec<String> {
+  events
+    .iter()
+    .map(|event| event.as_str().to_string())
+    .collect()
+}
+
+pub async fn insert_database_webhook<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  url: &str,
+  secret: &str,
+  events: &[AFDatabaseWebhookEvent],
+  uid: i64,
+) -> Result<AFDatabaseWebhook, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseWebhookRow>(
+    r#"
+      INSERT INTO af_database_webhook
+        (workspace_id, database_id, url, secret, event_types, created_by)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      RETURNING webhook_id, database_id, url, event_types, enabled, created_by,
+        created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(url)
+  .bind(secret)
+  .bind(event_types(events))
+  .bind(uid)
+  .fetch_one(executor)
+  .await?;
+  let mut webhook = AFDatabaseWebhook::from(row);
+  webhook.secret = Some(secret.to_string());
+  Ok(webhook)
+}
+
+pub async fn select_database_webhooks<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseWebhook>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseWebhookRow>(
+    r#"
+      SELECT webhook_id, database_id, url, event_types, enabled, created_by,
+        created_at, updated_at
+      FROM af_database_webhook
+      WHERE workspace_id = $1 AND database_id = $2
+      ORDER BY created_at ASC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Updates the given properties of a webhook, leaving the others unchanged.
+pub async fn update_database_webhook<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  webhook_id: &Uuid,
+  url: Option<&str>,
+  events: Option<&[AFDatabaseWebhookEvent]>,
+  enabled: Option<bool>,
+) -> Result<AFDatabaseWebhook, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseWebhookRow>(
+    r#"
+      UPDATE af_database_webhook
+      SET url = COALESCE($4, url),
+          event_types = COALESCE($5, event_types),
+          enabled = COALESCE($6, enabled),
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND database_id = $2 AND webhook_id = $3
+      RETURNING webhook_id, database_id, url, event_types, enabled, created_by,
+        created_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(webhook_id)
+  .bind(url)
+  .bind(events.map(event_types))
+  .bind(enabled)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| webhook_not_found(database_id, webhook_id))?;
+  Ok(row.into())
+}
+
+/// Deletes a webhook, together with its deliveries. The snapshot of the database is removed along
+/// with its last webhook.
+pub async fn delete_database_webhook(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  database_id: &str,
+  webhook_id: &Uuid,
+) -> Result<(), AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_database_webhook
+      WHERE workspace_id = $1 AND database_id = $2 AND webhook_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(webhook_id)
+  .execute(txn.deref_mut())
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(webhook_not_found(database_id, webhook_id));
+  }
+
+  sqlx::query(
+    r#"
+      DELETE FROM af_database_webhook_snapshot
+      WHERE database_id = $1
+        AND NOT EXISTS (SELECT 1 FROM af_database_webhook WHERE database_id = $1)
+    "#,
+  )
+  .bind(database_id)
+  .execute(txn.deref_mut())
+  .await?;
+  Ok(())
+}
+
+/// Returns the databases which have webhooks, enabled or not, so that their snapshot stays up to
+/// date while their webhooks are disabled.
+pub async fn select_webhook_databases<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+) -> Result<Vec<AFWebhookDatabaseId>, AppError> {
+  let rows = sqlx::query_as::<_, AFWebhookDatabaseId>(
+    r#"
+      SELECT DISTINCT workspace_id, database_id
+      FROM af_database_webhook
+    "#,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Saves the first snapshot of a database. Returns false if the database already has one.
+pub async fn insert_database_webhook_snapshot<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  row_ids: &[String],
+  field_ids: &[String],
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_database_webhook_snapshot (database_id, workspace_id, row_ids, field_ids)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (database_id) DO NOTHING
+    "#,
+  )
+  .bind(database_id)
+  .bind(workspace_id)
+  .bind(row_ids)
+  .bind(field_ids)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+/// Returns the snapshot of a database, locking it until the end of the transaction. Returns none
+/// when the database has no snapshot or when it's being polled by another transaction.
+pub async fn select_database_webhook_snapshot_for_update(
+  txn: &mut Transaction<'_, Postgres>,
+  database_id: &str,
+) -> Result<Option<AFDatabaseWebhookSnapshot>, AppError> {
+  let snapshot = sqlx::query_as::<_, AFDatabaseWebhookSnapshot>(
+    r#"
+      SELECT row_ids, field_ids, polled_at
+      FROM af_database_webhook_snapshot
+      WHERE database_id = $1
+      FOR UPDATE SKIP LOCKED
+    "#,
+  )
+  .bind(database_id)
+  .fetch_optional(txn.deref_mut())
+  .await?;
+  Ok(snapshot)
+}
+
+pub async fn update_database_webhook_snapshot(
+  txn: &mut Transaction<'_, Postgres>,
+  database_id: &str,
+  row_ids: &[String],
+  field_ids: &[String],
+  polled_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_database_webhook_snapshot
+      SET row_ids = $2, field_ids = $3, polled_at = $4
+      WHERE database_id = $1
+    "#,
+  )
+  .bind(database_id)
+  .bind(row_ids)
+  .bind(field_ids)
+  .bind(polled_at)
+  .execute(txn.deref_mut())
+  .await?;
+  Ok(())
+}
+
+/// Queues the delivery of an event to every enabled webhook of the database notified of it.
+pub async fn insert_database_webhook_deliveries<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  database_id: &str,
+  event: AFDatabaseWebhookEvent,
+  payload: &Value,
+) -> Result<u64, AppError> {
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_database_webhook_delivery (webhook_id, event_type, payload)
+      SELECT webhook_id, $2, $3
+      FROM af_database_webhook
+      WHERE database_id = $1 AND enabled AND $2 = ANY(event_types)
+    "#,
+  )
+  .bind(database_id)
+  .bind(event.as_str())
+  .bind(payload)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected())
+}
+
+/// Claims the pending deliveries which are due. The deliveries aren't claimed again before
+/// `lease_secs`, so that they're sent again if the worker stops while sending them.
+pub async fn claim_due_webhook_deliveries<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+  lease_secs: i64,
+) -> Result<Vec<AFWebhookDeliveryJob>, AppError> {
+  let jobs = sqlx::query_as::<_, AFWebhookDeliveryJob>(
+    r#"
+      UPDATE af_database_webhook_delivery d
+      SET next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $3)
+      FROM af_database_webhook w
+      WHERE d.webhook_id = w.webhook_id
+        AND d.delivery_id IN (
+          SELECT delivery_id
+          FROM af_database_webhook_delivery
+          WHERE status = $1 AND next_attempt_at <= CURRENT_TIMESTAMP
+          ORDER BY next_attempt_at ASC
+          LIMIT $2
+          FOR UPDATE SKIP LOCKED
+        )
+      RETURNING d.delivery_id, d.event_type, d.payload, d.attempts, w.url, w.secret
+    "#,
+  )
+  .bind(AFDatabaseWebhookDeliveryStatus::Pending as i16)
+  .bind(limit)
+  .bind(lease_secs as f64)
+  .fetch_all(executor)
+  .await?;
+  Ok(jobs)
+}
+
+/// Records the outcome of an attempt to send a delivery. Deliveries which are still pending are
+/// attempted again after `retry_in_secs`.
+pub async fn update_webhook_delivery_attempt<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  delivery_id: &Uuid,
+  status: AFDatabaseWebhookDeliveryStatus,
+  response_status: Option<i32>,
+  error: Option<&str>,
+  retry_in_secs: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_database_webhook_delivery
+      SET status = $2,
+          attempts = attempts + 1,
+          response_status = $3,
+          error = $4,
+          next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $5),
+          delivered_at = CASE WHEN $2 = $6 THEN CURRENT_TIMESTAMP ELSE delivered_at END
+      WHERE delivery_id = $1
+    "#,
+  )
+  .bind(delivery_id)
+  .bind(status as i16)
+  .bind(response_status)
+  .bind(error)
+  .bind(retry_in_secs as f64)
+  .bind(AFDatabaseWebhookDeliveryStatus::Delivered as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns the latest deliveries of a webhook, most recent first.
+pub async fn select_database_webhook_deliveries<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  webhook_id: &Uuid,
+  limit: i64,
+) -> Result<Vec<AFDatabaseWebhookDelivery>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseWebhookDeliveryRow>(
+    r#"
+      SELECT d.delivery_id, d.event_type, d.payload, d.status, d.attempts, d.response_status,
+        d.error, d.created_at, d.delivered_at
+      FROM af_database_webhook_delivery d
+      JOIN af_database_webhook w USING (webhook_id)
+      WHERE w.workspace_id = $1 AND w.database_id = $2 AND d.webhook_id = $3
+      ORDER BY d.created_at DESC
+      LIMIT $4
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(webhook_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  rows.into_iter().map(TryInto::try_into).collect()
+}
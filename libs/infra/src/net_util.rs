@@ -0,0 +1,236 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error};
+use url::{Host, Url};
+
+/// Parses a comma separated list of hosts, ie. the value of an environment variable.
+pub fn parse_hosts(value: &str) -> Vec<String> {
+  value
+    .split(',')
+    .map(|host| host.trim().to_lowercase())
+    .filter(|host| !host.is_empty())
+    .collect()
+}
+
+/// Returns whether the host is one of the allowed hosts, which may be reached even though they
+/// aren't public, ie. a service of the deployment.
+pub fn is_allowed_host(host: &str, allowed_hosts: &[String]) -> bool {
+  allowed_hosts
+    .iter()
+    .any(|allowed_host| allowed_host.eq_ignore_ascii_case(host))
+}
+
+/// Returns whether the address can be reached on the internet, ie. isn't a loopback, private,
+/// link-local or otherwise reserved address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(ip) => is_public_ipv4(ip),
+    IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+      Some(ip) => is_public_ipv4(ip),
+      None => is_public_ipv6(ip),
+    },
+  }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+  let [first, second, ..] = ip.octets();
+  !(ip.is_unspecified()
+    || ip.is_loopback()
+    || ip.is_private()
+    || ip.is_link_local()
+    || ip.is_broadcast()
+    || ip.is_multicast()
+    || ip.is_documentation()
+    // "this network", RFC 1122
+    || first == 0
+    // shared address space, RFC 6598
+    || (first == 100 && (64..128).contains(&second))
+    // reserved, RFC 1112
+    || first >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+  let first_segment = ip.segments()[0];
+  !(ip.is_unspecified()
+    || ip.is_loopback()
+    || ip.is_multicast()
+    // unique local, RFC 4193
+    || (first_segment & 0xfe00) == 0xfc00
+    // link-local
+    || (first_segment & 0xffc0) == 0xfe80)
+}
+
+/// Resolves the host, failing when one of its addresses isn't public, unless the host is allowed.
+pub async fn resolve_public_host(
+  host: &str,
+  port: u16,
+  allowed_hosts: &[String],
+) -> Result<Vec<SocketAddr>, Error> {
+  let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+  if addrs.is_empty() {
+    bail!("{} can't be resolved", host);
+  }
+  if !is_allowed_host(host, allowed_hosts) {
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+      bail!("{} resolves to the non-public address {}", host, addr.ip());
+    }
+  }
+  Ok(addrs)
+}
+
+/// Checks that the url can only reach public addresses, unless its host is allowed.
+pub async fn check_public_url(url: &Url, allowed_hosts: &[String]) -> Result<(), Error> {
+  let host = url.host().ok_or_else(|| anyhow!("{} has no host", url))?;
+  let ip = match host {
+    Host::Domain(domain) => {
+      let port = url.port_or_known_default().unwrap_or(80);
+      resolve_public_host(domain, port, allowed_hosts).await?;
+      return Ok(());
+    },
+    Host::Ipv4(ip) => IpAddr::V4(ip),
+    Host::Ipv6(ip) => IpAddr::V6(ip),
+  };
+  if !is_public_ip(ip) && !is_allowed_host(&ip.to_string(), allowed_hosts) {
+    bail!("{} is not a public address", ip);
+  }
+  Ok(())
+}
+
+/// A range of addresses, written as an address or in the CIDR notation, ie. `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+  addr: IpAddr,
+  prefix_len: u8,
+}
+
+impl IpRange {
+  pub fn contains(&self, ip: IpAddr) -> bool {
+    let ip = match ip {
+      IpAddr::V6(ip) => ip
+        .to_ipv4_mapped()
+        .map(IpAddr::V4)
+        .unwrap_or(IpAddr::V6(ip)),
+      ip => ip,
+    };
+    match (self.addr, ip) {
+      (IpAddr::V4(addr), IpAddr::V4(ip)) => prefix_matches(
+        u32::from(addr).into(),
+        u32::from(ip).into(),
+        32,
+        self.prefix_len,
+      ),
+      (IpAddr::V6(addr), IpAddr::V6(ip)) => {
+        prefix_matches(u128::from(addr), u128::from(ip), 128, self.prefix_len)
+      },
+      _ => false,
+    }
+  }
+}
+
+fn prefix_matches(addr: u128, ip: u128, bits: u8, prefix_len: u8) -> bool {
+  let shift = bits - prefix_len;
+  shift >= bits || (addr >> shift) == (ip >> shift)
+}
+
+impl FromStr for IpRange {
+  type Err = Error;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let (addr, prefix_len) = match value.split_once('/') {
+      Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+      None => (value, None),
+    };
+    let addr = addr
+      .parse::<IpAddr>()
+      .map_err(|_| anyhow!("{} is not an ip address", value))?;
+    let bits = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix_len = match prefix_len {
+      Some(prefix_len) => prefix_len
+        .parse::<u8>()
+        .ok()
+        .filter(|prefix_len| *prefix_len <= bits)
+        .ok_or_else(|| anyhow!("{} has an invalid prefix length", value))?,
+      None => bits,
+    };
+    Ok(Self { addr, prefix_len })
+  }
+}
+
+/// Parses a comma separated list of address ranges, ie. the value of an environment variable.
+pub fn parse_ip_ranges(value: &str) -> Result<Vec<IpRange>, Error> {
+  value
+    .split(',')
+    .map(str::trim)
+    .filter(|range| !range.is_empty())
+    .map(IpRange::from_str)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn is_public_ip_test() {
+    for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111"] {
+      assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in [
+      "127.0.0.1",
+      "10.1.2.3",
+      "172.16.0.1",
+      "192.168.1.1",
+      "169.254.169.254",
+      "100.64.0.1",
+      "0.0.0.0",
+      "::1",
+      "fe80::1",
+      "fd00::1",
+      "::ffff:127.0.0.1",
+    ] {
+      assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+  }
+
+  #[tokio::test]
+  async fn check_public_url_test() {
+    let allowed_hosts = parse_hosts(" Localhost, ,10.0.0.2");
+    assert_eq!(allowed_hosts, vec!["localhost", "10.0.0.2"]);
+    for url in [
+      "http://127.0.0.1/hook",
+      "http://[::1]:8080/",
+      "http://10.0.0.1/",
+    ] {
+      let url = Url::parse(url).unwrap();
+      assert!(check_public_url(&url, &[]).await.is_err(), "{}", url);
+    }
+    for url in ["http://localhost:9/hook", "http://10.0.0.2/hook"] {
+      let url = Url::parse(url).unwrap();
+      assert!(
+        check_public_url(&url, &allowed_hosts).await.is_ok(),
+        "{}",
+        url
+      );
+    }
+  }
+
+  #[test]
+  fn ip_range_test() {
+    let ranges = parse_ip_ranges("10.0.0.0/8, 127.0.0.1,fd00::/8").unwrap();
+    assert!(ranges[0].contains("10.1.2.3".parse().unwrap()));
+    assert!(!ranges[0].contains("11.0.0.1".parse().unwrap()));
+    assert!(ranges[1].contains("127.0.0.1".parse().unwrap()));
+    assert!(ranges[1].contains("::ffff:127.0.0.1".parse().unwrap()));
+    assert!(!ranges[1].contains("127.0.0.2".parse().unwrap()));
+    assert!(ranges[2].contains("fd12::1".parse().unwrap()));
+    assert!(!ranges[2].contains("10.0.0.1".parse().unwrap()));
+    assert!("0.0.0.0/0"
+      .parse::<IpRange>()
+      .unwrap()
+      .contains("8.8.8.8".parse().unwrap()));
+    assert!(parse_ip_ranges("").unwrap().is_empty());
+    assert!(parse_ip_ranges("10.0.0.0/33").is_err());
+    assert!(parse_ip_ranges("nginx").is_err());
+  }
+}
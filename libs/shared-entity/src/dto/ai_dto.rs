@@ -1,5 +1,9 @@
+use chrono::{DateTime, Utc};
+use infra::validate::validate_not_empty_str;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use uuid::Uuid;
+use validator::Validate;
 
 use crate::dto::chat_dto::ChatMessage;
 pub use appflowy_ai_client::dto::*;
@@ -36,6 +40,14 @@ pub struct CompleteTextParams {
   pub text: String,
   pub completion_type: Option<CompletionType>,
   pub custom_prompt: Option<CustomPrompt>,
+  /// Completes the text with a custom prompt template of the workspace. Can't be combined with
+  /// `completion_type` or `custom_prompt`.
+  #[serde(default)]
+  pub prompt_template_id: Option<Uuid>,
+  /// The page the text comes from, used to fill the `{{page_title}}` and `{{page_content}}`
+  /// variables of a prompt template.
+  #[serde(default)]
+  pub page_id: Option<String>,
 }
 
 impl CompleteTextParams {
@@ -44,8 +56,160 @@ impl CompleteTextParams {
       text,
       completion_type: Some(completion_type),
       custom_prompt: None,
+      prompt_template_id: None,
+      page_id: None,
     }
   }
+
+  pub fn new_with_prompt_template(text: String, prompt_template_id: Uuid) -> Self {
+    Self {
+      text,
+      completion_type: None,
+      custom_prompt: None,
+      prompt_template_id: Some(prompt_template_id),
+      page_id: None,
+    }
+  }
+
+  pub fn with_page_id(mut self, page_id: String) -> Self {
+    self.page_id = Some(page_id);
+    self
+  }
+}
+
+/// A custom prompt of a workspace. The prompts may contain the following variables, which are
+/// replaced when the template is used:
+/// - `{{text}}`: the text to complete, usually the selected text
+/// - `{{page_title}}`: the name of the page the text comes from
+/// - `{{page_content}}`: the plain text content of the page the text comes from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AIPromptTemplate {
+  pub template_id: Uuid,
+  pub name: String,
+  pub description: String,
+  pub system_prompt: String,
+  pub user_prompt: Option<String>,
+  pub created_by: Option<i64>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepeatedAIPromptTemplate {
+  pub items: Vec<AIPromptTemplate>,
+}
+
+#[derive(Clone, Debug, Validate, Serialize, Deserialize)]
+pub struct CreateAIPromptTemplateParams {
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub name: String,
+  #[serde(default)]
+  pub description: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub system_prompt: String,
+  #[serde(default)]
+  pub user_prompt: Option<String>,
+}
+
+/// Fields set to `None` are left unchanged.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UpdateAIPromptTemplateParams {
+  pub name: Option<String>,
+  pub description: Option<String>,
+  pub system_prompt: Option<String>,
+  pub user_prompt: Option<String>,
+}
+
+/// Titles suggested by AI for a page, based on its content.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PageTitleSuggestions {
+  pub titles: Vec<String>,
+}
+
+/// Format of a meeting transcript.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptFormat {
+  /// WebVTT captions, starting with a `WEBVTT` header.
+  Vtt,
+  /// SubRip subtitles, made of numbered cues.
+  Srt,
+  /// One utterance per line, optionally prefixed by the name of the speaker, ie. `Lucas: Hello`.
+  Text,
+}
+
+#[derive(Clone, Debug, Validate, Serialize, Deserialize)]
+pub struct CreateMeetingNotesParams {
+  /// View under which the page of the meeting notes is created.
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub parent_view_id: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub transcript: String,
+  /// Detected from the transcript when not set.
+  #[serde(default)]
+  pub format: Option<TranscriptFormat>,
+  /// Name of the page. Defaults to `Meeting notes` followed by the current date.
+  #[serde(default)]
+  pub name: Option<String>,
+  /// Database into which the action items of the meeting are inserted as rows.
+  #[serde(default)]
+  pub task_database: Option<MeetingTaskDatabase>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeetingTaskDatabase {
+  pub database_id: String,
+  /// Field, by id or name, set to the workspace member assigned to an action item.
+  #[serde(default)]
+  pub assignee_field: Option<String>,
+  /// Field, by id or name, set to the due date of an action item.
+  #[serde(default)]
+  pub due_date_field: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeetingNotes {
+  /// View id of the created page.
+  pub view_id: String,
+  pub summary: String,
+  pub decisions: Vec<String>,
+  pub action_items: Vec<MeetingActionItem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeetingActionItem {
+  pub content: String,
+  /// Name of the person the action item was assigned to in the meeting.
+  pub assignee: Option<String>,
+  /// Workspace member matching the assignee.
+  pub assignee_uid: Option<i64>,
+  /// Due date, formatted as `YYYY-MM-DD`.
+  pub due_date: Option<String>,
+  /// Row of the task database created for the action item.
+  pub row_id: Option<String>,
+}
+
+/// An AI response streamed over the realtime connection instead of an HTTP stream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AIStreamStarted {
+  pub stream_id: String,
+}
+
+/// Chunks of an AI response streamed over the realtime connection, used to catch up with the
+/// chunks missed while disconnected.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AIStreamChunks {
+  pub chunks: Vec<AIStreamChunkData>,
+  /// Whether the response has been fully streamed, in which case no chunk follows `chunks`.
+  pub finished: bool,
+  /// Set when the response failed.
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AIStreamChunkData {
+  pub seq: u64,
+  pub data: String,
 }
 
 #[derive(Debug)]
@@ -1,5 +1,6 @@
 // Data Transfer Objects (DTO)
 
+use chrono_tz::Tz;
 use gotrue_entity::dto::GotrueTokenResponse;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -71,3 +72,49 @@ pub struct DeleteUserQuery {
   pub provider_access_token: Option<String>,
   pub provider_refresh_token: Option<String>,
 }
+
+/// Notification settings of a user, which apply to the reminders of the dates the user set in
+/// database rows and documents.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct AFUserNotificationSettings {
+  /// IANA time zone, ie. `Europe/Paris`, in which the dates without time are reminded.
+  pub timezone: String,
+  pub reminder_enabled: bool,
+  /// Reminders which couldn't be delivered to an app of the user are sent by email.
+  pub reminder_email_enabled: bool,
+}
+
+impl Default for AFUserNotificationSettings {
+  fn default() -> Self {
+    Self {
+      timezone: "UTC".to_string(),
+      reminder_enabled: true,
+      reminder_email_enabled: true,
+    }
+  }
+}
+
+impl AFUserNotificationSettings {
+  /// Returns the time zone of the user, UTC when it's unknown.
+  pub fn tz(&self) -> Tz {
+    self.timezone.parse::<Tz>().unwrap_or(Tz::UTC)
+  }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct UpdateUserNotificationSettingsParams {
+  pub timezone: Option<String>,
+  pub reminder_enabled: Option<bool>,
+  pub reminder_email_enabled: Option<bool>,
+}
+
+impl UpdateUserNotificationSettingsParams {
+  pub fn validate(&self) -> Result<(), String> {
+    if let Some(timezone) = &self.timezone {
+      timezone
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone: {}", timezone))?;
+    }
+    Ok(())
+  }
+}
@@ -122,6 +122,54 @@ pub struct WorkspaceUsageAndLimit {
   pub ai_responses_unlimited: bool,
 }
 
+/// AI usage of a workspace in the current day and month, together with its quotas. Quotas set to
+/// `None` are unlimited.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkspaceAIUsageAndLimit {
+  pub plan: SubscriptionPlan,
+  pub daily_tokens: i64,
+  pub daily_token_limit: Option<i64>,
+  pub monthly_tokens: i64,
+  pub monthly_token_limit: Option<i64>,
+  pub daily_requests: i64,
+  pub daily_request_limit: Option<i64>,
+  pub monthly_requests: i64,
+  pub monthly_request_limit: Option<i64>,
+}
+
+impl WorkspaceAIUsageAndLimit {
+  /// Returns a description of the first quota which has been used up, if any.
+  pub fn exceeded_quota(&self) -> Option<String> {
+    let quotas = [
+      ("daily token", self.daily_tokens, self.daily_token_limit),
+      (
+        "monthly token",
+        self.monthly_tokens,
+        self.monthly_token_limit,
+      ),
+      (
+        "daily request",
+        self.daily_requests,
+        self.daily_request_limit,
+      ),
+      (
+        "monthly request",
+        self.monthly_requests,
+        self.monthly_request_limit,
+      ),
+    ];
+    quotas
+      .into_iter()
+      .find_map(|(name, used, limit)| match limit {
+        Some(limit) if used >= limit => Some(format!(
+          "AI {} quota of the workspace exceeded: {}/{}",
+          name, used, limit
+        )),
+        _ => None,
+      })
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SubscriptionCancelRequest {
   pub workspace_id: String,
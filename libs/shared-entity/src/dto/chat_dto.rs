@@ -94,6 +94,170 @@ impl ChatMessageMetadata {
   }
 }
 
+/// Source of a [ChatMessageMetadata] representing a [ChatCitation].
+pub const CHAT_CITATION_SOURCE: &str = "appflowy_cloud_citation";
+
+/// A fragment of a workspace document, retrieved from the workspace embeddings as a context of a
+/// question. Citations are passed to the AI service together with the question and stored in the
+/// metadata of the answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCitation {
+  pub object_id: String,
+  pub fragment_id: String,
+  pub content: String,
+  /// Cosine distance between the question and the fragment. Lower is better.
+  pub score: f64,
+}
+
+impl From<ChatCitation> for ChatMessageMetadata {
+  fn from(citation: ChatCitation) -> Self {
+    Self {
+      data: ChatRAGData::from_text(citation.content),
+      id: citation.object_id.clone(),
+      name: citation.object_id,
+      source: CHAT_CITATION_SOURCE.to_string(),
+      extra: Some(serde_json::json!({
+        "fragment_id": citation.fragment_id,
+        "score": citation.score,
+      })),
+    }
+  }
+}
+
+impl ChatMessageMetadata {
+  /// Returns the [ChatCitation] if this metadata was created from one.
+  pub fn citation(&self) -> Option<ChatCitation> {
+    if self.source != CHAT_CITATION_SOURCE {
+      return None;
+    }
+    let extra = self.extra.as_ref()?;
+    Some(ChatCitation {
+      object_id: self.id.clone(),
+      fragment_id: extra.get("fragment_id")?.as_str()?.to_string(),
+      content: self.data.content.clone(),
+      score: extra.get("score")?.as_f64()?,
+    })
+  }
+}
+
+/// Source of a [ChatMessageMetadata] referencing a database view. A question mentioning a
+/// database view is answered from the rows of the database instead of the workspace documents.
+pub const CHAT_DATABASE_SOURCE: &str = "appflowy_cloud_database";
+
+/// A database view mentioned in a question, ie. `@Tasks`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatDatabaseSource {
+  pub database_id: String,
+  pub view_id: String,
+  /// Name of the view, used to refer to the database in the answer.
+  pub name: String,
+}
+
+impl From<ChatDatabaseSource> for ChatMessageMetadata {
+  fn from(source: ChatDatabaseSource) -> Self {
+    Self {
+      data: ChatRAGData::from_text(String::new()),
+      id: source.view_id,
+      name: source.name,
+      source: CHAT_DATABASE_SOURCE.to_string(),
+      extra: Some(serde_json::json!({ "database_id": source.database_id })),
+    }
+  }
+}
+
+/// The rows of a database matching a question, stored in the metadata of the answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatDatabaseAnswer {
+  pub source: ChatDatabaseSource,
+  /// The filters the question was translated into.
+  pub query: ChatDatabaseQuery,
+  /// Ids of all the matching rows, in the order of the database.
+  pub row_ids: Vec<String>,
+}
+
+impl From<ChatDatabaseAnswer> for ChatMessageMetadata {
+  fn from(answer: ChatDatabaseAnswer) -> Self {
+    let database_id = answer.source.database_id.clone();
+    let mut metadata = ChatMessageMetadata::from(answer.source);
+    metadata.extra = Some(serde_json::json!({
+      "database_id": database_id,
+      "query": answer.query,
+      "row_ids": answer.row_ids,
+    }));
+    metadata
+  }
+}
+
+impl ChatMessageMetadata {
+  /// Returns the [ChatDatabaseSource] if this metadata references a database view.
+  pub fn database_source(&self) -> Option<ChatDatabaseSource> {
+    if self.source != CHAT_DATABASE_SOURCE {
+      return None;
+    }
+    let database_id = self.extra.as_ref()?.get("database_id")?.as_str()?;
+    Some(ChatDatabaseSource {
+      database_id: database_id.to_string(),
+      view_id: self.id.clone(),
+      name: self.name.clone(),
+    })
+  }
+
+  /// Returns the [ChatDatabaseAnswer] if this metadata was created from one.
+  pub fn database_answer(&self) -> Option<ChatDatabaseAnswer> {
+    let source = self.database_source()?;
+    let extra = self.extra.as_ref()?;
+    Some(ChatDatabaseAnswer {
+      source,
+      query: serde_json::from_value(extra.get("query")?.clone()).ok()?,
+      row_ids: serde_json::from_value(extra.get("row_ids")?.clone()).ok()?,
+    })
+  }
+}
+
+/// Filters over the rows of a database, translated from a question.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatDatabaseQuery {
+  /// Whether a row must match all the filters or any of them.
+  #[serde(default, rename = "match")]
+  pub match_mode: ChatDatabaseFilterMatch,
+  #[serde(default)]
+  pub filters: Vec<ChatDatabaseFilter>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatDatabaseFilterMatch {
+  #[default]
+  All,
+  Any,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatDatabaseFilter {
+  /// Name of the field the filter applies to.
+  pub field: String,
+  pub condition: ChatDatabaseFilterCondition,
+  /// Value the cells are compared with. Dates are written as `YYYY-MM-DD`. Ignored by
+  /// [ChatDatabaseFilterCondition::IsEmpty] and [ChatDatabaseFilterCondition::IsNotEmpty].
+  #[serde(default)]
+  pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatDatabaseFilterCondition {
+  Is,
+  IsNot,
+  Contains,
+  DoesNotContain,
+  IsEmpty,
+  IsNotEmpty,
+  GreaterThan,
+  LessThan,
+  Before,
+  After,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMetadataDescription {
   pub id: String,
@@ -293,6 +457,25 @@ pub struct ChatMessage {
   pub created_at: DateTime<Utc>,
   pub meta_data: serde_json::Value,
   pub reply_message_id: Option<i64>,
+  /// The message this message follows in the conversation. Regenerated answers and edited
+  /// questions share the same parent as the messages they replace.
+  #[serde(default)]
+  pub parent_message_id: Option<i64>,
+}
+
+/// The branches of a conversation starting at the same message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessageBranches {
+  /// The sibling messages, including the requested one, oldest first.
+  pub messages: Vec<ChatMessage>,
+  /// The sibling which is part of the active branch of the chat, if any.
+  pub active_message_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchChatBranchParams {
+  /// The message to select. The active branch continues with its most recent descendants.
+  pub message_id: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -369,3 +552,72 @@ pub struct CreateAnswerMessageParams {
 
   pub question_message_id: i64,
 }
+
+/// Permissions of a participant of a shared chat. Roles are ordered, each role has all the
+/// permissions of the roles below it.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize_repr, Deserialize_repr,
+)]
+#[repr(u8)]
+pub enum ChatParticipantRole {
+  /// Can read the messages of the chat.
+  Viewer = 0,
+  /// Can also ask questions and receive answers.
+  #[default]
+  Member = 1,
+  /// Can also manage the participants of the chat.
+  Owner = 2,
+}
+
+impl From<i16> for ChatParticipantRole {
+  fn from(value: i16) -> Self {
+    match value {
+      0 => ChatParticipantRole::Viewer,
+      2 => ChatParticipantRole::Owner,
+      _ => ChatParticipantRole::Member,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatParticipant {
+  pub uid: i64,
+  pub name: String,
+  pub email: String,
+  pub role: ChatParticipantRole,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatedChatParticipant {
+  pub items: Vec<ChatParticipant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatParticipantParams {
+  pub uid: i64,
+  #[serde(default)]
+  pub role: ChatParticipantRole,
+}
+
+/// Adds participants to a chat, or updates the roles of existing ones. The participants must be
+/// members of the workspace of the chat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsertChatParticipantsParams {
+  pub participants: Vec<ChatParticipantParams>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveChatParticipantsParams {
+  pub uids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct ExportChatParams {
+  /// The view under which the exported document page is created.
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub parent_view_id: String,
+  /// Name of the new page. Defaults to the name of the chat.
+  #[serde(default)]
+  pub name: Option<String>,
+}
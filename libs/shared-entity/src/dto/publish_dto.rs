@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use super::workspace_dto::{ViewIcon, ViewLayout};
+use super::workspace_dto::{AFDatabaseForm, ViewIcon, ViewLayout};
 
 /// Copied from AppFlowy-IO/AppFlowy/frontend/rust-lib/flowy-folder-pub/src/entities.rs
 /// TODO(zack): make AppFlowy use from this crate instead
@@ -34,6 +34,14 @@ pub struct PublishViewInfo {
   pub child_views: Option<Vec<PublishViewInfo>>,
 }
 
+/// Metadata of a published database form, from which visitors render the form.
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
+pub struct PublishDatabaseFormMetaData {
+  #[serde(flatten)]
+  pub meta: PublishViewMetaData,
+  pub form: AFDatabaseForm,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PublishDatabasePayload {
   pub meta: PublishViewMeta,
@@ -37,6 +37,9 @@ pub struct SearchDocumentResponseItem {
   pub created_by: String,
   /// Date when the document was created.
   pub created_at: DateTime<Utc>,
+  /// AI generated summary of the whole document, if any.
+  #[serde(default)]
+  pub summary: Option<String>,
 }
 
 /// Type of the document content to be presented in the search results.
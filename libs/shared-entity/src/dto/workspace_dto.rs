@@ -1,4 +1,5 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use collab_entity::{CollabType, EncodedCollab};
 use database_entity::dto::{AFRole, AFWebUser, AFWorkspaceInvitationStatus, PublishInfo};
 use serde::{Deserialize, Serialize};
@@ -254,6 +255,9 @@ pub struct FolderView {
   pub last_edited_time: DateTime<Utc>,
   /// contains fields like `is_space`, and font information
   pub extra: Option<serde_json::Value>,
+  /// AI generated summary of the content of the view, only available for documents
+  #[serde(default)]
+  pub summary: Option<String>,
   pub children: Vec<FolderView>,
 }
 
@@ -270,6 +274,9 @@ pub struct FolderViewMinimal {
 pub struct PublishInfoView {
   pub view: FolderViewMinimal,
   pub info: PublishInfo,
+  /// AI generated summary of the published view, only available for documents
+  #[serde(default)]
+  pub summary: Option<String>,
 }
 
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
@@ -390,6 +397,33 @@ pub struct AFDatabaseRowDetail {
   pub cells: HashMap<String, HashMap<String, serde_json::Value>>,
 }
 
+/// Data of a relation cell: the related rows, along with the value of their primary field.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFRelatedDatabaseRow {
+  pub row_id: String,
+  /// Missing when the related row couldn't be loaded.
+  pub primary_value: Option<String>,
+}
+
+/// Data of a checklist cell. When writing, a list of item names is accepted as well.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AFChecklistCell {
+  pub items: Vec<AFChecklistItem>,
+  /// Ratio of checked items, between 0 and 1.
+  #[serde(default)]
+  pub percentage: f64,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFChecklistItem {
+  /// Generated for new items.
+  #[serde(default)]
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub checked: bool,
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AFDatabaseField {
   pub id: String,
@@ -405,3 +439,712 @@ pub struct AFInsertDatabaseField {
   pub field_type: i64,                             // FieldType ID
   pub type_option_data: Option<serde_json::Value>, // TypeOptionData
 }
+
+/// Adds a rich text field whose cells are filled by the AI. The prompt may reference other fields
+/// of the row by their name, e.g. `Summarize {{Description}}`.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFInsertDatabaseAIField {
+  pub name: String,
+  pub prompt: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFUpdateDatabaseAIField {
+  pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseAIField {
+  pub field_id: String,
+  pub prompt: String,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Eq, PartialEq, Copy, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
+#[repr(i16)]
+pub enum AFDatabaseAICellStatus {
+  Pending = 0,
+  Completed = 1,
+  /// The workspace has used up its AI quota. The cell is computed again once it's renewed.
+  RateLimited = 2,
+  Failed = 3,
+}
+
+impl From<i16> for AFDatabaseAICellStatus {
+  fn from(value: i16) -> Self {
+    match value {
+      0 => AFDatabaseAICellStatus::Pending,
+      1 => AFDatabaseAICellStatus::Completed,
+      2 => AFDatabaseAICellStatus::RateLimited,
+      _ => AFDatabaseAICellStatus::Failed,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseAICell {
+  pub row_id: String,
+  pub status: AFDatabaseAICellStatus,
+  pub error: Option<String>,
+  pub attempts: i32,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// How the cells of a computed field are computed from the row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AFDatabaseComputation {
+  /// Expression over the other fields of the row, referenced by their name, e.g.
+  /// `{{Price}} * {{Quantity}}`. The field is a rich text field.
+  Formula { expression: String },
+  /// Aggregation of the rows related by a relation field of the row. The field is a number field.
+  Rollup {
+    /// Id or name of the relation field.
+    relation_field: String,
+    /// Id or name of the field of the related database which is aggregated. Not needed to count
+    /// the related rows.
+    #[serde(default)]
+    target_field: Option<String>,
+    aggregation: AFRollupAggregation,
+  },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AFRollupAggregation {
+  Count,
+  Sum,
+  Average,
+  Min,
+  Max,
+  /// Percentage of the related rows whose checkbox target field is checked.
+  PercentChecked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFInsertDatabaseComputedField {
+  pub name: String,
+  pub computation: AFDatabaseComputation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFUpdateDatabaseComputedField {
+  pub computation: AFDatabaseComputation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseComputedField {
+  pub field_id: String,
+  /// Fields of rollups are referenced by their id.
+  pub computation: AFDatabaseComputation,
+  /// Why the cells of the field couldn't be computed the last time, ie. because the formula
+  /// references a field which was renamed or removed.
+  pub error: Option<String>,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseFormFieldParams {
+  /// Id or name of the field.
+  pub field: String,
+  #[serde(default)]
+  pub required: bool,
+}
+
+/// Publishes a form through which anyone can add rows to the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFPublishDatabaseForm {
+  /// Publishing again with the same view id replaces the form.
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub title: String,
+  #[serde(default)]
+  pub description: String,
+  /// Fields shown in the form, in their order.
+  pub fields: Vec<AFDatabaseFormFieldParams>,
+  #[serde(default)]
+  pub captcha_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseFormField {
+  pub field: AFDatabaseField,
+  pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseForm {
+  pub view_id: Uuid,
+  pub database_id: String,
+  pub title: String,
+  pub description: String,
+  pub fields: Vec<AFDatabaseFormField>,
+  pub captcha_required: bool,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFSubmitDatabaseForm {
+  /// Values of the fields of the form, keyed by field id or name.
+  pub cells: HashMap<String, Value>,
+  /// Token obtained from the CAPTCHA widget, when the form requires one.
+  #[serde(default)]
+  pub captcha_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseFormSubmission {
+  pub row_id: String,
+}
+
+/// Query over the rows of a database. Rows are returned in the order of the view, unless sorted.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct QueryDatabaseRowsParams {
+  /// View whose row order is used, the inline view of the database when omitted.
+  pub view_id: Option<String>,
+  /// Applies the filters and sorts saved in the view, in addition to the ones of the query.
+  #[serde(default)]
+  pub with_view_settings: bool,
+  /// Rows must match all of the filters.
+  #[serde(default)]
+  pub filters: Vec<AFDatabaseRowFilter>,
+  #[serde(default)]
+  pub sorts: Vec<AFDatabaseRowSort>,
+  /// Cursor returned with the previous page.
+  pub cursor: Option<String>,
+  pub limit: Option<u32>,
+}
+
+/// Filter over the cells of a row. Fields are referenced by their id or their name.
+///
+/// Example: `{ "type": "number", "field": "Price", "gte": 10, "lt": 20 }`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AFDatabaseRowFilter {
+  And {
+    filters: Vec<AFDatabaseRowFilter>,
+  },
+  Or {
+    filters: Vec<AFDatabaseRowFilter>,
+  },
+  Not {
+    filter: Box<AFDatabaseRowFilter>,
+  },
+  /// Matches cells without data.
+  Empty {
+    field: String,
+  },
+  /// Matches the text of a cell of any type, ignoring the case.
+  Text {
+    field: String,
+    condition: AFTextFilterCondition,
+    value: String,
+  },
+  Number {
+    field: String,
+    gt: Option<f64>,
+    gte: Option<f64>,
+    lt: Option<f64>,
+    lte: Option<f64>,
+  },
+  /// Matches the date of date, created time and last edited time cells, as timestamps in seconds.
+  Date {
+    field: String,
+    gt: Option<i64>,
+    gte: Option<i64>,
+    lt: Option<i64>,
+    lte: Option<i64>,
+  },
+  /// Matches the options of select cells, by their id or their name.
+  Select {
+    field: String,
+    condition: AFSelectFilterCondition,
+    options: Vec<String>,
+  },
+  Checkbox {
+    field: String,
+    checked: bool,
+  },
+  /// Matches relation cells related to any of the rows.
+  Relation {
+    field: String,
+    row_ids: Vec<String>,
+  },
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AFTextFilterCondition {
+  Is,
+  Contains,
+  StartsWith,
+  EndsWith,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AFSelectFilterCondition {
+  /// The cell has any of the options.
+  Any,
+  /// The cell has all of the options.
+  All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseRowSort {
+  pub field: String,
+  #[serde(default)]
+  pub descending: bool,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowQueryResult {
+  pub rows: Vec<AFDatabaseRowDetail>,
+  /// Number of rows matching the query, across all pages.
+  pub total: usize,
+  /// Cursor of the next page, if any.
+  pub next_cursor: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct QueryDatabaseRowHistoryParams {
+  /// Returns the changes made before this time, to get the next page of the history.
+  pub before: Option<DateTime<Utc>>,
+  pub limit: Option<u32>,
+}
+
+/// A change of a cell of a row. Consecutive changes of a cell by the same user are merged into a
+/// single change, from the value before the first one to the value after the last one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowChange {
+  pub field_id: String,
+  /// Name of the field, none when the field has been deleted since.
+  pub field_name: Option<String>,
+  pub field_type: Option<String>,
+  /// User who made the change, none when the user has been deleted since.
+  pub uid: Option<i64>,
+  pub user_name: Option<String>,
+  /// Values of the cell before and after the change, in the same format as the data of the cells
+  /// of [AFDatabaseRowDetail]. Null when the cell was empty.
+  pub old_value: serde_json::Value,
+  pub new_value: serde_json::Value,
+  /// Time of the first change.
+  pub created_at: DateTime<Utc>,
+  /// Time of the last change.
+  pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AFDatabaseWebhookEvent {
+  #[serde(rename = "row.created")]
+  RowCreated,
+  #[serde(rename = "row.updated")]
+  RowUpdated,
+  #[serde(rename = "row.deleted")]
+  RowDeleted,
+  #[serde(rename = "field.added")]
+  FieldAdded,
+}
+
+impl AFDatabaseWebhookEvent {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AFDatabaseWebhookEvent::RowCreated => "row.created",
+      AFDatabaseWebhookEvent::RowUpdated => "row.updated",
+      AFDatabaseWebhookEvent::RowDeleted => "row.deleted",
+      AFDatabaseWebhookEvent::FieldAdded => "field.added",
+    }
+  }
+}
+
+impl std::str::FromStr for AFDatabaseWebhookEvent {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "row.created" => Ok(AFDatabaseWebhookEvent::RowCreated),
+      "row.updated" => Ok(AFDatabaseWebhookEvent::RowUpdated),
+      "row.deleted" => Ok(AFDatabaseWebhookEvent::RowDeleted),
+      "field.added" => Ok(AFDatabaseWebhookEvent::FieldAdded),
+      _ => Err(format!("unknown webhook event: {}", s)),
+    }
+  }
+}
+
+/// Registers a webhook notified of the changes of a database. Deliveries are signed with the
+/// secret, which is generated when omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFInsertDatabaseWebhook {
+  pub url: String,
+  pub secret: Option<String>,
+  pub events: Vec<AFDatabaseWebhookEvent>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFUpdateDatabaseWebhook {
+  pub url: Option<String>,
+  pub events: Option<Vec<AFDatabaseWebhookEvent>>,
+  pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseWebhook {
+  pub webhook_id: Uuid,
+  pub database_id: String,
+  pub url: String,
+  /// Secret used to sign the deliveries. Only returned when the webhook is created.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub secret: Option<String>,
+  pub events: Vec<AFDatabaseWebhookEvent>,
+  pub enabled: bool,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// Body of the requests sent to webhooks. The body is signed with the secret of the webhook, using
+/// HMAC-SHA256, in the `X-AppFlowy-Signature` header: `sha256=<hex digest>`.
+///
+/// Events are delivered at least once: receivers may use `event_id` to skip duplicates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseWebhookPayload {
+  pub event_id: Uuid,
+  pub event: AFDatabaseWebhookEvent,
+  pub workspace_id: String,
+  pub database_id: String,
+  pub created_at: DateTime<Utc>,
+  /// Row of the row events. The row of a deleted row event has the cells the row had when it was
+  /// deleted, if any.
+  pub row: Option<AFDatabaseRowDetail>,
+  /// Field of the field events.
+  pub field: Option<AFDatabaseField>,
+}
+
+#[derive(Eq, PartialEq, Copy, Debug, Clone, Serialize_repr, Deserialize_repr)]
+#[repr(i16)]
+pub enum AFDatabaseWebhookDeliveryStatus {
+  Pending = 0,
+  Delivered = 1,
+  /// The delivery failed after all of its attempts.
+  Failed = 2,
+}
+
+impl From<i16> for AFDatabaseWebhookDeliveryStatus {
+  fn from(value: i16) -> Self {
+    match value {
+      0 => AFDatabaseWebhookDeliveryStatus::Pending,
+      1 => AFDatabaseWebhookDeliveryStatus::Delivered,
+      _ => AFDatabaseWebhookDeliveryStatus::Failed,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseWebhookDelivery {
+  pub delivery_id: Uuid,
+  pub event: AFDatabaseWebhookEvent,
+  pub payload: AFDatabaseWebhookPayload,
+  pub status: AFDatabaseWebhookDeliveryStatus,
+  pub attempts: i32,
+  /// HTTP status of the response to the last attempt.
+  pub response_status: Option<i32>,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Appends the rows of a CSV, whose first record is the header, to a database.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFImportDatabaseCsv {
+  pub csv: String,
+  /// Field of the columns, by column header. Fields are referenced by their id or their name.
+  /// Other columns go to the field with the same name as their header.
+  #[serde(default)]
+  pub column_fields: HashMap<String, String>,
+  /// Creates a field for each column without a field. Such columns are skipped otherwise.
+  #[serde(default)]
+  pub create_fields: bool,
+  /// Type of the fields created for the columns, by column header. Rich text by default.
+  #[serde(default)]
+  pub field_types: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFImportDatabaseCsvResult {
+  /// Rows appended to the database, in the order of the CSV.
+  pub row_ids: Vec<String>,
+  pub created_field_ids: Vec<String>,
+  /// Records which were not imported.
+  pub errors: Vec<AFImportDatabaseCsvError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFImportDatabaseCsvError {
+  /// Line of the record in the CSV, starting from 1.
+  pub line: u64,
+  pub column: Option<String>,
+  pub error: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ExportDatabaseCsvParams {
+  /// View whose visible fields and row order are exported, the inline view when omitted.
+  pub view_id: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AFRowScheduleFrequency {
+  Daily,
+  Weekly,
+  Monthly,
+}
+
+/// Recurrence of a row schedule. The times of the runs are evaluated in the timezone of the rule,
+/// so that a run stays at the same local time across daylight saving changes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFRowScheduleRule {
+  pub frequency: AFRowScheduleFrequency,
+  /// Runs every `interval` days, weeks or months, counted from `start_date`.
+  #[serde(default = "default_row_schedule_interval")]
+  pub interval: u32,
+  /// Days of the week of a weekly rule, from 0 (Monday) to 6 (Sunday). The day of the week of
+  /// `start_date` when empty.
+  #[serde(default)]
+  pub weekdays: Vec<u32>,
+  /// Day of the month of a monthly rule, the day of `start_date` when omitted. Months which are
+  /// too short run on their last day.
+  pub month_day: Option<u32>,
+  pub hour: u32,
+  #[serde(default)]
+  pub minute: u32,
+  /// IANA name of the timezone, such as `Europe/Paris`.
+  #[serde(default = "default_row_schedule_timezone")]
+  pub timezone: String,
+  pub start_date: NaiveDate,
+  /// Last day of the schedule, if any.
+  pub end_date: Option<NaiveDate>,
+}
+
+fn default_row_schedule_interval() -> u32 {
+  1
+}
+
+fn default_row_schedule_timezone() -> String {
+  "UTC".to_string()
+}
+
+/// Rules are evaluated day by day, and a rule runs at least once a year and a month.
+const ROW_SCHEDULE_LOOKAHEAD_DAYS: usize = 800;
+
+impl AFRowScheduleRule {
+  pub fn validate(&self) -> Result<(), String> {
+    let max_interval = match self.frequency {
+      AFRowScheduleFrequency::Daily => 365,
+      AFRowScheduleFrequency::Weekly => 52,
+      AFRowScheduleFrequency::Monthly => 12,
+    };
+    if self.interval == 0 || self.interval > max_interval {
+      return Err(format!(
+        "The interval of a {:?} rule must be between 1 and {}",
+        self.frequency, max_interval
+      ));
+    }
+    if self.hour > 23 || self.minute > 59 {
+      return Err(format!("Invalid time: {}:{}", self.hour, self.minute));
+    }
+    if let Some(weekday) = self.weekdays.iter().find(|weekday| **weekday > 6) {
+      return Err(format!("Invalid day of the week: {}", weekday));
+    }
+    if let Some(month_day) = self.month_day.filter(|day| !(1..=31).contains(day)) {
+      return Err(format!("Invalid day of the month: {}", month_day));
+    }
+    if self
+      .end_date
+      .is_some_and(|end_date| end_date < self.start_date)
+    {
+      return Err("The end date of a rule can't be before its start date".to_string());
+    }
+    self.tz().map(|_| ())
+  }
+
+  pub fn tz(&self) -> Result<Tz, String> {
+    self
+      .timezone
+      .parse::<Tz>()
+      .map_err(|_| format!("Unknown timezone: {}", self.timezone))
+  }
+
+  /// Returns the first run of the rule strictly after `after`, none when the rule has ended or is
+  /// invalid.
+  pub fn next_occurrence(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if self.interval == 0 {
+      return None;
+    }
+    let tz = self.tz().ok()?;
+    let first_day = self.start_date.max(after.with_timezone(&tz).date_naive());
+    first_day
+      .iter_days()
+      .take(ROW_SCHEDULE_LOOKAHEAD_DAYS)
+      .take_while(|day| self.end_date.map_or(true, |end_date| *day <= end_date))
+      .filter(|day| self.runs_on(*day))
+      .filter_map(|day| self.time_on(&tz, day))
+      .find(|occurrence| *occurrence > after)
+  }
+
+  fn runs_on(&self, day: NaiveDate) -> bool {
+    let interval = self.interval as i64;
+    match self.frequency {
+      AFRowScheduleFrequency::Daily => (day - self.start_date).num_days() % interval == 0,
+      AFRowScheduleFrequency::Weekly => {
+        let weekday = day.weekday().num_days_from_monday();
+        let on_weekday = if self.weekdays.is_empty() {
+          weekday == self.start_date.weekday().num_days_from_monday()
+        } else {
+          self.weekdays.contains(&weekday)
+        };
+        let weeks = (week_start(day) - week_start(self.start_date)).num_days() / 7;
+        on_weekday && weeks % interval == 0
+      },
+      AFRowScheduleFrequency::Monthly => {
+        let months = (day.year() - self.start_date.year()) as i64 * 12 + day.month() as i64
+          - self.start_date.month() as i64;
+        let month_day = self.month_day.unwrap_or_else(|| self.start_date.day());
+        months % interval == 0 && day.day() == month_day.min(last_day_of_month(day))
+      },
+    }
+  }
+
+  /// Returns the time of the run on the day. A time skipped by a daylight saving change runs an
+  /// hour later.
+  fn time_on(&self, tz: &Tz, day: NaiveDate) -> Option<DateTime<Utc>> {
+    let time = day.and_hms_opt(self.hour, self.minute, 0)?;
+    tz.from_local_datetime(&time)
+      .earliest()
+      .or_else(|| {
+        tz.from_local_datetime(&(time + chrono::Duration::hours(1)))
+          .earliest()
+      })
+      .map(|time| time.with_timezone(&Utc))
+  }
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+  day - chrono::Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+fn last_day_of_month(day: NaiveDate) -> u32 {
+  let (year, month) = if day.month() == 12 {
+    (day.year() + 1, 1)
+  } else {
+    (day.year(), day.month() + 1)
+  };
+  NaiveDate::from_ymd_opt(year, month, 1)
+    .and_then(|first_day| first_day.pred_opt())
+    .map_or(28, |last_day| last_day.day())
+}
+
+/// What happens to the runs of a schedule which were missed, ie. while appflowy-worker was down.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[repr(i16)]
+pub enum AFMissedRunPolicy {
+  /// Missed runs are skipped, unless they were missed by a few minutes only.
+  #[default]
+  Skip = 0,
+  /// Only the latest missed run is made.
+  RunOnce = 1,
+  /// Every missed run is made, up to a limit.
+  RunAll = 2,
+}
+
+impl From<i16> for AFMissedRunPolicy {
+  fn from(value: i16) -> Self {
+    match value {
+      1 => AFMissedRunPolicy::RunOnce,
+      2 => AFMissedRunPolicy::RunAll,
+      _ => AFMissedRunPolicy::Skip,
+    }
+  }
+}
+
+/// Creates a row from a template each time the rule of the schedule runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFInsertDatabaseRowSchedule {
+  pub name: String,
+  pub rule: AFRowScheduleRule,
+  /// Cells of the created rows, in the same format as when inserting a row.
+  #[serde(default)]
+  pub cells: HashMap<String, Value>,
+  /// Date cells set relative to the time of each run, as a number of days, by field id or name.
+  /// For instance, `{"Due date": 3}` sets the due date of the rows three days after their run.
+  #[serde(default)]
+  pub relative_dates: HashMap<String, i64>,
+  #[serde(default)]
+  pub missed_run_policy: AFMissedRunPolicy,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFUpdateDatabaseRowSchedule {
+  pub name: Option<String>,
+  /// Runs of the new rule start from the time of the update.
+  pub rule: Option<AFRowScheduleRule>,
+  pub cells: Option<HashMap<String, Value>>,
+  pub relative_dates: Option<HashMap<String, i64>>,
+  pub missed_run_policy: Option<AFMissedRunPolicy>,
+  pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowSchedule {
+  pub schedule_id: Uuid,
+  pub database_id: String,
+  pub name: String,
+  pub rule: AFRowScheduleRule,
+  pub cells: HashMap<String, Value>,
+  pub relative_dates: HashMap<String, i64>,
+  pub missed_run_policy: AFMissedRunPolicy,
+  pub enabled: bool,
+  /// Time of the next run, none when the schedule has ended.
+  pub next_run_at: Option<DateTime<Utc>>,
+  pub last_run_at: Option<DateTime<Utc>>,
+  /// Rows are created on behalf of the creator of the schedule.
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Eq, PartialEq, Copy, Debug, Clone, Serialize_repr, Deserialize_repr)]
+#[repr(i16)]
+pub enum AFRowScheduleRunStatus {
+  Pending = 0,
+  Created = 1,
+  /// The row couldn't be created after all of its attempts.
+  Failed = 2,
+}
+
+impl From<i16> for AFRowScheduleRunStatus {
+  fn from(value: i16) -> Self {
+    match value {
+      0 => AFRowScheduleRunStatus::Pending,
+      1 => AFRowScheduleRunStatus::Created,
+      _ => AFRowScheduleRunStatus::Failed,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowScheduleRun {
+  pub run_id: Uuid,
+  /// Time the run was scheduled for, from which the relative dates of the row are set.
+  pub scheduled_for: DateTime<Utc>,
+  pub row_id: String,
+  pub status: AFRowScheduleRunStatus,
+  pub attempts: i32,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub completed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use collab_document::blocks::DocumentData;
+use serde_json::{json, Value};
+
+use crate::document::parser::{JsonToDocumentParser, SerdeBlock};
+use crate::document::{block, text_block};
+
+/// Maximum number of characters of a cited fragment displayed in the document.
+const CITATION_PREVIEW_LEN: usize = 200;
+
+/// A source used by the AI to answer a question.
+#[derive(Debug, Clone)]
+pub struct ChatDocumentCitation {
+  /// Id of the cited view.
+  pub object_id: String,
+  pub content: String,
+}
+
+/// Builds a document out of a conversation with the AI.
+///
+/// Each question is rendered as a heading, followed by the names of the files or pages attached
+/// to it. Answers are rendered as paragraphs, followed by a collapsed list of the pages they cite.
+#[derive(Debug, Default)]
+pub struct ChatDocumentBuilder {
+  children: Vec<SerdeBlock>,
+}
+
+impl ChatDocumentBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a callout describing where the document comes from, e.g. the name of the chat.
+  pub fn with_description(mut self, description: &str) -> Self {
+    self
+      .children
+      .push(text_block("callout", description, [("icon", json!("💬"))]));
+    self
+  }
+
+  pub fn with_question(mut self, content: &str, attachments: &[String]) -> Self {
+    if !self.children.is_empty() {
+      self.children.push(block("divider", HashMap::new(), vec![]));
+    }
+    self
+      .children
+      .push(text_block("heading", content.trim(), [("level", json!(3))]));
+    if !attachments.is_empty() {
+      self.children.push(text_block(
+        "paragraph",
+        &format!("Attached: {}", attachments.join(", ")),
+        [],
+      ));
+    }
+    self
+  }
+
+  pub fn with_answer(mut self, content: &str, citations: &[ChatDocumentCitation]) -> Self {
+    self.children.extend(
+      content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| text_block("paragraph", line, [])),
+    );
+    if !citations.is_empty() {
+      let sources = citations.iter().map(citation_block).collect();
+      let mut data = HashMap::from([("collapsed".to_string(), json!(true))]);
+      data.insert("delta".to_string(), json!([{ "insert": "Sources" }]));
+      self.children.push(block("toggle_list", data, sources));
+    }
+    self
+  }
+
+  pub fn build(self) -> DocumentData {
+    let root = block("page", HashMap::new(), self.children);
+    JsonToDocumentParser::serde_block_to_document(&root)
+  }
+}
+
+fn citation_block(citation: &ChatDocumentCitation) -> SerdeBlock {
+  let mut preview = citation
+    .content
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ");
+  if let Some((index, _)) = preview.char_indices().nth(CITATION_PREVIEW_LEN) {
+    preview.truncate(index);
+    preview.push('…');
+  }
+  let delta = json!([
+    {
+      "insert": "$",
+      "attributes": { "mention": { "type": "page", "page_id": citation.object_id } }
+    },
+    { "insert": format!(" {}", preview) }
+  ]);
+  block(
+    "bulleted_list",
+    HashMap::from([("delta".to_string(), delta)]),
+    vec![],
+  )
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use collab_document::blocks::DocumentData;
+use serde_json::json;
+
+use crate::document::parser::{JsonToDocumentParser, SerdeBlock};
+use crate::document::{block, text_block};
+
+/// A task agreed on during a meeting.
+#[derive(Debug, Clone, Default)]
+pub struct MeetingActionItem {
+  pub content: String,
+  pub assignee: Option<String>,
+  /// Due date, formatted as `YYYY-MM-DD`.
+  pub due_date: Option<String>,
+}
+
+/// Builds meeting notes out of the transcript of a meeting.
+///
+/// The notes start with the summary of the meeting, followed by the list of its decisions and a
+/// checklist of its action items. The transcript itself ends the document, in a collapsed toggle.
+#[derive(Debug, Default)]
+pub struct MeetingNotesBuilder {
+  children: Vec<SerdeBlock>,
+}
+
+impl MeetingNotesBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a callout describing where the notes come from, e.g. the date of the meeting.
+  pub fn with_description(mut self, description: &str) -> Self {
+    self
+      .children
+      .push(text_block("callout", description, [("icon", json!("📝"))]));
+    self
+  }
+
+  pub fn with_summary(mut self, summary: &str) -> Self {
+    self.children.push(heading("Summary"));
+    self.children.extend(
+      summary
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| text_block("paragraph", line, [])),
+    );
+    self
+  }
+
+  pub fn with_decisions(mut self, decisions: &[String]) -> Self {
+    if decisions.is_empty() {
+      return self;
+    }
+    self.children.push(heading("Decisions"));
+    self.children.extend(
+      decisions
+        .iter()
+        .map(|decision| text_block("bulleted_list", decision.trim(), [])),
+    );
+    self
+  }
+
+  pub fn with_action_items(mut self, action_items: &[MeetingActionItem]) -> Self {
+    if action_items.is_empty() {
+      return self;
+    }
+    self.children.push(heading("Action items"));
+    self.children.extend(action_items.iter().map(|item| {
+      let mut text = item.content.trim().to_string();
+      if let Some(assignee) = &item.assignee {
+        text.push_str(&format!(" — {}", assignee));
+      }
+      if let Some(due_date) = &item.due_date {
+        text.push_str(&format!(" (due {})", due_date));
+      }
+      text_block("todo_list", &text, [("checked", json!(false))])
+    }));
+    self
+  }
+
+  /// Adds the lines of the transcript, collapsed under a toggle.
+  pub fn with_transcript(mut self, lines: &[String]) -> Self {
+    if lines.is_empty() {
+      return self;
+    }
+    let lines = lines
+      .iter()
+      .map(|line| text_block("paragraph", line, []))
+      .collect();
+    let data = HashMap::from([
+      ("collapsed".to_string(), json!(true)),
+      ("delta".to_string(), json!([{ "insert": "Transcript" }])),
+    ]);
+    self.children.push(block("toggle_list", data, lines));
+    self
+  }
+
+  pub fn build(self) -> DocumentData {
+    let root = block("page", HashMap::new(), self.children);
+    JsonToDocumentParser::serde_block_to_document(&root)
+  }
+}
+
+fn heading(text: &str) -> SerdeBlock {
+  text_block("heading", text, [("level", json!(2))])
+}
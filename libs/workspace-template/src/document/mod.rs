@@ -1,2 +1,35 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::document::parser::SerdeBlock;
+
+pub mod chat;
 pub mod getting_started;
+pub mod meeting;
 mod parser;
+
+pub(crate) fn block(
+  ty: &str,
+  data: HashMap<String, Value>,
+  children: Vec<SerdeBlock>,
+) -> SerdeBlock {
+  SerdeBlock {
+    ty: ty.to_string(),
+    data,
+    children,
+  }
+}
+
+pub(crate) fn text_block<const N: usize>(
+  ty: &str,
+  text: &str,
+  data: [(&str, Value); N],
+) -> SerdeBlock {
+  let mut data = data
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect::<HashMap<_, _>>();
+  data.insert("delta".to_string(), json!([{ "insert": text }]));
+  block(ty, data, vec![])
+}
@@ -14,26 +14,29 @@ const TEXT_EXTERNAL_TYPE: &str = "text";
 impl JsonToDocumentParser {
   pub fn json_str_to_document(json_str: &str) -> Result<DocumentData> {
     let root = serde_json::from_str::<SerdeBlock>(json_str)?;
+    Ok(Self::serde_block_to_document(&root))
+  }
 
+  pub fn serde_block_to_document(root: &SerdeBlock) -> DocumentData {
     let page_id = nanoid!(10);
 
     // generate the blocks
     // the root's parent id is empty
-    let (blocks, text_map) = Self::generate_blocks(&root, Some(page_id.clone()), "".to_string());
+    let (blocks, text_map) = Self::generate_blocks(root, Some(page_id.clone()), "".to_string());
 
     // generate the children map
     let children_map = Self::generate_children_map(&blocks);
 
     // generate the text map
     let text_map = Self::generate_text_map(&text_map);
-    Ok(DocumentData {
+    DocumentData {
       page_id,
       blocks: blocks.into_iter().collect(),
       meta: DocumentMeta {
         children_map,
         text_map: Some(text_map),
       },
-    })
+    }
   }
 
   fn generate_blocks(
@@ -0,0 +1,53 @@
+use collab::preclude::uuid_v4;
+
+use crate::document::chat::{ChatDocumentBuilder, ChatDocumentCitation};
+use crate::document::getting_started::DocumentTemplate;
+use crate::{TemplateObjectId, WorkspaceTemplate};
+
+#[tokio::test]
+async fn create_document_from_chat_test() {
+  let cited_view_id = uuid_v4().to_string();
+  let data = ChatDocumentBuilder::new()
+    .with_description("Exported from chat: my first chat")
+    .with_question("What is AppFlowy?", &["notes.md".to_string()])
+    .with_answer(
+      "AppFlowy is an open source workspace.\n\nIt is built with Flutter and Rust.",
+      &[ChatDocumentCitation {
+        object_id: cited_view_id.clone(),
+        content: "AppFlowy is the AI collaborative workspace".to_string(),
+      }],
+    )
+    .with_question("Is it free?", &[])
+    .with_answer("Yes.", &[])
+    .build();
+
+  let types = data
+    .blocks
+    .values()
+    .map(|b| b.ty.as_str())
+    .collect::<Vec<_>>();
+  let count = |ty: &str| types.iter().filter(|t| **t == ty).count();
+  assert_eq!(count("page"), 1);
+  assert_eq!(count("callout"), 1);
+  assert_eq!(count("heading"), 2);
+  assert_eq!(count("divider"), 1);
+  // attachment line and three lines of answers
+  assert_eq!(count("paragraph"), 4);
+  assert_eq!(count("toggle_list"), 1);
+  assert_eq!(count("bulleted_list"), 1);
+
+  let text_map = data.meta.text_map.as_ref().unwrap();
+  assert!(text_map
+    .values()
+    .any(|delta| delta.contains(&cited_view_id)));
+
+  let object_id = uuid_v4().to_string();
+  let template_data = DocumentTemplate::from_data(data)
+    .create(object_id.clone())
+    .await
+    .unwrap();
+  match &template_data[0].template_id {
+    TemplateObjectId::Document(oid) => assert_eq!(oid, &object_id),
+    _ => panic!("Template data is not a document"),
+  }
+}
@@ -0,0 +1,62 @@
+use crate::document::meeting::{MeetingActionItem, MeetingNotesBuilder};
+
+#[test]
+fn create_meeting_notes_test() {
+  let data = MeetingNotesBuilder::new()
+    .with_description("Meeting of 2024-12-03")
+    .with_summary("We planned the next release.\n\nThe launch moves to Friday.")
+    .with_decisions(&["Ship on Friday".to_string()])
+    .with_action_items(&[
+      MeetingActionItem {
+        content: "Write the release notes".to_string(),
+        assignee: Some("Lucas".to_string()),
+        due_date: Some("2024-12-05".to_string()),
+      },
+      MeetingActionItem {
+        content: "Update the website".to_string(),
+        ..Default::default()
+      },
+    ])
+    .with_transcript(&[
+      "Lucas: Let's ship on Friday.".to_string(),
+      "Nathan: Agreed.".to_string(),
+    ])
+    .build();
+
+  let types = data
+    .blocks
+    .values()
+    .map(|b| b.ty.as_str())
+    .collect::<Vec<_>>();
+  let count = |ty: &str| types.iter().filter(|t| **t == ty).count();
+  assert_eq!(count("page"), 1);
+  assert_eq!(count("callout"), 1);
+  assert_eq!(count("heading"), 3);
+  assert_eq!(count("bulleted_list"), 1);
+  assert_eq!(count("todo_list"), 2);
+  assert_eq!(count("toggle_list"), 1);
+  // two lines of summary and two lines of transcript
+  assert_eq!(count("paragraph"), 4);
+
+  let text_map = data.meta.text_map.as_ref().unwrap();
+  assert!(text_map
+    .values()
+    .any(|delta| delta.contains("Write the release notes — Lucas (due 2024-12-05)")));
+}
+
+#[test]
+fn skip_empty_meeting_sections_test() {
+  let data = MeetingNotesBuilder::new()
+    .with_summary("Nothing was decided.")
+    .with_decisions(&[])
+    .with_action_items(&[])
+    .with_transcript(&[])
+    .build();
+  let types = data
+    .blocks
+    .values()
+    .map(|b| b.ty.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(types.iter().filter(|t| **t == "heading").count(), 1);
+  assert!(!types.contains(&"toggle_list"));
+}
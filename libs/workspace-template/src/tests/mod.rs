@@ -1 +1,3 @@
+mod chat_tests;
 mod getting_started_tests;
+mod meeting_tests;
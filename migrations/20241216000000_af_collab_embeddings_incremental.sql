@@ -0,0 +1,31 @@
+-- Fragment ids are now derived from the fragment content. Fragments which didn't change since the
+-- last indexing are passed without embedding, and only fragments no longer present in the document
+-- are removed.
+CREATE OR REPLACE PROCEDURE af_collab_embeddings_upsert(
+    IN p_workspace_id UUID,
+    IN p_oid TEXT,
+    IN p_partition_key INT,
+    IN p_tokens_used INT,
+    IN p_fragments af_fragment[]
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    DELETE FROM af_collab_embeddings
+    WHERE oid = p_oid
+      AND fragment_id NOT IN (SELECT f.fragment_id FROM UNNEST(p_fragments) as f);
+
+    UPDATE af_collab_embeddings SET indexed_at = NOW() WHERE oid = p_oid;
+
+    INSERT INTO af_collab_embeddings (fragment_id, oid, partition_key, content_type, content, embedding, indexed_at)
+    SELECT f.fragment_id, p_oid, p_partition_key, f.content_type, f.contents, f.embedding, NOW()
+    FROM UNNEST(p_fragments) as f
+    WHERE f.embedding IS NOT NULL
+    ON CONFLICT (fragment_id) DO NOTHING;
+
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed)
+    VALUES (now()::date, p_workspace_id, 0, 0, p_tokens_used)
+    ON CONFLICT (created_at, workspace_id)
+    DO UPDATE SET index_tokens_consumed = af_workspace_ai_usage.index_tokens_consumed + p_tokens_used;
+END
+$$;
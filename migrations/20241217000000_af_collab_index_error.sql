@@ -0,0 +1,48 @@
+-- Tracks collabs which failed to be indexed, so that their status can be reported and
+-- indexing can be retried.
+CREATE TABLE IF NOT EXISTS af_collab_index_error
+(
+    oid TEXT NOT NULL,
+    partition_key INTEGER NOT NULL,
+    workspace_id UUID NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (oid, partition_key),
+    FOREIGN KEY (oid, partition_key) REFERENCES af_collab (oid, partition_key) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_index_error_workspace_id
+    ON af_collab_index_error (workspace_id);
+
+-- successfully stored embeddings clear the previous indexing error
+CREATE OR REPLACE PROCEDURE af_collab_embeddings_upsert(
+    IN p_workspace_id UUID,
+    IN p_oid TEXT,
+    IN p_partition_key INT,
+    IN p_tokens_used INT,
+    IN p_fragments af_fragment[]
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    DELETE FROM af_collab_embeddings
+    WHERE oid = p_oid
+      AND fragment_id NOT IN (SELECT f.fragment_id FROM UNNEST(p_fragments) as f);
+
+    UPDATE af_collab_embeddings SET indexed_at = NOW() WHERE oid = p_oid;
+
+    INSERT INTO af_collab_embeddings (fragment_id, oid, partition_key, content_type, content, embedding, indexed_at)
+    SELECT f.fragment_id, p_oid, p_partition_key, f.content_type, f.contents, f.embedding, NOW()
+    FROM UNNEST(p_fragments) as f
+    WHERE f.embedding IS NOT NULL
+    ON CONFLICT (fragment_id) DO NOTHING;
+
+    DELETE FROM af_collab_index_error WHERE oid = p_oid AND partition_key = p_partition_key;
+
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed)
+    VALUES (now()::date, p_workspace_id, 0, 0, p_tokens_used)
+    ON CONFLICT (created_at, workspace_id)
+    DO UPDATE SET index_tokens_consumed = af_workspace_ai_usage.index_tokens_consumed + p_tokens_used;
+END
+$$;
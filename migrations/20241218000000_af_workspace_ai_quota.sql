@@ -0,0 +1,27 @@
+-- number of completion and chat requests, which are counted separately from search requests
+ALTER TABLE af_workspace_ai_usage ADD COLUMN IF NOT EXISTS ai_requests INT NOT NULL DEFAULT 0;
+
+-- default AI quotas of each subscription plan, NULL means unlimited
+CREATE TABLE IF NOT EXISTS af_ai_plan_quota (
+    plan SMALLINT PRIMARY KEY,              -- subscription plan, see `SubscriptionPlan`
+    daily_token_limit BIGINT,
+    monthly_token_limit BIGINT,
+    daily_request_limit BIGINT,
+    monthly_request_limit BIGINT
+);
+
+-- all plans are unlimited until configured otherwise
+INSERT INTO af_ai_plan_quota (plan)
+VALUES (0), (1), (2), (3), (4)
+ON CONFLICT DO NOTHING;
+
+-- subscription plan of a workspace, together with optional overrides of the plan quotas
+CREATE TABLE IF NOT EXISTS af_workspace_ai_quota (
+    workspace_id UUID PRIMARY KEY REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    plan SMALLINT NOT NULL DEFAULT 0,
+    daily_token_limit BIGINT,               -- NULL means using the limit of the plan
+    monthly_token_limit BIGINT,
+    daily_request_limit BIGINT,
+    monthly_request_limit BIGINT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
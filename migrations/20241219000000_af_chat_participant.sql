@@ -0,0 +1,11 @@
+-- members of the workspace a chat has been shared with. Chats without participants are accessible
+-- to every member of the workspace, as they used to be before chats could be shared.
+CREATE TABLE IF NOT EXISTS af_chat_participant (
+    chat_id UUID NOT NULL REFERENCES af_chat(chat_id) ON DELETE CASCADE,
+    uid BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    role SMALLINT NOT NULL DEFAULT 1,       -- see `ChatParticipantRole`
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (chat_id, uid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_chat_participant_uid ON af_chat_participant(uid);
@@ -0,0 +1,29 @@
+-- Chat messages form a tree: regenerated answers and edited questions are stored as siblings of
+-- the message they replace, instead of overwriting it.
+ALTER TABLE af_chat_messages
+    ADD COLUMN IF NOT EXISTS parent_message_id BIGINT DEFAULT NULL;
+
+-- The last message of the branch currently selected in the chat. New questions are appended after
+-- it, and only the messages from the root of the tree to it are listed.
+ALTER TABLE af_chat
+    ADD COLUMN IF NOT EXISTS active_message_id BIGINT DEFAULT NULL;
+
+-- Existing conversations are linear: each message follows the previous one.
+UPDATE af_chat_messages m
+SET parent_message_id = p.prev_message_id
+FROM (
+    SELECT message_id, LAG(message_id) OVER (PARTITION BY chat_id ORDER BY message_id) AS prev_message_id
+    FROM af_chat_messages
+) p
+WHERE m.message_id = p.message_id AND m.parent_message_id IS NULL;
+
+UPDATE af_chat c
+SET active_message_id = m.last_message_id
+FROM (
+    SELECT chat_id, MAX(message_id) AS last_message_id
+    FROM af_chat_messages
+    GROUP BY chat_id
+) m
+WHERE c.chat_id = m.chat_id AND c.active_message_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_af_chat_messages_parent_message_id ON af_chat_messages (parent_message_id);
@@ -0,0 +1,14 @@
+-- Custom prompts of a workspace, used to run workspace specific AI writing actions. The prompts may
+-- contain variables, e.g. `{{text}}`, which are filled when a completion is requested.
+CREATE TABLE IF NOT EXISTS af_ai_prompt_template (
+    template_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    system_prompt TEXT NOT NULL,
+    user_prompt TEXT,
+    created_by BIGINT REFERENCES af_user(uid) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (workspace_id, name)
+);
@@ -0,0 +1,30 @@
+-- Database fields whose cells are computed by the AI. The prompt may reference other fields of the
+-- row by their name, e.g. `{{Description}}`, which are filled in before the prompt is sent.
+CREATE TABLE IF NOT EXISTS af_ai_database_field (
+    database_id TEXT NOT NULL,
+    field_id TEXT NOT NULL,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    prompt TEXT NOT NULL,
+    created_by BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (database_id, field_id)
+);
+
+-- State of every cell of an AI field. `input_hash` identifies the prompt the cell was computed from,
+-- so that cells are only computed again when the fields they reference change.
+-- status: 0 - pending, 1 - completed, 2 - rate limited, 3 - failed
+CREATE TABLE IF NOT EXISTS af_ai_database_cell (
+    database_id TEXT NOT NULL,
+    field_id TEXT NOT NULL,
+    row_id TEXT NOT NULL,
+    status SMALLINT NOT NULL,
+    input_hash TEXT NOT NULL,
+    error TEXT,
+    attempts INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (database_id, field_id, row_id),
+    FOREIGN KEY (database_id, field_id) REFERENCES af_ai_database_field(database_id, field_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_ai_database_cell_status ON af_ai_database_cell (status, updated_at);
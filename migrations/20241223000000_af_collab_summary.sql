@@ -0,0 +1,13 @@
+-- AI generated summaries of the documents of a workspace, regenerated in the background when the
+-- content of a document changes significantly. `content_hash` and `content_len` describe the
+-- plain text content the summary was generated from.
+CREATE TABLE IF NOT EXISTS af_collab_summary (
+    oid TEXT PRIMARY KEY,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    content_len INT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_summary_workspace_id ON af_collab_summary (workspace_id);
@@ -0,0 +1,47 @@
+-- Outgoing webhooks of databases. `event_types` lists the events the webhook is notified of:
+-- row.created, row.updated, row.deleted and field.added.
+CREATE TABLE IF NOT EXISTS af_database_webhook (
+    webhook_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    database_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_webhook_database_id ON af_database_webhook (database_id);
+
+-- Rows and fields of the databases which have webhooks, as of the last time they were polled.
+-- Changes made through the realtime server are found by comparing the databases to their snapshot.
+CREATE TABLE IF NOT EXISTS af_database_webhook_snapshot (
+    database_id TEXT PRIMARY KEY,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    row_ids TEXT[] NOT NULL,
+    field_ids TEXT[] NOT NULL,
+    polled_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Events to be delivered to webhooks, which are sent by appflowy-worker.
+-- status: 0 - pending, 1 - delivered, 2 - failed
+CREATE TABLE IF NOT EXISTS af_database_webhook_delivery (
+    delivery_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    webhook_id UUID NOT NULL REFERENCES af_database_webhook(webhook_id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    response_status INT,
+    error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_webhook_delivery_pending
+    ON af_database_webhook_delivery (next_attempt_at) WHERE status = 0;
+CREATE INDEX IF NOT EXISTS idx_af_database_webhook_delivery_webhook_id
+    ON af_database_webhook_delivery (webhook_id, created_at DESC);
@@ -0,0 +1,16 @@
+-- Database fields whose cells are computed by the server, from a formula over the other fields of
+-- the row or a rollup over the rows related by a relation field. The computed values are written to
+-- the row collabs, so that all clients show the same values.
+-- `computation` is the json of `AFDatabaseComputation`. `error` is set when the cells couldn't be
+-- computed, ie. when the formula references a field which no longer exists.
+CREATE TABLE IF NOT EXISTS af_database_computed_field (
+    database_id TEXT NOT NULL,
+    field_id TEXT NOT NULL,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    computation JSONB NOT NULL,
+    error TEXT,
+    created_by BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (database_id, field_id)
+);
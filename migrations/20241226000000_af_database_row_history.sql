@@ -0,0 +1,19 @@
+-- Field-level changes of database rows, derived from the updates applied to the row collabs.
+-- Consecutive changes of a cell by the same user are merged into a single entry, which keeps the
+-- cell before the first change and after the last one. `old_cell` and `new_cell` are the json of
+-- the cells without their timestamps, and are null when the cell didn't exist or was removed.
+CREATE TABLE IF NOT EXISTS af_database_row_history (
+    history_id BIGSERIAL PRIMARY KEY,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    database_id TEXT NOT NULL,
+    row_id TEXT NOT NULL,
+    field_id TEXT NOT NULL,
+    uid BIGINT REFERENCES af_user(uid) ON DELETE SET NULL,
+    old_cell JSONB,
+    new_cell JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_row_history_row_id
+    ON af_database_row_history (row_id, changed_at DESC);
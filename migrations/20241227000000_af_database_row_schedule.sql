@@ -0,0 +1,45 @@
+-- Schedules creating rows of a database from a template. `rule` is the recurrence of the schedule,
+-- evaluated by appflowy-worker, which queues a run of the schedule each time it's due.
+-- missed_run_policy: 0 - skip, 1 - run once, 2 - run all
+CREATE TABLE IF NOT EXISTS af_database_row_schedule (
+    schedule_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    database_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    rule JSONB NOT NULL,
+    cells JSONB NOT NULL DEFAULT '{}',
+    relative_dates JSONB NOT NULL DEFAULT '{}',
+    missed_run_policy SMALLINT NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- null once the schedule has ended
+    next_run_at TIMESTAMP WITH TIME ZONE,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_by BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_row_schedule_database_id
+    ON af_database_row_schedule (database_id);
+CREATE INDEX IF NOT EXISTS idx_af_database_row_schedule_due
+    ON af_database_row_schedule (next_run_at) WHERE enabled;
+
+-- Rows to be created by the appflowy cloud server for the runs of the schedules. The id of the row
+-- is generated along with the run, so that the row is created once even if the run is retried.
+-- status: 0 - pending, 1 - created, 2 - failed
+CREATE TABLE IF NOT EXISTS af_database_row_schedule_run (
+    run_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    schedule_id UUID NOT NULL REFERENCES af_database_row_schedule(schedule_id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    row_id TEXT NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (schedule_id, scheduled_for)
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_row_schedule_run_pending
+    ON af_database_row_schedule_run (next_attempt_at) WHERE status = 0;
@@ -0,0 +1,48 @@
+-- Notification settings of a user. Users without settings get reminders in UTC, by realtime
+-- message and by email.
+CREATE TABLE IF NOT EXISTS af_user_notification_setting (
+    uid BIGINT PRIMARY KEY REFERENCES af_user(uid) ON DELETE CASCADE,
+    -- IANA time zone, ie. Europe/Paris
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    reminder_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    reminder_email_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Reminders set on the dates of database rows and the date mentions of documents, found by the
+-- realtime server when they are edited. `uid` is the user who set the reminder.
+-- The local times are set for the dates which are a time of the day of the user, ie. dates
+-- without time, and are resolved again into `event_at` and `remind_at` when their time zone
+-- changes.
+-- object_type: 0 - database row, 1 - document
+-- status: 0 - pending, 1 - notified, 2 - delivered, 3 - emailed, 4 - skipped
+CREATE TABLE IF NOT EXISTS af_reminder (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    object_type SMALLINT NOT NULL,
+    object_id TEXT NOT NULL,
+    reminder_id TEXT NOT NULL,
+    database_id TEXT,
+    field_id TEXT,
+    block_id TEXT,
+    uid BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+    title TEXT NOT NULL DEFAULT '',
+    include_time BOOLEAN NOT NULL DEFAULT FALSE,
+    event_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    remind_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    local_event_at TIMESTAMP,
+    local_remind_at TIMESTAMP,
+    status SMALLINT NOT NULL DEFAULT 0,
+    notified_at TIMESTAMP WITH TIME ZONE,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (object_id, reminder_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_reminder_pending
+    ON af_reminder (remind_at) WHERE status = 0;
+CREATE INDEX IF NOT EXISTS idx_af_reminder_notified
+    ON af_reminder (notified_at) WHERE status = 1;
+CREATE INDEX IF NOT EXISTS idx_af_reminder_uid
+    ON af_reminder (uid);
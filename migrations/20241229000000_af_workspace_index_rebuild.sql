@@ -0,0 +1,49 @@
+-- Last re-indexing requested for a workspace. Its documents are re-indexed by the consumers of the
+-- indexing queue, which record their progress here. Jobs of a previous re-indexing of the
+-- workspace are ignored, since their rebuild_id doesn't match anymore.
+CREATE TABLE IF NOT EXISTS af_workspace_index_rebuild (
+    workspace_id UUID PRIMARY KEY REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    rebuild_id UUID NOT NULL,
+    total BIGINT NOT NULL DEFAULT 0,
+    processed BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    cancelled BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+-- New fragments are stored before the stale ones are removed, and the embeddings of re-embedded
+-- fragments replace the stored ones.
+CREATE OR REPLACE PROCEDURE af_collab_embeddings_upsert(
+    IN p_workspace_id UUID,
+    IN p_oid TEXT,
+    IN p_partition_key INT,
+    IN p_tokens_used INT,
+    IN p_fragments af_fragment[]
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO af_collab_embeddings (fragment_id, oid, partition_key, content_type, content, embedding, indexed_at)
+    SELECT f.fragment_id, p_oid, p_partition_key, f.content_type, f.contents, f.embedding, NOW()
+    FROM UNNEST(p_fragments) as f
+    WHERE f.embedding IS NOT NULL
+    ON CONFLICT (fragment_id) DO UPDATE
+    SET content_type = EXCLUDED.content_type,
+        content = EXCLUDED.content,
+        embedding = EXCLUDED.embedding;
+
+    UPDATE af_collab_embeddings SET indexed_at = NOW() WHERE oid = p_oid;
+
+    DELETE FROM af_collab_embeddings
+    WHERE oid = p_oid
+      AND fragment_id NOT IN (SELECT f.fragment_id FROM UNNEST(p_fragments) as f);
+
+    DELETE FROM af_collab_index_error WHERE oid = p_oid AND partition_key = p_partition_key;
+
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed)
+    VALUES (now()::date, p_workspace_id, 0, 0, p_tokens_used)
+    ON CONFLICT (created_at, workspace_id)
+    DO UPDATE SET index_tokens_consumed = af_workspace_ai_usage.index_tokens_consumed + p_tokens_used;
+END
+$$;
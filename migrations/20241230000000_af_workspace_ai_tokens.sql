@@ -0,0 +1,2 @@
+-- number of tokens consumed by completion and chat requests, as reported by the AI service
+ALTER TABLE af_workspace_ai_usage ADD COLUMN IF NOT EXISTS ai_tokens_consumed BIGINT NOT NULL DEFAULT 0;
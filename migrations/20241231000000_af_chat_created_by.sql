@@ -0,0 +1,14 @@
+-- user who created a chat. Until the chat is shared, it's only accessible to its creator.
+ALTER TABLE af_chat ADD COLUMN IF NOT EXISTS created_by BIGINT REFERENCES af_user(uid) ON DELETE SET NULL;
+
+-- chats created before are attributed to the author of their first question
+UPDATE af_chat c
+SET created_by = (
+    SELECT u.uid
+    FROM af_chat_messages m
+    JOIN af_user u ON u.uid = (m.author->>'author_id')::BIGINT
+    WHERE m.chat_id = c.chat_id AND (m.author->>'author_type')::INT = 1
+    ORDER BY m.message_id
+    LIMIT 1
+)
+WHERE c.created_by IS NULL;
@@ -0,0 +1,38 @@
+use anyhow::Error;
+use collab_rt_entity::ai::{AIStreamEvent, AIStreamNotification, AI_STREAM_EVENT_CHANNEL};
+use collab_stream::pubsub::RedisPubSubListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+/// Receives the [AIStreamEvent]s published by the servers streaming AI responses.
+pub struct AIStreamListener {
+  listener: RedisPubSubListener<AIStreamNotification>,
+}
+
+impl AIStreamListener {
+  pub async fn new(redis_client: &redis::Client) -> Result<Self, Error> {
+    let listener = RedisPubSubListener::new(redis_client, AI_STREAM_EVENT_CHANNEL).await?;
+    Ok(Self { listener })
+  }
+
+  /// Receive events of the AI streams started by the user.
+  pub fn subscribe(&self, uid: i64) -> mpsc::Receiver<AIStreamEvent> {
+    let (tx, rx) = mpsc::channel(100);
+    let mut ai_stream_notify = self.listener.notify.subscribe();
+    tokio::spawn(async move {
+      loop {
+        match ai_stream_notify.recv().await {
+          Ok(notification) => {
+            if notification.uid == uid && tx.send(notification.event).await.is_err() {
+              break;
+            }
+          },
+          // missed chunks can be fetched by the client from the stream buffer
+          Err(RecvError::Lagged(_)) => continue,
+          Err(RecvError::Closed) => break,
+        }
+      }
+    });
+    rx
+  }
+}
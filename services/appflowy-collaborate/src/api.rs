@@ -21,6 +21,7 @@ use app_error::AppError;
 use authentication::jwt::{authorization_from_token, UserUuid};
 use collab_rt_entity::user::{AFUserChange, RealtimeUser, UserMessage};
 use collab_rt_entity::{HttpRealtimeMessage, RealtimeMessage};
+use database::reminder::update_reminder_delivered;
 use shared_entity::response::{AppResponse, AppResponseError};
 
 use crate::actix_ws::client::RealtimeClient;
@@ -279,7 +280,13 @@ async fn start_connect(
       );
 
       // Receive user change notifications and send them to the client.
-      listen_on_user_change(state, uid, tx);
+      listen_on_user_change(state, uid, tx.clone());
+      // Receive events of the shared chats the user participates in.
+      listen_on_chat_event(state, uid, tx.clone());
+      // Receive chunks of the AI responses streamed over the realtime connection.
+      listen_on_ai_stream_event(state, uid, tx.clone());
+      // Receive the reminders of the dates set by the user when they are due.
+      listen_on_reminder(state, uid, tx);
 
       match ws::WsResponseBuilder::new(client, request, payload)
         .frame_size(MAX_FRAME_SIZE * 2)
@@ -327,6 +334,51 @@ fn listen_on_user_change(state: &Data<AppState>, uid: i64, tx: Sender<RealtimeMe
   });
 }
 
+fn listen_on_chat_event(state: &Data<AppState>, uid: i64, tx: Sender<RealtimeMessage>) {
+  let mut chat_event_recv = state.pg_listeners.subscribe_chat_event(uid);
+  actix::spawn(async move {
+    while let Some(event) = chat_event_recv.recv().await {
+      trace!("Receive chat event: {}", event);
+      if tx.send(RealtimeMessage::Chat(event)).await.is_err() {
+        break;
+      }
+    }
+  });
+}
+
+fn listen_on_ai_stream_event(state: &Data<AppState>, uid: i64, tx: Sender<RealtimeMessage>) {
+  let mut ai_stream_event_recv = state.ai_stream_listener.subscribe(uid);
+  actix::spawn(async move {
+    while let Some(event) = ai_stream_event_recv.recv().await {
+      trace!("Receive AI stream event: {}", event);
+      if tx.send(RealtimeMessage::AIStream(event)).await.is_err() {
+        break;
+      }
+    }
+  });
+}
+
+fn listen_on_reminder(state: &Data<AppState>, uid: i64, tx: Sender<RealtimeMessage>) {
+  let mut reminder_recv = state.pg_listeners.subscribe_reminder(uid);
+  let pg_pool = state.pg_pool.clone();
+  actix::spawn(async move {
+    while let Some(notification) = reminder_recv.recv().await {
+      trace!("Receive reminder: {}", notification.reminder.reminder_id);
+      let msg = UserMessage::Reminder(notification.reminder);
+      if tx.send(RealtimeMessage::User(msg)).await.is_err() {
+        break;
+      }
+      // delivered reminders aren't sent by email
+      if let Err(err) = update_reminder_delivered(&pg_pool, &notification.id).await {
+        error!(
+          "Failed to mark reminder {} as delivered: {}",
+          notification.id, err
+        );
+      }
+    }
+  });
+}
+
 struct ConnectInfo {
   access_token: String,
   client_version: Version,
@@ -21,19 +21,23 @@ use sqlx::PgPool;
 use tracing::info;
 
 use crate::actix_ws::server::RealtimeServerActor;
+use crate::ai_stream::AIStreamListener;
 use crate::api::{collab_scope, ws_scope};
 use crate::collab::access_control::CollabStorageAccessControlImpl;
 use crate::collab::cache::CollabCache;
 use crate::collab::storage::CollabStorageImpl;
 use crate::command::{CLCommandReceiver, CLCommandSender};
-use crate::config::{Config, DatabaseSetting, S3Setting};
+use crate::config::{get_env_var, Config, DatabaseSetting, S3Setting};
 use crate::indexer::IndexerProvider;
 use crate::pg_listener::PgListeners;
+use crate::reminder::ReminderRecorder;
+use crate::row_history::RowHistoryRecorder;
 use crate::snapshot::SnapshotControl;
 use crate::state::{AppMetrics, AppState, UserCache};
 use crate::CollaborationServer;
 use access_control::casbin::access::AccessControl;
 use appflowy_ai_client::client::AppFlowyAIClient;
+use collab_stream::indexing_queue::{unique_consumer_name, IndexingQueue, IndexingQueueConfig};
 use database::file::s3_client_impl::AwsS3BucketClientImpl;
 
 pub struct Application {
@@ -82,6 +86,8 @@ pub async fn run_actix_server(
     config.collab.edit_state_max_count,
     config.collab.edit_state_max_secs,
     state.indexer_provider.clone(),
+    state.row_history_recorder.clone(),
+    state.reminder_recorder.clone(),
   )
   .await
   .unwrap();
@@ -102,18 +108,28 @@ pub async fn run_actix_server(
 pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<AppState, Error> {
   let metrics = AppMetrics::new();
   let pg_pool = get_connection_pool(&config.db_settings).await?;
-  let ai_client = AppFlowyAIClient::new(&config.ai.url());
-  let indexer_provider = IndexerProvider::new(pg_pool.clone(), ai_client);
 
   // User cache
   let user_cache = UserCache::new(pg_pool.clone()).await;
 
   info!("Connecting to Redis...");
-  let redis_conn_manager = get_redis_client(config.redis_uri.expose_secret()).await?;
+  let (redis_client, redis_conn_manager) =
+    get_redis_client(config.redis_uri.expose_secret()).await?;
+
+  let ai_client = AppFlowyAIClient::new(&config.ai.url());
+  let indexing_queue =
+    IndexingQueue::new(redis_conn_manager.clone(), IndexingQueueConfig::default()).await?;
+  let indexer_provider = IndexerProvider::new(
+    pg_pool.clone(),
+    ai_client,
+    indexing_queue,
+    metrics.embedding_metrics.clone(),
+  );
 
   // Pg listeners
   info!("Setting up Pg listeners...");
   let pg_listeners = Arc::new(PgListeners::new(&pg_pool).await?);
+  let ai_stream_listener = Arc::new(AIStreamListener::new(&redis_client).await?);
   let access_control =
     AccessControl::new(pg_pool.clone(), metrics.access_control_metrics.clone()).await?;
 
@@ -133,6 +149,8 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     config.collab.s3_collab_threshold as usize,
   );
 
+  spawn_indexing_queue_consumer(&indexer_provider, &collab_cache, &redis_conn_manager).await?;
+
   let collab_storage_access_control = CollabStorageAccessControlImpl {
     collab_access_control: Arc::new(collab_access_control.clone()),
     workspace_access_control: Arc::new(workspace_access_control.clone()),
@@ -153,24 +171,59 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   let app_state = AppState {
     config: Arc::new(config.clone()),
     pg_listeners,
+    ai_stream_listener,
     user_cache,
     redis_connection_manager: redis_conn_manager,
     access_control,
     collab_access_control_storage: collab_storage,
     metrics,
     indexer_provider,
+    row_history_recorder: Arc::new(RowHistoryRecorder::new(pg_pool.clone())),
+    reminder_recorder: Arc::new(ReminderRecorder::new(pg_pool.clone())),
+    pg_pool,
   };
   Ok(app_state)
 }
 
-async fn get_redis_client(redis_uri: &str) -> Result<redis::aio::ConnectionManager, Error> {
+/// Starts processing the indexing queue unless it's disabled, ie. because the queue is processed
+/// by appflowy-worker instead.
+pub async fn spawn_indexing_queue_consumer(
+  indexer_provider: &Arc<IndexerProvider>,
+  collab_cache: &CollabCache,
+  redis_conn_manager: &redis::aio::ConnectionManager,
+) -> Result<(), Error> {
+  let enabled = get_env_var("APPFLOWY_INDEXER_QUEUE_CONSUMER_ENABLED", "true")
+    .parse::<bool>()
+    .unwrap_or(true);
+  if enabled {
+    let queue =
+      IndexingQueue::new(redis_conn_manager.clone(), IndexingQueueConfig::default()).await?;
+    // the server and appflowy-collaborate may both consume the queue, so consumers have a name
+    // unique to their process unless one is given
+    let consumer = std::env::var("APPFLOWY_INDEXER_QUEUE_CONSUMER_NAME")
+      .ok()
+      .filter(|consumer| !consumer.is_empty())
+      .unwrap_or_else(|| unique_consumer_name("appflowy_collaborate"));
+    tokio::spawn(IndexerProvider::run_indexing_queue(
+      indexer_provider.clone(),
+      collab_cache.clone(),
+      queue,
+      consumer,
+    ));
+  }
+  Ok(())
+}
+
+async fn get_redis_client(
+  redis_uri: &str,
+) -> Result<(redis::Client, redis::aio::ConnectionManager), Error> {
   info!("Connecting to redis with uri: {}", redis_uri);
-  let manager = redis::Client::open(redis_uri)
-    .context("failed to connect to redis")?
+  let client = redis::Client::open(redis_uri).context("failed to connect to redis")?;
+  let manager = client
     .get_connection_manager()
     .await
     .context("failed to get the connection manager")?;
-  Ok(manager)
+  Ok((client, manager))
 }
 
 async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error> {
@@ -140,6 +140,8 @@ impl CollabDiskCache {
       upsert_collab_embeddings(
         transaction,
         &workspace_id,
+        &params.object_id,
+        &params.collab_type,
         em.tokens_consumed,
         em.params.clone(),
       )
@@ -8,7 +8,7 @@ use collab::lock::RwLock;
 use collab::preclude::Collab;
 use futures_util::{SinkExt, StreamExt};
 use tokio::select;
-use tokio::sync::broadcast::{channel, Sender};
+use tokio::sync::broadcast::{channel, Receiver, Sender};
 use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, trace, warn};
@@ -83,6 +83,12 @@ impl CollabBroadcast {
     this
   }
 
+  /// Returns a receiver of the messages broadcast to the subscribers, which include the updates
+  /// applied to the collab along with their origin.
+  pub fn subscribe_messages(&self) -> Receiver<CollabMessage> {
+    self.broadcast_sender.subscribe()
+  }
+
   fn observe_collab_changes(&mut self, collab: &Collab) {
     let (doc_sub, awareness_sub) = {
       // Observer the document's update and broadcast it to all subscribers.
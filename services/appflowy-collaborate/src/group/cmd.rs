@@ -147,9 +147,12 @@ where
             }
           },
           GroupCommand::GenerateCollabEmbedding { object_id, ret } => {
-            // TODO(nathan): generate embedding
             trace!("Generate embedding for group:{}", object_id);
-            let _ = ret.send(Ok(()));
+            let result = match self.group_manager.get_group(&object_id).await {
+              None => Err(RealtimeError::GroupNotFound(object_id.clone())),
+              Some(group) => group.enqueue_indexing().await,
+            };
+            let _ = ret.send(result);
           },
           GroupCommand::CalculateMissingUpdate {
             object_id,
@@ -23,8 +23,9 @@ use database::collab::CollabStorage;
 
 use crate::error::RealtimeError;
 use crate::group::broadcast::{CollabBroadcast, Subscription};
+use crate::group::observer::GroupObserver;
 use crate::group::persistence::GroupPersistence;
-use crate::indexer::Indexer;
+use crate::indexer::IndexerProvider;
 use crate::metrics::CollabRealtimeMetrics;
 
 /// A group used to manage a single [Collab] object
@@ -33,6 +34,8 @@ pub struct CollabGroup {
   pub object_id: String,
   collab: Arc<RwLock<Collab>>,
   collab_type: CollabType,
+  /// Set when the collab should be indexed, ie. when its workspace has indexing enabled.
+  indexer_provider: Option<Arc<IndexerProvider>>,
   /// A broadcast used to propagate updates produced by yrs [yrs::Doc] and [Awareness]
   /// to subscribes.
   broadcast: CollabBroadcast,
@@ -57,7 +60,8 @@ impl CollabGroup {
     persistence_interval: Duration,
     edit_state_max_count: u32,
     edit_state_max_secs: i64,
-    indexer: Option<Arc<dyn Indexer>>,
+    indexer_provider: Option<Arc<IndexerProvider>>,
+    observers: Vec<Box<dyn GroupObserver>>,
   ) -> Result<Self, StreamError>
   where
     S: CollabStorage,
@@ -69,8 +73,14 @@ impl CollabGroup {
     ));
     let broadcast = CollabBroadcast::new(&object_id, 1000, edit_state.clone(), &collab);
     let cancel = CancellationToken::new();
-
     let collab = Arc::new(RwLock::new(collab));
+    for observer in observers {
+      observer.spawn(
+        Arc::downgrade(&collab),
+        broadcast.subscribe_messages(),
+        cancel.clone(),
+      );
+    }
     tokio::spawn(
       GroupPersistence::new(
         workspace_id.clone(),
@@ -81,7 +91,7 @@ impl CollabGroup {
         collab.clone(),
         collab_type.clone(),
         persistence_interval,
-        indexer,
+        indexer_provider.clone(),
         cancel.clone(),
       )
       .run(),
@@ -91,6 +101,7 @@ impl CollabGroup {
       workspace_id,
       object_id,
       collab_type,
+      indexer_provider,
       collab,
       broadcast,
       subscribers: Default::default(),
@@ -123,6 +134,17 @@ impl CollabGroup {
     Ok(encode_collab)
   }
 
+  /// Enqueues the collab to be indexed. Does nothing if the collab is not indexed.
+  pub async fn enqueue_indexing(&self) -> Result<(), RealtimeError> {
+    if let Some(indexer_provider) = &self.indexer_provider {
+      indexer_provider
+        .enqueue_indexing(&self.workspace_id, &self.object_id, &self.collab_type)
+        .await
+        .map_err(|err| RealtimeError::Internal(err.into()))?;
+    }
+    Ok(())
+  }
+
   pub fn contains_user(&self, user: &RealtimeUser) -> bool {
     self.subscribers.contains_key(user)
   }
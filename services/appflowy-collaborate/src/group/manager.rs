@@ -19,9 +19,12 @@ use database_entity::dto::QueryCollabParams;
 use crate::client::client_msg_router::ClientMessageRouter;
 use crate::error::{CreateGroupFailedReason, RealtimeError};
 use crate::group::group_init::CollabGroup;
+use crate::group::observer::GroupRecorder;
 use crate::group::state::GroupManagementState;
 use crate::indexer::IndexerProvider;
 use crate::metrics::CollabRealtimeMetrics;
+use crate::reminder::ReminderRecorder;
+use crate::row_history::RowHistoryRecorder;
 
 pub struct GroupManager<S> {
   state: GroupManagementState,
@@ -32,6 +35,9 @@ pub struct GroupManager<S> {
   edit_state_max_count: u32,
   edit_state_max_secs: i64,
   indexer_provider: Arc<IndexerProvider>,
+  /// Recorders of the changes of the collabs of the groups, ie. the history and
+  /// the reminders of database rows.
+  recorders: Vec<Arc<dyn GroupRecorder>>,
 }
 
 impl<S> GroupManager<S>
@@ -47,6 +53,8 @@ where
     edit_state_max_count: u32,
     edit_state_max_secs: i64,
     indexer_provider: Arc<IndexerProvider>,
+    row_history_recorder: Arc<RowHistoryRecorder>,
+    reminder_recorder: Arc<ReminderRecorder>,
   ) -> Result<Self, RealtimeError> {
     Ok(Self {
       state: GroupManagementState::new(metrics_calculate.clone()),
@@ -57,6 +65,10 @@ where
       edit_state_max_count,
       edit_state_max_secs,
       indexer_provider,
+      recorders: vec![
+        row_history_recorder as Arc<dyn GroupRecorder>,
+        reminder_recorder,
+      ],
     })
   }
 
@@ -149,8 +161,11 @@ where
       collab_type
     );
 
-    let mut indexer = self.indexer_provider.indexer_for(&collab_type);
-    if indexer.is_some()
+    let mut indexer_provider = self
+      .indexer_provider
+      .indexer_for(&collab_type)
+      .map(|_| self.indexer_provider.clone());
+    if indexer_provider.is_some()
       && !self
         .indexer_provider
         .can_index_workspace(workspace_id)
@@ -158,8 +173,17 @@ where
         .map_err(|e| RealtimeError::Internal(e.into()))?
     {
       tracing::trace!("workspace {} indexing is disabled", workspace_id);
-      indexer = None;
+      indexer_provider = None;
     }
+    let observers = self
+      .recorders
+      .iter()
+      .filter_map(|recorder| {
+        recorder
+          .clone()
+          .observer(workspace_id, object_id, &collab_type, &collab)
+      })
+      .collect();
     let group = Arc::new(CollabGroup::new(
       user.uid,
       workspace_id.to_string(),
@@ -172,7 +196,8 @@ where
       self.persistence_interval,
       self.edit_state_max_count,
       self.edit_state_max_secs,
-      indexer,
+      indexer_provider,
+      observers,
     )?);
     self.state.insert_group(object_id, group);
     Ok(())
@@ -3,6 +3,7 @@ pub(crate) mod cmd;
 pub(crate) mod group_init;
 pub(crate) mod manager;
 mod null_sender;
+pub(crate) mod observer;
 mod persistence;
 mod plugin;
 pub(crate) mod protocol;
@@ -0,0 +1,158 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use app_error::AppError;
+use async_trait::async_trait;
+use collab::lock::RwLock;
+use collab::preclude::Collab;
+use collab_entity::CollabType;
+use collab_rt_entity::CollabMessage;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::broadcast::Receiver;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use tracing::{trace, warn};
+
+/// Creates the observers of the groups of the collabs it records the changes of.
+pub(crate) trait GroupRecorder: Send + Sync {
+  /// Returns an observer of the collab, none when the changes of the collab aren't recorded.
+  fn observer(
+    self: Arc<Self>,
+    workspace_id: &str,
+    object_id: &str,
+    collab_type: &CollabType,
+    collab: &Collab,
+  ) -> Option<Box<dyn GroupObserver>>;
+}
+
+/// An observer of the updates broadcast by the group of a collab.
+pub(crate) trait GroupObserver: Send {
+  /// Starts observing the updates of the collab, until the group is dropped.
+  fn spawn(
+    self: Box<Self>,
+    weak_collab: Weak<RwLock<Collab>>,
+    receiver: Receiver<CollabMessage>,
+    cancel: CancellationToken,
+  );
+}
+
+/// Compares a snapshot of a collab to its previous version after the updates of the collab.
+#[async_trait]
+pub(crate) trait SnapshotObserver: Send + Sync + 'static {
+  type Snapshot: Send + Sync;
+
+  /// Prefix of the logs of the observer.
+  const NAME: &'static str;
+  /// Updates received within this delay after an update are compared as a whole.
+  const DEBOUNCE: Duration = Duration::ZERO;
+
+  /// Returns the snapshot of the collab, none when the collab hasn't been initialized.
+  fn snapshot(&self, collab: &Collab) -> Option<Self::Snapshot>;
+
+  /// Records the changes between two snapshots of the collab made by the given user.
+  async fn on_change(
+    &self,
+    uid: i64,
+    previous: &Self::Snapshot,
+    current: &Self::Snapshot,
+  ) -> Result<(), AppError>;
+}
+
+/// Runs a [SnapshotObserver] on the updates broadcast by the group of a collab.
+pub(crate) struct DebouncedObserver<O: SnapshotObserver> {
+  observer: O,
+  object_id: String,
+  /// Snapshot as of the last update, none until the collab has been initialized by a client.
+  snapshot: Option<O::Snapshot>,
+}
+
+impl<O: SnapshotObserver> DebouncedObserver<O> {
+  pub(crate) fn new(observer: O, object_id: &str, collab: &Collab) -> Self {
+    let snapshot = observer.snapshot(collab);
+    Self {
+      observer,
+      object_id: object_id.to_string(),
+      snapshot,
+    }
+  }
+
+  async fn run(
+    mut self,
+    weak_collab: Weak<RwLock<Collab>>,
+    mut receiver: Receiver<CollabMessage>,
+    cancel: CancellationToken,
+  ) {
+    loop {
+      let message = tokio::select! {
+        _ = cancel.cancelled() => break,
+        message = receiver.recv() => message,
+      };
+      let mut editor = match message {
+        Ok(message) => update_editor(&message),
+        Err(RecvError::Lagged(count)) => {
+          trace!("[{}] skipped {} updates", O::NAME, count);
+          None
+        },
+        Err(RecvError::Closed) => break,
+      };
+      if !O::DEBOUNCE.is_zero() {
+        tokio::select! {
+          _ = cancel.cancelled() => break,
+          _ = sleep(O::DEBOUNCE) => {},
+        }
+      }
+      // Updates received meanwhile are compared as a whole, and attributed to the last user who
+      // sent one of them.
+      loop {
+        match receiver.try_recv() {
+          Ok(message) => {
+            if let Some(uid) = update_editor(&message) {
+              editor = Some(uid);
+            }
+          },
+          Err(TryRecvError::Lagged(_)) => continue,
+          Err(_) => break,
+        }
+      }
+
+      let collab = match weak_collab.upgrade() {
+        Some(collab) => collab,
+        None => break,
+      };
+      let snapshot = match self.observer.snapshot(&*collab.read().await) {
+        Some(snapshot) => snapshot,
+        None => continue,
+      };
+      if let (Some(uid), Some(previous)) = (editor, &self.snapshot) {
+        if let Err(err) = self.observer.on_change(uid, previous, &snapshot).await {
+          warn!(
+            "[{}] failed to record changes of {}: {}",
+            O::NAME,
+            self.object_id,
+            err
+          );
+        }
+      }
+      self.snapshot = Some(snapshot);
+    }
+  }
+}
+
+impl<O: SnapshotObserver> GroupObserver for DebouncedObserver<O> {
+  fn spawn(
+    self: Box<Self>,
+    weak_collab: Weak<RwLock<Collab>>,
+    receiver: Receiver<CollabMessage>,
+    cancel: CancellationToken,
+  ) {
+    tokio::spawn((*self).run(weak_collab, receiver, cancel));
+  }
+}
+
+/// Returns the user who sent the update, if the message is an update sent by a client.
+fn update_editor(message: &CollabMessage) -> Option<i64> {
+  match message {
+    CollabMessage::ServerBroadcast(_) => message.origin().client_user_id(),
+    _ => None,
+  }
+}
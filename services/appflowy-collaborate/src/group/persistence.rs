@@ -14,7 +14,7 @@ use database::collab::CollabStorage;
 use database_entity::dto::CollabParams;
 
 use crate::group::group_init::EditState;
-use crate::indexer::Indexer;
+use crate::indexer::IndexerProvider;
 
 pub(crate) struct GroupPersistence<S> {
   workspace_id: String,
@@ -27,7 +27,8 @@ pub(crate) struct GroupPersistence<S> {
   collab: Arc<RwLock<Collab>>,
   collab_type: CollabType,
   persistence_interval: Duration,
-  indexer: Option<Arc<dyn Indexer>>,
+  /// Set when the collab should be indexed after it's saved.
+  indexer_provider: Option<Arc<IndexerProvider>>,
   cancel: CancellationToken,
 }
 
@@ -45,7 +46,7 @@ where
     collab: Arc<RwLock<Collab>>,
     collab_type: CollabType,
     persistence_interval: Duration,
-    ai_client: Option<Arc<dyn Indexer>>,
+    indexer_provider: Option<Arc<IndexerProvider>>,
     cancel: CancellationToken,
   ) -> Self {
     Self {
@@ -57,7 +58,7 @@ where
       collab,
       collab_type,
       persistence_interval,
-      indexer: ai_client,
+      indexer_provider,
       cancel,
     }
   }
@@ -123,42 +124,12 @@ where
     let workspace_id = self.workspace_id.clone();
     let collab_type = self.collab_type.clone();
 
-    let params = {
-      let cloned_collab = self.collab.clone();
-      let (workspace_id, mut params, object_id) = tokio::task::spawn_blocking(move || {
-        let collab = cloned_collab.blocking_read();
-        let params = get_encode_collab(&workspace_id, &object_id, &collab, &collab_type)?;
-        Ok::<_, AppError>((workspace_id, params, object_id))
-      })
-      .await??;
-
-      let lock = self.collab.read().await;
-      if let Some(indexer) = &self.indexer {
-        match indexer.embedding_params(&lock).await {
-          Ok(embedding_params) => {
-            drop(lock); // we no longer need the lock
-            match indexer.embeddings(embedding_params).await {
-              Ok(embeddings) => {
-                params.embeddings = embeddings;
-              },
-              Err(err) => {
-                warn!(
-                  "failed to index embeddings from remote service for document {}/{}: {}",
-                  workspace_id, object_id, err
-                );
-              },
-            }
-          },
-          Err(err) => {
-            warn!(
-              "failed to get embedding params for document {}/{}: {}",
-              workspace_id, object_id, err
-            );
-          },
-        }
-      }
-      params
-    };
+    let cloned_collab = self.collab.clone();
+    let params = tokio::task::spawn_blocking(move || {
+      let collab = cloned_collab.blocking_read();
+      get_encode_collab(&workspace_id, &object_id, &collab, &collab_type)
+    })
+    .await??;
 
     self
       .storage
@@ -166,6 +137,18 @@ where
       .await?;
     // Update the edit state on successful save
     self.edit_state.tick();
+
+    if let Some(indexer_provider) = &self.indexer_provider {
+      if let Err(err) = indexer_provider
+        .enqueue_indexing(&self.workspace_id, &self.object_id, &self.collab_type)
+        .await
+      {
+        warn!(
+          "failed to enqueue indexing of document {}/{}: {}",
+          self.workspace_id, self.object_id, err
+        );
+      }
+    }
     Ok(())
   }
 }
@@ -9,30 +9,37 @@ use collab::preclude::Collab;
 use collab_document::document::DocumentBody;
 use collab_document::error::DocumentError;
 use collab_entity::CollabType;
+use database::index::select_collab_embedding_fragment_ids;
 use database_entity::dto::{AFCollabEmbeddingParams, AFCollabEmbeddings, EmbeddingContentType};
+use sqlx::PgPool;
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use crate::indexer::open_ai::split_text_by_max_content_len;
+use crate::indexer::open_ai::split_text_by_paragraphs;
 use crate::indexer::Indexer;
+use crate::metrics::EmbeddingMetrics;
 use tiktoken_rs::CoreBPE;
 use tracing::trace;
-use uuid::Uuid;
 
 pub struct DocumentIndexer {
+  db: PgPool,
   ai_client: AppFlowyAIClient,
   #[allow(dead_code)]
   tokenizer: Arc<CoreBPE>,
   embedding_model: EmbeddingModel,
+  metrics: Arc<EmbeddingMetrics>,
 }
 
 impl DocumentIndexer {
-  pub fn new(ai_client: AppFlowyAIClient) -> Arc<Self> {
+  pub fn new(db: PgPool, ai_client: AppFlowyAIClient, metrics: Arc<EmbeddingMetrics>) -> Arc<Self> {
     let tokenizer = tiktoken_rs::cl100k_base().unwrap();
 
     Arc::new(Self {
+      db,
       ai_client,
       tokenizer: Arc::new(tokenizer),
       embedding_model: EmbeddingModel::TextEmbedding3Small,
+      metrics,
     })
   }
 }
@@ -44,16 +51,8 @@ impl Indexer for DocumentIndexer {
     collab: &Collab,
   ) -> Result<Vec<AFCollabEmbeddingParams>, AppError> {
     let object_id = collab.object_id().to_string();
-    let document = DocumentBody::from_collab(collab).ok_or_else(|| {
-      anyhow!(
-        "Failed to get document body from collab `{}`: schema is missing required fields",
-        object_id
-      )
-    })?;
-
-    let result = document.to_plain_text(collab.transact(), false, true);
-    match result {
-      Ok(content) => {
+    match document_plain_text(collab)? {
+      Some(content) => {
         create_embedding(
           object_id,
           content,
@@ -62,13 +61,7 @@ impl Indexer for DocumentIndexer {
         )
         .await
       },
-      Err(err) => {
-        if matches!(err, DocumentError::NoRequiredData) {
-          Ok(vec![])
-        } else {
-          Err(AppError::Internal(err.into()))
-        }
-      },
+      None => Ok(vec![]),
     }
   }
 
@@ -84,15 +77,52 @@ impl Indexer for DocumentIndexer {
   async fn embeddings(
     &self,
     mut params: Vec<AFCollabEmbeddingParams>,
+    reembed: bool,
   ) -> Result<Option<AFCollabEmbeddings>, AppError> {
     let object_id = match params.first() {
-      None => return Ok(None),
+      // a document without content has no fragments, and the stored ones are removed
+      None => {
+        return Ok(Some(AFCollabEmbeddings {
+          tokens_consumed: 0,
+          params,
+        }))
+      },
       Some(first) => first.object_id.clone(),
     };
-    let contents: Vec<_> = params
-      .iter()
-      .map(|fragment| fragment.content.clone())
-      .collect();
+
+    // Fragment ids are derived from the fragment content, so fragments that are already stored
+    // for this document didn't change since the last indexing and can keep their embeddings.
+    let existing = if reembed {
+      Default::default()
+    } else {
+      select_collab_embedding_fragment_ids(&self.db, &object_id).await?
+    };
+    let mut indices = Vec::with_capacity(params.len());
+    let mut contents = Vec::with_capacity(params.len());
+    let mut tokens_saved = 0;
+    for (i, fragment) in params.iter().enumerate() {
+      if existing.contains(&fragment.fragment_id) {
+        tokens_saved += estimate_tokens(&fragment.content);
+      } else {
+        indices.push(i);
+        contents.push(fragment.content.clone());
+      }
+    }
+    let reused = params.len() - contents.len();
+    if contents.is_empty() {
+      trace!(
+        "[Embedding] all {} fragments of document {} are up to date",
+        params.len(),
+        object_id
+      );
+      self
+        .metrics
+        .record_embeddings(0, reused as u64, 0, tokens_saved);
+      return Ok(Some(AFCollabEmbeddings {
+        tokens_consumed: 0,
+        params,
+      }));
+    }
 
     let resp = self
       .ai_client
@@ -105,13 +135,14 @@ impl Indexer for DocumentIndexer {
       })
       .await?;
     trace!(
-      "[Embedding] request {} embeddings, received {} embeddings",
-      params.len(),
-      resp.data.len()
+      "[Embedding] request {} embeddings, received {} embeddings, reused {} fragments",
+      indices.len(),
+      resp.data.len(),
+      reused
     );
 
     for embedding in resp.data {
-      let param = &mut params[embedding.index as usize];
+      let param = &mut params[indices[embedding.index as usize]];
       let embedding: Vec<f32> = match embedding.embedding {
         EmbeddingOutput::Float(embedding) => embedding.into_iter().map(|f| f as f32).collect(),
         EmbeddingOutput::Base64(_) => {
@@ -124,10 +155,17 @@ impl Indexer for DocumentIndexer {
     }
 
     tracing::info!(
-      "received {} embeddings for document {} - tokens used: {}",
-      params.len(),
+      "received {} embeddings for document {} - tokens used: {}, tokens saved: ~{}",
+      indices.len(),
       object_id,
-      resp.total_tokens
+      resp.total_tokens,
+      tokens_saved
+    );
+    self.metrics.record_embeddings(
+      indices.len() as u64,
+      reused as u64,
+      resp.total_tokens as u64,
+      tokens_saved,
     );
     Ok(Some(AFCollabEmbeddings {
       tokens_consumed: resp.total_tokens as u32,
@@ -136,6 +174,22 @@ impl Indexer for DocumentIndexer {
   }
 }
 
+/// Returns the plain text content of a document collab, or `None` if the document has no content.
+pub(crate) fn document_plain_text(collab: &Collab) -> Result<Option<String>, AppError> {
+  let document = DocumentBody::from_collab(collab).ok_or_else(|| {
+    anyhow!(
+      "Failed to get document body from collab `{}`: schema is missing required fields",
+      collab.object_id()
+    )
+  })?;
+
+  match document.to_plain_text(collab.transact(), false, true) {
+    Ok(content) => Ok(Some(content)),
+    Err(DocumentError::NoRequiredData) => Ok(None),
+    Err(err) => Err(AppError::Internal(err.into())),
+  }
+}
+
 async fn create_embedding(
   object_id: String,
   content: String,
@@ -148,18 +202,42 @@ async fn create_embedding(
   ));
   // We assume that every token is ~4 bytes. We're going to split document content into fragments
   // of ~2000 tokens each.
-  let split_contents = split_text_by_max_content_len(content, 8000)?;
+  let split_contents = split_text_by_paragraphs(content, 8000)?;
+  let mut seen = HashSet::new();
   Ok(
     split_contents
       .into_iter()
-      .map(|content| AFCollabEmbeddingParams {
-        fragment_id: Uuid::new_v4().to_string(),
-        object_id: object_id.clone(),
-        collab_type: collab_type.clone(),
-        content_type: EmbeddingContentType::PlainText,
-        content,
-        embedding: None,
+      .filter_map(|content| {
+        let fragment_id = fragment_id(&object_id, &content);
+        // identical fragments within the same document would share the same id
+        if !seen.insert(fragment_id.clone()) {
+          return None;
+        }
+        Some(AFCollabEmbeddingParams {
+          fragment_id,
+          object_id: object_id.clone(),
+          collab_type: collab_type.clone(),
+          content_type: EmbeddingContentType::PlainText,
+          content,
+          embedding: None,
+        })
       })
       .collect(),
   )
 }
+
+/// Fragment id is a hash of the fragment content, scoped to its document. This way the same
+/// content always maps onto the same fragment, which lets us skip re-embedding unchanged fragments.
+#[inline]
+fn fragment_id(object_id: &str, content: &str) -> String {
+  let mut context = md5::Context::new();
+  context.consume(object_id.as_bytes());
+  context.consume(content.as_bytes());
+  format!("{:x}", context.compute())
+}
+
+/// We assume that every token is ~4 bytes.
+#[inline]
+fn estimate_tokens(content: &str) -> u64 {
+  (content.len() as u64).div_ceil(4)
+}
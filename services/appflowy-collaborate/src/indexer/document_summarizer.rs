@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{AIModel, CompletionType, CustomPrompt};
+use chrono::{DateTime, Utc};
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use database::ai_usage::{consume_workspace_ai_request, record_workspace_ai_tokens};
+use database::index::{select_collab_summary, upsert_collab_summary, AFCollabSummary};
+use sqlx::PgPool;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::indexer::document_indexer::document_plain_text;
+
+/// Documents shorter than this (in characters) are not worth summarizing.
+const MIN_SUMMARY_CONTENT_LEN: usize = 200;
+/// Only the beginning of a long document is sent to the AI service.
+const MAX_SUMMARY_INPUT_LEN: usize = 8000;
+/// Edits changing the document length by fewer characters than this only refresh the summary
+/// once it gets older than [SUMMARY_REFRESH_INTERVAL].
+const MIN_SUMMARY_CONTENT_CHANGE: usize = 100;
+const SUMMARY_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// A document being edited is summarized at most once per interval, however much it changes.
+const MIN_SUMMARY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const SUMMARY_PROMPT: &str = "Summarize the following document in at most three sentences. \
+Write the summary in the language of the document. Reply with the summary only.";
+
+/// Maintains AI generated summaries of documents. Summaries are regenerated only when the content
+/// of a document changed meaningfully since its last summary.
+pub struct DocumentSummarizer {
+  db: PgPool,
+  ai_client: AppFlowyAIClient,
+}
+
+impl DocumentSummarizer {
+  pub fn new(db: PgPool, ai_client: AppFlowyAIClient) -> Arc<Self> {
+    Arc::new(Self { db, ai_client })
+  }
+
+  pub async fn summarize(
+    &self,
+    workspace_id: &Uuid,
+    object_id: &str,
+    encoded_collab: EncodedCollab,
+  ) -> Result<(), AppError> {
+    let collab = Collab::new_with_source(
+      CollabOrigin::Empty,
+      object_id,
+      DataSource::DocStateV1(encoded_collab.doc_state.into()),
+      vec![],
+      false,
+    )
+    .map_err(|err| AppError::Internal(err.into()))?;
+    let content = match document_plain_text(&collab)? {
+      Some(content) => content,
+      None => return Ok(()),
+    };
+    let content = content.trim();
+    let content_len = content.chars().count();
+    if content_len < MIN_SUMMARY_CONTENT_LEN {
+      return Ok(());
+    }
+
+    let content_hash = format!("{:x}", md5::compute(content.as_bytes()));
+    let existing = select_collab_summary(&self.db, object_id).await?;
+    if !should_summarize(existing.as_ref(), &content_hash, content_len, Utc::now()) {
+      trace!("[Summary] summary of document {} is up to date", object_id);
+      return Ok(());
+    }
+
+    consume_workspace_ai_request(&self.db, workspace_id).await?;
+    let input: String = content.chars().take(MAX_SUMMARY_INPUT_LEN).collect();
+    let resp = self
+      .ai_client
+      .completion_text(
+        &input,
+        None::<CompletionType>,
+        Some(CustomPrompt {
+          system: SUMMARY_PROMPT.to_string(),
+          user: None,
+        }),
+        AIModel::DefaultModel,
+      )
+      .await?;
+    if let Some(usage) = &resp.usage {
+      record_workspace_ai_tokens(&self.db, workspace_id, usage.total_tokens).await?;
+    }
+
+    let summary = resp.text.trim();
+    if summary.is_empty() {
+      return Ok(());
+    }
+    upsert_collab_summary(
+      &self.db,
+      workspace_id,
+      object_id,
+      summary,
+      &content_hash,
+      content_len as i32,
+    )
+    .await?;
+    trace!("[Summary] updated summary of document {}", object_id);
+    Ok(())
+  }
+}
+
+fn should_summarize(
+  existing: Option<&AFCollabSummary>,
+  content_hash: &str,
+  content_len: usize,
+  now: DateTime<Utc>,
+) -> bool {
+  match existing {
+    None => true,
+    Some(existing) if existing.content_hash == content_hash => false,
+    Some(existing) => {
+      let age = (now - existing.updated_at).to_std().unwrap_or_default();
+      if age < MIN_SUMMARY_INTERVAL {
+        return false;
+      }
+      let len_change = content_len.abs_diff(existing.content_len.max(0) as usize);
+      len_change >= MIN_SUMMARY_CONTENT_CHANGE || age >= SUMMARY_REFRESH_INTERVAL
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn summary(content_hash: &str, content_len: i32, updated_at: DateTime<Utc>) -> AFCollabSummary {
+    AFCollabSummary {
+      oid: "doc".to_string(),
+      summary: "summary".to_string(),
+      content_hash: content_hash.to_string(),
+      content_len,
+      updated_at,
+    }
+  }
+
+  #[test]
+  fn summarize_only_meaningful_changes() {
+    let now = Utc::now();
+    assert!(should_summarize(None, "a", 500, now));
+
+    let just_updated = summary("a", 500, now - chrono::Duration::minutes(5));
+    assert!(!should_summarize(Some(&just_updated), "b", 600, now));
+    assert!(!should_summarize(Some(&just_updated), "b", 350, now));
+
+    let recent = summary("a", 500, now - chrono::Duration::minutes(20));
+    assert!(!should_summarize(Some(&recent), "a", 500, now));
+    assert!(!should_summarize(Some(&recent), "b", 520, now));
+    assert!(should_summarize(Some(&recent), "b", 600, now));
+    assert!(should_summarize(Some(&recent), "b", 350, now));
+
+    let old = summary("a", 500, now - chrono::Duration::hours(2));
+    assert!(!should_summarize(Some(&old), "a", 500, now));
+    assert!(should_summarize(Some(&old), "b", 501, now));
+  }
+}
@@ -1,6 +1,8 @@
 mod document_indexer;
+mod document_summarizer;
 mod open_ai;
 mod provider;
 
 pub use document_indexer::DocumentIndexer;
+pub use document_summarizer::DocumentSummarizer;
 pub use provider::*;
@@ -125,10 +125,54 @@ pub fn split_text_by_max_content_len(
   Ok(result)
 }
 
+/// Splits content into chunks of at most `max_content_len` bytes, aligned to paragraph (newline)
+/// boundaries whenever possible. Paragraphs longer than `max_content_len` are split further with
+/// [split_text_by_max_content_len].
+///
+/// Compared to splitting at fixed offsets, paragraph alignment keeps chunk boundaries stable
+/// between edits: changing a single paragraph only changes the chunk which contains it, so the
+/// remaining chunks keep the same content hash and don't need to be embedded again.
+pub fn split_text_by_paragraphs(
+  content: String,
+  max_content_len: usize,
+) -> Result<Vec<String>, AppError> {
+  if content.is_empty() {
+    return Ok(vec![]);
+  }
+
+  if content.len() <= max_content_len {
+    return Ok(vec![content]);
+  }
+
+  let mut result = Vec::new();
+  let mut fragment = String::with_capacity(max_content_len);
+  for paragraph in content.split_inclusive('\n') {
+    if fragment.len() + paragraph.len() > max_content_len && !fragment.is_empty() {
+      result.push(std::mem::take(&mut fragment));
+    }
+
+    if paragraph.len() > max_content_len {
+      result.extend(split_text_by_max_content_len(
+        paragraph.to_string(),
+        max_content_len,
+      )?);
+    } else {
+      fragment.push_str(paragraph);
+    }
+  }
+
+  if !fragment.is_empty() {
+    result.push(fragment);
+  }
+  Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
 
-  use crate::indexer::open_ai::{split_text_by_max_content_len, split_text_by_max_tokens};
+  use crate::indexer::open_ai::{
+    split_text_by_max_content_len, split_text_by_max_tokens, split_text_by_paragraphs,
+  };
   use tiktoken_rs::cl100k_base;
 
   #[test]
@@ -295,6 +339,47 @@ mod tests {
     let reconstructed_content: String = params.concat();
     assert_eq!(reconstructed_content, content);
   }
+
+  #[test]
+  fn test_split_by_paragraphs_keeps_paragraphs_together() {
+    let content = "first paragraph\nsecond paragraph\nthird paragraph\n".to_string();
+    let chunks = split_text_by_paragraphs(content.clone(), 35).unwrap();
+    assert_eq!(
+      chunks,
+      vec!["first paragraph\nsecond paragraph\n", "third paragraph\n"]
+    );
+    assert_eq!(chunks.concat(), content);
+  }
+
+  #[test]
+  fn test_split_by_paragraphs_edit_only_changes_one_chunk() {
+    let paragraphs: Vec<String> = (0..10)
+      .map(|i| format!("paragraph number {}\n", i))
+      .collect();
+    let original = split_text_by_paragraphs(paragraphs.concat(), 40).unwrap();
+
+    let mut edited = paragraphs.clone();
+    edited[5] = "paragraph number 5!\n".to_string();
+    let edited = split_text_by_paragraphs(edited.concat(), 40).unwrap();
+
+    assert_eq!(original.len(), edited.len());
+    let changed = original
+      .iter()
+      .zip(edited.iter())
+      .filter(|(a, b)| a != b)
+      .count();
+    assert_eq!(changed, 1);
+  }
+
+  #[test]
+  fn test_split_by_paragraphs_long_paragraph() {
+    let content = format!("short\n{}\nshort", "a".repeat(25));
+    let chunks = split_text_by_paragraphs(content.clone(), 10).unwrap();
+    for chunk in &chunks {
+      assert!(chunk.len() <= 10);
+    }
+    assert_eq!(chunks.concat(), content);
+  }
 }
 
 // #[cfg(test)]
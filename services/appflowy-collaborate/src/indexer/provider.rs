@@ -1,5 +1,3 @@
-use actix::dev::Stream;
-use async_stream::try_stream;
 use async_trait::async_trait;
 use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
@@ -8,21 +6,29 @@ use collab::preclude::Collab;
 use collab_entity::CollabType;
 use sqlx::PgPool;
 use std::collections::HashMap;
-use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio_stream::StreamExt;
+use std::time::{Duration, Instant};
 use tracing::info;
 use uuid::Uuid;
 
+use crate::collab::cache::CollabCache;
 use crate::config::get_env_var;
-use crate::indexer::DocumentIndexer;
+use crate::indexer::{DocumentIndexer, DocumentSummarizer};
+use crate::metrics::EmbeddingMetrics;
 use app_error::AppError;
 use appflowy_ai_client::client::AppFlowyAIClient;
-use database::collab::{CollabStorage, GetCollabOrigin};
-use database::index::{get_collabs_without_embeddings, upsert_collab_embeddings};
+use collab_stream::indexing_queue::{IndexingJob, IndexingQueue, IndexingTask, RetryOutcome};
+use database::ai_usage::check_workspace_ai_quota;
+use database::index::{
+  cancel_workspace_index_rebuild, get_collabs_without_embeddings,
+  get_workspace_collabs_for_indexing, record_workspace_index_rebuild_progress,
+  select_workspace_index_rebuild, start_workspace_index_rebuild, upsert_collab_embeddings,
+  upsert_collab_index_error,
+};
 use database::workspace::select_workspace_settings;
-use database_entity::dto::{AFCollabEmbeddingParams, AFCollabEmbeddings, CollabParams};
+use database_entity::dto::{
+  AFCollabEmbeddingParams, AFCollabEmbeddings, AFIndexRebuildProgress, CollabParams, QueryCollab,
+};
 
 #[async_trait]
 pub trait Indexer: Send + Sync {
@@ -38,15 +44,19 @@ pub trait Indexer: Send + Sync {
     collab_type: CollabType,
   ) -> Result<Vec<AFCollabEmbeddingParams>, AppError>;
 
+  /// Embeds the fragments. Fragments which are already stored keep their embeddings, unless
+  /// `reembed` is set, ie. after the embedding model has changed.
   async fn embeddings(
     &self,
     params: Vec<AFCollabEmbeddingParams>,
+    reembed: bool,
   ) -> Result<Option<AFCollabEmbeddings>, AppError>;
 
   async fn index(
     &self,
     object_id: &str,
     encoded_collab: EncodedCollab,
+    reembed: bool,
   ) -> Result<Option<AFCollabEmbeddings>, AppError> {
     let collab = Collab::new_with_source(
       CollabOrigin::Empty,
@@ -57,19 +67,37 @@ pub trait Indexer: Send + Sync {
     )
     .map_err(|err| AppError::Internal(err.into()))?;
     let embedding_params = self.embedding_params(&collab).await?;
-    self.embeddings(embedding_params).await
+    self.embeddings(embedding_params, reembed).await
   }
 }
 
+/// Delay before an enqueued indexing job is processed. It gives pending collab writes time to be
+/// flushed and merges frequent updates of the same collab into a single job.
+const INDEXING_JOB_DELAY: Duration = Duration::from_secs(5);
+/// Interval of polling the indexing queue when there are no jobs to process.
+const INDEXING_QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Interval of claiming the jobs left unacknowledged by consumers which stopped.
+const INDEXING_QUEUE_CLAIM_INTERVAL: Duration = Duration::from_secs(60);
+
 /// A structure responsible for resolving different [Indexer] types for different [CollabType]s,
 /// including access permission checks for the specific workspaces.
 pub struct IndexerProvider {
   db: PgPool,
   indexer_cache: HashMap<CollabType, Arc<dyn Indexer>>,
+  /// Keeps AI summaries of documents up to date, regenerated together with their embeddings.
+  summarizer: Option<Arc<DocumentSummarizer>>,
+  /// Queue of the producers. Consumers have their own queue.
+  queue: tokio::sync::Mutex<IndexingQueue>,
+  metrics: Arc<EmbeddingMetrics>,
 }
 
 impl IndexerProvider {
-  pub fn new(db: PgPool, ai_client: AppFlowyAIClient) -> Arc<Self> {
+  pub fn new(
+    db: PgPool,
+    ai_client: AppFlowyAIClient,
+    queue: IndexingQueue,
+    metrics: Arc<EmbeddingMetrics>,
+  ) -> Arc<Self> {
     let mut cache: HashMap<CollabType, Arc<dyn Indexer>> = HashMap::new();
     let enabled = get_env_var("APPFLOWY_INDEXER_ENABLED", "true")
       .parse::<bool>()
@@ -77,11 +105,22 @@ impl IndexerProvider {
 
     info!("Indexer is enabled: {}", enabled);
     if enabled {
-      cache.insert(CollabType::Document, DocumentIndexer::new(ai_client));
+      cache.insert(
+        CollabType::Document,
+        DocumentIndexer::new(db.clone(), ai_client.clone(), metrics.clone()),
+      );
     }
+    let summary_enabled = get_env_var("APPFLOWY_SUMMARY_ENABLED", "true")
+      .parse::<bool>()
+      .unwrap_or(true);
+    info!("Document summaries are enabled: {}", summary_enabled);
+    let summarizer = summary_enabled.then(|| DocumentSummarizer::new(db.clone(), ai_client));
     Arc::new(Self {
       db,
       indexer_cache: cache,
+      summarizer,
+      queue: tokio::sync::Mutex::new(queue),
+      metrics,
     })
   }
 
@@ -94,6 +133,12 @@ impl IndexerProvider {
     }
   }
 
+  /// Returns [AppError::AIQuotaExceeded] if the workspace has used up its AI quotas.
+  pub async fn check_ai_quota(&self, workspace_id: &str) -> Result<(), AppError> {
+    let uuid = Uuid::parse_str(workspace_id)?;
+    check_workspace_ai_quota(&self.db, &uuid).await
+  }
+
   /// Returns indexer for a specific type of [Collab] object.
   /// If collab of given type is not supported or workspace it belongs to has indexing disabled,
   /// returns `None`.
@@ -101,83 +146,427 @@ impl IndexerProvider {
     self.indexer_cache.get(collab_type).cloned()
   }
 
-  fn get_unindexed_collabs(
+  /// Enqueues indexing jobs for all collabs which have no embeddings yet.
+  pub async fn handle_unindexed_collabs(indexer: Arc<Self>) {
+    let collabs = match get_collabs_without_embeddings(&indexer.db).await {
+      Ok(collabs) => collabs,
+      Err(err) => {
+        tracing::error!("failed to get unindexed collabs: {}", err);
+        return;
+      },
+    };
+    let mut i = 0;
+    for cid in collabs {
+      let job = IndexingJob::new(cid.workspace_id.to_string(), cid.object_id, cid.collab_type);
+      // the queue is locked per job, not to hold back the other producers
+      let result = indexer
+        .queue
+        .lock()
+        .await
+        .enqueue(job, Duration::ZERO)
+        .await;
+      match result {
+        Ok(true) => i += 1,
+        Ok(false) => { /* already enqueued */ },
+        Err(err) => {
+          tracing::error!("failed to enqueue unindexed collab: {}", err);
+          return;
+        },
+      }
+    }
+    tracing::info!("enqueued {} unindexed collabs after restart", i);
+  }
+
+  /// Enqueues a collab to be indexed in the background. Collabs of types which are not supported by
+  /// any indexer nor summarized are ignored.
+  pub async fn enqueue_indexing(
     &self,
-    storage: Arc<dyn CollabStorage>,
-  ) -> Pin<Box<dyn Stream<Item = Result<UnindexedCollab, anyhow::Error>> + Send>> {
-    let db = self.db.clone();
-
-    Box::pin(try_stream! {
-      let collabs = get_collabs_without_embeddings(&db).await?;
-      if !collabs.is_empty() {
-        tracing::info!("found {} unindexed collabs", collabs.len());
+    workspace_id: &str,
+    object_id: &str,
+    collab_type: &CollabType,
+  ) -> Result<(), AppError> {
+    let summarized = self.summarizer.is_some() && collab_type == &CollabType::Document;
+    if !self.indexer_cache.contains_key(collab_type) && !summarized {
+      return Ok(());
+    }
+    let job = IndexingJob::new(
+      workspace_id.to_string(),
+      object_id.to_string(),
+      collab_type.clone(),
+    );
+    self
+      .queue
+      .lock()
+      .await
+      .enqueue(job, INDEXING_JOB_DELAY)
+      .await
+      .map_err(|err| AppError::Internal(err.into()))?;
+    Ok(())
+  }
+
+  /// Processes jobs from the indexing queue. Failed jobs are retried with an exponential backoff
+  /// and eventually moved to the dead-letter list. The consumer has its own `queue`, and its name
+  /// must be unique amongst the running consumers.
+  pub async fn run_indexing_queue(
+    indexer: Arc<Self>,
+    collab_cache: CollabCache,
+    mut queue: IndexingQueue,
+    consumer: String,
+  ) {
+    let batch_size = get_env_var("APPFLOWY_INDEXER_QUEUE_BATCH_SIZE", "10")
+      .parse::<usize>()
+      .unwrap_or(10);
+    info!("Indexing queue consumer {} started", consumer);
+
+    let mut last_claim: Option<Instant> = None;
+    loop {
+      if last_claim.map_or(true, |last_claim| {
+        last_claim.elapsed() >= INDEXING_QUEUE_CLAIM_INTERVAL
+      }) {
+        last_claim = Some(Instant::now());
+        indexer
+          .claim_unacked_tasks(&mut queue, &collab_cache, &consumer, batch_size)
+          .await;
       }
-      for cid in collabs {
-        match &cid.collab_type {
-          CollabType::Document => {
-            let collab = storage
-              .get_encode_collab(GetCollabOrigin::Server, cid.clone().into(), false)
-              .await?;
-
-            yield UnindexedCollab {
-              workspace_id: cid.workspace_id,
-              object_id: cid.object_id,
-              collab_type: cid.collab_type,
-              collab,
-            };
-          },
-          CollabType::Database
-          | CollabType::WorkspaceDatabase
-          | CollabType::Folder
-          | CollabType::DatabaseRow
-          | CollabType::UserAwareness
-          | CollabType::Unknown => { /* atm. only document types are supported */ },
-        }
+      match queue.consume(&consumer, batch_size).await {
+        Ok(tasks) if tasks.is_empty() => tokio::time::sleep(INDEXING_QUEUE_POLL_INTERVAL).await,
+        Ok(tasks) => {
+          indexer
+            .handle_indexing_tasks(&mut queue, &collab_cache, tasks)
+            .await
+        },
+        Err(err) => {
+          tracing::error!("failed to consume indexing jobs: {}", err);
+          if err.is_stream_not_exist() {
+            if let Err(err) = queue.ensure_consumer_group().await {
+              tracing::error!("failed to recreate indexing queue: {}", err);
+            }
+          }
+          tokio::time::sleep(INDEXING_QUEUE_POLL_INTERVAL * 5).await;
+        },
       }
-    })
+    }
   }
 
-  pub async fn handle_unindexed_collabs(indexer: Arc<Self>, storage: Arc<dyn CollabStorage>) {
-    let start = Instant::now();
-    let mut i = 0;
-    let mut stream = indexer.get_unindexed_collabs(storage);
-    while let Some(result) = stream.next().await {
-      match result {
-        Ok(collab) => {
-          let workspace = collab.workspace_id;
-          let oid = collab.object_id.clone();
-          if let Err(err) = Self::index_collab(&indexer, collab).await {
-            // only logging error in debug mode. Will be enabled in production if needed.
-            if cfg!(debug_assertions) {
-              tracing::warn!("failed to index collab {}/{}: {}", workspace, oid, err);
-            }
-          } else {
-            i += 1;
+  /// Processes the jobs left unacknowledged by consumers which stopped before finishing them.
+  async fn claim_unacked_tasks(
+    &self,
+    queue: &mut IndexingQueue,
+    collab_cache: &CollabCache,
+    consumer: &str,
+    batch_size: usize,
+  ) {
+    loop {
+      match queue.claim_unacked(consumer, batch_size).await {
+        Ok(tasks) if tasks.is_empty() => break,
+        Ok(tasks) => {
+          tracing::info!("claimed {} unacknowledged indexing jobs", tasks.len());
+          self.handle_indexing_tasks(queue, collab_cache, tasks).await
+        },
+        Err(err) => {
+          tracing::error!("failed to claim unacknowledged indexing jobs: {}", err);
+          break;
+        },
+      }
+    }
+  }
+
+  async fn handle_indexing_tasks(
+    &self,
+    queue: &mut IndexingQueue,
+    collab_cache: &CollabCache,
+    tasks: Vec<IndexingTask>,
+  ) {
+    let (mut processed, mut retried, mut dead_lettered) = (0, 0, 0);
+    for task in tasks {
+      let job = task.job.clone();
+      // whether the job is done, and whether it failed
+      let outcome = match self.handle_indexing_job(collab_cache, &task.job).await {
+        Ok(_) => {
+          if let Err(err) = queue.ack(&[task]).await {
+            tracing::error!("failed to acknowledge indexing job: {}", err);
           }
+          processed += 1;
+          Some(false)
+        },
+        Err(err) if err.is_ai_quota_exceeded() => {
+          // retrying won't help until the quota is renewed, the collab will be indexed again
+          // after restart or when it's modified
+          tracing::debug!("skip indexing collab {}: {}", task.job.object_id, err);
+          if let Err(err) = queue.ack(&[task]).await {
+            tracing::error!("failed to acknowledge indexing job: {}", err);
+          }
+          Some(true)
         },
         Err(err) => {
-          tracing::error!("failed to get unindexed document: {}", err);
+          let object_id = task.job.object_id.clone();
+          match queue.retry(task, &err.to_string()).await {
+            Ok(RetryOutcome::Scheduled(delay)) => {
+              tracing::warn!(
+                "failed to index collab {}, retrying in {:?}: {}",
+                object_id,
+                delay,
+                err
+              );
+              retried += 1;
+              None
+            },
+            Ok(RetryOutcome::DeadLettered) => {
+              dead_lettered += 1;
+              Some(true)
+            },
+            Ok(RetryOutcome::Superseded) => {
+              /* newer job is already enqueued */
+              None
+            },
+            Err(queue_err) => {
+              tracing::error!(
+                "failed to retry indexing job of {}: {}",
+                object_id,
+                queue_err
+              );
+              None
+            },
+          }
         },
+      };
+      if let (Some(rebuild_id), Some(failed)) = (&job.rebuild_id, outcome) {
+        self.record_rebuild_progress(&job, rebuild_id, failed).await;
       }
     }
+    self
+      .metrics
+      .record_indexing_jobs(processed, retried, dead_lettered);
+  }
+
+  async fn record_rebuild_progress(&self, job: &IndexingJob, rebuild_id: &Uuid, failed: bool) {
+    let result = match Uuid::parse_str(&job.workspace_id) {
+      Ok(workspace_id) => record_workspace_index_rebuild_progress(
+        &self.db,
+        &workspace_id,
+        rebuild_id,
+        1,
+        i64::from(failed),
+      )
+      .await
+      .map_err(AppError::from),
+      Err(err) => Err(err.into()),
+    };
+    if let Err(err) = result {
+      tracing::warn!(
+        "failed to record re-indexing progress of {}: {}",
+        job.workspace_id,
+        err
+      );
+    }
+  }
+
+  async fn handle_indexing_job(
+    &self,
+    collab_cache: &CollabCache,
+    job: &IndexingJob,
+  ) -> Result<(), AppError> {
+    if !self.can_index_workspace(&job.workspace_id).await? {
+      tracing::trace!("workspace {} indexing is disabled", job.workspace_id);
+      return Ok(());
+    }
+    let workspace_id = Uuid::parse_str(&job.workspace_id)?;
+    if let Some(rebuild_id) = &job.rebuild_id {
+      let rebuild = select_workspace_index_rebuild(&self.db, &workspace_id).await?;
+      let in_progress =
+        rebuild.is_some_and(|rebuild| &rebuild.rebuild_id == rebuild_id && !rebuild.is_finished());
+      if !in_progress {
+        tracing::trace!("skip job of cancelled re-indexing of {}", job.workspace_id);
+        return Ok(());
+      }
+    }
+    let query = QueryCollab::new(job.object_id.clone(), job.collab_type.clone());
+    let collab = match collab_cache
+      .get_encode_collab(&job.workspace_id, query)
+      .await
+    {
+      Ok(collab) => collab,
+      Err(err) if err.is_record_not_found() => {
+        // collab has been deleted in the meantime
+        return Ok(());
+      },
+      Err(err) => return Err(err),
+    };
+    if let Some(summarizer) = &self.summarizer {
+      if job.collab_type == CollabType::Document {
+        // summaries are best effort, failing to update one must not fail indexing of the document
+        if let Err(err) = summarizer
+          .summarize(&workspace_id, &job.object_id, collab.clone())
+          .await
+        {
+          if err.is_ai_quota_exceeded() {
+            tracing::debug!("skip summarizing document {}: {}", job.object_id, err);
+          } else {
+            tracing::warn!("failed to summarize document {}: {}", job.object_id, err);
+          }
+        }
+      }
+    }
+    self
+      .try_index_collab(
+        UnindexedCollab {
+          workspace_id,
+          object_id: job.object_id.clone(),
+          collab_type: job.collab_type.clone(),
+          collab,
+        },
+        job.rebuild_id.is_some(),
+      )
+      .await
+  }
+
+  /// Starts re-indexing all documents of a given workspace, by enqueuing them into the indexing
+  /// queue. If re-indexing of that workspace is already in progress, returns its current progress
+  /// instead.
+  pub async fn rebuild_workspace_index(
+    &self,
+    workspace_id: Uuid,
+  ) -> Result<AFIndexRebuildProgress, AppError> {
+    if self.indexer_cache.is_empty() {
+      return Err(AppError::ServiceTemporaryUnavailable(
+        "indexing is disabled on this server".to_string(),
+      ));
+    }
+    if !self.can_index_workspace(&workspace_id.to_string()).await? {
+      return Err(AppError::InvalidRequest(format!(
+        "search indexing is disabled in workspace {}",
+        workspace_id
+      )));
+    }
+
+    let collabs = get_workspace_collabs_for_indexing(&self.db, &workspace_id).await?;
+    let rebuild_id = Uuid::new_v4();
+    let rebuild = match start_workspace_index_rebuild(
+      &self.db,
+      &workspace_id,
+      &rebuild_id,
+      collabs.len() as i64,
+    )
+    .await?
+    {
+      Some(rebuild) => rebuild,
+      None => {
+        // the rebuild which is in progress is returned
+        return select_workspace_index_rebuild(&self.db, &workspace_id)
+          .await?
+          .map(AFIndexRebuildProgress::from)
+          .ok_or_else(|| {
+            AppError::Internal(anyhow::anyhow!(
+              "re-indexing of workspace {} not found",
+              workspace_id
+            ))
+          });
+      },
+    };
     tracing::info!(
-      "indexed {} unindexed collabs in {:?} after restart",
-      i,
-      start.elapsed()
+      "re-indexing {} collabs of workspace {}",
+      collabs.len(),
+      workspace_id
     );
+
+    let mut enqueued = 0;
+    for cid in collabs.iter() {
+      let job = IndexingJob::rebuild(
+        workspace_id.to_string(),
+        cid.object_id.clone(),
+        cid.collab_type.clone(),
+        rebuild_id,
+      );
+      let result = self.queue.lock().await.enqueue(job, Duration::ZERO).await;
+      if let Err(err) = result {
+        tracing::error!(
+          "failed to enqueue re-indexing of workspace {}: {}",
+          workspace_id,
+          err
+        );
+        break;
+      }
+      enqueued += 1;
+    }
+    if enqueued < collabs.len() {
+      // the collabs which couldn't be enqueued won't be processed
+      let remaining = (collabs.len() - enqueued) as i64;
+      record_workspace_index_rebuild_progress(
+        &self.db,
+        &workspace_id,
+        &rebuild_id,
+        remaining,
+        remaining,
+      )
+      .await?;
+    }
+    Ok(rebuild.into())
+  }
+
+  /// Returns progress of the last re-indexing requested for a given workspace.
+  pub async fn rebuild_progress(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Option<AFIndexRebuildProgress>, AppError> {
+    let rebuild = select_workspace_index_rebuild(&self.db, workspace_id).await?;
+    Ok(rebuild.map(AFIndexRebuildProgress::from))
   }
 
-  async fn index_collab(&self, unindexed: UnindexedCollab) -> Result<(), AppError> {
+  /// Cancels re-indexing of a given workspace. Documents which have already been indexed keep their
+  /// new embeddings.
+  pub async fn cancel_rebuild(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Option<AFIndexRebuildProgress>, AppError> {
+    let rebuild = cancel_workspace_index_rebuild(&self.db, workspace_id).await?;
+    Ok(rebuild.map(AFIndexRebuildProgress::from))
+  }
+
+  /// Indexes given collab, recording an indexing error in case of failure.
+  async fn try_index_collab(
+    &self,
+    unindexed: UnindexedCollab,
+    reembed: bool,
+  ) -> Result<(), AppError> {
+    let workspace_id = unindexed.workspace_id;
+    let object_id = unindexed.object_id.clone();
+    let collab_type = unindexed.collab_type.clone();
+    let result = self.index_collab(unindexed, reembed).await;
+    if let Err(err) = &result {
+      if let Err(db_err) = upsert_collab_index_error(
+        &self.db,
+        &workspace_id,
+        &object_id,
+        &collab_type,
+        &err.to_string(),
+      )
+      .await
+      {
+        tracing::warn!(
+          "failed to record indexing error of {}/{}: {}",
+          workspace_id,
+          object_id,
+          db_err
+        );
+      }
+    }
+    result
+  }
+
+  async fn index_collab(&self, unindexed: UnindexedCollab, reembed: bool) -> Result<(), AppError> {
     if let Some(indexer) = self.indexer_cache.get(&unindexed.collab_type) {
       let workspace_id = unindexed.workspace_id;
+      check_workspace_ai_quota(&self.db, &workspace_id).await?;
       let embeddings = indexer
-        .index(&unindexed.object_id, unindexed.collab)
+        .index(&unindexed.object_id, unindexed.collab, reembed)
         .await?;
       if let Some(embeddings) = embeddings {
         let mut tx = self.db.begin().await?;
         upsert_collab_embeddings(
           &mut tx,
           &workspace_id,
+          &unindexed.object_id,
+          &unindexed.collab_type,
           embeddings.tokens_consumed,
           embeddings.params,
         )
@@ -190,19 +579,23 @@ impl IndexerProvider {
 
   pub async fn create_collab_embeddings(
     &self,
+    workspace_id: &str,
     params: &CollabParams,
   ) -> Result<Option<AFCollabEmbeddings>, AppError> {
     let collab_type = params.collab_type.clone();
     let data = params.encoded_collab_v1.clone();
 
     if let Some(indexer) = self.indexer_for(&collab_type) {
+      self.check_ai_quota(workspace_id).await?;
       let encoded_collab = tokio::task::spawn_blocking(move || {
         let encode_collab = EncodedCollab::decode_from_bytes(&data)?;
         Ok::<_, AppError>(encode_collab)
       })
       .await??;
 
-      let embeddings = indexer.index(&params.object_id, encoded_collab).await?;
+      let embeddings = indexer
+        .index(&params.object_id, encoded_collab, false)
+        .await?;
       Ok(embeddings)
     } else {
       Ok(None)
@@ -1,4 +1,5 @@
 pub mod actix_ws;
+pub mod ai_stream;
 pub mod api;
 pub mod application;
 mod client;
@@ -13,6 +14,8 @@ pub mod indexer;
 pub mod metrics;
 mod permission;
 mod pg_listener;
+pub mod reminder;
+pub mod row_history;
 mod rt_server;
 pub mod snapshot;
 mod state;
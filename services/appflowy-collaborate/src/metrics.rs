@@ -209,3 +209,77 @@ impl Default for CollabMetrics {
     }
   }
 }
+
+#[derive(Clone, Default)]
+pub struct EmbeddingMetrics {
+  /// Number of document fragments sent to the embedding service.
+  embedded_fragments_count: Counter,
+  /// Number of document fragments which didn't change since last indexing and were not embedded again.
+  reused_fragments_count: Counter,
+  /// Number of tokens consumed by the embedding service.
+  tokens_used: Counter,
+  /// Estimated number of tokens saved by not embedding unchanged fragments.
+  tokens_saved: Counter,
+  /// Number of indexing jobs processed successfully.
+  indexing_jobs_processed: Counter,
+  /// Number of failed indexing jobs scheduled to be retried.
+  indexing_jobs_retried: Counter,
+  /// Number of indexing jobs which exceeded the maximum number of attempts.
+  indexing_jobs_dead_lettered: Counter,
+}
+
+impl EmbeddingMetrics {
+  pub fn register(registry: &mut Registry) -> Self {
+    let metrics = Self::default();
+    let embedding_registry = registry.sub_registry_with_prefix("embedding");
+    embedding_registry.register(
+      "embedded_fragments_count",
+      "number of fragments sent to the embedding service",
+      metrics.embedded_fragments_count.clone(),
+    );
+    embedding_registry.register(
+      "reused_fragments_count",
+      "number of unchanged fragments which were not embedded again",
+      metrics.reused_fragments_count.clone(),
+    );
+    embedding_registry.register(
+      "tokens_used",
+      "number of tokens consumed by the embedding service",
+      metrics.tokens_used.clone(),
+    );
+    embedding_registry.register(
+      "tokens_saved",
+      "estimated number of tokens saved by skipping unchanged fragments",
+      metrics.tokens_saved.clone(),
+    );
+    embedding_registry.register(
+      "indexing_jobs_processed",
+      "number of indexing jobs processed successfully",
+      metrics.indexing_jobs_processed.clone(),
+    );
+    embedding_registry.register(
+      "indexing_jobs_retried",
+      "number of failed indexing jobs scheduled to be retried",
+      metrics.indexing_jobs_retried.clone(),
+    );
+    embedding_registry.register(
+      "indexing_jobs_dead_lettered",
+      "number of indexing jobs moved to the dead-letter list",
+      metrics.indexing_jobs_dead_lettered.clone(),
+    );
+    metrics
+  }
+
+  pub fn record_embeddings(&self, embedded: u64, reused: u64, tokens_used: u64, tokens_saved: u64) {
+    self.embedded_fragments_count.inc_by(embedded);
+    self.reused_fragments_count.inc_by(reused);
+    self.tokens_used.inc_by(tokens_used);
+    self.tokens_saved.inc_by(tokens_saved);
+  }
+
+  pub fn record_indexing_jobs(&self, processed: u64, retried: u64, dead_lettered: u64) {
+    self.indexing_jobs_processed.inc_by(processed);
+    self.indexing_jobs_retried.inc_by(retried);
+    self.indexing_jobs_dead_lettered.inc_by(dead_lettered);
+  }
+}
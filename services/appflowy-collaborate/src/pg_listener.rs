@@ -1,16 +1,29 @@
 use anyhow::Error;
+use collab_rt_entity::chat::ChatEvent;
 use database::listener::PostgresDBListener;
-use database::pg_row::AFUserNotification;
+use database::pg_row::{
+  AFChatNotification, AFReminderPgNotification, AFUserNotification, CHAT_NOTIFICATION_CHANNEL,
+  REMINDER_NOTIFICATION_CHANNEL,
+};
 use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
 
 pub struct PgListeners {
   user_listener: UserListener,
+  chat_listener: ChatListener,
+  reminder_listener: ReminderListener,
 }
 
 impl PgListeners {
   pub async fn new(pg_pool: &PgPool) -> Result<Self, Error> {
     let user_listener = UserListener::new(pg_pool, "af_user_channel").await?;
-    Ok(Self { user_listener })
+    let chat_listener = ChatListener::new(pg_pool, CHAT_NOTIFICATION_CHANNEL).await?;
+    let reminder_listener = ReminderListener::new(pg_pool, REMINDER_NOTIFICATION_CHANNEL).await?;
+    Ok(Self {
+      user_listener,
+      chat_listener,
+      reminder_listener,
+    })
   }
 
   pub fn subscribe_user_change(&self, uid: i64) -> tokio::sync::mpsc::Receiver<AFUserNotification> {
@@ -27,8 +40,56 @@ impl PgListeners {
     });
     rx
   }
+
+  /// Receive events of the shared chats the user participates in.
+  pub fn subscribe_chat_event(&self, uid: i64) -> tokio::sync::mpsc::Receiver<ChatEvent> {
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    let mut chat_notify = self.chat_listener.notify.subscribe();
+    tokio::spawn(async move {
+      loop {
+        match chat_notify.recv().await {
+          Ok(notification) => {
+            if notification.recipients.contains(&uid) && tx.send(notification.event).await.is_err()
+            {
+              break;
+            }
+          },
+          // answer chunks may be dropped if the receiver is too slow
+          Err(RecvError::Lagged(_)) => continue,
+          Err(RecvError::Closed) => break,
+        }
+      }
+    });
+    rx
+  }
+
+  /// Receive the reminders of the dates set by the user which are due.
+  pub fn subscribe_reminder(
+    &self,
+    uid: i64,
+  ) -> tokio::sync::mpsc::Receiver<AFReminderPgNotification> {
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    let mut reminder_notify = self.reminder_listener.notify.subscribe();
+    tokio::spawn(async move {
+      loop {
+        match reminder_notify.recv().await {
+          Ok(notification) => {
+            if notification.uid == uid && tx.send(notification).await.is_err() {
+              break;
+            }
+          },
+          // missed reminders are sent by email
+          Err(RecvError::Lagged(_)) => continue,
+          Err(RecvError::Closed) => break,
+        }
+      }
+    });
+    rx
+  }
 }
 
 // pub type CollabMemberListener = PostgresDBListener<CollabMemberNotification>;
 // pub type WorkspaceMemberListener = PostgresDBListener<WorkspaceMemberNotification>;
 pub type UserListener = PostgresDBListener<AFUserNotification>;
+pub type ChatListener = PostgresDBListener<AFChatNotification>;
+pub type ReminderListener = PostgresDBListener<AFReminderPgNotification>;
@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use collab::preclude::Collab;
+use collab_database::rows::{Cell, RowDetail};
+use collab_database::template::entity::CELL_DATA;
+use collab_document::blocks::DocumentData;
+use collab_document::document::DocumentBody;
+use collab_entity::CollabType;
+use collab_rt_entity::user::AFReminderObjectType;
+use database::reminder::{
+  delete_reminders, select_reminder_owner_timezones, select_user_notification_settings,
+  upsert_reminders, AFReminderParams,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+use yrs::Any;
+
+use crate::group::observer::{DebouncedObserver, GroupObserver, GroupRecorder, SnapshotObserver};
+
+/// Objects are scanned for reminders at most once within this delay, as the whole document is
+/// read to find its date mentions.
+const REMINDER_SCAN_DELAY: Duration = Duration::from_secs(3);
+/// Dates without time are reminded at this hour of the day of the user.
+const DAY_REMINDER_HOUR: u32 = 9;
+const REMINDER_TITLE_MAX_LEN: usize = 100;
+const DATE_CELL_INCLUDE_TIME: &str = "include_time";
+const DATE_CELL_REMINDER_ID: &str = "reminder_id";
+const MENTION_REMINDER_ID: &str = "reminder_id";
+
+/// Date of a reminder, as it's set in the database row or the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReminderDate {
+  /// A point in time, ie. the date of a date cell.
+  Timestamp(i64),
+  /// A time of the day of the user, ie. the date of a date mention.
+  Local(NaiveDateTime),
+}
+
+/// How long before its date a reminder is due.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReminderOffset {
+  Minutes(i64),
+  Days(i64),
+}
+
+impl ReminderOffset {
+  /// Parses the reminder option of a date mention, ie. `fiveMinsBefore`. Returns none when the
+  /// mention has no reminder.
+  fn from_option(option: &str) -> Option<Self> {
+    let offset = match option {
+      "none" => return None,
+      "fiveMinsBefore" => ReminderOffset::Minutes(5),
+      "tenMinsBefore" => ReminderOffset::Minutes(10),
+      "fifteenMinsBefore" => ReminderOffset::Minutes(15),
+      "thirtyMinsBefore" => ReminderOffset::Minutes(30),
+      "oneHourBefore" => ReminderOffset::Minutes(60),
+      "twoHoursBefore" => ReminderOffset::Minutes(120),
+      "oneDayBefore" => ReminderOffset::Days(1),
+      "twoDaysBefore" => ReminderOffset::Days(2),
+      "oneWeekBefore" => ReminderOffset::Days(7),
+      // at the time of the event, on its day, or a custom time set by the client
+      _ => ReminderOffset::Minutes(0),
+    };
+    Some(offset)
+  }
+
+  fn duration(&self) -> chrono::Duration {
+    match self {
+      ReminderOffset::Minutes(minutes) => chrono::Duration::minutes(*minutes),
+      ReminderOffset::Days(days) => chrono::Duration::days(*days),
+    }
+  }
+}
+
+/// A reminder set on a date cell of a database row or on a date mention of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateReminder {
+  pub reminder_id: String,
+  pub field_id: Option<String>,
+  pub block_id: Option<String>,
+  pub date: ReminderDate,
+  pub include_time: bool,
+  pub offset: ReminderOffset,
+  /// Text of the block of a date mention. Reminders of rows are titled by their primary value,
+  /// which isn't part of the row.
+  pub title: String,
+}
+
+impl DateReminder {
+  /// Returns the times of the reminder in the given time zone. Dates without time are reminded
+  /// at [DAY_REMINDER_HOUR] on their day.
+  pub fn resolve(&self, tz: Tz) -> AFReminderParams {
+    let (event_at, remind_at, local_event_at, local_remind_at) = match &self.date {
+      ReminderDate::Timestamp(timestamp) if self.include_time => {
+        let event_at = DateTime::from_timestamp(*timestamp, 0).unwrap_or_default();
+        (event_at, event_at - self.offset.duration(), None, None)
+      },
+      date => {
+        let local_event_at = match date {
+          ReminderDate::Timestamp(timestamp) => start_of_day(
+            DateTime::from_timestamp(*timestamp, 0)
+              .unwrap_or_default()
+              .with_timezone(&tz)
+              .date_naive(),
+          ),
+          ReminderDate::Local(local) if self.include_time => *local,
+          ReminderDate::Local(local) => start_of_day(local.date()),
+        };
+        let local_base = if self.include_time {
+          local_event_at
+        } else {
+          local_event_at
+            .date()
+            .and_hms_opt(DAY_REMINDER_HOUR, 0, 0)
+            .unwrap_or(local_event_at)
+        };
+        let local_remind_at = local_base - self.offset.duration();
+        (
+          local_to_utc(tz, local_event_at),
+          local_to_utc(tz, local_remind_at),
+          Some(local_event_at),
+          Some(local_remind_at),
+        )
+      },
+    };
+    AFReminderParams {
+      reminder_id: self.reminder_id.clone(),
+      field_id: self.field_id.clone(),
+      block_id: self.block_id.clone(),
+      title: self.title.clone(),
+      include_time: self.include_time,
+      event_at,
+      remind_at,
+      local_event_at,
+      local_remind_at,
+    }
+  }
+}
+
+fn start_of_day(date: NaiveDate) -> NaiveDateTime {
+  date.and_hms_opt(0, 0, 0).unwrap_or_default()
+}
+
+/// Times skipped by a daylight saving time change are moved an hour later.
+fn local_to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+  tz.from_local_datetime(&local)
+    .earliest()
+    .or_else(|| {
+      tz.from_local_datetime(&(local + chrono::Duration::hours(1)))
+        .earliest()
+    })
+    .map(|time| time.with_timezone(&Utc))
+    .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+/// Returns the time zone of a user, UTC when it's unknown.
+pub fn parse_timezone(timezone: &str) -> Tz {
+  timezone.parse::<Tz>().unwrap_or(Tz::UTC)
+}
+
+/// The reminders of a database row or a document.
+pub struct ObjectReminders {
+  pub object_type: AFReminderObjectType,
+  pub database_id: Option<String>,
+  pub reminders: Vec<DateReminder>,
+}
+
+/// Returns the reminders of a database row or a document, none for other collabs or when the
+/// collab hasn't been initialized.
+pub fn collab_reminders(collab_type: &CollabType, collab: &Collab) -> Option<ObjectReminders> {
+  match collab_type {
+    CollabType::DatabaseRow => {
+      let row_detail = RowDetail::from_collab(collab)?;
+      Some(ObjectReminders {
+        object_type: AFReminderObjectType::DatabaseRow,
+        database_id: Some(row_detail.row.database_id.clone()),
+        reminders: row_reminders(&row_detail.row.cells),
+      })
+    },
+    CollabType::Document => {
+      let document = DocumentBody::from_collab(collab)?;
+      let data = document.get_document_data(&collab.transact()).ok()?;
+      Some(ObjectReminders {
+        object_type: AFReminderObjectType::Document,
+        database_id: None,
+        reminders: document_reminders(&data),
+      })
+    },
+    _ => None,
+  }
+}
+
+/// Returns the reminders of the date cells of a row, by reminder id.
+pub fn row_reminders(cells: &HashMap<String, Cell>) -> Vec<DateReminder> {
+  let mut reminders: Vec<DateReminder> = cells
+    .iter()
+    .filter_map(|(field_id, cell)| {
+      let reminder_id = match cell.get(DATE_CELL_REMINDER_ID) {
+        Some(Any::String(reminder_id)) if !reminder_id.is_empty() => reminder_id.to_string(),
+        _ => return None,
+      };
+      let timestamp = match cell.get(CELL_DATA)? {
+        Any::String(timestamp) => timestamp.parse().ok()?,
+        Any::BigInt(timestamp) => *timestamp,
+        Any::Number(timestamp) => *timestamp as i64,
+        _ => return None,
+      };
+      Some(DateReminder {
+        reminder_id,
+        field_id: Some(field_id.clone()),
+        block_id: None,
+        date: ReminderDate::Timestamp(timestamp),
+        include_time: matches!(cell.get(DATE_CELL_INCLUDE_TIME), Some(Any::Bool(true))),
+        offset: ReminderOffset::Minutes(0),
+        title: String::new(),
+      })
+    })
+    .collect();
+  reminders.sort_by(|a, b| a.reminder_id.cmp(&b.reminder_id));
+  reminders
+}
+
+/// Returns the reminders of the date mentions of a document, by reminder id.
+pub fn document_reminders(data: &DocumentData) -> Vec<DateReminder> {
+  let text_map = match &data.meta.text_map {
+    Some(text_map) => text_map,
+    None => return vec![],
+  };
+  let block_id_by_text_id: HashMap<&str, &str> = data
+    .blocks
+    .values()
+    .filter_map(|block| Some((block.external_id.as_deref()?, block.id.as_str())))
+    .collect();
+
+  let mut reminders = vec![];
+  for (text_id, delta) in text_map {
+    // most texts have no reminder, and aren't parsed
+    if !delta.contains(MENTION_REMINDER_ID) {
+      continue;
+    }
+    let ops = match serde_json::from_str::<Value>(delta) {
+      Ok(Value::Array(ops)) => ops,
+      _ => continue,
+    };
+    let title = delta_text(&ops);
+    for op in &ops {
+      if let Some(mut reminder) = date_mention_reminder(op) {
+        reminder.block_id = block_id_by_text_id
+          .get(text_id.as_str())
+          .map(|block_id| block_id.to_string());
+        reminder.title = title.clone();
+        reminders.push(reminder);
+      }
+    }
+  }
+  reminders.sort_by(|a, b| a.reminder_id.cmp(&b.reminder_id));
+  reminders
+}
+
+fn date_mention_reminder(op: &Value) -> Option<DateReminder> {
+  let mention = op.get("attributes")?.get("mention")?;
+  if mention.get("type")?.as_str()? != "date" {
+    return None;
+  }
+  let reminder_id = mention.get(MENTION_REMINDER_ID)?.as_str()?;
+  if reminder_id.is_empty() {
+    return None;
+  }
+  let option = mention
+    .get("reminder_option")
+    .and_then(Value::as_str)
+    .unwrap_or_default();
+  Some(DateReminder {
+    reminder_id: reminder_id.to_string(),
+    field_id: None,
+    block_id: None,
+    date: parse_mention_date(mention.get("date")?.as_str()?)?,
+    include_time: mention
+      .get("include_time")
+      .and_then(Value::as_bool)
+      .unwrap_or(false),
+    offset: ReminderOffset::from_option(option)?,
+    title: String::new(),
+  })
+}
+
+/// Dates of mentions are local to the user, unless they have an offset.
+fn parse_mention_date(date: &str) -> Option<ReminderDate> {
+  if let Ok(time) = DateTime::parse_from_rfc3339(date) {
+    return Some(ReminderDate::Timestamp(time.timestamp()));
+  }
+  if let Ok(local) = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f") {
+    return Some(ReminderDate::Local(local));
+  }
+  let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+  Some(ReminderDate::Local(start_of_day(date)))
+}
+
+/// Returns the text of a delta, without its mentions.
+fn delta_text(ops: &[Value]) -> String {
+  let text: String = ops
+    .iter()
+    .filter(|op| {
+      op.get("attributes")
+        .and_then(|attributes| attributes.get("mention"))
+        .is_none()
+    })
+    .filter_map(|op| op.get("insert").and_then(Value::as_str))
+    .collect();
+  text.trim().chars().take(REMINDER_TITLE_MAX_LEN).collect()
+}
+
+/// Returns the reminders which were added or changed, and the ids of the reminders which were
+/// removed.
+pub fn diff_reminders<'a>(
+  old_reminders: &[DateReminder],
+  new_reminders: &'a [DateReminder],
+) -> (Vec<&'a DateReminder>, Vec<String>) {
+  let changed = new_reminders
+    .iter()
+    .filter(|reminder| !old_reminders.contains(reminder))
+    .collect();
+  let removed = old_reminders
+    .iter()
+    .filter(|old| {
+      !new_reminders
+        .iter()
+        .any(|reminder| reminder.reminder_id == old.reminder_id)
+    })
+    .map(|old| old.reminder_id.clone())
+    .collect();
+  (changed, removed)
+}
+
+/// Saves the reminders set on the dates of the database rows and documents edited through the
+/// realtime server, so that they are sent to their users when they are due.
+pub struct ReminderRecorder {
+  pg_pool: PgPool,
+}
+
+impl ReminderRecorder {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self { pg_pool }
+  }
+
+  /// Saves the changes of the reminders of an object.
+  pub async fn save(
+    &self,
+    workspace_id: &Uuid,
+    object_id: &str,
+    object: &ObjectReminders,
+    uid: i64,
+    changed: &[&DateReminder],
+    removed: &[String],
+  ) -> Result<(), AppError> {
+    let mut txn = self.pg_pool.begin().await?;
+    if !removed.is_empty() {
+      delete_reminders(txn.deref_mut(), object_id, removed).await?;
+    }
+    if !changed.is_empty() {
+      let reminder_ids: Vec<String> = changed
+        .iter()
+        .map(|reminder| reminder.reminder_id.clone())
+        .collect();
+      let owner_timezones =
+        select_reminder_owner_timezones(txn.deref_mut(), object_id, &reminder_ids).await?;
+      let editor_tz = select_user_notification_settings(txn.deref_mut(), uid)
+        .await?
+        .tz();
+      let params: Vec<AFReminderParams> = changed
+        .iter()
+        .map(|reminder| {
+          let tz = owner_timezones
+            .get(&reminder.reminder_id)
+            .map(|timezone| parse_timezone(timezone))
+            .unwrap_or(editor_tz);
+          reminder.resolve(tz)
+        })
+        .collect();
+      upsert_reminders(
+        &mut txn,
+        workspace_id,
+        object.object_type,
+        object_id,
+        object.database_id.as_deref(),
+        uid,
+        &params,
+      )
+      .await?;
+    }
+    txn.commit().await?;
+    Ok(())
+  }
+}
+
+impl GroupRecorder for ReminderRecorder {
+  /// Returns an observer saving the reminders of the database row or document, once it's
+  /// spawned. Reminders are attributed to the user who added them.
+  fn observer(
+    self: Arc<Self>,
+    workspace_id: &str,
+    object_id: &str,
+    collab_type: &CollabType,
+    collab: &Collab,
+  ) -> Option<Box<dyn GroupObserver>> {
+    if !matches!(collab_type, CollabType::DatabaseRow | CollabType::Document) {
+      return None;
+    }
+    let workspace_id = match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => workspace_id,
+      Err(err) => {
+        warn!("[Reminder] invalid workspace id {}: {}", workspace_id, err);
+        return None;
+      },
+    };
+    let observer = ReminderObserver {
+      recorder: self,
+      workspace_id,
+      object_id: object_id.to_string(),
+      collab_type: collab_type.clone(),
+    };
+    Some(Box::new(DebouncedObserver::new(
+      observer, object_id, collab,
+    )))
+  }
+}
+
+struct ReminderObserver {
+  recorder: Arc<ReminderRecorder>,
+  workspace_id: Uuid,
+  object_id: String,
+  collab_type: CollabType,
+}
+
+#[async_trait]
+impl SnapshotObserver for ReminderObserver {
+  type Snapshot = ObjectReminders;
+
+  const NAME: &'static str = "Reminder";
+  const DEBOUNCE: Duration = REMINDER_SCAN_DELAY;
+
+  fn snapshot(&self, collab: &Collab) -> Option<ObjectReminders> {
+    collab_reminders(&self.collab_type, collab)
+  }
+
+  async fn on_change(
+    &self,
+    uid: i64,
+    previous: &ObjectReminders,
+    current: &ObjectReminders,
+  ) -> Result<(), AppError> {
+    let (changed, removed) = diff_reminders(&previous.reminders, &current.reminders);
+    if changed.is_empty() && removed.is_empty() {
+      return Ok(());
+    }
+    self
+      .recorder
+      .save(
+        &self.workspace_id,
+        &self.object_id,
+        current,
+        uid,
+        &changed,
+        &removed,
+      )
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use chrono::{NaiveDate, TimeZone, Utc};
+  use chrono_tz::Tz;
+  use collab_document::blocks::{Block, DocumentData, DocumentMeta};
+  use serde_json::json;
+
+  use super::{document_reminders, DateReminder, ReminderDate, ReminderOffset};
+
+  fn paragraph(id: &str, text_id: &str) -> Block {
+    Block {
+      id: id.to_string(),
+      ty: "paragraph".to_string(),
+      parent: "page".to_string(),
+      children: format!("{}_children", id),
+      external_id: Some(text_id.to_string()),
+      external_type: Some("text".to_string()),
+      data: HashMap::new(),
+    }
+  }
+
+  #[test]
+  fn document_reminders_test() {
+    let delta = json!([
+      { "insert": "Send the report " },
+      {
+        "insert": "$",
+        "attributes": {
+          "mention": {
+            "type": "date",
+            "date": "2024-12-27T14:30:00.000",
+            "include_time": true,
+            "reminder_id": "r1",
+            "reminder_option": "fifteenMinsBefore"
+          }
+        }
+      },
+      {
+        "insert": "$",
+        "attributes": {
+          "mention": { "type": "date", "date": "2024-12-28T00:00:00.000", "include_time": false }
+        }
+      }
+    ]);
+    let data = DocumentData {
+      page_id: "page".to_string(),
+      blocks: HashMap::from([
+        ("b1".to_string(), paragraph("b1", "t1")),
+        ("b2".to_string(), paragraph("b2", "t2")),
+      ]),
+      meta: DocumentMeta {
+        children_map: HashMap::new(),
+        text_map: Some(HashMap::from([
+          ("t1".to_string(), delta.to_string()),
+          (
+            "t2".to_string(),
+            json!([{ "insert": "no reminder" }]).to_string(),
+          ),
+        ])),
+      },
+    };
+
+    let reminders = document_reminders(&data);
+    assert_eq!(reminders.len(), 1);
+    let reminder = &reminders[0];
+    assert_eq!(reminder.reminder_id, "r1");
+    assert_eq!(reminder.block_id.as_deref(), Some("b1"));
+    assert_eq!(reminder.title, "Send the report");
+    assert_eq!(reminder.offset, ReminderOffset::Minutes(15));
+
+    // the time of the mention is local to the user
+    let params = reminder.resolve("Europe/Paris".parse::<Tz>().unwrap());
+    assert_eq!(
+      params.remind_at,
+      Utc.with_ymd_and_hms(2024, 12, 27, 13, 15, 0).unwrap()
+    );
+    assert_eq!(
+      params.local_remind_at,
+      NaiveDate::from_ymd_opt(2024, 12, 27)
+        .unwrap()
+        .and_hms_opt(14, 15, 0)
+    );
+  }
+
+  #[test]
+  fn resolve_date_cell_reminder_test() {
+    let event_at = Utc.with_ymd_and_hms(2024, 12, 27, 16, 0, 0).unwrap();
+    let mut reminder = DateReminder {
+      reminder_id: "r1".to_string(),
+      field_id: Some("due".to_string()),
+      block_id: None,
+      date: ReminderDate::Timestamp(event_at.timestamp()),
+      include_time: true,
+      offset: ReminderOffset::Minutes(0),
+      title: String::new(),
+    };
+    let tz = "America/New_York".parse::<Tz>().unwrap();
+    let params = reminder.resolve(tz);
+    assert_eq!(params.remind_at, event_at);
+    assert!(params.local_remind_at.is_none());
+
+    // a date without time is reminded in the morning of its day in the time zone of the user
+    reminder.include_time = false;
+    reminder.offset = ReminderOffset::Days(1);
+    let params = reminder.resolve(tz);
+    assert_eq!(
+      params.remind_at,
+      Utc.with_ymd_and_hms(2024, 12, 26, 14, 0, 0).unwrap()
+    );
+    assert_eq!(
+      params.local_event_at,
+      NaiveDate::from_ymd_opt(2024, 12, 27)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+    );
+  }
+}
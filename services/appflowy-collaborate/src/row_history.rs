@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+use app_error::AppError;
+use async_trait::async_trait;
+use chrono::Utc;
+use collab::preclude::Collab;
+use collab_database::rows::{Cell, RowDetail};
+use collab_entity::CollabType;
+use database::pg_row::AFDatabaseRowChange;
+use database::row_history::{
+  insert_database_row_changes, notify_database_row_change, AFDatabaseRowCellChange,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::group::observer::{DebouncedObserver, GroupObserver, GroupRecorder, SnapshotObserver};
+
+/// Consecutive changes of a cell by the same user within this window are recorded as one change.
+pub const ROW_HISTORY_MERGE_WINDOW_SECS: i64 = 60;
+/// Keys of a cell which are updated along with its content, and are left out of its history.
+const CELL_TIMESTAMP_KEYS: [&str; 2] = ["created_at", "last_modified"];
+
+/// Records the field-level changes of the database rows edited through the realtime server, and
+/// notifies the servers of them, ie. to compute the computed and AI cells of the rows.
+pub struct RowHistoryRecorder {
+  pg_pool: PgPool,
+}
+
+impl RowHistoryRecorder {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self { pg_pool }
+  }
+}
+
+impl GroupRecorder for RowHistoryRecorder {
+  /// Returns an observer recording the changes of the row of the collab, once it's spawned.
+  /// Updates sent by the server are not recorded, as they're recorded by the server when it makes
+  /// them, ie. when a row is updated through the REST API.
+  fn observer(
+    self: Arc<Self>,
+    workspace_id: &str,
+    object_id: &str,
+    collab_type: &CollabType,
+    collab: &Collab,
+  ) -> Option<Box<dyn GroupObserver>> {
+    if !matches!(collab_type, CollabType::DatabaseRow) {
+      return None;
+    }
+    let workspace_id = match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => workspace_id,
+      Err(err) => {
+        warn!(
+          "[RowHistory] invalid workspace id {}: {}",
+          workspace_id, err
+        );
+        return None;
+      },
+    };
+    let observer = RowHistoryObserver {
+      recorder: self,
+      workspace_id,
+    };
+    Some(Box::new(DebouncedObserver::new(
+      observer, object_id, collab,
+    )))
+  }
+}
+
+struct ObservedRow {
+  database_id: String,
+  row_id: String,
+  cells: HashMap<String, Cell>,
+}
+
+impl From<&RowDetail> for ObservedRow {
+  fn from(row_detail: &RowDetail) -> Self {
+    Self {
+      database_id: row_detail.row.database_id.clone(),
+      row_id: row_detail.row.id.to_string(),
+      cells: row_detail
+        .row
+        .cells
+        .iter()
+        .map(|(field_id, cell)| (field_id.clone(), cell.clone()))
+        .collect(),
+    }
+  }
+}
+
+struct RowHistoryObserver {
+  recorder: Arc<RowHistoryRecorder>,
+  workspace_id: Uuid,
+}
+
+#[async_trait]
+impl SnapshotObserver for RowHistoryObserver {
+  type Snapshot = ObservedRow;
+
+  const NAME: &'static str = "RowHistory";
+
+  fn snapshot(&self, collab: &Collab) -> Option<ObservedRow> {
+    RowDetail::from_collab(collab).map(|row_detail| ObservedRow::from(&row_detail))
+  }
+
+  async fn on_change(
+    &self,
+    uid: i64,
+    previous: &ObservedRow,
+    current: &ObservedRow,
+  ) -> Result<(), AppError> {
+    let changes = diff_row_cells(&previous.cells, &current.cells);
+    if changes.is_empty() {
+      return Ok(());
+    }
+    self.record(current, uid, &changes).await
+  }
+}
+
+impl RowHistoryObserver {
+  async fn record(
+    &self,
+    row: &ObservedRow,
+    uid: i64,
+    changes: &[AFDatabaseRowCellChange],
+  ) -> Result<(), AppError> {
+    let mut txn = self.recorder.pg_pool.begin().await?;
+    insert_database_row_changes(
+      &mut txn,
+      &self.workspace_id,
+      &row.database_id,
+      &row.row_id,
+      uid,
+      changes,
+      Utc::now(),
+      ROW_HISTORY_MERGE_WINDOW_SECS,
+    )
+    .await?;
+    let change = AFDatabaseRowChange {
+      workspace_id: self.workspace_id,
+      database_id: row.database_id.clone(),
+      row_id: row.row_id.clone(),
+      uid,
+      field_ids: changes
+        .iter()
+        .map(|change| change.field_id.clone())
+        .collect(),
+    };
+    notify_database_row_change(txn.deref_mut(), &change).await?;
+    txn.commit().await?;
+    Ok(())
+  }
+}
+
+/// Returns the cells which differ between two versions of a row, ignoring the timestamps of the
+/// cells.
+pub fn diff_row_cells(
+  old_cells: &HashMap<String, Cell>,
+  new_cells: &HashMap<String, Cell>,
+) -> Vec<AFDatabaseRowCellChange> {
+  let mut changes: Vec<AFDatabaseRowCellChange> = old_cells
+    .keys()
+    .chain(new_cells.keys().filter(|id| !old_cells.contains_key(*id)))
+    .filter_map(|field_id| {
+      let old_cell = old_cells.get(field_id).and_then(cell_content);
+      let new_cell = new_cells.get(field_id).and_then(cell_content);
+      (old_cell != new_cell).then(|| AFDatabaseRowCellChange {
+        field_id: field_id.clone(),
+        old_cell,
+        new_cell,
+      })
+    })
+    .collect();
+  changes.sort_by(|a, b| a.field_id.cmp(&b.field_id));
+  changes
+}
+
+/// Returns the json of the cell without its timestamps, none when the cell has no content.
+fn cell_content(cell: &Cell) -> Option<serde_json::Value> {
+  let content: HashMap<&String, _> = cell
+    .iter()
+    .filter(|(key, _)| !CELL_TIMESTAMP_KEYS.contains(&key.as_str()))
+    .collect();
+  if content.is_empty() {
+    return None;
+  }
+  serde_json::to_value(content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use collab_database::rows::Cell;
+  use yrs::Any;
+
+  use super::diff_row_cells;
+
+  fn text_cell(data: &str, last_modified: i64) -> Cell {
+    Cell::from([
+      ("data".to_string(), Any::String(data.into())),
+      ("field_type".to_string(), Any::BigInt(0)),
+      ("last_modified".to_string(), Any::BigInt(last_modified)),
+    ])
+  }
+
+  #[test]
+  fn diff_row_cells_test() {
+    let old_cells = HashMap::from([
+      ("status".to_string(), text_cell("To Do", 1)),
+      ("title".to_string(), text_cell("Weekly report", 1)),
+      ("notes".to_string(), text_cell("draft", 1)),
+    ]);
+    let new_cells = HashMap::from([
+      ("status".to_string(), text_cell("Done", 2)),
+      // only the timestamp changed
+      ("title".to_string(), text_cell("Weekly report", 2)),
+      ("owner".to_string(), text_cell("Lucas", 2)),
+    ]);
+
+    let changes = diff_row_cells(&old_cells, &new_cells);
+    let field_ids: Vec<&str> = changes
+      .iter()
+      .map(|change| change.field_id.as_str())
+      .collect();
+    assert_eq!(field_ids, vec!["notes", "owner", "status"]);
+
+    assert!(changes[0].old_cell.is_some());
+    assert!(changes[0].new_cell.is_none());
+    assert!(changes[1].old_cell.is_none());
+    assert_eq!(changes[1].new_cell.as_ref().unwrap()["data"], "Lucas");
+    assert_eq!(changes[2].old_cell.as_ref().unwrap()["data"], "To Do");
+    assert_eq!(changes[2].new_cell.as_ref().unwrap()["data"], "Done");
+    assert!(changes[2]
+      .new_cell
+      .as_ref()
+      .unwrap()
+      .get("last_modified")
+      .is_none());
+  }
+}
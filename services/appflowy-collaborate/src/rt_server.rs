@@ -25,6 +25,8 @@ use crate::error::{CreateGroupFailedReason, RealtimeError};
 use crate::group::cmd::{GroupCommand, GroupCommandRunner, GroupCommandSender};
 use crate::group::manager::GroupManager;
 use crate::indexer::IndexerProvider;
+use crate::reminder::ReminderRecorder;
+use crate::row_history::RowHistoryRecorder;
 use crate::rt_server::collaboration_runtime::COLLAB_RUNTIME;
 
 use crate::actix_ws::entities::{ClientGenerateEmbeddingMessage, ClientHttpUpdateMessage};
@@ -55,6 +57,8 @@ where
     edit_state_max_count: u32,
     edit_state_max_secs: i64,
     indexer_provider: Arc<IndexerProvider>,
+    row_history_recorder: Arc<RowHistoryRecorder>,
+    reminder_recorder: Arc<ReminderRecorder>,
   ) -> Result<Self, RealtimeError> {
     let enable_custom_runtime = get_env_var("APPFLOWY_COLLABORATE_MULTI_THREAD", "false")
       .parse::<bool>()
@@ -69,13 +73,15 @@ where
     let connect_state = ConnectState::new();
     let group_manager = Arc::new(
       GroupManager::new(
-        storage.clone(),
+        storage,
         access_control.clone(),
         metrics.clone(),
         group_persistence_interval,
         edit_state_max_count,
         edit_state_max_secs,
         indexer_provider.clone(),
+        row_history_recorder,
+        reminder_recorder,
       )
       .await?,
     );
@@ -90,7 +96,7 @@ where
       Arc::downgrade(&group_manager),
     );
 
-    spawn_handle_unindexed_collabs(indexer_provider, storage);
+    spawn_handle_unindexed_collabs(indexer_provider);
 
     Ok(Self {
       group_manager,
@@ -405,14 +411,8 @@ where
   }
 }
 
-fn spawn_handle_unindexed_collabs(
-  indexer_provider: Arc<IndexerProvider>,
-  storage: Arc<dyn CollabStorage>,
-) {
-  tokio::spawn(IndexerProvider::handle_unindexed_collabs(
-    indexer_provider,
-    storage,
-  ));
+fn spawn_handle_unindexed_collabs(indexer_provider: Arc<IndexerProvider>) {
+  tokio::spawn(IndexerProvider::handle_unindexed_collabs(indexer_provider));
 }
 
 fn spawn_period_check_inactive_group<S>(
@@ -10,11 +10,14 @@ use access_control::metrics::AccessControlMetrics;
 use app_error::AppError;
 use database::user::{select_all_uid_uuid, select_uid_from_uuid};
 
+use crate::ai_stream::AIStreamListener;
 use crate::collab::storage::CollabAccessControlStorage;
 use crate::config::Config;
 use crate::indexer::IndexerProvider;
-use crate::metrics::CollabMetrics;
+use crate::metrics::{CollabMetrics, EmbeddingMetrics};
 use crate::pg_listener::PgListeners;
+use crate::reminder::ReminderRecorder;
+use crate::row_history::RowHistoryRecorder;
 use crate::CollabRealtimeMetrics;
 
 pub type RedisConnectionManager = redis::aio::ConnectionManager;
@@ -23,12 +26,16 @@ pub type RedisConnectionManager = redis::aio::ConnectionManager;
 pub struct AppState {
   pub config: Arc<Config>,
   pub pg_listeners: Arc<PgListeners>,
+  pub ai_stream_listener: Arc<AIStreamListener>,
   pub user_cache: UserCache,
   pub redis_connection_manager: RedisConnectionManager,
   pub access_control: AccessControl,
   pub collab_access_control_storage: Arc<CollabAccessControlStorage>,
   pub metrics: AppMetrics,
   pub indexer_provider: Arc<IndexerProvider>,
+  pub row_history_recorder: Arc<RowHistoryRecorder>,
+  pub reminder_recorder: Arc<ReminderRecorder>,
+  pub pg_pool: PgPool,
 }
 
 #[derive(Clone)]
@@ -38,6 +45,7 @@ pub struct AppMetrics {
   pub access_control_metrics: Arc<AccessControlMetrics>,
   pub realtime_metrics: Arc<CollabRealtimeMetrics>,
   pub collab_metrics: Arc<CollabMetrics>,
+  pub embedding_metrics: Arc<EmbeddingMetrics>,
 }
 
 impl Default for AppMetrics {
@@ -52,11 +60,13 @@ impl AppMetrics {
     let access_control_metrics = Arc::new(AccessControlMetrics::register(&mut registry));
     let realtime_metrics = Arc::new(CollabRealtimeMetrics::register(&mut registry));
     let collab_metrics = Arc::new(CollabMetrics::register(&mut registry));
+    let embedding_metrics = Arc::new(EmbeddingMetrics::register(&mut registry));
     Self {
       registry: Arc::new(registry),
       access_control_metrics,
       realtime_metrics,
       collab_metrics,
+      embedding_metrics,
     }
   }
 }
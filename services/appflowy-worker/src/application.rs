@@ -1,4 +1,4 @@
-use crate::config::{Config, DatabaseSetting, Environment, S3Setting};
+use crate::config::{Config, DatabaseSetting, Environment, IndexerSetting, S3Setting};
 use anyhow::Error;
 use redis::aio::ConnectionManager;
 use sqlx::postgres::PgPoolOptions;
@@ -8,9 +8,17 @@ use crate::import_worker::worker::run_import_worker;
 use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
 
 use crate::import_worker::email_notifier::EmailNotifier;
+use crate::row_schedule_worker::run_row_schedule_worker;
 use crate::s3_client::S3ClientImpl;
+use crate::webhook_worker::run_webhook_worker;
 
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_collaborate::collab::cache::CollabCache;
+use appflowy_collaborate::indexer::IndexerProvider;
+use appflowy_collaborate::{CollabMetrics, EmbeddingMetrics};
 use axum::Router;
+use collab_stream::indexing_queue::{unique_consumer_name, IndexingQueue, IndexingQueueConfig};
+use database::file::s3_client_impl::AwsS3BucketClientImpl;
 use secrecy::ExposeSecret;
 
 use crate::mailer::AFWorkerMailer;
@@ -19,7 +27,8 @@ use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::get;
-use infra::env_util::get_env_var;
+use infra::env_util::{get_env_var, get_env_var_opt};
+use infra::net_util::parse_hosts;
 use mailer::sender::Mailer;
 use std::sync::{Arc, Once};
 use std::time::Duration;
@@ -50,6 +59,7 @@ pub fn init_subscriber(app_env: &Environment) {
     let level = std::env::var("RUST_LOG").unwrap_or("info".to_string());
     let mut filters = vec![];
     filters.push(format!("appflowy_worker={}", level));
+    filters.push(format!("appflowy_collaborate={}", level));
     let env_filter = EnvFilter::new(filters.join(","));
 
     let builder = tracing_subscriber::fmt()
@@ -93,6 +103,17 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
   let s3_client = get_aws_s3_client(&config.s3_setting).await?;
   let metrics = AppMetrics::new();
 
+  if config.indexer.enabled {
+    spawn_indexer_worker(
+      &config.indexer,
+      pg_pool.clone(),
+      redis_client.clone(),
+      &s3_client,
+      &metrics,
+    )
+    .await?;
+  }
+
   let state = AppState {
     redis_client,
     pg_pool,
@@ -124,6 +145,23 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     maximum_import_file_size,
   ));
 
+  let webhook_tick_interval = get_env_var("APPFLOWY_WORKER_WEBHOOK_TICK_INTERVAL", "5")
+    .parse::<u64>()
+    .unwrap_or(5);
+  tokio::spawn(run_webhook_worker(
+    state.pg_pool.clone(),
+    webhook_tick_interval,
+    parse_hosts(&get_env_var("APPFLOWY_WEBHOOK_ALLOWED_HOSTS", "")),
+  ));
+
+  let row_schedule_tick_interval = get_env_var("APPFLOWY_WORKER_ROW_SCHEDULE_TICK_INTERVAL", "10")
+    .parse::<u64>()
+    .unwrap_or(10);
+  tokio::spawn(run_row_schedule_worker(
+    state.pg_pool.clone(),
+    row_schedule_tick_interval,
+  ));
+
   let app = Router::new()
     .route("/metrics", get(metrics_handler))
     .with_state(Arc::new(state));
@@ -150,6 +188,45 @@ pub struct AppState {
   pub metrics: AppMetrics,
 }
 
+/// Processes the indexing queue in the worker, so that the collab service doesn't need to.
+async fn spawn_indexer_worker(
+  setting: &IndexerSetting,
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  s3_client: &S3ClientImpl,
+  metrics: &AppMetrics,
+) -> Result<(), Error> {
+  info!(
+    "Starting indexer worker, AI server: {}",
+    setting.ai_server_url()
+  );
+  let collab_cache = CollabCache::new(
+    redis_client.clone(),
+    pg_pool.clone(),
+    AwsS3BucketClientImpl::new(s3_client.inner.clone(), s3_client.bucket.clone()),
+    metrics.collab_metrics.clone(),
+    setting.s3_collab_threshold,
+  );
+  let indexing_queue =
+    IndexingQueue::new(redis_client.clone(), IndexingQueueConfig::default()).await?;
+  let indexer_provider = IndexerProvider::new(
+    pg_pool,
+    AppFlowyAIClient::new(&setting.ai_server_url()),
+    indexing_queue,
+    metrics.embedding_metrics.clone(),
+  );
+  let consumer_queue = IndexingQueue::new(redis_client, IndexingQueueConfig::default()).await?;
+  let consumer = get_env_var_opt("APPFLOWY_WORKER_INDEXER_CONSUMER_NAME")
+    .unwrap_or_else(|| unique_consumer_name("appflowy_worker"));
+  tokio::spawn(IndexerProvider::run_indexing_queue(
+    indexer_provider,
+    collab_cache,
+    consumer_queue,
+    consumer,
+  ));
+  Ok(())
+}
+
 async fn get_worker_mailer(config: &Config) -> Result<AFWorkerMailer, Error> {
   let mailer = Mailer::new(
     config.mailer.smtp_username.clone(),
@@ -211,15 +288,21 @@ pub struct AppMetrics {
   #[allow(dead_code)]
   registry: Arc<prometheus_client::registry::Registry>,
   import_metrics: Arc<ImportMetrics>,
+  collab_metrics: Arc<CollabMetrics>,
+  embedding_metrics: Arc<EmbeddingMetrics>,
 }
 
 impl AppMetrics {
   pub fn new() -> Self {
     let mut registry = prometheus_client::registry::Registry::default();
     let import_metrics = Arc::new(ImportMetrics::register(&mut registry));
+    let collab_metrics = Arc::new(CollabMetrics::register(&mut registry));
+    let embedding_metrics = Arc::new(EmbeddingMetrics::register(&mut registry));
     Self {
       registry: Arc::new(registry),
       import_metrics,
+      collab_metrics,
+      embedding_metrics,
     }
   }
 }
@@ -13,6 +13,7 @@ pub struct Config {
   pub db_settings: DatabaseSetting,
   pub s3_setting: S3Setting,
   pub mailer: MailerSetting,
+  pub indexer: IndexerSetting,
 }
 
 impl Config {
@@ -57,6 +58,14 @@ impl Config {
         smtp_username: get_env_var("APPFLOWY_MAILER_SMTP_USERNAME", "sender@example.com"),
         smtp_password: get_env_var("APPFLOWY_MAILER_SMTP_PASSWORD", "password").into(),
       },
+      indexer: IndexerSetting {
+        enabled: get_env_var("APPFLOWY_WORKER_INDEXER_ENABLED", "false")
+          .parse()
+          .context("fail to get APPFLOWY_WORKER_INDEXER_ENABLED")?,
+        ai_server_host: get_env_var("APPFLOWY_AI_SERVER_HOST", "localhost"),
+        ai_server_port: get_env_var("APPFLOWY_AI_SERVER_PORT", "5001").parse()?,
+        s3_collab_threshold: get_env_var("APPFLOWY_COLLAB_S3_THRESHOLD", "8000").parse()?,
+      },
     })
   }
 }
@@ -96,6 +105,22 @@ impl Display for DatabaseSetting {
   }
 }
 
+/// Settings of the indexing queue consumer, which creates embeddings of collabs enqueued by the
+/// collab service.
+#[derive(Debug, Clone)]
+pub struct IndexerSetting {
+  pub enabled: bool,
+  pub ai_server_host: String,
+  pub ai_server_port: u16,
+  pub s3_collab_threshold: usize,
+}
+
+impl IndexerSetting {
+  pub fn ai_server_url(&self) -> String {
+    format!("http://{}:{}", self.ai_server_host, self.ai_server_port)
+  }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct StreamSetting {
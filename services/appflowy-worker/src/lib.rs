@@ -2,4 +2,6 @@ pub mod error;
 pub mod import_worker;
 mod mailer;
 pub mod metric;
+pub mod row_schedule_worker;
 pub mod s3_client;
+pub mod webhook_worker;
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use collab_database::database::gen_row_id;
+use database::row_schedule::{
+  insert_row_schedule_runs, select_due_row_schedules_for_update, update_row_schedule_next_run,
+  AFDueRowSchedule,
+};
+use shared_entity::dto::workspace_dto::{AFMissedRunPolicy, AFRowScheduleRule};
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::time::interval;
+use tracing::{error, info, trace, warn};
+
+const SCHEDULE_BATCH_SIZE: i64 = 50;
+/// Runs missed by less than this are made by schedules skipping their missed runs.
+pub const MISSED_RUN_GRACE: chrono::Duration = chrono::Duration::minutes(15);
+/// Maximum number of missed runs made at once by schedules making every missed run.
+pub const MAX_MISSED_RUNS: usize = 50;
+
+/// Queues the runs of the database row schedules which are due. The rows of the runs are created by
+/// the appflowy cloud server, which broadcasts them to the clients editing the database, like the
+/// rows inserted through the REST API.
+///
+/// A schedule is moved to its next run in the same transaction as its runs are queued, and each
+/// run is unique for its time, so that no run is made twice across restarts of the worker. Runs
+/// missed while the worker was down are made according to the missed run policy of the schedule.
+pub async fn run_row_schedule_worker(pg_pool: PgPool, tick_interval_secs: u64) {
+  info!("[RowSchedule] worker started");
+  let mut tick = interval(Duration::from_secs(tick_interval_secs));
+  loop {
+    tick.tick().await;
+    loop {
+      let claimed = match queue_due_runs(&pg_pool).await {
+        Ok(claimed) => claimed,
+        Err(err) => {
+          error!("[RowSchedule] failed to queue due runs: {}", err);
+          break;
+        },
+      };
+      if claimed < SCHEDULE_BATCH_SIZE as usize {
+        break;
+      }
+    }
+  }
+}
+
+/// Queues the runs of a batch of due schedules. Returns the number of schedules which were due.
+async fn queue_due_runs(pg_pool: &PgPool) -> Result<usize, anyhow::Error> {
+  let now = Utc::now();
+  let mut txn = pg_pool.begin().await?;
+  let schedules = select_due_row_schedules_for_update(&mut txn, SCHEDULE_BATCH_SIZE).await?;
+  for schedule in &schedules {
+    queue_schedule_runs(&mut txn, schedule, now).await?;
+  }
+  txn.commit().await?;
+  Ok(schedules.len())
+}
+
+async fn queue_schedule_runs(
+  txn: &mut Transaction<'_, Postgres>,
+  schedule: &AFDueRowSchedule,
+  now: DateTime<Utc>,
+) -> Result<(), anyhow::Error> {
+  let rule = serde_json::from_value::<AFRowScheduleRule>(schedule.rule.clone());
+  let (runs, next_run_at) = match rule {
+    Ok(rule) => due_runs(
+      &rule,
+      AFMissedRunPolicy::from(schedule.missed_run_policy),
+      schedule.next_run_at,
+      now,
+    ),
+    Err(err) => {
+      // the schedule is ended, as it would be due again on every tick otherwise
+      warn!(
+        "[RowSchedule] invalid rule of schedule {}: {}",
+        schedule.schedule_id, err
+      );
+      (vec![], None)
+    },
+  };
+  trace!(
+    "[RowSchedule] schedule {}: {} runs, next run at {:?}",
+    schedule.schedule_id,
+    runs.len(),
+    next_run_at
+  );
+  let runs: Vec<(DateTime<Utc>, String)> = runs
+    .into_iter()
+    .map(|scheduled_for| (scheduled_for, gen_row_id().to_string()))
+    .collect();
+  insert_row_schedule_runs(txn, &schedule.schedule_id, &runs).await?;
+  update_row_schedule_next_run(
+    txn,
+    &schedule.schedule_id,
+    next_run_at,
+    runs.last().map(|(scheduled_for, _)| *scheduled_for),
+  )
+  .await?;
+  Ok(())
+}
+
+/// Returns the runs of a schedule to be made at `now`, from its next run, and the time of its
+/// following run, none when the schedule has ended.
+pub fn due_runs(
+  rule: &AFRowScheduleRule,
+  policy: AFMissedRunPolicy,
+  next_run_at: DateTime<Utc>,
+  now: DateTime<Utc>,
+) -> (Vec<DateTime<Utc>>, Option<DateTime<Utc>>) {
+  let mut due = vec![];
+  let mut occurrence = Some(next_run_at);
+  while let Some(scheduled_for) = occurrence.filter(|scheduled_for| *scheduled_for <= now) {
+    due.push(scheduled_for);
+    occurrence = rule.next_occurrence(scheduled_for);
+  }
+
+  let runs = match policy {
+    AFMissedRunPolicy::Skip => due
+      .into_iter()
+      .filter(|scheduled_for| now - *scheduled_for <= MISSED_RUN_GRACE)
+      .collect(),
+    AFMissedRunPolicy::RunOnce => due.pop().into_iter().collect(),
+    AFMissedRunPolicy::RunAll => due.split_off(due.len().saturating_sub(MAX_MISSED_RUNS)),
+  };
+  (runs, occurrence)
+}
@@ -0,0 +1,256 @@
+use std::error::Error as StdError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use database::webhook::{
+  claim_due_webhook_deliveries, update_webhook_delivery_attempt, AFWebhookDeliveryJob,
+};
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use infra::net_util::{check_public_url, resolve_public_host};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use sha2::Sha256;
+use shared_entity::dto::workspace_dto::AFDatabaseWebhookDeliveryStatus;
+use sqlx::PgPool;
+use tokio::time::interval;
+use tracing::{error, info, trace, warn};
+
+pub const SIGNATURE_HEADER: &str = "X-AppFlowy-Signature";
+pub const EVENT_HEADER: &str = "X-AppFlowy-Event";
+pub const DELIVERY_HEADER: &str = "X-AppFlowy-Delivery";
+
+const DELIVERY_BATCH_SIZE: i64 = 20;
+/// Deliveries being sent aren't claimed again before the lease expires.
+const DELIVERY_LEASE_SECS: i64 = 300;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// Sends the events of database webhooks queued by the appflowy cloud server. A delivery is
+/// retried with an exponential backoff until the receiver responds with a 2xx status, and is
+/// marked as failed after [MAX_DELIVERY_ATTEMPTS] attempts.
+///
+/// Webhooks can only reach public addresses, unless their host is one of the `allowed_hosts`.
+pub async fn run_webhook_worker(
+  pg_pool: PgPool,
+  tick_interval_secs: u64,
+  allowed_hosts: Vec<String>,
+) {
+  info!("[Webhook] delivery worker started");
+  let allowed_hosts: Arc<[String]> = allowed_hosts.into();
+  let client = webhook_client(allowed_hosts.clone());
+  let mut tick = interval(Duration::from_secs(tick_interval_secs));
+  loop {
+    tick.tick().await;
+    loop {
+      let jobs = match claim_due_webhook_deliveries(
+        &pg_pool,
+        DELIVERY_BATCH_SIZE,
+        DELIVERY_LEASE_SECS,
+      )
+      .await
+      {
+        Ok(jobs) => jobs,
+        Err(err) => {
+          error!("[Webhook] failed to claim deliveries: {}", err);
+          break;
+        },
+      };
+      if jobs.is_empty() {
+        break;
+      }
+      let claimed = jobs.len() as i64;
+      join_all(
+        jobs
+          .iter()
+          .map(|job| send_delivery(&pg_pool, &client, &allowed_hosts, job)),
+      )
+      .await;
+      if claimed < DELIVERY_BATCH_SIZE {
+        break;
+      }
+    }
+  }
+}
+
+/// Returns the client sending the deliveries. Redirects aren't followed and the hosts are resolved
+/// by [PublicResolver], so that the deliveries can't be sent to the services of the deployment.
+pub fn webhook_client(allowed_hosts: Arc<[String]>) -> reqwest::Client {
+  reqwest::Client::builder()
+    .timeout(DELIVERY_TIMEOUT)
+    .redirect(Policy::none())
+    .dns_resolver(Arc::new(PublicResolver { allowed_hosts }))
+    .build()
+    .expect("failed to create webhook http client")
+}
+
+/// Resolves the hosts of the webhooks, failing when they resolve to non-public addresses, unless
+/// they are allowed. The addresses are checked when the connection is made, so a host can't
+/// resolve to a public address when the webhook is checked and to a private one when it's sent.
+struct PublicResolver {
+  allowed_hosts: Arc<[String]>,
+}
+
+impl Resolve for PublicResolver {
+  fn resolve(&self, name: Name) -> Resolving {
+    let allowed_hosts = self.allowed_hosts.clone();
+    Box::pin(async move {
+      // the port is set by the connector
+      let addrs = resolve_public_host(name.as_str(), 0, &allowed_hosts)
+        .await
+        .map_err(Box::<dyn StdError + Send + Sync>::from)?;
+      Ok::<_, Box<dyn StdError + Send + Sync>>(Box::new(addrs.into_iter()) as Addrs)
+    })
+  }
+}
+
+async fn send_delivery(
+  pg_pool: &PgPool,
+  client: &reqwest::Client,
+  allowed_hosts: &[String],
+  job: &AFWebhookDeliveryJob,
+) {
+  let outcome = deliver_webhook(client, allowed_hosts, job).await;
+  let attempts = job.attempts + 1;
+  let status = match &outcome {
+    WebhookDeliveryOutcome::Delivered { .. } => AFDatabaseWebhookDeliveryStatus::Delivered,
+    _ if attempts >= MAX_DELIVERY_ATTEMPTS => AFDatabaseWebhookDeliveryStatus::Failed,
+    _ => AFDatabaseWebhookDeliveryStatus::Pending,
+  };
+  trace!(
+    "[Webhook] delivery {} attempt {}: {:?}",
+    job.delivery_id,
+    attempts,
+    outcome
+  );
+  if let Err(err) = update_webhook_delivery_attempt(
+    pg_pool,
+    &job.delivery_id,
+    status,
+    outcome.response_status().map(i32::from),
+    outcome.error(),
+    retry_delay_secs(attempts),
+  )
+  .await
+  {
+    warn!(
+      "[Webhook] failed to save attempt of delivery {}: {}",
+      job.delivery_id, err
+    );
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookDeliveryOutcome {
+  Delivered { status: u16 },
+  Rejected { status: u16 },
+  Failed { error: String },
+}
+
+impl WebhookDeliveryOutcome {
+  pub fn response_status(&self) -> Option<u16> {
+    match self {
+      WebhookDeliveryOutcome::Delivered { status }
+      | WebhookDeliveryOutcome::Rejected { status } => Some(*status),
+      WebhookDeliveryOutcome::Failed { .. } => None,
+    }
+  }
+
+  pub fn error(&self) -> Option<&str> {
+    match self {
+      WebhookDeliveryOutcome::Delivered { .. } => None,
+      WebhookDeliveryOutcome::Rejected { .. } => Some("rejected by the receiver"),
+      WebhookDeliveryOutcome::Failed { error } => Some(error),
+    }
+  }
+}
+
+/// Posts the payload of the delivery to the webhook, signed with the secret of the webhook. The
+/// delivery fails when the url of the webhook isn't public, unless its host is allowed.
+pub async fn deliver_webhook(
+  client: &reqwest::Client,
+  allowed_hosts: &[String],
+  job: &AFWebhookDeliveryJob,
+) -> WebhookDeliveryOutcome {
+  // urls with an ip address aren't resolved by the client
+  let url = match Url::parse(&job.url) {
+    Ok(url) => url,
+    Err(err) => {
+      return WebhookDeliveryOutcome::Failed {
+        error: err.to_string(),
+      }
+    },
+  };
+  if let Err(err) = check_public_url(&url, allowed_hosts).await {
+    return WebhookDeliveryOutcome::Failed {
+      error: err.to_string(),
+    };
+  }
+  let body = match serde_json::to_vec(&job.payload) {
+    Ok(body) => body,
+    Err(err) => {
+      return WebhookDeliveryOutcome::Failed {
+        error: err.to_string(),
+      }
+    },
+  };
+  let result = client
+    .post(url)
+    .header(reqwest::header::CONTENT_TYPE, "application/json")
+    .header(SIGNATURE_HEADER, sign_payload(&job.secret, &body))
+    .header(EVENT_HEADER, &job.event_type)
+    .header(DELIVERY_HEADER, job.delivery_id.to_string())
+    .body(body)
+    .send()
+    .await;
+  match result {
+    Ok(resp) if resp.status().is_success() => WebhookDeliveryOutcome::Delivered {
+      status: resp.status().as_u16(),
+    },
+    Ok(resp) => WebhookDeliveryOutcome::Rejected {
+      status: resp.status().as_u16(),
+    },
+    Err(err) => WebhookDeliveryOutcome::Failed {
+      error: err.to_string(),
+    },
+  }
+}
+
+/// Returns the value of the signature header: `sha256=` followed by the hex encoded HMAC-SHA256
+/// of the body, keyed with the secret of the webhook.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+  mac.update(body);
+  format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn retry_delay_secs(attempts: i32) -> i64 {
+  let exponent = (attempts - 1).clamp(0, 16) as u32;
+  (RETRY_BASE_SECS * 2_i64.pow(exponent)).min(RETRY_MAX_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sign_payload_test() {
+    // RFC 4231, test case 2
+    assert_eq!(
+      sign_payload("Jefe", b"what do ya want for nothing?"),
+      "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+  }
+
+  #[test]
+  fn retry_delay_test() {
+    assert_eq!(retry_delay_secs(1), 30);
+    assert_eq!(retry_delay_secs(2), 60);
+    assert_eq!(retry_delay_secs(4), 240);
+    assert_eq!(retry_delay_secs(MAX_DELIVERY_ATTEMPTS), RETRY_MAX_SECS);
+  }
+}
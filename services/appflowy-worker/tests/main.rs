@@ -1 +1,3 @@
 mod import_test;
+mod row_schedule_test;
+mod webhook_test;
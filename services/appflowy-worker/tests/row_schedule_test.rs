@@ -0,0 +1,136 @@
+use appflowy_worker::row_schedule_worker::{due_runs, MAX_MISSED_RUNS};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use shared_entity::dto::workspace_dto::{
+  AFMissedRunPolicy, AFRowScheduleFrequency, AFRowScheduleRule,
+};
+
+fn rule(frequency: AFRowScheduleFrequency) -> AFRowScheduleRule {
+  AFRowScheduleRule {
+    frequency,
+    interval: 1,
+    weekdays: vec![],
+    month_day: None,
+    hour: 9,
+    minute: 0,
+    timezone: "UTC".to_string(),
+    start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+    end_date: None,
+  }
+}
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+  Utc
+    .with_ymd_and_hms(year, month, day, hour, minute, 0)
+    .unwrap()
+}
+
+#[test]
+fn weekly_rule_test() {
+  let mut weekly = rule(AFRowScheduleFrequency::Weekly);
+  // 2024-01-01 is a Monday
+  assert_eq!(
+    weekly.next_occurrence(utc(2024, 1, 1, 8, 0)),
+    Some(utc(2024, 1, 1, 9, 0))
+  );
+  assert_eq!(
+    weekly.next_occurrence(utc(2024, 1, 1, 9, 0)),
+    Some(utc(2024, 1, 8, 9, 0))
+  );
+
+  // every other week, on Mondays and Fridays
+  weekly.interval = 2;
+  weekly.weekdays = vec![0, 4];
+  assert_eq!(
+    weekly.next_occurrence(utc(2024, 1, 1, 9, 0)),
+    Some(utc(2024, 1, 5, 9, 0))
+  );
+  assert_eq!(
+    weekly.next_occurrence(utc(2024, 1, 5, 9, 0)),
+    Some(utc(2024, 1, 15, 9, 0))
+  );
+}
+
+#[test]
+fn monthly_rule_test() {
+  let mut monthly = rule(AFRowScheduleFrequency::Monthly);
+  monthly.month_day = Some(31);
+  assert_eq!(
+    monthly.next_occurrence(utc(2024, 1, 31, 9, 0)),
+    Some(utc(2024, 2, 29, 9, 0))
+  );
+  assert_eq!(
+    monthly.next_occurrence(utc(2024, 2, 29, 9, 0)),
+    Some(utc(2024, 3, 31, 9, 0))
+  );
+
+  monthly.end_date = NaiveDate::from_ymd_opt(2024, 3, 30);
+  assert_eq!(monthly.next_occurrence(utc(2024, 2, 29, 9, 0)), None);
+}
+
+#[test]
+fn rule_timezone_test() {
+  let mut daily = rule(AFRowScheduleFrequency::Daily);
+  daily.timezone = "Europe/Paris".to_string();
+  // 9:00 in Paris is 8:00 UTC in winter, and 7:00 UTC in summer
+  assert_eq!(
+    daily.next_occurrence(utc(2024, 3, 30, 12, 0)),
+    Some(utc(2024, 3, 31, 7, 0))
+  );
+  assert_eq!(
+    daily.next_occurrence(utc(2024, 3, 29, 12, 0)),
+    Some(utc(2024, 3, 30, 8, 0))
+  );
+
+  // 2:30 doesn't exist in Paris on the day clocks move forward
+  daily.hour = 2;
+  daily.minute = 30;
+  assert_eq!(
+    daily.next_occurrence(utc(2024, 3, 30, 12, 0)),
+    Some(utc(2024, 3, 31, 1, 30))
+  );
+
+  daily.timezone = "Mars/Olympus".to_string();
+  assert!(daily.validate().is_err());
+  assert_eq!(daily.next_occurrence(utc(2024, 3, 30, 12, 0)), None);
+}
+
+#[test]
+fn missed_runs_test() {
+  let daily = rule(AFRowScheduleFrequency::Daily);
+  let next_run_at = utc(2024, 1, 1, 9, 0);
+
+  // the run is due
+  let now = utc(2024, 1, 1, 9, 5);
+  for policy in [
+    AFMissedRunPolicy::Skip,
+    AFMissedRunPolicy::RunOnce,
+    AFMissedRunPolicy::RunAll,
+  ] {
+    let (runs, next) = due_runs(&daily, policy, next_run_at, now);
+    assert_eq!(runs, vec![next_run_at]);
+    assert_eq!(next, Some(utc(2024, 1, 2, 9, 0)));
+  }
+
+  // three runs were missed
+  let now = utc(2024, 1, 3, 12, 0);
+  let (runs, next) = due_runs(&daily, AFMissedRunPolicy::Skip, next_run_at, now);
+  assert!(runs.is_empty());
+  assert_eq!(next, Some(utc(2024, 1, 4, 9, 0)));
+  let (runs, _) = due_runs(&daily, AFMissedRunPolicy::RunOnce, next_run_at, now);
+  assert_eq!(runs, vec![utc(2024, 1, 3, 9, 0)]);
+  let (runs, _) = due_runs(&daily, AFMissedRunPolicy::RunAll, next_run_at, now);
+  assert_eq!(
+    runs,
+    vec![
+      utc(2024, 1, 1, 9, 0),
+      utc(2024, 1, 2, 9, 0),
+      utc(2024, 1, 3, 9, 0)
+    ]
+  );
+
+  // only the latest missed runs are made
+  let now = utc(2024, 6, 1, 12, 0);
+  let (runs, _) = due_runs(&daily, AFMissedRunPolicy::RunAll, next_run_at, now);
+  assert_eq!(runs.len(), MAX_MISSED_RUNS);
+  assert_eq!(runs.last(), Some(&utc(2024, 6, 1, 9, 0)));
+}
@@ -0,0 +1,135 @@
+use appflowy_worker::webhook_worker::{
+  deliver_webhook, sign_payload, webhook_client, WebhookDeliveryOutcome, DELIVERY_HEADER,
+  EVENT_HEADER, SIGNATURE_HEADER,
+};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use database::webhook::AFWebhookDeliveryJob;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+#[derive(Default)]
+struct Receiver {
+  /// Status codes to respond with, in order. Requests are accepted once they run out.
+  responses: Mutex<Vec<StatusCode>>,
+  requests: Mutex<Vec<(HeaderMap, Bytes)>>,
+}
+
+async fn receive(
+  State(receiver): State<Arc<Receiver>>,
+  headers: HeaderMap,
+  body: Bytes,
+) -> StatusCode {
+  receiver.requests.lock().unwrap().push((headers, body));
+  let mut responses = receiver.responses.lock().unwrap();
+  if responses.is_empty() {
+    StatusCode::OK
+  } else {
+    responses.remove(0)
+  }
+}
+
+async fn run_receiver(responses: Vec<StatusCode>) -> (String, Arc<Receiver>) {
+  let receiver = Arc::new(Receiver {
+    responses: Mutex::new(responses),
+    ..Default::default()
+  });
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}/hook", listener.local_addr().unwrap());
+  let app = Router::new()
+    .route("/hook", post(receive))
+    .with_state(receiver.clone());
+  tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+  (url, receiver)
+}
+
+/// The receivers of the tests listen on the loopback address, which isn't public.
+fn allowed_hosts() -> Vec<String> {
+  vec!["127.0.0.1".to_string()]
+}
+
+fn delivery_job(url: String) -> AFWebhookDeliveryJob {
+  AFWebhookDeliveryJob {
+    delivery_id: uuid::Uuid::new_v4(),
+    event_type: "row.created".to_string(),
+    payload: json!({
+      "event": "row.created",
+      "row": { "id": "row_1", "cells": { "Name": { "data": "Task 1" } } },
+    }),
+    attempts: 0,
+    url,
+    secret: "secret".to_string(),
+  }
+}
+
+#[tokio::test]
+async fn deliver_signed_webhook_test() {
+  let (url, receiver) = run_receiver(vec![]).await;
+  let job = delivery_job(url);
+  let client = webhook_client(allowed_hosts().into());
+
+  let outcome = deliver_webhook(&client, &allowed_hosts(), &job).await;
+  assert_eq!(outcome, WebhookDeliveryOutcome::Delivered { status: 200 });
+
+  let requests = receiver.requests.lock().unwrap();
+  assert_eq!(requests.len(), 1);
+  let (headers, body) = &requests[0];
+  assert_eq!(
+    headers.get(SIGNATURE_HEADER).unwrap().to_str().unwrap(),
+    sign_payload("secret", body)
+  );
+  assert_eq!(headers.get(EVENT_HEADER).unwrap(), "row.created");
+  assert_eq!(
+    headers.get(DELIVERY_HEADER).unwrap().to_str().unwrap(),
+    job.delivery_id.to_string()
+  );
+  let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+  assert_eq!(payload, job.payload);
+}
+
+#[tokio::test]
+async fn deliver_rejected_webhook_test() {
+  let (url, receiver) = run_receiver(vec![StatusCode::INTERNAL_SERVER_ERROR]).await;
+  let job = delivery_job(url);
+  let client = webhook_client(allowed_hosts().into());
+
+  let outcome = deliver_webhook(&client, &allowed_hosts(), &job).await;
+  assert_eq!(outcome, WebhookDeliveryOutcome::Rejected { status: 500 });
+  assert_eq!(outcome.response_status(), Some(500));
+
+  // the same delivery is accepted when it's sent again
+  let outcome = deliver_webhook(&client, &allowed_hosts(), &job).await;
+  assert_eq!(outcome, WebhookDeliveryOutcome::Delivered { status: 200 });
+  assert_eq!(receiver.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn deliver_unreachable_webhook_test() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}/hook", listener.local_addr().unwrap());
+  drop(listener);
+
+  let client = webhook_client(allowed_hosts().into());
+  let outcome = deliver_webhook(&client, &allowed_hosts(), &delivery_job(url)).await;
+  assert!(matches!(outcome, WebhookDeliveryOutcome::Failed { .. }));
+  assert!(outcome.error().is_some());
+}
+
+#[tokio::test]
+async fn deliver_non_public_webhook_test() {
+  let (url, receiver) = run_receiver(vec![]).await;
+  let client = webhook_client(Vec::new().into());
+
+  let outcome = deliver_webhook(&client, &[], &delivery_job(url)).await;
+  assert!(matches!(outcome, WebhookDeliveryOutcome::Failed { .. }));
+  assert!(receiver.requests.lock().unwrap().is_empty());
+
+  // hosts resolving to non public addresses are rejected as well
+  let url = "http://localhost:9/hook".to_string();
+  let outcome = deliver_webhook(&client, &[], &delivery_job(url)).await;
+  assert!(matches!(outcome, WebhookDeliveryOutcome::Failed { .. }));
+}
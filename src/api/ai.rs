@@ -1,6 +1,14 @@
 use crate::api::util::ai_model_from_header;
+use crate::biz::ai::ops::{consume_ai_request, record_ai_usage};
+use crate::biz::ai::prompt::{
+  apply_prompt_template, create_prompt_template, delete_prompt_template, get_prompt_template,
+  list_prompt_templates, update_prompt_template,
+};
+use crate::biz::ai::stream::{get_ai_stream_chunks, start_ai_stream};
+use crate::biz::ai::transcript::create_meeting_notes;
 use crate::state::AppState;
 
+use access_control::act::Action;
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, HttpResponse, Scope};
 use app_error::AppError;
@@ -8,51 +16,112 @@ use appflowy_ai_client::dto::{
   CalculateSimilarityParams, CompleteTextResponse, LocalAIConfig, SimilarityResponse,
   TranslateRowParams, TranslateRowResponse,
 };
+use authentication::jwt::UserUuid;
+use database_entity::dto::AFRole;
 
 use futures_util::{stream, TryStreamExt};
 
 use serde::Deserialize;
 use shared_entity::dto::ai_dto::{
-  CompleteTextParams, SummarizeRowData, SummarizeRowParams, SummarizeRowResponse,
+  AIPromptTemplate, AIStreamChunks, AIStreamStarted, CompleteTextParams,
+  CreateAIPromptTemplateParams, CreateMeetingNotesParams, MeetingNotes, RepeatedAIPromptTemplate,
+  SummarizeRowData, SummarizeRowParams, SummarizeRowResponse, UpdateAIPromptTemplateParams,
 };
 use shared_entity::response::{AppResponse, JsonAppResponse};
 
 use tracing::{error, instrument, trace};
+use uuid::Uuid;
 
 pub fn ai_completion_scope() -> Scope {
   web::scope("/api/ai/{workspace_id}")
     .service(web::resource("/complete").route(web::post().to(complete_text_handler)))
     .service(web::resource("/complete/stream").route(web::post().to(stream_complete_text_handler)))
+    .service(
+      web::resource("/complete/realtime").route(web::post().to(realtime_complete_text_handler)),
+    )
+    .service(web::resource("/stream/{stream_id}").route(web::get().to(get_ai_stream_handler)))
+    .service(web::resource("/transcript").route(web::post().to(create_meeting_notes_handler)))
     .service(web::resource("/summarize_row").route(web::post().to(summarize_row_handler)))
     .service(web::resource("/translate_row").route(web::post().to(translate_row_handler)))
     .service(web::resource("/local/config").route(web::get().to(local_ai_config_handler)))
     .service(
       web::resource("/calculate_similarity").route(web::post().to(calculate_similarity_handler)),
     )
+    .service(
+      web::resource("/prompt")
+        .route(web::get().to(list_prompt_templates_handler))
+        .route(web::post().to(create_prompt_template_handler)),
+    )
+    .service(
+      web::resource("/prompt/{template_id}")
+        .route(web::get().to(get_prompt_template_handler))
+        .route(web::patch().to(update_prompt_template_handler))
+        .route(web::delete().to(delete_prompt_template_handler)),
+    )
 }
 
 async fn complete_text_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
   state: Data<AppState>,
   payload: Json<CompleteTextParams>,
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<CompleteTextResponse>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+  let mut params = payload.into_inner();
+  apply_prompt_template(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &mut params,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
-  let params = payload.into_inner();
   let resp = state
     .ai_client
-    .completion_text(&params.text, params.completion_type, None, ai_model)
+    .completion_text(
+      &params.text,
+      params.completion_type,
+      params.custom_prompt,
+      ai_model,
+    )
     .await
     .map_err(|err| AppError::Internal(err.into()))?;
+  record_ai_usage(&state.pg_pool, &workspace_id, resp.usage.as_ref()).await;
   Ok(AppResponse::Ok().with_data(resp).into())
 }
 
 async fn stream_complete_text_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
   state: Data<AppState>,
   payload: Json<CompleteTextParams>,
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+  let mut params = payload.into_inner();
+  apply_prompt_template(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &mut params,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
-  let params = payload.into_inner();
   match state
     .ai_client
     .stream_completion_text(
@@ -78,12 +147,132 @@ async fn stream_complete_text_handler(
   }
 }
 
+/// Completes the text like [stream_complete_text_handler], but streams the completion over the
+/// realtime connection of the user.
+async fn realtime_complete_text_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
+  state: Data<AppState>,
+  payload: Json<CompleteTextParams>,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<AIStreamStarted>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+  let mut params = payload.into_inner();
+  apply_prompt_template(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &mut params,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
+  let ai_model = ai_model_from_header(&req);
+  let stream = state
+    .ai_client
+    .stream_completion_text(
+      &params.text,
+      params.completion_type,
+      params.custom_prompt,
+      ai_model,
+    )
+    .await
+    .map_err(|err| AppError::AIServiceUnavailable(err.to_string()))?;
+  let stream_id = start_ai_stream(&state.redis_connection_manager, uid, stream).await?;
+  Ok(
+    AppResponse::Ok()
+      .with_data(AIStreamStarted { stream_id })
+      .into(),
+  )
+}
+
+#[derive(Deserialize, Debug)]
+struct AIStreamQuery {
+  /// Sequence number of the last chunk received by the client.
+  #[serde(default)]
+  after: u64,
+}
+
+async fn get_ai_stream_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  query: web::Query<AIStreamQuery>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<AIStreamChunks>> {
+  let (_workspace_id, stream_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let chunks = get_ai_stream_chunks(
+    &state.redis_connection_manager,
+    uid,
+    &stream_id,
+    query.into_inner().after,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(chunks).into())
+}
+
+#[instrument(level = "debug", skip_all, err)]
+async fn create_meeting_notes_handler(
+  user_uuid: UserUuid,
+  path: web::Path<Uuid>,
+  state: Data<AppState>,
+  payload: Json<CreateMeetingNotesParams>,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<MeetingNotes>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  let params = payload.into_inner();
+  let task_database_id = params
+    .task_database
+    .as_ref()
+    .map(|task_database| task_database.database_id.clone());
+  let notes = create_meeting_notes(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.ai_client,
+    uid,
+    workspace_id,
+    params,
+    ai_model_from_header(&req),
+  )
+  .await?;
+  if let Some(database_id) = task_database_id {
+    for row_id in notes
+      .action_items
+      .iter()
+      .filter_map(|item| item.row_id.as_ref())
+    {
+      state
+        .ai_field_scheduler
+        .schedule(&workspace_id.to_string(), &database_id, row_id);
+    }
+  }
+  Ok(AppResponse::Ok().with_data(notes).into())
+}
+
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn summarize_row_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
   state: Data<AppState>,
   payload: Json<SummarizeRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<SummarizeRowResponse>>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
   let params = payload.into_inner();
   match params.data {
     SummarizeRowData::Identity { .. } => {
@@ -100,10 +289,14 @@ async fn summarize_row_handler(
         );
       }
 
+      consume_ai_request(&state.pg_pool, &workspace_id).await?;
       let ai_model = ai_model_from_header(&req);
       let result = state.ai_client.summarize_row(&content, ai_model).await;
       let resp = match result {
-        Ok(resp) => SummarizeRowResponse { text: resp.text },
+        Ok(resp) => {
+          record_ai_usage(&state.pg_pool, &workspace_id, resp.usage.as_ref()).await;
+          SummarizeRowResponse { text: resp.text }
+        },
         Err(err) => {
           error!("Failed to summarize row: {:?}", err);
           SummarizeRowResponse {
@@ -119,14 +312,26 @@ async fn summarize_row_handler(
 
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn translate_row_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
   state: web::Data<AppState>,
   payload: web::Json<TranslateRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<TranslateRowResponse>>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let params = payload.into_inner();
   let ai_model = ai_model_from_header(&req);
   match state.ai_client.translate_row(params.data, ai_model).await {
-    Ok(resp) => Ok(AppResponse::Ok().with_data(resp).into()),
+    Ok(resp) => {
+      record_ai_usage(&state.pg_pool, &workspace_id, resp.usage.as_ref()).await;
+      Ok(AppResponse::Ok().with_data(resp).into())
+    },
     Err(err) => {
       error!("Failed to translate row: {:?}", err);
       Ok(
@@ -170,9 +375,18 @@ async fn local_ai_config_handler(
 
 #[instrument(level = "debug", skip_all, err)]
 async fn calculate_similarity_handler(
+  user_uuid: UserUuid,
+  path: web::Path<String>,
   state: web::Data<AppState>,
   payload: web::Json<CalculateSimilarityParams>,
 ) -> actix_web::Result<Json<AppResponse<SimilarityResponse>>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let params = payload.into_inner();
 
   let response = state
@@ -182,3 +396,87 @@ async fn calculate_similarity_handler(
     .map_err(|err| AppError::AIServiceUnavailable(err.to_string()))?;
   Ok(AppResponse::Ok().with_data(response).into())
 }
+
+async fn list_prompt_templates_handler(
+  user_uuid: UserUuid,
+  path: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<RepeatedAIPromptTemplate>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let templates = list_prompt_templates(&state.pg_pool, &workspace_id).await?;
+  Ok(AppResponse::Ok().with_data(templates).into())
+}
+
+async fn create_prompt_template_handler(
+  user_uuid: UserUuid,
+  path: web::Path<Uuid>,
+  state: Data<AppState>,
+  payload: Json<CreateAIPromptTemplateParams>,
+) -> actix_web::Result<JsonAppResponse<AIPromptTemplate>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let template =
+    create_prompt_template(&state.pg_pool, uid, &workspace_id, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(template).into())
+}
+
+async fn get_prompt_template_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<AIPromptTemplate>> {
+  let (workspace_id, template_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let template = get_prompt_template(&state.pg_pool, &workspace_id, &template_id).await?;
+  Ok(AppResponse::Ok().with_data(template).into())
+}
+
+async fn update_prompt_template_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+  payload: Json<UpdateAIPromptTemplateParams>,
+) -> actix_web::Result<JsonAppResponse<AIPromptTemplate>> {
+  let (workspace_id, template_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let template = update_prompt_template(
+    &state.pg_pool,
+    &workspace_id,
+    &template_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(template).into())
+}
+
+async fn delete_prompt_template_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, template_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  delete_prompt_template(&state.pg_pool, &workspace_id, &template_id).await?;
+  Ok(AppResponse::Ok().into())
+}
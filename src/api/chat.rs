@@ -1,26 +1,43 @@
+use crate::biz::chat::database::answer_database_question;
+use crate::biz::chat::export::export_chat_to_page;
 use crate::biz::chat::ops::{
-  create_chat, create_chat_message, delete_chat, generate_chat_message_answer, get_chat_messages,
+  create_chat, create_chat_message, delete_chat, generate_chat_message_answer,
+  get_chat_message_branches, get_chat_messages, select_chat_question, switch_chat_branch,
   update_chat_message,
 };
+use crate::biz::chat::participant::{
+  broadcast_answer_stream, broadcast_chat_message, enforce_chat_role, get_chat_participants,
+  remove_participants, upsert_participants,
+};
 use crate::state::AppState;
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, HttpResponse, Scope};
 
 use crate::api::util::ai_model_from_header;
+use crate::biz::ai::ops::consume_ai_request;
+use crate::biz::ai::stream::start_ai_stream;
 use app_error::AppError;
-use appflowy_ai_client::dto::{CreateChatContext, RepeatedRelatedQuestion};
+use appflowy_ai_client::dto::{
+  AIModel, CreateChatContext, RepeatedRelatedQuestion, STREAM_ANSWER_KEY, STREAM_METADATA_KEY,
+};
 use authentication::jwt::UserUuid;
 use bytes::Bytes;
 use database::chat;
+use futures::stream::BoxStream;
 use futures::Stream;
 use futures_util::stream;
-use futures_util::{FutureExt, TryStreamExt};
+use futures_util::{FutureExt, StreamExt, TryStreamExt};
 use pin_project::pin_project;
+use serde_json::json;
+use shared_entity::dto::ai_dto::AIStreamStarted;
 use shared_entity::dto::chat_dto::{
-  ChatAuthor, ChatMessage, ChatSettings, CreateAnswerMessageParams, CreateChatMessageParams,
-  CreateChatMessageParamsV2, CreateChatParams, GetChatMessageParams, MessageCursor,
-  RepeatedChatMessage, UpdateChatMessageContentParams, UpdateChatParams,
+  ChatAuthor, ChatMessage, ChatMessageBranches, ChatParticipantRole, ChatSettings,
+  CreateAnswerMessageParams, CreateChatMessageParams, CreateChatMessageParamsV2, CreateChatParams,
+  ExportChatParams, GetChatMessageParams, MessageCursor, RemoveChatParticipantsParams,
+  RepeatedChatMessage, RepeatedChatParticipant, SwitchChatBranchParams,
+  UpdateChatMessageContentParams, UpdateChatParams, UpsertChatParticipantsParams,
 };
+use shared_entity::dto::workspace_dto::Page;
 use shared_entity::response::{AppResponse, JsonAppResponse};
 use std::collections::HashMap;
 use std::pin::Pin;
@@ -51,6 +68,30 @@ pub fn chat_scope() -> Scope {
             .route(web::post().to(update_chat_settings_handler))
       )
 
+      // Participants
+      .service(
+        web::resource("/{chat_id}/participant")
+            .route(web::get().to(get_chat_participants_handler))
+            .route(web::put().to(upsert_chat_participants_handler))
+            .route(web::delete().to(remove_chat_participants_handler))
+      )
+
+      // Branches
+      .service(
+        web::resource("/{chat_id}/branch")
+            .route(web::put().to(switch_chat_branch_handler))
+      )
+      .service(
+        web::resource("/{chat_id}/{message_id}/branch")
+            .route(web::get().to(get_chat_message_branches_handler))
+      )
+
+      // Export
+      .service(
+        web::resource("/{chat_id}/export")
+            .route(web::post().to(export_chat_handler))
+      )
+
       // Message management
       .service(
         web::resource("/{chat_id}/message")
@@ -83,6 +124,10 @@ pub fn chat_scope() -> Scope {
         web::resource("/{chat_id}/{message_id}/v2/answer/stream")
             .route(web::get().to(answer_stream_v2_handler))
       )
+      .service(
+        web::resource("/{chat_id}/{message_id}/v2/answer/realtime")
+            .route(web::post().to(answer_realtime_v2_handler))
+      )
 
       // Additional functionality
       .service(
@@ -95,21 +140,33 @@ pub fn chat_scope() -> Scope {
       )
 }
 async fn create_chat_handler(
+  uuid: UserUuid,
   path: web::Path<String>,
   state: Data<AppState>,
   payload: Json<CreateChatParams>,
 ) -> actix_web::Result<JsonAppResponse<()>> {
   let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
   let params = payload.into_inner();
-  create_chat(&state.pg_pool, params, &workspace_id).await?;
+  create_chat(&state.pg_pool, uid, params, &workspace_id).await?;
   Ok(AppResponse::Ok().into())
 }
 
 async fn delete_chat_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String)>,
   state: Data<AppState>,
 ) -> actix_web::Result<JsonAppResponse<()>> {
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Owner,
+  )
+  .await?;
   delete_chat(&state.pg_pool, &chat_id).await?;
   Ok(AppResponse::Ok().into())
 }
@@ -129,22 +186,57 @@ async fn create_chat_context_handler(
 }
 
 async fn update_question_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
   state: Data<AppState>,
   payload: Json<UpdateChatMessageContentParams>,
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let params = payload.into_inner();
   let ai_model = ai_model_from_header(&req);
-  update_chat_message(&state.pg_pool, params, state.ai_client.clone(), ai_model).await?;
+  update_chat_message(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    state.config.appflowy_web_url.as_deref(),
+    params,
+    state.ai_client.clone(),
+    ai_model,
+    uid,
+    &workspace_id,
+    &state.metrics.request_metrics,
+  )
+  .await?;
   Ok(AppResponse::Ok().into())
 }
 
 async fn get_related_message_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String, i64)>,
   state: Data<AppState>,
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<RepeatedRelatedQuestion>> {
-  let (_workspace_id, chat_id, message_id) = path.into_inner();
+  let (workspace_id, chat_id, message_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
   let resp = state
     .ai_client
@@ -161,12 +253,25 @@ async fn create_question_handler(
   payload: Json<CreateChatMessageParams>,
   uuid: UserUuid,
 ) -> actix_web::Result<JsonAppResponse<ChatMessage>> {
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
   let params = payload.into_inner();
 
   // When create a question, we will extract the metadata from the question content.
   // metadata might include user mention file,page,or user. For example, @Get started.
   for metadata in params.metadata.clone() {
+    // mentioned databases are queried by the server when answering, instead of the AI service
+    if metadata.database_source().is_some() {
+      continue;
+    }
     let (data, desc) = metadata.split_data();
     if let Err(err) = data.validate() {
       error!("Failed to validate metadata: {}", err);
@@ -184,7 +289,6 @@ async fn create_question_handler(
       .map_err(AppError::from)?;
   }
 
-  let uid = state.user_cache.get_user_uid(&uuid).await?;
   let resp = create_chat_message(&state.pg_pool, uid, chat_id, params).await?;
   Ok(AppResponse::Ok().with_data(resp).into())
 }
@@ -200,6 +304,7 @@ async fn create_question_handler_v2(
 }
 
 async fn save_answer_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String)>,
   payload: Json<CreateAnswerMessageParams>,
   state: Data<AppState>,
@@ -207,7 +312,16 @@ async fn save_answer_handler(
   let payload = payload.into_inner();
   payload.validate().map_err(AppError::from)?;
 
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
   let message = database::chat::chat_ops::insert_answer_message(
     &state.pg_pool,
     ChatAuthor::ai(),
@@ -217,22 +331,39 @@ async fn save_answer_handler(
     payload.question_message_id,
   )
   .await?;
+  broadcast_chat_message(&state.pg_pool, &chat_id, &message).await;
 
   Ok(AppResponse::Ok().with_data(message).into())
 }
 async fn answer_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String, i64)>,
   state: Data<AppState>,
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<ChatMessage>> {
-  let (_workspace_id, chat_id, message_id) = path.into_inner();
+  let (workspace_id, chat_id, message_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
   let message = generate_chat_message_answer(
     &state.pg_pool,
+    &state.collab_access_control_storage,
+    state.config.appflowy_web_url.as_deref(),
     state.ai_client.clone(),
     message_id,
     &chat_id,
     ai_model,
+    uid,
+    &workspace_id,
+    &state.metrics.request_metrics,
   )
   .await?;
   Ok(AppResponse::Ok().with_data(message).into())
@@ -240,21 +371,69 @@ async fn answer_handler(
 
 #[instrument(level = "debug", skip_all, err)]
 async fn answer_stream_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String, i64)>,
   state: Data<AppState>,
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
-  let (_workspace_id, chat_id, question_id) = path.into_inner();
-  let (content, metadata) =
-    chat::chat_ops::select_chat_message_content(&state.pg_pool, question_id).await?;
-  let rag_ids = chat::chat_ops::select_chat_rag_ids(&state.pg_pool, &chat_id).await?;
+  let (workspace_id, chat_id, question_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, &workspace_id).await?;
+  let question = select_chat_question(
+    &state.pg_pool,
+    &state.ai_client,
+    &state.metrics.request_metrics,
+    uid,
+    &workspace_id,
+    &chat_id,
+    question_id,
+  )
+  .await?;
   let ai_model = ai_model_from_header(&req);
+  if let Some(source) = question.database {
+    let answer = answer_database_question(
+      &state.pg_pool,
+      &state.collab_access_control_storage,
+      &state.ai_client,
+      state.config.appflowy_web_url.as_deref(),
+      uid,
+      &workspace_id,
+      source,
+      &question.content,
+      ai_model,
+    )
+    .await?;
+    let answer_stream = stream::once(async move { Ok::<_, AppError>(Bytes::from(answer.content)) });
+    let answer_stream =
+      broadcast_answer_stream(&state.pg_pool, &chat_id, question_id, answer_stream).await;
+    return Ok(
+      HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .streaming(answer_stream),
+    );
+  }
   match state
     .ai_client
-    .stream_question(&chat_id, &content, Some(metadata), rag_ids, &ai_model)
+    .stream_question(
+      &chat_id,
+      &question.content,
+      Some(question.metadata),
+      question.rag_ids,
+      &ai_model,
+    )
     .await
   {
     Ok(answer_stream) => {
+      let answer_stream =
+        broadcast_answer_stream(&state.pg_pool, &chat_id, question_id, answer_stream).await;
       let new_answer_stream = answer_stream.map_err(AppError::from);
       Ok(
         HttpResponse::Ok()
@@ -274,54 +453,159 @@ async fn answer_stream_handler(
 
 #[instrument(level = "debug", skip_all, err)]
 async fn answer_stream_v2_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String, i64)>,
   state: Data<AppState>,
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
-  let (_workspace_id, chat_id, question_id) = path.into_inner();
-  let (content, metadata) =
-    chat::chat_ops::select_chat_message_content(&state.pg_pool, question_id).await?;
-  let rag_ids = chat::chat_ops::select_chat_rag_ids(&state.pg_pool, &chat_id).await?;
-  let ai_model = ai_model_from_header(&req);
+  let (workspace_id, chat_id, question_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let answer_stream = answer_v2_stream(
+    &state,
+    uid,
+    &workspace_id,
+    &chat_id,
+    question_id,
+    ai_model_from_header(&req),
+  )
+  .await?;
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/event-stream")
+      .streaming(answer_stream),
+  )
+}
+
+/// Streams the answer like [answer_stream_v2_handler], but over the realtime connection of the user.
+#[instrument(level = "debug", skip_all, err)]
+async fn answer_realtime_v2_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String, i64)>,
+  state: Data<AppState>,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<AIStreamStarted>> {
+  let (workspace_id, chat_id, question_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let answer_stream = answer_v2_stream(
+    &state,
+    uid,
+    &workspace_id,
+    &chat_id,
+    question_id,
+    ai_model_from_header(&req),
+  )
+  .await?;
+  let stream_id = start_ai_stream(&state.redis_connection_manager, uid, answer_stream).await?;
+  Ok(
+    AppResponse::Ok()
+      .with_data(AIStreamStarted { stream_id })
+      .into(),
+  )
+}
+
+/// Returns the stream of the answer to a question, made of JSON objects keyed by
+/// [STREAM_METADATA_KEY] and [STREAM_ANSWER_KEY].
+async fn answer_v2_stream(
+  state: &AppState,
+  uid: i64,
+  workspace_id: &str,
+  chat_id: &str,
+  question_id: i64,
+  ai_model: AIModel,
+) -> Result<BoxStream<'static, Result<Bytes, AppError>>, AppError> {
+  enforce_chat_role(
+    &state.pg_pool,
+    workspace_id,
+    chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  consume_ai_request(&state.pg_pool, workspace_id).await?;
+  let question = select_chat_question(
+    &state.pg_pool,
+    &state.ai_client,
+    &state.metrics.request_metrics,
+    uid,
+    workspace_id,
+    chat_id,
+    question_id,
+  )
+  .await?;
 
   trace!(
-    "[Chat] stream answer for chat: {}, question: {}, rag_ids: {:?}",
+    "[Chat] stream answer for chat: {}, question: {}, rag_ids: {:?}, citations: {}",
     chat_id,
-    content,
-    rag_ids
+    question.content,
+    question.rag_ids,
+    question.citations.len()
   );
+  if let Some(source) = question.database {
+    let answer = answer_database_question(
+      &state.pg_pool,
+      &state.collab_access_control_storage,
+      &state.ai_client,
+      state.config.appflowy_web_url.as_deref(),
+      uid,
+      workspace_id,
+      source,
+      &question.content,
+      ai_model,
+    )
+    .await?;
+    // the answer is sent at once, in the same format as the answers streamed by the AI service
+    let chunks = [
+      json!({ STREAM_METADATA_KEY: [answer.metadata] }),
+      json!({ STREAM_ANSWER_KEY: answer.content }),
+    ]
+    .into_iter()
+    .map(|value| {
+      serde_json::to_vec(&value)
+        .map(Bytes::from)
+        .map_err(AppError::from)
+    })
+    .collect::<Vec<_>>();
+    let answer_stream =
+      broadcast_answer_stream(&state.pg_pool, chat_id, question_id, stream::iter(chunks)).await;
+    return Ok(answer_stream.boxed());
+  }
+
+  // citations are sent ahead of the answer, in the same format as the metadata sent by the AI service
+  let citations = if question.citations.is_empty() {
+    None
+  } else {
+    let value = json!({ STREAM_METADATA_KEY: question.citations });
+    Some(Bytes::from(
+      serde_json::to_vec(&value).map_err(AppError::from)?,
+    ))
+  };
   match state
     .ai_client
     .stream_question_v2(
-      &chat_id,
+      chat_id,
       question_id,
-      &content,
-      Some(metadata),
-      rag_ids,
+      &question.content,
+      Some(question.metadata),
+      question.rag_ids,
       &ai_model,
     )
     .await
   {
     Ok(answer_stream) => {
-      let new_answer_stream = answer_stream.map_err(AppError::from);
-      Ok(
-        HttpResponse::Ok()
-          .content_type("text/event-stream")
-          .streaming(new_answer_stream),
-      )
+      let answer_stream = stream::iter(citations.map(Ok)).chain(answer_stream);
+      let answer_stream =
+        broadcast_answer_stream(&state.pg_pool, chat_id, question_id, answer_stream).await;
+      Ok(answer_stream.map_err(AppError::from).boxed())
+    },
+    Err(err) => {
+      Ok(stream::once(async move { Err(AppError::AIServiceUnavailable(err.to_string())) }).boxed())
     },
-    Err(err) => Ok(
-      HttpResponse::Ok()
-        .content_type("text/event-stream")
-        .streaming(stream::once(async move {
-          Err(AppError::AIServiceUnavailable(err.to_string()))
-        })),
-    ),
   }
 }
 
 #[instrument(level = "debug", skip_all, err)]
 async fn get_chat_message_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String)>,
   query: web::Query<HashMap<String, String>>,
   state: Data<AppState>,
@@ -344,33 +628,188 @@ async fn get_chat_message_handler(
   }
 
   trace!("get chat messages: {:?}", params);
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Viewer,
+  )
+  .await?;
   let messages = get_chat_messages(&state.pg_pool, params, &chat_id).await?;
   Ok(AppResponse::Ok().with_data(messages).into())
 }
 
 #[instrument(level = "debug", skip_all, err)]
 async fn get_chat_settings_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String)>,
   state: Data<AppState>,
 ) -> actix_web::Result<JsonAppResponse<ChatSettings>> {
-  let (_, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Viewer,
+  )
+  .await?;
   let chat_id_uuid = Uuid::parse_str(&chat_id).map_err(AppError::from)?;
   let settings = chat::chat_ops::select_chat_settings(&state.pg_pool, &chat_id_uuid).await?;
   Ok(AppResponse::Ok().with_data(settings).into())
 }
 
 async fn update_chat_settings_handler(
+  uuid: UserUuid,
   path: web::Path<(String, String)>,
   state: Data<AppState>,
   payload: Json<UpdateChatParams>,
 ) -> actix_web::Result<JsonAppResponse<()>> {
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Owner,
+  )
+  .await?;
   let chat_id_uuid = Uuid::parse_str(&chat_id).map_err(AppError::from)?;
   chat::chat_ops::update_chat_settings(&state.pg_pool, &chat_id_uuid, payload.into_inner()).await?;
   Ok(AppResponse::Ok().into())
 }
 
+async fn get_chat_participants_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<RepeatedChatParticipant>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Viewer,
+  )
+  .await?;
+  let participants = get_chat_participants(&state.pg_pool, &chat_id).await?;
+  Ok(AppResponse::Ok().with_data(participants).into())
+}
+
+async fn upsert_chat_participants_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<UpsertChatParticipantsParams>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Owner,
+  )
+  .await?;
+  upsert_participants(&state.pg_pool, &chat_id, uid, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
+
+async fn remove_chat_participants_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<RemoveChatParticipantsParams>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Owner,
+  )
+  .await?;
+  remove_participants(&state.pg_pool, &chat_id, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
+
+async fn get_chat_message_branches_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String, i64)>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<ChatMessageBranches>> {
+  let (workspace_id, chat_id, message_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Viewer,
+  )
+  .await?;
+  let branches = get_chat_message_branches(&state.pg_pool, &chat_id, message_id).await?;
+  Ok(AppResponse::Ok().with_data(branches).into())
+}
+
+async fn switch_chat_branch_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<SwitchChatBranchParams>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Member,
+  )
+  .await?;
+  switch_chat_branch(&state.pg_pool, &chat_id, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
+
+async fn export_chat_handler(
+  uuid: UserUuid,
+  path: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<ExportChatParams>,
+) -> actix_web::Result<JsonAppResponse<Page>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  enforce_chat_role(
+    &state.pg_pool,
+    &workspace_id,
+    &chat_id,
+    uid,
+    ChatParticipantRole::Viewer,
+  )
+  .await?;
+  let page = export_chat_to_page(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &chat_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(page).into())
+}
+
 #[pin_project]
 pub struct FinalAnswerStream<S, F> {
   #[pin]
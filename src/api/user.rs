@@ -1,5 +1,8 @@
 use crate::biz::user::user_delete::delete_user;
 use crate::biz::user::user_info::{get_profile, get_user_workspace_info, update_user};
+use crate::biz::user::user_notification::{
+  get_user_notification_settings, update_user_notification_settings,
+};
 use crate::biz::user::user_verify::verify_token;
 use crate::state::AppState;
 use actix_web::web::{Data, Json};
@@ -7,7 +10,10 @@ use actix_web::Result;
 use actix_web::{web, Scope};
 use authentication::jwt::{Authorization, UserUuid};
 use database_entity::dto::{AFUserProfile, AFUserWorkspaceInfo};
-use shared_entity::dto::auth_dto::{DeleteUserQuery, SignInTokenResponse, UpdateUserParams};
+use shared_entity::dto::auth_dto::{
+  AFUserNotificationSettings, DeleteUserQuery, SignInTokenResponse,
+  UpdateUserNotificationSettingsParams, UpdateUserParams,
+};
 use shared_entity::response::AppResponseError;
 use shared_entity::response::{AppResponse, JsonAppResponse};
 
@@ -17,6 +23,11 @@ pub fn user_scope() -> Scope {
     .service(web::resource("/update").route(web::post().to(update_user_handler)))
     .service(web::resource("/profile").route(web::get().to(get_user_profile_handler)))
     .service(web::resource("/workspace").route(web::get().to(get_user_workspace_info_handler)))
+    .service(
+      web::resource("/notification_settings")
+        .route(web::get().to(get_user_notification_settings_handler))
+        .route(web::patch().to(update_user_notification_settings_handler)),
+    )
     .service(web::resource("").route(web::delete().to(delete_user_handler)))
 }
 
@@ -53,6 +64,28 @@ async fn get_user_workspace_info_handler(
   Ok(AppResponse::Ok().with_data(info).into())
 }
 
+#[tracing::instrument(skip(state), err)]
+async fn get_user_notification_settings_handler(
+  uuid: UserUuid,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<AFUserNotificationSettings>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let settings = get_user_notification_settings(&state.pg_pool, uid).await?;
+  Ok(AppResponse::Ok().with_data(settings).into())
+}
+
+#[tracing::instrument(skip(state, payload), err)]
+async fn update_user_notification_settings_handler(
+  uuid: UserUuid,
+  payload: Json<UpdateUserNotificationSettingsParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<AFUserNotificationSettings>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let settings =
+    update_user_notification_settings(&state.pg_pool, uid, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(settings).into())
+}
+
 #[tracing::instrument(skip(state, auth, payload), err)]
 async fn update_user_handler(
   auth: Authorization,
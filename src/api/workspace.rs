@@ -34,7 +34,6 @@ use authentication::jwt::{Authorization, OptionalUserUuid, UserUuid};
 use bytes::BytesMut;
 use chrono::{DateTime, Duration, Utc};
 use collab::entity::EncodedCollab;
-use collab_database::entity::FieldType;
 use collab_entity::CollabType;
 use collab_folder::timestamp;
 use collab_rt_entity::collab_proto::{CollabDocStateParams, PayloadCompressionType};
@@ -50,6 +49,8 @@ use database_entity::dto::*;
 use futures_util::future::try_join_all;
 use prost::Message as ProstMessage;
 use rayon::prelude::*;
+use shared_entity::dto::ai_dto::PageTitleSuggestions;
+use shared_entity::dto::billing_dto::WorkspaceAIUsageAndLimit;
 use shared_entity::dto::workspace_dto::*;
 use shared_entity::response::AppResponseError;
 use shared_entity::response::{AppResponse, JsonAppResponse};
@@ -158,6 +159,10 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_page_view_handler))
         .route(web::patch().to(update_page_view_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/title-suggestion")
+        .route(web::get().to(get_page_title_suggestion_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/page-view/{view_id}/move")
         .route(web::post().to(move_page_handler)),
@@ -178,9 +183,22 @@ pub fn workspace_scope() -> Scope {
       web::resource("/{workspace_id}/batch/collab")
         .route(web::post().to(batch_create_collab_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/index/status")
+        .route(web::get().to(get_workspace_index_status_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/index/rebuild")
+        .route(web::post().to(post_workspace_index_rebuild_handler))
+        .route(web::delete().to(delete_workspace_index_rebuild_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/usage").route(web::get().to(get_workspace_usage_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/ai-usage-and-limit")
+        .route(web::get().to(get_workspace_ai_usage_and_limit_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/{object_id}/snapshot")
         .route(web::get().to(get_collab_snapshot_handler))
@@ -202,6 +220,10 @@ pub fn workspace_scope() -> Scope {
       web::resource("/published/{publish_namespace}/{publish_name}/blob")
         .route(web::get().to(get_published_collab_blob_handler)),
     )
+    .service(
+      web::resource("/published/{publish_namespace}/{publish_name}/form")
+        .route(web::post().to(post_published_form_submission_handler)),
+    )
     .service(
       web::resource("{workspace_id}/published-duplicate")
         .route(web::post().to(post_published_duplicate_handler)),
@@ -289,6 +311,86 @@ pub fn workspace_scope() -> Scope {
       web::resource("/{workspace_id}/database/{database_id}/row/detail")
         .route(web::get().to(list_database_row_details_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/query")
+        .route(web::post().to(query_database_rows_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/{row_id}")
+        .route(web::patch().to(patch_database_row_handler))
+        .route(web::delete().to(delete_database_row_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/{row_id}/history")
+        .route(web::get().to(list_database_row_history_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/import/csv")
+        .app_data(
+          web::JsonConfig::default().limit(10 * 1024 * 1024), // 10 MB
+        )
+        .route(web::post().to(import_database_csv_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/export/csv")
+        .route(web::get().to(export_database_csv_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/webhook")
+        .route(web::get().to(list_database_webhooks_handler))
+        .route(web::post().to(post_database_webhook_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/webhook/{webhook_id}")
+        .route(web::patch().to(patch_database_webhook_handler))
+        .route(web::delete().to(delete_database_webhook_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/webhook/{webhook_id}/delivery")
+        .route(web::get().to(list_database_webhook_deliveries_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/ai_field")
+        .route(web::get().to(list_database_ai_fields_handler))
+        .route(web::post().to(post_database_ai_field_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/ai_field/{field_id}")
+        .route(web::patch().to(patch_database_ai_field_handler))
+        .route(web::delete().to(delete_database_ai_field_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/ai_field/{field_id}/cell")
+        .route(web::get().to(list_database_ai_field_cells_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/computed_field")
+        .route(web::get().to(list_database_computed_fields_handler))
+        .route(web::post().to(post_database_computed_field_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/computed_field/{field_id}")
+        .route(web::patch().to(patch_database_computed_field_handler))
+        .route(web::delete().to(delete_database_computed_field_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/form")
+        .route(web::post().to(post_database_form_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row_schedule")
+        .route(web::get().to(list_database_row_schedules_handler))
+        .route(web::post().to(post_database_row_schedule_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row_schedule/{schedule_id}")
+        .route(web::patch().to(patch_database_row_schedule_handler))
+        .route(web::delete().to(delete_database_row_schedule_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row_schedule/{schedule_id}/run")
+        .route(web::get().to(list_database_row_schedule_runs_handler)),
+    )
 }
 
 pub fn collab_scope() -> Scope {
@@ -699,15 +801,27 @@ async fn create_collab_handler(
   {
     match state
       .indexer_provider
-      .create_collab_embeddings(&params)
+      .create_collab_embeddings(&workspace_id, &params)
       .await
     {
       Ok(embeddings) => params.embeddings = embeddings,
-      Err(err) => tracing::warn!(
-        "failed to fetch embeddings for document {}: {}",
-        params.object_id,
-        err
-      ),
+      Err(err) if err.is_ai_quota_exceeded() => {
+        tracing::debug!("skip indexing document {}: {}", params.object_id, err);
+      },
+      Err(err) => {
+        tracing::warn!(
+          "failed to fetch embeddings for document {}: {}",
+          params.object_id,
+          err
+        );
+        enqueue_indexing(
+          &state,
+          &workspace_id,
+          &params.object_id,
+          &params.collab_type,
+        )
+        .await;
+      },
     }
   }
 
@@ -827,12 +941,28 @@ async fn batch_create_collab_handler(
     .can_index_workspace(&workspace_id)
     .await?
   {
-    if let Err(err) = fetch_embeddings(&state.indexer_provider, &mut collab_params_list).await {
+    if let Err(err) = fetch_embeddings(
+      &state.indexer_provider,
+      &workspace_id,
+      &mut collab_params_list,
+    )
+    .await
+    {
       tracing::warn!(
         "failed to fetch embeddings for {} new documents: {}",
         collab_params_list.len(),
         err
       );
+      let retry = !err.is_ai_quota_exceeded();
+      for params in collab_params_list.iter().filter(|_| retry) {
+        enqueue_indexing(
+          &state,
+          &workspace_id,
+          &params.object_id,
+          &params.collab_type,
+        )
+        .await;
+      }
     }
   }
 
@@ -1153,6 +1283,29 @@ async fn get_page_view_handler(
   Ok(Json(AppResponse::Ok().with_data(page_collab)))
 }
 
+async fn get_page_title_suggestion_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PageTitleSuggestions>>> {
+  let (workspace_id, view_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let suggestions = biz::ai::page_title::suggest_page_titles(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.ai_client,
+    uid,
+    &workspace_id.to_string(),
+    &view_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(suggestions)))
+}
+
 #[instrument(level = "trace", skip_all, err)]
 async fn get_collab_snapshot_handler(
   payload: Json<QuerySnapshotParams>,
@@ -1268,13 +1421,29 @@ async fn update_collab_handler(
       .await
       .map_err(|err| AppError::Internal(err.into()))??;
 
-      match indexer.index(&mut_params.object_id, encoded).await {
+      let result = match state.indexer_provider.check_ai_quota(&workspace_id).await {
+        Ok(_) => indexer.index(&mut_params.object_id, encoded, false).await,
+        Err(err) => Err(err),
+      };
+      match result {
         Ok(embeddings) => mut_params.embeddings = embeddings,
-        Err(err) => tracing::warn!(
-          "failed to fetch embeddings for document {}: {}",
-          mut_params.object_id,
-          err
-        ),
+        Err(err) if err.is_ai_quota_exceeded() => {
+          tracing::debug!("skip indexing document {}: {}", mut_params.object_id, err);
+        },
+        Err(err) => {
+          tracing::warn!(
+            "failed to fetch embeddings for document {}: {}",
+            mut_params.object_id,
+            err
+          );
+          enqueue_indexing(
+            &state,
+            &workspace_id,
+            &mut_params.object_id,
+            &mut_params.collab_type,
+          )
+          .await;
+        },
       }
 
       params = mut_params;
@@ -1547,6 +1716,7 @@ async fn list_published_collab_info_handler(
   state: Data<AppState>,
 ) -> Result<Json<AppResponse<Vec<PublishInfoView>>>> {
   let publish_infos = biz::workspace::publish::list_collab_publish_info(
+    &state.pg_pool,
     state.published_collab_store.as_ref(),
     &state.collab_access_control_storage,
     &workspace_id.into_inner(),
@@ -1829,6 +1999,80 @@ async fn get_workspace_usage_handler(
   Ok(Json(AppResponse::Ok().with_data(res)))
 }
 
+async fn get_workspace_ai_usage_and_limit_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<WorkspaceAIUsageAndLimit>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let res = biz::ai::ops::get_workspace_ai_usage_and_limit(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(res)))
+}
+
+async fn get_workspace_index_status_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<AFWorkspaceIndexStatus>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let status =
+    biz::search::get_workspace_index_status(&state.pg_pool, &state.indexer_provider, workspace_id)
+      .await?;
+  Ok(Json(AppResponse::Ok().with_data(status)))
+}
+
+async fn post_workspace_index_rebuild_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<AFIndexRebuildProgress>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let progress = state
+    .indexer_provider
+    .rebuild_workspace_index(workspace_id)
+    .await?;
+  Ok(Json(AppResponse::Ok().with_data(progress)))
+}
+
+async fn delete_workspace_index_rebuild_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<AFIndexRebuildProgress>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let progress = state
+    .indexer_provider
+    .cancel_rebuild(&workspace_id)
+    .await?
+    .ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "no index rebuild has been requested for workspace {}",
+        workspace_id
+      ))
+    })?;
+  Ok(Json(AppResponse::Ok().with_data(progress)))
+}
+
 async fn get_workspace_folder_handler(
   user_uuid: UserUuid,
   workspace_id: web::Path<Uuid>,
@@ -2000,9 +2244,123 @@ async fn post_database_row_handler(
     cells_by_id.into_inner(),
   )
   .await?;
+  state
+    .ai_field_scheduler
+    .schedule(&workspace_id, &db_id, &new_db_row_id);
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
   Ok(Json(AppResponse::Ok().with_data(new_db_row_id)))
 }
 
+async fn patch_database_row_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+  cells_by_id: Json<HashMap<String, serde_json::Value>>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::collab::ops::update_database_row(
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &row_id,
+    uid,
+    cells_by_id.into_inner(),
+  )
+  .await?;
+  state
+    .ai_field_scheduler
+    .schedule(&workspace_id, &db_id, &row_id);
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_database_row_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::collab::ops::delete_database_row(
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &row_id,
+    uid,
+  )
+  .await?;
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_database_row_history_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+  query: web::Query<QueryDatabaseRowHistoryParams>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRowChange>>>> {
+  let (workspace_id, db_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let history = biz::collab::database_row_history::get_database_row_history(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &workspace_id,
+    &db_id,
+    &row_id,
+    query.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(history)))
+}
+
+async fn query_database_rows_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  params: Json<QueryDatabaseRowsParams>,
+) -> Result<Json<AppResponse<AFDatabaseRowQueryResult>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let result = biz::collab::database_query::query_database_rows(
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    params.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(result)))
+}
+
 async fn get_database_fields_handler(
   user_uuid: UserUuid,
   path_param: web::Path<(String, String)>,
@@ -2047,80 +2405,642 @@ async fn post_database_fields_handler(
     field.into_inner(),
   )
   .await?;
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
 
   Ok(Json(AppResponse::Ok().with_data(field_id)))
 }
 
-async fn list_database_row_id_updated_handler(
+async fn import_database_csv_handler(
   user_uuid: UserUuid,
   path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-  param: web::Query<ListDatabaseRowUpdatedParam>,
-) -> Result<Json<AppResponse<Vec<DatabaseRowUpdatedItem>>>> {
+  payload: Json<AFImportDatabaseCsv>,
+) -> Result<Json<AppResponse<AFImportDatabaseCsvResult>>> {
   let (workspace_id, db_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Read)
+    .enforce_action(&uid, &workspace_id, Action::Write)
     .await?;
 
-  // Default to 1 hour ago
-  let after: DateTime<Utc> = param
-    .after
-    .unwrap_or_else(|| Utc::now() - Duration::hours(1));
-
-  let db_rows = biz::collab::ops::list_database_row_ids_updated(
+  let result = biz::collab::database_csv::import_database_csv(
     &state.collab_access_control_storage,
     &state.pg_pool,
+    uid,
     &workspace_id,
     &db_id,
-    &after,
+    payload.into_inner(),
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+  for row_id in &result.row_ids {
+    state
+      .ai_field_scheduler
+      .schedule(&workspace_id, &db_id, row_id);
+  }
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
+  Ok(Json(AppResponse::Ok().with_data(result)))
 }
 
-async fn list_database_row_details_handler(
+async fn export_database_csv_handler(
   user_uuid: UserUuid,
   path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-  param: web::Query<ListDatabaseRowDetailParam>,
-) -> Result<Json<AppResponse<Vec<AFDatabaseRowDetail>>>> {
+  query: web::Query<ExportDatabaseCsvParams>,
+) -> Result<HttpResponse> {
   let (workspace_id, db_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let list_db_row_query = param.into_inner();
-  let row_ids = list_db_row_query.into_ids();
-
-  if let Err(e) = Uuid::parse_str(&workspace_id) {
-    return Err(
-      AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", db_id, e)).into(),
-    );
-  }
-  if let Err(e) = Uuid::parse_str(&db_id) {
-    return Err(AppError::InvalidRequest(format!("invalid database id `{}`: {}", db_id, e)).into());
-  }
-
-  for id in row_ids.iter() {
-    if let Err(e) = Uuid::parse_str(id) {
-      return Err(AppError::InvalidRequest(format!("invalid row id `{}`: {}", id, e)).into());
-    }
-  }
-
   state
     .workspace_access_control
     .enforce_action(&uid, &workspace_id, Action::Read)
     .await?;
 
-  static UNSUPPORTED_FIELD_TYPES: &[FieldType] = &[FieldType::Relation];
-
+  let csv = biz::collab::database_csv::export_database_csv(
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    query.into_inner().view_id,
+  )
+  .await?;
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/csv; charset=utf-8")
+      .body(csv),
+  )
+}
+
+async fn list_database_webhooks_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseWebhook>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id, AFRole::Owner)
+    .await?;
+
+  let webhooks =
+    biz::collab::database_webhook::list_database_webhooks(&state.pg_pool, &workspace_id, &db_id)
+      .await?;
+  Ok(Json(AppResponse::Ok().with_data(webhooks)))
+}
+
+async fn post_database_webhook_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFInsertDatabaseWebhook>,
+) -> Result<Json<AppResponse<AFDatabaseWebhook>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id, AFRole::Owner)
+    .await?;
+
+  let webhook = biz::collab::database_webhook::create_database_webhook(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    payload.into_inner(),
+    &state.config.webhook.allowed_hosts,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(webhook)))
+}
+
+async fn patch_database_webhook_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+  payload: Json<AFUpdateDatabaseWebhook>,
+) -> Result<Json<AppResponse<AFDatabaseWebhook>>> {
+  let (workspace_id, db_id, webhook_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id, AFRole::Owner)
+    .await?;
+
+  let webhook = biz::collab::database_webhook::update_database_webhook_settings(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &webhook_id,
+    payload.into_inner(),
+    &state.config.webhook.allowed_hosts,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(webhook)))
+}
+
+async fn delete_database_webhook_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, webhook_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id, AFRole::Owner)
+    .await?;
+
+  biz::collab::database_webhook::delete_database_webhook(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &webhook_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_database_webhook_deliveries_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseWebhookDelivery>>>> {
+  let (workspace_id, db_id, webhook_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id, AFRole::Owner)
+    .await?;
+
+  let deliveries = biz::collab::database_webhook::list_database_webhook_deliveries(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &webhook_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(deliveries)))
+}
+
+async fn list_database_ai_fields_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseAIField>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let ai_fields =
+    biz::ai::database_field::list_ai_fields(&state.pg_pool, &workspace_id, &db_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(ai_fields)))
+}
+
+async fn post_database_ai_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFInsertDatabaseAIField>,
+) -> Result<Json<AppResponse<AFDatabaseAIField>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let ai_field = biz::ai::database_field::create_ai_field(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.ai_field_scheduler,
+    uid,
+    &workspace_id,
+    &db_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(ai_field)))
+}
+
+async fn patch_database_ai_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFUpdateDatabaseAIField>,
+) -> Result<Json<AppResponse<AFDatabaseAIField>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let ai_field = biz::ai::database_field::update_ai_field(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.ai_field_scheduler,
+    &workspace_id,
+    &db_id,
+    &field_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(ai_field)))
+}
+
+async fn delete_database_ai_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::ai::database_field::delete_ai_field(&state.pg_pool, &workspace_id, &db_id, &field_id)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_database_ai_field_cells_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseAICell>>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let cells =
+    biz::ai::database_field::list_ai_field_cells(&state.pg_pool, &workspace_id, &db_id, &field_id)
+      .await?;
+  Ok(Json(AppResponse::Ok().with_data(cells)))
+}
+
+async fn list_database_computed_fields_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseComputedField>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let computed_fields = biz::collab::database_computed_field::list_computed_fields(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(computed_fields)))
+}
+
+async fn post_database_computed_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFInsertDatabaseComputedField>,
+) -> Result<Json<AppResponse<AFDatabaseComputedField>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let computed_field = biz::collab::database_computed_field::create_computed_field(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    payload.into_inner(),
+  )
+  .await?;
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
+  Ok(Json(AppResponse::Ok().with_data(computed_field)))
+}
+
+async fn patch_database_computed_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFUpdateDatabaseComputedField>,
+) -> Result<Json<AppResponse<AFDatabaseComputedField>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let computed_field = biz::collab::database_computed_field::update_computed_field(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    &field_id,
+    payload.into_inner(),
+  )
+  .await?;
+  state
+    .database_webhook_notifier
+    .notify(&workspace_id, &db_id);
+  Ok(Json(AppResponse::Ok().with_data(computed_field)))
+}
+
+async fn delete_database_computed_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::collab::database_computed_field::delete_computed_field(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &field_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn post_database_form_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  params: Json<AFPublishDatabaseForm>,
+) -> Result<Json<AppResponse<AFDatabaseForm>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let form = biz::collab::database_form::publish_database_form(
+    state.published_collab_store.as_ref(),
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.captcha_verifier,
+    &user_uuid,
+    &workspace_id,
+    &db_id,
+    params.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(form)))
+}
+
+async fn post_published_form_submission_handler(
+  req: HttpRequest,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  params: Json<AFSubmitDatabaseForm>,
+) -> Result<Json<AppResponse<AFDatabaseFormSubmission>>> {
+  let (publish_namespace, publish_name) = path_param.into_inner();
+  let remote_ip = biz::collab::database_form::visitor_ip(
+    req.peer_addr(),
+    req
+      .headers()
+      .get("X-Real-IP")
+      .and_then(|value| value.to_str().ok()),
+    &state.config.trusted_proxies,
+  );
+  let submission = biz::collab::database_form::submit_database_form(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.captcha_verifier,
+    &state.form_submission_limiter,
+    &publish_namespace,
+    &publish_name,
+    remote_ip.as_deref(),
+    params.into_inner(),
+  )
+  .await?;
+  state.ai_field_scheduler.schedule(
+    &submission.workspace_id,
+    &submission.database_id,
+    &submission.row_id,
+  );
+  state
+    .database_webhook_notifier
+    .notify(&submission.workspace_id, &submission.database_id);
+  Ok(Json(AppResponse::Ok().with_data(
+    AFDatabaseFormSubmission {
+      row_id: submission.row_id,
+    },
+  )))
+}
+
+async fn list_database_row_schedules_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRowSchedule>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let schedules = biz::collab::database_row_schedule::list_database_row_schedules(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(schedules)))
+}
+
+async fn post_database_row_schedule_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  payload: Json<AFInsertDatabaseRowSchedule>,
+) -> Result<Json<AppResponse<AFDatabaseRowSchedule>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let schedule = biz::collab::database_row_schedule::create_database_row_schedule(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &db_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(schedule)))
+}
+
+async fn patch_database_row_schedule_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+  payload: Json<AFUpdateDatabaseRowSchedule>,
+) -> Result<Json<AppResponse<AFDatabaseRowSchedule>>> {
+  let (workspace_id, db_id, schedule_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let schedule = biz::collab::database_row_schedule::update_database_row_schedule_settings(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &workspace_id,
+    &db_id,
+    &schedule_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(schedule)))
+}
+
+async fn delete_database_row_schedule_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, schedule_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::collab::database_row_schedule::delete_database_row_schedule(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &schedule_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_database_row_schedule_runs_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRowScheduleRun>>>> {
+  let (workspace_id, db_id, schedule_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let runs = biz::collab::database_row_schedule::list_database_row_schedule_runs(
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &schedule_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(runs)))
+}
+
+async fn list_database_row_id_updated_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  param: web::Query<ListDatabaseRowUpdatedParam>,
+) -> Result<Json<AppResponse<Vec<DatabaseRowUpdatedItem>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  // Default to 1 hour ago
+  let after: DateTime<Utc> = param
+    .after
+    .unwrap_or_else(|| Utc::now() - Duration::hours(1));
+
+  let db_rows = biz::collab::ops::list_database_row_ids_updated(
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &after,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+}
+
+async fn list_database_row_details_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  param: web::Query<ListDatabaseRowDetailParam>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRowDetail>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let list_db_row_query = param.into_inner();
+  let row_ids = list_db_row_query.into_ids();
+
+  if let Err(e) = Uuid::parse_str(&workspace_id) {
+    return Err(
+      AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", db_id, e)).into(),
+    );
+  }
+  if let Err(e) = Uuid::parse_str(&db_id) {
+    return Err(AppError::InvalidRequest(format!("invalid database id `{}`: {}", db_id, e)).into());
+  }
+
+  for id in row_ids.iter() {
+    if let Err(e) = Uuid::parse_str(id) {
+      return Err(AppError::InvalidRequest(format!("invalid row id `{}`: {}", id, e)).into());
+    }
+  }
+
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
   let db_rows = biz::collab::ops::list_database_row_details(
     &state.collab_access_control_storage,
+    &state.pg_pool,
     uid,
     workspace_id,
     db_id,
     &row_ids,
-    UNSUPPORTED_FIELD_TYPES,
   )
   .await?;
   Ok(Json(AppResponse::Ok().with_data(db_rows)))
@@ -2171,11 +3091,12 @@ async fn parser_realtime_msg(
 
 async fn fetch_embeddings(
   indexer_provider: &IndexerProvider,
+  workspace_id: &str,
   params: &mut [CollabParams],
 ) -> Result<(), AppError> {
   let mut futures = Vec::with_capacity(params.len());
   for param in params.iter() {
-    let future = indexer_provider.create_collab_embeddings(param);
+    let future = indexer_provider.create_collab_embeddings(workspace_id, param);
     futures.push(future);
   }
 
@@ -2187,6 +3108,27 @@ async fn fetch_embeddings(
   Ok(())
 }
 
+/// Enqueues a collab to be indexed in the background when its embeddings couldn't be created
+/// right away.
+async fn enqueue_indexing(
+  state: &AppState,
+  workspace_id: &str,
+  object_id: &str,
+  collab_type: &CollabType,
+) {
+  if let Err(err) = state
+    .indexer_provider
+    .enqueue_indexing(workspace_id, object_id, collab_type)
+    .await
+  {
+    tracing::error!(
+      "failed to enqueue indexing of document {}: {}",
+      object_id,
+      err
+    );
+  }
+}
+
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn get_collab_info_handler(
   payload: Json<WorkspaceCollabIdentify>,
@@ -20,6 +20,7 @@ use actix_web::cookie::Key;
 use actix_web::middleware::NormalizePath;
 use actix_web::{dev::Server, web::Data, App, HttpServer};
 use anyhow::{Context, Error};
+use appflowy_collaborate::ai_stream::AIStreamListener;
 use appflowy_collaborate::collab::access_control::CollabStorageAccessControlImpl;
 use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
 use aws_sdk_s3::operation::create_bucket::CreateBucketError;
@@ -37,12 +38,16 @@ use tracing::{error, info};
 
 use appflowy_ai_client::client::AppFlowyAIClient;
 use appflowy_collaborate::actix_ws::server::RealtimeServerActor;
+use appflowy_collaborate::application::spawn_indexing_queue_consumer;
 use appflowy_collaborate::collab::cache::CollabCache;
 use appflowy_collaborate::collab::storage::CollabStorageImpl;
 use appflowy_collaborate::command::{CLCommandReceiver, CLCommandSender};
 use appflowy_collaborate::indexer::IndexerProvider;
+use appflowy_collaborate::reminder::ReminderRecorder;
+use appflowy_collaborate::row_history::RowHistoryRecorder;
 use appflowy_collaborate::snapshot::SnapshotControl;
 use appflowy_collaborate::CollaborationServer;
+use collab_stream::indexing_queue::{IndexingQueue, IndexingQueueConfig};
 use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
 use mailer::sender::Mailer;
 use snowflake::Snowflake;
@@ -61,6 +66,12 @@ use crate::api::template::template_scope;
 use crate::api::user::user_scope;
 use crate::api::workspace::{collab_scope, workspace_scope};
 use crate::api::ws::ws_scope;
+use crate::biz::ai::database_field::spawn_ai_field_worker;
+use crate::biz::collab::database_computed_field::spawn_computed_field_worker;
+use crate::biz::collab::database_form::{CaptchaVerifier, FormSubmissionLimiter};
+use crate::biz::collab::database_row_schedule::spawn_row_schedule_runner;
+use crate::biz::collab::database_webhook::spawn_database_webhook_watcher;
+use crate::biz::collab::reminder::spawn_reminder_dispatcher;
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::{
   PublishedCollabPostgresStore, PublishedCollabS3StoreWithPostgresFallback, PublishedCollabStore,
@@ -136,6 +147,8 @@ pub async fn run_actix_server(
     config.collab.edit_state_max_count,
     config.collab.edit_state_max_secs,
     state.indexer_provider.clone(),
+    Arc::new(RowHistoryRecorder::new(state.pg_pool.clone())),
+    Arc::new(ReminderRecorder::new(state.pg_pool.clone())),
   )
   .await
   .unwrap();
@@ -244,15 +257,24 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
 
   // Redis
   info!("Connecting to Redis...");
-  let redis_conn_manager = get_redis_client(config.redis_uri.expose_secret()).await?;
+  let (redis_client, redis_conn_manager) =
+    get_redis_client(config.redis_uri.expose_secret()).await?;
 
   info!("Setup AppFlowy AI: {}", config.appflowy_ai.url());
   let appflowy_ai_client = AppFlowyAIClient::new(&config.appflowy_ai.url());
-  let indexer_provider = IndexerProvider::new(pg_pool.clone(), appflowy_ai_client.clone());
+  let indexing_queue =
+    IndexingQueue::new(redis_conn_manager.clone(), IndexingQueueConfig::default()).await?;
+  let indexer_provider = IndexerProvider::new(
+    pg_pool.clone(),
+    appflowy_ai_client.clone(),
+    indexing_queue,
+    metrics.embedding_metrics.clone(),
+  );
 
   // Pg listeners
   info!("Setting up Pg listeners...");
   let pg_listeners = Arc::new(PgListeners::new(&pg_pool).await?);
+  let ai_stream_listener = Arc::new(AIStreamListener::new(&redis_client).await?);
   // let collab_member_listener = pg_listeners.subscribe_collab_member_change();
 
   info!(
@@ -289,6 +311,8 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     config.collab.s3_collab_threshold as usize,
   );
 
+  spawn_indexing_queue_consumer(&indexer_provider, &collab_cache, &redis_conn_manager).await?;
+
   let collab_storage_access_control = CollabStorageAccessControlImpl {
     collab_access_control: collab_access_control.clone(),
     workspace_access_control: workspace_access_control.clone(),
@@ -317,7 +341,31 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     .connect_lazy();
 
   let grpc_history_client = Arc::new(Mutex::new(HistoryClient::new(channel)));
+  let ai_field_scheduler = spawn_ai_field_worker(
+    pg_pool.clone(),
+    collab_access_control_storage.clone(),
+    appflowy_ai_client.clone(),
+  );
+  let database_webhook_notifier =
+    spawn_database_webhook_watcher(pg_pool.clone(), collab_access_control_storage.clone());
+  spawn_row_schedule_runner(
+    pg_pool.clone(),
+    collab_access_control_storage.clone(),
+    ai_field_scheduler.clone(),
+    database_webhook_notifier.clone(),
+  );
+  spawn_computed_field_worker(
+    pg_pool.clone(),
+    collab_access_control_storage.clone(),
+    pg_listeners.subscribe_database_row_change(),
+  );
   let mailer = get_mailer(&config.mailer).await?;
+  spawn_reminder_dispatcher(
+    pg_pool.clone(),
+    collab_access_control_storage.clone(),
+    mailer.clone(),
+    config.appflowy_web_url.clone(),
+  );
 
   info!("Application state initialized");
   Ok(AppState {
@@ -336,12 +384,17 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     published_collab_store,
     bucket_client: s3_client,
     pg_listeners,
+    ai_stream_listener,
     metrics,
     gotrue_admin,
     mailer,
     ai_client: appflowy_ai_client,
     grpc_history_client,
     indexer_provider,
+    ai_field_scheduler,
+    database_webhook_notifier,
+    captcha_verifier: CaptchaVerifier::from_config(config),
+    form_submission_limiter: FormSubmissionLimiter::default(),
   })
 }
 
@@ -358,14 +411,16 @@ fn get_admin_client(
   )
 }
 
-async fn get_redis_client(redis_uri: &str) -> Result<redis::aio::ConnectionManager, Error> {
+async fn get_redis_client(
+  redis_uri: &str,
+) -> Result<(redis::Client, redis::aio::ConnectionManager), Error> {
   info!("Connecting to redis with uri: {}", redis_uri);
-  let manager = redis::Client::open(redis_uri)
-    .context("failed to connect to redis")?
+  let client = redis::Client::open(redis_uri).context("failed to connect to redis")?;
+  let manager = client
     .get_connection_manager()
     .await
     .context("failed to get the connection manager")?;
-  Ok(manager)
+  Ok((client, manager))
 }
 
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<aws_sdk_s3::Client, Error> {
@@ -0,0 +1,784 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::AIModel;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::{DateTime, Utc};
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use collab_database::rows::{DatabaseRowBody, RowDetail};
+use collab_database::template::entity::CELL_DATA;
+use collab_entity::CollabType;
+use dashmap::DashMap;
+use database::ai_field::{
+  delete_ai_database_field, insert_ai_database_field, select_ai_database_cell_states,
+  select_ai_database_cells, select_ai_database_fields, select_ai_databases,
+  select_retriable_ai_database_rows, update_ai_database_field_prompt, upsert_ai_database_cell,
+  AFAIDatabaseCellState,
+};
+use database::collab::{CollabStorage, GetCollabOrigin};
+use database_entity::dto::CollabParams;
+use serde_json::Value;
+use shared_entity::dto::ai_dto::{CompletionType, CustomPrompt};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseAICell, AFDatabaseAICellStatus, AFDatabaseAIField, AFInsertDatabaseAIField,
+  AFInsertDatabaseField, AFUpdateDatabaseAIField,
+};
+use sqlx::PgPool;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::{interval, sleep, Instant};
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use crate::biz::ai::ops::{consume_ai_request, record_ai_usage};
+use crate::biz::ai::prompt::{prompt_variables, render_prompt};
+use crate::biz::collab::ops::{
+  add_database_field, list_database_row_ids, list_database_row_ids_updated,
+};
+use crate::biz::collab::utils::{
+  collab_to_bin, field_by_id_name_uniq, get_database_body, get_latest_collab,
+  get_row_details_serde, type_option_reader_by_id, type_option_writer_by_id,
+};
+use crate::biz::workspace::ops::broadcast_update;
+
+/// Interval of looking for rows which were edited, ie. through the realtime server, or whose AI
+/// cells should be computed again.
+const AI_FIELD_POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// Maximum number of rows computed at the same time.
+const AI_FIELD_MAX_CONCURRENT_ROWS: usize = 4;
+/// Minimum interval between two AI requests of the same workspace.
+const AI_FIELD_WORKSPACE_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+/// Delay before computing the cells which were rate limited again.
+const AI_FIELD_RATE_LIMITED_RETRY_DELAY: Duration = Duration::from_secs(15 * 60);
+/// Delay before computing the cells which failed again.
+const AI_FIELD_FAILED_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Number of failures after which a cell is computed again only when its input changes.
+const AI_FIELD_MAX_ATTEMPTS: i32 = 3;
+/// Maximum number of rows retried in a single poll.
+const AI_FIELD_RETRY_BATCH_SIZE: i64 = 100;
+
+const AI_FIELD_SYSTEM_PROMPT: &str = "You fill a cell of a database row by following the \
+instruction of the user. Reply with the content of the cell only, without any explanation.";
+
+pub async fn create_ai_field(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  scheduler: &AIFieldScheduler,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  params: AFInsertDatabaseAIField,
+) -> Result<AFDatabaseAIField, AppError> {
+  if params.name.trim().is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The name of an AI field can't be empty".to_string(),
+    ));
+  }
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let fields = get_fields(collab_storage, workspace_id, database_id).await?;
+  validate_prompt(&params.prompt, &fields, None)?;
+
+  let field_id = add_database_field(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    AFInsertDatabaseField {
+      name: params.name,
+      field_type: FieldType::RichText.into(),
+      type_option_data: None,
+    },
+  )
+  .await?;
+  let ai_field = insert_ai_database_field(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    &field_id,
+    &params.prompt,
+    uid,
+  )
+  .await?;
+  schedule_all_rows(collab_storage, scheduler, workspace_id, database_id).await?;
+  Ok(ai_field)
+}
+
+pub async fn list_ai_fields(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseAIField>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_ai_database_fields(pg_pool, &workspace_uuid, database_id).await
+}
+
+/// Changes the prompt of an AI field and computes all of its cells again.
+pub async fn update_ai_field(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  scheduler: &AIFieldScheduler,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+  params: AFUpdateDatabaseAIField,
+) -> Result<AFDatabaseAIField, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let fields = get_fields(collab_storage, workspace_id, database_id).await?;
+  validate_prompt(&params.prompt, &fields, Some(field_id))?;
+
+  let mut txn = pg_pool.begin().await?;
+  let ai_field = update_ai_database_field_prompt(
+    &mut txn,
+    &workspace_uuid,
+    database_id,
+    field_id,
+    &params.prompt,
+  )
+  .await?;
+  txn.commit().await?;
+  schedule_all_rows(collab_storage, scheduler, workspace_id, database_id).await?;
+  Ok(ai_field)
+}
+
+/// Stops filling the field with the AI. The field and the cells computed so far are kept.
+pub async fn delete_ai_field(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  delete_ai_database_field(pg_pool, &workspace_uuid, database_id, field_id).await
+}
+
+pub async fn list_ai_field_cells(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+) -> Result<Vec<AFDatabaseAICell>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_ai_database_cells(pg_pool, &workspace_uuid, database_id, field_id).await
+}
+
+async fn get_fields(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<Field>, AppError> {
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let fields = db_body.fields.get_all_fields(&db_collab.transact());
+  Ok(fields)
+}
+
+/// Checks that the prompt references at least one field of the database, and that all the
+/// referenced fields exist and are not the AI field itself.
+fn validate_prompt(
+  prompt: &str,
+  fields: &[Field],
+  ai_field_id: Option<&str>,
+) -> Result<(), AppError> {
+  let variables = prompt_variables(prompt);
+  if variables.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The prompt of an AI field must reference at least one field, e.g. {{Description}}"
+        .to_string(),
+    ));
+  }
+  let field_by_name = field_by_id_name_uniq(fields.to_vec())
+    .into_values()
+    .map(|field| (field.name.clone(), field))
+    .collect::<HashMap<_, _>>();
+  for name in variables {
+    match field_by_name.get(name) {
+      None => {
+        return Err(AppError::InvalidRequest(format!(
+          "The prompt references an unknown field: {}",
+          name
+        )))
+      },
+      Some(field) if Some(field.id.as_str()) == ai_field_id => {
+        return Err(AppError::InvalidRequest(
+          "The prompt of an AI field can't reference the field itself".to_string(),
+        ))
+      },
+      Some(_) => {},
+    }
+  }
+  Ok(())
+}
+
+async fn schedule_all_rows(
+  collab_storage: &CollabAccessControlStorage,
+  scheduler: &AIFieldScheduler,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<(), AppError> {
+  for row in list_database_row_ids(collab_storage, workspace_id, database_id).await? {
+    scheduler.schedule(workspace_id, database_id, &row.id);
+  }
+  Ok(())
+}
+
+/// Schedules computing the AI cells of database rows in the background.
+#[derive(Clone)]
+pub struct AIFieldScheduler {
+  sender: mpsc::UnboundedSender<AIFieldJob>,
+}
+
+impl AIFieldScheduler {
+  pub fn schedule(&self, workspace_id: &str, database_id: &str, row_id: &str) {
+    let job = AIFieldJob {
+      workspace_id: workspace_id.to_string(),
+      database_id: database_id.to_string(),
+      row_id: row_id.to_string(),
+    };
+    if let Err(err) = self.sender.send(job) {
+      warn!("[AI field] failed to schedule row {}: {}", row_id, err);
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct AIFieldJob {
+  workspace_id: String,
+  database_id: String,
+  row_id: String,
+}
+
+/// Starts computing the AI cells of database rows in the background. Rows are computed when they
+/// are scheduled, ie. after being inserted, and when they are found to be edited.
+pub fn spawn_ai_field_worker(
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  ai_client: AppFlowyAIClient,
+) -> AIFieldScheduler {
+  let (sender, receiver) = mpsc::unbounded_channel();
+  let worker = Arc::new(AIFieldWorker {
+    pg_pool,
+    collab_storage,
+    ai_client,
+    permits: Arc::new(Semaphore::new(AI_FIELD_MAX_CONCURRENT_ROWS)),
+    in_progress: DashMap::new(),
+    next_request_at: DashMap::new(),
+  });
+  tokio::spawn(worker.run(receiver));
+  AIFieldScheduler { sender }
+}
+
+struct AIFieldWorker {
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  ai_client: AppFlowyAIClient,
+  permits: Arc<Semaphore>,
+  /// Rows being computed. The value is set when the row is scheduled again in the meantime, in which
+  /// case it's computed once more after it's done.
+  in_progress: DashMap<String, bool>,
+  /// Earliest time of the next AI request of each workspace.
+  next_request_at: DashMap<String, Instant>,
+}
+
+/// A cell whose new content should be written to the row.
+struct ComputedCell {
+  field_id: String,
+  content: String,
+  /// Hash of the prompt the content was computed from.
+  input_hash: String,
+  uid: i64,
+}
+
+impl AIFieldWorker {
+  async fn run(self: Arc<Self>, mut receiver: mpsc::UnboundedReceiver<AIFieldJob>) {
+    info!("[AI field] worker started");
+    let mut poll_interval = interval(AI_FIELD_POLL_INTERVAL);
+    let mut last_poll = Utc::now();
+    loop {
+      tokio::select! {
+        job = receiver.recv() => match job {
+          Some(job) => self.clone().spawn_job(job).await,
+          None => break,
+        },
+        _ = poll_interval.tick() => {
+          // rows edited right before the previous poll may not have been persisted yet
+          let since = last_poll - chrono::Duration::seconds(5);
+          last_poll = Utc::now();
+          for job in self.poll_jobs(since).await {
+            self.clone().spawn_job(job).await;
+          }
+        },
+      }
+    }
+  }
+
+  async fn spawn_job(self: Arc<Self>, job: AIFieldJob) {
+    if let Some(mut rerun) = self.in_progress.get_mut(&job.row_id) {
+      *rerun = true;
+      return;
+    }
+    self.in_progress.insert(job.row_id.clone(), false);
+    // the permit is awaited by the task, so that the jobs received in the meantime are still
+    // deduplicated instead of queuing up in the channel
+    tokio::spawn(async move {
+      let _permit = match self.permits.clone().acquire_owned().await {
+        Ok(permit) => permit,
+        Err(_) => {
+          self.in_progress.remove(&job.row_id);
+          return;
+        },
+      };
+      loop {
+        if let Err(err) = self.compute_row(&job).await {
+          warn!(
+            "[AI field] failed to compute row {} of database {}: {}",
+            job.row_id, job.database_id, err
+          );
+        }
+        if self
+          .in_progress
+          .remove_if(&job.row_id, |_, rerun| !*rerun)
+          .is_some()
+        {
+          break;
+        }
+        if let Some(mut rerun) = self.in_progress.get_mut(&job.row_id) {
+          *rerun = false;
+        }
+      }
+    });
+  }
+
+  /// Returns rows edited since the given time in databases which have AI fields, together with rows
+  /// whose AI cells should be computed again.
+  async fn poll_jobs(&self, since: DateTime<Utc>) -> Vec<AIFieldJob> {
+    let mut jobs = vec![];
+    let databases = match select_ai_databases(&self.pg_pool).await {
+      Ok(databases) => databases,
+      Err(err) => {
+        warn!("[AI field] failed to get databases with AI fields: {}", err);
+        return jobs;
+      },
+    };
+    for database in databases {
+      let workspace_id = database.workspace_id.to_string();
+      match list_database_row_ids_updated(
+        &self.collab_storage,
+        &self.pg_pool,
+        &workspace_id,
+        &database.database_id,
+        &since,
+      )
+      .await
+      {
+        Ok(rows) => jobs.extend(rows.into_iter().map(|row| AIFieldJob {
+          workspace_id: workspace_id.clone(),
+          database_id: database.database_id.clone(),
+          row_id: row.row_id,
+        })),
+        Err(err) => warn!(
+          "[AI field] failed to get updated rows of database {}: {}",
+          database.database_id, err
+        ),
+      }
+    }
+
+    let now = Utc::now();
+    let result = select_retriable_ai_database_rows(
+      &self.pg_pool,
+      now - chrono::Duration::from_std(AI_FIELD_RATE_LIMITED_RETRY_DELAY).unwrap_or_default(),
+      now - chrono::Duration::from_std(AI_FIELD_FAILED_RETRY_DELAY).unwrap_or_default(),
+      AI_FIELD_MAX_ATTEMPTS,
+      AI_FIELD_RETRY_BATCH_SIZE,
+    )
+    .await;
+    match result {
+      Ok(rows) => jobs.extend(rows.into_iter().map(|row| AIFieldJob {
+        workspace_id: row.workspace_id.to_string(),
+        database_id: row.database_id,
+        row_id: row.row_id,
+      })),
+      Err(err) => warn!("[AI field] failed to get rows to retry: {}", err),
+    }
+    jobs
+  }
+
+  async fn compute_row(&self, job: &AIFieldJob) -> Result<(), AppError> {
+    let workspace_uuid = Uuid::parse_str(&job.workspace_id)?;
+    let ai_fields =
+      select_ai_database_fields(&self.pg_pool, &workspace_uuid, &job.database_id).await?;
+    if ai_fields.is_empty() {
+      return Ok(());
+    }
+
+    let fields = get_fields(&self.collab_storage, &job.workspace_id, &job.database_id).await?;
+    let row_collab = match get_latest_collab(
+      &self.collab_storage,
+      GetCollabOrigin::Server,
+      &job.workspace_id,
+      &job.row_id,
+      CollabType::DatabaseRow,
+    )
+    .await
+    {
+      Ok(collab) => collab,
+      // the row has been deleted in the meantime
+      Err(err) if err.is_record_not_found() => return Ok(()),
+      Err(err) => return Err(err),
+    };
+    let row_detail = RowDetail::from_collab(&row_collab)
+      .ok_or_else(|| AppError::Internal(anyhow!("Failed to read row: {}", job.row_id)))?;
+    let type_option_reader_by_id = type_option_reader_by_id(&fields);
+    let field_by_id = field_by_id_name_uniq(fields.clone());
+    let values = get_row_details_serde(row_detail, &field_by_id, &type_option_reader_by_id)
+      .into_iter()
+      .map(|(name, cell)| {
+        let value = cell.get(CELL_DATA).map(cell_to_text).unwrap_or_default();
+        (name, value)
+      })
+      .collect::<HashMap<_, _>>();
+    let states =
+      select_ai_database_cell_states(&self.pg_pool, &job.database_id, &job.row_id).await?;
+
+    let mut computed = vec![];
+    let mut rate_limited: Option<String> = None;
+    for ai_field in ai_fields {
+      // the field may have been removed from the database
+      let Some(field) = field_by_id.get(&ai_field.field_id) else {
+        continue;
+      };
+      let variables = values
+        .iter()
+        .filter(|(name, _)| **name != field.name)
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect::<HashMap<_, _>>();
+      let prompt = render_prompt(&ai_field.prompt, &variables);
+      let input_hash = format!("{:x}", md5::compute(&prompt));
+      if !should_compute(states.get(&ai_field.field_id), &input_hash) {
+        continue;
+      }
+
+      let has_input = prompt_variables(&ai_field.prompt)
+        .into_iter()
+        .any(|name| variables.get(name).is_some_and(|v| !v.trim().is_empty()));
+      if !has_input {
+        // there is nothing to compute the cell from, so it's cleared instead
+        trace!("[AI field] clear cell {}/{}", job.row_id, field.id);
+        let is_empty = !values.get(&field.name).is_some_and(|v| !v.is_empty());
+        if is_empty {
+          self
+            .record_state(
+              job,
+              &field.id,
+              AFDatabaseAICellStatus::Completed,
+              &input_hash,
+              None,
+            )
+            .await;
+        } else {
+          computed.push(ComputedCell {
+            field_id: field.id.clone(),
+            content: String::new(),
+            input_hash,
+            uid: ai_field.created_by,
+          });
+        }
+        continue;
+      }
+
+      if let Some(err) = &rate_limited {
+        self
+          .record_state(
+            job,
+            &field.id,
+            AFDatabaseAICellStatus::RateLimited,
+            &input_hash,
+            Some(err),
+          )
+          .await;
+        continue;
+      }
+
+      self
+        .record_state(
+          job,
+          &field.id,
+          AFDatabaseAICellStatus::Pending,
+          &input_hash,
+          None,
+        )
+        .await;
+      match self.complete(&job.workspace_id, &prompt).await {
+        Ok(content) => computed.push(ComputedCell {
+          field_id: field.id.clone(),
+          content,
+          input_hash,
+          uid: ai_field.created_by,
+        }),
+        Err(err) if err.is_ai_quota_exceeded() => {
+          let err = err.to_string();
+          self
+            .record_state(
+              job,
+              &field.id,
+              AFDatabaseAICellStatus::RateLimited,
+              &input_hash,
+              Some(&err),
+            )
+            .await;
+          rate_limited = Some(err);
+        },
+        Err(err) => {
+          self
+            .record_state(
+              job,
+              &field.id,
+              AFDatabaseAICellStatus::Failed,
+              &input_hash,
+              Some(&err.to_string()),
+            )
+            .await;
+        },
+      }
+    }
+
+    if computed.is_empty() {
+      return Ok(());
+    }
+    if let Err(err) = self.write_cells(job, &fields, &computed).await {
+      let message = err.to_string();
+      for cell in &computed {
+        self
+          .record_state(
+            job,
+            &cell.field_id,
+            AFDatabaseAICellStatus::Failed,
+            &cell.input_hash,
+            Some(&message),
+          )
+          .await;
+      }
+      return Err(err);
+    }
+    Ok(())
+  }
+
+  /// Sends the prompt to the AI, making sure that requests of a workspace are spaced out and don't
+  /// exceed its AI quota.
+  async fn complete(&self, workspace_id: &str, prompt: &str) -> Result<String, AppError> {
+    let wait = {
+      let now = Instant::now();
+      let mut next_request_at = self
+        .next_request_at
+        .entry(workspace_id.to_string())
+        .or_insert(now);
+      let request_at = (*next_request_at).max(now);
+      *next_request_at = request_at + AI_FIELD_WORKSPACE_REQUEST_INTERVAL;
+      request_at - now
+    };
+    if !wait.is_zero() {
+      sleep(wait).await;
+    }
+
+    consume_ai_request(&self.pg_pool, workspace_id).await?;
+    let resp = self
+      .ai_client
+      .completion_text(
+        prompt,
+        None::<CompletionType>,
+        Some(CustomPrompt {
+          system: AI_FIELD_SYSTEM_PROMPT.to_string(),
+          user: None,
+        }),
+        AIModel::DefaultModel,
+      )
+      .await
+      .map_err(|err| AppError::AIServiceUnavailable(err.to_string()))?;
+    record_ai_usage(&self.pg_pool, workspace_id, resp.usage.as_ref()).await;
+    Ok(resp.text.trim().to_string())
+  }
+
+  /// Writes the computed cells to the latest version of the row, which may have been edited while
+  /// the cells were computed, and broadcasts them to the clients editing the row as an update.
+  async fn write_cells(
+    &self,
+    job: &AIFieldJob,
+    fields: &[Field],
+    computed: &[ComputedCell],
+  ) -> Result<(), AppError> {
+    let mut row_collab = match get_latest_collab(
+      &self.collab_storage,
+      GetCollabOrigin::Server,
+      &job.workspace_id,
+      &job.row_id,
+      CollabType::DatabaseRow,
+    )
+    .await
+    {
+      Ok(collab) => collab,
+      // the row has been deleted in the meantime
+      Err(err) if err.is_record_not_found() => return Ok(()),
+      Err(err) => return Err(err),
+    };
+    let type_option_writer_by_id = type_option_writer_by_id(fields);
+    let row_body = DatabaseRowBody::open(job.row_id.clone().into(), &mut row_collab)
+      .map_err(|err| AppError::Internal(anyhow!("Failed to open row {}: {}", job.row_id, err)))?;
+    let update = {
+      let mut txn = row_collab.transact_mut();
+      row_body.update(&mut txn, |row_update| {
+        row_update
+          .set_last_modified(Utc::now().timestamp())
+          .update_cells(|cells_update| {
+            for cell in computed {
+              if let Some(writer) = type_option_writer_by_id.get(&cell.field_id) {
+                let new_cell = writer.convert_json_to_cell(Value::String(cell.content.clone()));
+                cells_update.insert_cell(&cell.field_id, new_cell);
+              }
+            }
+          });
+      });
+      txn.encode_update_v1()
+    };
+    let encoded_row = collab_to_bin(row_collab, CollabType::DatabaseRow).await?;
+
+    // the row is written on behalf of the user who created the AI field
+    let uid = computed[0].uid;
+    let mut txn = self.pg_pool.begin().await?;
+    self
+      .collab_storage
+      .upsert_new_collab_with_transaction(
+        &job.workspace_id,
+        &uid,
+        CollabParams {
+          object_id: job.row_id.clone(),
+          encoded_collab_v1: encoded_row.into(),
+          collab_type: CollabType::DatabaseRow,
+          embeddings: None,
+        },
+        &mut txn,
+        "inserting AI cells of database row from server",
+      )
+      .await?;
+    for cell in computed {
+      upsert_ai_database_cell(
+        txn.deref_mut(),
+        &job.database_id,
+        &cell.field_id,
+        &job.row_id,
+        AFDatabaseAICellStatus::Completed,
+        &cell.input_hash,
+        None,
+      )
+      .await?;
+    }
+    txn.commit().await?;
+    broadcast_update(&self.collab_storage, &job.row_id, update).await
+  }
+
+  async fn record_state(
+    &self,
+    job: &AIFieldJob,
+    field_id: &str,
+    status: AFDatabaseAICellStatus,
+    input_hash: &str,
+    error: Option<&str>,
+  ) {
+    if let Err(err) = upsert_ai_database_cell(
+      &self.pg_pool,
+      &job.database_id,
+      field_id,
+      &job.row_id,
+      status,
+      input_hash,
+      error,
+    )
+    .await
+    {
+      warn!(
+        "[AI field] failed to record state of cell {}/{}: {}",
+        job.row_id, field_id, err
+      );
+    }
+  }
+}
+
+/// A cell is computed when its input changed, or when the previous attempt didn't succeed.
+fn should_compute(state: Option<&AFAIDatabaseCellState>, input_hash: &str) -> bool {
+  match state {
+    None => true,
+    Some(state) if state.input_hash != input_hash => true,
+    Some(state) => match AFDatabaseAICellStatus::from(state.status) {
+      AFDatabaseAICellStatus::Completed => false,
+      AFDatabaseAICellStatus::Pending | AFDatabaseAICellStatus::RateLimited => true,
+      AFDatabaseAICellStatus::Failed => state.attempts < AI_FIELD_MAX_ATTEMPTS,
+    },
+  }
+}
+
+/// Converts the JSON value of a cell to the text used in prompts.
+fn cell_to_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    Value::Array(items) => items
+      .iter()
+      .map(cell_to_text)
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join(", "),
+    value => value.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn cell_to_text_test() {
+    assert_eq!(cell_to_text(&json!(null)), "");
+    assert_eq!(cell_to_text(&json!("To Do")), "To Do");
+    assert_eq!(cell_to_text(&json!(["social", "", "news"])), "social, news");
+    assert_eq!(cell_to_text(&json!(true)), "true");
+  }
+
+  #[test]
+  fn should_compute_test() {
+    let state = |status: AFDatabaseAICellStatus, attempts: i32| AFAIDatabaseCellState {
+      field_id: "field".to_string(),
+      status: status as i16,
+      input_hash: "hash".to_string(),
+      attempts,
+    };
+    assert!(should_compute(None, "hash"));
+    assert!(should_compute(
+      Some(&state(AFDatabaseAICellStatus::Completed, 0)),
+      "other"
+    ));
+    assert!(!should_compute(
+      Some(&state(AFDatabaseAICellStatus::Completed, 0)),
+      "hash"
+    ));
+    assert!(should_compute(
+      Some(&state(AFDatabaseAICellStatus::RateLimited, 0)),
+      "hash"
+    ));
+    assert!(should_compute(
+      Some(&state(AFDatabaseAICellStatus::Failed, 1)),
+      "hash"
+    ));
+    assert!(!should_compute(
+      Some(&state(
+        AFDatabaseAICellStatus::Failed,
+        AI_FIELD_MAX_ATTEMPTS
+      )),
+      "hash"
+    ));
+  }
+}
@@ -0,0 +1,6 @@
+pub mod database_field;
+pub mod ops;
+pub mod page_title;
+pub mod prompt;
+pub mod stream;
+pub mod transcript;
@@ -0,0 +1,44 @@
+use app_error::AppError;
+use appflowy_ai_client::dto::AIUsage;
+use database::ai_usage::{
+  consume_workspace_ai_request, record_workspace_ai_tokens, select_workspace_ai_usage_and_limit,
+};
+use shared_entity::dto::billing_dto::WorkspaceAIUsageAndLimit;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Checks that the workspace didn't use up its AI quotas and counts a new AI request. Must be called
+/// before every completion or chat request sent to the AI service.
+pub async fn consume_ai_request(pg_pool: &PgPool, workspace_id: &str) -> Result<(), AppError> {
+  let workspace_id = Uuid::parse_str(workspace_id)?;
+  consume_workspace_ai_request(pg_pool, &workspace_id).await
+}
+
+/// Records the tokens consumed by a request sent to the AI service, if it reported them. The request
+/// has already been served, so a failure is only logged.
+pub async fn record_ai_usage(pg_pool: &PgPool, workspace_id: &str, usage: Option<&AIUsage>) {
+  let usage = match usage {
+    Some(usage) if usage.total_tokens > 0 => usage,
+    _ => return,
+  };
+  let result = match Uuid::parse_str(workspace_id) {
+    Ok(workspace_id) => {
+      record_workspace_ai_tokens(pg_pool, &workspace_id, usage.total_tokens).await
+    },
+    Err(err) => Err(err.into()),
+  };
+  if let Err(err) = result {
+    tracing::warn!(
+      "failed to record AI usage of workspace {}: {}",
+      workspace_id,
+      err
+    );
+  }
+}
+
+pub async fn get_workspace_ai_usage_and_limit(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceAIUsageAndLimit, AppError> {
+  select_workspace_ai_usage_and_limit(pg_pool, workspace_id).await
+}
@@ -0,0 +1,110 @@
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{AIModel, CompletionType, CustomPrompt};
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use shared_entity::dto::ai_dto::PageTitleSuggestions;
+use sqlx::PgPool;
+
+use crate::biz::ai::ops::{consume_ai_request, record_ai_usage};
+use crate::biz::ai::prompt::get_page_content;
+
+const MAX_TITLE_SUGGESTIONS: usize = 3;
+const MAX_TITLE_LEN: usize = 100;
+
+const TITLE_SUGGESTION_PROMPT: &str = "Suggest three short titles for the following document. \
+Write the titles in the language of the document. Reply with one title per line, without numbering \
+or quotes.";
+
+/// Suggests titles for a page based on its content. Meant for pages which haven't been named by
+/// their authors yet.
+pub async fn suggest_page_titles(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  ai_client: &AppFlowyAIClient,
+  uid: i64,
+  workspace_id: &str,
+  view_id: &str,
+) -> Result<PageTitleSuggestions, AppError> {
+  let content = get_page_content(collab_storage, uid, workspace_id, view_id).await?;
+  let content = content.trim();
+  if content.is_empty() {
+    return Err(AppError::InvalidRequest(format!(
+      "page:{} has no content to suggest a title from",
+      view_id
+    )));
+  }
+
+  consume_ai_request(pg_pool, workspace_id).await?;
+  let resp = ai_client
+    .completion_text(
+      content,
+      None::<CompletionType>,
+      Some(CustomPrompt {
+        system: TITLE_SUGGESTION_PROMPT.to_string(),
+        user: None,
+      }),
+      AIModel::DefaultModel,
+    )
+    .await?;
+  record_ai_usage(pg_pool, workspace_id, resp.usage.as_ref()).await;
+  Ok(PageTitleSuggestions {
+    titles: parse_titles(&resp.text),
+  })
+}
+
+/// Extracts titles from the AI response, one per line, dropping list markers and quotes which
+/// the model may add despite being asked not to.
+fn parse_titles(text: &str) -> Vec<String> {
+  let mut titles: Vec<String> = Vec::new();
+  for line in text.lines() {
+    let title = strip_list_marker(line.trim())
+      .trim()
+      .trim_matches(['"', '\'', '“', '”'])
+      .trim();
+    if title.is_empty() || title.chars().count() > MAX_TITLE_LEN {
+      continue;
+    }
+    if !titles.iter().any(|existing| existing == title) {
+      titles.push(title.to_string());
+    }
+    if titles.len() == MAX_TITLE_SUGGESTIONS {
+      break;
+    }
+  }
+  titles
+}
+
+/// Strips a bullet (`-`, `*`) or a numbered list marker (`1.`, `1)`) from the start of a line,
+/// keeping titles which merely start with a number, ie. `2024 Roadmap`.
+fn strip_list_marker(line: &str) -> &str {
+  if let Some(rest) = line.strip_prefix(['-', '*']) {
+    return rest;
+  }
+  let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+  if digits > 0 {
+    if let Some(rest) = line[digits..].strip_prefix(['.', ')']) {
+      return rest;
+    }
+  }
+  line
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_titles_test() {
+    let text =
+      "1. \"Quarterly Report\"\n\n- Sales Overview\n2) Quarterly Report\n* Q3 Results\nExtra";
+    assert_eq!(
+      parse_titles(text),
+      vec!["Quarterly Report", "Sales Overview", "Q3 Results"]
+    );
+    assert!(parse_titles("  \n").is_empty());
+    assert_eq!(
+      parse_titles("1. 2024 Roadmap\n3D Printing Guide\n10) 5.5 Release Notes"),
+      vec!["2024 Roadmap", "3D Printing Guide", "5.5 Release Notes"]
+    );
+  }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab_document::document::DocumentBody;
+use collab_entity::CollabType;
+use database::ai_prompt::{
+  delete_ai_prompt_template, insert_ai_prompt_template, select_ai_prompt_template,
+  select_ai_prompt_templates, update_ai_prompt_template,
+};
+use database::collab::GetCollabOrigin;
+use shared_entity::dto::ai_dto::{
+  AIPromptTemplate, CompleteTextParams, CreateAIPromptTemplateParams, CustomPrompt,
+  RepeatedAIPromptTemplate, UpdateAIPromptTemplateParams,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::biz::collab::ops::get_latest_collab_folder;
+use crate::biz::collab::utils::{collab_from_doc_state, get_latest_collab_encoded};
+
+const TEXT_VARIABLE: &str = "text";
+const PAGE_TITLE_VARIABLE: &str = "page_title";
+const PAGE_CONTENT_VARIABLE: &str = "page_content";
+
+/// Maximum number of characters of a page passed to a prompt template.
+const PAGE_CONTENT_MAX_LEN: usize = 8000;
+
+pub async fn create_prompt_template(
+  pg_pool: &PgPool,
+  uid: i64,
+  workspace_id: &Uuid,
+  params: CreateAIPromptTemplateParams,
+) -> Result<AIPromptTemplate, AppError> {
+  params.validate()?;
+  insert_ai_prompt_template(pg_pool, workspace_id, uid, params).await
+}
+
+pub async fn list_prompt_templates(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<RepeatedAIPromptTemplate, AppError> {
+  let items = select_ai_prompt_templates(pg_pool, workspace_id).await?;
+  Ok(RepeatedAIPromptTemplate { items })
+}
+
+pub async fn get_prompt_template(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+) -> Result<AIPromptTemplate, AppError> {
+  select_ai_prompt_template(pg_pool, workspace_id, template_id).await
+}
+
+pub async fn update_prompt_template(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+  params: UpdateAIPromptTemplateParams,
+) -> Result<AIPromptTemplate, AppError> {
+  let is_blank = |value: &Option<String>| value.as_ref().is_some_and(|v| v.trim().is_empty());
+  if is_blank(&params.name) || is_blank(&params.system_prompt) {
+    return Err(AppError::InvalidRequest(
+      "The name and the system prompt of a prompt template can't be empty".to_string(),
+    ));
+  }
+  update_ai_prompt_template(pg_pool, workspace_id, template_id, params).await
+}
+
+pub async fn delete_prompt_template(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  template_id: &Uuid,
+) -> Result<(), AppError> {
+  delete_ai_prompt_template(pg_pool, workspace_id, template_id).await
+}
+
+/// Replaces the prompt template referenced by the params, if any, with a custom prompt whose
+/// variables are filled from the text to complete and the page it comes from.
+pub async fn apply_prompt_template(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  params: &mut CompleteTextParams,
+) -> Result<(), AppError> {
+  let template_id = match params.prompt_template_id {
+    None => return Ok(()),
+    Some(template_id) => template_id,
+  };
+  if params.completion_type.is_some() || params.custom_prompt.is_some() {
+    return Err(AppError::InvalidRequest(
+      "A prompt template can't be combined with a completion type or a custom prompt".to_string(),
+    ));
+  }
+
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let template = select_ai_prompt_template(pg_pool, &workspace_uuid, &template_id).await?;
+  let uses_variable = |name: &str| {
+    let placeholder = format!("{{{{{}}}}}", name);
+    template.system_prompt.contains(&placeholder)
+      || template
+        .user_prompt
+        .as_ref()
+        .is_some_and(|user_prompt| user_prompt.contains(&placeholder))
+  };
+
+  let mut variables = HashMap::from([(TEXT_VARIABLE, params.text.clone())]);
+  if let Some(page_id) = &params.page_id {
+    if uses_variable(PAGE_TITLE_VARIABLE) {
+      let title = get_page_title(collab_storage, uid, workspace_id, page_id).await?;
+      variables.insert(PAGE_TITLE_VARIABLE, title);
+    }
+    if uses_variable(PAGE_CONTENT_VARIABLE) {
+      let content = get_page_content(collab_storage, uid, workspace_id, page_id).await?;
+      variables.insert(PAGE_CONTENT_VARIABLE, content);
+    }
+  }
+
+  params.custom_prompt = Some(CustomPrompt {
+    system: render_prompt(&template.system_prompt, &variables),
+    user: template
+      .user_prompt
+      .as_ref()
+      .map(|user_prompt| render_prompt(user_prompt, &variables)),
+  });
+  Ok(())
+}
+
+/// Replaces the `{{name}}` placeholders of a prompt with the values of the variables. Unknown
+/// variables are replaced with an empty string.
+pub(crate) fn render_prompt(prompt: &str, variables: &HashMap<&str, String>) -> String {
+  let mut rendered = String::with_capacity(prompt.len());
+  let mut rest = prompt;
+  while let Some(start) = rest.find("{{") {
+    let Some(len) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    rendered.push_str(&rest[..start]);
+    let name = rest[start + 2..start + 2 + len].trim();
+    if let Some(value) = variables.get(name) {
+      rendered.push_str(value);
+    } else {
+      warn!("[AI] unknown variable in prompt template: {}", name);
+    }
+    rest = &rest[start + 2 + len + 2..];
+  }
+  rendered.push_str(rest);
+  rendered
+}
+
+/// Returns the names of the `{{name}}` placeholders of a prompt.
+pub(crate) fn prompt_variables(prompt: &str) -> Vec<&str> {
+  let mut variables = vec![];
+  let mut rest = prompt;
+  while let Some(start) = rest.find("{{") {
+    let Some(len) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    variables.push(rest[start + 2..start + 2 + len].trim());
+    rest = &rest[start + 2 + len + 2..];
+  }
+  variables
+}
+
+async fn get_page_title(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  page_id: &str,
+) -> Result<String, AppError> {
+  let folder =
+    get_latest_collab_folder(collab_storage, GetCollabOrigin::User { uid }, workspace_id).await?;
+  let view = folder
+    .get_view(page_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("page:{} is not found", page_id)))?;
+  Ok(view.name.clone())
+}
+
+pub(crate) async fn get_page_content(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  page_id: &str,
+) -> Result<String, AppError> {
+  let encoded_collab = get_latest_collab_encoded(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    workspace_id,
+    page_id,
+    CollabType::Document,
+  )
+  .await?;
+  let collab = collab_from_doc_state(encoded_collab.doc_state.to_vec(), page_id)?;
+  let document = DocumentBody::from_collab(&collab)
+    .ok_or_else(|| AppError::Internal(anyhow!("Failed to open document: {}", page_id)))?;
+  let mut content = document
+    .to_plain_text(collab.transact(), false, true)
+    .map_err(|err| AppError::Internal(anyhow!("Failed to read document {}: {}", page_id, err)))?;
+  if let Some((index, _)) = content.char_indices().nth(PAGE_CONTENT_MAX_LEN) {
+    content.truncate(index);
+  }
+  Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_prompt_test() {
+    let variables = HashMap::from([
+      (TEXT_VARIABLE, "hello world".to_string()),
+      (PAGE_TITLE_VARIABLE, "Notes".to_string()),
+    ]);
+    assert_eq!(
+      render_prompt("Rewrite {{text}} from {{ page_title }}.", &variables),
+      "Rewrite hello world from Notes."
+    );
+    assert_eq!(
+      render_prompt("{{page_content}}{{text}}", &variables),
+      "hello world"
+    );
+    assert_eq!(
+      render_prompt("unclosed {{text", &variables),
+      "unclosed {{text"
+    );
+  }
+
+  #[test]
+  fn prompt_variables_test() {
+    assert_eq!(
+      prompt_variables("Translate {{ Description }} to {{Language}}, {{unclosed"),
+      vec!["Description", "Language"]
+    );
+    assert!(prompt_variables("no variables").is_empty());
+  }
+}
@@ -0,0 +1,266 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use bytes::Bytes;
+use collab_rt_entity::ai::{
+  AIStreamChunk, AIStreamEvent, AIStreamNotification, AI_STREAM_EVENT_CHANNEL,
+};
+use futures_util::{Stream, StreamExt};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared_entity::dto::ai_dto::{AIStreamChunkData, AIStreamChunks};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::state::RedisConnectionManager;
+
+/// How long the chunks of a stream are kept for clients to catch up after reconnecting.
+const AI_STREAM_TTL_SECS: u64 = 10 * 60;
+
+fn stream_state_key(stream_id: &str) -> String {
+  format!("af_ai_stream:{}", stream_id)
+}
+
+fn stream_chunks_key(stream_id: &str) -> String {
+  format!("af_ai_stream:{}:chunks", stream_id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AIStreamState {
+  uid: i64,
+  status: AIStreamStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum AIStreamStatus {
+  Streaming,
+  Finished,
+  Failed(String),
+}
+
+/// Streams an AI response to the realtime connections of the user, instead of the response of the
+/// request. Returns the id of the stream, which identifies its [AIStreamEvent]s.
+///
+/// The chunks are kept in Redis for a while, so that a client which missed some of them can fetch
+/// them with [get_ai_stream_chunks].
+pub async fn start_ai_stream<S, E>(
+  redis_client: &RedisConnectionManager,
+  uid: i64,
+  stream: S,
+) -> Result<String, AppError>
+where
+  S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+  E: Display + Send + 'static,
+{
+  let stream_id = Uuid::new_v4().to_string();
+  set_stream_state(redis_client, &stream_id, uid, AIStreamStatus::Streaming).await?;
+
+  let mut redis_client = redis_client.clone();
+  let id = stream_id.clone();
+  tokio::spawn(async move {
+    let stream_id = id;
+    let mut stream = Box::pin(stream);
+    let mut decoder = Utf8Decoder::default();
+    let mut last_seq = 0;
+    let mut error = None;
+    while let Some(item) = stream.next().await {
+      let data = match item {
+        Ok(bytes) => decoder.decode(&bytes),
+        Err(err) => {
+          error = Some(err.to_string());
+          break;
+        },
+      };
+      if data.is_empty() {
+        continue;
+      }
+      // a chunk which isn't saved can't be fetched by a client which missed its event, so the
+      // stream fails instead of continuing with a gap
+      if let Err(err) = push_chunk(&mut redis_client, &stream_id, last_seq + 1, &data).await {
+        warn!(
+          "[AI] failed to save chunk {} of stream {}: {}",
+          last_seq + 1,
+          stream_id,
+          err
+        );
+        error = Some("Failed to save the response".to_string());
+        break;
+      }
+      last_seq += 1;
+      let event = AIStreamEvent::Chunk(AIStreamChunk {
+        stream_id: stream_id.clone(),
+        seq: last_seq,
+        data,
+      });
+      if let Err(err) = publish_ai_stream_event(&mut redis_client, uid, event).await {
+        warn!("[AI] failed to publish stream chunk: {}", err);
+      }
+    }
+
+    let (status, event) = match error {
+      None => (
+        AIStreamStatus::Finished,
+        AIStreamEvent::Finished {
+          stream_id: stream_id.clone(),
+          last_seq,
+        },
+      ),
+      Some(error) => (
+        AIStreamStatus::Failed(error.clone()),
+        AIStreamEvent::Failed {
+          stream_id: stream_id.clone(),
+          last_seq,
+          error,
+        },
+      ),
+    };
+    trace!("[AI] stream {} ended after {} chunks", stream_id, last_seq);
+    if let Err(err) = set_stream_state(&redis_client, &stream_id, uid, status).await {
+      warn!("[AI] failed to save state of stream {}: {}", stream_id, err);
+    }
+    if let Err(err) = publish_ai_stream_event(&mut redis_client, uid, event).await {
+      warn!("[AI] failed to publish end of stream: {}", err);
+    }
+  });
+  Ok(stream_id)
+}
+
+/// Returns the chunks of a stream following `after_seq`.
+pub async fn get_ai_stream_chunks(
+  redis_client: &RedisConnectionManager,
+  uid: i64,
+  stream_id: &str,
+  after_seq: u64,
+) -> Result<AIStreamChunks, AppError> {
+  let mut redis_client = redis_client.clone();
+  // the state is read before the chunks, so that a finished stream is never missing chunks
+  let state: Option<String> = redis_client
+    .get(stream_state_key(stream_id))
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to get AI stream state: {}", err)))?;
+  let state = state
+    .and_then(|state| serde_json::from_str::<AIStreamState>(&state).ok())
+    .filter(|state| state.uid == uid)
+    .ok_or_else(|| AppError::RecordNotFound(format!("AI stream:{} not found", stream_id)))?;
+
+  let chunks: Vec<String> = redis_client
+    .zrangebyscore(
+      stream_chunks_key(stream_id),
+      format!("({}", after_seq),
+      "+inf",
+    )
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to get AI stream chunks: {}", err)))?;
+  let chunks = chunks
+    .iter()
+    .map(|chunk| serde_json::from_str::<AIStreamChunkData>(chunk))
+    .collect::<Result<Vec<_>, _>>()?;
+  let (finished, error) = match state.status {
+    AIStreamStatus::Streaming => (false, None),
+    AIStreamStatus::Finished => (true, None),
+    AIStreamStatus::Failed(error) => (true, Some(error)),
+  };
+  Ok(AIStreamChunks {
+    chunks,
+    finished,
+    error,
+  })
+}
+
+async fn set_stream_state(
+  redis_client: &RedisConnectionManager,
+  stream_id: &str,
+  uid: i64,
+  status: AIStreamStatus,
+) -> Result<(), AppError> {
+  let state = serde_json::to_string(&AIStreamState { uid, status })?;
+  let _: () = redis_client
+    .clone()
+    .set_ex(stream_state_key(stream_id), state, AI_STREAM_TTL_SECS)
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to save AI stream state: {}", err)))?;
+  Ok(())
+}
+
+/// Saves a chunk scored by its sequence number, so that the chunks following a given one can be
+/// read by score whatever the order they were saved in.
+async fn push_chunk(
+  redis_client: &mut RedisConnectionManager,
+  stream_id: &str,
+  seq: u64,
+  data: &str,
+) -> Result<(), AppError> {
+  let chunk = serde_json::to_string(&AIStreamChunkData {
+    seq,
+    data: data.to_string(),
+  })?;
+  let key = stream_chunks_key(stream_id);
+  let () = redis::pipe()
+    .zadd(&key, chunk, seq)
+    .ignore()
+    .expire(&key, AI_STREAM_TTL_SECS as i64)
+    .ignore()
+    .query_async(redis_client)
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to save AI stream chunk: {}", err)))?;
+  Ok(())
+}
+
+/// Delivers an event of an AI stream to the realtime connections of the user who started it,
+/// whichever server they are connected to.
+async fn publish_ai_stream_event(
+  redis_client: &mut RedisConnectionManager,
+  uid: i64,
+  event: AIStreamEvent,
+) -> Result<(), AppError> {
+  let payload = serde_json::to_string(&AIStreamNotification { uid, event })?;
+  let () = redis_client
+    .publish(AI_STREAM_EVENT_CHANNEL, payload)
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to publish AI stream event: {}", err)))?;
+  Ok(())
+}
+
+/// Decodes the chunks of a stream as UTF-8, keeping the bytes of a character split across two
+/// chunks until the next chunk.
+#[derive(Default)]
+struct Utf8Decoder {
+  pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+  fn decode(&mut self, bytes: &[u8]) -> String {
+    self.pending.extend_from_slice(bytes);
+    let valid_up_to = match std::str::from_utf8(&self.pending) {
+      Ok(_) => self.pending.len(),
+      // an incomplete character at the end of the chunk
+      Err(err) if err.error_len().is_none() => err.valid_up_to(),
+      // invalid bytes, which are replaced instead of blocking the stream
+      Err(_) => {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        return text;
+      },
+    };
+    let rest = self.pending.split_off(valid_up_to);
+    String::from_utf8(std::mem::replace(&mut self.pending, rest)).unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn utf8_decoder_test() {
+    let text = "héllo 世界";
+    let bytes = text.as_bytes();
+    let mut decoder = Utf8Decoder::default();
+    // splits "é" and "世" across chunks
+    assert_eq!(decoder.decode(&bytes[..2]), "h");
+    assert_eq!(decoder.decode(&bytes[2..8]), "éllo ");
+    assert_eq!(decoder.decode(&bytes[8..]), "世界");
+    assert_eq!(decoder.decode(&[b'a', 0xff, b'b']), "a\u{fffd}b");
+  }
+}
@@ -0,0 +1,593 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{AIModel, CompletionType, CustomPrompt};
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::{NaiveDate, Utc};
+use database::pg_row::AFWorkspaceMemberRow;
+use database::workspace::select_workspace_member_list;
+use serde::Deserialize;
+use serde_json::Value;
+use shared_entity::dto::ai_dto::{
+  CreateMeetingNotesParams, MeetingActionItem, MeetingNotes, MeetingTaskDatabase, TranscriptFormat,
+};
+use shared_entity::dto::workspace_dto::AFDatabaseField;
+use sqlx::PgPool;
+use tracing::{trace, warn};
+use uuid::Uuid;
+use validator::Validate;
+use workspace_template::document::meeting::{self, MeetingNotesBuilder};
+
+use crate::biz::ai::ops::{consume_ai_request, record_ai_usage};
+use crate::biz::collab::ops::{get_database_fields, insert_database_row};
+use crate::biz::workspace::page_view::create_document_page_with_data;
+
+/// Maximum number of characters of a transcript sent to the AI service. Longer transcripts are
+/// truncated, but kept whole in the page.
+const MAX_TRANSCRIPT_LEN: usize = 60_000;
+/// Maximum number of characters of the name of a speaker, ie. `Lucas` in `Lucas: Hello`.
+const MAX_SPEAKER_LEN: usize = 40;
+
+const MEETING_NOTES_PROMPT: &str = "You write the notes of a meeting from its transcript. Reply \
+with a JSON object only, without any explanation, in the format {\"summary\": <summary of the \
+meeting in a few sentences>, \"decisions\": [<decision>], \"action_items\": [{\"content\": <task>, \
+\"assignee\": <name of the person responsible for the task, or null>, \"due_date\": <YYYY-MM-DD, \
+or null>}]}. Write in the language of the transcript. Only list decisions and action items which \
+were agreed on in the meeting.";
+
+/// Notes of a meeting as returned by the AI service.
+#[derive(Debug, Default, Deserialize)]
+struct AIMeetingNotes {
+  #[serde(default)]
+  summary: String,
+  #[serde(default)]
+  decisions: Vec<String>,
+  #[serde(default)]
+  action_items: Vec<AIActionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AIActionItem {
+  content: String,
+  #[serde(default)]
+  assignee: Option<String>,
+  #[serde(default)]
+  due_date: Option<String>,
+}
+
+/// Creates a page with the notes of a meeting, written by AI from its transcript. The action items
+/// of the meeting are also inserted into the task database of the params, if any.
+pub async fn create_meeting_notes(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  ai_client: &AppFlowyAIClient,
+  uid: i64,
+  workspace_id: Uuid,
+  params: CreateMeetingNotesParams,
+  ai_model: AIModel,
+) -> Result<MeetingNotes, AppError> {
+  params.validate()?;
+  let lines = parse_transcript(&params.transcript, params.format);
+  if lines.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "the transcript has no content".to_string(),
+    ));
+  }
+  // the task database is checked before the transcript is sent to the AI service
+  let task_fields = match &params.task_database {
+    None => None,
+    Some(task_database) => {
+      Some(resolve_task_fields(collab_storage, &workspace_id.to_string(), task_database).await?)
+    },
+  };
+  let members = select_workspace_member_list(pg_pool, &workspace_id).await?;
+  let today = Utc::now().date_naive();
+
+  consume_ai_request(pg_pool, &workspace_id.to_string()).await?;
+  let resp = ai_client
+    .completion_text(
+      &truncate(&lines.join("\n"), MAX_TRANSCRIPT_LEN),
+      None::<CompletionType>,
+      Some(CustomPrompt {
+        system: meeting_notes_prompt(&members, today),
+        user: None,
+      }),
+      ai_model,
+    )
+    .await?;
+  record_ai_usage(pg_pool, &workspace_id.to_string(), resp.usage.as_ref()).await;
+  trace!("[AI] meeting notes: {}", resp.text);
+  let notes = parse_meeting_notes(&resp.text);
+
+  let mut action_items: Vec<MeetingActionItem> = notes
+    .action_items
+    .into_iter()
+    .filter(|item| !item.content.trim().is_empty())
+    .map(|item| {
+      let assignee = item
+        .assignee
+        .map(|assignee| assignee.trim().to_string())
+        .filter(|assignee| !assignee.is_empty());
+      let member = assignee
+        .as_deref()
+        .and_then(|assignee| match_member(assignee, &members));
+      MeetingActionItem {
+        content: item.content.trim().to_string(),
+        // the name of the member is preferred, the transcript may only use a nickname
+        assignee: member.map(|member| member.name.clone()).or(assignee),
+        assignee_uid: member.map(|member| member.uid),
+        due_date: item
+          .due_date
+          .and_then(|date| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok())
+          .map(|date| date.format("%Y-%m-%d").to_string()),
+        row_id: None,
+      }
+    })
+    .collect();
+  let decisions: Vec<String> = notes
+    .decisions
+    .into_iter()
+    .map(|decision| decision.trim().to_string())
+    .filter(|decision| !decision.is_empty())
+    .collect();
+
+  let document_data = MeetingNotesBuilder::new()
+    .with_description(&format!(
+      "Written by AI from the transcript of a meeting, on {}",
+      today.format("%Y-%m-%d")
+    ))
+    .with_summary(&notes.summary)
+    .with_decisions(&decisions)
+    .with_action_items(
+      &action_items
+        .iter()
+        .map(|item| meeting::MeetingActionItem {
+          content: item.content.clone(),
+          assignee: item.assignee.clone(),
+          due_date: item.due_date.clone(),
+        })
+        .collect::<Vec<_>>(),
+    )
+    .with_transcript(&lines)
+    .build();
+  let name = params
+    .name
+    .filter(|name| !name.trim().is_empty())
+    .unwrap_or_else(|| format!("Meeting notes {}", today.format("%Y-%m-%d")));
+  let page = create_document_page_with_data(
+    pg_pool,
+    collab_storage,
+    uid,
+    workspace_id,
+    &params.parent_view_id,
+    Some(&name),
+    document_data,
+  )
+  .await?;
+
+  if let Some(task_fields) = task_fields {
+    insert_action_items(
+      pg_pool,
+      collab_storage,
+      uid,
+      &workspace_id.to_string(),
+      &task_fields,
+      &mut action_items,
+    )
+    .await?;
+  }
+
+  Ok(MeetingNotes {
+    view_id: page.view_id,
+    summary: notes.summary,
+    decisions,
+    action_items,
+  })
+}
+
+/// Fields of the task database set from the action items of a meeting.
+struct TaskDatabaseFields {
+  database_id: String,
+  primary: AFDatabaseField,
+  assignee: Option<AFDatabaseField>,
+  due_date: Option<AFDatabaseField>,
+}
+
+async fn resolve_task_fields(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  task_database: &MeetingTaskDatabase,
+) -> Result<TaskDatabaseFields, AppError> {
+  let fields =
+    get_database_fields(collab_storage, workspace_id, &task_database.database_id).await?;
+  let find_field = |id_or_name: &str| {
+    fields
+      .iter()
+      .find(|field| field.id == id_or_name || field.name.eq_ignore_ascii_case(id_or_name.trim()))
+      .cloned()
+      .ok_or_else(|| {
+        AppError::InvalidRequest(format!(
+          "field:{} not found in database:{}",
+          id_or_name, task_database.database_id
+        ))
+      })
+  };
+  let primary = fields
+    .iter()
+    .find(|field| field.is_primary)
+    .cloned()
+    .ok_or_else(|| AppError::Internal(anyhow!("database has no primary field")))?;
+  Ok(TaskDatabaseFields {
+    database_id: task_database.database_id.clone(),
+    primary,
+    assignee: task_database
+      .assignee_field
+      .as_deref()
+      .map(find_field)
+      .transpose()?,
+    due_date: task_database
+      .due_date_field
+      .as_deref()
+      .map(find_field)
+      .transpose()?,
+  })
+}
+
+/// Inserts a row per action item into the task database, setting the row id of the items.
+async fn insert_action_items(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  fields: &TaskDatabaseFields,
+  action_items: &mut [MeetingActionItem],
+) -> Result<(), AppError> {
+  for item in action_items.iter_mut() {
+    let mut cells = HashMap::from([(fields.primary.id.clone(), Value::from(item.content.clone()))]);
+    // only members of the workspace are assigned tasks
+    if let (Some(field), Some(_), Some(assignee)) =
+      (&fields.assignee, item.assignee_uid, &item.assignee)
+    {
+      cells.insert(field.id.clone(), Value::from(assignee.clone()));
+    }
+    if let (Some(field), Some(due_date)) = (&fields.due_date, &item.due_date) {
+      let value = match NaiveDate::parse_from_str(due_date, "%Y-%m-%d") {
+        Ok(date) if field.field_type == "DateTime" => date
+          .and_hms_opt(0, 0, 0)
+          .map(|date| Value::from(date.and_utc().timestamp())),
+        _ => Some(Value::from(due_date.clone())),
+      };
+      if let Some(value) = value {
+        cells.insert(field.id.clone(), value);
+      }
+    }
+    let row_id = insert_database_row(
+      collab_storage,
+      pg_pool,
+      workspace_id,
+      &fields.database_id,
+      uid,
+      cells,
+    )
+    .await?;
+    item.row_id = Some(row_id);
+  }
+  Ok(())
+}
+
+fn meeting_notes_prompt(members: &[AFWorkspaceMemberRow], today: NaiveDate) -> String {
+  let mut prompt = format!(
+    "{}\nThe meeting took place on {}.",
+    MEETING_NOTES_PROMPT,
+    today.format("%Y-%m-%d")
+  );
+  let names: Vec<&str> = members
+    .iter()
+    .map(|member| member.name.as_str())
+    .filter(|name| !name.is_empty())
+    .collect();
+  if !names.is_empty() {
+    prompt.push_str(&format!(
+      " The participants may include: {}.",
+      names.join(", ")
+    ));
+  }
+  prompt
+}
+
+/// Parses the notes returned by the AI service, which may wrap them in a code block. A response
+/// which isn't JSON is kept as the summary, so that the transcript isn't lost.
+fn parse_meeting_notes(text: &str) -> AIMeetingNotes {
+  let json = match (text.find('{'), text.rfind('}')) {
+    (Some(start), Some(end)) if start < end => &text[start..=end],
+    _ => "",
+  };
+  match serde_json::from_str::<AIMeetingNotes>(json) {
+    Ok(notes) => notes,
+    Err(err) => {
+      warn!("[AI] failed to parse meeting notes: {}", err);
+      AIMeetingNotes {
+        summary: text.trim().to_string(),
+        ..Default::default()
+      }
+    },
+  }
+}
+
+/// Matches the name of an assignee with a member of the workspace, by name, email or first name.
+/// A first name shared by several members matches none of them.
+fn match_member<'a>(
+  assignee: &str,
+  members: &'a [AFWorkspaceMemberRow],
+) -> Option<&'a AFWorkspaceMemberRow> {
+  let assignee = assignee.trim().trim_start_matches('@').to_lowercase();
+  if assignee.is_empty() {
+    return None;
+  }
+  let exact = members.iter().find(|member| {
+    member.name.to_lowercase() == assignee
+      || member.email.to_lowercase() == assignee
+      || member
+        .email
+        .split('@')
+        .next()
+        .is_some_and(|local| local.to_lowercase() == assignee)
+  });
+  if exact.is_some() {
+    return exact;
+  }
+  let mut by_first_name = members.iter().filter(|member| {
+    member
+      .name
+      .split_whitespace()
+      .next()
+      .is_some_and(|first_name| first_name.to_lowercase() == assignee)
+  });
+  match (by_first_name.next(), by_first_name.next()) {
+    (Some(member), None) => Some(member),
+    _ => None,
+  }
+}
+
+/// Returns the utterances of a transcript, one per line, prefixed by their speaker when known.
+/// Consecutive utterances of the same speaker are merged.
+fn parse_transcript(transcript: &str, format: Option<TranscriptFormat>) -> Vec<String> {
+  let transcript = transcript.trim_start_matches('\u{feff}');
+  let format = format.unwrap_or_else(|| detect_format(transcript));
+  let utterances = match format {
+    TranscriptFormat::Vtt | TranscriptFormat::Srt => parse_cues(transcript),
+    TranscriptFormat::Text => transcript
+      .lines()
+      .map(|line| split_speaker(strip_timestamp(line.trim())))
+      .filter(|(_, text)| !text.is_empty())
+      .collect(),
+  };
+
+  let mut merged: Vec<(Option<String>, String)> = Vec::new();
+  for (speaker, text) in utterances {
+    match merged.last_mut() {
+      Some((last_speaker, last_text)) if speaker.is_some() && *last_speaker == speaker => {
+        last_text.push(' ');
+        last_text.push_str(&text);
+      },
+      _ => merged.push((speaker, text)),
+    }
+  }
+  merged
+    .into_iter()
+    .map(|(speaker, text)| match speaker {
+      Some(speaker) => format!("{}: {}", speaker, text),
+      None => text,
+    })
+    .collect()
+}
+
+fn detect_format(transcript: &str) -> TranscriptFormat {
+  if transcript.trim_start().starts_with("WEBVTT") {
+    TranscriptFormat::Vtt
+  } else if transcript.lines().any(is_timing_line) {
+    TranscriptFormat::Srt
+  } else {
+    TranscriptFormat::Text
+  }
+}
+
+fn is_timing_line(line: &str) -> bool {
+  line.contains("-->")
+}
+
+/// Parses the cues of WebVTT or SubRip captions. Cues are separated by blank lines, and their text
+/// follows their timing line, ie. `00:00:01.000 --> 00:00:04.000`. Blocks without a timing line,
+/// like the header, notes and styles of WebVTT, are skipped.
+fn parse_cues(transcript: &str) -> Vec<(Option<String>, String)> {
+  let mut utterances = Vec::new();
+  let mut block: Vec<&str> = Vec::new();
+  for line in transcript.lines().chain(std::iter::once("")) {
+    let line = line.trim();
+    if !line.is_empty() {
+      block.push(line);
+      continue;
+    }
+    if let Some(timing) = block.iter().position(|line| is_timing_line(line)) {
+      let mut speaker = None;
+      let mut texts = Vec::new();
+      for line in &block[timing + 1..] {
+        let (line_speaker, text) = match voice_speaker(line) {
+          Some(voice) => (Some(voice), strip_tags(line)),
+          None => split_speaker(&strip_tags(line)),
+        };
+        speaker = speaker.or(line_speaker);
+        if !text.is_empty() {
+          texts.push(text);
+        }
+      }
+      if !texts.is_empty() {
+        utterances.push((speaker, texts.join(" ")));
+      }
+    }
+    block.clear();
+  }
+  utterances
+}
+
+/// Returns the speaker of a WebVTT voice tag, ie. `Lucas` in `<v Lucas>Hello`.
+fn voice_speaker(line: &str) -> Option<String> {
+  let start = line.find("<v")?;
+  let tag = &line[start + 2..start + line[start..].find('>')?];
+  // the tag may have classes, ie. <v.loud Lucas>
+  let (_, name) = tag.split_once(' ')?;
+  let name = name.trim();
+  (!name.is_empty()).then(|| name.to_string())
+}
+
+fn strip_tags(line: &str) -> String {
+  let mut text = String::with_capacity(line.len());
+  let mut in_tag = false;
+  for c in line.chars() {
+    match c {
+      '<' => in_tag = true,
+      '>' if in_tag => in_tag = false,
+      c if !in_tag => text.push(c),
+      _ => {},
+    }
+  }
+  text
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&nbsp;", " ")
+    .replace("&amp;", "&")
+    .trim()
+    .to_string()
+}
+
+/// Removes a leading timestamp, ie. `[00:01:02]` or `(10:30)`.
+fn strip_timestamp(line: &str) -> &str {
+  for (open, close) in [('[', ']'), ('(', ')')] {
+    if let Some(rest) = line.strip_prefix(open) {
+      if let Some((timestamp, rest)) = rest.split_once(close) {
+        if !timestamp.is_empty()
+          && timestamp
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, ':' | '.' | ','))
+        {
+          return rest.trim_start();
+        }
+      }
+    }
+  }
+  line
+}
+
+/// Splits `Lucas: Hello` into its speaker and text. Prefixes which don't look like a name, ie.
+/// because they contain digits or are too long, are kept in the text.
+fn split_speaker(line: &str) -> (Option<String>, String) {
+  if let Some((speaker, text)) = line.split_once(':') {
+    let speaker = speaker.trim();
+    let text = text.trim();
+    let is_name = !speaker.is_empty()
+      && speaker.chars().count() <= MAX_SPEAKER_LEN
+      && speaker.split_whitespace().count() <= 4
+      && speaker
+        .chars()
+        .all(|c| c.is_alphabetic() || matches!(c, ' ' | '.' | '-' | '\'' | '_'));
+    if is_name && !text.is_empty() {
+      return (Some(speaker.to_string()), text.to_string());
+    }
+  }
+  (None, line.trim().to_string())
+}
+
+fn truncate(text: &str, max_len: usize) -> String {
+  match text.char_indices().nth(max_len) {
+    Some((index, _)) => text[..index].to_string(),
+    None => text.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use database_entity::dto::AFRole;
+
+  fn member(uid: i64, name: &str, email: &str) -> AFWorkspaceMemberRow {
+    AFWorkspaceMemberRow {
+      uid,
+      name: name.to_string(),
+      email: email.to_string(),
+      role: AFRole::Member,
+    }
+  }
+
+  #[test]
+  fn parse_vtt_transcript_test() {
+    let transcript = "WEBVTT\n\nNOTE recorded by the meeting app\n\n1\n00:00:00.000 --> \
+    00:00:02.000\n<v Lucas Xu>Let's ship on <b>Friday</b>.\n\n2\n00:00:02.000 --> 00:00:04.000\n<v \
+    Lucas Xu>The notes are ready.\n\n00:00:04.000 --> 00:00:06.000\n<v.loud Nathan>Agreed &amp; \
+    done.\n";
+    assert_eq!(
+      parse_transcript(transcript, None),
+      vec![
+        "Lucas Xu: Let's ship on Friday. The notes are ready.",
+        "Nathan: Agreed & done.",
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_srt_transcript_test() {
+    let transcript = "1\r\n00:00:00,000 --> 00:00:02,000\r\nLucas: Let's ship\r\non \
+    Friday.\r\n\r\n2\r\n00:00:02,000 --> 00:00:04,000\r\n<i>Nathan: Agreed.</i>\r\n";
+    assert_eq!(
+      parse_transcript(transcript, None),
+      vec!["Lucas: Let's ship on Friday.", "Nathan: Agreed."]
+    );
+  }
+
+  #[test]
+  fn parse_text_transcript_test() {
+    let transcript = "[00:00:01] Lucas: Let's ship on Friday.\nLucas: Any objection?\n\nAt \
+    10:30 we took a break.\nNathan: None.";
+    assert_eq!(
+      parse_transcript(transcript, Some(TranscriptFormat::Text)),
+      vec![
+        "Lucas: Let's ship on Friday. Any objection?",
+        "At 10:30 we took a break.",
+        "Nathan: None.",
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_meeting_notes_test() {
+    let notes = parse_meeting_notes(
+      "```json\n{\"summary\": \"Release planning\", \"decisions\": [\"Ship on Friday\"], \
+      \"action_items\": [{\"content\": \"Write notes\", \"assignee\": \"Lucas\", \"due_date\": \
+      \"2024-12-05\"}]}\n```",
+    );
+    assert_eq!(notes.summary, "Release planning");
+    assert_eq!(notes.decisions, vec!["Ship on Friday"]);
+    assert_eq!(notes.action_items[0].assignee.as_deref(), Some("Lucas"));
+
+    let notes = parse_meeting_notes("The team planned the release.");
+    assert_eq!(notes.summary, "The team planned the release.");
+    assert!(notes.action_items.is_empty());
+  }
+
+  #[test]
+  fn match_member_test() {
+    let members = vec![
+      member(1, "Lucas Xu", "lucas.xu@appflowy.io"),
+      member(2, "Nathan Foo", "nathan@appflowy.io"),
+      member(3, "Nathan Bar", "nbar@appflowy.io"),
+    ];
+    assert_eq!(match_member("lucas xu", &members).unwrap().uid, 1);
+    assert_eq!(match_member("Lucas", &members).unwrap().uid, 1);
+    assert_eq!(match_member("@lucas.xu", &members).unwrap().uid, 1);
+    assert_eq!(match_member("nbar", &members).unwrap().uid, 3);
+    // two members are named Nathan
+    assert!(match_member("Nathan", &members).is_none());
+    assert!(match_member("Annie", &members).is_none());
+  }
+}
@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{AIModel, CompletionType, CustomPrompt};
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::{DateTime, NaiveDate, Utc};
+use collab_database::template::entity::CELL_DATA;
+use serde_json::Value;
+use shared_entity::dto::chat_dto::{
+  ChatDatabaseAnswer, ChatDatabaseFilter, ChatDatabaseFilterCondition, ChatDatabaseFilterMatch,
+  ChatDatabaseQuery, ChatDatabaseSource, ChatMessageMetadata,
+};
+use shared_entity::dto::workspace_dto::{AFDatabaseField, AFDatabaseRowDetail};
+use sqlx::PgPool;
+use tracing::{trace, warn};
+
+use crate::biz::ai::ops::record_ai_usage;
+use crate::biz::collab::ops::{
+  get_database_fields, list_database_row_details, list_database_row_ids,
+};
+
+/// Number of rows loaded at once when looking for the rows matching a question.
+const CHAT_DATABASE_PAGE_ROWS: usize = 1000;
+/// Maximum number of matching rows listed in the table of an answer. All of them are part of the
+/// answer metadata.
+const CHAT_DATABASE_TABLE_ROWS: usize = 20;
+/// Maximum number of options of a select field described to the AI service.
+const CHAT_DATABASE_MAX_FIELD_OPTIONS: usize = 20;
+
+const CHAT_DATABASE_QUERY_PROMPT: &str = "You translate questions about a database into filters \
+over its rows. Reply with a JSON object only, without any explanation, in the format \
+{\"match\": \"all\" or \"any\", \"filters\": [{\"field\": <field name>, \"condition\": <condition>, \
+\"value\": <value>}]}. The conditions are: is, is_not, contains, does_not_contain, is_empty, \
+is_not_empty, greater_than, less_than, before, after. Use before and after for dates, written as \
+YYYY-MM-DD. Use an empty list of filters if the question is about all the rows.";
+
+/// A question answered from the rows of a database: the answer and its metadata.
+pub struct ChatDatabaseAnswerMessage {
+  pub content: String,
+  pub metadata: ChatMessageMetadata,
+}
+
+/// Returns the database view a question mentions in its metadata, if any.
+pub fn database_source_from_metadata(metadata: &Value) -> Option<ChatDatabaseSource> {
+  let items = match metadata {
+    Value::Array(items) => items.as_slice(),
+    value => std::slice::from_ref(value),
+  };
+  items.iter().find_map(|item| {
+    serde_json::from_value::<ChatMessageMetadata>(item.clone())
+      .ok()?
+      .database_source()
+  })
+}
+
+/// Answers a question from the rows of a database. The AI service translates the question into
+/// filters over the fields of the database, which are then applied to the rows by the server.
+/// The answer lists the matching rows in a table linking to them.
+#[allow(clippy::too_many_arguments)]
+pub async fn answer_database_question(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  ai_client: &AppFlowyAIClient,
+  appflowy_web_url: Option<&str>,
+  uid: i64,
+  workspace_id: &str,
+  source: ChatDatabaseSource,
+  question: &str,
+  ai_model: AIModel,
+) -> Result<ChatDatabaseAnswerMessage, AppError> {
+  let fields = get_database_fields(collab_storage, workspace_id, &source.database_id).await?;
+  let resp = ai_client
+    .completion_text(
+      question,
+      None::<CompletionType>,
+      Some(CustomPrompt {
+        system: database_query_prompt(&fields, Utc::now().date_naive()),
+        user: None,
+      }),
+      ai_model,
+    )
+    .await?;
+  record_ai_usage(pg_pool, workspace_id, resp.usage.as_ref()).await;
+  trace!(
+    "[Chat] database query for question: {}: {}",
+    question,
+    resp.text
+  );
+  let query = match parse_database_query(&resp.text, &fields) {
+    Ok(query) => query,
+    Err(err) => {
+      warn!(
+        "[Chat] failed to translate question into database query: {}",
+        err
+      );
+      return Ok(ChatDatabaseAnswerMessage {
+        content: format!(
+          "I couldn't turn the question into filters over the rows of {}. Try to refer to its \
+          fields by name.",
+          source.name
+        ),
+        metadata: ChatMessageMetadata::from(source),
+      });
+    },
+  };
+
+  let row_ids: Vec<String> =
+    list_database_row_ids(collab_storage, workspace_id, &source.database_id)
+      .await?
+      .into_iter()
+      .map(|row| row.id)
+      .collect();
+  // all the rows are searched, a page at a time, keeping only the matching ones
+  let mut rows: Vec<AFDatabaseRowDetail> = vec![];
+  for page in row_ids.chunks(CHAT_DATABASE_PAGE_ROWS) {
+    let page_rows = list_database_row_details(
+      collab_storage,
+      pg_pool,
+      uid,
+      workspace_id.to_string(),
+      source.database_id.clone(),
+      &page.iter().map(String::as_str).collect::<Vec<_>>(),
+    )
+    .await?;
+    rows.extend(page_rows.into_iter().filter(|row| row_matches(row, &query)));
+  }
+  // rows are loaded in any order, keep the order of the database
+  let position: HashMap<&str, usize> = row_ids
+    .iter()
+    .enumerate()
+    .map(|(i, id)| (id.as_str(), i))
+    .collect();
+  rows.sort_by_key(|row| position.get(row.id.as_str()).copied());
+
+  let content = format_answer(&rows, &fields, &query, &source, |row_id| {
+    format!(
+      "{}/app/{}/{}?r={}",
+      appflowy_web_url.unwrap_or_default(),
+      workspace_id,
+      source.view_id,
+      row_id
+    )
+  });
+  let metadata = ChatMessageMetadata::from(ChatDatabaseAnswer {
+    source,
+    query,
+    row_ids: rows.into_iter().map(|row| row.id).collect(),
+  });
+  Ok(ChatDatabaseAnswerMessage { content, metadata })
+}
+
+fn database_query_prompt(fields: &[AFDatabaseField], today: NaiveDate) -> String {
+  let mut prompt = format!(
+    "{}\nToday is {}. The fields of the database are:",
+    CHAT_DATABASE_QUERY_PROMPT,
+    today.format("%Y-%m-%d")
+  );
+  for field in fields {
+    prompt.push_str(&format!("\n- {} ({})", field.name, field.field_type));
+    let options: Vec<&str> = field
+      .type_option
+      .get("options")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(|option| option.get("name")?.as_str())
+      .take(CHAT_DATABASE_MAX_FIELD_OPTIONS)
+      .collect();
+    if !options.is_empty() {
+      prompt.push_str(&format!(", options: {}", options.join(", ")));
+    }
+  }
+  prompt
+}
+
+/// Parses the filters returned by the AI service, which may wrap them in a code block. Field
+/// names are matched case-insensitively and a filter on an unknown field fails the whole query,
+/// because ignoring it would list rows the question excludes.
+fn parse_database_query(
+  text: &str,
+  fields: &[AFDatabaseField],
+) -> Result<ChatDatabaseQuery, AppError> {
+  let start = text.find('{');
+  let end = text.rfind('}');
+  let json = match (start, end) {
+    (Some(start), Some(end)) if start < end => &text[start..=end],
+    _ => {
+      return Err(AppError::InvalidRequest(format!(
+        "no database query in: {}",
+        text
+      )))
+    },
+  };
+  let mut query: ChatDatabaseQuery = serde_json::from_str(json)?;
+  for filter in query.filters.iter_mut() {
+    let field = fields
+      .iter()
+      .find(|field| field.name.eq_ignore_ascii_case(filter.field.trim()))
+      .ok_or_else(|| AppError::InvalidRequest(format!("unknown field: {}", filter.field)))?;
+    filter.field.clone_from(&field.name);
+  }
+  Ok(query)
+}
+
+fn row_matches(row: &AFDatabaseRowDetail, query: &ChatDatabaseQuery) -> bool {
+  let mut matches = query
+    .filters
+    .iter()
+    .map(|filter| filter_matches(row_cell(row, &filter.field), filter));
+  match query.match_mode {
+    ChatDatabaseFilterMatch::All => matches.all(|matched| matched),
+    ChatDatabaseFilterMatch::Any => query.filters.is_empty() || matches.any(|matched| matched),
+  }
+}
+
+fn row_cell<'a>(row: &'a AFDatabaseRowDetail, field: &str) -> &'a Value {
+  row
+    .cells
+    .get(field)
+    .and_then(|cell| cell.get(CELL_DATA))
+    .unwrap_or(&Value::Null)
+}
+
+fn filter_matches(cell: &Value, filter: &ChatDatabaseFilter) -> bool {
+  let value = cell_text(&filter.value);
+  match filter.condition {
+    ChatDatabaseFilterCondition::Is => cell_is(cell, &value),
+    ChatDatabaseFilterCondition::IsNot => !cell_is(cell, &value),
+    ChatDatabaseFilterCondition::Contains => cell_contains(cell, &value),
+    ChatDatabaseFilterCondition::DoesNotContain => !cell_contains(cell, &value),
+    ChatDatabaseFilterCondition::IsEmpty => cell_text(cell).is_empty(),
+    ChatDatabaseFilterCondition::IsNotEmpty => !cell_text(cell).is_empty(),
+    ChatDatabaseFilterCondition::GreaterThan => {
+      matches!((cell_number(cell), value.parse::<f64>()), (Some(a), Ok(b)) if a > b)
+    },
+    ChatDatabaseFilterCondition::LessThan => {
+      matches!((cell_number(cell), value.parse::<f64>()), (Some(a), Ok(b)) if a < b)
+    },
+    ChatDatabaseFilterCondition::Before => {
+      matches!((cell_date(cell), parse_date(&value)), (Some(a), Some(b)) if a < b)
+    },
+    ChatDatabaseFilterCondition::After => {
+      matches!((cell_date(cell), parse_date(&value)), (Some(a), Some(b)) if a > b)
+    },
+  }
+}
+
+/// Multi-value cells, ie. multi-select, match if any of their values does.
+fn cell_is(cell: &Value, value: &str) -> bool {
+  match cell {
+    Value::Array(items) => items.iter().any(|item| cell_is(item, value)),
+    Value::Bool(checked) => parse_bool(value) == Some(*checked),
+    // unchecked checkboxes may have no data
+    Value::Null => parse_bool(value) == Some(false),
+    cell => {
+      let text = cell_text(cell);
+      match (text.parse::<f64>(), value.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => match (cell_date(cell), parse_date(value)) {
+          (Some(a), Some(b)) => a == b,
+          _ => text.to_lowercase() == value.to_lowercase(),
+        },
+      }
+    },
+  }
+}
+
+fn cell_contains(cell: &Value, value: &str) -> bool {
+  cell_text(cell)
+    .to_lowercase()
+    .contains(&value.to_lowercase())
+}
+
+fn cell_number(cell: &Value) -> Option<f64> {
+  match cell {
+    Value::Number(number) => number.as_f64(),
+    cell => cell_text(cell).trim().parse().ok(),
+  }
+}
+
+/// Day of a date or time cell, whose data is either a timestamp in seconds or an object with a
+/// `timestamp`.
+fn cell_date(cell: &Value) -> Option<NaiveDate> {
+  let timestamp = match cell {
+    Value::Object(map) => map.get("timestamp")?.as_i64()?,
+    Value::Number(number) => number.as_i64()?,
+    Value::String(text) => match text.parse::<i64>() {
+      Ok(timestamp) => timestamp,
+      Err(_) => return parse_date(text),
+    },
+    _ => return None,
+  };
+  DateTime::<Utc>::from_timestamp(timestamp, 0).map(|time| time.date_naive())
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+  let value = value.trim();
+  NaiveDate::parse_from_str(value, "%Y-%m-%d")
+    .ok()
+    .or_else(|| {
+      DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.date_naive())
+    })
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+  match value.trim().to_lowercase().as_str() {
+    "true" | "yes" | "checked" => Some(true),
+    "false" | "no" | "unchecked" | "" => Some(false),
+    _ => None,
+  }
+}
+
+/// Text of a cell as displayed in the answer.
+fn cell_text(cell: &Value) -> String {
+  match cell {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    Value::Bool(checked) => if *checked { "Yes" } else { "No" }.to_string(),
+    Value::Number(number) => number.to_string(),
+    Value::Array(items) => items
+      .iter()
+      .map(cell_text)
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join(", "),
+    Value::Object(map) => cell_date(cell)
+      .map(|date| date.format("%Y-%m-%d").to_string())
+      .or_else(|| {
+        // related rows, checklists and their items, media files
+        ["primary_value", "name", "items", "row_id"]
+          .iter()
+          .find_map(|key| map.get(*key).filter(|value| !value.is_null()))
+          .map(cell_text)
+      })
+      .unwrap_or_else(|| cell.to_string()),
+  }
+}
+
+fn describe_filter(filter: &ChatDatabaseFilter) -> String {
+  let condition = match filter.condition {
+    ChatDatabaseFilterCondition::Is => "is",
+    ChatDatabaseFilterCondition::IsNot => "is not",
+    ChatDatabaseFilterCondition::Contains => "contains",
+    ChatDatabaseFilterCondition::DoesNotContain => "does not contain",
+    ChatDatabaseFilterCondition::IsEmpty => return format!("{} is empty", filter.field),
+    ChatDatabaseFilterCondition::IsNotEmpty => return format!("{} is not empty", filter.field),
+    ChatDatabaseFilterCondition::GreaterThan => "is greater than",
+    ChatDatabaseFilterCondition::LessThan => "is less than",
+    ChatDatabaseFilterCondition::Before => "is before",
+    ChatDatabaseFilterCondition::After => "is after",
+  };
+  format!(
+    "{} {} {}",
+    filter.field,
+    condition,
+    cell_text(&filter.value)
+  )
+}
+
+/// Lists the matching rows in a markdown table. The table shows the primary field, linked to the
+/// row, and the fields the rows were filtered by.
+fn format_answer(
+  rows: &[AFDatabaseRowDetail],
+  fields: &[AFDatabaseField],
+  query: &ChatDatabaseQuery,
+  source: &ChatDatabaseSource,
+  row_link: impl Fn(&str) -> String,
+) -> String {
+  let separator = match query.match_mode {
+    ChatDatabaseFilterMatch::All => " and ",
+    ChatDatabaseFilterMatch::Any => " or ",
+  };
+  let condition = query
+    .filters
+    .iter()
+    .map(describe_filter)
+    .collect::<Vec<_>>()
+    .join(separator);
+  let condition = if condition.is_empty() {
+    String::new()
+  } else {
+    format!(" where {}", condition)
+  };
+  if rows.is_empty() {
+    return format!("There are no rows in {}{}.", source.name, condition);
+  }
+
+  let primary = fields
+    .iter()
+    .find(|field| field.is_primary)
+    .map(|field| field.name.as_str());
+  let mut columns: Vec<&str> = primary.into_iter().collect();
+  for filter in query.filters.iter() {
+    if !columns.contains(&filter.field.as_str()) {
+      columns.push(&filter.field);
+    }
+  }
+
+  let mut answer = format!(
+    "Found {} {} in {}{}:\n\n",
+    rows.len(),
+    if rows.len() == 1 { "row" } else { "rows" },
+    source.name,
+    condition
+  );
+  if columns.is_empty() {
+    columns.push("Row");
+  }
+  answer.push_str(&format!("| {} |\n", columns.join(" | ")));
+  answer.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+  for row in rows.iter().take(CHAT_DATABASE_TABLE_ROWS) {
+    let cells: Vec<String> = columns
+      .iter()
+      .enumerate()
+      .map(|(i, column)| {
+        let text = escape_table_cell(&cell_text(row_cell(row, column)));
+        if i == 0 {
+          let title = if text.is_empty() {
+            "Untitled"
+          } else {
+            text.as_str()
+          };
+          format!("[{}]({})", title, row_link(&row.id))
+        } else {
+          text
+        }
+      })
+      .collect();
+    answer.push_str(&format!("| {} |\n", cells.join(" | ")));
+  }
+  if rows.len() > CHAT_DATABASE_TABLE_ROWS {
+    answer.push_str(&format!(
+      "\nAnd {} more.",
+      rows.len() - CHAT_DATABASE_TABLE_ROWS
+    ));
+  }
+  answer.trim_end().to_string()
+}
+
+fn escape_table_cell(text: &str) -> String {
+  text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn field(name: &str, field_type: &str, is_primary: bool) -> AFDatabaseField {
+    AFDatabaseField {
+      id: name.to_lowercase(),
+      name: name.to_string(),
+      field_type: field_type.to_string(),
+      type_option: HashMap::new(),
+      is_primary,
+    }
+  }
+
+  fn row(id: &str, cells: Value) -> AFDatabaseRowDetail {
+    let cells = cells
+      .as_object()
+      .unwrap()
+      .iter()
+      .map(|(name, data)| {
+        (
+          name.clone(),
+          HashMap::from([(CELL_DATA.to_string(), data.clone())]),
+        )
+      })
+      .collect();
+    AFDatabaseRowDetail {
+      id: id.to_string(),
+      cells,
+    }
+  }
+
+  #[test]
+  fn parse_database_query_test() {
+    let fields = vec![
+      field("Name", "RichText", true),
+      field("Assignee", "SingleSelect", false),
+    ];
+    let text = "```json\n{\"filters\": [{\"field\": \"assignee\", \"condition\": \"is\", \
+    \"value\": \"Alice\"}]}\n```";
+    let query = parse_database_query(text, &fields).unwrap();
+    assert_eq!(query.match_mode, ChatDatabaseFilterMatch::All);
+    assert_eq!(query.filters[0].field, "Assignee");
+    assert_eq!(query.filters[0].condition, ChatDatabaseFilterCondition::Is);
+
+    let text = r#"{"filters": [{"field": "Owner", "condition": "is", "value": "Alice"}]}"#;
+    assert!(parse_database_query(text, &fields).is_err());
+    assert!(parse_database_query("Alice's tasks", &fields).is_err());
+  }
+
+  #[test]
+  fn filter_rows_test() {
+    let rows = vec![
+      row(
+        "1",
+        json!({"Name": "Write docs", "Assignee": "Alice", "Due": {"timestamp": 1733011200},
+          "Tags": ["docs", "urgent"], "Points": "3", "Done": false}),
+      ),
+      row(
+        "2",
+        json!({"Name": "Fix bug", "Assignee": "Bob", "Due": {"timestamp": 1735689600},
+          "Tags": ["bug"], "Points": "5", "Done": true}),
+      ),
+      row("3", json!({"Name": "Plan", "Assignee": "alice"})),
+    ];
+    let query: ChatDatabaseQuery = serde_json::from_value(json!({
+      "filters": [
+        {"field": "Assignee", "condition": "is", "value": "Alice"},
+        {"field": "Due", "condition": "before", "value": "2024-12-23"}
+      ]
+    }))
+    .unwrap();
+    let matching = |query: &ChatDatabaseQuery| -> Vec<&str> {
+      rows
+        .iter()
+        .filter(|row| row_matches(row, query))
+        .map(|row| row.id.as_str())
+        .collect()
+    };
+    assert_eq!(matching(&query), vec!["1"]);
+
+    let query: ChatDatabaseQuery = serde_json::from_value(json!({
+      "match": "any",
+      "filters": [
+        {"field": "Tags", "condition": "is", "value": "bug"},
+        {"field": "Points", "condition": "less_than", "value": 4}
+      ]
+    }))
+    .unwrap();
+    assert_eq!(matching(&query), vec!["1", "2"]);
+
+    let query: ChatDatabaseQuery = serde_json::from_value(json!({
+      "filters": [
+        {"field": "Done", "condition": "is", "value": "false"},
+        {"field": "Due", "condition": "is_empty"}
+      ]
+    }))
+    .unwrap();
+    assert_eq!(matching(&query), vec!["3"]);
+    assert_eq!(matching(&ChatDatabaseQuery::default()), vec!["1", "2", "3"]);
+  }
+
+  #[test]
+  fn format_answer_test() {
+    let fields = vec![
+      field("Name", "RichText", true),
+      field("Assignee", "SingleSelect", false),
+    ];
+    let source = ChatDatabaseSource {
+      database_id: "db".to_string(),
+      view_id: "view".to_string(),
+      name: "Tasks".to_string(),
+    };
+    let query: ChatDatabaseQuery = serde_json::from_value(json!({
+      "filters": [{"field": "Assignee", "condition": "is", "value": "Alice"}]
+    }))
+    .unwrap();
+    let rows = vec![row("1", json!({"Name": "A | B", "Assignee": "Alice"}))];
+    let answer = format_answer(&rows, &fields, &query, &source, |id| format!("/row/{}", id));
+    assert_eq!(
+      answer,
+      "Found 1 row in Tasks where Assignee is Alice:\n\n| Name | Assignee |\n| --- | --- |\n\
+      | [A \\| B](/row/1) | Alice |"
+    );
+    let answer = format_answer(&[], &fields, &query, &source, |id| id.to_string());
+    assert_eq!(
+      answer,
+      "There are no rows in Tasks where Assignee is Alice."
+    );
+  }
+}
@@ -0,0 +1,96 @@
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_document::blocks::DocumentData;
+use database::chat::chat_ops::{get_all_chat_messages, select_chat};
+use serde_json::Value;
+use shared_entity::dto::chat_dto::{
+  ChatAuthorType, ChatMessage, ChatMessageMetadata, ExportChatParams,
+};
+use shared_entity::dto::workspace_dto::Page;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+use workspace_template::document::chat::{ChatDocumentBuilder, ChatDocumentCitation};
+
+use crate::biz::workspace::page_view::create_document_page_with_data;
+
+/// Exports all the messages of a chat to a new document page, created under the given parent view.
+pub async fn export_chat_to_page(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  chat_id: &str,
+  params: ExportChatParams,
+) -> Result<Page, AppError> {
+  params.validate()?;
+  let workspace_id = Uuid::parse_str(workspace_id)?;
+  let chat = select_chat(pg_pool, chat_id).await?;
+  if chat.workspace_id != workspace_id {
+    return Err(AppError::RecordNotFound(format!(
+      "chat with given id:{} is not found in workspace:{}",
+      chat_id, workspace_id
+    )));
+  }
+
+  let messages = get_all_chat_messages(pg_pool, chat_id).await?;
+  let document_data = chat_messages_to_document(&chat.name, &messages);
+  let name = params.name.unwrap_or(chat.name);
+  create_document_page_with_data(
+    pg_pool,
+    collab_storage,
+    uid,
+    workspace_id,
+    &params.parent_view_id,
+    Some(&name),
+    document_data,
+  )
+  .await
+}
+
+/// Messages written by the AI are rendered as answers, every other message as a question.
+fn chat_messages_to_document(chat_name: &str, messages: &[ChatMessage]) -> DocumentData {
+  let description = format!(
+    "Exported from the AI chat \"{}\" on {}",
+    chat_name,
+    Utc::now().format("%Y-%m-%d %H:%M UTC")
+  );
+  messages
+    .iter()
+    .fold(
+      ChatDocumentBuilder::new().with_description(&description),
+      |builder, message| match message.author.author_type {
+        ChatAuthorType::AI => {
+          let citations = message_metadata(message)
+            .filter_map(|item| serde_json::from_value::<ChatMessageMetadata>(item.clone()).ok())
+            .filter_map(|metadata| metadata.citation())
+            .map(|citation| ChatDocumentCitation {
+              object_id: citation.object_id,
+              content: citation.content,
+            })
+            .collect::<Vec<_>>();
+          builder.with_answer(&message.content, &citations)
+        },
+        _ => {
+          let attachments = message_metadata(message)
+            .filter_map(|item| item.get("name")?.as_str())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+          builder.with_question(&message.content, &attachments)
+        },
+      },
+    )
+    .build()
+}
+
+/// Message metadata is either a single object or an array of objects.
+fn message_metadata(message: &ChatMessage) -> impl Iterator<Item = &Value> {
+  let items: &[Value] = match &message.meta_data {
+    Value::Null => &[],
+    Value::Array(items) => items,
+    value => std::slice::from_ref(value),
+  };
+  items.iter()
+}
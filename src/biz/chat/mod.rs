@@ -1 +1,4 @@
+pub mod database;
+pub mod export;
 pub mod ops;
+pub mod participant;
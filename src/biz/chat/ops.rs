@@ -3,34 +3,49 @@ use anyhow::anyhow;
 
 use app_error::AppError;
 use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use async_stream::stream;
 use database::chat;
 use database::chat::chat_ops::{
-  delete_answer_message_by_question_message_id, insert_answer_message,
-  insert_answer_message_with_transaction, insert_chat, insert_question_message,
-  select_chat_messages,
+  insert_answer_message, insert_answer_message_with_transaction, insert_chat,
+  insert_question_message, insert_question_message_in_branch, select_chat_message,
+  select_chat_message_branches, select_chat_messages, update_chat_active_branch, ChatMessageParent,
 };
 use futures::stream::Stream;
-use serde_json::json;
+use serde_json::{json, Value};
 use shared_entity::dto::chat_dto::{
-  ChatAuthor, ChatAuthorType, ChatMessage, ChatMessageType, CreateChatMessageParams,
-  CreateChatParams, GetChatMessageParams, RepeatedChatMessage, UpdateChatMessageContentParams,
+  ChatAuthor, ChatAuthorType, ChatCitation, ChatDatabaseSource, ChatMessage, ChatMessageBranches,
+  ChatMessageMetadata, ChatMessageType, ChatParticipantRole, CreateChatMessageParams,
+  CreateChatParams, GetChatMessageParams, RepeatedChatMessage, SwitchChatBranchParams,
+  UpdateChatMessageContentParams,
 };
 use sqlx::PgPool;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+use crate::api::metrics::RequestMetrics;
+use crate::biz::ai::ops::record_ai_usage;
+use crate::biz::chat::database::{answer_database_question, database_source_from_metadata};
+use crate::biz::chat::participant::{broadcast_chat_message, enforce_chat_role};
+use crate::biz::search::search_chat_context;
 
 use appflowy_ai_client::dto::AIModel;
 use validator::Validate;
 
+/// Creates a chat. It has no participants, and only its creator can access it, until it's
+/// shared, see [upsert_participants].
+///
+/// [upsert_participants]: crate::biz::chat::participant::upsert_participants
 pub(crate) async fn create_chat(
   pg_pool: &PgPool,
+  uid: i64,
   params: CreateChatParams,
   workspace_id: &str,
 ) -> Result<(), AppError> {
   params.validate()?;
   trace!("[Chat] create chat {:?}", params);
 
-  insert_chat(pg_pool, workspace_id, params).await?;
+  insert_chat(pg_pool, workspace_id, uid, params).await?;
   Ok(())
 }
 
@@ -41,73 +56,182 @@ pub(crate) async fn delete_chat(pg_pool: &PgPool, chat_id: &str) -> Result<(), A
   Ok(())
 }
 
+/// Edits a question and answers it again. The edited question is added next to the original one,
+/// which remains available, together with the conversation following it, as another branch.
+/// Participants can edit their own questions, and owners of the chat the questions of anyone.
+#[allow(clippy::too_many_arguments)]
 pub async fn update_chat_message(
   pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  appflowy_web_url: Option<&str>,
   params: UpdateChatMessageContentParams,
   ai_client: AppFlowyAIClient,
   ai_model: AIModel,
+  uid: i64,
+  workspace_id: &str,
+  metrics: &RequestMetrics,
 ) -> Result<(), AppError> {
-  let mut txn = pg_pool.begin().await?;
-  delete_answer_message_by_question_message_id(&mut txn, params.message_id).await?;
-  chat::chat_ops::update_chat_message_content(&mut txn, &params).await?;
-  txn.commit().await.map_err(|err| {
-    AppError::Internal(anyhow!(
-      "Failed to commit transaction to update chat message: {}",
-      err
-    ))
-  })?;
-
-  // TODO(nathan): query the metadata from the database
-  let new_answer = ai_client
-    .send_question(
+  let original = select_chat_message(pg_pool, &params.chat_id, params.message_id).await?;
+  if !matches!(original.author.author_type, ChatAuthorType::Human) {
+    return Err(AppError::InvalidRequest(
+      "Only questions can be edited".to_string(),
+    ));
+  }
+  // questions of other participants can only be edited by the owners of the chat
+  if original.author.author_id != uid {
+    enforce_chat_role(
+      pg_pool,
+      workspace_id,
       &params.chat_id,
-      params.message_id,
-      &params.content,
-      &ai_model,
-      None,
+      uid,
+      ChatParticipantRole::Owner,
     )
     .await?;
-  let _answer = insert_answer_message(
+  }
+  let question = insert_question_message_in_branch(
+    pg_pool,
+    ChatAuthor::new(uid, ChatAuthorType::Human),
+    &params.chat_id,
+    params.content,
+    original.meta_data,
+    ChatMessageParent::Message(original.parent_message_id),
+  )
+  .await?;
+  broadcast_chat_message(pg_pool, &params.chat_id, &question).await;
+
+  let (content, metadata) = match database_source_from_metadata(&question.meta_data) {
+    Some(source) => {
+      let answer = answer_database_question(
+        pg_pool,
+        collab_storage,
+        &ai_client,
+        appflowy_web_url,
+        uid,
+        workspace_id,
+        source,
+        &question.content,
+        ai_model,
+      )
+      .await?;
+      (
+        answer.content,
+        append_chat_metadata(None, &[answer.metadata]),
+      )
+    },
+    None => {
+      // TODO(nathan): query the metadata from the database
+      let rag_ids = chat::chat_ops::select_chat_rag_ids(pg_pool, &params.chat_id).await?;
+      let citations = retrieve_chat_citations(
+        pg_pool,
+        &ai_client,
+        metrics,
+        uid,
+        workspace_id,
+        &params.chat_id,
+        &question.content,
+        rag_ids,
+      )
+      .await;
+      let metadata = append_chat_metadata(None, &citations);
+      let new_answer = ai_client
+        .send_question(
+          &params.chat_id,
+          question.message_id,
+          &question.content,
+          &ai_model,
+          metadata,
+        )
+        .await?;
+      record_ai_usage(pg_pool, workspace_id, new_answer.usage.as_ref()).await;
+      (
+        new_answer.content,
+        append_chat_metadata(new_answer.metadata, &citations),
+      )
+    },
+  };
+  let answer = insert_answer_message(
     pg_pool,
     ChatAuthor::ai(),
     &params.chat_id,
-    new_answer.content,
-    new_answer.metadata,
-    params.message_id,
+    content,
+    metadata,
+    question.message_id,
   )
   .await?;
+  broadcast_chat_message(pg_pool, &params.chat_id, &answer).await;
 
   Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_chat_message_answer(
   pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  appflowy_web_url: Option<&str>,
   ai_client: AppFlowyAIClient,
   question_message_id: i64,
   chat_id: &str,
   ai_model: AIModel,
+  uid: i64,
+  workspace_id: &str,
+  metrics: &RequestMetrics,
 ) -> Result<ChatMessage, AppError> {
-  let (content, metadata) =
-    chat::chat_ops::select_chat_message_content(pg_pool, question_message_id).await?;
-  let new_answer = ai_client
-    .send_question(
-      chat_id,
-      question_message_id,
-      &content,
-      &ai_model,
-      Some(metadata),
-    )
-    .await?;
+  let question = select_chat_question(
+    pg_pool,
+    &ai_client,
+    metrics,
+    uid,
+    workspace_id,
+    chat_id,
+    question_message_id,
+  )
+  .await?;
+  let (content, metadata) = match question.database {
+    Some(source) => {
+      let answer = answer_database_question(
+        pg_pool,
+        collab_storage,
+        &ai_client,
+        appflowy_web_url,
+        uid,
+        workspace_id,
+        source,
+        &question.content,
+        ai_model,
+      )
+      .await?;
+      (
+        answer.content,
+        append_chat_metadata(None, &[answer.metadata]),
+      )
+    },
+    None => {
+      let new_answer = ai_client
+        .send_question(
+          chat_id,
+          question_message_id,
+          &question.content,
+          &ai_model,
+          Some(question.metadata),
+        )
+        .await?;
+      info!("new_answer: {:?}", new_answer);
+      record_ai_usage(pg_pool, workspace_id, new_answer.usage.as_ref()).await;
+      (
+        new_answer.content,
+        append_chat_metadata(new_answer.metadata, &question.citations),
+      )
+    },
+  };
 
-  info!("new_answer: {:?}", new_answer);
   // Save the answer to the database
   let mut txn = pg_pool.begin().await?;
   let message = insert_answer_message_with_transaction(
     &mut txn,
     ChatAuthor::ai(),
     chat_id,
-    new_answer.content,
-    new_answer.metadata.unwrap_or_default(),
+    content,
+    metadata.unwrap_or_default(),
     question_message_id,
   )
   .await?;
@@ -117,10 +241,137 @@ pub async fn generate_chat_message_answer(
       err
     ))
   })?;
+  broadcast_chat_message(pg_pool, chat_id, &message).await;
 
   Ok(message)
 }
 
+/// Number of workspace document fragments passed to the AI service as a context of a question.
+const CHAT_CONTEXT_LIMIT: i32 = 5;
+
+/// A question message together with the context, which should be sent to the AI service.
+pub struct ChatQuestionContext {
+  pub content: String,
+  /// Metadata of the question, extended with the retrieved [ChatCitation]s.
+  pub metadata: Value,
+  pub rag_ids: Vec<String>,
+  pub citations: Vec<ChatMessageMetadata>,
+  /// The database view mentioned by the question, whose rows answer it instead of the AI service.
+  pub database: Option<ChatDatabaseSource>,
+}
+
+/// Loads a question message and retrieves document fragments relevant to it from the workspace
+/// embeddings. Questions about a database aren't answered from documents, so no fragments are
+/// retrieved for them.
+pub async fn select_chat_question(
+  pg_pool: &PgPool,
+  ai_client: &AppFlowyAIClient,
+  metrics: &RequestMetrics,
+  uid: i64,
+  workspace_id: &str,
+  chat_id: &str,
+  question_message_id: i64,
+) -> Result<ChatQuestionContext, AppError> {
+  let (content, metadata) =
+    chat::chat_ops::select_chat_message_content(pg_pool, question_message_id).await?;
+  let rag_ids = chat::chat_ops::select_chat_rag_ids(pg_pool, chat_id).await?;
+  let database = database_source_from_metadata(&metadata);
+  let citations = match database {
+    Some(_) => vec![],
+    None => {
+      retrieve_chat_citations(
+        pg_pool,
+        ai_client,
+        metrics,
+        uid,
+        workspace_id,
+        chat_id,
+        &content,
+        rag_ids.clone(),
+      )
+      .await
+    },
+  };
+  let metadata = append_chat_metadata(Some(metadata), &citations).unwrap_or_default();
+  Ok(ChatQuestionContext {
+    content,
+    metadata,
+    rag_ids,
+    citations,
+    database,
+  })
+}
+
+/// Retrieves the document fragments most relevant to a question from the workspace embeddings.
+/// The search is restricted to the RAG ids of the chat, if any, and to the documents the user has
+/// access to. Retrieval is best effort: on failure the question is answered without citations.
+#[allow(clippy::too_many_arguments)]
+pub async fn retrieve_chat_citations(
+  pg_pool: &PgPool,
+  ai_client: &AppFlowyAIClient,
+  metrics: &RequestMetrics,
+  uid: i64,
+  workspace_id: &str,
+  chat_id: &str,
+  question: &str,
+  rag_ids: Vec<String>,
+) -> Vec<ChatMessageMetadata> {
+  let result = async {
+    let workspace_id = Uuid::parse_str(workspace_id)?;
+    search_chat_context(
+      pg_pool,
+      ai_client,
+      uid,
+      workspace_id,
+      question,
+      rag_ids,
+      CHAT_CONTEXT_LIMIT,
+      metrics,
+    )
+    .await
+  }
+  .await;
+  match result {
+    Ok(items) => items
+      .into_iter()
+      .filter_map(|item| {
+        let content = item.content_preview?;
+        Some(ChatMessageMetadata::from(ChatCitation {
+          object_id: item.object_id,
+          fragment_id: item.fragment_id,
+          content,
+          score: item.score,
+        }))
+      })
+      .collect(),
+    Err(err) => {
+      warn!(
+        "[Chat] failed to retrieve context for chat {}: {}",
+        chat_id, err
+      );
+      vec![]
+    },
+  }
+}
+
+/// Appends citations to the metadata of a message, which is expected to be a JSON array. The
+/// metadata is returned unchanged if there are no citations.
+pub fn append_chat_metadata(
+  metadata: Option<Value>,
+  citations: &[ChatMessageMetadata],
+) -> Option<Value> {
+  if citations.is_empty() {
+    return metadata;
+  }
+  let mut items = match metadata {
+    None | Some(Value::Null) => vec![],
+    Some(Value::Array(items)) => items,
+    Some(value) => vec![value],
+  };
+  items.extend(citations.iter().map(|citation| json!(citation)));
+  Some(Value::Array(items))
+}
+
 pub async fn create_chat_message(
   pg_pool: &PgPool,
   uid: i64,
@@ -138,6 +389,7 @@ pub async fn create_chat_message(
     params.metadata,
   )
   .await?;
+  broadcast_chat_message(&pg_pool, &chat_id, &question).await;
   Ok(question)
 }
 
@@ -232,3 +484,30 @@ pub async fn get_chat_messages(
   txn.commit().await?;
   Ok(messages)
 }
+
+pub async fn get_chat_message_branches(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  message_id: i64,
+) -> Result<ChatMessageBranches, AppError> {
+  let mut txn = pg_pool.begin().await?;
+  let branches = select_chat_message_branches(&mut txn, chat_id, message_id).await?;
+  txn.commit().await?;
+  Ok(branches)
+}
+
+/// Selects the branch of the conversation containing the given message. Subsequent questions are
+/// asked in the selected branch.
+pub async fn switch_chat_branch(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  params: SwitchChatBranchParams,
+) -> Result<(), AppError> {
+  let active_message_id = update_chat_active_branch(pg_pool, chat_id, params.message_id).await?;
+  trace!(
+    "[Chat] switch chat {} to branch ending with message {}",
+    chat_id,
+    active_message_id
+  );
+  Ok(())
+}
@@ -0,0 +1,180 @@
+use actix_web::web::Bytes;
+use app_error::AppError;
+use async_stream::stream;
+use collab_rt_entity::chat::{ChatAnswerChunk, ChatEvent, ChatMessageEvent};
+use database::chat::chat_ops::{
+  delete_chat_participants, lock_chat, notify_chat_event, select_chat_has_owner,
+  select_chat_participant_role, select_chat_participant_uids, select_chat_participants,
+  upsert_chat_participants,
+};
+use futures::stream::Stream;
+use shared_entity::dto::chat_dto::{
+  ChatMessage, ChatParticipantParams, ChatParticipantRole, RemoveChatParticipantsParams,
+  RepeatedChatParticipant, UpsertChatParticipantsParams,
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::future::Future;
+use std::ops::DerefMut;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Returns an error if the user doesn't have at least the `required` role in the chat.
+pub async fn enforce_chat_role(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  chat_id: &str,
+  uid: i64,
+  required: ChatParticipantRole,
+) -> Result<(), AppError> {
+  let chat_id = Uuid::parse_str(chat_id)?;
+  match select_chat_participant_role(pg_pool, &chat_id, uid).await? {
+    Some(role) if role >= required => Ok(()),
+    _ => Err(AppError::NotEnoughPermissions {
+      user: uid.to_string(),
+      workspace_id: workspace_id.to_string(),
+    }),
+  }
+}
+
+pub async fn get_chat_participants(
+  pg_pool: &PgPool,
+  chat_id: &str,
+) -> Result<RepeatedChatParticipant, AppError> {
+  let chat_id = Uuid::parse_str(chat_id)?;
+  let items = select_chat_participants(pg_pool, &chat_id).await?;
+  Ok(RepeatedChatParticipant { items })
+}
+
+/// Shares a chat with the given members of the workspace. If the chat hasn't been shared yet, the
+/// user sharing it becomes its owner.
+pub async fn upsert_participants(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  uid: i64,
+  params: UpsertChatParticipantsParams,
+) -> Result<(), AppError> {
+  let chat_id = Uuid::parse_str(chat_id)?;
+  let mut participants = params.participants;
+  let mut txn = pg_pool.begin().await?;
+  lock_chat(&mut txn, &chat_id).await?;
+  let existing = select_chat_participant_uids(txn.deref_mut(), &chat_id).await?;
+  if existing.is_empty() && participants.iter().all(|p| p.uid != uid) {
+    participants.push(ChatParticipantParams {
+      uid,
+      role: ChatParticipantRole::Owner,
+    });
+  }
+  upsert_chat_participants(&mut txn, &chat_id, &participants).await?;
+  ensure_chat_owner(&mut txn, &chat_id).await?;
+  txn.commit().await?;
+  Ok(())
+}
+
+pub async fn remove_participants(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  params: RemoveChatParticipantsParams,
+) -> Result<(), AppError> {
+  let chat_id = Uuid::parse_str(chat_id)?;
+  let mut txn = pg_pool.begin().await?;
+  lock_chat(&mut txn, &chat_id).await?;
+  delete_chat_participants(txn.deref_mut(), &chat_id, &params.uids).await?;
+  ensure_chat_owner(&mut txn, &chat_id).await?;
+  txn.commit().await?;
+  Ok(())
+}
+
+/// Returns an error if a shared chat would be left without an owner, who could no longer manage
+/// its participants.
+async fn ensure_chat_owner(
+  txn: &mut Transaction<'_, Postgres>,
+  chat_id: &Uuid,
+) -> Result<(), AppError> {
+  if !select_chat_has_owner(txn.deref_mut(), chat_id).await? {
+    return Err(AppError::InvalidRequest(
+      "the last owner of a chat can't be removed or demoted".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Returns the users who should receive the events of a chat. Chats which haven't been shared have
+/// no participants, in which case their events are not broadcast.
+pub async fn select_chat_event_recipients(pg_pool: &PgPool, chat_id: &str) -> Vec<i64> {
+  let result = match Uuid::parse_str(chat_id) {
+    Ok(chat_id) => select_chat_participant_uids(pg_pool, &chat_id).await,
+    Err(err) => Err(AppError::from(err)),
+  };
+  result.unwrap_or_else(|err| {
+    warn!(
+      "[Chat] failed to select participants of chat {}: {}",
+      chat_id, err
+    );
+    vec![]
+  })
+}
+
+/// Broadcasts a new message of a chat to its participants.
+pub async fn broadcast_chat_message(pg_pool: &PgPool, chat_id: &str, message: &ChatMessage) {
+  let recipients = select_chat_event_recipients(pg_pool, chat_id).await;
+  if recipients.is_empty() {
+    return;
+  }
+  let event = ChatEvent::NewMessage(ChatMessageEvent {
+    chat_id: chat_id.to_string(),
+    message_id: message.message_id,
+    author_uid: message.author.author_id,
+    content: message.content.clone(),
+    reply_message_id: message.reply_message_id,
+    created_at: message.created_at.timestamp_millis(),
+  });
+  if let Err(err) = notify_chat_event(pg_pool, recipients, event).await {
+    warn!(
+      "[Chat] failed to broadcast message of chat {}: {}",
+      chat_id, err
+    );
+  }
+}
+
+/// Wraps a streamed answer so that each of its chunks is also broadcast to the participants of the
+/// chat. Chunks are published in the background and never slow down the wrapped stream.
+pub fn broadcast_answer_stream<S, E>(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  question_id: i64,
+  answer_stream: S,
+) -> impl Future<Output = impl Stream<Item = Result<Bytes, E>>>
+where
+  S: Stream<Item = Result<Bytes, E>>,
+{
+  // owned, so that the returned stream doesn't borrow the arguments and can outlive the request
+  let pg_pool = pg_pool.clone();
+  let chat_id = chat_id.to_string();
+  async move {
+    let recipients = select_chat_event_recipients(&pg_pool, &chat_id).await;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ChatEvent>();
+    if !recipients.is_empty() {
+      tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+          if let Err(err) = notify_chat_event(&pg_pool, recipients.clone(), event).await {
+            warn!("[Chat] failed to broadcast answer chunk: {}", err);
+          }
+        }
+      });
+    }
+
+    stream! {
+      for await item in answer_stream {
+        if let Ok(data) = &item {
+          let _ = tx.send(ChatEvent::AnswerChunk(ChatAnswerChunk {
+            chat_id: chat_id.clone(),
+            question_id,
+            data: data.to_vec(),
+          }));
+        }
+        yield item;
+      }
+      let _ = tx.send(ChatEvent::AnswerFinished { chat_id, question_id });
+    }
+  }
+}
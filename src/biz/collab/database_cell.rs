@@ -0,0 +1,466 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use collab_database::rows::{Cell, CELL_FIELD_TYPE};
+use collab_database::template::entity::CELL_DATA;
+use serde_json::{json, Value};
+use shared_entity::dto::workspace_dto::{
+  AFChecklistCell, AFChecklistItem, AFDatabaseRowDetail, AFRelatedDatabaseRow,
+};
+use tracing::warn;
+use uuid::Uuid;
+use yrs::Any;
+
+use super::database_query::get_row_details;
+use super::utils::{get_database_body, type_option_reader_by_id};
+
+/// Key of the related database in the type option of a relation field.
+const RELATION_DATABASE_ID: &str = "database_id";
+const CHECKLIST_OPTION_COLOR: &str = "Purple";
+
+/// Field types whose cells are read and written from their raw data, as they have no type option
+/// reader or writer.
+pub fn is_raw_cell_field_type(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::Relation | FieldType::Checklist | FieldType::Media
+  )
+}
+
+/// Returns the json value of a cell of a field without type option reader. Relation cells are
+/// the related rows, without their primary value, see [fill_related_rows].
+pub fn raw_cell_json(field_type: &FieldType, cell: &Cell) -> Value {
+  match field_type {
+    FieldType::Relation => json!(related_row_ids(cell)
+      .into_iter()
+      .map(|row_id| AFRelatedDatabaseRow {
+        row_id,
+        primary_value: None,
+      })
+      .collect::<Vec<_>>()),
+    FieldType::Checklist => json!(checklist_cell(cell)),
+    FieldType::Media => Value::Array(media_files(cell)),
+    _ => Value::Null,
+  }
+}
+
+/// Converts the json value of a cell of a field without type option writer. Returns none when the
+/// value isn't valid for the field type.
+pub fn raw_json_to_cell(
+  field_type: &FieldType,
+  field_type_id: i64,
+  value: Value,
+  workspace_id: &str,
+) -> Option<Cell> {
+  let data = match field_type {
+    FieldType::Relation => {
+      let row_ids: Vec<Any> = json_related_row_ids(value)?
+        .iter()
+        .map(|row_id| Any::String(Arc::from(row_id.as_str())))
+        .collect();
+      Any::Array(Arc::from(row_ids))
+    },
+    FieldType::Checklist => {
+      let items = json_checklist_items(value)?;
+      let options: Vec<Value> = items
+        .iter()
+        .map(|item| json!({ "id": item.id, "name": item.name, "color": CHECKLIST_OPTION_COLOR }))
+        .collect();
+      let selected_option_ids: Vec<&str> = items
+        .iter()
+        .filter(|item| item.checked)
+        .map(|item| item.id.as_str())
+        .collect();
+      let data = json!({ "options": options, "selected_option_ids": selected_option_ids });
+      Any::String(Arc::from(data.to_string()))
+    },
+    FieldType::Media => {
+      let files: Vec<Any> = match value {
+        Value::Array(files) => files
+          .into_iter()
+          .map(|file| json_media_file(file, workspace_id))
+          .collect::<Option<Vec<_>>>()?,
+        file => vec![json_media_file(file, workspace_id)?],
+      }
+      .into_iter()
+      .map(|file| Any::String(Arc::from(file.to_string())))
+      .collect();
+      Any::Array(Arc::from(files))
+    },
+    _ => return None,
+  };
+  Some(Cell::from([
+    (CELL_FIELD_TYPE.to_string(), Any::BigInt(field_type_id)),
+    (CELL_DATA.to_string(), data),
+  ]))
+}
+
+/// Ids of the rows related by a relation cell.
+pub fn related_row_ids(cell: &Cell) -> Vec<String> {
+  match cell.get(CELL_DATA) {
+    Some(Any::Array(ids)) => ids
+      .iter()
+      .filter_map(|id| match id {
+        Any::String(id) => Some(id.to_string()),
+        _ => None,
+      })
+      .collect(),
+    Some(Any::String(ids)) => split_names(ids),
+    _ => vec![],
+  }
+}
+
+fn json_related_row_ids(value: Value) -> Option<Vec<String>> {
+  match value {
+    Value::Null => Some(vec![]),
+    Value::String(ids) => Some(split_names(&ids)),
+    Value::Array(rows) => rows
+      .into_iter()
+      .map(|row| match row {
+        Value::String(row_id) => Some(row_id),
+        Value::Object(mut row) => match row.remove("row_id") {
+          Some(Value::String(row_id)) => Some(row_id),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect(),
+    _ => None,
+  }
+}
+
+fn checklist_cell(cell: &Cell) -> AFChecklistCell {
+  let data: Value = match cell.get(CELL_DATA) {
+    Some(Any::String(data)) => serde_json::from_str(data).unwrap_or_default(),
+    _ => Value::Null,
+  };
+  let selected_option_ids: HashSet<&str> = data["selected_option_ids"]
+    .as_array()
+    .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+    .unwrap_or_default();
+  let items: Vec<AFChecklistItem> = data["options"]
+    .as_array()
+    .map(|options| {
+      options
+        .iter()
+        .map(|option| {
+          let id = option["id"].as_str().unwrap_or_default().to_string();
+          AFChecklistItem {
+            checked: selected_option_ids.contains(id.as_str()),
+            id,
+            name: option["name"].as_str().unwrap_or_default().to_string(),
+          }
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  let percentage = if items.is_empty() {
+    0.0
+  } else {
+    items.iter().filter(|item| item.checked).count() as f64 / items.len() as f64
+  };
+  AFChecklistCell { items, percentage }
+}
+
+fn json_checklist_items(value: Value) -> Option<Vec<AFChecklistItem>> {
+  let mut items = match value {
+    Value::Null => vec![],
+    Value::String(names) => split_names(&names)
+      .into_iter()
+      .map(|name| AFChecklistItem {
+        name,
+        ..Default::default()
+      })
+      .collect(),
+    Value::Array(items) => items
+      .into_iter()
+      .map(|item| match item {
+        Value::String(name) => Some(AFChecklistItem {
+          name,
+          ..Default::default()
+        }),
+        item => serde_json::from_value(item).ok(),
+      })
+      .collect::<Option<Vec<_>>>()?,
+    value => serde_json::from_value::<AFChecklistCell>(value).ok()?.items,
+  };
+  for item in items.iter_mut().filter(|item| item.id.is_empty()) {
+    item.id = Uuid::new_v4().to_string();
+  }
+  Some(items)
+}
+
+fn media_files(cell: &Cell) -> Vec<Value> {
+  match cell.get(CELL_DATA) {
+    Some(Any::Array(files)) => files
+      .iter()
+      .filter_map(|file| match file {
+        Any::String(file) => serde_json::from_str(file).ok(),
+        _ => None,
+      })
+      .collect(),
+    Some(Any::String(data)) => match serde_json::from_str::<Value>(data) {
+      Ok(Value::Object(mut data)) => match data.remove("files") {
+        Some(Value::Array(files)) => files,
+        _ => vec![],
+      },
+      _ => vec![],
+    },
+    _ => vec![],
+  }
+}
+
+/// Completes a file of a media cell. Files of the file storage may be given by their `parent_dir`
+/// and `file_id`, their url being the url of the blob.
+fn json_media_file(value: Value, workspace_id: &str) -> Option<Value> {
+  let mut file = match value {
+    Value::String(url) => serde_json::Map::from_iter([("url".to_string(), Value::String(url))]),
+    Value::Object(file) => file,
+    _ => return None,
+  };
+  let parent_dir = file.remove("parent_dir");
+  let file_id = file.remove("file_id");
+  if let (Some(Value::String(parent_dir)), Some(Value::String(file_id))) = (parent_dir, file_id) {
+    let url = format!(
+      "/api/file_storage/{}/v1/blob/{}/{}",
+      workspace_id, parent_dir, file_id
+    );
+    file.insert("url".to_string(), Value::String(url));
+    file
+      .entry("upload_type")
+      .or_insert_with(|| json!("CloudMedia"));
+  }
+  let url = file.get("url")?.as_str()?.to_string();
+  if url.is_empty() {
+    return None;
+  }
+  file
+    .entry("id")
+    .or_insert_with(|| json!(Uuid::new_v4().to_string()));
+  file.entry("name").or_insert_with(|| {
+    let name = url.rsplit('/').next().unwrap_or_default();
+    json!(name)
+  });
+  file
+    .entry("upload_type")
+    .or_insert_with(|| json!("NetworkMedia"));
+  file.entry("file_type").or_insert_with(|| json!(0));
+  Some(Value::Object(file))
+}
+
+fn split_names(names: &str) -> Vec<String> {
+  names
+    .split(',')
+    .map(str::trim)
+    .filter(|name| !name.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// Id of the database related by a relation field.
+pub fn related_database_id(relation_field: &Field) -> Option<String> {
+  let type_option = relation_field.get_any_type_option(FieldType::Relation.type_id())?;
+  match type_option.get(RELATION_DATABASE_ID) {
+    Some(Any::String(database_id)) => Some(database_id.to_string()),
+    _ => None,
+  }
+}
+
+/// Sets the primary value of the rows related by the relation cells of the rows. `field_by_id` are
+/// the fields of the cells of the rows, by id, with unique names.
+pub async fn fill_related_rows(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_uuid_str: &str,
+  field_by_id: &HashMap<String, Field>,
+  rows: &mut [AFDatabaseRowDetail],
+) {
+  let relation_fields: Vec<(&Field, String)> = field_by_id
+    .values()
+    .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+    .filter_map(|field| Some((field, related_database_id(field)?)))
+    .collect();
+
+  let mut row_ids_by_database: HashMap<&str, HashSet<String>> = HashMap::new();
+  for (field, database_id) in &relation_fields {
+    for row in rows.iter() {
+      let related_rows = row
+        .cells
+        .get(&field.name)
+        .and_then(|cell| cell.get(CELL_DATA))
+        .and_then(Value::as_array);
+      for related_row in related_rows.into_iter().flatten() {
+        if let Some(row_id) = related_row["row_id"].as_str() {
+          row_ids_by_database
+            .entry(database_id.as_str())
+            .or_default()
+            .insert(row_id.to_string());
+        }
+      }
+    }
+  }
+
+  let mut primary_value_by_row_id: HashMap<String, String> = HashMap::new();
+  for (database_id, row_ids) in row_ids_by_database {
+    let row_ids: Vec<String> = row_ids.into_iter().collect();
+    match primary_values(
+      collab_storage,
+      uid,
+      workspace_uuid_str,
+      database_id,
+      &row_ids,
+    )
+    .await
+    {
+      Ok(primary_values) => primary_value_by_row_id.extend(primary_values),
+      Err(err) => warn!(
+        "Failed to get primary values of related database {}: {}",
+        database_id, err
+      ),
+    }
+  }
+
+  for (field, _) in &relation_fields {
+    for row in rows.iter_mut() {
+      let related_rows = row
+        .cells
+        .get_mut(&field.name)
+        .and_then(|cell| cell.get_mut(CELL_DATA))
+        .and_then(Value::as_array_mut);
+      for related_row in related_rows.into_iter().flatten() {
+        let primary_value = related_row["row_id"]
+          .as_str()
+          .and_then(|row_id| primary_value_by_row_id.get(row_id));
+        if let (Some(primary_value), Some(related_row)) =
+          (primary_value, related_row.as_object_mut())
+        {
+          related_row.insert("primary_value".to_string(), json!(primary_value));
+        }
+      }
+    }
+  }
+}
+
+/// Returns the value of the primary field of the rows of a database, by row id.
+pub(crate) async fn primary_values(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  row_ids: &[String],
+) -> Result<HashMap<String, String>, app_error::AppError> {
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+  let primary_field = db_body
+    .fields
+    .get_all_fields(&db_collab.transact())
+    .into_iter()
+    .find(|field| field.is_primary);
+  let primary_field = match primary_field {
+    Some(field) => field,
+    None => return Ok(HashMap::new()),
+  };
+  let readers = type_option_reader_by_id(std::slice::from_ref(&primary_field));
+  let reader = match readers.get(&primary_field.id) {
+    Some(reader) => reader,
+    None => return Ok(HashMap::new()),
+  };
+
+  let rows = get_row_details(collab_storage, uid, workspace_uuid_str, row_ids).await;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| {
+        let value = row
+          .row
+          .cells
+          .get(&primary_field.id)
+          .map(|cell| reader.json_cell(cell))
+          .unwrap_or_default();
+        let primary_value = match value {
+          Value::Null => String::new(),
+          Value::String(text) => text,
+          value => value.to_string(),
+        };
+        (row.row.id.to_string(), primary_value)
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn checklist_cell_round_trip_test() {
+    let cell = raw_json_to_cell(
+      &FieldType::Checklist,
+      FieldType::Checklist.into(),
+      json!([{ "name": "Write tests", "checked": true }, "Ship it"]),
+      "workspace",
+    )
+    .unwrap();
+    let checklist = checklist_cell(&cell);
+    assert_eq!(checklist.items.len(), 2);
+    assert_eq!(checklist.items[0].name, "Write tests");
+    assert!(checklist.items[0].checked);
+    assert!(!checklist.items[0].id.is_empty());
+    assert_eq!(checklist.items[1].name, "Ship it");
+    assert!(!checklist.items[1].checked);
+    assert_eq!(checklist.percentage, 0.5);
+  }
+
+  #[test]
+  fn relation_cell_round_trip_test() {
+    let cell = raw_json_to_cell(
+      &FieldType::Relation,
+      FieldType::Relation.into(),
+      json!(["row_1", { "row_id": "row_2", "primary_value": "Row 2" }]),
+      "workspace",
+    )
+    .unwrap();
+    assert_eq!(related_row_ids(&cell), vec!["row_1", "row_2"]);
+    assert_eq!(
+      raw_cell_json(&FieldType::Relation, &cell),
+      json!([
+        { "row_id": "row_1", "primary_value": null },
+        { "row_id": "row_2", "primary_value": null },
+      ])
+    );
+    assert!(raw_json_to_cell(
+      &FieldType::Relation,
+      FieldType::Relation.into(),
+      json!(1),
+      "workspace"
+    )
+    .is_none());
+  }
+
+  #[test]
+  fn media_cell_round_trip_test() {
+    let cell = raw_json_to_cell(
+      &FieldType::Media,
+      FieldType::Media.into(),
+      json!([
+        { "name": "cat.png", "parent_dir": "dir", "file_id": "cat_id" },
+        "https://appflowy.io/logo.svg",
+      ]),
+      "workspace",
+    )
+    .unwrap();
+    let files = media_files(&cell);
+    assert_eq!(files.len(), 2);
+    assert_eq!(
+      files[0]["url"],
+      "/api/file_storage/workspace/v1/blob/dir/cat_id"
+    );
+    assert_eq!(files[0]["name"], "cat.png");
+    assert!(files[0].get("file_id").is_none());
+    assert_eq!(files[1]["name"], "logo.svg");
+    assert!(!files[1]["id"].as_str().unwrap().is_empty());
+  }
+}
@@ -0,0 +1,825 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_database::entity::FieldType;
+use collab_database::fields::{Field, TypeOptionCellReader};
+use collab_database::rows::{Cell, DatabaseRowBody, RowDetail};
+use collab_database::template::entity::CELL_DATA;
+use collab_entity::CollabType;
+use database::collab::{CollabStorage, GetCollabOrigin};
+use database::computed_field::{
+  delete_database_computed_field, insert_database_computed_field, select_database_computed_fields,
+  update_database_computed_field, update_database_computed_field_error,
+};
+use database::pg_row::AFDatabaseRowChange;
+use database_entity::dto::CollabParams;
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseComputation, AFDatabaseComputedField, AFInsertDatabaseComputedField,
+  AFInsertDatabaseField, AFRollupAggregation, AFUpdateDatabaseComputedField,
+};
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::broadcast::Receiver;
+use tokio::time::sleep;
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+use yrs::Any;
+
+use super::database_cell::{related_database_id, related_row_ids};
+use super::database_formula::{Formula, FormulaValue};
+use super::database_query::get_row_details;
+use super::ops::{add_database_field, list_database_row_ids};
+use super::utils::{
+  collab_to_bin, field_by_id_name_uniq, get_database_body, get_latest_collab,
+  get_row_details_serde, type_option_reader_by_id, type_option_writer_by_id,
+};
+use crate::biz::workspace::ops::broadcast_update;
+
+/// Changes of rows notified within this delay are computed together, so that a row being edited
+/// is computed once.
+const COMPUTED_FIELD_DEBOUNCE: Duration = Duration::from_secs(2);
+
+pub async fn create_computed_field(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  params: AFInsertDatabaseComputedField,
+) -> Result<AFDatabaseComputedField, AppError> {
+  if params.name.trim().is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The name of a computed field can't be empty".to_string(),
+    ));
+  }
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let fields = get_fields(collab_storage, workspace_id, database_id).await?;
+  let computation = resolve_computation(
+    collab_storage,
+    workspace_id,
+    &fields,
+    params.computation,
+    None,
+  )
+  .await?;
+
+  let field_type = match computation {
+    AFDatabaseComputation::Formula { .. } => FieldType::RichText,
+    AFDatabaseComputation::Rollup { .. } => FieldType::Number,
+  };
+  let field_id = add_database_field(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    AFInsertDatabaseField {
+      name: params.name,
+      field_type: field_type.into(),
+      type_option_data: None,
+    },
+  )
+  .await?;
+  let computed_field = insert_database_computed_field(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    &field_id,
+    &computation,
+    uid,
+  )
+  .await?;
+  compute_all_rows(pg_pool, collab_storage, uid, workspace_id, database_id).await;
+  Ok(computed_field)
+}
+
+pub async fn list_computed_fields(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseComputedField>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_database_computed_fields(pg_pool, &workspace_uuid, database_id).await
+}
+
+/// Changes how the cells of a computed field are computed, and computes all of them again. A
+/// formula can't be changed to a rollup, and the other way around.
+pub async fn update_computed_field(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+  params: AFUpdateDatabaseComputedField,
+) -> Result<AFDatabaseComputedField, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let current = select_database_computed_fields(pg_pool, &workspace_uuid, database_id)
+    .await?
+    .into_iter()
+    .find(|computed_field| computed_field.field_id == field_id)
+    .ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "computed field:{} is not found in database:{}",
+        field_id, database_id
+      ))
+    })?;
+  if std::mem::discriminant(&current.computation) != std::mem::discriminant(&params.computation) {
+    return Err(AppError::InvalidRequest(
+      "A formula can't be changed to a rollup, and the other way around".to_string(),
+    ));
+  }
+
+  let fields = get_fields(collab_storage, workspace_id, database_id).await?;
+  let computation = resolve_computation(
+    collab_storage,
+    workspace_id,
+    &fields,
+    params.computation,
+    Some(field_id),
+  )
+  .await?;
+  let computed_field = update_database_computed_field(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    field_id,
+    &computation,
+  )
+  .await?;
+  compute_all_rows(pg_pool, collab_storage, uid, workspace_id, database_id).await;
+  Ok(computed_field)
+}
+
+/// Stops computing the cells of the field. The field and its cells are kept.
+pub async fn delete_computed_field(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  delete_database_computed_field(pg_pool, &workspace_uuid, database_id, field_id).await
+}
+
+async fn get_fields(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<Field>, AppError> {
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let fields = db_body.fields.get_all_fields(&db_collab.transact());
+  Ok(fields)
+}
+
+fn find_field<'a>(fields: &'a [Field], id_or_name: &str) -> Option<&'a Field> {
+  fields
+    .iter()
+    .find(|field| field.id == id_or_name)
+    .or_else(|| fields.iter().find(|field| field.name == id_or_name))
+}
+
+/// Checks that the formula is valid and only references other fields of the database, or that the
+/// fields of the rollup exist. The fields of rollups are returned by their id.
+async fn resolve_computation(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  fields: &[Field],
+  computation: AFDatabaseComputation,
+  computed_field_id: Option<&str>,
+) -> Result<AFDatabaseComputation, AppError> {
+  match computation {
+    AFDatabaseComputation::Formula { expression } => {
+      let formula = Formula::parse(&expression)
+        .map_err(|err| AppError::InvalidRequest(format!("Invalid formula: {}", err)))?;
+      let field_by_name = field_by_id_name_uniq(fields.to_vec())
+        .into_values()
+        .map(|field| (field.name.clone(), field))
+        .collect::<HashMap<_, _>>();
+      for name in formula.fields() {
+        match field_by_name.get(name) {
+          None => {
+            return Err(AppError::InvalidRequest(format!(
+              "The formula references an unknown field: {}",
+              name
+            )))
+          },
+          Some(field) if Some(field.id.as_str()) == computed_field_id => {
+            return Err(AppError::InvalidRequest(
+              "A formula can't reference its own field".to_string(),
+            ))
+          },
+          Some(_) => {},
+        }
+      }
+      Ok(AFDatabaseComputation::Formula { expression })
+    },
+    AFDatabaseComputation::Rollup {
+      relation_field,
+      target_field,
+      aggregation,
+    } => {
+      let relation_field = find_field(fields, &relation_field)
+        .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+        .ok_or_else(|| {
+          AppError::InvalidRequest(format!("Relation field not found: {}", relation_field))
+        })?;
+      let related_database_id = related_database_id(relation_field).ok_or_else(|| {
+        AppError::InvalidRequest(format!(
+          "Relation field {} isn't related to a database",
+          relation_field.name
+        ))
+      })?;
+      let target_field = match (target_field, aggregation) {
+        (None, AFRollupAggregation::Count) => None,
+        (None, _) => {
+          return Err(AppError::InvalidRequest(format!(
+            "A {:?} rollup needs a target field",
+            aggregation
+          )))
+        },
+        (Some(target_field), _) => {
+          let related_fields =
+            get_fields(collab_storage, workspace_id, &related_database_id).await?;
+          let field = find_field(&related_fields, &target_field).ok_or_else(|| {
+            AppError::InvalidRequest(format!("Target field not found: {}", target_field))
+          })?;
+          let expected_type = match aggregation {
+            AFRollupAggregation::Count => None,
+            AFRollupAggregation::PercentChecked => Some(FieldType::Checkbox),
+            _ => Some(FieldType::Number),
+          };
+          if let Some(expected_type) = expected_type {
+            if FieldType::from(field.field_type) != expected_type {
+              return Err(AppError::InvalidRequest(format!(
+                "The target field of a {:?} rollup must be a {:?} field",
+                aggregation, expected_type
+              )));
+            }
+          }
+          Some(field.id.clone())
+        },
+      };
+      Ok(AFDatabaseComputation::Rollup {
+        relation_field: relation_field.id.clone(),
+        target_field,
+        aggregation,
+      })
+    },
+  }
+}
+
+async fn compute_all_rows(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+) {
+  let result = async {
+    let row_ids: Vec<String> = list_database_row_ids(collab_storage, workspace_id, database_id)
+      .await?
+      .into_iter()
+      .map(|row| row.id)
+      .collect();
+    let mut rows = get_row_details(collab_storage, uid, workspace_id, &row_ids).await;
+    compute_and_write_rows(
+      pg_pool,
+      collab_storage,
+      workspace_id,
+      database_id,
+      &mut rows,
+    )
+    .await
+  }
+  .await;
+  if let Err(err) = result {
+    warn!(
+      "[Computed field] failed to compute rows of database {}: {}",
+      database_id, err
+    );
+  }
+}
+
+/// Computes the cells of the computed fields of the rows, and sets them in the rows. Nothing is
+/// written, so that reading rows doesn't edit them.
+pub async fn compute_database_rows(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  rows: &mut [RowDetail],
+) -> Result<(), AppError> {
+  compute_rows(
+    pg_pool,
+    collab_storage,
+    workspace_id,
+    database_id,
+    rows,
+    false,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Computes the cells of the computed fields of the rows, and writes the cells whose value changed
+/// to their collabs so that all clients show the same values. The cells of formulas depending on
+/// the time aren't written, as their value changes each time they are computed.
+async fn compute_and_write_rows(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  rows: &mut [RowDetail],
+) -> Result<(), AppError> {
+  let changed_rows = compute_rows(
+    pg_pool,
+    collab_storage,
+    workspace_id,
+    database_id,
+    rows,
+    true,
+  )
+  .await?;
+  for changed_row in changed_rows {
+    trace!(
+      "[Computed field] update {} cells of row {}",
+      changed_row.cells.len(),
+      changed_row.row_id
+    );
+    write_row_cells(
+      pg_pool,
+      collab_storage,
+      workspace_id,
+      changed_row.uid,
+      &changed_row.row_id,
+      changed_row.cells,
+    )
+    .await?;
+  }
+  Ok(())
+}
+
+/// Cells of a row whose computed value changed, written on behalf of the user who created the
+/// first of their computed fields.
+struct ChangedRow {
+  row_id: String,
+  uid: i64,
+  cells: Vec<(String, Cell)>,
+}
+
+/// Computes the cells of the computed fields of the rows and sets them in the rows. Returns the
+/// cells whose value changed and can be written. The errors of the computed fields are saved when
+/// `save_errors` is set.
+async fn compute_rows(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  rows: &mut [RowDetail],
+  save_errors: bool,
+) -> Result<Vec<ChangedRow>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let computed_fields =
+    select_database_computed_fields(pg_pool, &workspace_uuid, database_id).await?;
+  if computed_fields.is_empty() || rows.is_empty() {
+    return Ok(vec![]);
+  }
+
+  let fields = get_fields(collab_storage, workspace_id, database_id).await?;
+  let field_by_id = field_by_id_name_uniq(fields.clone());
+  let type_option_reader_by_id = type_option_reader_by_id(&fields);
+  let type_option_writer_by_id = type_option_writer_by_id(&fields);
+  let field_type_by_name: HashMap<&str, i64> = field_by_id
+    .values()
+    .map(|field| (field.name.as_str(), field.field_type))
+    .collect();
+
+  let mut computers = vec![];
+  for computed_field in &computed_fields {
+    // the field may have been removed from the database
+    let Some(field) = field_by_id.get(&computed_field.field_id) else {
+      continue;
+    };
+    let result = Computer::new(
+      collab_storage,
+      workspace_id,
+      computed_field,
+      &field_by_id,
+      rows,
+    )
+    .await;
+    let error = match result {
+      Ok(computer) => {
+        computers.push((field, computed_field.created_by, computer));
+        None
+      },
+      Err(err) => Some(err),
+    };
+    if save_errors && error != computed_field.error {
+      update_database_computed_field_error(
+        pg_pool,
+        database_id,
+        &computed_field.field_id,
+        error.as_deref(),
+      )
+      .await?;
+    }
+  }
+
+  let mut changed_rows = vec![];
+  for row in rows.iter_mut() {
+    let mut values: HashMap<String, FormulaValue> =
+      get_row_details_serde(row.clone(), &field_by_id, &type_option_reader_by_id)
+        .into_iter()
+        .filter_map(|(name, mut cell)| {
+          let field_type = FieldType::from(*field_type_by_name.get(name.as_str())?);
+          let value = cell.remove(CELL_DATA).unwrap_or_default();
+          Some((name, FormulaValue::from_cell_json(&field_type, &value)))
+        })
+        .collect();
+
+    let mut new_cells: Vec<(String, Cell)> = vec![];
+    let mut changed_row = None;
+    for (field, created_by, computer) in &computers {
+      let Some(writer) = type_option_writer_by_id.get(&field.id) else {
+        continue;
+      };
+      let value = computer.compute(row, &values);
+      let new_cell = writer.convert_json_to_cell(Value::String(value.to_text()));
+      let current_data = row
+        .row
+        .cells
+        .get(&field.id)
+        .and_then(|cell| cell.get(CELL_DATA));
+      if cell_text(current_data) != cell_text(new_cell.get(CELL_DATA)) {
+        if !computer.is_time_dependent() {
+          changed_row
+            .get_or_insert_with(|| ChangedRow {
+              row_id: row.row.id.to_string(),
+              uid: *created_by,
+              cells: vec![],
+            })
+            .cells
+            .push((field.id.clone(), new_cell.clone()));
+        }
+        new_cells.push((field.id.clone(), new_cell));
+      }
+      // formulas created later may reference the field
+      values.insert(field.name.clone(), value);
+    }
+
+    for (field_id, new_cell) in new_cells {
+      row.row.cells.insert(field_id, new_cell);
+    }
+    changed_rows.extend(changed_row);
+  }
+  Ok(changed_rows)
+}
+
+fn cell_text(data: Option<&Any>) -> String {
+  match data {
+    None | Some(Any::Null) | Some(Any::Undefined) => String::new(),
+    Some(Any::String(text)) => text.to_string(),
+    Some(data) => format!("{:?}", data),
+  }
+}
+
+/// Writes cells of a row on behalf of the user who created the computed field.
+async fn write_row_cells(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  uid: i64,
+  row_id: &str,
+  new_cells: Vec<(String, Cell)>,
+) -> Result<(), AppError> {
+  let mut row_collab = get_latest_collab(
+    collab_storage,
+    GetCollabOrigin::Server,
+    workspace_id,
+    row_id,
+    CollabType::DatabaseRow,
+  )
+  .await?;
+  let row_body = DatabaseRowBody::open(row_id.to_string().into(), &mut row_collab)
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to open row {}: {}", row_id, e)))?;
+  let update = {
+    let mut txn = row_collab.transact_mut();
+    row_body.update(&mut txn, |row_update| {
+      row_update
+        .set_last_modified(Utc::now().timestamp())
+        .update_cells(|cells_update| {
+          for (field_id, new_cell) in new_cells {
+            cells_update.insert_cell(&field_id, new_cell);
+          }
+        });
+    });
+    txn.encode_update_v1()
+  };
+  let encoded_row = collab_to_bin(row_collab, CollabType::DatabaseRow).await?;
+
+  let mut txn = pg_pool.begin().await?;
+  collab_storage
+    .upsert_new_collab_with_transaction(
+      workspace_id,
+      &uid,
+      CollabParams {
+        object_id: row_id.to_string(),
+        encoded_collab_v1: encoded_row.into(),
+        collab_type: CollabType::DatabaseRow,
+        embeddings: None,
+      },
+      &mut txn,
+      "inserting computed cells of database row from server",
+    )
+    .await?;
+  txn.commit().await?;
+  broadcast_update(collab_storage, row_id, update).await
+}
+
+/// Computes the cells of a computed field. Errors are reported on the field when it's created.
+enum Computer {
+  Formula(Formula),
+  Rollup {
+    relation_field_id: String,
+    aggregation: AFRollupAggregation,
+    /// Values of the target field of the related rows, by row id.
+    target_value_by_row_id: HashMap<String, FormulaValue>,
+  },
+}
+
+impl Computer {
+  async fn new(
+    collab_storage: &CollabAccessControlStorage,
+    workspace_id: &str,
+    computed_field: &AFDatabaseComputedField,
+    field_by_id: &HashMap<String, Field>,
+    rows: &[RowDetail],
+  ) -> Result<Self, String> {
+    match &computed_field.computation {
+      AFDatabaseComputation::Formula { expression } => {
+        let formula = Formula::parse(expression).map_err(|err| err.to_string())?;
+        for name in formula.fields() {
+          if !field_by_id.values().any(|field| field.name == name) {
+            return Err(format!("The formula references an unknown field: {}", name));
+          }
+        }
+        Ok(Computer::Formula(formula))
+      },
+      AFDatabaseComputation::Rollup {
+        relation_field,
+        target_field,
+        aggregation,
+      } => {
+        let field = field_by_id
+          .get(relation_field)
+          .ok_or_else(|| format!("Relation field not found: {}", relation_field))?;
+        let related_database_id = related_database_id(field)
+          .ok_or_else(|| format!("Relation field {} isn't related to a database", field.name))?;
+
+        let mut target_value_by_row_id = HashMap::new();
+        if let Some(target_field) = target_field {
+          let related_fields = get_fields(collab_storage, workspace_id, &related_database_id)
+            .await
+            .map_err(|err| err.to_string())?;
+          let target = related_fields
+            .into_iter()
+            .find(|field| &field.id == target_field)
+            .ok_or_else(|| format!("Target field not found: {}", target_field))?;
+          let readers = type_option_reader_by_id(std::slice::from_ref(&target));
+          let reader = readers
+            .get(&target.id)
+            .ok_or_else(|| format!("Target field {} can't be read", target.name))?;
+          let related_row_ids: Vec<String> = rows
+            .iter()
+            .flat_map(|row| {
+              row
+                .row
+                .cells
+                .get(relation_field)
+                .map(related_row_ids)
+                .unwrap_or_default()
+            })
+            .collect();
+          let related_rows = get_row_details(
+            collab_storage,
+            computed_field.created_by,
+            workspace_id,
+            &related_row_ids,
+          )
+          .await;
+          target_value_by_row_id = related_rows
+            .into_iter()
+            .map(|row| {
+              let value = target_value(&target, &**reader, &row);
+              (row.row.id.to_string(), value)
+            })
+            .collect();
+        }
+        Ok(Computer::Rollup {
+          relation_field_id: relation_field.clone(),
+          aggregation: *aggregation,
+          target_value_by_row_id,
+        })
+      },
+    }
+  }
+
+  fn is_time_dependent(&self) -> bool {
+    match self {
+      Computer::Formula(formula) => formula.is_time_dependent(),
+      Computer::Rollup { .. } => false,
+    }
+  }
+
+  fn compute(&self, row: &RowDetail, values: &HashMap<String, FormulaValue>) -> FormulaValue {
+    match self {
+      Computer::Formula(formula) => formula.evaluate(values),
+      Computer::Rollup {
+        relation_field_id,
+        aggregation,
+        target_value_by_row_id,
+      } => {
+        let related_row_ids = row
+          .row
+          .cells
+          .get(relation_field_id)
+          .map(related_row_ids)
+          .unwrap_or_default();
+        let target_values: Vec<&FormulaValue> = related_row_ids
+          .iter()
+          .filter_map(|row_id| target_value_by_row_id.get(row_id))
+          .collect();
+        rollup(*aggregation, related_row_ids.len(), &target_values)
+      },
+    }
+  }
+}
+
+fn target_value(
+  target: &Field,
+  reader: &dyn TypeOptionCellReader,
+  row: &RowDetail,
+) -> FormulaValue {
+  match row.row.cells.get(&target.id) {
+    Some(cell) => {
+      FormulaValue::from_cell_json(&FieldType::from(target.field_type), &reader.json_cell(cell))
+    },
+    None => FormulaValue::Empty,
+  }
+}
+
+/// Aggregates the values of the target field of the related rows. Empty values are ignored, except
+/// when counting the related rows.
+fn rollup(
+  aggregation: AFRollupAggregation,
+  related_rows: usize,
+  target_values: &[&FormulaValue],
+) -> FormulaValue {
+  let numbers: Vec<f64> = target_values
+    .iter()
+    .filter_map(|value| match value {
+      FormulaValue::Number(number) => Some(*number),
+      _ => None,
+    })
+    .collect();
+  let number = match aggregation {
+    AFRollupAggregation::Count => Some(related_rows as f64),
+    AFRollupAggregation::Sum => Some(numbers.iter().sum()),
+    AFRollupAggregation::Average if numbers.is_empty() => None,
+    AFRollupAggregation::Average => Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+    AFRollupAggregation::Min => numbers.iter().copied().reduce(f64::min),
+    AFRollupAggregation::Max => numbers.iter().copied().reduce(f64::max),
+    AFRollupAggregation::PercentChecked if target_values.is_empty() => None,
+    AFRollupAggregation::PercentChecked => {
+      let checked = target_values
+        .iter()
+        .filter(|value| matches!(value, FormulaValue::Bool(true)))
+        .count();
+      Some((checked as f64 * 100.0 / target_values.len() as f64).round())
+    },
+  };
+  number
+    .map(FormulaValue::Number)
+    .unwrap_or(FormulaValue::Empty)
+}
+
+/// Computes the cells of the rows in the background, as their changes are notified through
+/// [database::pg_row::DATABASE_ROW_CHANGE_CHANNEL], and writes them. Rows read through the REST
+/// API are computed again when they are read, as the cells of formulas depending on the time
+/// aren't written.
+pub fn spawn_computed_field_worker(
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  mut row_changes: Receiver<AFDatabaseRowChange>,
+) {
+  tokio::spawn(async move {
+    info!("[Computed field] worker started");
+    loop {
+      let change = match row_changes.recv().await {
+        Ok(change) => change,
+        Err(RecvError::Lagged(count)) => {
+          warn!("[Computed field] missed {} row changes", count);
+          continue;
+        },
+        Err(RecvError::Closed) => break,
+      };
+      sleep(COMPUTED_FIELD_DEBOUNCE).await;
+      let mut changed_row_ids: HashMap<(Uuid, String), HashSet<String>> = HashMap::new();
+      let mut add_change = |change: AFDatabaseRowChange| {
+        changed_row_ids
+          .entry((change.workspace_id, change.database_id))
+          .or_default()
+          .insert(change.row_id);
+      };
+      add_change(change);
+      loop {
+        match row_changes.try_recv() {
+          Ok(change) => add_change(change),
+          Err(TryRecvError::Lagged(count)) => {
+            warn!("[Computed field] missed {} row changes", count)
+          },
+          Err(_) => break,
+        }
+      }
+
+      for ((workspace_id, database_id), row_ids) in changed_row_ids {
+        let workspace_id = workspace_id.to_string();
+        let row_ids: Vec<String> = row_ids.into_iter().collect();
+        // the uid isn't checked when reading collabs from the server
+        let mut rows = get_row_details(&collab_storage, 0, &workspace_id, &row_ids).await;
+        let result = compute_and_write_rows(
+          &pg_pool,
+          &collab_storage,
+          &workspace_id,
+          &database_id,
+          &mut rows,
+        )
+        .await;
+        if let Err(err) = result {
+          warn!(
+            "[Computed field] failed to compute updated rows of database {}: {}",
+            database_id, err
+          );
+        }
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rollup_test() {
+    let numbers = [
+      FormulaValue::Number(3.0),
+      FormulaValue::Empty,
+      FormulaValue::Number(1.5),
+    ];
+    let numbers: Vec<&FormulaValue> = numbers.iter().collect();
+    assert_eq!(
+      rollup(AFRollupAggregation::Count, 3, &numbers),
+      FormulaValue::Number(3.0)
+    );
+    assert_eq!(
+      rollup(AFRollupAggregation::Sum, 3, &numbers),
+      FormulaValue::Number(4.5)
+    );
+    assert_eq!(
+      rollup(AFRollupAggregation::Average, 3, &numbers),
+      FormulaValue::Number(2.25)
+    );
+    assert_eq!(
+      rollup(AFRollupAggregation::Min, 3, &numbers),
+      FormulaValue::Number(1.5)
+    );
+    assert_eq!(
+      rollup(AFRollupAggregation::Max, 3, &numbers),
+      FormulaValue::Number(3.0)
+    );
+    assert_eq!(
+      rollup(AFRollupAggregation::Average, 0, &[]),
+      FormulaValue::Empty
+    );
+
+    let checks = [
+      FormulaValue::Bool(true),
+      FormulaValue::Bool(false),
+      FormulaValue::Bool(true),
+      FormulaValue::Empty,
+    ];
+    let checks: Vec<&FormulaValue> = checks.iter().collect();
+    assert_eq!(
+      rollup(AFRollupAggregation::PercentChecked, 4, &checks),
+      FormulaValue::Number(50.0)
+    );
+  }
+}
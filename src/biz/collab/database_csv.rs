@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use collab_database::rows::Cell;
+use collab_database::template::entity::CELL_DATA;
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{
+  AFImportDatabaseCsv, AFImportDatabaseCsvError, AFImportDatabaseCsvResult, AFInsertDatabaseField,
+};
+use sqlx::PgPool;
+use yrs::Any;
+
+use super::database_cell::{is_raw_cell_field_type, raw_json_to_cell};
+use super::database_query::get_row_details;
+use super::ops::{add_database_field, insert_database_rows};
+use super::utils::{
+  field_by_id_name_uniq, get_database_body, get_row_details_serde, type_option_reader_by_id,
+  type_option_writer_by_id,
+};
+
+const MAX_CSV_IMPORT_ROWS: usize = 10_000;
+/// Key of the visibility of a field in its settings. Fields are hidden when it's `AlwaysHidden`.
+const FIELD_VISIBILITY: &str = "visibility";
+const FIELD_ALWAYS_HIDDEN: i64 = 2;
+
+/// Where the values of a column of the CSV go.
+enum ColumnTarget {
+  Field(Field),
+  NewField { name: String, field_type: i64 },
+  Skip,
+}
+
+/// A value of the CSV converted for its field, before the fields to create are created.
+enum ImportedValue {
+  /// Cell of a field without type option.
+  Cell(Cell),
+  /// Json value of a cell, written with the type option of its field.
+  Json(Value),
+}
+
+/// Appends the records of the CSV to the database. The values are converted according to the type
+/// of their field, and records with invalid values are reported instead of being imported. New
+/// fields are only created if at least one record is imported.
+pub async fn import_database_csv(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  uid: i64,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  params: AFImportDatabaseCsv,
+) -> Result<AFImportDatabaseCsvResult, AppError> {
+  let mut reader = csv::ReaderBuilder::new()
+    .has_headers(true)
+    .trim(csv::Trim::All)
+    .from_reader(params.csv.as_bytes());
+  let headers: Vec<String> = reader
+    .headers()
+    .map_err(|err| AppError::InvalidRequest(format!("Invalid CSV header: {}", err)))?
+    .iter()
+    .map(|header| header.to_string())
+    .collect();
+
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+  let all_fields = db_body.fields.get_all_fields(&db_collab.transact());
+  let targets = resolve_columns(&headers, &all_fields, &params)?;
+
+  let mut errors = vec![];
+  let mut records = vec![];
+  for record in reader.records() {
+    match record {
+      Ok(record) => {
+        if record.iter().all(|value| value.is_empty()) {
+          continue;
+        }
+        let line = record.position().map(|pos| pos.line()).unwrap_or_default();
+        records.push((line, record));
+      },
+      Err(err) => errors.push(AFImportDatabaseCsvError {
+        line: err.position().map(|pos| pos.line()).unwrap_or_default(),
+        column: None,
+        error: err.to_string(),
+      }),
+    }
+  }
+  if records.len() > MAX_CSV_IMPORT_ROWS {
+    return Err(AppError::InvalidRequest(format!(
+      "At most {} rows can be imported at once",
+      MAX_CSV_IMPORT_ROWS
+    )));
+  }
+
+  // the values are converted and validated before any field is created, so that a CSV without
+  // any valid record leaves the database unchanged
+  let mut imported_rows: Vec<Vec<(usize, ImportedValue)>> = Vec::with_capacity(records.len());
+  'records: for (line, record) in records {
+    let mut values = vec![];
+    for (column, value) in record.iter().enumerate() {
+      let value = unescape_csv_formula(value);
+      let field_type_id = match targets.get(column) {
+        Some(ColumnTarget::Field(field)) => field.field_type,
+        Some(ColumnTarget::NewField { field_type, .. }) => *field_type,
+        _ => continue,
+      };
+      let field_type = FieldType::from(field_type_id);
+      let json_value = match csv_value_to_json(&field_type, value) {
+        Ok(Some(json_value)) => json_value,
+        Ok(None) => continue,
+        Err(error) => {
+          errors.push(AFImportDatabaseCsvError {
+            line,
+            column: headers.get(column).cloned(),
+            error,
+          });
+          continue 'records;
+        },
+      };
+      if is_raw_cell_field_type(&field_type) {
+        match raw_json_to_cell(&field_type, field_type_id, json_value, workspace_uuid_str) {
+          Some(cell) => values.push((column, ImportedValue::Cell(cell))),
+          None => {
+            errors.push(AFImportDatabaseCsvError {
+              line,
+              column: headers.get(column).cloned(),
+              error: format!("{} is not a valid {:?} value", value, field_type),
+            });
+            continue 'records;
+          },
+        }
+      } else {
+        values.push((column, ImportedValue::Json(json_value)));
+      }
+    }
+    imported_rows.push(values);
+  }
+  if imported_rows.is_empty() {
+    errors.sort_by_key(|error| error.line);
+    return Ok(AFImportDatabaseCsvResult {
+      row_ids: vec![],
+      created_field_ids: vec![],
+      errors,
+    });
+  }
+
+  let mut created_field_ids = vec![];
+  let mut field_id_by_column: HashMap<usize, String> = HashMap::new();
+  for (column, target) in targets.iter().enumerate() {
+    match target {
+      ColumnTarget::Field(field) => {
+        field_id_by_column.insert(column, field.id.clone());
+      },
+      ColumnTarget::NewField { name, field_type } => {
+        let field_id = add_database_field(
+          uid,
+          collab_storage,
+          pg_pool,
+          workspace_uuid_str,
+          database_uuid_str,
+          AFInsertDatabaseField {
+            name: name.clone(),
+            field_type: *field_type,
+            ..Default::default()
+          },
+        )
+        .await?;
+        field_id_by_column.insert(column, field_id.clone());
+        created_field_ids.push(field_id);
+      },
+      ColumnTarget::Skip => {},
+    }
+  }
+
+  let all_fields = if created_field_ids.is_empty() {
+    all_fields
+  } else {
+    // the type options of the new fields are needed to write their cells
+    let (db_collab, db_body) =
+      get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+    let txn = db_collab.transact();
+    db_body.fields.get_all_fields(&txn)
+  };
+  let type_option_writer_by_id = type_option_writer_by_id(&all_fields);
+  let cells_by_row = imported_rows
+    .into_iter()
+    .map(|values| {
+      let mut cells: HashMap<String, Cell> = HashMap::new();
+      for (column, value) in values {
+        let Some(field_id) = field_id_by_column.get(&column) else {
+          continue;
+        };
+        match value {
+          ImportedValue::Cell(cell) => {
+            cells.insert(field_id.clone(), cell);
+          },
+          ImportedValue::Json(json_value) => {
+            if let Some(cell_writer) = type_option_writer_by_id.get(field_id) {
+              cells.insert(
+                field_id.clone(),
+                cell_writer.convert_json_to_cell(json_value),
+              );
+            }
+          },
+        }
+      }
+      cells
+    })
+    .collect();
+
+  let row_ids = insert_database_rows(
+    collab_storage,
+    pg_pool,
+    workspace_uuid_str,
+    database_uuid_str,
+    uid,
+    cells_by_row,
+  )
+  .await?;
+  errors.sort_by_key(|error| error.line);
+  Ok(AFImportDatabaseCsvResult {
+    row_ids,
+    created_field_ids,
+    errors,
+  })
+}
+
+fn resolve_columns(
+  headers: &[String],
+  all_fields: &[Field],
+  params: &AFImportDatabaseCsv,
+) -> Result<Vec<ColumnTarget>, AppError> {
+  let find_field = |id_or_name: &str| {
+    all_fields
+      .iter()
+      .find(|field| field.id == id_or_name)
+      .or_else(|| all_fields.iter().find(|field| field.name == id_or_name))
+  };
+
+  let mut column_by_field_id: HashMap<String, &str> = HashMap::new();
+  let mut targets = Vec::with_capacity(headers.len());
+  for header in headers {
+    let field = match params.column_fields.get(header) {
+      Some(id_or_name) => Some(find_field(id_or_name).ok_or_else(|| {
+        AppError::InvalidRequest(format!(
+          "Field {} of column {} not found",
+          id_or_name, header
+        ))
+      })?),
+      None => find_field(header),
+    };
+    let target = match field {
+      Some(field) => {
+        let field_type = FieldType::from(field.field_type);
+        if let Some(other) = column_by_field_id.insert(field.id.clone(), header) {
+          return Err(AppError::InvalidRequest(format!(
+            "Columns {} and {} are both imported into field {}",
+            other, header, field.name
+          )));
+        }
+        if is_read_only(&field_type) {
+          ColumnTarget::Skip
+        } else {
+          ColumnTarget::Field(field.clone())
+        }
+      },
+      None if params.create_fields && !header.is_empty() => {
+        let field_type = params
+          .field_types
+          .get(header)
+          .map(|field_type| FieldType::from(*field_type))
+          .unwrap_or(FieldType::RichText);
+        if field_type == FieldType::Relation || is_read_only(&field_type) {
+          return Err(AppError::InvalidRequest(format!(
+            "A field of type {:?} can't be created for column {}",
+            field_type, header
+          )));
+        }
+        ColumnTarget::NewField {
+          name: header.clone(),
+          field_type: field_type.into(),
+        }
+      },
+      None => ColumnTarget::Skip,
+    };
+    targets.push(target);
+  }
+  Ok(targets)
+}
+
+/// Fields whose cells are computed from the row.
+fn is_read_only(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::CreatedTime | FieldType::LastEditedTime
+  )
+}
+
+/// Converts a value of the CSV to the json value of a cell. Empty values are skipped.
+fn csv_value_to_json(field_type: &FieldType, value: &str) -> Result<Option<Value>, String> {
+  if value.is_empty() {
+    return Ok(None);
+  }
+  let json_value = match field_type {
+    FieldType::Number => {
+      let number = value
+        .replace(',', "")
+        .parse::<f64>()
+        .map_err(|_| format!("{} is not a number", value))?;
+      serde_json::json!(number)
+    },
+    FieldType::DateTime => {
+      Value::from(parse_timestamp(value).ok_or_else(|| format!("{} is not a date", value))?)
+    },
+    FieldType::Checkbox => Value::Bool(
+      parse_checkbox(value).ok_or_else(|| format!("{} is not a checkbox value", value))?,
+    ),
+    FieldType::MultiSelect | FieldType::Media => Value::Array(
+      value
+        .split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(|option| Value::String(option.to_string()))
+        .collect(),
+    ),
+    _ => Value::String(value.to_string()),
+  };
+  Ok(Some(json_value))
+}
+
+/// Parses a timestamp in seconds, a RFC 3339 date time, or a date time or date in UTC.
+pub(super) fn parse_timestamp(value: &str) -> Option<i64> {
+  if let Ok(timestamp) = value.parse::<i64>() {
+    return Some(timestamp);
+  }
+  if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+    return Some(date_time.timestamp());
+  }
+  for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(value, format) {
+      return Some(date_time.and_utc().timestamp());
+    }
+  }
+  for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+    if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+      return Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp());
+    }
+  }
+  None
+}
+
+pub(super) fn parse_checkbox(value: &str) -> Option<bool> {
+  match value.to_lowercase().as_str() {
+    "true" | "yes" | "1" | "checked" | "x" => Some(true),
+    "false" | "no" | "0" | "unchecked" => Some(false),
+    _ => None,
+  }
+}
+
+/// Returns the rows of the view as a CSV, with its visible fields in their order. Relation cells
+/// are exported as the ids of the related rows, so that they can be imported again.
+pub async fn export_database_csv(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  view_id: Option<String>,
+) -> Result<String, AppError> {
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+  let (all_fields, view) = {
+    let txn = db_collab.transact();
+    let view_id = view_id.unwrap_or_else(|| db_body.get_inline_view_id(&txn));
+    let view = db_body.views.get_view(&txn, &view_id).ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "view {} not found in database {}",
+        view_id, database_uuid_str
+      ))
+    })?;
+    (db_body.fields.get_all_fields(&txn), view)
+  };
+
+  let exported_fields: Vec<Field> = view
+    .field_orders
+    .iter()
+    .filter_map(|field_order| all_fields.iter().find(|field| field.id == field_order.id))
+    .filter(|field| {
+      let visibility = view
+        .field_settings
+        .get(&field.id)
+        .and_then(|settings| settings.get(FIELD_VISIBILITY));
+      !matches!(visibility, Some(Any::BigInt(visibility)) if *visibility == FIELD_ALWAYS_HIDDEN)
+    })
+    .cloned()
+    .collect();
+  let type_option_reader_by_id = type_option_reader_by_id(&exported_fields);
+  let field_by_id = field_by_id_name_uniq(exported_fields.clone());
+  let headers: Vec<&str> = exported_fields
+    .iter()
+    .filter_map(|field| field_by_id.get(&field.id))
+    .map(|field| field.name.as_str())
+    .collect();
+
+  let row_ids: Vec<String> = view
+    .row_orders
+    .iter()
+    .map(|row_order| row_order.id.to_string())
+    .collect();
+  let rows = get_row_details(collab_storage, uid, workspace_uuid_str, &row_ids).await;
+
+  let mut writer = csv::Writer::from_writer(vec![]);
+  writer
+    .write_record(&headers)
+    .map_err(|err| AppError::Internal(err.into()))?;
+  for row in rows {
+    let mut cells = get_row_details_serde(row, &field_by_id, &type_option_reader_by_id);
+    let record: Vec<String> = headers
+      .iter()
+      .map(|name| {
+        cells
+          .remove(*name)
+          .and_then(|mut cell| cell.remove(CELL_DATA))
+          .map(json_to_csv_value)
+          .map(escape_csv_formula)
+          .unwrap_or_default()
+      })
+      .collect();
+    writer
+      .write_record(&record)
+      .map_err(|err| AppError::Internal(err.into()))?;
+  }
+  let bytes = writer
+    .into_inner()
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("Failed to write CSV: {}", err)))?;
+  String::from_utf8(bytes).map_err(|err| AppError::Internal(err.into()))
+}
+
+/// Converts the json value of a cell, as returned by the type option readers, to a value of the
+/// CSV which can be imported again.
+fn json_to_csv_value(value: Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::Bool(true) => "Yes".to_string(),
+    Value::Bool(false) => "No".to_string(),
+    Value::Number(number) => number.to_string(),
+    Value::String(s) => s,
+    Value::Array(values) => values
+      .into_iter()
+      .map(json_to_csv_value)
+      .filter(|value| !value.is_empty())
+      .collect::<Vec<_>>()
+      .join(", "),
+    Value::Object(mut object) => match object.get("timestamp").and_then(Value::as_i64) {
+      Some(timestamp) => DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|date_time| date_time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default(),
+      // related rows, media files, checklists and their items
+      None => match ["row_id", "url", "items", "name"]
+        .iter()
+        .find_map(|key| object.remove(*key))
+      {
+        Some(value) => json_to_csv_value(value),
+        None => Value::Object(object).to_string(),
+      },
+    },
+  }
+}
+
+/// Characters starting the values spreadsheets evaluate as formulas.
+const CSV_FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Prefixes a value which a spreadsheet would evaluate as a formula with `'`, so that it's
+/// displayed as text instead.
+fn escape_csv_formula(value: String) -> String {
+  if value.starts_with(CSV_FORMULA_PREFIXES) {
+    format!("'{}", value)
+  } else {
+    value
+  }
+}
+
+/// Reverts [escape_csv_formula], so that an exported CSV can be imported again.
+fn unescape_csv_formula(value: &str) -> &str {
+  match value.strip_prefix('\'') {
+    Some(rest) if rest.starts_with(CSV_FORMULA_PREFIXES) => rest,
+    _ => value,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn csv_value_to_json_test() {
+    assert_eq!(
+      csv_value_to_json(&FieldType::Number, "1,234.5").unwrap(),
+      Some(serde_json::json!(1234.5))
+    );
+    assert!(csv_value_to_json(&FieldType::Number, "twelve").is_err());
+    assert_eq!(
+      csv_value_to_json(&FieldType::DateTime, "2024-12-03").unwrap(),
+      Some(serde_json::json!(1733184000))
+    );
+    assert_eq!(
+      csv_value_to_json(&FieldType::DateTime, "2024-12-03T07:17:01Z").unwrap(),
+      Some(serde_json::json!(1733210221))
+    );
+    assert!(csv_value_to_json(&FieldType::DateTime, "tomorrow").is_err());
+    assert_eq!(
+      csv_value_to_json(&FieldType::Checkbox, "Yes").unwrap(),
+      Some(Value::Bool(true))
+    );
+    assert!(csv_value_to_json(&FieldType::Checkbox, "maybe").is_err());
+    assert_eq!(
+      csv_value_to_json(&FieldType::MultiSelect, "social, news,").unwrap(),
+      Some(serde_json::json!(["social", "news"]))
+    );
+    assert_eq!(csv_value_to_json(&FieldType::RichText, "").unwrap(), None);
+  }
+
+  #[test]
+  fn json_to_csv_value_round_trip_test() {
+    let date = json_to_csv_value(serde_json::json!({ "timestamp": 1733210221 }));
+    assert_eq!(date, "2024-12-03T07:17:01Z");
+    assert_eq!(parse_timestamp(&date), Some(1733210221));
+    assert_eq!(
+      json_to_csv_value(serde_json::json!(["social", "news"])),
+      "social, news"
+    );
+    assert_eq!(
+      json_to_csv_value(serde_json::json!([
+        { "row_id": "row_1", "primary_value": "Task 1" },
+        { "row_id": "row_2", "primary_value": null },
+      ])),
+      "row_1, row_2"
+    );
+    assert_eq!(
+      json_to_csv_value(serde_json::json!({
+        "items": [{ "id": "1", "name": "a", "checked": true }, { "id": "2", "name": "b" }],
+        "percentage": 0.5,
+      })),
+      "a, b"
+    );
+    let checked = json_to_csv_value(Value::Bool(true));
+    assert_eq!(parse_checkbox(&checked), Some(true));
+  }
+
+  #[test]
+  fn csv_formula_escape_test() {
+    for value in ["=SUM(A1:A2)", "+1", "-2", "@cmd", "\tx", "\rx"] {
+      let escaped = escape_csv_formula(value.to_string());
+      assert_eq!(escaped, format!("'{}", value));
+      assert_eq!(unescape_csv_formula(&escaped), value);
+    }
+    assert_eq!(escape_csv_formula("Task A".to_string()), "Task A");
+    assert_eq!(escape_csv_formula("1.5".to_string()), "1.5");
+    assert_eq!(unescape_csv_formula("'quoted'"), "'quoted'");
+  }
+}
@@ -0,0 +1,673 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use database::publish::{
+  select_published_collab_with_publisher, select_published_data_for_view_id,
+};
+use database_entity::dto::{PublishCollabItem, PublishCollabMetadata};
+use governor::clock::DefaultClock;
+use governor::state::keyed::DefaultKeyedStateStore;
+use governor::{Quota, RateLimiter};
+use infra::net_util::IpRange;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use serde_json::Value;
+use shared_entity::dto::publish_dto::{
+  PublishDatabaseFormMetaData, PublishViewInfo, PublishViewMetaData,
+};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseForm, AFDatabaseFormField, AFPublishDatabaseForm, AFSubmitDatabaseForm, ViewLayout,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::database_csv::{parse_checkbox, parse_timestamp};
+use super::database_query::select_option_ids;
+use super::ops::{insert_database_row, to_af_database_field};
+use super::utils::get_database_body;
+use crate::biz::workspace::publish::PublishedCollabStore;
+use crate::config::config::{Config, Environment};
+
+/// Token accepted in the local environment when no CAPTCHA service is configured.
+pub const LOCAL_CAPTCHA_TOKEN: &str = "local-captcha-token";
+
+/// Submissions of a form allowed per minute from the same address.
+const SUBMISSIONS_PER_VISITOR_PER_MINUTE: u32 = 5;
+/// Submissions of a form allowed per minute from all addresses.
+const SUBMISSIONS_PER_FORM_PER_MINUTE: u32 = 60;
+/// Number of rate limiter entries above which the ones which are back to full capacity are dropped.
+const MAX_RATE_LIMITER_KEYS: usize = 10_000;
+const MAX_FORM_TEXT_LENGTH: usize = 10_000;
+/// Uid of the rows submitted through forms. Submissions are anonymous, so they are made on behalf
+/// of the server rather than of the publisher of the form, like the other changes of the server.
+const FORM_SUBMISSION_UID: i64 = 0;
+
+type KeyedRateLimiter<K> = RateLimiter<K, DefaultKeyedStateStore<K>, DefaultClock>;
+
+/// Limits the submissions of published forms, per visitor address and per form.
+#[derive(Clone)]
+pub struct FormSubmissionLimiter {
+  by_visitor: Arc<KeyedRateLimiter<(String, Uuid)>>,
+  by_form: Arc<KeyedRateLimiter<Uuid>>,
+}
+
+impl Default for FormSubmissionLimiter {
+  fn default() -> Self {
+    Self::new(
+      SUBMISSIONS_PER_VISITOR_PER_MINUTE,
+      SUBMISSIONS_PER_FORM_PER_MINUTE,
+    )
+  }
+}
+
+impl FormSubmissionLimiter {
+  pub fn new(per_visitor_per_minute: u32, per_form_per_minute: u32) -> Self {
+    let quota = |per_minute: u32| Quota::per_minute(NonZeroU32::new(per_minute.max(1)).unwrap());
+    Self {
+      by_visitor: Arc::new(RateLimiter::keyed(quota(per_visitor_per_minute))),
+      by_form: Arc::new(RateLimiter::keyed(quota(per_form_per_minute))),
+    }
+  }
+
+  fn check(&self, form_view_id: &Uuid, remote_ip: Option<&str>) -> Result<(), AppError> {
+    if self.by_visitor.len() > MAX_RATE_LIMITER_KEYS {
+      self.by_visitor.retain_recent();
+    }
+    if self.by_form.len() > MAX_RATE_LIMITER_KEYS {
+      self.by_form.retain_recent();
+    }
+
+    let visitor = remote_ip.unwrap_or_default().to_string();
+    if self
+      .by_visitor
+      .check_key(&(visitor, *form_view_id))
+      .is_err()
+    {
+      return Err(AppError::TooManyRequests(
+        "Too many submissions of this form, try again later".to_string(),
+      ));
+    }
+    if self.by_form.check_key(form_view_id).is_err() {
+      return Err(AppError::TooManyRequests(
+        "This form receives too many submissions, try again later".to_string(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// Verifies the CAPTCHA tokens of form submissions.
+#[derive(Clone)]
+pub enum CaptchaVerifier {
+  /// Calls a siteverify endpoint compatible with hCaptcha or Turnstile.
+  Remote {
+    client: reqwest::Client,
+    verify_url: String,
+    secret: Secret<String>,
+  },
+  /// Stub used in the local environment when no CAPTCHA service is configured. Only
+  /// [LOCAL_CAPTCHA_TOKEN] is accepted.
+  Local,
+  /// No CAPTCHA service is configured, forms can't require a CAPTCHA.
+  Unavailable,
+}
+
+#[derive(Deserialize)]
+struct CaptchaVerifyResponse {
+  success: bool,
+}
+
+impl CaptchaVerifier {
+  pub fn from_config(config: &Config) -> Self {
+    match (&config.captcha.verify_url, &config.app_env) {
+      (Some(verify_url), _) => Self::Remote {
+        client: reqwest::Client::new(),
+        verify_url: verify_url.clone(),
+        secret: config.captcha.secret.clone(),
+      },
+      (None, Environment::Local) => Self::Local,
+      (None, Environment::Production) => Self::Unavailable,
+    }
+  }
+
+  pub fn is_available(&self) -> bool {
+    !matches!(self, Self::Unavailable)
+  }
+
+  pub async fn verify(&self, token: Option<&str>, remote_ip: Option<&str>) -> Result<(), AppError> {
+    let token = match token {
+      Some(token) if !token.is_empty() => token,
+      _ => {
+        return Err(AppError::InvalidRequest(
+          "This form requires a CAPTCHA".to_string(),
+        ))
+      },
+    };
+    let success = match self {
+      Self::Remote {
+        client,
+        verify_url,
+        secret,
+      } => {
+        let mut form = vec![
+          ("secret", secret.expose_secret().as_str()),
+          ("response", token),
+        ];
+        if let Some(remote_ip) = remote_ip {
+          form.push(("remoteip", remote_ip));
+        }
+        let resp = client
+          .post(verify_url)
+          .form(&form)
+          .send()
+          .await?
+          .error_for_status()?;
+        resp.json::<CaptchaVerifyResponse>().await?.success
+      },
+      Self::Local => token == LOCAL_CAPTCHA_TOKEN,
+      Self::Unavailable => {
+        return Err(AppError::ServiceTemporaryUnavailable(
+          "CAPTCHA verification is not configured".to_string(),
+        ))
+      },
+    };
+    if !success {
+      return Err(AppError::InvalidRequest(
+        "CAPTCHA verification failed".to_string(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// A row added through a published form.
+pub struct FormSubmission {
+  pub workspace_id: String,
+  pub database_id: String,
+  pub row_id: String,
+}
+
+/// Fields whose cells visitors can fill through a form.
+fn is_form_field_type(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::RichText
+      | FieldType::Number
+      | FieldType::DateTime
+      | FieldType::SingleSelect
+      | FieldType::MultiSelect
+      | FieldType::Checkbox
+      | FieldType::URL
+  )
+}
+
+/// Publishes a form of the database under the publish namespace of the workspace. The form can be
+/// unpublished like any other published view.
+#[allow(clippy::too_many_arguments)]
+pub async fn publish_database_form(
+  published_collab_store: &dyn PublishedCollabStore,
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  captcha_verifier: &CaptchaVerifier,
+  user_uuid: &Uuid,
+  workspace_id: &str,
+  database_id: &str,
+  params: AFPublishDatabaseForm,
+) -> Result<AFDatabaseForm, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  if params.title.trim().is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The title of a form can't be empty".to_string(),
+    ));
+  }
+  if params.fields.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "A form needs at least one field".to_string(),
+    ));
+  }
+  if params.captcha_required && !captcha_verifier.is_available() {
+    return Err(AppError::InvalidRequest(
+      "CAPTCHA verification is not configured on this server".to_string(),
+    ));
+  }
+  if let Some((metadata, _)) = select_published_data_for_view_id(pg_pool, &params.view_id).await? {
+    if metadata.get("form").is_none() {
+      return Err(AppError::InvalidRequest(format!(
+        "View {} is already published and is not a form",
+        params.view_id
+      )));
+    }
+  }
+
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let all_fields = db_body.fields.get_all_fields(&db_collab.transact());
+  let mut form_field_ids = HashSet::with_capacity(params.fields.len());
+  let mut form_fields = Vec::with_capacity(params.fields.len());
+  for field_params in params.fields {
+    let field = all_fields
+      .iter()
+      .find(|field| field.id == field_params.field)
+      .or_else(|| {
+        all_fields
+          .iter()
+          .find(|field| field.name == field_params.field)
+      })
+      .ok_or_else(|| {
+        AppError::InvalidRequest(format!("Field not found: {}", field_params.field))
+      })?;
+    let field_type = FieldType::from(field.field_type);
+    if !is_form_field_type(&field_type) {
+      return Err(AppError::InvalidRequest(format!(
+        "{:?} field {} can't be filled through a form",
+        field_type, field.name
+      )));
+    }
+    if !form_field_ids.insert(field.id.clone()) {
+      return Err(AppError::InvalidRequest(format!(
+        "Field {} is in the form more than once",
+        field.name
+      )));
+    }
+    form_fields.push(AFDatabaseFormField {
+      field: to_af_database_field(field.clone()),
+      required: field_params.required,
+    });
+  }
+
+  let form = AFDatabaseForm {
+    view_id: params.view_id,
+    database_id: database_id.to_string(),
+    title: params.title,
+    description: params.description,
+    fields: form_fields,
+    captcha_required: params.captcha_required,
+  };
+  let now = Utc::now().timestamp();
+  let metadata = PublishDatabaseFormMetaData {
+    meta: PublishViewMetaData {
+      view: PublishViewInfo {
+        view_id: form.view_id.to_string(),
+        name: form.title.clone(),
+        layout: ViewLayout::Grid,
+        last_edited_time: now,
+        created_at: now,
+        ..Default::default()
+      },
+      ..Default::default()
+    },
+    form: form.clone(),
+  };
+  let publish_item = PublishCollabItem {
+    meta: PublishCollabMetadata {
+      view_id: form.view_id,
+      publish_name: params.publish_name,
+      metadata: serde_json::to_value(&metadata)?,
+    },
+    data: serde_json::to_vec(&form)?,
+  };
+  published_collab_store
+    .publish_collabs(vec![publish_item], &workspace_uuid, user_uuid)
+    .await?;
+  Ok(form)
+}
+
+/// Adds a row to the database of a published form. Visitors don't need access to the workspace, so
+/// the row is added on behalf of the user who published the form.
+#[allow(clippy::too_many_arguments)]
+pub async fn submit_database_form(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  captcha_verifier: &CaptchaVerifier,
+  limiter: &FormSubmissionLimiter,
+  publish_namespace: &str,
+  publish_name: &str,
+  remote_ip: Option<&str>,
+  params: AFSubmitDatabaseForm,
+) -> Result<FormSubmission, AppError> {
+  let form_not_found = || {
+    AppError::RecordNotFound(format!(
+      "Form {} is not published in {}",
+      publish_name, publish_namespace
+    ))
+  };
+  let published = select_published_collab_with_publisher(pg_pool, publish_namespace, publish_name)
+    .await?
+    .ok_or_else(form_not_found)?;
+  let form = serde_json::from_value::<PublishDatabaseFormMetaData>(published.metadata)
+    .map_err(|_| form_not_found())?
+    .form;
+
+  limiter.check(&published.view_id, remote_ip)?;
+  if form.captcha_required {
+    captcha_verifier
+      .verify(params.captcha_token.as_deref(), remote_ip)
+      .await?;
+  }
+
+  let workspace_id = published.workspace_id.to_string();
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, &workspace_id, &form.database_id).await?;
+  let all_fields = db_body.fields.get_all_fields(&db_collab.transact());
+  let cells = form_cells(&form, &all_fields, params.cells)?;
+  let row_id = insert_database_row(
+    collab_storage,
+    pg_pool,
+    &workspace_id,
+    &form.database_id,
+    FORM_SUBMISSION_UID,
+    cells,
+  )
+  .await?;
+  Ok(FormSubmission {
+    workspace_id,
+    database_id: form.database_id,
+    row_id,
+  })
+}
+
+/// Validates the values submitted through a form against the fields of the database, returning the
+/// json values of the cells of the new row keyed by field id. Fields removed from the database
+/// since the form was published are ignored.
+fn form_cells(
+  form: &AFDatabaseForm,
+  all_fields: &[Field],
+  mut submitted: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>, AppError> {
+  let mut cells = HashMap::with_capacity(form.fields.len());
+  for form_field in &form.fields {
+    let value = submitted
+      .remove(&form_field.field.id)
+      .or_else(|| submitted.remove(&form_field.field.name));
+    let field = match all_fields
+      .iter()
+      .find(|field| field.id == form_field.field.id)
+    {
+      Some(field) => field,
+      None => {
+        warn!(
+          "field {} of form {} is not in database {}",
+          form_field.field.id, form.view_id, form.database_id
+        );
+        continue;
+      },
+    };
+    let value = form_value(field, value)
+      .map_err(|err| AppError::InvalidRequest(format!("{}: {}", field.name, err)))?;
+    match value {
+      Some(value) => {
+        cells.insert(field.id.clone(), value);
+      },
+      None if form_field.required => {
+        return Err(AppError::InvalidRequest(format!(
+          "{} is required",
+          field.name
+        )));
+      },
+      None => {},
+    }
+  }
+  if let Some(key) = submitted.keys().next() {
+    return Err(AppError::InvalidRequest(format!(
+      "{} is not a field of the form",
+      key
+    )));
+  }
+  Ok(cells)
+}
+
+/// Converts a submitted value to the json value of a cell, or `None` when it's empty.
+fn form_value(field: &Field, value: Option<Value>) -> Result<Option<Value>, String> {
+  let value = match value {
+    None | Some(Value::Null) => return Ok(None),
+    Some(Value::String(text)) if text.trim().is_empty() => return Ok(None),
+    Some(Value::Array(items)) if items.is_empty() => return Ok(None),
+    Some(value) => value,
+  };
+  let field_type = FieldType::from(field.field_type);
+  let value = match (&field_type, value) {
+    (FieldType::RichText | FieldType::URL, Value::String(text)) => {
+      if text.chars().count() > MAX_FORM_TEXT_LENGTH {
+        return Err(format!(
+          "must be at most {} characters long",
+          MAX_FORM_TEXT_LENGTH
+        ));
+      }
+      Value::String(text)
+    },
+    (FieldType::Number, Value::Number(number)) => Value::Number(number),
+    (FieldType::Number, Value::String(text)) => {
+      let number = text
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("{} is not a number", text))?;
+      if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+      } else {
+        serde_json::json!(number)
+      }
+    },
+    (FieldType::DateTime, Value::Number(number)) => Value::from(
+      number
+        .as_i64()
+        .ok_or_else(|| format!("{} is not a date", number))?,
+    ),
+    (FieldType::DateTime, Value::String(text)) => {
+      Value::from(parse_timestamp(text.trim()).ok_or_else(|| format!("{} is not a date", text))?)
+    },
+    // a required checkbox has to be checked, ie. to accept terms
+    (FieldType::Checkbox, Value::Bool(checked)) => return Ok(checked.then_some(Value::Bool(true))),
+    (FieldType::Checkbox, Value::String(text)) => {
+      let checked =
+        parse_checkbox(text.trim()).ok_or_else(|| format!("{} is not a checkbox value", text))?;
+      return Ok(checked.then_some(Value::Bool(true)));
+    },
+    (FieldType::SingleSelect, Value::String(option)) => {
+      select_option_ids(field, &[option.clone()]).map_err(|err| err.to_string())?;
+      Value::String(option)
+    },
+    (FieldType::MultiSelect, Value::Array(options)) => {
+      let options = options
+        .into_iter()
+        .map(|option| match option {
+          Value::String(option) => Ok(option),
+          other => Err(format!("{} is not an option", other)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      select_option_ids(field, &options).map_err(|err| err.to_string())?;
+      Value::Array(options.into_iter().map(Value::String).collect())
+    },
+    (FieldType::MultiSelect, Value::String(options)) => {
+      let options: Vec<String> = options
+        .split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(str::to_string)
+        .collect();
+      select_option_ids(field, &options).map_err(|err| err.to_string())?;
+      Value::Array(options.into_iter().map(Value::String).collect())
+    },
+    (field_type, value) if is_form_field_type(field_type) => {
+      return Err(format!("{} is not a valid {:?} value", value, field_type))
+    },
+    (field_type, _) => {
+      return Err(format!(
+        "{:?} fields can't be filled through a form",
+        field_type
+      ))
+    },
+  };
+  Ok(Some(value))
+}
+
+/// Returns the address of the visitor. The `X-Real-IP` header set by the proxy is only used when
+/// the request comes from one of the trusted proxies, since visitors may set it themselves.
+pub fn visitor_ip(
+  peer_addr: Option<SocketAddr>,
+  real_ip: Option<&str>,
+  trusted_proxies: &[IpRange],
+) -> Option<String> {
+  let peer_ip = peer_addr?.ip();
+  if trusted_proxies.iter().any(|proxy| proxy.contains(peer_ip)) {
+    if let Some(real_ip) = real_ip.and_then(|real_ip| real_ip.trim().parse::<IpAddr>().ok()) {
+      return Some(real_ip.to_string());
+    }
+  }
+  Some(peer_ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use collab_database::fields::select_type_option::{
+    SelectOption, SelectOptionColor, SingleSelectTypeOption,
+  };
+  use infra::net_util::parse_ip_ranges;
+  use serde_json::json;
+
+  use super::*;
+
+  fn field(id: &str, field_type: FieldType) -> Field {
+    Field {
+      id: id.to_string(),
+      name: id.to_string(),
+      field_type: field_type.into(),
+      ..Default::default()
+    }
+  }
+
+  fn form(fields: &[(&Field, bool)]) -> AFDatabaseForm {
+    AFDatabaseForm {
+      view_id: Uuid::new_v4(),
+      database_id: "db".to_string(),
+      title: "Feedback".to_string(),
+      description: String::new(),
+      fields: fields
+        .iter()
+        .map(|(field, required)| AFDatabaseFormField {
+          field: to_af_database_field((*field).clone()),
+          required: *required,
+        })
+        .collect(),
+      captcha_required: false,
+    }
+  }
+
+  #[test]
+  fn form_cells_test() {
+    let name = field("Name", FieldType::RichText);
+    let age = field("Age", FieldType::Number);
+    let terms = field("Terms", FieldType::Checkbox);
+    let form = form(&[(&name, true), (&age, false), (&terms, true)]);
+    let fields = vec![name, age, terms];
+
+    let cells = form_cells(
+      &form,
+      &fields,
+      HashMap::from([
+        ("Name".to_string(), json!("Lucas")),
+        ("Age".to_string(), json!("42")),
+        ("Terms".to_string(), json!("yes")),
+      ]),
+    )
+    .unwrap();
+    assert_eq!(cells["Name"], json!("Lucas"));
+    assert_eq!(cells["Age"], json!(42));
+    assert_eq!(cells["Terms"], json!(true));
+
+    // required fields must be filled, and checkboxes checked
+    for (key, value) in [("Name", json!(" ")), ("Terms", json!(false))] {
+      let mut submitted = HashMap::from([
+        ("Name".to_string(), json!("Lucas")),
+        ("Terms".to_string(), json!(true)),
+      ]);
+      submitted.insert(key.to_string(), value);
+      assert!(form_cells(&form, &fields, submitted).is_err());
+    }
+    // values must match the type of the field
+    let submitted = HashMap::from([
+      ("Name".to_string(), json!("Lucas")),
+      ("Terms".to_string(), json!(true)),
+      ("Age".to_string(), json!("forty")),
+    ]);
+    assert!(form_cells(&form, &fields, submitted).is_err());
+    // fields which are not in the form are rejected
+    let submitted = HashMap::from([
+      ("Name".to_string(), json!("Lucas")),
+      ("Terms".to_string(), json!(true)),
+      ("Status".to_string(), json!("Done")),
+    ]);
+    assert!(form_cells(&form, &fields, submitted).is_err());
+  }
+
+  #[test]
+  fn form_select_value_test() {
+    let mut status = field("Status", FieldType::SingleSelect);
+    let mut type_option = SingleSelectTypeOption::default();
+    type_option
+      .options
+      .push(SelectOption::with_color("Done", SelectOptionColor::Purple));
+    status
+      .type_options
+      .insert(FieldType::SingleSelect.to_string(), type_option.into());
+
+    assert_eq!(
+      form_value(&status, Some(json!("done"))).unwrap(),
+      Some(json!("done"))
+    );
+    assert!(form_value(&status, Some(json!("Unknown"))).is_err());
+    assert!(form_value(&status, Some(json!(1))).is_err());
+    assert_eq!(form_value(&status, Some(json!(""))).unwrap(), None);
+  }
+
+  #[test]
+  fn visitor_ip_test() {
+    let proxy = Some("10.0.0.2:40000".parse().unwrap());
+    let visitor = Some("203.0.113.7:50000".parse().unwrap());
+    let trusted_proxies = parse_ip_ranges("10.0.0.0/8").unwrap();
+    assert_eq!(
+      visitor_ip(proxy, Some("198.51.100.1"), &trusted_proxies),
+      Some("198.51.100.1".to_string())
+    );
+    assert_eq!(
+      visitor_ip(proxy, None, &trusted_proxies),
+      Some("10.0.0.2".to_string())
+    );
+    // the header of the requests which don't come from a trusted proxy is ignored
+    assert_eq!(
+      visitor_ip(visitor, Some("198.51.100.1"), &trusted_proxies),
+      Some("203.0.113.7".to_string())
+    );
+    assert_eq!(
+      visitor_ip(proxy, Some("198.51.100.1"), &[]),
+      Some("10.0.0.2".to_string())
+    );
+    assert_eq!(
+      visitor_ip(proxy, Some("not an ip"), &trusted_proxies),
+      Some("10.0.0.2".to_string())
+    );
+    assert_eq!(
+      visitor_ip(None, Some("198.51.100.1"), &trusted_proxies),
+      None
+    );
+  }
+
+  #[test]
+  fn form_submission_limiter_test() {
+    let limiter = FormSubmissionLimiter::new(2, 3);
+    let form = Uuid::new_v4();
+    assert!(limiter.check(&form, Some("10.0.0.1")).is_ok());
+    assert!(limiter.check(&form, Some("10.0.0.1")).is_ok());
+    assert!(limiter.check(&form, Some("10.0.0.1")).is_err());
+    assert!(limiter.check(&form, Some("10.0.0.2")).is_ok());
+    // the form itself is limited, whatever the address
+    assert!(limiter.check(&form, Some("10.0.0.3")).is_err());
+    assert!(limiter.check(&Uuid::new_v4(), Some("10.0.0.3")).is_ok());
+  }
+}
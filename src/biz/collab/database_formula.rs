@@ -0,0 +1,869 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use collab_database::entity::FieldType;
+use serde_json::Value;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+/// Longest formula which can be parsed, in characters.
+const MAX_FORMULA_LEN: usize = 1000;
+/// Deepest nesting of the expressions of a formula, so that parsing and evaluating it can't
+/// overflow the stack.
+const MAX_FORMULA_DEPTH: usize = 64;
+
+/// Value of a field referenced by a formula, or of the result of a formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaValue {
+  Empty,
+  Number(f64),
+  Text(String),
+  Bool(bool),
+  /// Timestamp in seconds.
+  Date(i64),
+}
+
+impl FormulaValue {
+  /// Converts the json value of a cell, as returned by the type option readers.
+  pub fn from_cell_json(field_type: &FieldType, value: &Value) -> Self {
+    match (field_type, value) {
+      (_, Value::Null) => FormulaValue::Empty,
+      (FieldType::Number, value) => {
+        // formatted numbers, ie. currencies, keep their digits
+        let text: String = json_text(value)
+          .chars()
+          .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+          .collect();
+        text
+          .parse()
+          .map(FormulaValue::Number)
+          .unwrap_or(FormulaValue::Empty)
+      },
+      (
+        FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime,
+        Value::Object(map),
+      ) => {
+        let timestamp = match map.get("timestamp") {
+          Some(Value::Number(number)) => number.as_i64(),
+          Some(Value::String(text)) => text.parse().ok(),
+          _ => None,
+        };
+        timestamp
+          .map(FormulaValue::Date)
+          .unwrap_or(FormulaValue::Empty)
+      },
+      (FieldType::Checkbox, Value::Bool(checked)) => FormulaValue::Bool(*checked),
+      (FieldType::Checkbox, value) => FormulaValue::Bool(matches!(
+        json_text(value).to_lowercase().as_str(),
+        "yes" | "true" | "1"
+      )),
+      (_, Value::Number(number)) => number
+        .as_f64()
+        .map(FormulaValue::Number)
+        .unwrap_or(FormulaValue::Empty),
+      (_, value) => match json_text(value) {
+        text if text.is_empty() => FormulaValue::Empty,
+        text => FormulaValue::Text(text),
+      },
+    }
+  }
+
+  pub fn as_number(&self) -> Option<f64> {
+    match self {
+      FormulaValue::Empty => Some(0.0),
+      FormulaValue::Number(number) => Some(*number),
+      FormulaValue::Bool(checked) => Some(if *checked { 1.0 } else { 0.0 }),
+      FormulaValue::Text(text) => text.trim().parse().ok(),
+      FormulaValue::Date(timestamp) => Some(*timestamp as f64),
+    }
+  }
+
+  fn is_truthy(&self) -> bool {
+    match self {
+      FormulaValue::Empty => false,
+      FormulaValue::Number(number) => *number != 0.0,
+      FormulaValue::Text(text) => !text.is_empty(),
+      FormulaValue::Bool(checked) => *checked,
+      FormulaValue::Date(_) => true,
+    }
+  }
+
+  /// Text written to the cell of the field, empty for empty values.
+  pub fn to_text(&self) -> String {
+    match self {
+      FormulaValue::Empty => String::new(),
+      FormulaValue::Number(number) => format_number(*number),
+      FormulaValue::Text(text) => text.clone(),
+      FormulaValue::Bool(checked) => checked.to_string(),
+      FormulaValue::Date(timestamp) => DateTime::<Utc>::from_timestamp(*timestamp, 0)
+        .map(|date_time| date_time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default(),
+    }
+  }
+}
+
+fn json_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    Value::Bool(checked) => checked.to_string(),
+    Value::Number(number) => number.to_string(),
+    Value::Array(items) => items
+      .iter()
+      .map(json_text)
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join(", "),
+    // related rows, checklists and their items, media files
+    Value::Object(map) => ["primary_value", "name", "items", "row_id"]
+      .iter()
+      .find_map(|key| map.get(*key).filter(|value| !value.is_null()))
+      .map(json_text)
+      .unwrap_or_default(),
+  }
+}
+
+/// Formats integers without decimals, and other numbers with at most 10 decimals.
+pub fn format_number(number: f64) -> String {
+  if number.fract() == 0.0 && number.abs() < 1e15 {
+    format!("{}", number as i64)
+  } else {
+    let text = format!("{:.10}", number);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormulaError(pub String);
+
+impl fmt::Display for FormulaError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+/// A parsed formula. Fields are referenced by their name, e.g. `{{Price}} * {{Quantity}}`.
+///
+/// The formula language has numbers, strings in double quotes, `true` and `false`, the arithmetic
+/// operators `+ - * / %`, `&` to concatenate texts, the comparisons `== != < <= > >=`, the logical
+/// operators `&& || !`, and the functions:
+/// - `if(condition, then, else)`
+/// - `concat(a, b, ...)`, `len(text)`, `lower(text)`, `upper(text)`
+/// - `round(number[, digits])`, `abs(number)`, `min(a, b, ...)`, `max(a, b, ...)`
+/// - `now()`, `date_diff(end, start[, unit])` where unit is one of `"seconds"`, `"minutes"`,
+///   `"hours"`, `"days"` (the default) or `"weeks"`
+/// - `empty(value)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+  expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+  Literal(FormulaValue),
+  Field(String),
+  Unary(UnaryOp, Box<Expr>),
+  Binary(BinaryOp, Box<Expr>, Box<Expr>),
+  Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnaryOp {
+  Neg,
+  Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+  Concat,
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  And,
+  Or,
+}
+
+const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+  ("if", 3, Some(3)),
+  ("concat", 1, None),
+  ("len", 1, Some(1)),
+  ("lower", 1, Some(1)),
+  ("upper", 1, Some(1)),
+  ("round", 1, Some(2)),
+  ("abs", 1, Some(1)),
+  ("min", 1, None),
+  ("max", 1, None),
+  ("now", 0, Some(0)),
+  ("date_diff", 2, Some(3)),
+  ("empty", 1, Some(1)),
+];
+
+impl Formula {
+  pub fn parse(expression: &str) -> Result<Self, FormulaError> {
+    if expression.chars().count() > MAX_FORMULA_LEN {
+      return Err(FormulaError(format!(
+        "formula is longer than {} characters",
+        MAX_FORMULA_LEN
+      )));
+    }
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+      tokens,
+      pos: 0,
+      depth: 0,
+    };
+    let expr = parser.or()?;
+    match parser.peek() {
+      None => Ok(Self { expr }),
+      Some(token) => Err(FormulaError(format!("unexpected {}", token))),
+    }
+  }
+
+  /// Names of the fields referenced by the formula.
+  pub fn fields(&self) -> Vec<&str> {
+    let mut fields = vec![];
+    self.expr.fields(&mut fields);
+    fields
+  }
+
+  /// Whether the value of the formula depends on the time it's evaluated, ie. it calls `now()`.
+  pub fn is_time_dependent(&self) -> bool {
+    self.expr.is_time_dependent()
+  }
+
+  /// Evaluates the formula with the values of the fields of a row, by field name. Values which
+  /// can't be computed, ie. dividing by zero, are empty.
+  pub fn evaluate(&self, values: &HashMap<String, FormulaValue>) -> FormulaValue {
+    self.expr.evaluate(values, Utc::now().timestamp(), 0)
+  }
+}
+
+impl Expr {
+  fn is_time_dependent(&self) -> bool {
+    match self {
+      Expr::Literal(_) | Expr::Field(_) => false,
+      Expr::Unary(_, expr) => expr.is_time_dependent(),
+      Expr::Binary(_, left, right) => left.is_time_dependent() || right.is_time_dependent(),
+      Expr::Call(name, args) => name == "now" || args.iter().any(Expr::is_time_dependent),
+    }
+  }
+
+  fn fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+    match self {
+      Expr::Literal(_) => {},
+      Expr::Field(name) => {
+        if !fields.contains(&name.as_str()) {
+          fields.push(name);
+        }
+      },
+      Expr::Unary(_, expr) => expr.fields(fields),
+      Expr::Binary(_, left, right) => {
+        left.fields(fields);
+        right.fields(fields);
+      },
+      Expr::Call(_, args) => args.iter().for_each(|arg| arg.fields(fields)),
+    }
+  }
+
+  fn evaluate(
+    &self,
+    values: &HashMap<String, FormulaValue>,
+    now: i64,
+    depth: usize,
+  ) -> FormulaValue {
+    if depth > MAX_FORMULA_DEPTH {
+      return FormulaValue::Empty;
+    }
+    let depth = depth + 1;
+    match self {
+      Expr::Literal(value) => value.clone(),
+      Expr::Field(name) => values.get(name).cloned().unwrap_or(FormulaValue::Empty),
+      Expr::Unary(UnaryOp::Not, expr) => {
+        FormulaValue::Bool(!expr.evaluate(values, now, depth).is_truthy())
+      },
+      Expr::Unary(UnaryOp::Neg, expr) => {
+        number(expr.evaluate(values, now, depth).as_number().map(|n| -n))
+      },
+      Expr::Binary(BinaryOp::And, left, right) => FormulaValue::Bool(
+        left.evaluate(values, now, depth).is_truthy()
+          && right.evaluate(values, now, depth).is_truthy(),
+      ),
+      Expr::Binary(BinaryOp::Or, left, right) => FormulaValue::Bool(
+        left.evaluate(values, now, depth).is_truthy()
+          || right.evaluate(values, now, depth).is_truthy(),
+      ),
+      Expr::Binary(op, left, right) => binary(
+        *op,
+        left.evaluate(values, now, depth),
+        right.evaluate(values, now, depth),
+      ),
+      Expr::Call(name, args) => {
+        if name == "if" {
+          return if args[0].evaluate(values, now, depth).is_truthy() {
+            args[1].evaluate(values, now, depth)
+          } else {
+            args[2].evaluate(values, now, depth)
+          };
+        }
+        let args: Vec<FormulaValue> = args
+          .iter()
+          .map(|arg| arg.evaluate(values, now, depth))
+          .collect();
+        call(name, args, now)
+      },
+    }
+  }
+}
+
+fn number(number: Option<f64>) -> FormulaValue {
+  match number {
+    Some(number) if number.is_finite() => FormulaValue::Number(number),
+    _ => FormulaValue::Empty,
+  }
+}
+
+fn binary(op: BinaryOp, left: FormulaValue, right: FormulaValue) -> FormulaValue {
+  use FormulaValue::*;
+  match (op, &left, &right) {
+    (BinaryOp::Concat, _, _) => Text(left.to_text() + &right.to_text()),
+    (BinaryOp::Add, Text(_), _) | (BinaryOp::Add, _, Text(_)) => {
+      Text(left.to_text() + &right.to_text())
+    },
+    (BinaryOp::Add, Date(date), Number(days)) | (BinaryOp::Add, Number(days), Date(date)) => {
+      add_days(*date, *days).map_or(Empty, Date)
+    },
+    (BinaryOp::Sub, Date(date), Number(days)) => add_days(*date, -days).map_or(Empty, Date),
+    (BinaryOp::Sub, Date(end), Date(start)) => match end.checked_sub(*start) {
+      Some(seconds) => Number(seconds as f64 / SECONDS_PER_DAY),
+      None => Empty,
+    },
+    (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem, _, _) => {
+      let (Some(a), Some(b)) = (left.as_number(), right.as_number()) else {
+        return Empty;
+      };
+      number(Some(match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        _ => a % b,
+      }))
+    },
+    (BinaryOp::Eq, _, _) => Bool(compare(&left, &right) == Some(std::cmp::Ordering::Equal)),
+    (BinaryOp::Ne, _, _) => Bool(compare(&left, &right) != Some(std::cmp::Ordering::Equal)),
+    (_, _, _) => {
+      let Some(ordering) = compare(&left, &right) else {
+        return Bool(false);
+      };
+      Bool(match op {
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Le => ordering.is_le(),
+        BinaryOp::Gt => ordering.is_gt(),
+        _ => ordering.is_ge(),
+      })
+    },
+  }
+}
+
+/// Shifts a timestamp by a number of days, or returns `None` if the result doesn't fit in a
+/// timestamp.
+fn add_days(date: i64, days: f64) -> Option<i64> {
+  let seconds = days * SECONDS_PER_DAY;
+  // `i64::MAX as f64` is 2^63, the first value out of range
+  if !seconds.is_finite() || seconds.abs() >= i64::MAX as f64 {
+    return None;
+  }
+  date.checked_add(seconds as i64)
+}
+
+/// Numbers, dates and checkboxes are compared by value, other values by their text.
+fn compare(left: &FormulaValue, right: &FormulaValue) -> Option<std::cmp::Ordering> {
+  use FormulaValue::*;
+  match (left, right) {
+    (Text(_), _) | (_, Text(_)) => Some(left.to_text().cmp(&right.to_text())),
+    (Empty, Empty) => Some(std::cmp::Ordering::Equal),
+    _ => left.as_number()?.partial_cmp(&right.as_number()?),
+  }
+}
+
+fn call(name: &str, args: Vec<FormulaValue>, now: i64) -> FormulaValue {
+  match name {
+    "concat" => FormulaValue::Text(args.iter().map(FormulaValue::to_text).collect()),
+    "len" => FormulaValue::Number(args[0].to_text().chars().count() as f64),
+    "lower" => FormulaValue::Text(args[0].to_text().to_lowercase()),
+    "upper" => FormulaValue::Text(args[0].to_text().to_uppercase()),
+    "round" => {
+      let digits = args.get(1).and_then(FormulaValue::as_number).unwrap_or(0.0);
+      let factor = 10_f64.powi(digits as i32);
+      number(args[0].as_number().map(|n| (n * factor).round() / factor))
+    },
+    "abs" => number(args[0].as_number().map(f64::abs)),
+    "min" => number(
+      args
+        .iter()
+        .filter_map(FormulaValue::as_number)
+        .reduce(f64::min),
+    ),
+    "max" => number(
+      args
+        .iter()
+        .filter_map(FormulaValue::as_number)
+        .reduce(f64::max),
+    ),
+    "now" => FormulaValue::Date(now),
+    "date_diff" => {
+      let (FormulaValue::Date(end), FormulaValue::Date(start)) = (&args[0], &args[1]) else {
+        return FormulaValue::Empty;
+      };
+      let unit = args
+        .get(2)
+        .map(FormulaValue::to_text)
+        .unwrap_or_else(|| "days".to_string());
+      let seconds = match unit.as_str() {
+        "seconds" => 1.0,
+        "minutes" => 60.0,
+        "hours" => 3600.0,
+        "weeks" => 7.0 * SECONDS_PER_DAY,
+        _ => SECONDS_PER_DAY,
+      };
+      match end.checked_sub(*start) {
+        Some(diff) => FormulaValue::Number((diff as f64 / seconds).trunc()),
+        None => FormulaValue::Empty,
+      }
+    },
+    "empty" => FormulaValue::Bool(args[0].to_text().is_empty()),
+    _ => FormulaValue::Empty,
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Number(f64),
+  Text(String),
+  Field(String),
+  Ident(String),
+  Op(&'static str),
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Token::Number(number) => write!(f, "number {}", format_number(*number)),
+      Token::Text(text) => write!(f, "text \"{}\"", text),
+      Token::Field(name) => write!(f, "field {{{{{}}}}}", name),
+      Token::Ident(name) => write!(f, "{}", name),
+      Token::Op(op) => write!(f, "'{}'", op),
+    }
+  }
+}
+
+const OPERATORS: &[&str] = &[
+  "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "&", "!", "(", ")", ",",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, FormulaError> {
+  let mut tokens = vec![];
+  let mut rest = expression;
+  loop {
+    rest = rest.trim_start();
+    let Some(c) = rest.chars().next() else {
+      return Ok(tokens);
+    };
+    if let Some(field) = rest.strip_prefix("{{") {
+      let end = field
+        .find("}}")
+        .ok_or_else(|| FormulaError("unclosed field reference".to_string()))?;
+      tokens.push(Token::Field(field[..end].trim().to_string()));
+      rest = &field[end + 2..];
+    } else if c == '"' {
+      let mut text = String::new();
+      let mut chars = rest[1..].char_indices();
+      let end = loop {
+        match chars.next() {
+          Some((i, '"')) => break i + 2,
+          Some((_, '\\')) => match chars.next() {
+            Some((_, c)) => text.push(c),
+            None => return Err(FormulaError("unclosed text".to_string())),
+          },
+          Some((_, c)) => text.push(c),
+          None => return Err(FormulaError("unclosed text".to_string())),
+        }
+      };
+      tokens.push(Token::Text(text));
+      rest = &rest[end..];
+    } else if c.is_ascii_digit() || c == '.' {
+      let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+      let number = rest[..end]
+        .parse()
+        .map_err(|_| FormulaError(format!("invalid number {}", &rest[..end])))?;
+      tokens.push(Token::Number(number));
+      rest = &rest[end..];
+    } else if c.is_alphabetic() || c == '_' {
+      let end = rest
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+      tokens.push(Token::Ident(rest[..end].to_lowercase()));
+      rest = &rest[end..];
+    } else {
+      let op: &'static str = OPERATORS
+        .iter()
+        .copied()
+        .find(|op| rest.starts_with(op))
+        .ok_or_else(|| FormulaError(format!("unexpected character '{}'", c)))?;
+      tokens.push(Token::Op(op));
+      rest = &rest[op.len()..];
+    }
+  }
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  pos: usize,
+  /// Depth of the expression being parsed.
+  depth: usize,
+}
+
+impl Parser {
+  /// Parses a nested expression, failing when the formula is nested too deeply.
+  fn nested(
+    &mut self,
+    parse: impl FnOnce(&mut Self) -> Result<Expr, FormulaError>,
+  ) -> Result<Expr, FormulaError> {
+    self.depth += 1;
+    if self.depth > MAX_FORMULA_DEPTH {
+      return Err(FormulaError("formula is nested too deeply".to_string()));
+    }
+    let expr = parse(self);
+    self.depth -= 1;
+    expr
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos)
+  }
+
+  fn advance(&mut self) -> Result<Token, FormulaError> {
+    let token = self
+      .tokens
+      .get(self.pos)
+      .cloned()
+      .ok_or_else(|| FormulaError("unexpected end of formula".to_string()))?;
+    self.pos += 1;
+    Ok(token)
+  }
+
+  /// Consumes the next token if it's one of the operators.
+  fn eat(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+    match self.peek() {
+      Some(Token::Op(op)) if ops.contains(op) => {
+        let op = *op;
+        self.pos += 1;
+        Some(op)
+      },
+      _ => None,
+    }
+  }
+
+  fn expect(&mut self, op: &'static str) -> Result<(), FormulaError> {
+    match self.advance()? {
+      Token::Op(next) if next == op => Ok(()),
+      token => Err(FormulaError(format!("expected '{}', found {}", op, token))),
+    }
+  }
+
+  fn binary_level(
+    &mut self,
+    ops: &[&'static str],
+    operand: fn(&mut Self) -> Result<Expr, FormulaError>,
+  ) -> Result<Expr, FormulaError> {
+    let mut expr = operand(self)?;
+    let depth = self.depth;
+    while let Some(op) = self.eat(ops) {
+      let op = match op {
+        "||" => BinaryOp::Or,
+        "&&" => BinaryOp::And,
+        "==" => BinaryOp::Eq,
+        "!=" => BinaryOp::Ne,
+        "<" => BinaryOp::Lt,
+        "<=" => BinaryOp::Le,
+        ">" => BinaryOp::Gt,
+        ">=" => BinaryOp::Ge,
+        "+" => BinaryOp::Add,
+        "-" => BinaryOp::Sub,
+        "&" => BinaryOp::Concat,
+        "*" => BinaryOp::Mul,
+        "/" => BinaryOp::Div,
+        _ => BinaryOp::Rem,
+      };
+      // every operator of a chain nests the expression on its left
+      let right = self.nested(operand)?;
+      expr = Expr::Binary(op, Box::new(expr), Box::new(right));
+      self.depth += 1;
+    }
+    self.depth = depth;
+    Ok(expr)
+  }
+
+  fn or(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["||"], Self::and)
+  }
+
+  fn and(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["&&"], Self::equality)
+  }
+
+  fn equality(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["==", "!="], Self::comparison)
+  }
+
+  fn comparison(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["<", "<=", ">", ">="], Self::additive)
+  }
+
+  fn additive(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["+", "-", "&"], Self::multiplicative)
+  }
+
+  fn multiplicative(&mut self) -> Result<Expr, FormulaError> {
+    self.binary_level(&["*", "/", "%"], Self::unary)
+  }
+
+  fn unary(&mut self) -> Result<Expr, FormulaError> {
+    match self.eat(&["-", "!"]) {
+      Some("-") => Ok(Expr::Unary(
+        UnaryOp::Neg,
+        Box::new(self.nested(Self::unary)?),
+      )),
+      Some(_) => Ok(Expr::Unary(
+        UnaryOp::Not,
+        Box::new(self.nested(Self::unary)?),
+      )),
+      None => self.primary(),
+    }
+  }
+
+  fn primary(&mut self) -> Result<Expr, FormulaError> {
+    match self.advance()? {
+      Token::Number(number) => Ok(Expr::Literal(FormulaValue::Number(number))),
+      Token::Text(text) => Ok(Expr::Literal(FormulaValue::Text(text))),
+      Token::Field(name) => Ok(Expr::Field(name)),
+      Token::Op("(") => {
+        let expr = self.nested(Self::or)?;
+        self.expect(")")?;
+        Ok(expr)
+      },
+      Token::Ident(name) if name == "true" => Ok(Expr::Literal(FormulaValue::Bool(true))),
+      Token::Ident(name) if name == "false" => Ok(Expr::Literal(FormulaValue::Bool(false))),
+      Token::Ident(name) => {
+        let (_, min_args, max_args) = FUNCTIONS
+          .iter()
+          .find(|(function, _, _)| *function == name)
+          .ok_or_else(|| FormulaError(format!("unknown function {}", name)))?;
+        self.expect("(")?;
+        let mut args = vec![];
+        if self.eat(&[")"]).is_none() {
+          loop {
+            args.push(self.nested(Self::or)?);
+            if self.eat(&[","]).is_none() {
+              self.expect(")")?;
+              break;
+            }
+          }
+        }
+        if args.len() < *min_args || max_args.is_some_and(|max| args.len() > max) {
+          return Err(FormulaError(format!(
+            "wrong number of arguments for {}",
+            name
+          )));
+        }
+        Ok(Expr::Call(name, args))
+      },
+      token => Err(FormulaError(format!("unexpected {}", token))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn evaluate(expression: &str, values: &[(&str, FormulaValue)]) -> FormulaValue {
+    let values = values
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.clone()))
+      .collect();
+    Formula::parse(expression).unwrap().evaluate(&values)
+  }
+
+  #[test]
+  fn arithmetic_test() {
+    let values = [
+      ("Price", FormulaValue::Number(2.5)),
+      ("Quantity", FormulaValue::Number(4.0)),
+    ];
+    assert_eq!(
+      evaluate("{{Price}} * {{Quantity}} + 1", &values),
+      FormulaValue::Number(11.0)
+    );
+    assert_eq!(
+      evaluate("-({{Price}} - 0.5) * 2 % 3", &values),
+      FormulaValue::Number(-1.0)
+    );
+    assert_eq!(evaluate("1 / 0", &values), FormulaValue::Empty);
+    assert_eq!(
+      evaluate("round(10 / 3, 2)", &values),
+      FormulaValue::Number(3.33)
+    );
+    // empty fields count as 0
+    assert_eq!(
+      evaluate("{{Missing}} + 1", &values),
+      FormulaValue::Number(1.0)
+    );
+  }
+
+  #[test]
+  fn text_test() {
+    let values = [
+      ("First", FormulaValue::Text("Ada".to_string())),
+      ("Last", FormulaValue::Text("Lovelace".to_string())),
+    ];
+    assert_eq!(
+      evaluate("{{First}} & \" \" & upper({{Last}})", &values),
+      FormulaValue::Text("Ada LOVELACE".to_string())
+    );
+    assert_eq!(
+      evaluate("concat({{First}}, 1, true)", &values),
+      FormulaValue::Text("Ada1true".to_string())
+    );
+    assert_eq!(
+      evaluate("len({{Last}})", &values),
+      FormulaValue::Number(8.0)
+    );
+  }
+
+  #[test]
+  fn date_and_condition_test() {
+    let values = [
+      ("Start", FormulaValue::Date(1733184000)),
+      ("End", FormulaValue::Date(1733184000 + 3 * 86400 + 3600)),
+      ("Done", FormulaValue::Bool(true)),
+    ];
+    assert_eq!(
+      evaluate("date_diff({{End}}, {{Start}})", &values),
+      FormulaValue::Number(3.0)
+    );
+    assert_eq!(
+      evaluate("date_diff({{End}}, {{Start}}, \"hours\")", &values),
+      FormulaValue::Number(73.0)
+    );
+    assert_eq!(
+      evaluate(
+        "if({{Done}} && date_diff({{End}}, {{Start}}) >= 3, \"late\", \"on time\")",
+        &values
+      ),
+      FormulaValue::Text("late".to_string())
+    );
+    assert_eq!(
+      evaluate("if(!{{Done}}, 1, 2)", &values),
+      FormulaValue::Number(2.0)
+    );
+    assert_eq!(
+      evaluate("{{Start}} + 1 - {{Start}}", &values),
+      FormulaValue::Number(1.0)
+    );
+    assert_eq!(
+      evaluate("date_diff({{Start}} - 2, {{End}})", &values),
+      FormulaValue::Number(-5.0)
+    );
+    assert!(Formula::parse("date_diff(now(), {{Start}})")
+      .unwrap()
+      .is_time_dependent());
+    assert!(!Formula::parse("date_diff({{End}}, {{Start}})")
+      .unwrap()
+      .is_time_dependent());
+  }
+
+  #[test]
+  fn date_overflow_test() {
+    let values = [
+      ("Start", FormulaValue::Date(1733184000)),
+      ("Huge", FormulaValue::Number(1e300)),
+      ("Max", FormulaValue::Date(i64::MAX - 10)),
+      ("Min", FormulaValue::Date(i64::MIN + 10)),
+    ];
+    assert_eq!(evaluate("now() + {{Huge}}", &values), FormulaValue::Empty);
+    assert_eq!(
+      evaluate("{{Start}} - {{Huge}}", &values),
+      FormulaValue::Empty
+    );
+    assert_eq!(
+      evaluate("{{Start}} + 1000000000000 * 1000000000000", &values),
+      FormulaValue::Empty
+    );
+    assert_eq!(evaluate("{{Max}} + 1", &values), FormulaValue::Empty);
+    assert_eq!(evaluate("{{Min}} - 1", &values), FormulaValue::Empty);
+    assert_eq!(evaluate("{{Max}} - {{Min}}", &values), FormulaValue::Empty);
+    assert_eq!(
+      evaluate("date_diff({{Min}}, {{Max}})", &values),
+      FormulaValue::Empty
+    );
+  }
+
+  #[test]
+  fn parse_error_test() {
+    assert!(Formula::parse("1 +").is_err());
+    assert!(Formula::parse("{{Price").is_err());
+    assert!(Formula::parse("\"text").is_err());
+    assert!(Formula::parse("unknown(1)").is_err());
+    assert!(Formula::parse("if(1, 2)").is_err());
+    assert!(Formula::parse("(1 + 2").is_err());
+    assert!(Formula::parse("1 2").is_err());
+    assert!(Formula::parse(&format!("{}1{}", "(".repeat(100), ")".repeat(100))).is_err());
+    assert!(Formula::parse(&format!("{}1", "-".repeat(100))).is_err());
+    assert!(Formula::parse(&vec!["1"; 100].join(" + ")).is_err());
+    assert!(Formula::parse(&"1".repeat(MAX_FORMULA_LEN + 1)).is_err());
+    assert_eq!(
+      evaluate(&format!("{}1{}", "(".repeat(20), ")".repeat(20)), &[]),
+      FormulaValue::Number(1.0)
+    );
+    assert_eq!(
+      Formula::parse("{{A}} + {{B}} * {{A}}").unwrap().fields(),
+      vec!["A", "B"]
+    );
+  }
+
+  #[test]
+  fn from_cell_json_test() {
+    assert_eq!(
+      FormulaValue::from_cell_json(&FieldType::Number, &serde_json::json!("$1,234.50")),
+      FormulaValue::Number(1234.5)
+    );
+    assert_eq!(
+      FormulaValue::from_cell_json(
+        &FieldType::DateTime,
+        &serde_json::json!({ "timestamp": 1733184000 })
+      ),
+      FormulaValue::Date(1733184000)
+    );
+    assert_eq!(
+      FormulaValue::from_cell_json(&FieldType::Checkbox, &serde_json::json!("Yes")),
+      FormulaValue::Bool(true)
+    );
+    assert_eq!(
+      FormulaValue::from_cell_json(&FieldType::MultiSelect, &serde_json::json!(["a", "b"])),
+      FormulaValue::Text("a, b".to_string())
+    );
+    assert_eq!(format_number(3.0), "3");
+    assert_eq!(format_number(0.1 + 0.2), "0.3");
+  }
+}
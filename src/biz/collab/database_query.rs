@@ -0,0 +1,1052 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab::preclude::Collab;
+use collab_database::entity::FieldType;
+use collab_database::fields::{Field, TypeOptionCellReader};
+use collab_database::rows::{Cell, RowDetail};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::template::timestamp_parse::TimestampCellData;
+use collab_entity::{CollabType, EncodedCollab};
+use collab_folder::CollabOrigin;
+use database::collab::CollabStorage;
+use database_entity::dto::{QueryCollab, QueryCollabResult};
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseRowDetail, AFDatabaseRowFilter, AFDatabaseRowQueryResult, AFDatabaseRowSort,
+  AFSelectFilterCondition, AFTextFilterCondition, QueryDatabaseRowsParams,
+};
+use tracing::warn;
+use yrs::Any;
+
+use super::database_cell::{fill_related_rows, raw_cell_json, related_row_ids};
+use super::utils::{
+  field_by_id_name_uniq, field_by_name_uniq, get_database_body, get_row_details_serde,
+  type_option_reader_by_id, type_options_serde,
+};
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Returns a page of the rows of a database matching the filters of the query, in the order of its
+/// sorts. The cursor of the next page is returned along with the rows.
+pub async fn query_database_rows(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  params: QueryDatabaseRowsParams,
+) -> Result<AFDatabaseRowQueryResult, AppError> {
+  let limit = params
+    .limit
+    .map(|limit| limit as usize)
+    .unwrap_or(DEFAULT_QUERY_LIMIT)
+    .clamp(1, MAX_QUERY_LIMIT);
+
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+  let (all_fields, view) = {
+    let txn = db_collab.transact();
+    let view_id = params
+      .view_id
+      .clone()
+      .unwrap_or_else(|| db_body.get_inline_view_id(&txn));
+    let view = db_body.views.get_view(&txn, &view_id).ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "view {} not found in database {}",
+        view_id, database_uuid_str
+      ))
+    })?;
+    (db_body.fields.get_all_fields(&txn), view)
+  };
+
+  let schema = RowSchema::new(all_fields);
+  let mut filters = params
+    .filters
+    .iter()
+    .map(|filter| schema.resolve_filter(filter))
+    .collect::<Result<Vec<_>, _>>()?;
+  let mut sorts = params
+    .sorts
+    .iter()
+    .map(|sort| schema.resolve_sort(sort))
+    .collect::<Result<Vec<_>, _>>()?;
+  if params.with_view_settings {
+    filters.extend(view.filters.iter().filter_map(|f| schema.saved_filter(f)));
+    sorts.extend(view.sorts.iter().filter_map(|s| schema.saved_sort(s)));
+  }
+
+  let row_ids: Vec<String> = view
+    .row_orders
+    .iter()
+    .map(|row_order| row_order.id.to_string())
+    .collect();
+  let mut rows = get_row_details(collab_storage, uid, workspace_uuid_str, &row_ids).await;
+  rows.retain(|row| filters.iter().all(|filter| schema.matches(row, filter)));
+  if !sorts.is_empty() {
+    // the sort is stable, rows comparing equal keep the order of the view
+    rows.sort_by(|a, b| schema.compare(a, b, &sorts));
+  }
+
+  let total = rows.len();
+  let start = match &params.cursor {
+    Some(cursor) => cursor_position(cursor, &rows)?,
+    None => 0,
+  };
+  let end = (start + limit).min(total);
+  let next_cursor = match rows[start..end].last() {
+    Some(last_row) if end < total => Some(encode_cursor(end, last_row.row.id.as_str())),
+    _ => None,
+  };
+  let mut rows: Vec<AFDatabaseRowDetail> = rows
+    .drain(start..end)
+    .map(|row| schema.row_detail(row))
+    .collect();
+  fill_related_rows(
+    collab_storage,
+    uid,
+    workspace_uuid_str,
+    &schema.returned_field_by_id,
+    &mut rows,
+  )
+  .await;
+  Ok(AFDatabaseRowQueryResult {
+    rows,
+    total,
+    next_cursor,
+  })
+}
+
+/// Loads the rows, in the order of their ids. Rows which fail to load are skipped.
+pub async fn get_row_details(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_uuid_str: &str,
+  row_ids: &[String],
+) -> Vec<RowDetail> {
+  let query_collabs: Vec<QueryCollab> = row_ids
+    .iter()
+    .map(|id| QueryCollab {
+      object_id: id.clone(),
+      collab_type: CollabType::DatabaseRow,
+    })
+    .collect();
+  let mut row_by_id: HashMap<String, RowDetail> = collab_storage
+    .batch_get_collab(&uid, workspace_uuid_str, query_collabs, true)
+    .await
+    .into_iter()
+    .filter_map(|(id, result)| match result {
+      QueryCollabResult::Success { encode_collab_v1 } => {
+        let ec = EncodedCollab::decode_from_bytes(&encode_collab_v1)
+          .map_err(|err| warn!("Failed to decode row {}: {:?}", id, err))
+          .ok()?;
+        let collab = Collab::new_with_source(CollabOrigin::Server, &id, ec.into(), vec![], false)
+          .map_err(|err| warn!("Failed to create collab of row {}: {:?}", id, err))
+          .ok()?;
+        let row_detail = RowDetail::from_collab(&collab)?;
+        Some((id, row_detail))
+      },
+      QueryCollabResult::Failed { error } => {
+        warn!("Failed to get row {}: {:?}", id, error);
+        None
+      },
+    })
+    .collect();
+  row_ids
+    .iter()
+    .filter_map(|id| row_by_id.remove(id))
+    .collect()
+}
+
+/// Filter over the cells of a row, whose fields are resolved.
+#[derive(Debug, PartialEq)]
+enum RowFilter {
+  And(Vec<RowFilter>),
+  Or(Vec<RowFilter>),
+  Not(Box<RowFilter>),
+  Empty {
+    field_id: String,
+  },
+  Text {
+    field_id: String,
+    condition: AFTextFilterCondition,
+    /// Lowercase text to look for.
+    value: String,
+  },
+  Number {
+    field_id: String,
+    range: Range<f64>,
+  },
+  Date {
+    field_id: String,
+    range: Range<i64>,
+  },
+  Select {
+    field_id: String,
+    condition: AFSelectFilterCondition,
+    option_ids: Vec<String>,
+  },
+  Checkbox {
+    field_id: String,
+    checked: bool,
+  },
+  Relation {
+    field_id: String,
+    row_ids: Vec<String>,
+  },
+}
+
+#[derive(Debug, PartialEq)]
+struct Range<T> {
+  gt: Option<T>,
+  gte: Option<T>,
+  lt: Option<T>,
+  lte: Option<T>,
+}
+
+impl<T: PartialOrd> Range<T> {
+  fn contains(&self, value: &T) -> bool {
+    self.gt.as_ref().map_or(true, |gt| value > gt)
+      && self.gte.as_ref().map_or(true, |gte| value >= gte)
+      && self.lt.as_ref().map_or(true, |lt| value < lt)
+      && self.lte.as_ref().map_or(true, |lte| value <= lte)
+  }
+}
+
+impl<T> Default for Range<T> {
+  fn default() -> Self {
+    Self {
+      gt: None,
+      gte: None,
+      lt: None,
+      lte: None,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+struct RowSort {
+  field_id: String,
+  descending: bool,
+}
+
+/// Value of a cell, as compared by sorts.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortKey {
+  Number(f64),
+  Timestamp(i64),
+  Checkbox(bool),
+  Text(String),
+}
+
+struct RowSchema {
+  field_by_id: HashMap<String, Field>,
+  field_by_name: HashMap<String, Field>,
+  /// Fields of the cells returned with the rows, by id, with unique names.
+  returned_field_by_id: HashMap<String, Field>,
+  type_option_reader_by_id: HashMap<String, Box<dyn TypeOptionCellReader>>,
+}
+
+impl RowSchema {
+  fn new(fields: Vec<Field>) -> Self {
+    Self {
+      type_option_reader_by_id: type_option_reader_by_id(&fields),
+      returned_field_by_id: field_by_id_name_uniq(fields.clone()),
+      field_by_id: fields
+        .iter()
+        .map(|field| (field.id.clone(), field.clone()))
+        .collect(),
+      field_by_name: field_by_name_uniq(fields),
+    }
+  }
+
+  fn field(&self, id_or_name: &str) -> Result<&Field, AppError> {
+    self
+      .field_by_id
+      .get(id_or_name)
+      .or_else(|| self.field_by_name.get(id_or_name))
+      .ok_or_else(|| AppError::InvalidRequest(format!("field not found: {}", id_or_name)))
+  }
+
+  /// Returns the field, checking that it's one of the types a filter applies to.
+  fn field_of_type(&self, id_or_name: &str, field_types: &[FieldType]) -> Result<&Field, AppError> {
+    let field = self.field(id_or_name)?;
+    let field_type = FieldType::from(field.field_type);
+    if !field_types.contains(&field_type) {
+      return Err(AppError::InvalidRequest(format!(
+        "field {} of type {:?} can't be filtered or sorted this way",
+        id_or_name, field_type
+      )));
+    }
+    Ok(field)
+  }
+
+  fn resolve_filter(&self, filter: &AFDatabaseRowFilter) -> Result<RowFilter, AppError> {
+    let filter = match filter {
+      AFDatabaseRowFilter::And { filters } => RowFilter::And(
+        filters
+          .iter()
+          .map(|filter| self.resolve_filter(filter))
+          .collect::<Result<_, _>>()?,
+      ),
+      AFDatabaseRowFilter::Or { filters } => RowFilter::Or(
+        filters
+          .iter()
+          .map(|filter| self.resolve_filter(filter))
+          .collect::<Result<_, _>>()?,
+      ),
+      AFDatabaseRowFilter::Not { filter } => RowFilter::Not(Box::new(self.resolve_filter(filter)?)),
+      AFDatabaseRowFilter::Empty { field } => RowFilter::Empty {
+        field_id: self.field(field)?.id.clone(),
+      },
+      AFDatabaseRowFilter::Text {
+        field,
+        condition,
+        value,
+      } => RowFilter::Text {
+        field_id: self.field(field)?.id.clone(),
+        condition: *condition,
+        value: value.to_lowercase(),
+      },
+      AFDatabaseRowFilter::Number {
+        field,
+        gt,
+        gte,
+        lt,
+        lte,
+      } => RowFilter::Number {
+        field_id: self.field_of_type(field, &[FieldType::Number])?.id.clone(),
+        range: Range {
+          gt: *gt,
+          gte: *gte,
+          lt: *lt,
+          lte: *lte,
+        },
+      },
+      AFDatabaseRowFilter::Date {
+        field,
+        gt,
+        gte,
+        lt,
+        lte,
+      } => RowFilter::Date {
+        field_id: self.field_of_type(field, DATE_FIELD_TYPES)?.id.clone(),
+        range: Range {
+          gt: *gt,
+          gte: *gte,
+          lt: *lt,
+          lte: *lte,
+        },
+      },
+      AFDatabaseRowFilter::Select {
+        field,
+        condition,
+        options,
+      } => {
+        let field = self.field_of_type(field, SELECT_FIELD_TYPES)?;
+        RowFilter::Select {
+          field_id: field.id.clone(),
+          condition: *condition,
+          option_ids: select_option_ids(field, options)?,
+        }
+      },
+      AFDatabaseRowFilter::Checkbox { field, checked } => RowFilter::Checkbox {
+        field_id: self
+          .field_of_type(field, &[FieldType::Checkbox])?
+          .id
+          .clone(),
+        checked: *checked,
+      },
+      AFDatabaseRowFilter::Relation { field, row_ids } => RowFilter::Relation {
+        field_id: self
+          .field_of_type(field, &[FieldType::Relation])?
+          .id
+          .clone(),
+        row_ids: row_ids.clone(),
+      },
+    };
+    Ok(filter)
+  }
+
+  fn resolve_sort(&self, sort: &AFDatabaseRowSort) -> Result<RowSort, AppError> {
+    Ok(RowSort {
+      field_id: self.field(&sort.field)?.id.clone(),
+      descending: sort.descending,
+    })
+  }
+
+  /// Converts a filter saved in a view. Filters which are incomplete, ie. without content, or not
+  /// supported are skipped, like the clients do.
+  fn saved_filter(&self, filter: &HashMap<String, Any>) -> Option<RowFilter> {
+    match any_i64(filter.get("filter_type")).unwrap_or(0) {
+      // and, or
+      filter_type @ (1 | 2) => {
+        let children = match filter.get("children") {
+          Some(Any::Array(children)) => children
+            .iter()
+            .filter_map(|child| match child {
+              Any::Map(child) => self.saved_filter(child),
+              _ => None,
+            })
+            .collect(),
+          _ => vec![],
+        };
+        if filter_type == 1 {
+          Some(RowFilter::And(children))
+        } else {
+          Some(RowFilter::Or(children))
+        }
+      },
+      _ => {
+        let field_id = any_str(filter.get("field_id"))?;
+        let field = self.field_by_id.get(field_id)?;
+        let condition = any_i64(filter.get("condition"))?;
+        let content = any_str(filter.get("content")).unwrap_or_default();
+        let filter = saved_field_filter(field, condition, content);
+        if filter.is_none() {
+          warn!(
+            "skip saved filter of field {} with condition {}",
+            field_id, condition
+          );
+        }
+        filter
+      },
+    }
+  }
+
+  fn saved_sort(&self, sort: &HashMap<String, Any>) -> Option<RowSort> {
+    let field_id = any_str(sort.get("field_id"))?;
+    self.field_by_id.get(field_id)?;
+    Some(RowSort {
+      field_id: field_id.to_string(),
+      descending: any_i64(sort.get("condition")) == Some(1),
+    })
+  }
+
+  fn matches(&self, row: &RowDetail, filter: &RowFilter) -> bool {
+    match filter {
+      RowFilter::And(filters) => filters.iter().all(|filter| self.matches(row, filter)),
+      RowFilter::Or(filters) => {
+        filters.is_empty() || filters.iter().any(|filter| self.matches(row, filter))
+      },
+      RowFilter::Not(filter) => !self.matches(row, filter),
+      RowFilter::Empty { field_id } => self.is_empty(row, field_id),
+      RowFilter::Text {
+        field_id,
+        condition,
+        value,
+      } => {
+        let text = self.text(row, field_id).to_lowercase();
+        match condition {
+          AFTextFilterCondition::Is => text == *value,
+          AFTextFilterCondition::Contains => text.contains(value.as_str()),
+          AFTextFilterCondition::StartsWith => text.starts_with(value.as_str()),
+          AFTextFilterCondition::EndsWith => text.ends_with(value.as_str()),
+        }
+      },
+      RowFilter::Number { field_id, range } => self
+        .number(row, field_id)
+        .map_or(false, |number| range.contains(&number)),
+      RowFilter::Date { field_id, range } => self
+        .timestamp(row, field_id)
+        .map_or(false, |timestamp| range.contains(&timestamp)),
+      RowFilter::Select {
+        field_id,
+        condition,
+        option_ids,
+      } => {
+        let cell_option_ids = self.select_option_ids(row, field_id);
+        match condition {
+          AFSelectFilterCondition::Any => option_ids.iter().any(|id| cell_option_ids.contains(id)),
+          AFSelectFilterCondition::All => option_ids.iter().all(|id| cell_option_ids.contains(id)),
+        }
+      },
+      RowFilter::Checkbox { field_id, checked } => self.is_checked(row, field_id) == *checked,
+      RowFilter::Relation { field_id, row_ids } => {
+        let related_row_ids = self.related_row_ids(row, field_id);
+        row_ids.iter().any(|id| related_row_ids.contains(id))
+      },
+    }
+  }
+
+  /// Compares the rows by the sorts, in order. Empty cells come last, whatever the direction.
+  fn compare(&self, a: &RowDetail, b: &RowDetail, sorts: &[RowSort]) -> Ordering {
+    for sort in sorts {
+      let ordering = match (
+        self.sort_key(a, &sort.field_id),
+        self.sort_key(b, &sort.field_id),
+      ) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => {
+          let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+          if sort.descending {
+            ordering.reverse()
+          } else {
+            ordering
+          }
+        },
+      };
+      if ordering != Ordering::Equal {
+        return ordering;
+      }
+    }
+    Ordering::Equal
+  }
+
+  fn sort_key(&self, row: &RowDetail, field_id: &str) -> Option<SortKey> {
+    match self.field_type(field_id) {
+      FieldType::Number => self.number(row, field_id).map(SortKey::Number),
+      FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime => {
+        self.timestamp(row, field_id).map(SortKey::Timestamp)
+      },
+      FieldType::Checkbox => Some(SortKey::Checkbox(self.is_checked(row, field_id))),
+      _ => {
+        let text = self.text(row, field_id);
+        if text.is_empty() {
+          None
+        } else {
+          Some(SortKey::Text(text.to_lowercase()))
+        }
+      },
+    }
+  }
+
+  fn field_type(&self, field_id: &str) -> FieldType {
+    self
+      .field_by_id
+      .get(field_id)
+      .map(|field| FieldType::from(field.field_type))
+      .unwrap_or(FieldType::RichText)
+  }
+
+  /// Cell of the row, the time cells being filled from the row itself.
+  fn cell(&self, row: &RowDetail, field_id: &str) -> Cell {
+    if let Some(cell) = row.row.cells.get(field_id) {
+      return cell.clone();
+    }
+    match self.field_type(field_id) {
+      field_type @ FieldType::CreatedTime => {
+        TimestampCellData::new(Some(row.row.created_at)).to_cell(field_type)
+      },
+      field_type @ FieldType::LastEditedTime => {
+        TimestampCellData::new(Some(row.row.modified_at)).to_cell(field_type)
+      },
+      _ => Cell::new(),
+    }
+  }
+
+  fn json_cell(&self, row: &RowDetail, field_id: &str) -> Value {
+    match self.field_type(field_id) {
+      FieldType::Relation => Value::Array(
+        self
+          .related_row_ids(row, field_id)
+          .into_iter()
+          .map(Value::String)
+          .collect(),
+      ),
+      field_type => match self.type_option_reader_by_id.get(field_id) {
+        Some(reader) => reader.json_cell(&self.cell(row, field_id)),
+        None => raw_cell_json(&field_type, &self.cell(row, field_id)),
+      },
+    }
+  }
+
+  fn text(&self, row: &RowDetail, field_id: &str) -> String {
+    json_text(&self.json_cell(row, field_id))
+  }
+
+  fn number(&self, row: &RowDetail, field_id: &str) -> Option<f64> {
+    match self.json_cell(row, field_id) {
+      Value::Number(number) => number.as_f64(),
+      value => {
+        // formatted numbers, ie. currencies, keep their digits
+        let text: String = json_text(&value)
+          .chars()
+          .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+          .collect();
+        text.parse().ok()
+      },
+    }
+  }
+
+  fn timestamp(&self, row: &RowDetail, field_id: &str) -> Option<i64> {
+    match self.field_type(field_id) {
+      FieldType::CreatedTime => Some(row.row.created_at),
+      FieldType::LastEditedTime => Some(row.row.modified_at),
+      _ => match self.json_cell(row, field_id) {
+        Value::Object(map) => map.get("timestamp").and_then(json_i64),
+        value => json_i64(&value),
+      },
+    }
+  }
+
+  fn is_checked(&self, row: &RowDetail, field_id: &str) -> bool {
+    match self.json_cell(row, field_id) {
+      Value::Bool(checked) => checked,
+      value => matches!(
+        json_text(&value).to_lowercase().as_str(),
+        "yes" | "true" | "1"
+      ),
+    }
+  }
+
+  fn select_option_ids(&self, row: &RowDetail, field_id: &str) -> Vec<String> {
+    match self.cell(row, field_id).get(CELL_DATA) {
+      Some(Any::String(ids)) => split_ids(ids),
+      _ => vec![],
+    }
+  }
+
+  fn related_row_ids(&self, row: &RowDetail, field_id: &str) -> Vec<String> {
+    related_row_ids(&self.cell(row, field_id))
+  }
+
+  fn is_empty(&self, row: &RowDetail, field_id: &str) -> bool {
+    match self.field_type(field_id) {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        self.select_option_ids(row, field_id).is_empty()
+      },
+      FieldType::Relation => self.related_row_ids(row, field_id).is_empty(),
+      FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime => {
+        self.timestamp(row, field_id).is_none()
+      },
+      FieldType::Checkbox => !self.is_checked(row, field_id),
+      _ => self.text(row, field_id).trim().is_empty(),
+    }
+  }
+
+  /// Cells of the row by field name, like [super::ops::list_database_row_details].
+  fn row_detail(&self, row: RowDetail) -> AFDatabaseRowDetail {
+    let id = row.row.id.to_string();
+    let cells = get_row_details_serde(
+      row,
+      &self.returned_field_by_id,
+      &self.type_option_reader_by_id,
+    );
+    AFDatabaseRowDetail { id, cells }
+  }
+}
+
+const DATE_FIELD_TYPES: &[FieldType] = &[
+  FieldType::DateTime,
+  FieldType::CreatedTime,
+  FieldType::LastEditedTime,
+];
+const SELECT_FIELD_TYPES: &[FieldType] = &[FieldType::SingleSelect, FieldType::MultiSelect];
+
+/// Converts a filter on a field saved in a view. Conditions are numbered as in the clients.
+fn saved_field_filter(field: &Field, condition: i64, content: &str) -> Option<RowFilter> {
+  let field_id = field.id.clone();
+  let filter = match FieldType::from(field.field_type) {
+    FieldType::RichText | FieldType::URL => {
+      let text = |condition| RowFilter::Text {
+        field_id: field_id.clone(),
+        condition,
+        value: content.to_lowercase(),
+      };
+      match condition {
+        0 => text(AFTextFilterCondition::Is),
+        1 => not(text(AFTextFilterCondition::Is)),
+        2 => text(AFTextFilterCondition::Contains),
+        3 => not(text(AFTextFilterCondition::Contains)),
+        4 => text(AFTextFilterCondition::StartsWith),
+        5 => text(AFTextFilterCondition::EndsWith),
+        6 => RowFilter::Empty { field_id },
+        7 => not(RowFilter::Empty { field_id }),
+        _ => return None,
+      }
+    },
+    FieldType::Number => {
+      if condition == 6 || condition == 7 {
+        let empty = RowFilter::Empty { field_id };
+        return Some(if condition == 6 { empty } else { not(empty) });
+      }
+      let value = content.trim().parse::<f64>().ok()?;
+      let number = |range| RowFilter::Number {
+        field_id: field_id.clone(),
+        range,
+      };
+      match condition {
+        0 => number(Range {
+          gte: Some(value),
+          lte: Some(value),
+          ..Default::default()
+        }),
+        1 => not(number(Range {
+          gte: Some(value),
+          lte: Some(value),
+          ..Default::default()
+        })),
+        2 => number(Range {
+          gt: Some(value),
+          ..Default::default()
+        }),
+        3 => number(Range {
+          lt: Some(value),
+          ..Default::default()
+        }),
+        4 => number(Range {
+          gte: Some(value),
+          ..Default::default()
+        }),
+        5 => number(Range {
+          lte: Some(value),
+          ..Default::default()
+        }),
+        _ => return None,
+      }
+    },
+    FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime => {
+      if condition == 6 || condition == 7 {
+        let empty = RowFilter::Empty { field_id };
+        return Some(if condition == 6 { empty } else { not(empty) });
+      }
+      // dates of saved filters are days, in UTC
+      let content: Value = serde_json::from_str(content).ok()?;
+      let day = |key: &str| {
+        let timestamp = content.get(key).and_then(json_i64)?;
+        Some(timestamp - timestamp.rem_euclid(SECONDS_PER_DAY))
+      };
+      let range = match condition {
+        0 => {
+          let day = day("timestamp")?;
+          Range {
+            gte: Some(day),
+            lt: Some(day + SECONDS_PER_DAY),
+            ..Default::default()
+          }
+        },
+        1 => Range {
+          lt: Some(day("timestamp")?),
+          ..Default::default()
+        },
+        2 => Range {
+          gte: Some(day("timestamp")? + SECONDS_PER_DAY),
+          ..Default::default()
+        },
+        3 => Range {
+          lt: Some(day("timestamp")? + SECONDS_PER_DAY),
+          ..Default::default()
+        },
+        4 => Range {
+          gte: Some(day("timestamp")?),
+          ..Default::default()
+        },
+        5 => Range {
+          gte: Some(day("start")?),
+          lt: Some(day("end")? + SECONDS_PER_DAY),
+          ..Default::default()
+        },
+        _ => return None,
+      };
+      RowFilter::Date { field_id, range }
+    },
+    FieldType::SingleSelect | FieldType::MultiSelect => {
+      if condition == 4 || condition == 5 {
+        let empty = RowFilter::Empty { field_id };
+        return Some(if condition == 4 { empty } else { not(empty) });
+      }
+      let option_ids = split_ids(content);
+      if option_ids.is_empty() {
+        return None;
+      }
+      let select = |condition| RowFilter::Select {
+        field_id: field_id.clone(),
+        condition,
+        option_ids: option_ids.clone(),
+      };
+      match condition {
+        0 => select(AFSelectFilterCondition::Any),
+        1 => not(select(AFSelectFilterCondition::Any)),
+        2 => select(AFSelectFilterCondition::All),
+        3 => not(select(AFSelectFilterCondition::Any)),
+        _ => return None,
+      }
+    },
+    FieldType::Checkbox => match condition {
+      0 => RowFilter::Checkbox {
+        field_id,
+        checked: true,
+      },
+      1 => RowFilter::Checkbox {
+        field_id,
+        checked: false,
+      },
+      _ => return None,
+    },
+    _ => return None,
+  };
+  Some(filter)
+}
+
+fn not(filter: RowFilter) -> RowFilter {
+  RowFilter::Not(Box::new(filter))
+}
+
+/// Ids of the options of a select field, given by their id or their name.
+pub(super) fn select_option_ids(
+  field: &Field,
+  options: &[String],
+) -> Result<Vec<String>, AppError> {
+  let field_type = FieldType::from(field.field_type);
+  let type_option = serde_json::to_value(type_options_serde(&field.type_options, &field_type))?;
+  let field_options = type_option
+    .get("content")
+    .unwrap_or(&type_option)
+    .get("options")
+    .and_then(Value::as_array)
+    .cloned()
+    .unwrap_or_default();
+  options
+    .iter()
+    .map(|option| {
+      field_options
+        .iter()
+        .find(|field_option| {
+          field_option.get("id").and_then(Value::as_str) == Some(option.as_str())
+            || field_option
+              .get("name")
+              .and_then(Value::as_str)
+              .map_or(false, |name| name.eq_ignore_ascii_case(option))
+        })
+        .and_then(|field_option| field_option.get("id").and_then(Value::as_str))
+        .map(str::to_string)
+        .ok_or_else(|| {
+          AppError::InvalidRequest(format!(
+            "option {} not found in field {}",
+            option, field.name
+          ))
+        })
+    })
+    .collect()
+}
+
+fn split_ids(ids: &str) -> Vec<String> {
+  ids
+    .split(',')
+    .map(str::trim)
+    .filter(|id| !id.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+fn json_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(text) => text.clone(),
+    Value::Bool(checked) => checked.to_string(),
+    Value::Number(number) => number.to_string(),
+    Value::Array(items) => items
+      .iter()
+      .map(json_text)
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join(", "),
+    // dates, checklists and their items, media files
+    Value::Object(map) => ["timestamp", "items", "name"]
+      .iter()
+      .find_map(|key| map.get(*key))
+      .map(json_text)
+      .unwrap_or_default(),
+  }
+}
+
+fn json_i64(value: &Value) -> Option<i64> {
+  match value {
+    Value::Number(number) => number.as_i64(),
+    Value::String(text) => text.trim().parse().ok(),
+    _ => None,
+  }
+}
+
+fn any_i64(value: Option<&Any>) -> Option<i64> {
+  match value? {
+    Any::BigInt(n) => Some(*n),
+    Any::Number(n) => Some(*n as i64),
+    Any::String(text) => text.parse().ok(),
+    _ => None,
+  }
+}
+
+fn any_str(value: Option<&Any>) -> Option<&str> {
+  match value? {
+    Any::String(text) => Some(text.as_ref()),
+    _ => None,
+  }
+}
+
+/// The cursor of a page is the position of its first row, along with the id of the last row of
+/// the previous page: the next page starts after that row, even if rows were added or removed
+/// before it in the meantime.
+fn encode_cursor(position: usize, last_row_id: &str) -> String {
+  format!("{}:{}", position, last_row_id)
+}
+
+fn cursor_position(cursor: &str, rows: &[RowDetail]) -> Result<usize, AppError> {
+  let (position, last_row_id) = decode_cursor(cursor)?;
+  let position = rows
+    .iter()
+    .position(|row| row.row.id.as_str() == last_row_id)
+    .map(|i| i + 1)
+    .unwrap_or(position);
+  Ok(position.min(rows.len()))
+}
+
+fn decode_cursor(cursor: &str) -> Result<(usize, &str), AppError> {
+  cursor
+    .split_once(':')
+    .and_then(|(position, last_row_id)| Some((position.parse().ok()?, last_row_id)))
+    .ok_or_else(|| AppError::InvalidRequest(format!("invalid cursor: {}", cursor)))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use super::*;
+
+  fn field(id: &str, field_type: FieldType) -> Field {
+    Field {
+      id: id.to_string(),
+      name: id.to_string(),
+      field_type: field_type.into(),
+      ..Default::default()
+    }
+  }
+
+  fn saved(entries: &[(&str, Any)]) -> HashMap<String, Any> {
+    entries
+      .iter()
+      .map(|(key, value)| (key.to_string(), value.clone()))
+      .collect()
+  }
+
+  #[test]
+  fn cursor_test() {
+    let cursor = encode_cursor(100, "row:1");
+    assert_eq!(decode_cursor(&cursor).unwrap(), (100, "row:1"));
+    assert!(decode_cursor("row_1").is_err());
+    assert!(decode_cursor("abc:row_1").is_err());
+  }
+
+  #[test]
+  fn range_test() {
+    let range = Range {
+      gte: Some(10.0),
+      lt: Some(20.0),
+      ..Default::default()
+    };
+    assert!(range.contains(&10.0));
+    assert!(range.contains(&19.5));
+    assert!(!range.contains(&20.0));
+    assert!(!range.contains(&9.0));
+    assert!(Range::<i64>::default().contains(&0));
+  }
+
+  #[test]
+  fn resolve_filter_test() {
+    let schema = RowSchema::new(vec![
+      field("price", FieldType::Number),
+      field("done", FieldType::Checkbox),
+    ]);
+    let filter: AFDatabaseRowFilter =
+      serde_json::from_value(serde_json::json!({ "type": "number", "field": "price", "gte": 10 }))
+        .unwrap();
+    assert_eq!(
+      schema.resolve_filter(&filter).unwrap(),
+      RowFilter::Number {
+        field_id: "price".to_string(),
+        range: Range {
+          gte: Some(10.0),
+          ..Default::default()
+        },
+      }
+    );
+
+    // checkbox fields have no numbers
+    let filter = AFDatabaseRowFilter::Number {
+      field: "done".to_string(),
+      gt: Some(1.0),
+      gte: None,
+      lt: None,
+      lte: None,
+    };
+    assert!(schema.resolve_filter(&filter).is_err());
+    let filter = AFDatabaseRowFilter::Empty {
+      field: "unknown".to_string(),
+    };
+    assert!(schema.resolve_filter(&filter).is_err());
+  }
+
+  #[test]
+  fn saved_filter_test() {
+    let schema = RowSchema::new(vec![
+      field("name", FieldType::RichText),
+      field("price", FieldType::Number),
+      field("due", FieldType::DateTime),
+      field("tags", FieldType::MultiSelect),
+    ]);
+    let filter = saved(&[
+      ("filter_type", Any::BigInt(1)),
+      (
+        "children",
+        Any::Array(Arc::from(vec![
+          Any::Map(Arc::new(saved(&[
+            ("field_id", Any::String(Arc::from("name"))),
+            ("condition", Any::BigInt(3)),
+            ("content", Any::String(Arc::from("Draft"))),
+          ]))),
+          Any::Map(Arc::new(saved(&[
+            ("field_id", Any::String(Arc::from("price"))),
+            ("condition", Any::BigInt(2)),
+            ("content", Any::String(Arc::from("5"))),
+          ]))),
+          Any::Map(Arc::new(saved(&[
+            ("field_id", Any::String(Arc::from("due"))),
+            ("condition", Any::BigInt(0)),
+            (
+              "content",
+              Any::String(Arc::from(r#"{"timestamp":1733210221}"#)),
+            ),
+          ]))),
+          // incomplete filters are skipped
+          Any::Map(Arc::new(saved(&[
+            ("field_id", Any::String(Arc::from("tags"))),
+            ("condition", Any::BigInt(0)),
+            ("content", Any::String(Arc::from(""))),
+          ]))),
+        ])),
+      ),
+    ]);
+    assert_eq!(
+      schema.saved_filter(&filter).unwrap(),
+      RowFilter::And(vec![
+        not(RowFilter::Text {
+          field_id: "name".to_string(),
+          condition: AFTextFilterCondition::Contains,
+          value: "draft".to_string(),
+        }),
+        RowFilter::Number {
+          field_id: "price".to_string(),
+          range: Range {
+            gt: Some(5.0),
+            ..Default::default()
+          },
+        },
+        RowFilter::Date {
+          field_id: "due".to_string(),
+          range: Range {
+            gte: Some(1733184000),
+            lt: Some(1733184000 + SECONDS_PER_DAY),
+            ..Default::default()
+          },
+        },
+      ])
+    );
+  }
+}
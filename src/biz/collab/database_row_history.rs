@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab_database::entity::FieldType;
+use collab_database::fields::{Field, TypeOptionCellReader};
+use collab_database::rows::Cell;
+use collab_database::template::entity::CELL_DATA;
+use database::row_history::{select_database_row_history, AFDatabaseRowHistoryRow};
+use shared_entity::dto::workspace_dto::{AFDatabaseRowChange, QueryDatabaseRowHistoryParams};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::database_cell::{is_raw_cell_field_type, raw_cell_json};
+use super::utils::{get_database_body, type_option_reader_by_id};
+
+const DEFAULT_ROW_HISTORY_LIMIT: u32 = 50;
+const MAX_ROW_HISTORY_LIMIT: u32 = 200;
+
+/// Returns the changes of the cells of a row, the most recent first. The values of the cells are
+/// read with the current type option of their field.
+pub async fn get_database_row_history(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  row_id: &str,
+  params: QueryDatabaseRowHistoryParams,
+) -> Result<Vec<AFDatabaseRowChange>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let limit = params
+    .limit
+    .unwrap_or(DEFAULT_ROW_HISTORY_LIMIT)
+    .clamp(1, MAX_ROW_HISTORY_LIMIT);
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let fields = db_body.fields.get_all_fields(&db_collab.transact());
+  let reader = CellValueReader::new(fields);
+
+  let history = select_database_row_history(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    row_id,
+    params.before,
+    limit as i64,
+  )
+  .await?;
+  Ok(
+    history
+      .into_iter()
+      .map(|change| reader.row_change(change))
+      .collect(),
+  )
+}
+
+struct CellValueReader {
+  field_by_id: HashMap<String, Field>,
+  type_option_reader_by_id: HashMap<String, Box<dyn TypeOptionCellReader>>,
+}
+
+impl CellValueReader {
+  fn new(fields: Vec<Field>) -> Self {
+    Self {
+      type_option_reader_by_id: type_option_reader_by_id(&fields),
+      field_by_id: fields
+        .into_iter()
+        .map(|field| (field.id.clone(), field))
+        .collect(),
+    }
+  }
+
+  fn row_change(&self, change: AFDatabaseRowHistoryRow) -> AFDatabaseRowChange {
+    let field = self.field_by_id.get(&change.field_id);
+    AFDatabaseRowChange {
+      field_name: field.map(|field| field.name.clone()),
+      field_type: field.map(|field| format!("{:?}", FieldType::from(field.field_type))),
+      old_value: self.cell_value(field, change.old_cell),
+      new_value: self.cell_value(field, change.new_cell),
+      field_id: change.field_id,
+      uid: change.uid,
+      user_name: change.user_name,
+      created_at: change.created_at,
+      changed_at: change.changed_at,
+    }
+  }
+
+  /// Returns the value of a cell recorded in the history. The cells of deleted fields are returned
+  /// as they're stored.
+  fn cell_value(
+    &self,
+    field: Option<&Field>,
+    cell: Option<serde_json::Value>,
+  ) -> serde_json::Value {
+    let cell = match cell {
+      Some(cell) => cell,
+      None => return serde_json::Value::Null,
+    };
+    let field = match field {
+      Some(field) => field,
+      None => return cell.get(CELL_DATA).cloned().unwrap_or_default(),
+    };
+    let cell: Cell = match serde_json::from_value(cell) {
+      Ok(cell) => cell,
+      Err(err) => {
+        tracing::warn!("invalid cell in the history of field {}: {}", field.id, err);
+        return serde_json::Value::Null;
+      },
+    };
+    let field_type = FieldType::from(field.field_type);
+    match self.type_option_reader_by_id.get(&field.id) {
+      Some(reader) => reader.json_cell(&cell),
+      None if is_raw_cell_field_type(&field_type) => raw_cell_json(&field_type, &cell),
+      None => serde_json::Value::Null,
+    }
+  }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_database::entity::FieldType;
+use collab_database::rows::RowId;
+use database::row_schedule::{
+  claim_due_row_schedule_runs, delete_database_row_schedule as delete_row_schedule,
+  insert_database_row_schedule, select_database_row_schedule, select_database_row_schedule_runs,
+  select_database_row_schedules, update_database_row_schedule, update_row_schedule_run_attempt,
+  AFRowScheduleRunJob,
+};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseRowSchedule, AFDatabaseRowScheduleRun, AFInsertDatabaseRowSchedule, AFRowScheduleRule,
+  AFRowScheduleRunStatus, AFUpdateDatabaseRowSchedule,
+};
+use sqlx::PgPool;
+use tokio::time::interval;
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use super::database_webhook::DatabaseWebhookNotifier;
+use super::ops::insert_database_row_with_id;
+use super::utils::{field_by_name_uniq, get_database_body};
+use crate::biz::ai::database_field::AIFieldScheduler;
+
+/// Interval of looking for the runs of row schedules queued by appflowy-worker.
+const ROW_SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const RUN_BATCH_SIZE: i64 = 20;
+/// Runs being made aren't claimed again before the lease expires.
+const RUN_LEASE_SECS: i64 = 300;
+const MAX_RUN_ATTEMPTS: i32 = 5;
+const RUN_RETRY_BASE_SECS: i64 = 30;
+const ROW_SCHEDULE_RUN_LIST_LIMIT: i64 = 100;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub async fn create_database_row_schedule(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  params: AFInsertDatabaseRowSchedule,
+) -> Result<AFDatabaseRowSchedule, AppError> {
+  validate_row_schedule_name(&params.name)?;
+  validate_row_schedule_rule(&params.rule)?;
+  validate_relative_dates(
+    collab_storage,
+    workspace_id,
+    database_id,
+    &params.relative_dates,
+  )
+  .await?;
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let next_run_at = params.rule.next_occurrence(Utc::now());
+  insert_database_row_schedule(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    &params,
+    next_run_at,
+    uid,
+  )
+  .await
+}
+
+pub async fn list_database_row_schedules(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseRowSchedule>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_database_row_schedules(pg_pool, &workspace_uuid, database_id).await
+}
+
+/// Updates the given properties of a schedule. A schedule whose rule is changed, or which is
+/// enabled again, is moved to the first run of its rule from now on, so that the runs missed while
+/// it was disabled aren't made.
+pub async fn update_database_row_schedule_settings(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  schedule_id: &Uuid,
+  params: AFUpdateDatabaseRowSchedule,
+) -> Result<AFDatabaseRowSchedule, AppError> {
+  if let Some(name) = &params.name {
+    validate_row_schedule_name(name)?;
+  }
+  if let Some(rule) = &params.rule {
+    validate_row_schedule_rule(rule)?;
+  }
+  if let Some(relative_dates) = &params.relative_dates {
+    validate_relative_dates(collab_storage, workspace_id, database_id, relative_dates).await?;
+  }
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let schedule =
+    select_database_row_schedule(pg_pool, &workspace_uuid, database_id, schedule_id).await?;
+  let reschedule = params.rule.is_some() || (params.enabled == Some(true) && !schedule.enabled);
+  let next_run_at = if reschedule {
+    params
+      .rule
+      .as_ref()
+      .unwrap_or(&schedule.rule)
+      .next_occurrence(Utc::now())
+  } else {
+    None
+  };
+  update_database_row_schedule(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    schedule_id,
+    params.name.as_deref(),
+    params.rule.as_ref(),
+    params.cells.as_ref(),
+    params.relative_dates.as_ref(),
+    params.missed_run_policy,
+    params.enabled,
+    reschedule,
+    next_run_at,
+  )
+  .await
+}
+
+pub async fn delete_database_row_schedule(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  schedule_id: &Uuid,
+) -> Result<(), AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  delete_row_schedule(pg_pool, &workspace_uuid, database_id, schedule_id).await
+}
+
+pub async fn list_database_row_schedule_runs(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  schedule_id: &Uuid,
+) -> Result<Vec<AFDatabaseRowScheduleRun>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_database_row_schedule_runs(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    schedule_id,
+    ROW_SCHEDULE_RUN_LIST_LIMIT,
+  )
+  .await
+}
+
+fn validate_row_schedule_name(name: &str) -> Result<(), AppError> {
+  if name.trim().is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The name of a row schedule can't be empty".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+fn validate_row_schedule_rule(rule: &AFRowScheduleRule) -> Result<(), AppError> {
+  rule.validate().map_err(AppError::InvalidRequest)
+}
+
+/// Checks that the relative dates of a schedule are set to date fields of the database.
+async fn validate_relative_dates(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  relative_dates: &HashMap<String, i64>,
+) -> Result<(), AppError> {
+  if relative_dates.is_empty() {
+    return Ok(());
+  }
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let fields = db_body.fields.get_all_fields(&db_collab.transact());
+  let field_by_name = field_by_name_uniq(fields.clone());
+  for field_ref in relative_dates.keys() {
+    let field = fields
+      .iter()
+      .find(|field| &field.id == field_ref)
+      .or_else(|| field_by_name.get(field_ref))
+      .ok_or_else(|| AppError::InvalidRequest(format!("Field not found: {}", field_ref)))?;
+    if FieldType::from(field.field_type) != FieldType::DateTime {
+      return Err(AppError::InvalidRequest(format!(
+        "Relative dates can only be set to date fields: {}",
+        field_ref
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Creates the rows of the runs of row schedules, which are queued by appflowy-worker when they are
+/// due. Rows are created like the rows inserted through the REST API, so that the clients editing
+/// the database receive them.
+///
+/// The id of the row of a run is generated along with the run, so that the row is created once
+/// when creating it is retried, ie. when the server stopped before the run was marked as created.
+pub fn spawn_row_schedule_runner(
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  ai_field_scheduler: AIFieldScheduler,
+  database_webhook_notifier: DatabaseWebhookNotifier,
+) {
+  let runner = RowScheduleRunner {
+    pg_pool,
+    collab_storage,
+    ai_field_scheduler,
+    database_webhook_notifier,
+  };
+  tokio::spawn(runner.run());
+}
+
+struct RowScheduleRunner {
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  ai_field_scheduler: AIFieldScheduler,
+  database_webhook_notifier: DatabaseWebhookNotifier,
+}
+
+impl RowScheduleRunner {
+  async fn run(self) {
+    info!("[RowSchedule] runner started");
+    let mut poll_interval = interval(ROW_SCHEDULE_POLL_INTERVAL);
+    loop {
+      poll_interval.tick().await;
+      loop {
+        let jobs =
+          match claim_due_row_schedule_runs(&self.pg_pool, RUN_BATCH_SIZE, RUN_LEASE_SECS).await {
+            Ok(jobs) => jobs,
+            Err(err) => {
+              warn!("[RowSchedule] failed to claim runs: {}", err);
+              break;
+            },
+          };
+        let claimed = jobs.len() as i64;
+        for job in &jobs {
+          self.make_run(job).await;
+        }
+        if claimed < RUN_BATCH_SIZE {
+          break;
+        }
+      }
+    }
+  }
+
+  async fn make_run(&self, job: &AFRowScheduleRunJob) {
+    let result = self.create_run_row(job).await;
+    let attempts = job.attempts + 1;
+    let status = match &result {
+      Ok(_) => AFRowScheduleRunStatus::Created,
+      Err(_) if attempts >= MAX_RUN_ATTEMPTS => AFRowScheduleRunStatus::Failed,
+      Err(_) => AFRowScheduleRunStatus::Pending,
+    };
+    trace!(
+      "[RowSchedule] run {} attempt {}: {:?}",
+      job.run_id,
+      attempts,
+      result
+    );
+    let error = result.err().map(|err| err.to_string());
+    if let Err(err) = update_row_schedule_run_attempt(
+      &self.pg_pool,
+      &job.run_id,
+      status,
+      error.as_deref(),
+      retry_delay_secs(attempts),
+    )
+    .await
+    {
+      warn!(
+        "[RowSchedule] failed to save attempt of run {}: {}",
+        job.run_id, err
+      );
+    }
+  }
+
+  async fn create_run_row(&self, job: &AFRowScheduleRunJob) -> Result<(), AppError> {
+    let workspace_id = job.workspace_id.to_string();
+    if database_has_row(
+      &self.collab_storage,
+      &workspace_id,
+      &job.database_id,
+      &job.row_id,
+    )
+    .await?
+    {
+      // created by a previous attempt
+      return Ok(());
+    }
+
+    let mut cells: HashMap<String, serde_json::Value> = serde_json::from_value(job.cells.clone())?;
+    let relative_dates: HashMap<String, i64> = serde_json::from_value(job.relative_dates.clone())?;
+    for (field_ref, days) in relative_dates {
+      let timestamp = job.scheduled_for.timestamp() + days * SECONDS_PER_DAY;
+      cells.insert(field_ref, serde_json::Value::from(timestamp));
+    }
+    let row_id = insert_database_row_with_id(
+      &self.collab_storage,
+      &self.pg_pool,
+      &workspace_id,
+      &job.database_id,
+      job.created_by,
+      RowId::from(job.row_id.clone()),
+      cells,
+    )
+    .await?;
+    self
+      .ai_field_scheduler
+      .schedule(&workspace_id, &job.database_id, &row_id);
+    self
+      .database_webhook_notifier
+      .notify(&workspace_id, &job.database_id);
+    Ok(())
+  }
+}
+
+fn retry_delay_secs(attempts: i32) -> i64 {
+  let exponent = (attempts - 1).clamp(0, 16) as u32;
+  RUN_RETRY_BASE_SECS * 2_i64.pow(exponent)
+}
+
+async fn database_has_row(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+  row_id: &str,
+) -> Result<bool, AppError> {
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let txn = db_collab.transact();
+  let iid = db_body.get_inline_view_id(&txn);
+  Ok(
+    db_body
+      .views
+      .get_row_orders(&txn, &iid)
+      .iter()
+      .any(|row_order| row_order.id.as_str() == row_id),
+  )
+}
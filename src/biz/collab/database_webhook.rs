@@ -0,0 +1,479 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_database::fields::{Field, TypeOptionCellReader};
+use collab_database::rows::RowDetail;
+use collab_entity::CollabType;
+use database::collab::{select_last_updated_database_row_ids, GetCollabOrigin};
+use database::webhook::{
+  delete_database_webhook as delete_webhook, insert_database_webhook,
+  insert_database_webhook_deliveries, insert_database_webhook_snapshot,
+  select_database_webhook_deliveries, select_database_webhook_snapshot_for_update,
+  select_database_webhooks, select_webhook_databases, update_database_webhook,
+  update_database_webhook_snapshot,
+};
+use infra::net_util::check_public_url;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseRowDetail, AFDatabaseWebhook, AFDatabaseWebhookDelivery, AFDatabaseWebhookEvent,
+  AFDatabaseWebhookPayload, AFInsertDatabaseWebhook, AFUpdateDatabaseWebhook,
+};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::time::interval;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::ops::to_af_database_field;
+use super::utils::{
+  field_by_id_name_uniq, get_database_body, get_latest_collab, get_row_details_serde,
+  type_option_reader_by_id,
+};
+
+/// Interval of looking for the changes of databases which have webhooks, ie. changes made through
+/// the realtime server.
+const WEBHOOK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Rows edited right before the previous poll may not have been persisted yet.
+const WEBHOOK_POLL_MARGIN: chrono::Duration = chrono::Duration::seconds(5);
+const WEBHOOK_SECRET_LEN: usize = 32;
+const WEBHOOK_DELIVERY_LIST_LIMIT: i64 = 100;
+
+/// Creates a webhook of the database. The secret of the webhook is only returned here.
+pub async fn create_database_webhook(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  params: AFInsertDatabaseWebhook,
+  allowed_hosts: &[String],
+) -> Result<AFDatabaseWebhook, AppError> {
+  validate_webhook_url(&params.url, allowed_hosts).await?;
+  validate_webhook_events(&params.events)?;
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let (row_ids, fields) = get_database_snapshot(collab_storage, workspace_id, database_id).await?;
+  let secret = params
+    .secret
+    .filter(|secret| !secret.is_empty())
+    .unwrap_or_else(|| {
+      rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(WEBHOOK_SECRET_LEN)
+        .map(char::from)
+        .collect()
+    });
+
+  let mut txn = pg_pool.begin().await?;
+  let webhook = insert_database_webhook(
+    txn.deref_mut(),
+    &workspace_uuid,
+    database_id,
+    &params.url,
+    &secret,
+    &params.events,
+    uid,
+  )
+  .await?;
+  // changes are found from the state of the database when its first webhook was created
+  insert_database_webhook_snapshot(
+    txn.deref_mut(),
+    &workspace_uuid,
+    database_id,
+    &row_ids,
+    &field_ids(&fields),
+  )
+  .await?;
+  txn.commit().await?;
+  Ok(webhook)
+}
+
+pub async fn list_database_webhooks(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseWebhook>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_database_webhooks(pg_pool, &workspace_uuid, database_id).await
+}
+
+pub async fn update_database_webhook_settings(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  webhook_id: &Uuid,
+  params: AFUpdateDatabaseWebhook,
+  allowed_hosts: &[String],
+) -> Result<AFDatabaseWebhook, AppError> {
+  if let Some(url) = &params.url {
+    validate_webhook_url(url, allowed_hosts).await?;
+  }
+  if let Some(events) = &params.events {
+    validate_webhook_events(events)?;
+  }
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  update_database_webhook(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    webhook_id,
+    params.url.as_deref(),
+    params.events.as_deref(),
+    params.enabled,
+  )
+  .await
+}
+
+pub async fn delete_database_webhook(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  webhook_id: &Uuid,
+) -> Result<(), AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  let mut txn = pg_pool.begin().await?;
+  delete_webhook(&mut txn, &workspace_uuid, database_id, webhook_id).await?;
+  txn.commit().await?;
+  Ok(())
+}
+
+pub async fn list_database_webhook_deliveries(
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  webhook_id: &Uuid,
+) -> Result<Vec<AFDatabaseWebhookDelivery>, AppError> {
+  let workspace_uuid = Uuid::parse_str(workspace_id)?;
+  select_database_webhook_deliveries(
+    pg_pool,
+    &workspace_uuid,
+    database_id,
+    webhook_id,
+    WEBHOOK_DELIVERY_LIST_LIMIT,
+  )
+  .await
+}
+
+/// Checks that the url is an http or https url which can only reach public addresses, so that
+/// webhooks can't be used to reach the services of the deployment, unless their host is allowed.
+/// The addresses are checked again when the deliveries are sent.
+async fn validate_webhook_url(url: &str, allowed_hosts: &[String]) -> Result<(), AppError> {
+  let parsed_url = match url::Url::parse(url) {
+    Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+    _ => {
+      return Err(AppError::InvalidRequest(format!(
+        "The url of a webhook must be an http or https url: {}",
+        url
+      )))
+    },
+  };
+  check_public_url(&parsed_url, allowed_hosts)
+    .await
+    .map_err(|err| {
+      AppError::InvalidRequest(format!("The url of a webhook must be public: {}", err))
+    })
+}
+
+fn validate_webhook_events(events: &[AFDatabaseWebhookEvent]) -> Result<(), AppError> {
+  if events.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "A webhook must be notified of at least one event".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Returns the rows, in the order of the inline view, and the fields of the database.
+async fn get_database_snapshot(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<(Vec<String>, Vec<Field>), AppError> {
+  let (db_collab, db_body) = get_database_body(collab_storage, workspace_id, database_id).await?;
+  let txn = db_collab.transact();
+  let iid = db_body.get_inline_view_id(&txn);
+  let row_ids = db_body
+    .views
+    .get_row_orders(&txn, &iid)
+    .into_iter()
+    .map(|row_order| row_order.id.to_string())
+    .collect();
+  Ok((row_ids, db_body.fields.get_all_fields(&txn)))
+}
+
+fn field_ids(fields: &[Field]) -> Vec<String> {
+  fields.iter().map(|field| field.id.clone()).collect()
+}
+
+/// Looks for the changes of a database in the background, as soon as it's notified of them, ie.
+/// after rows are inserted through the REST API.
+#[derive(Clone)]
+pub struct DatabaseWebhookNotifier {
+  sender: mpsc::UnboundedSender<WebhookDatabase>,
+}
+
+impl DatabaseWebhookNotifier {
+  pub fn notify(&self, workspace_id: &str, database_id: &str) {
+    let database = WebhookDatabase {
+      workspace_id: workspace_id.to_string(),
+      database_id: database_id.to_string(),
+    };
+    if let Err(err) = self.sender.send(database) {
+      warn!(
+        "[Webhook] failed to notify changes of database {}: {}",
+        database_id, err
+      );
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct WebhookDatabase {
+  workspace_id: String,
+  database_id: String,
+}
+
+/// Starts looking for the changes of the databases which have webhooks, and queues the events to
+/// be delivered by appflowy-worker.
+///
+/// Changes are found by comparing each database to its snapshot, so that changes made through the
+/// realtime server are found as well as the ones made through the REST API.
+pub fn spawn_database_webhook_watcher(
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+) -> DatabaseWebhookNotifier {
+  let (sender, receiver) = mpsc::unbounded_channel();
+  let watcher = DatabaseWebhookWatcher {
+    pg_pool,
+    collab_storage,
+  };
+  tokio::spawn(watcher.run(receiver));
+  DatabaseWebhookNotifier { sender }
+}
+
+struct DatabaseWebhookWatcher {
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+}
+
+impl DatabaseWebhookWatcher {
+  async fn run(self, mut receiver: mpsc::UnboundedReceiver<WebhookDatabase>) {
+    info!("[Webhook] database watcher started");
+    let mut poll_interval = interval(WEBHOOK_POLL_INTERVAL);
+    loop {
+      tokio::select! {
+        database = receiver.recv() => match database {
+          Some(database) => self.poll(&database).await,
+          None => break,
+        },
+        _ = poll_interval.tick() => {
+          let databases = match select_webhook_databases(&self.pg_pool).await {
+            Ok(databases) => databases,
+            Err(err) => {
+              warn!("[Webhook] failed to get databases with webhooks: {}", err);
+              continue;
+            },
+          };
+          for database in databases {
+            let database = WebhookDatabase {
+              workspace_id: database.workspace_id.to_string(),
+              database_id: database.database_id,
+            };
+            self.poll(&database).await;
+          }
+        },
+      }
+    }
+  }
+
+  async fn poll(&self, database: &WebhookDatabase) {
+    if let Err(err) = self.poll_database(database).await {
+      warn!(
+        "[Webhook] failed to poll changes of database {}: {}",
+        database.database_id, err
+      );
+    }
+  }
+
+  /// Queues the events of the changes made to the database since its snapshot, and updates the
+  /// snapshot. Databases without webhooks, or being polled by another server, are skipped.
+  async fn poll_database(&self, database: &WebhookDatabase) -> Result<(), AppError> {
+    let polled_at = Utc::now();
+    let mut txn = self.pg_pool.begin().await?;
+    let snapshot =
+      match select_database_webhook_snapshot_for_update(&mut txn, &database.database_id).await? {
+        Some(snapshot) => snapshot,
+        None => return Ok(()),
+      };
+    let (row_ids, fields) = get_database_snapshot(
+      &self.collab_storage,
+      &database.workspace_id,
+      &database.database_id,
+    )
+    .await?;
+
+    let previous_row_ids: HashSet<&str> = snapshot.row_ids.iter().map(String::as_str).collect();
+    let current_row_ids: HashSet<&str> = row_ids.iter().map(String::as_str).collect();
+    let created_row_ids: Vec<&str> = row_ids
+      .iter()
+      .map(String::as_str)
+      .filter(|id| !previous_row_ids.contains(id))
+      .collect();
+    let deleted_row_ids: Vec<&str> = snapshot
+      .row_ids
+      .iter()
+      .map(String::as_str)
+      .filter(|id| !current_row_ids.contains(id))
+      .collect();
+    let kept_row_ids: Vec<String> = row_ids
+      .iter()
+      .filter(|id| previous_row_ids.contains(id.as_str()))
+      .cloned()
+      .collect();
+    let workspace_uuid = Uuid::parse_str(&database.workspace_id)?;
+    let updated_row_ids: Vec<String> = if kept_row_ids.is_empty() {
+      vec![]
+    } else {
+      select_last_updated_database_row_ids(
+        &self.pg_pool,
+        &workspace_uuid,
+        &kept_row_ids,
+        &(snapshot.polled_at - WEBHOOK_POLL_MARGIN),
+      )
+      .await?
+      .into_iter()
+      .map(|row| row.row_id)
+      .collect()
+    };
+    let previous_field_ids: HashSet<&str> = snapshot.field_ids.iter().map(String::as_str).collect();
+    let added_fields: Vec<Field> = fields
+      .iter()
+      .filter(|field| !previous_field_ids.contains(field.id.as_str()))
+      .cloned()
+      .collect();
+
+    let row_events = created_row_ids
+      .iter()
+      .map(|id| (AFDatabaseWebhookEvent::RowCreated, *id))
+      .chain(
+        updated_row_ids
+          .iter()
+          .map(|id| (AFDatabaseWebhookEvent::RowUpdated, id.as_str())),
+      )
+      .chain(
+        deleted_row_ids
+          .iter()
+          .map(|id| (AFDatabaseWebhookEvent::RowDeleted, *id)),
+      );
+    let row_reader = RowReader::new(fields.clone());
+    for (event, row_id) in row_events {
+      let row = self.get_row_detail(database, &row_reader, row_id).await;
+      let payload = webhook_payload(database, event);
+      let payload = AFDatabaseWebhookPayload {
+        row: Some(row),
+        ..payload
+      };
+      insert_database_webhook_deliveries(
+        txn.deref_mut(),
+        &database.database_id,
+        event,
+        &serde_json::to_value(payload)?,
+      )
+      .await?;
+    }
+    for field in added_fields {
+      let event = AFDatabaseWebhookEvent::FieldAdded;
+      let payload = AFDatabaseWebhookPayload {
+        field: Some(to_af_database_field(field)),
+        ..webhook_payload(database, event)
+      };
+      insert_database_webhook_deliveries(
+        txn.deref_mut(),
+        &database.database_id,
+        event,
+        &serde_json::to_value(payload)?,
+      )
+      .await?;
+    }
+
+    update_database_webhook_snapshot(
+      &mut txn,
+      &database.database_id,
+      &row_ids,
+      &field_ids(&fields),
+      polled_at,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(())
+  }
+
+  /// Returns the cells of the row. Rows which can't be loaded, ie. deleted rows, have no cells.
+  async fn get_row_detail(
+    &self,
+    database: &WebhookDatabase,
+    row_reader: &RowReader,
+    row_id: &str,
+  ) -> AFDatabaseRowDetail {
+    let row_detail = get_latest_collab(
+      &self.collab_storage,
+      GetCollabOrigin::Server,
+      &database.workspace_id,
+      row_id,
+      CollabType::DatabaseRow,
+    )
+    .await
+    .ok()
+    .and_then(|collab| RowDetail::from_collab(&collab));
+    AFDatabaseRowDetail {
+      id: row_id.to_string(),
+      cells: row_detail
+        .map(|row_detail| row_reader.cells(row_detail))
+        .unwrap_or_default(),
+    }
+  }
+}
+
+fn webhook_payload(
+  database: &WebhookDatabase,
+  event: AFDatabaseWebhookEvent,
+) -> AFDatabaseWebhookPayload {
+  AFDatabaseWebhookPayload {
+    event_id: Uuid::new_v4(),
+    event,
+    workspace_id: database.workspace_id.clone(),
+    database_id: database.database_id.clone(),
+    created_at: Utc::now(),
+    row: None,
+    field: None,
+  }
+}
+
+/// Reads the cells of rows in the same shape as [super::ops::list_database_row_details], without
+/// the primary values of related rows.
+struct RowReader {
+  field_by_id: HashMap<String, Field>,
+  type_option_reader_by_id: HashMap<String, Box<dyn TypeOptionCellReader>>,
+}
+
+impl RowReader {
+  fn new(fields: Vec<Field>) -> Self {
+    Self {
+      type_option_reader_by_id: type_option_reader_by_id(&fields),
+      field_by_id: field_by_id_name_uniq(fields),
+    }
+  }
+
+  fn cells(&self, row_detail: RowDetail) -> HashMap<String, HashMap<String, serde_json::Value>> {
+    get_row_details_serde(
+      row_detail,
+      &self.field_by_id,
+      &self.type_option_reader_by_id,
+    )
+  }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use app_error::AppError;
 use chrono::DateTime;
@@ -18,6 +18,7 @@ pub fn collab_folder_to_folder_view(
   folder: &Folder,
   max_depth: u32,
   pubished_view_ids: &HashSet<String>,
+  summaries: &HashMap<String, String>,
 ) -> Result<FolderView, AppError> {
   let mut unviewable = HashSet::new();
   let mut my_private_view_ids = HashSet::new();
@@ -44,6 +45,7 @@ pub fn collab_folder_to_folder_view(
     &unviewable,
     &my_private_view_ids,
     pubished_view_ids,
+    summaries,
     false,
     0,
     max_depth,
@@ -63,6 +65,7 @@ fn to_folder_view(
   unviewable: &HashSet<String>,
   private_view_ids: &HashSet<String>,
   published_view_ids: &HashSet<String>,
+  summaries: &HashMap<String, String>,
   parent_is_private: bool,
   depth: u32,
   max_depth: u32,
@@ -110,6 +113,7 @@ fn to_folder_view(
         unviewable,
         private_view_ids,
         published_view_ids,
+        summaries,
         is_private,
         depth + 1,
         max_depth,
@@ -130,6 +134,7 @@ fn to_folder_view(
     created_at: DateTime::from_timestamp(view.created_at, 0).unwrap_or_default(),
     last_edited_time: DateTime::from_timestamp(view.last_edited_time, 0).unwrap_or_default(),
     extra,
+    summary: summaries.get(view_id).cloned(),
     children,
   })
 }
@@ -138,6 +143,7 @@ pub fn section_items_to_favorite_folder_view(
   section_items: &[SectionItem],
   folder: &Folder,
   published_view_ids: &HashSet<String>,
+  summaries: &HashMap<String, String>,
 ) -> Vec<FavoriteFolderView> {
   section_items
     .iter()
@@ -155,6 +161,7 @@ pub fn section_items_to_favorite_folder_view(
           last_edited_time: DateTime::from_timestamp(v.last_edited_time, 0).unwrap_or_default(),
           layout: to_dto_view_layout(&v.layout),
           extra: v.extra.as_ref().map(|e| parse_extra_field_as_json(e)),
+          summary: summaries.get(&v.id).cloned(),
           children: vec![],
         };
         FavoriteFolderView {
@@ -170,6 +177,7 @@ pub fn section_items_to_recent_folder_view(
   section_items: &[SectionItem],
   folder: &Folder,
   published_view_ids: &HashSet<String>,
+  summaries: &HashMap<String, String>,
 ) -> Vec<RecentFolderView> {
   section_items
     .iter()
@@ -187,6 +195,7 @@ pub fn section_items_to_recent_folder_view(
           last_edited_time: DateTime::from_timestamp(v.last_edited_time, 0).unwrap_or_default(),
           layout: to_dto_view_layout(&v.layout),
           extra: v.extra.as_ref().map(|e| parse_extra_field_as_json(e)),
+          summary: summaries.get(&v.id).cloned(),
           children: vec![],
         };
         RecentFolderView {
@@ -218,6 +227,7 @@ pub fn section_items_to_trash_folder_view(
           last_edited_time: DateTime::from_timestamp(v.last_edited_time, 0).unwrap_or_default(),
           layout: to_dto_view_layout(&v.layout),
           extra: v.extra.as_ref().map(|e| parse_extra_field_as_json(e)),
+          summary: None,
           children: vec![],
         };
         TrashFolderView {
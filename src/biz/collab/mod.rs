@@ -1,4 +1,14 @@
+pub mod database_cell;
+pub mod database_computed_field;
+pub mod database_csv;
+pub mod database_form;
+pub mod database_formula;
+pub mod database_query;
+pub mod database_row_history;
+pub mod database_row_schedule;
+pub mod database_webhook;
 pub mod folder_view;
 pub mod ops;
 pub mod publish_outline;
+pub mod reminder;
 pub mod utils;
@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use app_error::AppError;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use appflowy_collaborate::row_history::{diff_row_cells, ROW_HISTORY_MERGE_WINDOW_SECS};
 use chrono::DateTime;
 use chrono::Utc;
 use collab::preclude::Collab;
@@ -15,21 +16,21 @@ use collab_database::rows::Cell;
 use collab_database::rows::CreateRowParams;
 use collab_database::rows::DatabaseRowBody;
 use collab_database::rows::Row;
-use collab_database::rows::RowDetail;
+use collab_database::rows::RowId;
 use collab_database::views::OrderObjectPosition;
 use collab_database::workspace_database::WorkspaceDatabase;
 use collab_database::workspace_database::WorkspaceDatabaseBody;
 use collab_entity::CollabType;
-use collab_entity::EncodedCollab;
 use collab_folder::SectionItem;
 use collab_folder::{CollabOrigin, Folder};
 use database::collab::select_last_updated_database_row_ids;
 use database::collab::select_workspace_database_oid;
 use database::collab::{CollabStorage, GetCollabOrigin};
+use database::index::select_workspace_collab_summaries;
+use database::pg_row::AFDatabaseRowChange;
 use database::publish::select_published_view_ids_for_workspace;
 use database::publish::select_workspace_id_for_publish_namespace;
-use database_entity::dto::QueryCollab;
-use database_entity::dto::QueryCollabResult;
+use database::row_history::{insert_database_row_changes, notify_database_row_change};
 use database_entity::dto::{CollabParams, WorkspaceCollabIdentify};
 use shared_entity::dto::workspace_dto::AFDatabase;
 use shared_entity::dto::workspace_dto::AFDatabaseField;
@@ -44,6 +45,11 @@ use shared_entity::dto::workspace_dto::TrashFolderView;
 use sqlx::PgPool;
 use std::ops::DerefMut;
 
+use crate::biz::collab::database_cell::{
+  fill_related_rows, is_raw_cell_field_type, raw_json_to_cell,
+};
+use crate::biz::collab::database_computed_field::compute_database_rows;
+use crate::biz::collab::database_query::get_row_details;
 use crate::biz::collab::utils::field_by_name_uniq;
 use crate::biz::workspace::ops::broadcast_update;
 use access_control::collab::CollabAccessControl;
@@ -228,10 +234,12 @@ pub async fn get_user_favorite_folder_views(
     .into_iter()
     .filter(|s| !deleted_section_item_ids.contains(&s.id))
     .collect();
+  let summaries = select_workspace_collab_summaries(pg_pool, &workspace_id).await?;
   Ok(section_items_to_favorite_folder_view(
     &favorite_section_items,
     &folder,
     &publish_view_ids,
+    &summaries,
   ))
 }
 
@@ -262,10 +270,12 @@ pub async fn get_user_recent_folder_views(
     .into_iter()
     .map(|id| id.to_string())
     .collect();
+  let summaries = select_workspace_collab_summaries(pg_pool, &workspace_id).await?;
   Ok(section_items_to_recent_folder_view(
     &recent_section_items,
     &folder,
     &publish_view_ids,
+    &summaries,
   ))
 }
 
@@ -310,12 +320,14 @@ pub async fn get_user_workspace_structure(
     .into_iter()
     .map(|id| id.to_string())
     .collect();
+  let summaries = select_workspace_collab_summaries(pg_pool, &workspace_id).await?;
   collab_folder_to_folder_view(
     workspace_id,
     root_view_id,
     &folder,
     depth,
     &publish_view_ids,
+    &summaries,
   )
 }
 
@@ -486,98 +498,153 @@ pub async fn insert_database_row(
   database_uuid_str: &str,
   uid: i64,
   cell_value_by_id: HashMap<String, serde_json::Value>,
+) -> Result<String, AppError> {
+  insert_database_row_with_id(
+    collab_storage,
+    pg_pool,
+    workspace_uuid_str,
+    database_uuid_str,
+    uid,
+    gen_row_id(),
+    cell_value_by_id,
+  )
+  .await
+}
+
+/// Like [insert_database_row], with the id of the new row chosen by the caller.
+pub async fn insert_database_row_with_id(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  uid: i64,
+  row_id: RowId,
+  cell_value_by_id: HashMap<String, serde_json::Value>,
 ) -> Result<String, AppError> {
   // get database types and type options
-  let (mut db_collab, db_body) =
+  let (db_collab, db_body) =
     get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
 
   let all_fields = db_body.fields.get_all_fields(&db_collab.transact());
-  let field_by_id = all_fields.iter().fold(HashMap::new(), |mut acc, field| {
-    acc.insert(field.id.clone(), field.clone());
-    acc
-  });
-  let type_option_reader_by_id = type_option_writer_by_id(&all_fields);
-  let field_by_name = field_by_name_uniq(all_fields);
+  let new_cells = cells_from_json(
+    workspace_uuid_str,
+    database_uuid_str,
+    all_fields,
+    cell_value_by_id,
+  );
 
-  let new_db_row_id = gen_row_id();
-  let mut new_db_row_collab =
-    Collab::new_with_origin(CollabOrigin::Empty, new_db_row_id.clone(), vec![], false);
+  let mut new_db_row_ids = insert_database_rows_with_ids(
+    collab_storage,
+    pg_pool,
+    workspace_uuid_str,
+    database_uuid_str,
+    uid,
+    vec![(row_id, new_cells)],
+  )
+  .await?;
+  Ok(new_db_row_ids.remove(0))
+}
 
-  let new_db_row_body = {
-    let db_row_body = DatabaseRowBody::create(
-      new_db_row_id.clone(),
-      &mut new_db_row_collab,
-      Row::empty(new_db_row_id.clone(), database_uuid_str),
-    );
-    let mut txn = new_db_row_collab.transact_mut();
+/// Appends rows to every view of the database, with the cells of each row keyed by field id.
+/// The rows are saved in a single transaction, along with the database.
+pub async fn insert_database_rows(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  uid: i64,
+  cells_by_row: Vec<HashMap<String, Cell>>,
+) -> Result<Vec<String>, AppError> {
+  let rows = cells_by_row
+    .into_iter()
+    .map(|cells| (gen_row_id(), cells))
+    .collect();
+  insert_database_rows_with_ids(
+    collab_storage,
+    pg_pool,
+    workspace_uuid_str,
+    database_uuid_str,
+    uid,
+    rows,
+  )
+  .await
+}
 
-    // set last_modified and created_at
-    db_row_body.update(&mut txn, |row_update| {
-      row_update
-        .set_last_modified(Utc::now().timestamp())
-        .set_created_at(Utc::now().timestamp());
-    });
+async fn insert_database_rows_with_ids(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  uid: i64,
+  rows: Vec<(RowId, HashMap<String, Cell>)>,
+) -> Result<Vec<String>, AppError> {
+  let (mut db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+
+  let mut new_db_row_ids = Vec::with_capacity(rows.len());
+  let mut row_orders = Vec::with_capacity(rows.len());
+  let mut db_row_ec_v1s = Vec::with_capacity(rows.len());
+  let mut new_db_row_field_ids = Vec::with_capacity(rows.len());
+  for (new_db_row_id, new_cells) in rows {
+    new_db_row_field_ids.push(new_cells.keys().cloned().collect::<Vec<String>>());
+    let mut new_db_row_collab =
+      Collab::new_with_origin(CollabOrigin::Empty, new_db_row_id.clone(), vec![], false);
+
+    let new_db_row_body = {
+      let db_row_body = DatabaseRowBody::create(
+        new_db_row_id.clone(),
+        &mut new_db_row_collab,
+        Row::empty(new_db_row_id.clone(), database_uuid_str),
+      );
+      let mut txn = new_db_row_collab.transact_mut();
+
+      // set last_modified and created_at
+      db_row_body.update(&mut txn, |row_update| {
+        row_update
+          .set_last_modified(Utc::now().timestamp())
+          .set_created_at(Utc::now().timestamp());
+      });
 
-    for (id, serde_val) in cell_value_by_id {
-      let field = match field_by_id.get(&id) {
-        Some(f) => f,
-        // try use field name if id not found
-        None => match field_by_name.get(&id) {
-          Some(f) => f,
-          None => {
-            tracing::warn!(
-              "field not found: {} for database: {}",
-              id,
-              database_uuid_str
-            );
-            continue;
-          },
-        },
-      };
-      let cell_writer = match type_option_reader_by_id.get(&field.id) {
-        Some(cell_writer) => cell_writer,
-        None => {
-          tracing::error!("Failed to get type option writer for field: {}", field.id);
-          continue;
-        },
-      };
-      let new_cell: Cell = cell_writer.convert_json_to_cell(serde_val);
       db_row_body.update(&mut txn, |row_update| {
         row_update.update_cells(|cells_update| {
-          cells_update.insert_cell(&field.id, new_cell);
+          for (field_id, new_cell) in new_cells {
+            cells_update.insert_cell(&field_id, new_cell);
+          }
         });
       });
-    }
-    db_row_body
-  };
-
-  // Create new row order
-  let ts_now = chrono::Utc::now().timestamp();
-  let row_order = db_body
-    .create_row(CreateRowParams {
-      id: new_db_row_id.clone(),
-      database_id: database_uuid_str.to_string(),
-      cells: new_db_row_body
-        .cells(&new_db_row_collab.transact())
-        .unwrap_or_default(),
-      height: 30,
-      visibility: true,
-      row_position: OrderObjectPosition::End,
-      created_at: ts_now,
-      modified_at: ts_now,
-    })
-    .await
-    .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create row: {:?}", e)))?;
-
-  // Prepare new row collab binary to store in postgres
-  let db_row_ec_v1 = collab_to_bin(new_db_row_collab, CollabType::DatabaseRow).await?;
+      db_row_body
+    };
+
+    // Create new row order
+    let ts_now = chrono::Utc::now().timestamp();
+    let row_order = db_body
+      .create_row(CreateRowParams {
+        id: new_db_row_id.clone(),
+        database_id: database_uuid_str.to_string(),
+        cells: new_db_row_body
+          .cells(&new_db_row_collab.transact())
+          .unwrap_or_default(),
+        height: 30,
+        visibility: true,
+        row_position: OrderObjectPosition::End,
+        created_at: ts_now,
+        modified_at: ts_now,
+      })
+      .await
+      .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create row: {:?}", e)))?;
+    row_orders.push(row_order);
+
+    // Prepare new row collab binary to store in postgres
+    db_row_ec_v1s.push(collab_to_bin(new_db_row_collab, CollabType::DatabaseRow).await?);
+    new_db_row_ids.push(new_db_row_id.to_string());
+  }
 
-  // For each database view, add the new row order
+  // For each database view, add the new row orders
   let db_collab_update = {
     let mut txn = db_collab.transact_mut();
     let mut db_views = db_body.views.get_all_views(&txn);
     for db_view in db_views.iter_mut() {
-      db_view.row_orders.push(row_order.clone());
+      db_view.row_orders.extend(row_orders.iter().cloned());
     }
     db_body.views.clear(&mut txn);
     for view in db_views {
@@ -588,24 +655,211 @@ pub async fn insert_database_row(
   };
   let updated_db_collab = collab_to_bin(db_collab, CollabType::Database).await?;
 
+  let workspace_uuid = Uuid::parse_str(workspace_uuid_str)?;
   let mut db_txn = pg_pool.begin().await?;
-  // insert row
+  // insert rows
+  for ((new_db_row_id, db_row_ec_v1), field_ids) in new_db_row_ids
+    .iter()
+    .zip(db_row_ec_v1s)
+    .zip(new_db_row_field_ids)
+  {
+    collab_storage
+      .upsert_new_collab_with_transaction(
+        workspace_uuid_str,
+        &uid,
+        CollabParams {
+          object_id: new_db_row_id.clone(),
+          encoded_collab_v1: db_row_ec_v1.into(),
+          collab_type: CollabType::DatabaseRow,
+          embeddings: None,
+        },
+        &mut db_txn,
+        "inserting new database row from server",
+      )
+      .await?;
+    let change = AFDatabaseRowChange {
+      workspace_id: workspace_uuid,
+      database_id: database_uuid_str.to_string(),
+      row_id: new_db_row_id.clone(),
+      uid,
+      field_ids,
+    };
+    notify_database_row_change(db_txn.deref_mut(), &change).await?;
+  }
+
+  // update database
   collab_storage
     .upsert_new_collab_with_transaction(
       workspace_uuid_str,
       &uid,
       CollabParams {
-        object_id: new_db_row_id.to_string(),
+        object_id: database_uuid_str.to_string(),
+        encoded_collab_v1: updated_db_collab.into(),
+        collab_type: CollabType::Database,
+        embeddings: None,
+      },
+      &mut db_txn,
+      "inserting updated database from server",
+    )
+    .await?;
+
+  db_txn.commit().await?;
+  broadcast_update(collab_storage, database_uuid_str, db_collab_update).await?;
+  Ok(new_db_row_ids)
+}
+
+/// Updates the cells of a row of the database. Like [insert_database_row], the cells are
+/// identified by the id or the name of their field, and their values are converted according to
+/// the type option of the field. The changed cells are recorded in the history of the row.
+pub async fn update_database_row(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  row_id: &str,
+  uid: i64,
+  cell_value_by_id: HashMap<String, serde_json::Value>,
+) -> Result<(), AppError> {
+  let (db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+  let (all_fields, is_database_row) = {
+    let txn = db_collab.transact();
+    let iid = db_body.get_inline_view_id(&txn);
+    let is_database_row = db_body
+      .views
+      .get_row_orders(&txn, &iid)
+      .iter()
+      .any(|row_order| row_order.id.as_str() == row_id);
+    (db_body.fields.get_all_fields(&txn), is_database_row)
+  };
+  if !is_database_row {
+    return Err(AppError::RecordNotFound(format!(
+      "row {} not found in database {}",
+      row_id, database_uuid_str
+    )));
+  }
+  let new_cells = cells_from_json(
+    workspace_uuid_str,
+    database_uuid_str,
+    all_fields,
+    cell_value_by_id,
+  );
+
+  let mut db_row_collab = get_latest_collab(
+    collab_storage,
+    GetCollabOrigin::Server,
+    workspace_uuid_str,
+    row_id,
+    CollabType::DatabaseRow,
+  )
+  .await?;
+  let db_row_body = DatabaseRowBody::open(row_id.to_string().into(), &mut db_row_collab)
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to open row {}: {}", row_id, e)))?;
+  let (db_row_update, cell_changes) = {
+    let mut txn = db_row_collab.transact_mut();
+    let old_cells = db_row_body.cells(&txn).unwrap_or_default();
+    db_row_body.update(&mut txn, |row_update| {
+      row_update
+        .set_last_modified(Utc::now().timestamp())
+        .update_cells(|cells_update| {
+          for (field_id, new_cell) in new_cells {
+            cells_update.insert_cell(&field_id, new_cell);
+          }
+        });
+    });
+    let new_cells = db_row_body.cells(&txn).unwrap_or_default();
+    (
+      txn.encode_update_v1(),
+      diff_row_cells(&old_cells, &new_cells),
+    )
+  };
+  let db_row_ec_v1 = collab_to_bin(db_row_collab, CollabType::DatabaseRow).await?;
+
+  let mut db_txn = pg_pool.begin().await?;
+  collab_storage
+    .upsert_new_collab_with_transaction(
+      workspace_uuid_str,
+      &uid,
+      CollabParams {
+        object_id: row_id.to_string(),
         encoded_collab_v1: db_row_ec_v1.into(),
         collab_type: CollabType::DatabaseRow,
         embeddings: None,
       },
       &mut db_txn,
-      "inserting new database row from server",
+      "inserting updated database row from server",
     )
     .await?;
+  let workspace_uuid = Uuid::parse_str(workspace_uuid_str)?;
+  insert_database_row_changes(
+    &mut db_txn,
+    &workspace_uuid,
+    database_uuid_str,
+    row_id,
+    uid,
+    &cell_changes,
+    Utc::now(),
+    ROW_HISTORY_MERGE_WINDOW_SECS,
+  )
+  .await?;
+  if !cell_changes.is_empty() {
+    let change = AFDatabaseRowChange {
+      workspace_id: workspace_uuid,
+      database_id: database_uuid_str.to_string(),
+      row_id: row_id.to_string(),
+      uid,
+      field_ids: cell_changes
+        .iter()
+        .map(|change| change.field_id.clone())
+        .collect(),
+    };
+    notify_database_row_change(db_txn.deref_mut(), &change).await?;
+  }
+  db_txn.commit().await?;
+  broadcast_update(collab_storage, row_id, db_row_update).await?;
+  Ok(())
+}
 
-  // update database
+/// Deletes a row of the database by removing it from the row orders of every view of the
+/// database.
+pub async fn delete_database_row(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  row_id: &str,
+  uid: i64,
+) -> Result<(), AppError> {
+  let (mut db_collab, db_body) =
+    get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
+
+  let db_collab_update = {
+    let mut txn = db_collab.transact_mut();
+    let mut db_views = db_body.views.get_all_views(&txn);
+    let mut is_database_row = false;
+    for db_view in db_views.iter_mut() {
+      let row_count = db_view.row_orders.len();
+      db_view
+        .row_orders
+        .retain(|row_order| row_order.id.as_str() != row_id);
+      is_database_row |= db_view.row_orders.len() != row_count;
+    }
+    if !is_database_row {
+      return Err(AppError::RecordNotFound(format!(
+        "row {} not found in database {}",
+        row_id, database_uuid_str
+      )));
+    }
+    db_body.views.clear(&mut txn);
+    for view in db_views {
+      db_body.views.insert_view(&mut txn, view);
+    }
+
+    txn.encode_update_v1()
+  };
+  let updated_db_collab = collab_to_bin(db_collab, CollabType::Database).await?;
+
+  let mut db_txn = pg_pool.begin().await?;
   collab_storage
     .upsert_new_collab_with_transaction(
       workspace_uuid_str,
@@ -620,10 +874,64 @@ pub async fn insert_database_row(
       "inserting updated database from server",
     )
     .await?;
-
   db_txn.commit().await?;
   broadcast_update(collab_storage, database_uuid_str, db_collab_update).await?;
-  Ok(new_db_row_id.to_string())
+  Ok(())
+}
+
+/// Converts the json values of cells, by the id or the name of their field, to the cells of a
+/// database row. Values of unknown fields, and invalid values of raw cell fields, are skipped.
+fn cells_from_json(
+  workspace_uuid_str: &str,
+  database_uuid_str: &str,
+  all_fields: Vec<Field>,
+  cell_value_by_id: HashMap<String, serde_json::Value>,
+) -> HashMap<String, Cell> {
+  let field_by_id = all_fields.iter().fold(HashMap::new(), |mut acc, field| {
+    acc.insert(field.id.clone(), field.clone());
+    acc
+  });
+  let type_option_writer_by_id = type_option_writer_by_id(&all_fields);
+  let field_by_name = field_by_name_uniq(all_fields);
+
+  let mut cells = HashMap::with_capacity(cell_value_by_id.len());
+  for (id, serde_val) in cell_value_by_id {
+    let field = match field_by_id.get(&id) {
+      Some(f) => f,
+      // try use field name if id not found
+      None => match field_by_name.get(&id) {
+        Some(f) => f,
+        None => {
+          tracing::warn!(
+            "field not found: {} for database: {}",
+            id,
+            database_uuid_str
+          );
+          continue;
+        },
+      },
+    };
+    let field_type = FieldType::from(field.field_type);
+    if is_raw_cell_field_type(&field_type) {
+      match raw_json_to_cell(&field_type, field.field_type, serde_val, workspace_uuid_str) {
+        Some(new_cell) => {
+          cells.insert(field.id.clone(), new_cell);
+        },
+        None => tracing::warn!("invalid value for field: {} of type {:?}", id, field_type),
+      }
+      continue;
+    }
+    let cell_writer = match type_option_writer_by_id.get(&field.id) {
+      Some(cell_writer) => cell_writer,
+      None => {
+        tracing::error!("Failed to get type option writer for field: {}", field.id);
+        continue;
+      },
+    };
+    let new_cell: Cell = cell_writer.convert_json_to_cell(serde_val);
+    cells.insert(field.id.clone(), new_cell);
+  }
+  cells
 }
 
 pub async fn get_database_fields(
@@ -635,18 +943,18 @@ pub async fn get_database_fields(
     get_database_body(collab_storage, workspace_uuid_str, database_uuid_str).await?;
 
   let all_fields = db_body.fields.get_all_fields(&db_collab.transact());
-  let mut acc = Vec::with_capacity(all_fields.len());
-  for field in all_fields {
-    let field_type = FieldType::from(field.field_type);
-    acc.push(AFDatabaseField {
-      id: field.id,
-      name: field.name,
-      field_type: format!("{:?}", field_type),
-      type_option: type_options_serde(&field.type_options, &field_type),
-      is_primary: field.is_primary,
-    });
+  Ok(all_fields.into_iter().map(to_af_database_field).collect())
+}
+
+pub fn to_af_database_field(field: Field) -> AFDatabaseField {
+  let field_type = FieldType::from(field.field_type);
+  AFDatabaseField {
+    id: field.id,
+    name: field.name,
+    field_type: format!("{:?}", field_type),
+    type_option: type_options_serde(&field.type_options, &field_type),
+    is_primary: field.is_primary,
   }
-  Ok(acc)
 }
 
 // inserts a new field into the database
@@ -739,13 +1047,14 @@ pub async fn list_database_row_ids_updated(
   Ok(updated_row_ids)
 }
 
+/// Returns the cells of the rows by field name. The cells of computed fields are computed first.
 pub async fn list_database_row_details(
   collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
   uid: i64,
   workspace_uuid_str: String,
   database_uuid_str: String,
   row_ids: &[&str],
-  unsupported_field_types: &[FieldType],
 ) -> Result<Vec<AFDatabaseRowDetail>, AppError> {
   let (database_collab, db_body) =
     get_database_body(collab_storage, &workspace_uuid_str, &database_uuid_str).await?;
@@ -754,7 +1063,6 @@ pub async fn list_database_row_details(
     .fields
     .get_all_fields(&database_collab.transact())
     .into_iter()
-    .filter(|field| !unsupported_field_types.contains(&FieldType::from(field.field_type)))
     .collect();
   if all_fields.is_empty() {
     return Ok(vec![]);
@@ -762,50 +1070,39 @@ pub async fn list_database_row_details(
 
   let type_option_reader_by_id = type_option_reader_by_id(&all_fields);
   let field_by_id = field_by_id_name_uniq(all_fields);
-  let query_collabs: Vec<QueryCollab> = row_ids
-    .iter()
-    .map(|id| QueryCollab {
-      object_id: id.to_string(),
-      collab_type: CollabType::DatabaseRow,
-    })
-    .collect();
-  let database_row_details = collab_storage
-    .batch_get_collab(&uid, &workspace_uuid_str, query_collabs, true)
-    .await
+  let row_ids: Vec<String> = row_ids.iter().map(|id| id.to_string()).collect();
+  let mut rows = get_row_details(collab_storage, uid, &workspace_uuid_str, &row_ids).await;
+  if let Err(err) = compute_database_rows(
+    pg_pool,
+    collab_storage,
+    &workspace_uuid_str,
+    &database_uuid_str,
+    &mut rows,
+  )
+  .await
+  {
+    tracing::warn!(
+      "Failed to compute the computed fields of database {}: {}",
+      database_uuid_str,
+      err
+    );
+  }
+  let mut database_row_details = rows
     .into_iter()
-    .flat_map(|(id, result)| match result {
-      QueryCollabResult::Success { encode_collab_v1 } => {
-        let ec = match EncodedCollab::decode_from_bytes(&encode_collab_v1) {
-          Ok(ec) => ec,
-          Err(err) => {
-            tracing::error!("Failed to decode encoded collab: {:?}", err);
-            return None;
-          },
-        };
-        let collab =
-          match Collab::new_with_source(CollabOrigin::Server, &id, ec.into(), vec![], false) {
-            Ok(collab) => collab,
-            Err(err) => {
-              tracing::error!("Failed to create collab: {:?}", err);
-              return None;
-            },
-          };
-        let row_detail = match RowDetail::from_collab(&collab) {
-          Some(row_detail) => row_detail,
-          None => {
-            tracing::error!("Failed to get row detail from collab: {:?}", collab);
-            return None;
-          },
-        };
-        let cells = get_row_details_serde(row_detail, &field_by_id, &type_option_reader_by_id);
-        Some(AFDatabaseRowDetail { id, cells })
-      },
-      QueryCollabResult::Failed { error } => {
-        tracing::warn!("Failed to get collab: {:?}", error);
-        None
-      },
+    .map(|row_detail| {
+      let id = row_detail.row.id.to_string();
+      let cells = get_row_details_serde(row_detail, &field_by_id, &type_option_reader_by_id);
+      AFDatabaseRowDetail { id, cells }
     })
     .collect::<Vec<AFDatabaseRowDetail>>();
+  fill_related_rows(
+    collab_storage,
+    uid,
+    &workspace_uuid_str,
+    &field_by_id,
+    &mut database_row_details,
+  )
+  .await;
 
   Ok(database_row_details)
 }
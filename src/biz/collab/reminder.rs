@@ -0,0 +1,325 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use appflowy_collaborate::reminder::{collab_reminders, parse_timezone};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use collab_entity::CollabType;
+use collab_rt_entity::user::{AFReminderNotification, AFReminderObjectType};
+use database::collab::GetCollabOrigin;
+use database::reminder::{
+  claim_due_reminders, claim_undelivered_reminders, delete_reminders, notify_reminder,
+  reminder_object_type_from_i16, select_user_notification_settings, update_reminder_status,
+  update_reminder_title, upsert_reminders, AFDueReminder, AFReminderStatus, AFUndeliveredReminder,
+};
+use sqlx::PgPool;
+use tokio::time::interval;
+use tracing::{info, trace, warn};
+
+use super::database_cell::primary_values;
+use super::utils::get_latest_collab;
+use crate::mailer::{AFCloudMailer, ReminderMailerParam};
+
+const REMINDER_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const REMINDER_BATCH_SIZE: i64 = 50;
+/// Reminders which are late by more than this, ie. because the server was down, aren't sent.
+const REMINDER_MAX_DELAY_SECS: i64 = 24 * 60 * 60;
+/// Reminders which weren't delivered to an app of the user within this delay are sent by email.
+const REMINDER_EMAIL_DELAY_SECS: i64 = 60;
+const UNTITLED_REMINDER: &str = "Untitled";
+
+/// Sends the reminders of the dates of database rows and documents to the users who set them
+/// when they are due. Reminders are sent to the realtime connections of the user, and by email
+/// when the user has no app open to receive them, unless the user turned them off.
+///
+/// A reminder is checked against its row or document before it's sent, so that a date which was
+/// removed or changed without the realtime server, ie. through the REST API, isn't reminded.
+pub fn spawn_reminder_dispatcher(
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  mailer: AFCloudMailer,
+  appflowy_web_url: Option<String>,
+) {
+  let dispatcher = ReminderDispatcher {
+    pg_pool,
+    collab_storage,
+    mailer,
+    appflowy_web_url,
+  };
+  tokio::spawn(dispatcher.run());
+}
+
+struct ReminderDispatcher {
+  pg_pool: PgPool,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  mailer: AFCloudMailer,
+  appflowy_web_url: Option<String>,
+}
+
+impl ReminderDispatcher {
+  async fn run(self) {
+    info!("[Reminder] dispatcher started");
+    let mut poll_interval = interval(REMINDER_POLL_INTERVAL);
+    loop {
+      poll_interval.tick().await;
+      self.notify_due_reminders().await;
+      self.email_undelivered_reminders().await;
+    }
+  }
+
+  async fn notify_due_reminders(&self) {
+    loop {
+      let reminders = match claim_due_reminders(&self.pg_pool, REMINDER_BATCH_SIZE).await {
+        Ok(reminders) => reminders,
+        Err(err) => {
+          warn!("[Reminder] failed to claim due reminders: {}", err);
+          return;
+        },
+      };
+      let claimed = reminders.len() as i64;
+      for reminder in &reminders {
+        if let Err(err) = self.notify(reminder).await {
+          warn!(
+            "[Reminder] failed to send reminder {}: {}",
+            reminder.id, err
+          );
+        }
+      }
+      if claimed < REMINDER_BATCH_SIZE {
+        return;
+      }
+    }
+  }
+
+  async fn notify(&self, reminder: &AFDueReminder) -> Result<(), AppError> {
+    let settings = select_user_notification_settings(&self.pg_pool, reminder.uid).await?;
+    let late_secs = (Utc::now() - reminder.remind_at).num_seconds();
+    if !settings.reminder_enabled || late_secs > REMINDER_MAX_DELAY_SECS {
+      trace!("[Reminder] skip reminder {}", reminder.id);
+      return update_reminder_status(&self.pg_pool, &reminder.id, AFReminderStatus::Skipped).await;
+    }
+
+    let workspace_id = reminder.workspace_id.to_string();
+    let object_type = reminder_object_type_from_i16(reminder.object_type);
+    let collab_type = match object_type {
+      AFReminderObjectType::DatabaseRow => CollabType::DatabaseRow,
+      AFReminderObjectType::Document => CollabType::Document,
+    };
+    let collab = match get_latest_collab(
+      &self.collab_storage,
+      GetCollabOrigin::Server,
+      &workspace_id,
+      &reminder.object_id,
+      collab_type.clone(),
+    )
+    .await
+    {
+      Ok(collab) => Some(collab),
+      Err(err) if err.is_record_not_found() => None,
+      Err(err) => return Err(err),
+    };
+    let object = collab
+      .as_ref()
+      .and_then(|collab| collab_reminders(&collab_type, collab));
+    let date_reminder = object.as_ref().and_then(|object| {
+      object
+        .reminders
+        .iter()
+        .find(|date_reminder| date_reminder.reminder_id == reminder.reminder_id)
+    });
+    let (object, date_reminder) = match (&object, date_reminder) {
+      (Some(object), Some(date_reminder)) => (object, date_reminder),
+      _ => {
+        trace!("[Reminder] reminder {} was removed", reminder.id);
+        return delete_reminders(
+          &self.pg_pool,
+          &reminder.object_id,
+          &[reminder.reminder_id.clone()],
+        )
+        .await;
+      },
+    };
+    let params = date_reminder.resolve(settings.tz());
+    if params.remind_at != reminder.remind_at {
+      trace!("[Reminder] reminder {} was moved", reminder.id);
+      let mut txn = self.pg_pool.begin().await?;
+      upsert_reminders(
+        &mut txn,
+        &reminder.workspace_id,
+        object_type,
+        &reminder.object_id,
+        object.database_id.as_deref(),
+        reminder.uid,
+        &[params],
+      )
+      .await?;
+      txn.commit().await?;
+      return Ok(());
+    }
+
+    let title = match object_type {
+      AFReminderObjectType::DatabaseRow => self.row_title(reminder).await,
+      AFReminderObjectType::Document => date_reminder.title.clone(),
+    };
+    update_reminder_title(&self.pg_pool, &reminder.id, &title).await?;
+    let notification = AFReminderNotification {
+      reminder_id: reminder.reminder_id.clone(),
+      workspace_id,
+      object_type,
+      object_id: reminder.object_id.clone(),
+      database_id: reminder.database_id.clone(),
+      field_id: reminder.field_id.clone(),
+      block_id: reminder.block_id.clone(),
+      title,
+      event_at: reminder.event_at.timestamp(),
+      include_time: reminder.include_time,
+      remind_at: reminder.remind_at.timestamp(),
+    };
+    notify_reminder(&self.pg_pool, reminder.id, reminder.uid, notification).await
+  }
+
+  /// Returns the primary value of the row of a reminder, empty when it can't be read.
+  async fn row_title(&self, reminder: &AFDueReminder) -> String {
+    let database_id = match &reminder.database_id {
+      Some(database_id) => database_id,
+      None => return String::new(),
+    };
+    match primary_values(
+      &self.collab_storage,
+      reminder.uid,
+      &reminder.workspace_id.to_string(),
+      database_id,
+      &[reminder.object_id.clone()],
+    )
+    .await
+    {
+      Ok(mut primary_values) => primary_values
+        .remove(&reminder.object_id)
+        .unwrap_or_default(),
+      Err(err) => {
+        warn!(
+          "[Reminder] failed to get the primary value of row {}: {}",
+          reminder.object_id, err
+        );
+        String::new()
+      },
+    }
+  }
+
+  async fn email_undelivered_reminders(&self) {
+    loop {
+      let reminders = match claim_undelivered_reminders(
+        &self.pg_pool,
+        REMINDER_BATCH_SIZE,
+        REMINDER_EMAIL_DELAY_SECS,
+      )
+      .await
+      {
+        Ok(reminders) => reminders,
+        Err(err) => {
+          warn!("[Reminder] failed to claim undelivered reminders: {}", err);
+          return;
+        },
+      };
+      let claimed = reminders.len() as i64;
+      for reminder in &reminders {
+        if let Err(err) = self.email(reminder).await {
+          warn!(
+            "[Reminder] failed to email reminder {}: {}",
+            reminder.id, err
+          );
+          if let Err(err) =
+            update_reminder_status(&self.pg_pool, &reminder.id, AFReminderStatus::Skipped).await
+          {
+            warn!(
+              "[Reminder] failed to save status of reminder {}: {}",
+              reminder.id, err
+            );
+          }
+        }
+      }
+      if claimed < REMINDER_BATCH_SIZE {
+        return;
+      }
+    }
+  }
+
+  async fn email(&self, reminder: &AFUndeliveredReminder) -> Result<(), AppError> {
+    if reminder.reminder_email_enabled == Some(false) {
+      return update_reminder_status(&self.pg_pool, &reminder.id, AFReminderStatus::Skipped).await;
+    }
+    let tz = reminder
+      .timezone
+      .as_deref()
+      .map(parse_timezone)
+      .unwrap_or(Tz::UTC);
+    let title = if reminder.title.is_empty() {
+      UNTITLED_REMINDER.to_string()
+    } else {
+      reminder.title.clone()
+    };
+    let param = ReminderMailerParam {
+      title,
+      date: format_reminder_date(reminder.event_at, reminder.include_time, tz),
+      open_url: self.open_url(reminder),
+    };
+    self
+      .mailer
+      .send_reminder(reminder.name.clone(), &reminder.email, param)
+      .await
+      .map_err(AppError::Internal)
+  }
+
+  /// Documents are opened at their page, and rows in their workspace, as the view of their
+  /// database isn't known.
+  fn open_url(&self, reminder: &AFUndeliveredReminder) -> Option<String> {
+    let appflowy_web_url = self.appflowy_web_url.as_ref()?;
+    let url = match reminder_object_type_from_i16(reminder.object_type) {
+      AFReminderObjectType::Document => format!(
+        "{}/app/{}/{}",
+        appflowy_web_url, reminder.workspace_id, reminder.object_id
+      ),
+      AFReminderObjectType::DatabaseRow => {
+        format!("{}/app/{}", appflowy_web_url, reminder.workspace_id)
+      },
+    };
+    Some(url)
+  }
+}
+
+/// Formats the date of a reminder in the time zone of the user, ie. `Friday, December 27, 2024
+/// 14:30 (Europe/Paris)`.
+pub fn format_reminder_date(event_at: DateTime<Utc>, include_time: bool, tz: Tz) -> String {
+  let local = event_at.with_timezone(&tz);
+  if include_time {
+    format!("{} ({})", local.format("%A, %B %-d, %Y %H:%M"), tz.name())
+  } else {
+    local.format("%A, %B %-d, %Y").to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use chrono_tz::Tz;
+
+  use super::format_reminder_date;
+
+  #[test]
+  fn format_reminder_date_test() {
+    let event_at = Utc.with_ymd_and_hms(2024, 12, 27, 13, 30, 0).unwrap();
+    let tz = "Europe/Paris".parse::<Tz>().unwrap();
+    assert_eq!(
+      format_reminder_date(event_at, true, tz),
+      "Friday, December 27, 2024 14:30 (Europe/Paris)"
+    );
+    // dates without time are at midnight of the user
+    let event_at = Utc.with_ymd_and_hms(2024, 12, 26, 23, 0, 0).unwrap();
+    assert_eq!(
+      format_reminder_date(event_at, false, tz),
+      "Friday, December 27, 2024"
+    );
+  }
+}
@@ -27,6 +27,8 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
 
+use super::database_cell::{is_raw_cell_field_type, raw_cell_json};
+
 pub fn get_row_details_serde(
   row_detail: RowDetail,
   field_by_id_name_uniq: &HashMap<String, Field>,
@@ -51,8 +53,10 @@ pub fn get_row_details_serde(
         }
       },
     };
+    let field_type = FieldType::from(field.field_type);
     let cell_value = match type_option_reader_by_id.get(&field.id) {
       Some(tor) => tor.json_cell(&cell),
+      None if is_raw_cell_field_type(&field_type) => raw_cell_json(&field_type, &cell),
       None => {
         tracing::error!("Failed to get type option reader by id: {}", field.id);
         serde_json::Value::Null
@@ -108,12 +112,16 @@ pub fn field_by_id_name_uniq(mut fields: Vec<Field>) -> HashMap<String, Field> {
 }
 
 /// create a map type option writer by field id
+/// fields of raw cell field types have no writer, see [is_raw_cell_field_type]
 pub fn type_option_writer_by_id(
   fields: &[Field],
 ) -> HashMap<String, Box<dyn TypeOptionCellWriter>> {
   let mut type_option_reader_by_id: HashMap<String, Box<dyn TypeOptionCellWriter>> =
     HashMap::with_capacity(fields.len());
   for field in fields {
+    if is_raw_cell_field_type(&FieldType::from(field.field_type)) {
+      continue;
+    }
     let field_id: String = field.id.clone();
     let type_option_reader: Box<dyn TypeOptionCellWriter> = {
       let field_type: &FieldType = &FieldType::from(field.field_type);
@@ -129,12 +137,16 @@ pub fn type_option_writer_by_id(
 }
 
 /// create a map type option reader by field id
+/// fields of raw cell field types have no reader, see [is_raw_cell_field_type]
 pub fn type_option_reader_by_id(
   fields: &[Field],
 ) -> HashMap<String, Box<dyn TypeOptionCellReader>> {
   let mut type_option_reader_by_id: HashMap<String, Box<dyn TypeOptionCellReader>> =
     HashMap::with_capacity(fields.len());
   for field in fields {
+    if is_raw_cell_field_type(&FieldType::from(field.field_type)) {
+      continue;
+    }
     let field_id: String = field.id.clone();
     let type_option_reader: Box<dyn TypeOptionCellReader> = {
       let field_type: &FieldType = &FieldType::from(field.field_type);
@@ -1,4 +1,5 @@
 pub mod access_request;
+pub mod ai;
 pub mod chat;
 pub mod collab;
 pub mod data_import;
@@ -1,11 +1,19 @@
 use crate::api::metrics::RequestMetrics;
-use app_error::ErrorCode;
+use anyhow::anyhow;
+use app_error::{AppError, ErrorCode};
 use appflowy_ai_client::client::AppFlowyAIClient;
 use appflowy_ai_client::dto::{
   EmbeddingEncodingFormat, EmbeddingInput, EmbeddingModel, EmbeddingOutput, EmbeddingRequest,
 };
 
-use database::index::{search_documents, SearchDocumentParams};
+use appflowy_collaborate::indexer::IndexerProvider;
+use database::ai_usage::check_workspace_ai_quota;
+use database::index::{
+  search_documents, select_collab_index_errors, select_workspace_index_counts, SearchDocumentItem,
+  SearchDocumentParams,
+};
+use database::workspace::select_workspace_settings;
+use database_entity::dto::AFWorkspaceIndexStatus;
 use shared_entity::dto::search_dto::{
   SearchContentType, SearchDocumentRequest, SearchDocumentResponseItem,
 };
@@ -22,37 +30,9 @@ pub async fn search_document(
   request: SearchDocumentRequest,
   metrics: &RequestMetrics,
 ) -> Result<Vec<SearchDocumentResponseItem>, AppResponseError> {
-  let embeddings = ai_client
-    .embeddings(EmbeddingRequest {
-      input: EmbeddingInput::String(request.query.clone()),
-      model: EmbeddingModel::TextEmbedding3Small.to_string(),
-      chunk_size: 500,
-      encoding_format: EmbeddingEncodingFormat::Float,
-      dimensions: EmbeddingModel::TextEmbedding3Small.default_dimensions(),
-    })
-    .await
-    .map_err(|e| AppResponseError::new(ErrorCode::Internal, e.to_string()))?;
-  let total_tokens = embeddings.total_tokens as u32;
-  metrics.record_search_tokens_used(&workspace_id, total_tokens);
-  tracing::info!(
-    "workspace {} OpenAI API search tokens used: {}",
-    workspace_id,
-    total_tokens
-  );
-
-  let embedding = embeddings
-    .data
-    .first()
-    .ok_or_else(|| AppResponseError::new(ErrorCode::Internal, "OpenAI returned no embeddings"))?;
-  let embedding = match &embedding.embedding {
-    EmbeddingOutput::Float(vector) => vector.iter().map(|&v| v as f32).collect(),
-    EmbeddingOutput::Base64(_) => {
-      return Err(AppResponseError::new(
-        ErrorCode::Internal,
-        "OpenAI returned embeddings in unsupported format",
-      ))
-    },
-  };
+  check_workspace_ai_quota(pg_pool, &workspace_id).await?;
+  let (embedding, total_tokens) =
+    embed_query(ai_client, &workspace_id, &request.query, metrics).await?;
 
   let mut tx = pg_pool
     .begin()
@@ -66,6 +46,7 @@ pub async fn search_document(
       limit: request.limit.unwrap_or(10) as i32,
       preview: request.preview_size.unwrap_or(500) as i32,
       embedding,
+      object_ids: vec![],
     },
     total_tokens,
   )
@@ -89,7 +70,105 @@ pub async fn search_document(
         preview: item.content_preview,
         created_by: item.created_by,
         created_at: item.created_at,
+        summary: item.summary,
       })
       .collect(),
   )
 }
+
+/// Returns the document fragments of a workspace most relevant to a chat question, which the user
+/// has access to. If `object_ids` is not empty, only these documents are searched.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_chat_context(
+  pg_pool: &PgPool,
+  ai_client: &AppFlowyAIClient,
+  uid: i64,
+  workspace_id: Uuid,
+  question: &str,
+  object_ids: Vec<String>,
+  limit: i32,
+  metrics: &RequestMetrics,
+) -> Result<Vec<SearchDocumentItem>, AppError> {
+  let (embedding, total_tokens) = embed_query(ai_client, &workspace_id, question, metrics).await?;
+  let mut tx = pg_pool.begin().await?;
+  let results = search_documents(
+    &mut tx,
+    SearchDocumentParams {
+      user_id: uid,
+      workspace_id,
+      limit,
+      preview: CHAT_CONTEXT_FRAGMENT_LEN,
+      embedding,
+      object_ids,
+    },
+    total_tokens,
+  )
+  .await?;
+  tx.commit().await?;
+  Ok(results)
+}
+
+/// Maximum number of characters of a single document fragment passed as a chat context.
+const CHAT_CONTEXT_FRAGMENT_LEN: i32 = 2000;
+
+async fn embed_query(
+  ai_client: &AppFlowyAIClient,
+  workspace_id: &Uuid,
+  query: &str,
+  metrics: &RequestMetrics,
+) -> Result<(Vec<f32>, u32), AppError> {
+  let embeddings = ai_client
+    .embeddings(EmbeddingRequest {
+      input: EmbeddingInput::String(query.to_string()),
+      model: EmbeddingModel::TextEmbedding3Small.to_string(),
+      chunk_size: 500,
+      encoding_format: EmbeddingEncodingFormat::Float,
+      dimensions: EmbeddingModel::TextEmbedding3Small.default_dimensions(),
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.into()))?;
+  let total_tokens = embeddings.total_tokens as u32;
+  metrics.record_search_tokens_used(workspace_id, total_tokens);
+  tracing::info!(
+    "workspace {} OpenAI API search tokens used: {}",
+    workspace_id,
+    total_tokens
+  );
+
+  let embedding = embeddings
+    .data
+    .first()
+    .ok_or_else(|| AppError::Internal(anyhow!("OpenAI returned no embeddings")))?;
+  match &embedding.embedding {
+    EmbeddingOutput::Float(vector) => {
+      Ok((vector.iter().map(|&v| v as f32).collect(), total_tokens))
+    },
+    EmbeddingOutput::Base64(_) => Err(AppError::Internal(anyhow!(
+      "OpenAI returned embeddings in unsupported format"
+    ))),
+  }
+}
+
+/// Maximum number of per-object indexing errors returned as part of [AFWorkspaceIndexStatus].
+const MAX_INDEX_ERRORS: i64 = 100;
+
+pub async fn get_workspace_index_status(
+  pg_pool: &PgPool,
+  indexer_provider: &IndexerProvider,
+  workspace_id: Uuid,
+) -> Result<AFWorkspaceIndexStatus, AppResponseError> {
+  let disabled = select_workspace_settings(pg_pool, &workspace_id)
+    .await?
+    .map(|settings| settings.disable_search_indexing)
+    .unwrap_or(false);
+  let counts = select_workspace_index_counts(pg_pool, &workspace_id).await?;
+  let errors = select_collab_index_errors(pg_pool, &workspace_id, MAX_INDEX_ERRORS).await?;
+  Ok(AFWorkspaceIndexStatus {
+    disabled,
+    indexed: counts.indexed,
+    pending: counts.pending,
+    failed: counts.failed,
+    errors,
+    rebuild: indexer_provider.rebuild_progress(&workspace_id).await?,
+  })
+}
@@ -0,0 +1,27 @@
+use app_error::AppError;
+use database::reminder::{select_user_notification_settings, upsert_user_notification_settings};
+use shared_entity::dto::auth_dto::{
+  AFUserNotificationSettings, UpdateUserNotificationSettingsParams,
+};
+use sqlx::PgPool;
+
+pub async fn get_user_notification_settings(
+  pg_pool: &PgPool,
+  uid: i64,
+) -> Result<AFUserNotificationSettings, AppError> {
+  select_user_notification_settings(pg_pool, uid).await
+}
+
+/// Updates the given notification settings of the user. Pending reminders of dates without time
+/// are moved to the new time zone of the user.
+pub async fn update_user_notification_settings(
+  pg_pool: &PgPool,
+  uid: i64,
+  params: UpdateUserNotificationSettingsParams,
+) -> Result<AFUserNotificationSettings, AppError> {
+  params.validate().map_err(AppError::InvalidRequest)?;
+  let mut txn = pg_pool.begin().await?;
+  let settings = upsert_user_notification_settings(&mut txn, uid, &params).await?;
+  txn.commit().await?;
+  Ok(settings)
+}
@@ -31,6 +31,7 @@ use collab_database::views::{
 };
 use collab_database::workspace_database::{NoPersistenceDatabaseCollabService, WorkspaceDatabase};
 use collab_database::{database::DatabaseBody, rows::RowId};
+use collab_document::blocks::DocumentData;
 use collab_document::document::Document;
 use collab_document::document_data::default_document_data;
 use collab_entity::{CollabType, EncodedCollab};
@@ -38,6 +39,7 @@ use collab_folder::hierarchy_builder::NestedChildViewBuilder;
 use collab_folder::{timestamp, CollabOrigin, Folder};
 use collab_rt_entity::user::RealtimeUser;
 use database::collab::{select_workspace_database_oid, CollabStorage, GetCollabOrigin};
+use database::index::select_collab_summary;
 use database::publish::select_published_view_ids_for_workspace;
 use database::user::select_web_user_from_uid;
 use database_entity::dto::{CollabParams, QueryCollab, QueryCollabResult};
@@ -217,11 +219,18 @@ pub async fn create_page(
 fn prepare_default_document_collab_param() -> Result<CollabParams, AppError> {
   let object_id = Uuid::new_v4().to_string();
   let document_data = default_document_data(&object_id);
+  prepare_document_collab_param(object_id, document_data)
+}
+
+fn prepare_document_collab_param(
+  object_id: String,
+  document_data: DocumentData,
+) -> Result<CollabParams, AppError> {
   let document = Document::create(&object_id, document_data)
-    .map_err(|err| AppError::Internal(anyhow!("Failed to create default document: {}", err)))?;
+    .map_err(|err| AppError::Internal(anyhow!("Failed to create document: {}", err)))?;
   let encoded_collab_v1 = document
     .encode_collab()
-    .map_err(|err| AppError::Internal(anyhow!("Failed to encode default document: {}", err)))?
+    .map_err(|err| AppError::Internal(anyhow!("Failed to encode document: {}", err)))?
     .encode_to_bytes()?;
   Ok(CollabParams {
     object_id: object_id.clone(),
@@ -731,7 +740,52 @@ async fn create_document_page(
   name: Option<&str>,
 ) -> Result<Page, AppError> {
   let default_document_collab_params = prepare_default_document_collab_param()?;
-  let view_id = default_document_collab_params.object_id.clone();
+  insert_document_page(
+    pg_pool,
+    collab_storage,
+    uid,
+    workspace_id,
+    parent_view_id,
+    name,
+    default_document_collab_params,
+  )
+  .await
+}
+
+/// Creates a document page with the given content under the parent view.
+pub async fn create_document_page_with_data(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: Uuid,
+  parent_view_id: &str,
+  name: Option<&str>,
+  document_data: DocumentData,
+) -> Result<Page, AppError> {
+  let document_collab_params =
+    prepare_document_collab_param(Uuid::new_v4().to_string(), document_data)?;
+  insert_document_page(
+    pg_pool,
+    collab_storage,
+    uid,
+    workspace_id,
+    parent_view_id,
+    name,
+    document_collab_params,
+  )
+  .await
+}
+
+async fn insert_document_page(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: Uuid,
+  parent_view_id: &str,
+  name: Option<&str>,
+  document_collab_params: CollabParams,
+) -> Result<Page, AppError> {
+  let view_id = document_collab_params.object_id.clone();
   let collab_origin = GetCollabOrigin::User { uid };
   let mut folder =
     get_latest_collab_folder(collab_storage, collab_origin, &workspace_id.to_string()).await?;
@@ -751,7 +805,7 @@ async fn create_document_page(
     .upsert_new_collab_with_transaction(
       &workspace_id.to_string(),
       &uid,
-      default_document_collab_params,
+      document_collab_params,
       &mut transaction,
       &action,
     )
@@ -1112,6 +1166,9 @@ pub async fn get_page_view_collab(
     created_at: DateTime::from_timestamp(view.created_at, 0).unwrap_or_default(),
     last_edited_time: DateTime::from_timestamp(view.last_edited_time, 0).unwrap_or_default(),
     extra: view.extra.as_ref().map(|e| parse_extra_field_as_json(e)),
+    summary: select_collab_summary(pg_pool, view_id)
+      .await?
+      .map(|summary| summary.summary),
     children: vec![],
   };
   let page_collab_data = match view.layout {
@@ -1,6 +1,7 @@
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use database::{
   collab::GetCollabOrigin,
+  index::select_workspace_collab_summaries,
   publish::{
     insert_non_orginal_workspace_publish_namespace, select_all_published_collab_info,
     select_default_published_view_id, select_default_published_view_id_for_namespace,
@@ -202,6 +203,7 @@ pub async fn get_workspace_publish_namespace(
 }
 
 pub async fn list_collab_publish_info(
+  pg_pool: &PgPool,
   publish_collab_store: &dyn PublishedCollabStore,
   collab_storage: &CollabAccessControlStorage,
   workspace_id: &Uuid,
@@ -216,15 +218,18 @@ pub async fn list_collab_publish_info(
   let publish_infos = publish_collab_store
     .list_collab_publish_info(workspace_id)
     .await?;
+  let mut summaries = select_workspace_collab_summaries(pg_pool, workspace_id).await?;
 
   let mut publish_info_views: Vec<PublishInfoView> = Vec::with_capacity(publish_infos.len());
   for publish_info in publish_infos {
     let view_id = publish_info.view_id.to_string();
+    let summary = summaries.remove(&view_id);
     match folder.get_view(&view_id) {
       Some(view) => {
         publish_info_views.push(PublishInfoView {
           view: to_dto_folder_view_miminal(&view),
           info: publish_info,
+          summary,
         });
       },
       None => {
@@ -236,6 +241,7 @@ pub async fn list_collab_publish_info(
             ..Default::default()
           },
           info: publish_info,
+          summary,
         });
       },
     };
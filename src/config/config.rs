@@ -8,6 +8,7 @@ use serde::Deserialize;
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
 
 use infra::env_util::{get_env_var, get_env_var_opt};
+use infra::net_util::{parse_hosts, parse_ip_ranges, IpRange};
 use mailer::config::MailerSetting;
 
 #[derive(Clone, Debug)]
@@ -27,6 +28,10 @@ pub struct Config {
   pub mailer: MailerSetting,
   pub apple_oauth: AppleOAuthSetting,
   pub appflowy_web_url: Option<String>,
+  pub captcha: CaptchaSetting,
+  pub webhook: WebhookSetting,
+  /// Proxies trusted to report the address of the clients, in the `X-Real-IP` header.
+  pub trusted_proxies: Vec<IpRange>,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -140,6 +145,21 @@ pub struct GrpcHistorySetting {
   pub addrs: String,
 }
 
+/// Service verifying the CAPTCHA tokens of published form submissions. Any service with a
+/// siteverify endpoint compatible with hCaptcha or Turnstile can be used.
+#[derive(Clone, Debug)]
+pub struct CaptchaSetting {
+  pub verify_url: Option<String>,
+  pub secret: Secret<String>,
+}
+
+/// Database webhooks can only be sent to public addresses, unless their host is allowed, ie. a
+/// service of the deployment.
+#[derive(Clone, Debug)]
+pub struct WebhookSetting {
+  pub allowed_hosts: Vec<String>,
+}
+
 #[derive(Clone, Debug)]
 pub struct CollabSetting {
   pub group_persistence_interval_secs: u64,
@@ -271,6 +291,14 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       client_secret: get_env_var("APPFLOWY_APPLE_OAUTH_CLIENT_SECRET", "").into(),
     },
     appflowy_web_url: get_env_var_opt("APPFLOWY_WEB_URL"),
+    captcha: CaptchaSetting {
+      verify_url: get_env_var_opt("APPFLOWY_CAPTCHA_VERIFY_URL"),
+      secret: get_env_var("APPFLOWY_CAPTCHA_SECRET", "").into(),
+    },
+    webhook: WebhookSetting {
+      allowed_hosts: parse_hosts(&get_env_var("APPFLOWY_WEBHOOK_ALLOWED_HOSTS", "")),
+    },
+    trusted_proxies: parse_ip_ranges(&get_env_var("APPFLOWY_TRUSTED_PROXIES", ""))?,
   };
   Ok(config)
 }
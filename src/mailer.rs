@@ -5,6 +5,7 @@ pub const WORKSPACE_INVITE_TEMPLATE_NAME: &str = "workspace_invite";
 pub const WORKSPACE_ACCESS_REQUEST_TEMPLATE_NAME: &str = "workspace_access_request";
 pub const WORKSPACE_ACCESS_REQUEST_APPROVED_NOTIFICATION_TEMPLATE_NAME: &str =
   "workspace_access_request_approved_notification";
+pub const REMINDER_TEMPLATE_NAME: &str = "reminder";
 
 #[derive(Clone)]
 pub struct AFCloudMailer(Mailer);
@@ -84,6 +85,25 @@ impl AFCloudMailer {
       )
       .await
   }
+
+  pub async fn send_reminder(
+    &self,
+    recipient_name: Option<String>,
+    email: &str,
+    param: ReminderMailerParam,
+  ) -> Result<(), anyhow::Error> {
+    let subject = format!("Reminder: {}", param.title);
+    self
+      .0
+      .send_email_template(
+        recipient_name,
+        email,
+        REMINDER_TEMPLATE_NAME,
+        param,
+        &subject,
+      )
+      .await
+  }
 }
 
 async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
@@ -94,6 +114,7 @@ async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
   let access_request_approved_notification_template = include_str!(
     "../assets/mailer_templates/build_production/access_request_approved_notification.html"
   );
+  let reminder_template = include_str!("../assets/mailer_templates/build_production/reminder.html");
   let template_strings = HashMap::from([
     (WORKSPACE_INVITE_TEMPLATE_NAME, workspace_invite_template),
     (
@@ -104,6 +125,7 @@ async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
       WORKSPACE_ACCESS_REQUEST_APPROVED_NOTIFICATION_TEMPLATE_NAME,
       access_request_approved_notification_template,
     ),
+    (REMINDER_TEMPLATE_NAME, reminder_template),
   ]);
 
   for (template_name, template_string) in template_strings {
@@ -143,3 +165,11 @@ pub struct WorkspaceAccessRequestApprovedMailerParam {
   pub workspace_member_count: i64,
   pub launch_workspace_url: String,
 }
+
+#[derive(serde::Serialize)]
+pub struct ReminderMailerParam {
+  pub title: String,
+  /// Date of the reminder, formatted in the time zone of the user.
+  pub date: String,
+  pub open_url: Option<String>,
+}
@@ -13,10 +13,11 @@ use uuid::Uuid;
 use access_control::metrics::AccessControlMetrics;
 use app_error::AppError;
 use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_collaborate::ai_stream::AIStreamListener;
 use appflowy_collaborate::collab::cache::CollabCache;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use appflowy_collaborate::indexer::IndexerProvider;
-use appflowy_collaborate::metrics::CollabMetrics;
+use appflowy_collaborate::metrics::{CollabMetrics, EmbeddingMetrics};
 use appflowy_collaborate::CollabRealtimeMetrics;
 use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
 use database::user::{select_all_uid_uuid, select_uid_from_uuid};
@@ -26,6 +27,9 @@ use snowflake::Snowflake;
 use tonic_proto::history::history_client::HistoryClient;
 
 use crate::api::metrics::{AppFlowyWebMetrics, PublishedCollabMetrics, RequestMetrics};
+use crate::biz::ai::database_field::AIFieldScheduler;
+use crate::biz::collab::database_form::{CaptchaVerifier, FormSubmissionLimiter};
+use crate::biz::collab::database_webhook::DatabaseWebhookNotifier;
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::PublishedCollabStore;
 use crate::config::config::Config;
@@ -49,12 +53,17 @@ pub struct AppState {
   pub published_collab_store: Arc<dyn PublishedCollabStore>,
   pub bucket_client: AwsS3BucketClientImpl,
   pub pg_listeners: Arc<PgListeners>,
+  pub ai_stream_listener: Arc<AIStreamListener>,
   pub metrics: AppMetrics,
   pub gotrue_admin: GoTrueAdmin,
   pub mailer: AFCloudMailer,
   pub ai_client: AppFlowyAIClient,
   pub grpc_history_client: Arc<Mutex<HistoryClient<tonic::transport::Channel>>>,
   pub indexer_provider: Arc<IndexerProvider>,
+  pub ai_field_scheduler: AIFieldScheduler,
+  pub database_webhook_notifier: DatabaseWebhookNotifier,
+  pub captcha_verifier: CaptchaVerifier,
+  pub form_submission_limiter: FormSubmissionLimiter,
 }
 
 impl AppState {
@@ -125,6 +134,7 @@ pub struct AppMetrics {
   pub collab_metrics: Arc<CollabMetrics>,
   pub published_collab_metrics: Arc<PublishedCollabMetrics>,
   pub appflowy_web_metrics: Arc<AppFlowyWebMetrics>,
+  pub embedding_metrics: Arc<EmbeddingMetrics>,
 }
 
 impl Default for AppMetrics {
@@ -142,6 +152,7 @@ impl AppMetrics {
     let collab_metrics = Arc::new(CollabMetrics::register(&mut registry));
     let published_collab_metrics = Arc::new(PublishedCollabMetrics::register(&mut registry));
     let appflowy_web_metrics = Arc::new(AppFlowyWebMetrics::register(&mut registry));
+    let embedding_metrics = Arc::new(EmbeddingMetrics::register(&mut registry));
     Self {
       registry: Arc::new(registry),
       request_metrics,
@@ -150,6 +161,7 @@ impl AppMetrics {
       collab_metrics,
       published_collab_metrics,
       appflowy_web_metrics,
+      embedding_metrics,
     }
   }
 }
@@ -1,14 +1,19 @@
 use crate::ai_test::util::read_text_from_asset;
 
+use app_error::ErrorCode;
 use assert_json_diff::{assert_json_eq, assert_json_include};
 use client_api::entity::{QuestionStream, QuestionStreamValue};
 use client_api_test::{ai_test_enabled, TestClient};
+use collab_rt_entity::chat::ChatEvent;
+use database_entity::dto::AFRole;
 use futures_util::StreamExt;
 use serde_json::json;
 use shared_entity::dto::chat_dto::{
-  ChatMessageMetadata, ChatRAGData, CreateChatMessageParams, CreateChatParams, MessageCursor,
-  UpdateChatParams,
+  ChatDatabaseSource, ChatMessageMetadata, ChatParticipantParams, ChatParticipantRole, ChatRAGData,
+  CreateChatMessageParams, CreateChatParams, MessageCursor, RemoveChatParticipantsParams,
+  UpdateChatParams, UpsertChatParticipantsParams,
 };
+use std::time::Duration;
 
 #[tokio::test]
 async fn update_chat_settings_test() {
@@ -294,6 +299,103 @@ async fn generate_chat_message_answer_test() {
   assert!(!answer.is_empty());
 }
 
+#[tokio::test]
+async fn realtime_chat_message_answer_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateChatParams {
+    chat_id: chat_id.clone(),
+    name: "my realtime chat".to_string(),
+    rag_ids: vec![],
+  };
+  test_client
+    .api_client
+    .create_chat(&workspace_id, params)
+    .await
+    .unwrap();
+  let params = CreateChatMessageParams::new_user("Hello");
+  let question = test_client
+    .api_client
+    .create_question(&workspace_id, &chat_id, params)
+    .await
+    .unwrap();
+  let answer_stream = test_client
+    .api_client
+    .stream_answer_v2_realtime(
+      &test_client.ws_client,
+      &workspace_id,
+      &chat_id,
+      question.message_id,
+    )
+    .await
+    .unwrap();
+  let answer = tokio::time::timeout(Duration::from_secs(30), collect_answer(answer_stream))
+    .await
+    .unwrap();
+  assert!(!answer.is_empty());
+}
+
+#[tokio::test]
+async fn chat_with_database_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let databases = test_client
+    .api_client
+    .list_databases(&workspace_id)
+    .await
+    .unwrap();
+  let todo_db = &databases[0];
+  let source = ChatDatabaseSource {
+    database_id: todo_db.id.clone(),
+    view_id: todo_db.views[0].view_id.clone(),
+    name: todo_db.views[0].name.clone(),
+  };
+
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateChatParams {
+    chat_id: chat_id.clone(),
+    name: "database chat".to_string(),
+    rag_ids: vec![],
+  };
+  test_client
+    .api_client
+    .create_chat(&workspace_id, params)
+    .await
+    .unwrap();
+  let params = CreateChatMessageParams::new_user("Which tasks are done?")
+    .with_metadata(ChatMessageMetadata::from(source.clone()));
+  let question = test_client
+    .api_client
+    .create_question(&workspace_id, &chat_id, params)
+    .await
+    .unwrap();
+
+  // the answer is built from the rows of the database and references it in its metadata
+  let answer = test_client
+    .api_client
+    .get_answer(&workspace_id, &chat_id, question.message_id)
+    .await
+    .unwrap();
+  assert!(answer.content.contains(&source.name));
+  let metadata: Vec<ChatMessageMetadata> = serde_json::from_value(answer.meta_data).unwrap();
+  assert_eq!(metadata.len(), 1);
+  assert_eq!(metadata[0].database_source(), Some(source.clone()));
+  // rows are linked from the answer
+  if let Some(row_id) = metadata[0]
+    .database_answer()
+    .and_then(|answer| answer.row_ids.first().cloned())
+  {
+    assert!(answer.content.contains(&row_id));
+  }
+}
+
 #[tokio::test]
 async fn create_chat_context_test() {
   if !ai_test_enabled() {
@@ -404,3 +506,148 @@ async fn collect_answer(mut stream: QuestionStream) -> String {
   }
   answer
 }
+
+#[tokio::test]
+async fn shared_chat_participant_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+
+  let owner = TestClient::new_user().await;
+  let member = TestClient::new_user().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &member, AFRole::Member)
+    .await
+    .unwrap();
+
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  owner
+    .api_client
+    .create_chat(
+      &workspace_id,
+      CreateChatParams {
+        chat_id: chat_id.clone(),
+        name: "shared chat".to_string(),
+        rag_ids: vec![],
+      },
+    )
+    .await
+    .unwrap();
+
+  // until it's shared, only its creator can access the chat
+  let error = member
+    .api_client
+    .get_chat_participants(&workspace_id, &chat_id)
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::NotEnoughPermissions);
+
+  // share the chat with the other member as a viewer
+  let member_uid = member.uid().await;
+  owner
+    .api_client
+    .upsert_chat_participants(
+      &workspace_id,
+      &chat_id,
+      UpsertChatParticipantsParams {
+        participants: vec![ChatParticipantParams {
+          uid: member_uid,
+          role: ChatParticipantRole::Viewer,
+        }],
+      },
+    )
+    .await
+    .unwrap();
+  let participants = member
+    .api_client
+    .get_chat_participants(&workspace_id, &chat_id)
+    .await
+    .unwrap();
+  assert_eq!(participants.items.len(), 2);
+
+  // viewers can't ask questions
+  let error = member
+    .api_client
+    .create_question(
+      &workspace_id,
+      &chat_id,
+      CreateChatMessageParams::new_user("hello"),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::NotEnoughPermissions);
+
+  // messages created by the owner are broadcast to the other participants
+  let mut chat_event_rx = member.ws_client.subscribe_chat_events();
+  let question = owner
+    .api_client
+    .create_question(
+      &workspace_id,
+      &chat_id,
+      CreateChatMessageParams::new_user("hello"),
+    )
+    .await
+    .unwrap();
+  let event = tokio::time::timeout(Duration::from_secs(10), chat_event_rx.recv())
+    .await
+    .unwrap()
+    .unwrap();
+  match event {
+    ChatEvent::NewMessage(event) => {
+      assert_eq!(event.chat_id, chat_id);
+      assert_eq!(event.message_id, question.message_id);
+      assert_eq!(event.content, "hello");
+    },
+    _ => panic!("unexpected chat event: {}", event),
+  }
+
+  // the last owner can be neither demoted nor removed
+  let owner_uid = owner.uid().await;
+  let error = owner
+    .api_client
+    .upsert_chat_participants(
+      &workspace_id,
+      &chat_id,
+      UpsertChatParticipantsParams {
+        participants: vec![ChatParticipantParams {
+          uid: owner_uid,
+          role: ChatParticipantRole::Member,
+        }],
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::InvalidRequest);
+  let error = owner
+    .api_client
+    .remove_chat_participants(
+      &workspace_id,
+      &chat_id,
+      RemoveChatParticipantsParams {
+        uids: vec![owner_uid],
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::InvalidRequest);
+
+  // removed participants lose access to the chat
+  owner
+    .api_client
+    .remove_chat_participants(
+      &workspace_id,
+      &chat_id,
+      RemoveChatParticipantsParams {
+        uids: vec![member_uid],
+      },
+    )
+    .await
+    .unwrap();
+  let error = member
+    .api_client
+    .get_chat_participants(&workspace_id, &chat_id)
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::NotEnoughPermissions);
+}
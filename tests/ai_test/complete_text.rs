@@ -1,6 +1,13 @@
+use app_error::ErrorCode;
 use appflowy_ai_client::dto::{AIModel, CompletionType};
+use bytes::Bytes;
 use client_api_test::{ai_test_enabled, TestClient};
-use shared_entity::dto::ai_dto::CompleteTextParams;
+use futures_util::{Stream, StreamExt};
+use shared_entity::dto::ai_dto::{
+  CompleteTextParams, CreateAIPromptTemplateParams, UpdateAIPromptTemplateParams,
+};
+use shared_entity::response::AppResponseError;
+use std::time::Duration;
 
 #[tokio::test]
 async fn improve_writing_test() {
@@ -23,3 +30,124 @@ async fn improve_writing_test() {
     .unwrap();
   assert!(!resp.text.is_empty());
 }
+
+#[tokio::test]
+async fn realtime_completion_text_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let params = CompleteTextParams::new_with_completion_type(
+    "I feel hungry".to_string(),
+    CompletionType::MakeLonger,
+  );
+  let stream = test_client
+    .api_client
+    .stream_completion_text_realtime(&test_client.ws_client, &workspace_id, params)
+    .await
+    .unwrap();
+  let text = collect_text(stream).await;
+  assert!(!text.is_empty());
+}
+
+#[tokio::test]
+async fn resume_realtime_completion_text_after_reconnect_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let params = CompleteTextParams::new_with_completion_type(
+    "I feel hungry".to_string(),
+    CompletionType::MakeLonger,
+  );
+  let stream = test_client
+    .api_client
+    .stream_completion_text_realtime(&test_client.ws_client, &workspace_id, params)
+    .await
+    .unwrap();
+  // the chunks streamed while disconnected are fetched after reconnecting
+  test_client.disconnect().await;
+  tokio::time::sleep(Duration::from_secs(2)).await;
+  test_client.reconnect().await;
+  let text = tokio::time::timeout(Duration::from_secs(30), collect_text(stream))
+    .await
+    .unwrap();
+  assert!(!text.is_empty());
+}
+
+async fn collect_text(stream: impl Stream<Item = Result<Bytes, AppResponseError>>) -> String {
+  let chunks: Vec<Bytes> = stream.map(|chunk| chunk.unwrap()).collect::<Vec<_>>().await;
+  String::from_utf8(chunks.concat()).unwrap()
+}
+
+#[tokio::test]
+async fn prompt_template_crud_test() {
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+
+  let template = test_client
+    .api_client
+    .create_ai_prompt_template(
+      &workspace_id,
+      CreateAIPromptTemplateParams {
+        name: "Meeting notes".to_string(),
+        description: "Convert to meeting notes".to_string(),
+        system_prompt: "You write meeting notes for {{page_title}}".to_string(),
+        user_prompt: Some("{{text}}".to_string()),
+      },
+    )
+    .await
+    .unwrap();
+
+  // template names are unique in a workspace
+  let error = test_client
+    .api_client
+    .create_ai_prompt_template(
+      &workspace_id,
+      CreateAIPromptTemplateParams {
+        name: "Meeting notes".to_string(),
+        description: "".to_string(),
+        system_prompt: "duplicate".to_string(),
+        user_prompt: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::RecordAlreadyExists);
+
+  let updated = test_client
+    .api_client
+    .update_ai_prompt_template(
+      &workspace_id,
+      &template.template_id,
+      UpdateAIPromptTemplateParams {
+        user_prompt: Some("".to_string()),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(updated.name, "Meeting notes");
+  assert_eq!(updated.user_prompt, None);
+
+  let templates = test_client
+    .api_client
+    .list_ai_prompt_templates(&workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(templates.items.len(), 1);
+
+  test_client
+    .api_client
+    .delete_ai_prompt_template(&workspace_id, &template.template_id)
+    .await
+    .unwrap();
+  let error = test_client
+    .api_client
+    .get_ai_prompt_template(&workspace_id, &template.template_id)
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::RecordNotFound);
+}
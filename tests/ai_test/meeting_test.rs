@@ -0,0 +1,161 @@
+use app_error::ErrorCode;
+use client_api_test::{ai_test_enabled, TestClient};
+use collab_database::entity::FieldType;
+use shared_entity::dto::ai_dto::{CreateMeetingNotesParams, MeetingTaskDatabase};
+use shared_entity::dto::workspace_dto::AFInsertDatabaseField;
+
+const TRANSCRIPT: &str = "WEBVTT
+
+1
+00:00:00.000 --> 00:00:04.000
+<v Lucas>We agreed to release version two on Friday.
+
+2
+00:00:04.000 --> 00:00:08.000
+<v Nathan>I will write the release notes by Thursday.
+
+3
+00:00:08.000 --> 00:00:12.000
+<v Lucas>Great, and I will update the website.
+";
+
+async fn general_space_id(test_client: &TestClient, workspace_id: &str) -> String {
+  let folder_view = test_client
+    .api_client
+    .get_workspace_folder(workspace_id, Some(2), None)
+    .await
+    .unwrap();
+  folder_view
+    .children
+    .into_iter()
+    .find(|v| v.name == "General")
+    .unwrap()
+    .view_id
+}
+
+#[tokio::test]
+async fn create_meeting_notes_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let parent_view_id = general_space_id(&test_client, &workspace_id).await;
+  let todo_db = test_client
+    .api_client
+    .list_databases(&workspace_id)
+    .await
+    .unwrap()
+    .remove(0);
+  test_client
+    .api_client
+    .add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: "Assignee".to_string(),
+        field_type: FieldType::RichText.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+  let notes = test_client
+    .api_client
+    .create_meeting_notes(
+      &workspace_id,
+      CreateMeetingNotesParams {
+        parent_view_id: parent_view_id.clone(),
+        transcript: TRANSCRIPT.to_string(),
+        format: None,
+        name: Some("Release meeting".to_string()),
+        task_database: Some(MeetingTaskDatabase {
+          database_id: todo_db.id.clone(),
+          assignee_field: Some("assignee".to_string()),
+          due_date_field: None,
+        }),
+      },
+    )
+    .await
+    .unwrap();
+  assert!(!notes.summary.is_empty());
+
+  let folder_view = test_client
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(3), None)
+    .await
+    .unwrap();
+  let general_space = folder_view
+    .children
+    .iter()
+    .find(|v| v.view_id == parent_view_id)
+    .unwrap();
+  assert!(general_space
+    .children
+    .iter()
+    .any(|v| v.view_id == notes.view_id && v.name == "Release meeting"));
+
+  // every action item is a row of the task database
+  let row_ids: Vec<String> = test_client
+    .api_client
+    .list_database_row_ids(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|row| row.id)
+    .collect();
+  for item in &notes.action_items {
+    assert!(row_ids.contains(item.row_id.as_ref().unwrap()));
+  }
+}
+
+#[tokio::test]
+async fn create_meeting_notes_with_invalid_params_test() {
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let parent_view_id = general_space_id(&test_client, &workspace_id).await;
+  let todo_db = test_client
+    .api_client
+    .list_databases(&workspace_id)
+    .await
+    .unwrap()
+    .remove(0);
+
+  let err = test_client
+    .api_client
+    .create_meeting_notes(
+      &workspace_id,
+      CreateMeetingNotesParams {
+        parent_view_id: parent_view_id.clone(),
+        transcript: "WEBVTT\n\nNOTE nothing was said\n".to_string(),
+        format: None,
+        name: None,
+        task_database: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  // the fields of the task database are checked before the transcript is sent to the AI service
+  let err = test_client
+    .api_client
+    .create_meeting_notes(
+      &workspace_id,
+      CreateMeetingNotesParams {
+        parent_view_id,
+        transcript: TRANSCRIPT.to_string(),
+        format: None,
+        name: None,
+        task_database: Some(MeetingTaskDatabase {
+          database_id: todo_db.id,
+          assignee_field: Some("Unknown field".to_string()),
+          due_date_field: None,
+        }),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
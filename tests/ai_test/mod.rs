@@ -1,5 +1,6 @@
 mod chat_test;
 mod complete_text;
 // mod local_ai_test;
+mod meeting_test;
 mod summarize_row;
 mod util;
@@ -1,6 +1,17 @@
+use app_error::ErrorCode;
 use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
 use collab_database::entity::FieldType;
-use shared_entity::dto::workspace_dto::AFInsertDatabaseField;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseComputation, AFDatabaseRowFilter, AFDatabaseRowSort, AFDatabaseWebhookEvent,
+  AFImportDatabaseCsv, AFInsertDatabaseAIField, AFInsertDatabaseComputedField,
+  AFInsertDatabaseField, AFInsertDatabaseRowSchedule, AFInsertDatabaseWebhook, AFMissedRunPolicy,
+  AFRollupAggregation, AFRowScheduleFrequency, AFRowScheduleRule, AFSelectFilterCondition,
+  AFTextFilterCondition, AFUpdateDatabaseAIField, AFUpdateDatabaseComputedField,
+  AFUpdateDatabaseRowSchedule, AFUpdateDatabaseWebhook, QueryDatabaseRowHistoryParams,
+  QueryDatabaseRowsParams,
+};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[tokio::test]
 async fn database_fields_crud() {
@@ -106,49 +117,1058 @@ async fn database_fields_crud() {
 }
 
 #[tokio::test]
-async fn database_fields_unsupported_field_type() {
+async fn database_relation_checklist_and_media_fields() {
   let (c, _user) = generate_unique_registered_user_client().await;
   let workspace_id = workspace_id_from_client(&c).await;
   let databases = c.list_databases(&workspace_id).await.unwrap();
   assert_eq!(databases.len(), 1);
   let todo_db = &databases[0];
 
-  let my_rel_field_id = {
-    c.add_database_field(
+  let my_rel_field_id = c
+    .add_database_field(
       &workspace_id,
       &todo_db.id,
       &AFInsertDatabaseField {
         name: "MyRelationCol".to_string(),
         field_type: FieldType::Relation.into(),
+        type_option_data: Some(serde_json::json!({ "database_id": todo_db.id })),
+      },
+    )
+    .await
+    .unwrap();
+  for (name, field_type) in [
+    ("MyChecklistCol", FieldType::Checklist),
+    ("MyMediaCol", FieldType::Media),
+  ] {
+    c.add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: name.to_string(),
+        field_type: field_type.into(),
         ..Default::default()
       },
     )
     .await
-    .unwrap()
+    .unwrap();
+  }
+
+  let related_row_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({ "Description": "related task" }),
+    )
+    .await
+    .unwrap();
+  let new_row_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({
+          "Description": "my task 123",
+          my_rel_field_id: [related_row_id],
+          "MyChecklistCol": [{ "name": "write tests", "checked": true }, "ship it"],
+          "MyMediaCol": [{ "name": "cat.png", "parent_dir": "dir", "file_id": "cat_id" }],
+      }),
+    )
+    .await
+    .unwrap();
+
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&new_row_id])
+    .await
+    .unwrap();
+  assert_eq!(row_details.len(), 1);
+  let cells = &row_details[0].cells;
+  assert_eq!(
+    cells["MyRelationCol"]["data"],
+    serde_json::json!([{ "row_id": related_row_id, "primary_value": "related task" }])
+  );
+  let checklist = &cells["MyChecklistCol"]["data"];
+  assert_eq!(checklist["items"][0]["name"], "write tests");
+  assert_eq!(checklist["items"][0]["checked"], true);
+  assert_eq!(checklist["items"][1]["name"], "ship it");
+  assert_eq!(checklist["items"][1]["checked"], false);
+  assert_eq!(checklist["percentage"], 0.5);
+  let media = &cells["MyMediaCol"]["data"];
+  assert_eq!(media[0]["name"], "cat.png");
+  assert_eq!(
+    media[0]["url"],
+    format!("/api/file_storage/{}/v1/blob/dir/cat_id", workspace_id)
+  );
+
+  // check all items, keeping their ids, and remove the relation
+  let mut items = checklist["items"].clone();
+  for item in items.as_array_mut().unwrap() {
+    item["checked"] = serde_json::json!(true);
+  }
+  c.update_database_item(
+    &workspace_id,
+    &todo_db.id,
+    &new_row_id,
+    &serde_json::json!({ "MyChecklistCol": { "items": items.clone() }, "MyRelationCol": [] }),
+  )
+  .await
+  .unwrap();
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&new_row_id])
+    .await
+    .unwrap();
+  let cells = &row_details[0].cells;
+  assert_eq!(cells["MyRelationCol"]["data"], serde_json::json!([]));
+  assert_eq!(cells["MyChecklistCol"]["data"]["items"], items);
+  assert_eq!(cells["MyChecklistCol"]["data"]["percentage"], 1.0);
+}
+
+#[tokio::test]
+async fn database_row_update_and_delete() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let my_num_field_id = c
+    .add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: "MyNumberColumn".to_string(),
+        field_type: FieldType::Number.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  let row_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({
+          "Description": "my task 123",
+          "Status": "To Do",
+          my_num_field_id.clone(): 123,
+      }),
+    )
+    .await
+    .unwrap();
+
+  // update by field name and by field id, leaving other cells unchanged
+  c.update_database_item(
+    &workspace_id,
+    &todo_db.id,
+    &row_id,
+    &serde_json::json!({
+        "Status": "Done",
+        my_num_field_id: 456,
+    }),
+  )
+  .await
+  .unwrap();
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&row_id])
+    .await
+    .unwrap();
+  let row_detail = &row_details[0];
+  assert_eq!(row_detail.cells["Description"]["data"], "my task 123");
+  assert_eq!(row_detail.cells["Status"]["data"], "Done");
+  assert_eq!(row_detail.cells["MyNumberColumn"]["data"], "456");
+
+  c.delete_database_item(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap();
+  let row_ids = c
+    .list_database_row_ids(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert!(row_ids.iter().all(|row| row.id != row_id));
+
+  // the row is no longer part of the database
+  let err = c
+    .update_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &row_id,
+      &serde_json::json!({ "Status": "To Do" }),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+  let err = c
+    .delete_database_item(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_row_history() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let uid = c.get_profile().await.unwrap().uid;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let row_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({
+          "Description": "weekly report",
+          "Status": "To Do",
+      }),
+    )
+    .await
+    .unwrap();
+  for cells in [
+    serde_json::json!({ "Status": "Doing" }),
+    serde_json::json!({ "Status": "Done" }),
+    serde_json::json!({ "Description": "monthly report" }),
+  ] {
+    c.update_database_item(&workspace_id, &todo_db.id, &row_id, &cells)
+      .await
+      .unwrap();
+  }
+
+  // consecutive changes of the status are merged into one change
+  let history = c
+    .get_database_row_history(&workspace_id, &todo_db.id, &row_id, &Default::default())
+    .await
+    .unwrap();
+  assert_eq!(history.len(), 2);
+  assert_eq!(history[0].field_name.as_deref(), Some("Description"));
+  assert_eq!(history[0].old_value, "weekly report");
+  assert_eq!(history[0].new_value, "monthly report");
+  assert_eq!(history[1].field_name.as_deref(), Some("Status"));
+  assert_eq!(history[1].old_value, "To Do");
+  assert_eq!(history[1].new_value, "Done");
+  assert!(history.iter().all(|change| change.uid == Some(uid)));
+
+  // the change is dropped when the cell is back to its previous value
+  c.update_database_item(
+    &workspace_id,
+    &todo_db.id,
+    &row_id,
+    &serde_json::json!({ "Description": "weekly report" }),
+  )
+  .await
+  .unwrap();
+  let history = c
+    .get_database_row_history(
+      &workspace_id,
+      &todo_db.id,
+      &row_id,
+      &QueryDatabaseRowHistoryParams {
+        limit: Some(10),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(history.len(), 1);
+  assert_eq!(history[0].field_name.as_deref(), Some("Status"));
+
+  // older changes are returned page by page
+  let history = c
+    .get_database_row_history(
+      &workspace_id,
+      &todo_db.id,
+      &row_id,
+      &QueryDatabaseRowHistoryParams {
+        before: Some(history[0].changed_at),
+        limit: Some(10),
+      },
+    )
+    .await
+    .unwrap();
+  assert!(history.is_empty());
+}
+
+#[tokio::test]
+async fn database_rows_query() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  c.add_database_field(
+    &workspace_id,
+    &todo_db.id,
+    &AFInsertDatabaseField {
+      name: "Estimate".to_string(),
+      field_type: FieldType::Number.into(),
+      ..Default::default()
+    },
+  )
+  .await
+  .unwrap();
+  for estimate in 1..=5 {
+    let status = if estimate % 2 == 0 { "Done" } else { "To Do" };
+    c.add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({
+          "Description": format!("task {}", estimate),
+          "Status": status,
+          "Estimate": estimate,
+      }),
+    )
+    .await
+    .unwrap();
+  }
+
+  // page through the rows whose estimate is at least 2, from the largest
+  let mut params = QueryDatabaseRowsParams {
+    filters: vec![AFDatabaseRowFilter::Number {
+      field: "Estimate".to_string(),
+      gt: None,
+      gte: Some(2.0),
+      lt: None,
+      lte: None,
+    }],
+    sorts: vec![AFDatabaseRowSort {
+      field: "Estimate".to_string(),
+      descending: true,
+    }],
+    limit: Some(3),
+    ..Default::default()
   };
-  {
-    let my_description = "my task 123";
-    let my_status = "To Do";
-    let new_row_id = c
+  let mut estimates = vec![];
+  loop {
+    let result = c
+      .query_database_rows(&workspace_id, &todo_db.id, &params)
+      .await
+      .unwrap();
+    assert_eq!(result.total, 4);
+    estimates.extend(
+      result
+        .rows
+        .iter()
+        .map(|row| row.cells["Estimate"]["data"].as_str().unwrap().to_string()),
+    );
+    match result.next_cursor {
+      Some(cursor) => params.cursor = Some(cursor),
+      None => break,
+    }
+  }
+  assert_eq!(estimates, vec!["5", "4", "3", "2"]);
+
+  // select options are matched by name, text ignoring the case
+  let result = c
+    .query_database_rows(
+      &workspace_id,
+      &todo_db.id,
+      &QueryDatabaseRowsParams {
+        filters: vec![
+          AFDatabaseRowFilter::Select {
+            field: "Status".to_string(),
+            condition: AFSelectFilterCondition::Any,
+            options: vec!["Done".to_string()],
+          },
+          AFDatabaseRowFilter::Text {
+            field: "Description".to_string(),
+            condition: AFTextFilterCondition::StartsWith,
+            value: "TASK".to_string(),
+          },
+        ],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  let descriptions: Vec<&str> = result
+    .rows
+    .iter()
+    .map(|row| row.cells["Description"]["data"].as_str().unwrap())
+    .collect();
+  assert_eq!(descriptions, vec!["task 2", "task 4"]);
+
+  let err = c
+    .query_database_rows(
+      &workspace_id,
+      &todo_db.id,
+      &QueryDatabaseRowsParams {
+        filters: vec![AFDatabaseRowFilter::Empty {
+          field: "Unknown field".to_string(),
+        }],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
+#[tokio::test]
+async fn database_computed_field_crud() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  for name in ["Price", "Quantity"] {
+    c.add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: name.to_string(),
+        field_type: FieldType::Number.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  }
+  c.add_database_field(
+    &workspace_id,
+    &todo_db.id,
+    &AFInsertDatabaseField {
+      name: "Subtasks".to_string(),
+      field_type: FieldType::Relation.into(),
+      type_option_data: Some(serde_json::json!({ "database_id": todo_db.id })),
+    },
+  )
+  .await
+  .unwrap();
+
+  // formulas must parse and reference existing fields
+  for expression in ["{{Price}} *", "{{Price}} * {{UnknownColumn}}"] {
+    let err = c
+      .add_database_computed_field(
+        &workspace_id,
+        &todo_db.id,
+        &AFInsertDatabaseComputedField {
+          name: "Total".to_string(),
+          computation: AFDatabaseComputation::Formula {
+            expression: expression.to_string(),
+          },
+        },
+      )
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest);
+  }
+
+  let total = c
+    .add_database_computed_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseComputedField {
+        name: "Total".to_string(),
+        computation: AFDatabaseComputation::Formula {
+          expression: "{{Price}} * {{Quantity}}".to_string(),
+        },
+      },
+    )
+    .await
+    .unwrap();
+  let subtask_price = c
+    .add_database_computed_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseComputedField {
+        name: "Subtask price".to_string(),
+        computation: AFDatabaseComputation::Rollup {
+          relation_field: "Subtasks".to_string(),
+          target_field: Some("Price".to_string()),
+          aggregation: AFRollupAggregation::Sum,
+        },
+      },
+    )
+    .await
+    .unwrap();
+  let computed_fields = c
+    .list_database_computed_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(computed_fields.len(), 2);
+
+  let mut subtask_ids = vec![];
+  for (price, quantity) in [(2, 3), (5, 4)] {
+    let row_id = c
       .add_database_item(
         &workspace_id,
         &todo_db.id,
-        &serde_json::json!({
-            "Description": my_description,
-            "Status": my_status,
-            "Multiselect": ["social", "news"],
-            my_rel_field_id: "relation_data"
-        }),
+        &serde_json::json!({ "Price": price, "Quantity": quantity }),
       )
       .await
       .unwrap();
+    subtask_ids.push(row_id);
+  }
+  let parent_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({ "Price": 1, "Quantity": 1, "Subtasks": subtask_ids }),
+    )
+    .await
+    .unwrap();
 
-    let row_details = c
-      .list_database_row_details(&workspace_id, &todo_db.id, &[&new_row_id])
+  let row_details = c
+    .list_database_row_details(
+      &workspace_id,
+      &todo_db.id,
+      &[&subtask_ids[0], &subtask_ids[1], &parent_id],
+    )
+    .await
+    .unwrap();
+  let values: Vec<(&str, &str)> = row_details
+    .iter()
+    .map(|row| {
+      (
+        row.cells["Total"]["data"].as_str().unwrap(),
+        row.cells["Subtask price"]["data"]
+          .as_str()
+          .unwrap_or_default(),
+      )
+    })
+    .collect();
+  assert_eq!(values, vec![("6", "0"), ("20", "0"), ("1", "7")]);
+
+  // a formula can't become a rollup
+  let err = c
+    .update_database_computed_field(
+      &workspace_id,
+      &todo_db.id,
+      &total.field_id,
+      &AFUpdateDatabaseComputedField {
+        computation: AFDatabaseComputation::Rollup {
+          relation_field: "Subtasks".to_string(),
+          target_field: None,
+          aggregation: AFRollupAggregation::Count,
+        },
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  c.update_database_computed_field(
+    &workspace_id,
+    &todo_db.id,
+    &subtask_price.field_id,
+    &AFUpdateDatabaseComputedField {
+      computation: AFDatabaseComputation::Rollup {
+        relation_field: "Subtasks".to_string(),
+        target_field: None,
+        aggregation: AFRollupAggregation::Count,
+      },
+    },
+  )
+  .await
+  .unwrap();
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&parent_id])
+    .await
+    .unwrap();
+  assert_eq!(row_details[0].cells["Subtask price"]["data"], "2");
+
+  c.delete_database_computed_field(&workspace_id, &todo_db.id, &total.field_id)
+    .await
+    .unwrap();
+  let computed_fields = c
+    .list_database_computed_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(computed_fields.len(), 1);
+  assert_eq!(computed_fields[0].field_id, subtask_price.field_id);
+  let err = c
+    .delete_database_computed_field(&workspace_id, &todo_db.id, &total.field_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_ai_field_crud() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  // prompts must reference existing fields
+  let err = c
+    .add_database_ai_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseAIField {
+        name: "Summary".to_string(),
+        prompt: "Summarize {{UnknownColumn}}".to_string(),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let ai_field = c
+    .add_database_ai_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseAIField {
+        name: "Summary".to_string(),
+        prompt: "Summarize {{Description}}".to_string(),
+      },
+    )
+    .await
+    .unwrap();
+  let fields = c
+    .get_database_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert!(fields
+    .iter()
+    .any(|field| field.id == ai_field.field_id && field.name == "Summary"));
+
+  // the AI field can't reference itself
+  let err = c
+    .update_database_ai_field(
+      &workspace_id,
+      &todo_db.id,
+      &ai_field.field_id,
+      &AFUpdateDatabaseAIField {
+        prompt: "Shorten {{Summary}}".to_string(),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let updated = c
+    .update_database_ai_field(
+      &workspace_id,
+      &todo_db.id,
+      &ai_field.field_id,
+      &AFUpdateDatabaseAIField {
+        prompt: "Summarize {{Description}} in a single sentence".to_string(),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    updated.prompt,
+    "Summarize {{Description}} in a single sentence"
+  );
+  let ai_fields = c
+    .list_database_ai_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(ai_fields.len(), 1);
+  assert_eq!(ai_fields[0].field_id, ai_field.field_id);
+
+  c.list_database_ai_field_cells(&workspace_id, &todo_db.id, &ai_field.field_id)
+    .await
+    .unwrap();
+
+  c.delete_database_ai_field(&workspace_id, &todo_db.id, &ai_field.field_id)
+    .await
+    .unwrap();
+  assert!(c
+    .list_database_ai_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .is_empty());
+  // the field itself is kept
+  let fields = c
+    .get_database_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert!(fields.iter().any(|field| field.id == ai_field.field_id));
+}
+
+#[tokio::test]
+async fn database_webhook_crud() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let err = c
+    .create_database_webhook(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseWebhook {
+        url: "ftp://localhost/hook".to_string(),
+        secret: None,
+        events: vec![AFDatabaseWebhookEvent::RowCreated],
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+  // only localhost is allowed amongst the non public hosts
+  for url in [
+    "http://169.254.169.254/hook",
+    "http://10.0.0.1/hook",
+    "http://[::1]/hook",
+  ] {
+    let err = c
+      .create_database_webhook(
+        &workspace_id,
+        &todo_db.id,
+        &AFInsertDatabaseWebhook {
+          url: url.to_string(),
+          secret: None,
+          events: vec![AFDatabaseWebhookEvent::RowCreated],
+        },
+      )
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest);
+  }
+  let err = c
+    .create_database_webhook(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseWebhook {
+        url: "http://localhost:9/hook".to_string(),
+        secret: None,
+        events: vec![],
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let webhook = c
+    .create_database_webhook(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseWebhook {
+        url: "http://localhost:9/hook".to_string(),
+        secret: None,
+        events: vec![
+          AFDatabaseWebhookEvent::RowCreated,
+          AFDatabaseWebhookEvent::RowDeleted,
+        ],
+      },
+    )
+    .await
+    .unwrap();
+  assert!(webhook.enabled);
+  assert!(webhook
+    .secret
+    .as_ref()
+    .is_some_and(|secret| !secret.is_empty()));
+  let webhooks = c
+    .list_database_webhooks(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(webhooks.len(), 1);
+  assert_eq!(webhooks[0].webhook_id, webhook.webhook_id);
+  // the secret is only returned when the webhook is created
+  assert!(webhooks[0].secret.is_none());
+
+  let row_id = c
+    .add_database_item(
+      &workspace_id,
+      &todo_db.id,
+      &serde_json::json!({ "Description": "my webhook task" }),
+    )
+    .await
+    .unwrap();
+
+  // the delivery of the new row is queued in the background
+  let mut delivery = None;
+  for _ in 0..30 {
+    let deliveries = c
+      .list_database_webhook_deliveries(&workspace_id, &todo_db.id, &webhook.webhook_id)
       .await
       .unwrap();
-    assert_eq!(row_details.len(), 1);
-    let new_row_detail = &row_details[0];
-    assert!(!new_row_detail.cells.contains_key("MyRelationCol"));
+    delivery = deliveries
+      .into_iter()
+      .find(|delivery| delivery.event == AFDatabaseWebhookEvent::RowCreated);
+    if delivery.is_some() {
+      break;
+    }
+    tokio::time::sleep(Duration::from_secs(1)).await;
   }
+  let delivery = delivery.expect("row.created delivery is queued");
+  assert_eq!(delivery.payload.database_id, todo_db.id);
+  let row = delivery.payload.row.unwrap();
+  assert_eq!(row.id, row_id);
+  assert_eq!(row.cells["Description"]["data"], "my webhook task");
+
+  let updated = c
+    .update_database_webhook(
+      &workspace_id,
+      &todo_db.id,
+      &webhook.webhook_id,
+      &AFUpdateDatabaseWebhook {
+        enabled: Some(false),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert!(!updated.enabled);
+  assert_eq!(updated.url, webhook.url);
+
+  c.delete_database_webhook(&workspace_id, &todo_db.id, &webhook.webhook_id)
+    .await
+    .unwrap();
+  assert!(c
+    .list_database_webhooks(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .is_empty());
+  let err = c
+    .delete_database_webhook(&workspace_id, &todo_db.id, &webhook.webhook_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_row_schedule_crud() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+  c.add_database_field(
+    &workspace_id,
+    &todo_db.id,
+    &AFInsertDatabaseField {
+      name: "Due date".to_string(),
+      field_type: FieldType::DateTime.into(),
+      ..Default::default()
+    },
+  )
+  .await
+  .unwrap();
+
+  let weekly_report = |timezone: &str, relative_date_field: &str| AFInsertDatabaseRowSchedule {
+    name: "Weekly report".to_string(),
+    rule: AFRowScheduleRule {
+      frequency: AFRowScheduleFrequency::Weekly,
+      interval: 1,
+      weekdays: vec![0],
+      month_day: None,
+      hour: 9,
+      minute: 0,
+      timezone: timezone.to_string(),
+      start_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+      end_date: None,
+    },
+    cells: HashMap::from([
+      (
+        "Description".to_string(),
+        serde_json::json!("weekly report"),
+      ),
+      ("Status".to_string(), serde_json::json!("To Do")),
+    ]),
+    relative_dates: HashMap::from([(relative_date_field.to_string(), 3)]),
+    missed_run_policy: AFMissedRunPolicy::RunOnce,
+  };
+
+  let err = c
+    .create_database_row_schedule(
+      &workspace_id,
+      &todo_db.id,
+      &weekly_report("Mars/Olympus", "Due date"),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+  let err = c
+    .create_database_row_schedule(
+      &workspace_id,
+      &todo_db.id,
+      &weekly_report("Europe/Paris", "Description"),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let schedule = c
+    .create_database_row_schedule(
+      &workspace_id,
+      &todo_db.id,
+      &weekly_report("Europe/Paris", "Due date"),
+    )
+    .await
+    .unwrap();
+  assert!(schedule.enabled);
+  assert_eq!(schedule.missed_run_policy, AFMissedRunPolicy::RunOnce);
+  let next_run_at = schedule.next_run_at.unwrap();
+  assert!(next_run_at > chrono::Utc::now());
+  assert_eq!(
+    next_run_at
+      .with_timezone(&chrono_tz::Europe::Paris)
+      .format("%a %H:%M")
+      .to_string(),
+    "Mon 09:00"
+  );
+  let schedules = c
+    .list_database_row_schedules(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(schedules.len(), 1);
+  assert_eq!(schedules[0].schedule_id, schedule.schedule_id);
+  assert_eq!(schedules[0].relative_dates["Due date"], 3);
+
+  // the schedule moves to the first run of its new rule
+  let mut rule = schedule.rule.clone();
+  rule.weekdays = vec![4];
+  let updated = c
+    .update_database_row_schedule(
+      &workspace_id,
+      &todo_db.id,
+      &schedule.schedule_id,
+      &AFUpdateDatabaseRowSchedule {
+        rule: Some(rule),
+        enabled: Some(false),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert!(!updated.enabled);
+  assert_eq!(updated.name, schedule.name);
+  assert_eq!(
+    updated
+      .next_run_at
+      .unwrap()
+      .with_timezone(&chrono_tz::Europe::Paris)
+      .format("%a %H:%M")
+      .to_string(),
+    "Fri 09:00"
+  );
+  assert!(c
+    .list_database_row_schedule_runs(&workspace_id, &todo_db.id, &schedule.schedule_id)
+    .await
+    .unwrap()
+    .is_empty());
+
+  c.delete_database_row_schedule(&workspace_id, &todo_db.id, &schedule.schedule_id)
+    .await
+    .unwrap();
+  assert!(c
+    .list_database_row_schedules(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .is_empty());
+  let err = c
+    .delete_database_row_schedule(&workspace_id, &todo_db.id, &schedule.schedule_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_csv_import_and_export() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let csv = "Name,Status,Price,Due,Done,Multiselect
+Task A,To Do,12.5,2024-12-03,Yes,\"social, news\"
+Task B,Done,not a number,,,
+,,,,,
+Task C,Done,1,2024-12-04T10:00:00Z,no,
+";
+  let result = c
+    .import_database_csv(
+      &workspace_id,
+      &todo_db.id,
+      &AFImportDatabaseCsv {
+        csv: csv.to_string(),
+        column_fields: HashMap::from([("Name".to_string(), "Description".to_string())]),
+        create_fields: true,
+        field_types: HashMap::from([
+          ("Price".to_string(), FieldType::Number.into()),
+          ("Due".to_string(), FieldType::DateTime.into()),
+          ("Done".to_string(), FieldType::Checkbox.into()),
+        ]),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(result.row_ids.len(), 2);
+  assert_eq!(result.created_field_ids.len(), 3);
+  assert_eq!(result.errors.len(), 1);
+  assert_eq!(result.errors[0].line, 3);
+  assert_eq!(result.errors[0].column.as_deref(), Some("Price"));
+
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&result.row_ids[0]])
+    .await
+    .unwrap();
+  let task_a = &row_details[0];
+  assert_eq!(task_a.cells["Description"]["data"], "Task A");
+  assert_eq!(task_a.cells["Status"]["data"], "To Do");
+  assert_eq!(task_a.cells["Price"]["data"], "12.5");
+  assert_eq!(task_a.cells["Due"]["data"]["timestamp"], 1733184000);
+  assert_eq!(task_a.cells["Done"]["data"], true);
+  assert_eq!(task_a.cells["Multiselect"]["data"][0], "social");
+
+  // columns without a field are skipped unless fields are created
+  let result = c
+    .import_database_csv(
+      &workspace_id,
+      &todo_db.id,
+      &AFImportDatabaseCsv {
+        csv: "Description,Unknown\nTask D,ignored\n".to_string(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(result.row_ids.len(), 1);
+  assert!(result.created_field_ids.is_empty());
+
+  // no field is created if none of the records can be imported
+  let result = c
+    .import_database_csv(
+      &workspace_id,
+      &todo_db.id,
+      &AFImportDatabaseCsv {
+        csv: "Description,Weight\nTask F,heavy\n".to_string(),
+        create_fields: true,
+        field_types: HashMap::from([("Weight".to_string(), FieldType::Number.into())]),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert!(result.row_ids.is_empty());
+  assert!(result.created_field_ids.is_empty());
+  assert_eq!(result.errors.len(), 1);
+
+  let err = c
+    .import_database_csv(
+      &workspace_id,
+      &todo_db.id,
+      &AFImportDatabaseCsv {
+        csv: "Name\nTask E\n".to_string(),
+        column_fields: HashMap::from([("Name".to_string(), "Unknown field".to_string())]),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let exported = c
+    .export_database_csv(&workspace_id, &todo_db.id, None)
+    .await
+    .unwrap();
+  let mut lines = exported.lines();
+  let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+  assert!(header.contains(&"Description"));
+  assert!(header.contains(&"Price"));
+  let lines: Vec<&str> = lines.collect();
+  let task_a_line = lines.iter().position(|line| line.starts_with("Task A"));
+  let task_c_line = lines.iter().position(|line| line.starts_with("Task C"));
+  let task_d_line = lines.iter().position(|line| line.starts_with("Task D"));
+  // rows are exported in the order of the view
+  assert!(task_a_line.unwrap() < task_c_line.unwrap());
+  assert!(task_c_line.unwrap() < task_d_line.unwrap());
+  assert!(lines[task_a_line.unwrap()].contains("2024-12-03T00:00:00Z"));
+
+  let err = c
+    .export_database_csv(&workspace_id, &todo_db.id, Some("unknown_view"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
 }
@@ -189,6 +189,59 @@ async fn test_document_indexing_and_search() {
   assert!(preview.contains("Welcome to AppFlowy"));
 }
 
+#[tokio::test]
+async fn test_workspace_index_status_and_rebuild() {
+  let mut test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+
+  let object_id = uuid::Uuid::new_v4().to_string();
+  let tennis_player = create_document_collab(&object_id, "kathryn_tennis_story.md").await;
+  test_client
+    .create_collab_with_data(
+      &workspace_id,
+      &object_id,
+      CollabType::Document,
+      tennis_player.encode_collab().unwrap(),
+    )
+    .await
+    .unwrap();
+
+  let status = test_client
+    .api_client
+    .get_workspace_index_status(&workspace_id)
+    .await
+    .unwrap();
+  assert!(!status.disabled);
+  assert!(status.indexed + status.pending + status.failed >= 1);
+  assert!(status.rebuild.is_none());
+
+  let progress = test_client
+    .api_client
+    .rebuild_workspace_index(&workspace_id)
+    .await
+    .unwrap();
+  assert!(!progress.cancelled);
+
+  let mut rebuild = None;
+  for _ in 0..30 {
+    let status = test_client
+      .api_client
+      .get_workspace_index_status(&workspace_id)
+      .await
+      .unwrap();
+    let progress = status.rebuild.unwrap();
+    if progress.finished_at.is_some() {
+      rebuild = Some(progress);
+      break;
+    }
+    sleep(Duration::from_secs(1)).await;
+  }
+  let rebuild = rebuild.expect("re-indexing should finish in 30 seconds");
+  assert!(!rebuild.cancelled);
+  assert!(rebuild.total >= 1);
+  assert_eq!(rebuild.processed, rebuild.total);
+}
+
 async fn create_document_collab(document_id: &str, file_name: &str) -> Document {
   let file_path = PathBuf::from(format!("tests/search/asset/{}", file_name));
   let md = std::fs::read_to_string(file_path).unwrap();
@@ -1,7 +1,9 @@
 use crate::sql_test::util::{setup_db, test_create_user};
 use database::chat::chat_ops::{
-  delete_chat, get_all_chat_messages, insert_chat, insert_question_message, select_chat,
-  select_chat_messages, select_chat_settings, update_chat_settings,
+  delete_chat, get_all_chat_messages, insert_answer_message, insert_chat, insert_question_message,
+  insert_question_message_in_branch, select_chat, select_chat_message_branches,
+  select_chat_messages, select_chat_settings, update_chat_active_branch, update_chat_settings,
+  ChatMessageParent,
 };
 use serde_json::json;
 use shared_entity::dto::chat_dto::{
@@ -28,6 +30,7 @@ async fn chat_crud_test(pool: PgPool) {
     insert_chat(
       &pool,
       &user.workspace_id,
+      user.uid,
       CreateChatParams {
         chat_id: chat_id.clone(),
         name: "my first chat".to_string(),
@@ -79,6 +82,7 @@ async fn chat_message_crud_test(pool: PgPool) {
     insert_chat(
       &pool,
       &user.workspace_id,
+      user.uid,
       CreateChatParams {
         chat_id: chat_id.clone(),
         name: "my first chat".to_string(),
@@ -201,7 +205,7 @@ async fn chat_setting_test(pool: PgPool) {
     rag_ids: vec!["rag1".to_string(), "rag2".to_string()],
   };
 
-  insert_chat(&pool, &workspace_id, insert_params)
+  insert_chat(&pool, &workspace_id, user.uid, insert_params)
     .await
     .expect("Failed to insert chat");
 
@@ -249,3 +253,126 @@ async fn chat_setting_test(pool: PgPool) {
   );
   assert_eq!(settings.rag_ids, vec!["rag3", "rag4"]);
 }
+
+#[sqlx::test(migrations = false)]
+async fn chat_message_branch_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_uuid = uuid::Uuid::new_v4();
+  let name = user_uuid.to_string();
+  let email = format!("{}@appflowy.io", name);
+  let user = test_create_user(&pool, user_uuid, &email, &name)
+    .await
+    .unwrap();
+
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  insert_chat(
+    &pool,
+    &user.workspace_id,
+    user.uid,
+    CreateChatParams {
+      chat_id: chat_id.clone(),
+      name: "my first chat".to_string(),
+      rag_ids: vec![],
+    },
+  )
+  .await
+  .unwrap();
+
+  let author = ChatAuthor::new(user.uid, ChatAuthorType::Human);
+  let question = insert_question_message(
+    &pool,
+    author.clone(),
+    &chat_id,
+    "question".to_string(),
+    vec![],
+  )
+  .await
+  .unwrap();
+  let answer_1 = insert_answer_message(
+    &pool,
+    ChatAuthor::ai(),
+    &chat_id,
+    "answer 1".to_string(),
+    None,
+    question.message_id,
+  )
+  .await
+  .unwrap();
+  let follow_up = insert_question_message(
+    &pool,
+    author.clone(),
+    &chat_id,
+    "follow up".to_string(),
+    vec![],
+  )
+  .await
+  .unwrap();
+  assert_eq!(follow_up.parent_message_id, Some(answer_1.message_id));
+
+  // regenerating the answer adds a new branch, which becomes the active one
+  let answer_2 = insert_answer_message(
+    &pool,
+    ChatAuthor::ai(),
+    &chat_id,
+    "answer 2".to_string(),
+    None,
+    question.message_id,
+  )
+  .await
+  .unwrap();
+  let messages = get_all_chat_messages(&pool, &chat_id).await.unwrap();
+  let contents = messages
+    .iter()
+    .map(|m| m.content.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(contents, vec!["question", "answer 2"]);
+
+  let mut txn = pool.begin().await.unwrap();
+  let branches = select_chat_message_branches(&mut txn, &chat_id, answer_1.message_id)
+    .await
+    .unwrap();
+  txn.commit().await.unwrap();
+  let ids = branches
+    .messages
+    .iter()
+    .map(|m| m.message_id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec![answer_1.message_id, answer_2.message_id]);
+  assert_eq!(branches.active_message_id, Some(answer_2.message_id));
+
+  // switching back to the first answer restores the conversation following it
+  let active_message_id = update_chat_active_branch(&pool, &chat_id, answer_1.message_id)
+    .await
+    .unwrap();
+  assert_eq!(active_message_id, follow_up.message_id);
+  let mut txn = pool.begin().await.unwrap();
+  let result = select_chat_messages(&mut txn, &chat_id, GetChatMessageParams::next_back(10))
+    .await
+    .unwrap();
+  txn.commit().await.unwrap();
+  let contents = result
+    .messages
+    .iter()
+    .map(|m| m.content.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(contents, vec!["follow up", "answer 1", "question"]);
+  assert_eq!(result.total, 3);
+  assert!(!result.has_more);
+
+  // editing the question adds a sibling of the original question
+  let edited = insert_question_message_in_branch(
+    &pool,
+    author,
+    &chat_id,
+    "edited question".to_string(),
+    json!([]),
+    ChatMessageParent::Message(question.parent_message_id),
+  )
+  .await
+  .unwrap();
+  assert_eq!(edited.parent_message_id, None);
+  let messages = get_all_chat_messages(&pool, &chat_id).await.unwrap();
+  assert_eq!(messages.len(), 1);
+  assert_eq!(messages[0].message_id, edited.message_id);
+}
@@ -1,6 +1,10 @@
 use crate::sql_test::util::{generate_random_bytes, setup_db, test_create_user};
 
 use collab_entity::CollabType;
+use database::ai_usage::{
+  check_workspace_ai_quota, consume_workspace_ai_request, record_workspace_ai_tokens,
+  select_workspace_ai_usage_and_limit,
+};
 use database::collab::{
   insert_into_af_collab, insert_into_af_collab_bulk_for_user, select_blob_from_af_collab,
   select_collab_meta_from_af_collab,
@@ -277,3 +281,84 @@ async fn test_batch_insert_comparison(pool: PgPool) {
     }
   }
 }
+
+#[sqlx::test(migrations = false)]
+async fn workspace_ai_request_quota_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_uuid = uuid::Uuid::new_v4();
+  let name = user_uuid.to_string();
+  let email = format!("{}@appflowy.io", name);
+  let user = test_create_user(&pool, user_uuid, &email, &name)
+    .await
+    .unwrap();
+  let workspace_id = uuid::Uuid::parse_str(&user.workspace_id).unwrap();
+
+  // no quota is configured, so the workspace is unlimited
+  consume_workspace_ai_request(&pool, &workspace_id)
+    .await
+    .unwrap();
+  check_workspace_ai_quota(&pool, &workspace_id)
+    .await
+    .unwrap();
+
+  sqlx::query(
+    "INSERT INTO af_workspace_ai_quota(workspace_id, daily_request_limit) VALUES ($1, 2)",
+  )
+  .bind(workspace_id)
+  .execute(&pool)
+  .await
+  .unwrap();
+  check_workspace_ai_quota(&pool, &workspace_id)
+    .await
+    .unwrap();
+
+  consume_workspace_ai_request(&pool, &workspace_id)
+    .await
+    .unwrap();
+  let usage = select_workspace_ai_usage_and_limit(&pool, &workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(usage.daily_requests, 2);
+  assert_eq!(usage.monthly_requests, 2);
+  assert_eq!(usage.daily_request_limit, Some(2));
+  assert!(usage.monthly_request_limit.is_none());
+
+  let err = check_workspace_ai_quota(&pool, &workspace_id)
+    .await
+    .unwrap_err();
+  assert!(err.is_ai_quota_exceeded());
+
+  // a request over the quota is not counted
+  let err = consume_workspace_ai_request(&pool, &workspace_id)
+    .await
+    .unwrap_err();
+  assert!(err.is_ai_quota_exceeded());
+  let usage = select_workspace_ai_usage_and_limit(&pool, &workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(usage.daily_requests, 2);
+
+  // tokens reported by the AI service count towards the token quotas
+  sqlx::query(
+    "UPDATE af_workspace_ai_quota SET daily_request_limit = NULL, daily_token_limit = 100 WHERE workspace_id = $1",
+  )
+  .bind(workspace_id)
+  .execute(&pool)
+  .await
+  .unwrap();
+  consume_workspace_ai_request(&pool, &workspace_id)
+    .await
+    .unwrap();
+  record_workspace_ai_tokens(&pool, &workspace_id, 100)
+    .await
+    .unwrap();
+  let usage = select_workspace_ai_usage_and_limit(&pool, &workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(usage.daily_tokens, 100);
+  let err = consume_workspace_ai_request(&pool, &workspace_id)
+    .await
+    .unwrap_err();
+  assert!(err.is_ai_quota_exceeded());
+}
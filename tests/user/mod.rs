@@ -1,4 +1,5 @@
 mod delete;
+mod notification_settings;
 mod refresh;
 mod sign_in;
 mod sign_out;
@@ -0,0 +1,34 @@
+use app_error::ErrorCode;
+use client_api_test::*;
+use shared_entity::dto::auth_dto::{
+  AFUserNotificationSettings, UpdateUserNotificationSettingsParams,
+};
+
+#[tokio::test]
+async fn update_user_notification_settings_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let settings = c.get_user_notification_settings().await.unwrap();
+  assert_eq!(settings, AFUserNotificationSettings::default());
+
+  let settings = c
+    .update_user_notification_settings(&UpdateUserNotificationSettingsParams {
+      timezone: Some("Europe/Paris".to_string()),
+      reminder_email_enabled: Some(false),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(settings.timezone, "Europe/Paris");
+  assert!(settings.reminder_enabled);
+  assert!(!settings.reminder_email_enabled);
+  assert_eq!(c.get_user_notification_settings().await.unwrap(), settings);
+
+  let err = c
+    .update_user_notification_settings(&UpdateUserNotificationSettingsParams {
+      timezone: Some("Mars/Olympus_Mons".to_string()),
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
@@ -1,5 +1,6 @@
 use std::{collections::HashSet, time::Duration};
 
+use app_error::ErrorCode;
 use client_api::entity::{QueryCollab, QueryCollabParams};
 use client_api_test::{
   generate_unique_registered_user, generate_unique_registered_user_client, TestClient,
@@ -208,6 +209,45 @@ async fn create_new_document_page() {
   .unwrap();
 }
 
+#[tokio::test]
+async fn suggest_title_for_empty_page() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspaces = c.get_workspaces().await.unwrap();
+  let workspace_id = workspaces[0].workspace_id;
+  let folder_view = c
+    .get_workspace_folder(&workspace_id.to_string(), Some(2), None)
+    .await
+    .unwrap();
+  let general_space = &folder_view
+    .children
+    .into_iter()
+    .find(|v| v.name == "General")
+    .unwrap();
+  let page = c
+    .create_workspace_page_view(
+      workspace_id,
+      &CreatePageParams {
+        parent_view_id: general_space.view_id.clone(),
+        layout: ViewLayout::Document,
+        name: None,
+      },
+    )
+    .await
+    .unwrap();
+  let page_collab = c
+    .get_workspace_page_view(workspace_id, &page.view_id)
+    .await
+    .unwrap();
+  assert!(page_collab.view.summary.is_none());
+
+  // there is nothing to suggest a title from
+  let err = c
+    .get_workspace_page_title_suggestions(workspace_id, &page.view_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
 #[tokio::test]
 async fn move_page_to_another_space() {
   let registered_user = generate_unique_registered_user().await;
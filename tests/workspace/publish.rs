@@ -1,4 +1,5 @@
 use app_error::ErrorCode;
+use appflowy_cloud::biz::collab::database_form::LOCAL_CAPTCHA_TOKEN;
 use appflowy_cloud::biz::collab::folder_view::collab_folder_to_folder_view;
 use appflowy_cloud::biz::collab::utils::collab_from_doc_state;
 use client_api::entity::{
@@ -18,7 +19,10 @@ use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder, UserId};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use shared_entity::dto::publish_dto::PublishDatabaseData;
+use shared_entity::dto::publish_dto::{PublishDatabaseData, PublishDatabaseFormMetaData};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseFormFieldParams, AFInsertDatabaseField, AFPublishDatabaseForm, AFSubmitDatabaseForm,
+};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::thread::sleep;
@@ -1118,6 +1122,7 @@ async fn duplicate_to_workspace_doc_inline_database() {
       &folder,
       5,
       &HashSet::default(),
+      &HashMap::default(),
     )
     .unwrap();
     let doc_3_fv = folder_view.children[0]
@@ -1803,3 +1808,127 @@ async fn test_republish_patch() {
   .await
   .unwrap();
 }
+
+#[tokio::test]
+async fn publish_database_form_and_submit() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+  c.add_database_field(
+    &workspace_id,
+    &todo_db.id,
+    &AFInsertDatabaseField {
+      name: "Rating".to_string(),
+      field_type: FieldType::Number.into(),
+      ..Default::default()
+    },
+  )
+  .await
+  .unwrap();
+
+  let field = |name: &str, required: bool| AFDatabaseFormFieldParams {
+    field: name.to_string(),
+    required,
+  };
+  let mut params = AFPublishDatabaseForm {
+    view_id: Uuid::new_v4(),
+    publish_name: "feedback".to_string(),
+    title: "Feedback".to_string(),
+    description: "Tell us what you think".to_string(),
+    fields: vec![field("Description", true), field("Unknown field", false)],
+    captcha_required: true,
+  };
+  let err = c
+    .publish_database_form(&workspace_id, &todo_db.id, &params)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  params.fields = vec![
+    field("Description", true),
+    field("Status", false),
+    field("Rating", false),
+  ];
+  let form = c
+    .publish_database_form(&workspace_id, &todo_db.id, &params)
+    .await
+    .unwrap();
+  assert_eq!(form.fields.len(), 3);
+  assert!(form.fields[0].required);
+
+  // visitors render the form from the published metadata, without signing in
+  let namespace = c
+    .get_workspace_publish_namespace(&workspace_id)
+    .await
+    .unwrap();
+  let visitor = localhost_client();
+  let metadata: PublishDatabaseFormMetaData = visitor
+    .get_published_collab(&namespace, "feedback")
+    .await
+    .unwrap();
+  assert_eq!(metadata.form, form);
+
+  let submission =
+    |description: &str, rating: &str, captcha_token: Option<&str>| AFSubmitDatabaseForm {
+      cells: HashMap::from([
+        ("Description".to_string(), serde_json::json!(description)),
+        ("Status".to_string(), serde_json::json!("Done")),
+        ("Rating".to_string(), serde_json::json!(rating)),
+      ]),
+      captcha_token: captcha_token.map(str::to_string),
+    };
+  let invalid_submissions = [
+    submission("great", "5", None),
+    submission("great", "5", Some("wrong-token")),
+    submission("", "5", Some(LOCAL_CAPTCHA_TOKEN)),
+    submission("great", "five", Some(LOCAL_CAPTCHA_TOKEN)),
+  ];
+  for params in &invalid_submissions {
+    let err = visitor
+      .submit_published_database_form(&namespace, "feedback", params)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest);
+  }
+
+  let row_id = visitor
+    .submit_published_database_form(
+      &namespace,
+      "feedback",
+      &submission("great", "5", Some(LOCAL_CAPTCHA_TOKEN)),
+    )
+    .await
+    .unwrap()
+    .row_id;
+  let row_details = c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&row_id])
+    .await
+    .unwrap();
+  assert_eq!(row_details[0].cells["Description"]["data"], "great");
+  assert_eq!(row_details[0].cells["Rating"]["data"], "5");
+
+  // every submission of the visitor counts, there are at most 5 per minute
+  let err = visitor
+    .submit_published_database_form(
+      &namespace,
+      "feedback",
+      &submission("great", "5", Some(LOCAL_CAPTCHA_TOKEN)),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::TooManyRequests);
+
+  c.unpublish_collabs(&workspace_id, &[form.view_id])
+    .await
+    .unwrap();
+  let err = visitor
+    .submit_published_database_form(
+      &namespace,
+      "feedback",
+      &submission("great", "5", Some(LOCAL_CAPTCHA_TOKEN)),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}